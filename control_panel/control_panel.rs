@@ -20,6 +20,10 @@ use tokio::sync::oneshot;
 
 use actix_web::web::Data;
 
+/// Environment variable with IPC address of engine which control panel connects to.
+/// It isn't set by engine, so it should match `core.rpc_ipc_address` of engine
+static IPC_ADDRESS_VARIABLE: &str = "MMB_IPC_ADDRESS";
+
 pub type WebMmbRpcClient = web::Data<Arc<Mutex<Option<MmbRpcClient>>>>;
 
 pub(crate) struct ControlPanel {
@@ -45,7 +49,9 @@ impl ControlPanel {
     }
 
     pub async fn build_rpc_client() -> Option<MmbRpcClient> {
-        ipc::connect::<_, MmbRpcClient>(IPC_ADDRESS)
+        let ipc_address =
+            std::env::var(IPC_ADDRESS_VARIABLE).unwrap_or_else(|_| IPC_ADDRESS.to_owned());
+        ipc::connect::<_, MmbRpcClient>(ipc_address)
             .await
             .map_err(|err| log::warn! {"Failed to connect to IPC server: {}", err.to_string()})
             .ok()
@@ -77,10 +83,31 @@ impl ControlPanel {
             App::new()
                 .app_data(Data::new(client.clone()))
                 .service(endpoints::health)
+                .service(endpoints::status)
                 .service(endpoints::stop)
                 .service(endpoints::stats)
+                .service(endpoints::query_stats)
+                .service(endpoints::balances)
+                .service(endpoints::rate_limits)
+                .service(endpoints::shadow_report)
                 .service(endpoints::get_config)
                 .service(endpoints::set_config)
+                .service(endpoints::get_config_schema)
+                .service(endpoints::withdraw)
+                .service(endpoints::confirm_withdraw)
+                .service(endpoints::pending_refills)
+                .service(endpoints::reload_order_filter)
+                .service(endpoints::panic_button)
+                .service(endpoints::stop_exchange)
+                .service(endpoints::add_exchange)
+                .service(endpoints::disable_market)
+                .service(endpoints::enable_market)
+                .service(endpoints::audit_log)
+                .service(endpoints::orders)
+                .service(endpoints::order_timeline)
+                .service(endpoints::export_state)
+                .service(endpoints::import_state)
+                .service(endpoints::cancel_handover)
                 .service(
                     actix_files::Files::new("/", webui_dir)
                         .use_last_modified(true)
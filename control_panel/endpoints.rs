@@ -1,18 +1,83 @@
-use actix_web::{get, post, web, HttpResponse, Responder};
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
 use futures::FutureExt;
 
 use crate::control_panel::{send_request, WebMmbRpcClient};
 
 // New endpoints have to be added as a service for actix server and webui control page. Look at super::control_panel::start() and webui/README.md
 
+/// Header with identity of operator which is set by authenticating proxy in front of control panel
+static OPERATOR_HEADER: &str = "X-Operator";
+/// Header with secret shared between authenticating proxy and control panel
+static OPERATOR_SECRET_HEADER: &str = "X-Operator-Secret";
+/// Environment variable with secret which authenticating proxy should send with operator identity
+static OPERATOR_SECRET_VARIABLE: &str = "MMB_OPERATOR_SECRET";
+
+/// Operator identity is trusted only if it's sent together with valid shared secret,
+/// otherwise anyone who can reach control panel could act on behalf of any operator
+fn get_operator(req: &HttpRequest) -> Result<Option<String>, HttpResponse> {
+    let get_header = |name| {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    };
+
+    let operator = match get_header(OPERATOR_HEADER) {
+        Some(operator) => operator,
+        None => return Ok(None),
+    };
+
+    let expected_secret = match std::env::var(OPERATOR_SECRET_VARIABLE) {
+        Ok(secret) if !secret.is_empty() => secret,
+        _ => {
+            log::warn!(
+                "{} header is rejected because {} isn't set",
+                OPERATOR_HEADER,
+                OPERATOR_SECRET_VARIABLE
+            );
+            return Err(HttpResponse::Unauthorized().body(format!(
+                "{OPERATOR_HEADER} header isn't accepted without shared secret"
+            )));
+        }
+    };
+
+    match get_header(OPERATOR_SECRET_HEADER) {
+        Some(secret) if secrets_are_equal(secret.as_bytes(), expected_secret.as_bytes()) => {
+            Ok(Some(operator.to_owned()))
+        }
+        _ => {
+            log::warn!("Operator {} isn't authenticated", operator);
+            Err(HttpResponse::Unauthorized().body("Operator isn't authenticated"))
+        }
+    }
+}
+
+/// Comparison time doesn't depend on position of the first mismatched byte
+fn secrets_are_equal(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right)
+            .fold(0, |diff, (left, right)| diff | (left ^ right))
+            == 0
+}
+
 #[get("/health")]
 pub(super) async fn health(client: WebMmbRpcClient) -> impl Responder {
     send_request(client, |client| client.health().boxed()).await
 }
 
+#[get("/status")]
+pub(super) async fn status(client: WebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.status().boxed()).await
+}
+
 #[post("/stop")]
-pub(super) async fn stop(client: WebMmbRpcClient) -> impl Responder {
-    send_request(client, |client| client.stop().boxed()).await
+pub(super) async fn stop(req: HttpRequest, client: WebMmbRpcClient) -> impl Responder {
+    let operator = match get_operator(&req) {
+        Ok(operator) => operator,
+        Err(response) => return response,
+    };
+    send_request(client, move |client| client.stop(operator.clone()).boxed()).await
 }
 
 #[get("/config")]
@@ -21,7 +86,11 @@ pub(super) async fn get_config(client: WebMmbRpcClient) -> impl Responder {
 }
 
 #[post("/config")]
-pub(super) async fn set_config(body: web::Bytes, client: WebMmbRpcClient) -> impl Responder {
+pub(super) async fn set_config(
+    req: HttpRequest,
+    body: web::Bytes,
+    client: WebMmbRpcClient,
+) -> impl Responder {
     let settings = match String::from_utf8((&body).to_vec()) {
         Ok(settings) => settings,
         Err(err) => {
@@ -33,13 +102,322 @@ pub(super) async fn set_config(body: web::Bytes, client: WebMmbRpcClient) -> imp
         }
     };
 
+    let operator = match get_operator(&req) {
+        Ok(operator) => operator,
+        Err(response) => return response,
+    };
     send_request(client, move |client| {
-        client.set_config(settings.clone()).boxed()
+        client
+            .set_config(settings.clone(), operator.clone())
+            .boxed()
     })
     .await
 }
 
+#[get("/config/schema")]
+pub(super) async fn get_config_schema(client: WebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.get_config_schema().boxed()).await
+}
+
 #[get("/stats")]
 pub(super) async fn stats(client: WebMmbRpcClient) -> impl Responder {
     send_request(client, |client| client.stats().boxed()).await
 }
+
+#[post("/stats/query")]
+pub(super) async fn query_stats(body: web::Bytes, client: WebMmbRpcClient) -> impl Responder {
+    let query = match String::from_utf8((&body).to_vec()) {
+        Ok(query) => query,
+        Err(err) => {
+            return HttpResponse::BadRequest().body(format!(
+                "Failed to convert input stats query({:?}) to utf8 string: {}",
+                body, err,
+            ))
+        }
+    };
+
+    send_request(client, move |client| {
+        client.query_stats(query.clone()).boxed()
+    })
+    .await
+}
+
+#[get("/balances")]
+pub(super) async fn balances(client: WebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.balances().boxed()).await
+}
+
+#[get("/rate_limits")]
+pub(super) async fn rate_limits(client: WebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.rate_limits().boxed()).await
+}
+
+#[get("/shadow")]
+pub(super) async fn shadow_report(client: WebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.shadow_report().boxed()).await
+}
+
+#[post("/withdraw")]
+pub(super) async fn withdraw(
+    req: HttpRequest,
+    body: web::Bytes,
+    client: WebMmbRpcClient,
+) -> impl Responder {
+    let withdrawal_request = match String::from_utf8((&body).to_vec()) {
+        Ok(withdrawal_request) => withdrawal_request,
+        Err(err) => {
+            return HttpResponse::BadRequest().body(format!(
+                "Failed to convert input withdrawal request({:?}) to utf8 string: {}",
+                body,
+                err.to_string(),
+            ))
+        }
+    };
+
+    let operator = match get_operator(&req) {
+        Ok(operator) => operator,
+        Err(response) => return response,
+    };
+    send_request(client, move |client| {
+        client
+            .withdraw(withdrawal_request.clone(), operator.clone())
+            .boxed()
+    })
+    .await
+}
+
+#[get("/withdraw/refills")]
+pub(super) async fn pending_refills(client: WebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.pending_refills().boxed()).await
+}
+
+#[post("/withdraw/confirm/{token}")]
+pub(super) async fn confirm_withdraw(
+    req: HttpRequest,
+    token: web::Path<String>,
+    client: WebMmbRpcClient,
+) -> impl Responder {
+    let token = token.into_inner();
+    let operator = match get_operator(&req) {
+        Ok(operator) => operator,
+        Err(response) => return response,
+    };
+    send_request(client, move |client| {
+        client
+            .confirm_withdraw(token.clone(), operator.clone())
+            .boxed()
+    })
+    .await
+}
+
+#[post("/order_filter/reload")]
+pub(super) async fn reload_order_filter(
+    req: HttpRequest,
+    client: WebMmbRpcClient,
+) -> impl Responder {
+    let operator = match get_operator(&req) {
+        Ok(operator) => operator,
+        Err(response) => return response,
+    };
+    send_request(client, move |client| {
+        client.reload_order_filter(operator.clone()).boxed()
+    })
+    .await
+}
+
+#[post("/panic_button/{close_positions}")]
+pub(super) async fn panic_button(
+    req: HttpRequest,
+    close_positions: web::Path<bool>,
+    client: WebMmbRpcClient,
+) -> impl Responder {
+    let close_positions = close_positions.into_inner();
+    let operator = match get_operator(&req) {
+        Ok(operator) => operator,
+        Err(response) => return response,
+    };
+    send_request(client, move |client| {
+        client
+            .panic_button(close_positions, operator.clone())
+            .boxed()
+    })
+    .await
+}
+
+#[post("/exchange/{exchange_account_id}/stop")]
+pub(super) async fn stop_exchange(
+    req: HttpRequest,
+    exchange_account_id: web::Path<String>,
+    client: WebMmbRpcClient,
+) -> impl Responder {
+    let exchange_account_id = exchange_account_id.into_inner();
+    let operator = match get_operator(&req) {
+        Ok(operator) => operator,
+        Err(response) => return response,
+    };
+    send_request(client, move |client| {
+        client
+            .stop_exchange(exchange_account_id.clone(), operator.clone())
+            .boxed()
+    })
+    .await
+}
+
+#[post("/exchange/{exchange_account_id}/market/{base}/{quote}/disable")]
+pub(super) async fn disable_market(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    client: WebMmbRpcClient,
+) -> impl Responder {
+    let (exchange_account_id, base, quote) = path.into_inner();
+    let currency_pair = format!("{base}/{quote}");
+    let operator = match get_operator(&req) {
+        Ok(operator) => operator,
+        Err(response) => return response,
+    };
+    send_request(client, move |client| {
+        client
+            .disable_market(
+                exchange_account_id.clone(),
+                currency_pair.clone(),
+                operator.clone(),
+            )
+            .boxed()
+    })
+    .await
+}
+
+#[post("/exchange/{exchange_account_id}/market/{base}/{quote}/enable")]
+pub(super) async fn enable_market(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    client: WebMmbRpcClient,
+) -> impl Responder {
+    let (exchange_account_id, base, quote) = path.into_inner();
+    let currency_pair = format!("{base}/{quote}");
+    let operator = match get_operator(&req) {
+        Ok(operator) => operator,
+        Err(response) => return response,
+    };
+    send_request(client, move |client| {
+        client
+            .enable_market(
+                exchange_account_id.clone(),
+                currency_pair.clone(),
+                operator.clone(),
+            )
+            .boxed()
+    })
+    .await
+}
+
+#[post("/exchange")]
+pub(super) async fn add_exchange(
+    req: HttpRequest,
+    body: web::Bytes,
+    client: WebMmbRpcClient,
+) -> impl Responder {
+    let exchange_settings = match String::from_utf8((&body).to_vec()) {
+        Ok(exchange_settings) => exchange_settings,
+        Err(err) => {
+            return HttpResponse::BadRequest().body(format!(
+                "Failed to convert input exchange settings to utf8 string: {}",
+                err.to_string(),
+            ))
+        }
+    };
+
+    let operator = match get_operator(&req) {
+        Ok(operator) => operator,
+        Err(response) => return response,
+    };
+    send_request(client, move |client| {
+        client
+            .add_exchange(exchange_settings.clone(), operator.clone())
+            .boxed()
+    })
+    .await
+}
+
+#[get("/audit_log/{count}")]
+pub(super) async fn audit_log(count: web::Path<usize>, client: WebMmbRpcClient) -> impl Responder {
+    let count = count.into_inner();
+    send_request(client, move |client| client.audit_log(count).boxed()).await
+}
+
+/// Query parameters are passed to engine as is: `status`, `exchange_account_id`, `pair`, `from`,
+/// `to`, `after_sequence`, `offset` and `limit`
+#[get("/orders")]
+pub(super) async fn orders(req: HttpRequest, client: WebMmbRpcClient) -> impl Responder {
+    let query = req.query_string().to_owned();
+    send_request(client, move |client| client.orders(query.clone()).boxed()).await
+}
+
+#[get("/orders/{client_order_id}/timeline")]
+pub(super) async fn order_timeline(
+    client_order_id: web::Path<String>,
+    client: WebMmbRpcClient,
+) -> impl Responder {
+    let client_order_id = client_order_id.into_inner();
+    send_request(client, move |client| {
+        client.order_timeline(client_order_id.clone()).boxed()
+    })
+    .await
+}
+
+#[post("/state/export/{hand_over}")]
+pub(super) async fn export_state(
+    req: HttpRequest,
+    hand_over: web::Path<bool>,
+    client: WebMmbRpcClient,
+) -> impl Responder {
+    let hand_over = hand_over.into_inner();
+    let operator = match get_operator(&req) {
+        Ok(operator) => operator,
+        Err(response) => return response,
+    };
+    send_request(client, move |client| {
+        client.export_state(hand_over, operator.clone()).boxed()
+    })
+    .await
+}
+
+#[post("/state/import")]
+pub(super) async fn import_state(
+    req: HttpRequest,
+    body: web::Bytes,
+    client: WebMmbRpcClient,
+) -> impl Responder {
+    let archive = match String::from_utf8((&body).to_vec()) {
+        Ok(archive) => archive,
+        Err(err) => {
+            return HttpResponse::BadRequest().body(format!(
+                "Failed to convert input state archive to utf8 string: {}",
+                err,
+            ))
+        }
+    };
+
+    let operator = match get_operator(&req) {
+        Ok(operator) => operator,
+        Err(response) => return response,
+    };
+    send_request(client, move |client| {
+        client
+            .import_state(archive.clone(), operator.clone())
+            .boxed()
+    })
+    .await
+}
+
+#[post("/state/cancel_handover")]
+pub(super) async fn cancel_handover(req: HttpRequest, client: WebMmbRpcClient) -> impl Responder {
+    let operator = match get_operator(&req) {
+        Ok(operator) => operator,
+        Err(response) => return response,
+    };
+    send_request(client, move |client| {
+        client.cancel_handover(operator.clone()).boxed()
+    })
+    .await
+}
@@ -31,11 +31,7 @@ pub mod tests {
         balance_manager::balance_request::BalanceRequest,
         exchanges::{
             common::{Amount, CurrencyCode, CurrencyPair, ExchangeAccountId, Price},
-            general::{
-                exchange::Exchange,
-                symbol::{Precision, Symbol},
-                test_helper::get_test_exchange_by_currency_codes,
-            },
+            general::{exchange::Exchange, test_helper::get_test_exchange_by_currency_codes},
         },
         orders::{
             fill::{OrderFill, OrderFillType},
@@ -45,6 +41,7 @@ pub mod tests {
             },
         },
         service_configuration::configuration_descriptor::ConfigurationDescriptor,
+        test_util::SymbolBuilder,
     };
 
     pub struct BalanceChangesCalculatorTestsBase {
@@ -136,23 +133,12 @@ pub mod tests {
                 (false, false) => (Self::base(), None),
             };
 
-            let mut symbol = Symbol::new(
-                false,
-                is_derivative,
-                Self::base().as_str().into(),
-                Self::base(),
-                Self::quote().as_str().into(),
-                Self::quote(),
-                None,
-                None,
-                None,
-                None,
-                None,
-                amount_currency_code,
-                balance_currency_code,
-                Precision::ByTick { tick: dec!(0.1) },
-                Precision::ByTick { tick: dec!(0) },
-            );
+            let mut symbol = SymbolBuilder::new(Self::base(), Self::quote())
+                .is_derivative(is_derivative)
+                .amount_currency_code(amount_currency_code)
+                .amount_tick(dec!(0))
+                .build();
+            symbol.balance_currency_code = balance_currency_code;
             if is_reversed {
                 symbol.amount_multiplier = Self::amount_multiplier();
             }
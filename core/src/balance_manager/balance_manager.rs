@@ -1,16 +1,20 @@
 use std::collections::{HashMap, HashSet};
+use std::slice;
 use std::sync::Arc;
 
 use crate::balance_changes::balance_changes_service::BalanceChangesService;
 use crate::balance_manager::balance_reservation::BalanceReservation;
+use crate::balance_manager::capital_allocation::{CapitalAllocationError, CapitalAllocations};
 use crate::balance_manager::position_change::PositionChange;
 use crate::balances::balance_reservation_manager::BalanceReservationManager;
+use crate::error::{MmbResult, ValidationError};
 use crate::exchanges::common::{Amount, Price};
 use crate::exchanges::common::{CurrencyCode, CurrencyPair, MarketAccountId};
 use crate::exchanges::events::ExchangeBalancesAndPositions;
 use crate::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
-use crate::exchanges::general::symbol::{BeforeAfter, Symbol};
-use crate::explanation::Explanation;
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::symbol::{BeforeAfter, Round, Symbol};
+use crate::explanation::{Explanation, OptionExplanationAddReasonExt};
 use crate::misc::derivative_position::DerivativePosition;
 use crate::misc::reserve_parameters::ReserveParameters;
 use crate::misc::service_value_tree::ServiceValueTree;
@@ -32,12 +36,21 @@ use mmb_utils::{impl_mock_initializer, DateTime};
 use parking_lot::Mutex;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use thiserror::Error;
 
 #[cfg(test)]
 use crate::MOCK_MUTEX;
 #[cfg(test)]
 use mockall::automock;
 
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ReserveError {
+    #[error(transparent)]
+    CapitalAllocation(#[from] CapitalAllocationError),
+    #[error("Balance isn't enough for reservation")]
+    NotEnoughBalance,
+}
+
 /// The entity for getting information about account balances for selected exchanges
 #[derive(Clone)]
 pub struct BalanceManager {
@@ -45,6 +58,7 @@ pub struct BalanceManager {
     balance_reservation_manager: BalanceReservationManager,
     last_order_fills: HashMap<MarketAccountId, OrderFill>,
     balance_changes_service: Option<Arc<BalanceChangesService>>,
+    capital_allocations: CapitalAllocations,
 }
 
 impl BalanceManager {
@@ -58,6 +72,7 @@ impl BalanceManager {
             ),
             last_order_fills: HashMap::new(),
             balance_changes_service: None,
+            capital_allocations: CapitalAllocations::default(),
         }))
     }
 
@@ -108,6 +123,20 @@ impl BalanceManager {
             .get_reservation_ids()
     }
 
+    /// Currencies of current reservations on exchange account
+    pub(crate) fn get_reserved_currency_codes(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+    ) -> HashSet<CurrencyCode> {
+        self.balance_reservation_manager
+            .balance_reservation_storage
+            .get_all_raw_reservations()
+            .values()
+            .filter(|x| x.exchange_account_id == exchange_account_id)
+            .map(|x| x.reservation_currency_code)
+            .collect()
+    }
+
     #[cfg(test)]
     pub(crate) fn restore_balance_state_with_reservations_handling(
         &mut self,
@@ -121,16 +150,104 @@ impl BalanceManager {
         Ok(())
     }
 
-    pub fn unreserve_rest(&mut self, reservation_id: ReservationId) -> Result<()> {
+    /// Makes exchange account which is added after engine start available for reservations
+    pub fn add_exchange(&mut self, exchange: Arc<Exchange>) {
+        let mut exchanges_by_id = self.balance_reservation_manager.exchanges_by_id().clone();
+        let _ = exchanges_by_id.insert(exchange.exchange_account_id, exchange);
+        self.balance_reservation_manager
+            .currency_pair_to_symbol_converter =
+            CurrencyPairToSymbolConverter::new(exchanges_by_id);
+    }
+
+    /// Exchange account which failed to be added after engine start
+    pub fn remove_exchange(&mut self, exchange_account_id: ExchangeAccountId) {
+        let mut exchanges_by_id = self.balance_reservation_manager.exchanges_by_id().clone();
+        let _ = exchanges_by_id.remove(&exchange_account_id);
+        self.balance_reservation_manager
+            .currency_pair_to_symbol_converter =
+            CurrencyPairToSymbolConverter::new(exchanges_by_id);
+    }
+
+    /// Sets position by filled amount of derivative market, e.g. position which is moved
+    /// from engine on another host
+    pub fn restore_position(
+        &mut self,
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        position: Decimal,
+    ) -> Result<()> {
+        let symbol = self
+            .balance_reservation_manager
+            .exchanges_by_id()
+            .get(&exchange_account_id)
+            .with_context(|| format!("Exchange {exchange_account_id} isn't found"))?
+            .get_symbol(currency_pair)?;
+
+        self.balance_reservation_manager
+            .restore_fill_amount_position(exchange_account_id, symbol, position)?;
+        self.save_balances();
+        Ok(())
+    }
+
+    /// Releases all reservations of exchange account, e.g. when exchange is stopped
+    /// Releases reservations of exchange account except parts which are approved for
+    /// `not_finished_orders`, because such orders can still be filled
+    pub fn unreserve_by_exchange_account_id(
+        &mut self,
+        exchange_account_id: ExchangeAccountId,
+        not_finished_orders: &HashSet<ClientOrderId>,
+    ) -> MmbResult<()> {
+        let reservations = self
+            .get_reservation_ids()
+            .into_iter()
+            .filter_map(|reservation_id| {
+                let reservation = self.get_reservation(reservation_id)?;
+                if reservation.exchange_account_id != exchange_account_id {
+                    return None;
+                }
+
+                let finished_parts = reservation
+                    .approved_parts
+                    .iter()
+                    .filter(|(client_order_id, _)| !not_finished_orders.contains(*client_order_id))
+                    .map(|(client_order_id, part)| {
+                        (client_order_id.clone(), part.unreserved_amount)
+                    })
+                    .collect_vec();
+                Some((
+                    reservation_id,
+                    reservation.not_approved_amount,
+                    finished_parts,
+                ))
+            })
+            .collect_vec();
+
+        for (reservation_id, not_approved_amount, finished_parts) in reservations {
+            for (client_order_id, amount) in finished_parts {
+                // Reservation is removed when the rest of it is within precision error
+                if self.get_reservation(reservation_id).is_some() {
+                    self.unreserve_by_client_order_id(reservation_id, client_order_id, amount)?;
+                }
+            }
+
+            if self.get_reservation(reservation_id).is_some() {
+                self.unreserve(reservation_id, not_approved_amount)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn unreserve_rest(&mut self, reservation_id: ReservationId) -> MmbResult<()> {
         let amount = self
             .balance_reservation_manager
             .get_reservation(reservation_id)
-            .with_context(|| format!("Can't find reservation_id: {}", reservation_id))?
+            .ok_or(ValidationError::ReservationNotFound(reservation_id))?
             .unreserved_amount;
         return self.unreserve(reservation_id, amount);
     }
 
-    pub fn unreserve(&mut self, reservation_id: ReservationId, amount: Amount) -> Result<()> {
+    pub fn unreserve(&mut self, reservation_id: ReservationId, amount: Amount) -> MmbResult<()> {
         self.balance_reservation_manager
             .unreserve(reservation_id, amount, &None)?;
         self.save_balances();
@@ -142,7 +259,7 @@ impl BalanceManager {
         reservation_id: ReservationId,
         client_order_id: ClientOrderId,
         amount: Amount,
-    ) -> Result<()> {
+    ) -> MmbResult<()> {
         self.balance_reservation_manager.unreserve(
             reservation_id,
             amount,
@@ -452,6 +569,7 @@ impl BalanceManager {
     pub fn custom_clone(this: Arc<Mutex<Self>>) -> Arc<Mutex<BalanceManager>> {
         let this_locked = this.lock();
         let balances = this_locked.get_balances();
+        let capital_allocations = this_locked.capital_allocations.clone();
         let exchanges_by_id = this_locked.balance_reservation_manager.exchanges_by_id();
         let new_balance_manager =
             Self::new(CurrencyPairToSymbolConverter::new(exchanges_by_id.clone()));
@@ -459,6 +577,7 @@ impl BalanceManager {
 
         let mut new_bm_lock = new_balance_manager.lock();
         new_bm_lock.restore_balance_state(&balances, true);
+        new_bm_lock.set_capital_allocations(capital_allocations);
         new_bm_lock.balance_reservation_manager.is_call_from_clone = true;
         drop(new_bm_lock);
 
@@ -510,11 +629,11 @@ impl BalanceManager {
                     None => continue,
                 };
 
-                bm_locked.unreserve_by_client_order_id(
-                    reservation_id,
-                    client_order_id,
-                    order.amount(),
-                )?
+                // Reservation is in base currency, so quote amount is converted by price of order
+                let amount = order.base_amount().with_context(|| {
+                    format!("Unable to get base amount of order {}", client_order_id)
+                })?;
+                bm_locked.unreserve_by_client_order_id(reservation_id, client_order_id, amount)?
             }
         }
 
@@ -580,6 +699,14 @@ impl BalanceManager {
             .balance_reservation_manager
             .currency_pair_to_symbol_converter
             .get_symbol(exchange_account_id, order_snapshot.header.currency_pair);
+        self.capital_allocations.register_fill(
+            configuration_descriptor,
+            exchange_account_id,
+            &symbol,
+            order_snapshot.header.side,
+            order_fill.amount(),
+            order_fill.price(),
+        );
         self.handle_order_fill(
             configuration_descriptor.clone(),
             exchange_account_id,
@@ -810,19 +937,57 @@ impl BalanceManager {
         true
     }
 
+    pub fn set_capital_allocations(&mut self, capital_allocations: CapitalAllocations) {
+        self.capital_allocations = capital_allocations;
+    }
+
+    fn check_capital_allocations(
+        &self,
+        reserve_parameters: &[ReserveParameters],
+    ) -> Result<(), CapitalAllocationError> {
+        let reservations = self
+            .balance_reservation_manager
+            .balance_reservation_storage
+            .get_all_raw_reservations()
+            .values();
+
+        self.capital_allocations
+            .check(reserve_parameters, reservations)
+            .map_err(|error| {
+                log::warn!("{}", error);
+                error
+            })
+    }
+
     pub fn try_reserve(
         &mut self,
         reserve_parameters: &ReserveParameters,
         explanation: &mut Option<Explanation>,
-    ) -> Option<ReservationId> {
-        if let Some(reservation_id) = self
+    ) -> MmbResult<ReservationId> {
+        Ok(self.try_reserve_checked(reserve_parameters, explanation)?)
+    }
+
+    /// Same as `try_reserve`, but returns `ReserveError` which can be matched exhaustively
+    pub fn try_reserve_checked(
+        &mut self,
+        reserve_parameters: &ReserveParameters,
+        explanation: &mut Option<Explanation>,
+    ) -> Result<ReservationId, ReserveError> {
+        if let Err(error) = self.check_capital_allocations(slice::from_ref(reserve_parameters)) {
+            explanation.with_reason(|| error.to_string());
+            return Err(error.into());
+        }
+
+        match self
             .balance_reservation_manager
             .try_reserve(reserve_parameters, explanation)
         {
-            self.save_balances();
-            return Some(reservation_id);
+            Some(reservation_id) => {
+                self.save_balances();
+                Ok(reservation_id)
+            }
+            None => Err(ReserveError::NotEnoughBalance),
         }
-        None
     }
 
     pub fn try_reserve_pair(
@@ -830,6 +995,8 @@ impl BalanceManager {
         order1: ReserveParameters,
         order2: ReserveParameters,
     ) -> Option<(ReservationId, ReservationId)> {
+        self.check_capital_allocations(&[order1.clone(), order2.clone()])
+            .ok()?;
         let reservations_id = self
             .balance_reservation_manager
             .try_reserve_multiple(&[order1, order2], &mut None)?;
@@ -846,6 +1013,8 @@ impl BalanceManager {
         order2: ReserveParameters,
         order3: ReserveParameters,
     ) -> Option<(ReservationId, ReservationId, ReservationId)> {
+        self.check_capital_allocations(&[order1.clone(), order2.clone(), order3.clone()])
+            .ok()?;
         let reservations_id = self
             .balance_reservation_manager
             .try_reserve_multiple(&[order1, order2, order3], &mut None)?;
@@ -925,6 +1094,39 @@ impl BalanceManager {
         }
     }
 
+    /// Max order amount in amount currency code which uses `utilization_percent` of available
+    /// leveraged balance, so max amount of strategy scales with capital
+    pub fn get_max_amount_by_utilization(
+        &self,
+        configuration_descriptor: ConfigurationDescriptor,
+        side: OrderSide,
+        exchange_account_id: ExchangeAccountId,
+        symbol: Arc<Symbol>,
+        price_quote_to_base: Price,
+        utilization_percent: Decimal,
+        explanation: &mut Option<Explanation>,
+    ) -> Option<Amount> {
+        let balance = self.get_leveraged_balance_in_amount_currency_code(
+            configuration_descriptor,
+            side,
+            exchange_account_id,
+            symbol.clone(),
+            price_quote_to_base,
+            explanation,
+        )?;
+        let max_amount =
+            symbol.amount_round(balance * utilization_percent / dec!(100), Round::Floor);
+
+        explanation.with_reason(|| {
+            format!(
+                "max_amount {} is {}% of leveraged balance {}",
+                max_amount, utilization_percent, balance
+            )
+        });
+
+        Some(max_amount)
+    }
+
     pub fn get_balance_by_currency_code(
         &self,
         configuration_descriptor: ConfigurationDescriptor,
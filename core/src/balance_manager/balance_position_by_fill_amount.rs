@@ -35,6 +35,11 @@ impl BalancePositionByFillAmount {
             .cloned()
     }
 
+    /// Positions of all markets
+    pub fn positions(&self) -> impl Iterator<Item = (&MarketAccountId, &Decimal)> {
+        self.position_by_fill_amount.iter()
+    }
+
     pub(crate) fn set(
         &mut self,
         exchange_account_id: ExchangeAccountId,
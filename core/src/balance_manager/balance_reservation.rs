@@ -82,7 +82,7 @@ impl BalanceReservation {
         &self,
         amount_in_current_currency: Amount,
     ) -> Amount {
-        self.symbol.convert_amount_from_amount_currency_code(
+        self.symbol.get_amount_value_in_currency(
             self.reservation_currency_code,
             amount_in_current_currency,
             self.price,
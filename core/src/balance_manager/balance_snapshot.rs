@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use itertools::Itertools;
+use parking_lot::Mutex;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+
+use crate::balance_manager::balance_manager::BalanceManager;
+use crate::exchanges::common::{Amount, CurrencyCode, ExchangeAccountId};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::symbol::BeforeAfter;
+use crate::service_configuration::configuration_descriptor::{
+    ServiceConfigurationKey, ServiceName,
+};
+
+/// Amount of currency which is reserved by strategy configuration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StrategyReservation {
+    pub service_name: ServiceName,
+    pub service_configuration_key: ServiceConfigurationKey,
+    pub amount: Amount,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CurrencyBalanceSnapshot {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_code: CurrencyCode,
+    /// Balance received from exchange
+    pub total: Amount,
+    /// Total balance without reserved amounts
+    pub available: Amount,
+    pub reserved: Vec<StrategyReservation>,
+    /// Amount which is locked by not finished orders on exchange
+    pub in_flight: Amount,
+}
+
+/// Breakdown of balances of all exchange accounts by reservations of strategies and open orders
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BalanceSnapshot {
+    pub balances: Vec<CurrencyBalanceSnapshot>,
+}
+
+impl BalanceSnapshot {
+    pub fn collect(
+        balance_manager: &Mutex<BalanceManager>,
+        exchanges: &DashMap<ExchangeAccountId, Arc<Exchange>>,
+    ) -> Self {
+        let balances = balance_manager.lock().get_balances();
+        let mut builder = BalanceSnapshotBuilder::default();
+
+        for (exchange_account_id, balance_by_currency) in
+            balances.balances_by_exchange_id.iter().flatten()
+        {
+            for (currency_code, amount) in balance_by_currency {
+                builder.entry(*exchange_account_id, *currency_code).total += amount;
+            }
+        }
+
+        for reservation in balances
+            .balance_reservations_by_reservation_id
+            .iter()
+            .flat_map(|x| x.values())
+        {
+            let amount = reservation.symbol.convert_amount_from_amount_currency_code(
+                reservation.reservation_currency_code,
+                reservation.unreserved_amount,
+                reservation.price,
+            );
+            builder.add_reserved(
+                reservation.exchange_account_id,
+                reservation.reservation_currency_code,
+                reservation.configuration_descriptor.service_name,
+                reservation
+                    .configuration_descriptor
+                    .service_configuration_key,
+                amount,
+            );
+        }
+
+        for exchange in exchanges.iter() {
+            for order in exchange.orders.not_finished.iter() {
+                let symbol = match exchange.symbols.get(&order.currency_pair()) {
+                    Some(symbol) => symbol.clone(),
+                    None => continue,
+                };
+
+                let remaining_amount = match order.remaining_base_amount() {
+                    Some(remaining_amount) => remaining_amount,
+                    None => continue,
+                };
+                let currency_code = symbol.get_trade_code(order.side(), BeforeAfter::Before);
+                let amount = symbol.convert_amount_from_amount_currency_code(
+                    currency_code,
+                    remaining_amount,
+                    order.price(),
+                );
+                builder
+                    .entry(exchange.exchange_account_id, currency_code)
+                    .in_flight += amount;
+            }
+        }
+
+        builder.build()
+    }
+}
+
+#[derive(Default)]
+struct BalanceSnapshotBuilder {
+    balances: HashMap<(ExchangeAccountId, CurrencyCode), CurrencyBalanceSnapshot>,
+}
+
+impl BalanceSnapshotBuilder {
+    fn entry(
+        &mut self,
+        exchange_account_id: ExchangeAccountId,
+        currency_code: CurrencyCode,
+    ) -> &mut CurrencyBalanceSnapshot {
+        self.balances
+            .entry((exchange_account_id, currency_code))
+            .or_insert_with(|| CurrencyBalanceSnapshot {
+                exchange_account_id,
+                currency_code,
+                total: dec!(0),
+                available: dec!(0),
+                reserved: vec![],
+                in_flight: dec!(0),
+            })
+    }
+
+    fn add_reserved(
+        &mut self,
+        exchange_account_id: ExchangeAccountId,
+        currency_code: CurrencyCode,
+        service_name: ServiceName,
+        service_configuration_key: ServiceConfigurationKey,
+        amount: Amount,
+    ) {
+        let reserved = &mut self.entry(exchange_account_id, currency_code).reserved;
+        match reserved.iter_mut().find(|x| {
+            x.service_name == service_name
+                && x.service_configuration_key == service_configuration_key
+        }) {
+            Some(strategy_reservation) => strategy_reservation.amount += amount,
+            None => reserved.push(StrategyReservation {
+                service_name,
+                service_configuration_key,
+                amount,
+            }),
+        }
+    }
+
+    fn build(self) -> BalanceSnapshot {
+        let balances = self
+            .balances
+            .into_values()
+            .map(|mut balance| {
+                let reserved: Amount = balance.reserved.iter().map(|x| x.amount).sum();
+                balance.available = balance.total - reserved;
+                balance
+            })
+            .sorted_by_key(|x| {
+                (
+                    x.exchange_account_id.to_string(),
+                    x.currency_code.to_string(),
+                )
+            })
+            .collect_vec();
+
+        BalanceSnapshot { balances }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reservations_are_grouped_by_strategy() {
+        let binance = ExchangeAccountId::new("Binance".into(), 0);
+        let btc: CurrencyCode = "btc".into();
+        let usdt: CurrencyCode = "usdt".into();
+
+        let mut builder = BalanceSnapshotBuilder::default();
+        builder.entry(binance, btc).total = dec!(2);
+        builder.entry(binance, usdt).total = dec!(1000);
+        builder.add_reserved(binance, btc, "mm".into(), "btc_usdt".into(), dec!(0.5));
+        builder.add_reserved(binance, btc, "mm".into(), "btc_usdt".into(), dec!(0.25));
+        builder.add_reserved(binance, btc, "arb".into(), "btc_usdt".into(), dec!(1));
+        builder.entry(binance, btc).in_flight = dec!(0.75);
+
+        let snapshot = builder.build();
+
+        assert_eq!(snapshot.balances.len(), 2);
+        let btc_balance = &snapshot.balances[0];
+        assert_eq!(btc_balance.currency_code, btc);
+        assert_eq!(btc_balance.available, dec!(0.25));
+        assert_eq!(btc_balance.in_flight, dec!(0.75));
+        assert_eq!(
+            btc_balance.reserved,
+            vec![
+                StrategyReservation {
+                    service_name: "mm".into(),
+                    service_configuration_key: "btc_usdt".into(),
+                    amount: dec!(0.75),
+                },
+                StrategyReservation {
+                    service_name: "arb".into(),
+                    service_configuration_key: "btc_usdt".into(),
+                    amount: dec!(1),
+                },
+            ]
+        );
+        assert_eq!(snapshot.balances[1].available, dec!(1000));
+    }
+}
@@ -0,0 +1,437 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::balance_manager::balance_reservation::BalanceReservation;
+use crate::commission_ledger::PriceSource;
+use crate::exchanges::common::{Amount, CurrencyCode, ExchangeAccountId, MarketAccountId, Price};
+use crate::exchanges::general::symbol::Symbol;
+use crate::misc::reserve_parameters::ReserveParameters;
+use crate::orders::order::OrderSide;
+use crate::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+
+/// Max amount which can be reserved by strategy configuration at the same time
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CapitalAllocationSettings {
+    pub service_name: String,
+    pub service_configuration_key: String,
+    /// Currency of allocation. Reservations and positions are converted to it by price of order
+    /// or fill, and by current price of quote currency if it's outside of currency pair
+    pub currency_code: CurrencyCode,
+    pub max_amount: Amount,
+}
+
+impl CapitalAllocationSettings {
+    pub fn configuration_descriptor(&self) -> ConfigurationDescriptor {
+        ConfigurationDescriptor::new(
+            self.service_name.as_str().into(),
+            self.service_configuration_key.as_str().into(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CapitalAllocationError {
+    #[error("Reservation of {requested_amount} {currency_code} for {configuration_descriptor:?} exceeds capital allocation {max_amount} {currency_code}, already used {used_amount} {currency_code}")]
+    Exceeded {
+        configuration_descriptor: ConfigurationDescriptor,
+        currency_code: CurrencyCode,
+        max_amount: Amount,
+        /// Reserved amount together with exposure of filled positions
+        used_amount: Amount,
+        requested_amount: Amount,
+    },
+    #[error("Amount for {configuration_descriptor:?} on {currency_pair} can't be converted to currency {currency_code} of capital allocation, because there is no price for it")]
+    NotConvertible {
+        configuration_descriptor: ConfigurationDescriptor,
+        currency_pair: String,
+        currency_code: CurrencyCode,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CapitalAllocation {
+    currency_code: CurrencyCode,
+    max_amount: Amount,
+}
+
+impl CapitalAllocation {
+    fn convert(
+        &self,
+        configuration_descriptor: ConfigurationDescriptor,
+        symbol: &Symbol,
+        amount: Amount,
+        price: Price,
+        price_source: Option<&dyn PriceSource>,
+    ) -> Result<Amount, CapitalAllocationError> {
+        let currency_code = self.currency_code;
+        if currency_code == symbol.amount_currency_code
+            || currency_code == symbol.base_currency_code()
+            || currency_code == symbol.quote_currency_code()
+        {
+            return Ok(symbol.convert_amount_from_amount_currency_code(
+                currency_code,
+                amount,
+                price,
+            ));
+        }
+
+        let quote_currency_code = symbol.quote_currency_code();
+        let quote_price = price_source
+            .and_then(|x| x.get_price(quote_currency_code, currency_code))
+            .ok_or_else(|| CapitalAllocationError::NotConvertible {
+                configuration_descriptor,
+                currency_pair: symbol.currency_pair().to_string(),
+                currency_code,
+            })?;
+        let quote_amount =
+            symbol.convert_amount_from_amount_currency_code(quote_currency_code, amount, price);
+        Ok(quote_amount * quote_price)
+    }
+}
+
+/// Position of strategy on market which is opened by fills
+#[derive(Debug, Clone)]
+struct FilledPosition {
+    symbol: Arc<Symbol>,
+    /// Signed amount in amount currency, positive for long position
+    amount: Amount,
+    /// Price of last fill
+    price: Price,
+}
+
+/// Limits of capital which strategies can use, so experimental strategies can't use balance
+/// of production strategies on the same exchange account. Strategies without allocation
+/// aren't limited. Both reserved amounts and exposure of positions opened by fills are counted
+#[derive(Clone, Default)]
+pub struct CapitalAllocations {
+    by_configuration_descriptor: HashMap<ConfigurationDescriptor, CapitalAllocation>,
+    positions: HashMap<ConfigurationDescriptor, HashMap<MarketAccountId, FilledPosition>>,
+    price_source: Option<Arc<dyn PriceSource>>,
+}
+
+impl CapitalAllocations {
+    pub fn new<'a>(settings: impl IntoIterator<Item = &'a CapitalAllocationSettings>) -> Self {
+        let by_configuration_descriptor = settings
+            .into_iter()
+            .map(|x| {
+                let allocation = CapitalAllocation {
+                    currency_code: x.currency_code,
+                    max_amount: x.max_amount,
+                };
+                (x.configuration_descriptor(), allocation)
+            })
+            .collect();
+
+        Self {
+            by_configuration_descriptor,
+            positions: HashMap::new(),
+            price_source: None,
+        }
+    }
+
+    /// Enables allocations in currencies outside of currency pairs of strategies
+    pub fn with_price_source(mut self, price_source: Arc<dyn PriceSource>) -> Self {
+        self.price_source = Some(price_source);
+        self
+    }
+
+    /// Updates position of strategy by fill, so it's counted in its allocation
+    pub(crate) fn register_fill(
+        &mut self,
+        configuration_descriptor: ConfigurationDescriptor,
+        exchange_account_id: ExchangeAccountId,
+        symbol: &Arc<Symbol>,
+        side: OrderSide,
+        amount: Amount,
+        price: Price,
+    ) {
+        if !self
+            .by_configuration_descriptor
+            .contains_key(&configuration_descriptor)
+        {
+            return;
+        }
+
+        let market_account_id = MarketAccountId::new(exchange_account_id, symbol.currency_pair());
+        let position = self
+            .positions
+            .entry(configuration_descriptor)
+            .or_default()
+            .entry(market_account_id)
+            .or_insert_with(|| FilledPosition {
+                symbol: symbol.clone(),
+                amount: dec!(0),
+                price,
+            });
+        position.amount += match side {
+            OrderSide::Buy => amount,
+            OrderSide::Sell => -amount,
+        };
+        position.price = price;
+    }
+
+    fn price_source(&self) -> Option<&dyn PriceSource> {
+        self.price_source.as_deref()
+    }
+
+    /// Checks that reservations together with already existing ones fit allocations of strategies
+    pub(crate) fn check<'a>(
+        &self,
+        reserve_parameters: &[ReserveParameters],
+        reservations: impl Iterator<Item = &'a BalanceReservation>,
+    ) -> Result<(), CapitalAllocationError> {
+        if self.by_configuration_descriptor.is_empty() {
+            return Ok(());
+        }
+
+        let mut requested_amounts = HashMap::new();
+        for parameters in reserve_parameters {
+            let configuration_descriptor = parameters.configuration_descriptor;
+            if let Some(allocation) = self
+                .by_configuration_descriptor
+                .get(&configuration_descriptor)
+            {
+                let amount = allocation.convert(
+                    configuration_descriptor,
+                    &parameters.symbol,
+                    parameters.amount,
+                    parameters.price,
+                    self.price_source(),
+                )?;
+                *requested_amounts
+                    .entry(configuration_descriptor)
+                    .or_insert(dec!(0)) += amount;
+            }
+        }
+
+        if requested_amounts.is_empty() {
+            return Ok(());
+        }
+
+        let mut used_amounts = HashMap::new();
+        for reservation in reservations {
+            let configuration_descriptor = reservation.configuration_descriptor;
+            if !requested_amounts.contains_key(&configuration_descriptor) {
+                continue;
+            }
+
+            let allocation = self.by_configuration_descriptor[&configuration_descriptor];
+            let amount = allocation.convert(
+                configuration_descriptor,
+                &reservation.symbol,
+                reservation.unreserved_amount,
+                reservation.price,
+                self.price_source(),
+            )?;
+            *used_amounts
+                .entry(configuration_descriptor)
+                .or_insert(dec!(0)) += amount;
+        }
+
+        for (configuration_descriptor, requested_amount) in requested_amounts {
+            let allocation = self.by_configuration_descriptor[&configuration_descriptor];
+            let mut used_amount = used_amounts
+                .get(&configuration_descriptor)
+                .copied()
+                .unwrap_or(dec!(0));
+            for position in self
+                .positions
+                .get(&configuration_descriptor)
+                .into_iter()
+                .flat_map(|x| x.values())
+            {
+                used_amount += allocation.convert(
+                    configuration_descriptor,
+                    &position.symbol,
+                    position.amount.abs(),
+                    position.price,
+                    self.price_source(),
+                )?;
+            }
+
+            if used_amount + requested_amount > allocation.max_amount {
+                return Err(CapitalAllocationError::Exceeded {
+                    configuration_descriptor,
+                    currency_code: allocation.currency_code,
+                    max_amount: allocation.max_amount,
+                    used_amount,
+                    requested_amount,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Debug for CapitalAllocations {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CapitalAllocations")
+            .field(
+                "by_configuration_descriptor",
+                &self.by_configuration_descriptor,
+            )
+            .field("positions", &self.positions)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::SymbolBuilder;
+
+    fn symbol() -> Arc<Symbol> {
+        Arc::new(SymbolBuilder::new("btc".into(), "usdt".into()).build())
+    }
+
+    fn allocations(currency_code: &str) -> CapitalAllocations {
+        CapitalAllocations::new(&[CapitalAllocationSettings {
+            service_name: "experimental".into(),
+            service_configuration_key: "btc_usdt".into(),
+            currency_code: currency_code.into(),
+            max_amount: dec!(1000),
+        }])
+    }
+
+    fn reserve_parameters(service_name: &str, amount: Amount) -> ReserveParameters {
+        ReserveParameters::new(
+            ConfigurationDescriptor::new(service_name.into(), "btc_usdt".into()),
+            ExchangeAccountId::new("Binance".into(), 0),
+            symbol(),
+            OrderSide::Buy,
+            dec!(50000),
+            amount,
+        )
+    }
+
+    fn reservation(amount: Amount) -> BalanceReservation {
+        let parameters = reserve_parameters("experimental", amount);
+        let mut reservation = BalanceReservation::new(
+            parameters.configuration_descriptor,
+            parameters.exchange_account_id,
+            parameters.symbol,
+            parameters.order_side,
+            parameters.price,
+            amount,
+            dec!(0),
+            amount * parameters.price,
+            "usdt".into(),
+        );
+        reservation.unreserved_amount = amount;
+        reservation
+    }
+
+    #[test]
+    fn reservations_are_limited_by_allocation() {
+        let allocations = allocations("usdt");
+        let reservations = [reservation(dec!(0.01))];
+
+        let fitting = [reserve_parameters("experimental", dec!(0.01))];
+        assert_eq!(allocations.check(&fitting, reservations.iter()), Ok(()));
+
+        let exceeding = [reserve_parameters("experimental", dec!(0.011))];
+        let error = allocations
+            .check(&exceeding, reservations.iter())
+            .expect_err("in test");
+        assert!(matches!(
+            error,
+            CapitalAllocationError::Exceeded {
+                used_amount,
+                requested_amount,
+                ..
+            } if used_amount == dec!(500) && requested_amount == dec!(550)
+        ));
+
+        // Requested amounts of several reservations are summed
+        let pair = [
+            reserve_parameters("experimental", dec!(0.01)),
+            reserve_parameters("experimental", dec!(0.01)),
+        ];
+        assert!(allocations.check(&pair, reservations.iter()).is_err());
+    }
+
+    #[test]
+    fn strategy_without_allocation_is_not_limited() {
+        let parameters = [reserve_parameters("production", dec!(100))];
+
+        assert_eq!(
+            allocations("usdt").check(&parameters, [reservation(dec!(1))].iter()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn filled_positions_are_counted_in_allocation() {
+        let mut allocations = allocations("usdt");
+        let configuration_descriptor =
+            ConfigurationDescriptor::new("experimental".into(), "btc_usdt".into());
+        let exchange_account_id = ExchangeAccountId::new("Binance".into(), 0);
+        allocations.register_fill(
+            configuration_descriptor,
+            exchange_account_id,
+            &symbol(),
+            OrderSide::Buy,
+            dec!(0.01),
+            dec!(40000),
+        );
+
+        let parameters = [reserve_parameters("experimental", dec!(0.01))];
+        assert_eq!(allocations.check(&parameters, [].iter()), Ok(()));
+        assert!(matches!(
+            allocations.check(&parameters, [reservation(dec!(0.003))].iter()),
+            Err(CapitalAllocationError::Exceeded { used_amount, .. }) if used_amount == dec!(550)
+        ));
+
+        // Closed position doesn't use allocation
+        allocations.register_fill(
+            configuration_descriptor,
+            exchange_account_id,
+            &symbol(),
+            OrderSide::Sell,
+            dec!(0.01),
+            dec!(45000),
+        );
+        let parameters = [reserve_parameters("experimental", dec!(0.02))];
+        assert_eq!(allocations.check(&parameters, [].iter()), Ok(()));
+    }
+
+    struct TestPriceSource;
+
+    impl PriceSource for TestPriceSource {
+        fn get_price(&self, from: CurrencyCode, to: CurrencyCode) -> Option<Price> {
+            (from == "usdt".into() && to == "eth".into()).then(|| dec!(0.0005))
+        }
+    }
+
+    #[test]
+    fn allocation_in_currency_outside_pair_is_converted_by_price_source() {
+        let allocations = allocations("eth").with_price_source(Arc::new(TestPriceSource));
+
+        // 0.02 BTC * 50000 USDT * 0.0005 ETH = 0.5 ETH
+        let parameters = [reserve_parameters("experimental", dec!(0.02))];
+        assert_eq!(allocations.check(&parameters, [].iter()), Ok(()));
+
+        let parameters = [reserve_parameters("experimental", dec!(41))];
+        assert!(matches!(
+            allocations.check(&parameters, [].iter()),
+            Err(CapitalAllocationError::Exceeded { requested_amount, .. })
+                if requested_amount == dec!(1025)
+        ));
+    }
+
+    #[test]
+    fn allocation_in_currency_without_price_is_rejected() {
+        let parameters = [reserve_parameters("experimental", dec!(0.01))];
+
+        assert!(matches!(
+            allocations("eth").check(&parameters, [].iter()),
+            Err(CapitalAllocationError::NotConvertible { .. })
+        ));
+    }
+}
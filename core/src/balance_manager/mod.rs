@@ -3,7 +3,9 @@ pub mod balance_manager;
 pub(crate) mod balance_position_by_fill_amount;
 pub mod balance_request;
 pub(crate) mod balance_reservation;
+pub mod balance_snapshot;
 pub(crate) mod balances;
+pub mod capital_allocation;
 pub(crate) mod position_change;
 
 #[cfg(test)]
@@ -15,16 +15,15 @@ use crate::{
     exchanges::{
         common::{Amount, ExchangeAccountId, Price},
         general::{
-            currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter,
-            exchange::Exchange,
-            symbol::{Precision, Symbol},
-            test_helper::get_test_exchange_with_symbol_and_id,
+            currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter, exchange::Exchange,
+            symbol::Symbol, test_helper::get_test_exchange_with_symbol_and_id,
         },
     },
     orders::{
         fill::{OrderFill, OrderFillType},
         order::{OrderFillRole, OrderSide},
     },
+    test_util::SymbolBuilder,
 };
 
 pub struct BalanceManagerDerivative {
@@ -56,14 +55,14 @@ impl BalanceManagerDerivative {
     }
 
     fn create_balance_manager(
-        is_reversed: bool,
+        symbol: Symbol,
     ) -> (
         Arc<Symbol>,
         Arc<Mutex<BalanceManager>>,
         HashMap<ExchangeAccountId, Arc<Exchange>>,
     ) {
         let (symbol, exchanges_by_id) =
-            BalanceManagerDerivative::create_balance_manager_ctor_parameters(is_reversed);
+            BalanceManagerDerivative::create_balance_manager_ctor_parameters(symbol);
         let currency_pair_to_symbol_converter =
             CurrencyPairToSymbolConverter::new(exchanges_by_id.clone());
 
@@ -71,9 +70,7 @@ impl BalanceManagerDerivative {
         (symbol, balance_manager, exchanges_by_id)
     }
 
-    fn create_balance_manager_ctor_parameters(
-        is_reversed: bool,
-    ) -> (Arc<Symbol>, HashMap<ExchangeAccountId, Arc<Exchange>>) {
+    fn create_symbol(is_reversed: bool) -> Symbol {
         let base = BalanceManagerBase::eth();
         let quote = BalanceManagerBase::btc();
 
@@ -83,26 +80,20 @@ impl BalanceManagerDerivative {
             (BalanceManagerBase::eth(), BalanceManagerBase::btc())
         };
 
-        let mut symbol = Symbol::new(
-            false,
-            true,
-            base.as_str().into(),
-            base,
-            quote.as_str().into(),
-            quote,
-            None,
-            None,
-            None,
-            None,
-            None,
-            amount,
-            Some(balance),
-            Precision::ByTick { tick: dec!(0.1) },
-            Precision::ByTick { tick: dec!(0.001) },
-        );
+        let mut symbol = SymbolBuilder::new(base, quote)
+            .is_derivative(true)
+            .amount_currency_code(amount)
+            .balance_currency_code(balance)
+            .build();
         if is_reversed {
             symbol.amount_multiplier = dec!(0.001);
         }
+        symbol
+    }
+
+    fn create_balance_manager_ctor_parameters(
+        symbol: Symbol,
+    ) -> (Arc<Symbol>, HashMap<ExchangeAccountId, Arc<Exchange>>) {
         let symbol = Arc::from(symbol);
         let exchange_1 = get_test_exchange_with_symbol_and_id(
             symbol.clone(),
@@ -124,8 +115,14 @@ impl BalanceManagerDerivative {
     }
 
     fn new(is_reversed: bool) -> Self {
+        BalanceManagerDerivative::new_with_symbol(BalanceManagerDerivative::create_symbol(
+            is_reversed,
+        ))
+    }
+
+    fn new_with_symbol(symbol: Symbol) -> Self {
         let (symbol, balance_manager, exchanges_by_id) =
-            BalanceManagerDerivative::create_balance_manager(is_reversed);
+            BalanceManagerDerivative::create_balance_manager(symbol);
         let mut balance_manager_base = BalanceManagerBase::new();
         balance_manager_base.set_balance_manager(balance_manager);
         balance_manager_base.set_symbol(symbol);
@@ -237,6 +234,7 @@ mod tests {
     use crate::balance_manager::balance_manager::BalanceManager;
     use crate::balance_manager::tests::balance_manager_base::BalanceManagerBase;
     use crate::exchanges::common::{Amount, CurrencyCode, Price};
+    use crate::exchanges::general::symbol::{ContractType, Round};
     use crate::explanation::Explanation;
 
     use crate::orders::order::{OrderSide, OrderStatus, ReservationId};
@@ -385,7 +383,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None)
-            .is_some());
+            .is_ok());
 
         assert_eq!(
             test_object
@@ -440,7 +438,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None)
-            .is_some());
+            .is_ok());
 
         assert_eq!(
             test_object
@@ -455,6 +453,45 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn reservation_should_use_contract_size() {
+        init_infrastructure("log.txt");
+        // Linear contracts of 0.001 eth are equivalent to reversed symbol with amount multiplier
+        let mut symbol = BalanceManagerDerivative::create_symbol(true);
+        symbol.amount_multiplier = dec!(1);
+        let symbol =
+            symbol.with_contract(ContractType::Linear, dec!(0.001), BalanceManagerBase::btc());
+
+        let test_object = BalanceManagerDerivative::new_with_symbol(symbol);
+        let exchange_account_id = test_object.balance_manager_base.exchange_account_id_1;
+        BalanceManagerBase::update_balance(
+            &mut *test_object.balance_manager(),
+            exchange_account_id,
+            hashmap![BalanceManagerBase::btc() => dec!(100)],
+        );
+
+        let reserve_parameters = test_object.balance_manager_base.create_reserve_parameters(
+            OrderSide::Sell,
+            BalanceManagerDerivative::price(),
+            dec!(5),
+        );
+        test_object
+            .balance_manager()
+            .try_reserve(&reserve_parameters, &mut None)
+            .expect("in test");
+
+        assert_eq!(
+            test_object
+                .balance_manager_base
+                .get_balance_by_currency_code(
+                    BalanceManagerBase::btc(),
+                    BalanceManagerDerivative::price()
+                )
+                .expect("in test"),
+            (dec!(100) - dec!(5) * BalanceManagerDerivative::price() * dec!(0.001)) * dec!(0.95)
+        );
+    }
+
     // TODO: add log checking must contain an error
     #[rstest]
     #[case(OrderSide::Buy, true)]
@@ -875,7 +912,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None)
-            .is_some());
+            .is_ok());
 
         assert_eq!(
             test_object
@@ -991,7 +1028,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None)
-            .is_some());
+            .is_ok());
 
         assert_eq!(
             test_object
@@ -1105,7 +1142,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None)
-            .is_some());
+            .is_ok());
 
         assert_eq!(
             test_object
@@ -1221,7 +1258,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None)
-            .is_some());
+            .is_ok());
 
         assert_eq!(
             test_object
@@ -1361,7 +1398,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None)
-            .is_some());
+            .is_ok());
 
         assert_eq!(
             test_object
@@ -1525,7 +1562,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None)
-            .is_some());
+            .is_ok());
         assert_eq!(
             test_object
                 .balance_manager_base
@@ -1686,7 +1723,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None)
-            .is_some());
+            .is_ok());
 
         assert_eq!(
             test_object
@@ -1854,7 +1891,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None)
-            .is_some());
+            .is_ok());
         assert_eq!(
             test_object
                 .balance_manager_base
@@ -2407,7 +2444,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None)
-            .is_some());
+            .is_ok());
 
         assert_eq!(
             test_object
@@ -2474,7 +2511,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None)
-            .is_some());
+            .is_ok());
 
         assert_eq!(
             test_object
@@ -3793,6 +3830,26 @@ mod tests {
             )
             .expect("in test");
         assert_eq!(margin_sell, (dec!(5) + dec!(1.9)) / dec!(0.2) * dec!(0.2));
+
+        let max_amount = test_object
+            .balance_manager()
+            .get_max_amount_by_utilization(
+                test_object
+                    .balance_manager_base
+                    .configuration_descriptor
+                    .clone(),
+                OrderSide::Buy,
+                exchange_account_id,
+                symbol.clone(),
+                BalanceManagerDerivative::price(),
+                dec!(50),
+                &mut None,
+            )
+            .expect("in test");
+        assert_eq!(
+            max_amount,
+            symbol.amount_round(margin_buy * dec!(0.5), Round::Floor)
+        );
     }
 
     #[test]
@@ -4471,7 +4528,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None)
-            .is_some());
+            .is_ok());
 
         let reserve_parameters = test_object.balance_manager_base.create_reserve_parameters(
             OrderSide::Sell,
@@ -4481,6 +4538,6 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None,)
-            .is_none());
+            .is_err());
     }
 }
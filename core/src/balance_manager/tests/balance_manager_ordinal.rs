@@ -18,16 +18,15 @@ use crate::{
     exchanges::{
         common::{Amount, ExchangeAccountId, Price},
         general::{
-            currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter,
-            exchange::Exchange,
-            symbol::{Precision, Symbol},
-            test_helper::get_test_exchange_with_symbol_and_id,
+            currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter, exchange::Exchange,
+            symbol::Symbol, test_helper::get_test_exchange_with_symbol_and_id,
         },
     },
     orders::{
         fill::{OrderFill, OrderFillType},
         order::OrderFillRole,
     },
+    test_util::SymbolBuilder,
 };
 
 pub struct BalanceManagerOrdinal {
@@ -49,23 +48,11 @@ impl BalanceManagerOrdinal {
     ) -> (Arc<Symbol>, HashMap<ExchangeAccountId, Arc<Exchange>>) {
         let base = BalanceManagerBase::eth();
         let quote = BalanceManagerBase::btc();
-        let symbol = Arc::from(Symbol::new(
-            false,
-            false,
-            base.as_str().into(),
-            base,
-            quote.as_str().into(),
-            quote,
-            None,
-            None,
-            None,
-            None,
-            None,
-            base,
-            Some(quote),
-            Precision::ByTick { tick: dec!(0.1) },
-            Precision::ByTick { tick: dec!(0.001) },
-        ));
+        let symbol = Arc::new(
+            SymbolBuilder::new(base, quote)
+                .balance_currency_code(quote)
+                .build(),
+        );
 
         let exchange_1 = get_test_exchange_with_symbol_and_id(
             symbol.clone(),
@@ -144,7 +131,7 @@ impl BalanceManagerOrdinal {
 }
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
     use std::sync::Arc;
     use std::time::Duration;
 
@@ -158,14 +145,15 @@ mod tests {
 
     use crate::balance_manager::balance_manager::BalanceManager;
     use crate::balance_manager::position_change::PositionChange;
+    use crate::error::{MmbError, ValidationError};
     use crate::exchanges::common::{Amount, CurrencyCode, MarketAccountId, Price};
     use crate::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
-    use crate::exchanges::general::symbol::{Precision, Symbol};
     use crate::misc::reserve_parameters::ReserveParameters;
     use crate::orders::order::{
         ClientOrderFillId, ClientOrderId, OrderSide, OrderSnapshot, OrderStatus, ReservationId,
     };
     use crate::orders::pool::OrdersPool;
+    use crate::test_util::SymbolBuilder;
     use crate::{
         balance_manager::tests::balance_manager_base::BalanceManagerBase,
         exchanges::common::ExchangeAccountId,
@@ -560,7 +548,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None,)
-            .is_none());
+            .is_err());
         assert_eq!(
             test_object
                 .balance_manager()
@@ -623,7 +611,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None,)
-            .is_none());
+            .is_err());
         assert_eq!(
             test_object
                 .balance_manager()
@@ -1273,6 +1261,13 @@ mod tests {
                 if !error.to_string().contains("Can't find reservation_id=") {
                     assert!(false, "{:?}", error)
                 }
+                assert!(matches!(
+                    error,
+                    MmbError::Validation {
+                        error: ValidationError::ReservationNotFound(_),
+                        ..
+                    }
+                ));
             }
         };
 
@@ -1290,23 +1285,13 @@ mod tests {
         init_infrastructure("log.txt");
         let test_object = create_test_obj_by_currency_code(BalanceManagerBase::eth(), dec!(5));
 
-        let symbol = Arc::from(Symbol::new(
-            false,
-            false,
-            BalanceManagerBase::eth().as_str().into(),
-            BalanceManagerBase::eth(),
-            BalanceManagerBase::btc().as_str().into(),
-            BalanceManagerBase::btc(),
-            None,
-            None,
-            None,
-            None,
-            Some(dec!(1)),
-            BalanceManagerBase::eth(),
-            Some(BalanceManagerBase::btc()),
-            Precision::ByTick { tick: dec!(0.1) },
-            Precision::ByTick { tick: dec!(1) },
-        ));
+        let symbol = Arc::new(
+            SymbolBuilder::new(BalanceManagerBase::eth(), BalanceManagerBase::btc())
+                .min_cost(dec!(1))
+                .balance_currency_code(BalanceManagerBase::btc())
+                .amount_tick(dec!(1))
+                .build(),
+        );
 
         let reserve_parameters = ReserveParameters::new(
             test_object
@@ -2522,7 +2507,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None)
-            .is_some());
+            .is_ok());
 
         BalanceManagerBase::update_balance(
             &mut *test_object.balance_manager(),
@@ -3187,7 +3172,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None)
-            .is_some());
+            .is_ok());
 
         let order_pool = OrdersPool::new();
         let order_ref = order_pool.add_snapshot_initial(Arc::new(RwLock::new(order.clone())));
@@ -3290,7 +3275,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None)
-            .is_some());
+            .is_ok());
 
         let order_pool = OrdersPool::new();
         let order_ref = order_pool.add_snapshot_initial(Arc::new(RwLock::new(order.clone())));
@@ -3402,7 +3387,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None)
-            .is_some());
+            .is_ok());
         test_object.balance_manager().approve_reservation(
             order.header.reservation_id.expect("in test"),
             &order.header.client_order_id,
@@ -3517,7 +3502,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None)
-            .is_some());
+            .is_ok());
 
         let cloned_balance_manager = BalanceManager::clone_and_subtract_not_approved_data(
             test_object
@@ -4299,7 +4284,7 @@ mod tests {
         assert!(test_object
             .balance_manager()
             .try_reserve(&reserve_parameters, &mut None)
-            .is_some());
+            .is_ok());
 
         assert_eq!(
             test_object
@@ -5329,6 +5314,74 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn unreserve_by_exchange_account_id_keeps_parts_of_not_finished_orders() {
+        init_infrastructure("log.txt");
+        let mut test_object = create_test_obj_by_currency_code(BalanceManagerBase::eth(), dec!(5));
+        let exchange_account_id = test_object.balance_manager_base.exchange_account_id_1;
+
+        let reserve_parameters_1 = test_object.balance_manager_base.create_reserve_parameters(
+            OrderSide::Sell,
+            dec!(0.2),
+            dec!(3),
+        );
+        let reservation_id_1 = test_object
+            .balance_manager()
+            .try_reserve(&reserve_parameters_1, &mut None)
+            .expect("in test");
+        let reserve_parameters_2 = test_object.balance_manager_base.create_reserve_parameters(
+            OrderSide::Sell,
+            dec!(0.2),
+            dec!(1),
+        );
+        let reservation_id_2 = test_object
+            .balance_manager()
+            .try_reserve(&reserve_parameters_2, &mut None)
+            .expect("in test");
+
+        let finished_order = test_object
+            .balance_manager_base
+            .create_order(OrderSide::Sell, reservation_id_1);
+        let not_finished_order = test_object
+            .balance_manager_base
+            .create_order(OrderSide::Sell, reservation_id_1);
+        test_object.balance_manager().approve_reservation(
+            reservation_id_1,
+            &finished_order.header.client_order_id,
+            dec!(1),
+        );
+        test_object.balance_manager().approve_reservation(
+            reservation_id_1,
+            &not_finished_order.header.client_order_id,
+            dec!(1),
+        );
+
+        let not_finished_orders =
+            HashSet::from([not_finished_order.header.client_order_id.clone()]);
+        test_object
+            .balance_manager()
+            .unreserve_by_exchange_account_id(exchange_account_id, &not_finished_orders)
+            .expect("in test");
+
+        let balance_manager = test_object.balance_manager();
+        assert!(balance_manager.get_reservation(reservation_id_2).is_none());
+        let reservation = balance_manager.get_reservation_expected(reservation_id_1);
+        assert_eq!(reservation.unreserved_amount, dec!(1));
+        assert_eq!(reservation.not_approved_amount, dec!(0));
+        assert_eq!(
+            reservation
+                .approved_parts
+                .get(&finished_order.header.client_order_id)
+                .expect("in test")
+                .unreserved_amount,
+            dec!(0)
+        );
+        assert_eq!(
+            balance_manager.get_balance_by_reserve_parameters(&reserve_parameters_1),
+            Some(dec!(4))
+        );
+    }
+
     fn order_was_filled(
         test_object: &mut BalanceManagerOrdinal,
         order: &mut OrderSnapshot,
@@ -21,6 +21,7 @@ use crate::balances::{
     balance_reservation_storage::BalanceReservationStorage,
     virtual_balance_holder::VirtualBalanceHolder,
 };
+use crate::error::ValidationError;
 use crate::exchanges::common::{
     Amount, CurrencyCode, CurrencyPair, ExchangeAccountId, MarketAccountId, Price,
 };
@@ -181,13 +182,15 @@ impl BalanceReservationManager {
                     return Ok(());
                 }
 
-                bail!(
-                    "Can't find reservation_id={} for BalanceReservationManager::unreserve({}) attempt in list: {}",
-                    reservation_id,
-                    amount,
-                    reservation_ids
-                    .to_string()
-                )
+                let error = ValidationError::ReservationNotFound(reservation_id);
+                return Err(error).with_context(|| {
+                    format!(
+                        "Can't find reservation_id={} for BalanceReservationManager::unreserve({}) attempt in list: {}",
+                        reservation_id,
+                        amount,
+                        reservation_ids.to_string()
+                    )
+                });
             }
         };
 
@@ -410,12 +413,11 @@ impl BalanceReservationManager {
                     )
                 });
 
-                let mut free_amount_in_currency_code = symbol
-                    .convert_amount_from_amount_currency_code(
-                        currency_code,
-                        free_amount_in_amount_currency_code,
-                        price,
-                    );
+                let mut free_amount_in_currency_code = symbol.get_amount_value_in_currency(
+                    currency_code,
+                    free_amount_in_amount_currency_code,
+                    price,
+                );
                 free_amount_in_currency_code /= leverage;
                 free_amount_in_currency_code *= symbol.amount_multiplier;
 
@@ -598,7 +600,7 @@ impl BalanceReservationManager {
             )
         });
 
-        let balance_in_amount_currency = symbol.convert_amount_into_amount_currency_code(
+        let balance_in_amount_currency = symbol.get_amount_by_value_in_currency(
             request.currency_code,
             balance_in_currency_code,
             price,
@@ -619,7 +621,7 @@ impl BalanceReservationManager {
             )
         });
 
-        let mut limited_balance_in_currency_code = symbol.convert_amount_from_amount_currency_code(
+        let mut limited_balance_in_currency_code = symbol.get_amount_value_in_currency(
             request.currency_code,
             limited_balance_in_amount_currency,
             price,
@@ -971,7 +973,7 @@ impl BalanceReservationManager {
             );
 
             change_amount_in_currency =
-                symbol.convert_amount_from_amount_currency_code(currency_code, fill_amount, price);
+                symbol.get_amount_value_in_currency(currency_code, fill_amount, price);
         }
         if symbol.amount_currency_code == currency_code {
             let mut position_change = fill_amount;
@@ -998,7 +1000,7 @@ impl BalanceReservationManager {
                     price,
                 );
 
-                change_amount_in_currency = symbol.convert_amount_from_amount_currency_code(
+                change_amount_in_currency = symbol.get_amount_value_in_currency(
                     currency_code,
                     diff_in_amount_currency,
                     price,
@@ -1121,7 +1123,7 @@ impl BalanceReservationManager {
                 symbol.currency_pair(),
                 converted_commission_currency_code,
             );
-            let commission_in_amount_currency = symbol.convert_amount_into_amount_currency_code(
+            let commission_in_amount_currency = symbol.get_amount_by_value_in_currency(
                 converted_commission_currency_code,
                 converted_commission_amount,
                 price,
@@ -1653,15 +1655,12 @@ impl BalanceReservationManager {
             .expect("failed to get exchange")
             .get_balance_reservation_currency_code(symbol.clone(), reserve_parameters.order_side);
 
-        let amount_in_reservation_currency_code = symbol.convert_amount_from_amount_currency_code(
-            reservation_currency_code,
-            amount,
-            price,
-        );
+        let amount_in_reservation_currency_code =
+            symbol.get_amount_value_in_currency(reservation_currency_code, amount, price);
 
         let (cost_in_amount_currency_code, taken_free_amount) =
             self.calculate_reservation_cost(reserve_parameters);
-        let cost_in_reservation_currency_code = symbol.convert_amount_from_amount_currency_code(
+        let cost_in_reservation_currency_code = symbol.get_amount_value_in_currency(
             reservation_currency_code,
             cost_in_amount_currency_code,
             price,
@@ -1741,7 +1740,7 @@ impl BalanceReservationManager {
 
         let new_raw_rest_amount = reservation.amount - approved_sum;
         let new_rest_amount_in_reservation_currency =
-            reservation.symbol.convert_amount_from_amount_currency_code(
+            reservation.symbol.get_amount_value_in_currency(
                 reservation.reservation_currency_code,
                 new_raw_rest_amount,
                 new_price,
@@ -1792,7 +1791,7 @@ impl BalanceReservationManager {
         let reservation = self.get_mut_reservation_expected(reservation_id);
         reservation.price = new_price;
 
-        let reservation_amount_diff = reservation.symbol.convert_amount_into_amount_currency_code(
+        let reservation_amount_diff = reservation.symbol.get_amount_by_value_in_currency(
             reservation.reservation_currency_code,
             reservation_amount_diff_in_reservation_currency,
             reservation.price,
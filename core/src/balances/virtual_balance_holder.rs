@@ -92,7 +92,7 @@ impl VirtualBalanceHolder {
         price: Price,
     ) {
         if !symbol.is_derivative {
-            let diff_in_request_currency = symbol.convert_amount_from_amount_currency_code(
+            let diff_in_request_currency = symbol.get_amount_value_in_currency(
                 request.currency_code,
                 diff_in_amount_currency,
                 price,
@@ -107,7 +107,7 @@ impl VirtualBalanceHolder {
                     .balance_currency_code
                     .expect("symbol.balance_currency_code should be non None"),
             );
-            let diff_in_balance_currency_code = symbol.convert_amount_from_amount_currency_code(
+            let diff_in_balance_currency_code = symbol.get_amount_value_in_currency(
                 balance_currency_code_request.currency_code,
                 diff_in_amount_currency,
                 price,
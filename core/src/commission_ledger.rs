@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use parking_lot::{Mutex, RwLock};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::common::{Amount, CurrencyCode, CurrencyPair, ExchangeAccountId, Price};
+use crate::exchanges::general::exchange::Exchange;
+
+/// Current price of one currency in another
+pub trait PriceSource: Send + Sync {
+    /// Price of `from` currency in `to` currency. `None` if there is no market for conversion
+    fn get_price(&self, from: CurrencyCode, to: CurrencyCode) -> Option<Price>;
+}
+
+/// Mid prices of order book tops of exchanges
+pub struct OrderBookPriceSource {
+    exchanges: Arc<DashMap<ExchangeAccountId, Arc<Exchange>>>,
+}
+
+impl OrderBookPriceSource {
+    pub fn new(exchanges: Arc<DashMap<ExchangeAccountId, Arc<Exchange>>>) -> Arc<Self> {
+        Arc::new(Self { exchanges })
+    }
+
+    fn get_mid_price(&self, currency_pair: CurrencyPair) -> Option<Price> {
+        self.exchanges.iter().find_map(|exchange| {
+            let top = exchange.order_book_top.get(&currency_pair)?;
+            match (&top.ask, &top.bid) {
+                (Some(ask), Some(bid)) => Some((ask.price + bid.price) / dec!(2)),
+                (Some(level), None) | (None, Some(level)) => Some(level.price),
+                (None, None) => None,
+            }
+        })
+    }
+}
+
+impl PriceSource for OrderBookPriceSource {
+    fn get_price(&self, from: CurrencyCode, to: CurrencyCode) -> Option<Price> {
+        if let Some(price) = self.get_mid_price(CurrencyPair::from_codes(from, to)) {
+            return Some(price);
+        }
+
+        self.get_mid_price(CurrencyPair::from_codes(to, from))
+            .filter(|price| !price.is_zero())
+            .map(|price| Decimal::ONE / price)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommissionAccrual {
+    /// Commission in currency in which it is charged
+    pub amount: Amount,
+    /// Commission converted to reference currency at time of fill
+    pub reference_amount: Amount,
+    /// Part of `amount` which isn't included in `reference_amount` because price was unavailable
+    pub unconverted_amount: Amount,
+}
+
+/// Commissions accrued by fills, keyed by currency of commission
+#[derive(Default, Serialize, Deserialize)]
+pub struct CommissionLedger {
+    reference_currency_code: RwLock<Option<CurrencyCode>>,
+    accruals: Mutex<HashMap<CurrencyCode, CommissionAccrual>>,
+    #[serde(skip)]
+    price_source: RwLock<Option<Arc<dyn PriceSource>>>,
+}
+
+impl CommissionLedger {
+    /// Enables conversion of commissions to reference currency
+    pub fn setup_conversion(
+        &self,
+        reference_currency_code: CurrencyCode,
+        price_source: Arc<dyn PriceSource>,
+    ) {
+        *self.reference_currency_code.write() = Some(reference_currency_code);
+        *self.price_source.write() = Some(price_source);
+    }
+
+    pub fn register_commission(&self, currency_code: CurrencyCode, amount: Amount) {
+        if amount.is_zero() {
+            return;
+        }
+
+        let reference_amount = self.convert_to_reference_currency(currency_code, amount);
+
+        let mut accruals = self.accruals.lock();
+        let accrual = accruals.entry(currency_code).or_default();
+        accrual.amount += amount;
+        match reference_amount {
+            Some(reference_amount) => accrual.reference_amount += reference_amount,
+            None => accrual.unconverted_amount += amount,
+        }
+    }
+
+    /// Moves commission which was unconverted at time of fill to `reference_amount` when exchange
+    /// converts it later (see `CommissionCorrectionEvent`)
+    pub fn correct_commission(
+        &self,
+        currency_code: CurrencyCode,
+        amount: Amount,
+        converted_currency_code: CurrencyCode,
+        converted_amount: Amount,
+    ) {
+        if amount.is_zero() {
+            return;
+        }
+
+        let reference_amount =
+            match self.convert_to_reference_currency(converted_currency_code, converted_amount) {
+                Some(reference_amount) => reference_amount,
+                None => return,
+            };
+
+        let mut accruals = self.accruals.lock();
+        let accrual = match accruals.get_mut(&currency_code) {
+            Some(accrual) => accrual,
+            None => return,
+        };
+        // Commission could be converted by price source at time of fill
+        let corrected_amount = amount.min(accrual.unconverted_amount);
+        if corrected_amount.is_zero() {
+            return;
+        }
+
+        accrual.unconverted_amount -= corrected_amount;
+        accrual.reference_amount += reference_amount * corrected_amount / amount;
+    }
+
+    pub fn accruals(&self) -> HashMap<CurrencyCode, CommissionAccrual> {
+        self.accruals.lock().clone()
+    }
+
+    /// Sum of all commissions in reference currency
+    pub fn total_reference_amount(&self) -> Amount {
+        self.accruals
+            .lock()
+            .values()
+            .map(|accrual| accrual.reference_amount)
+            .sum()
+    }
+
+    fn convert_to_reference_currency(
+        &self,
+        currency_code: CurrencyCode,
+        amount: Amount,
+    ) -> Option<Amount> {
+        let reference_currency_code = (*self.reference_currency_code.read())?;
+        if currency_code == reference_currency_code {
+            return Some(amount);
+        }
+
+        let price_source = self.price_source.read().clone()?;
+        let price = price_source.get_price(currency_code, reference_currency_code)?;
+        Some(amount * price)
+    }
+}
+
+impl Debug for CommissionLedger {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CommissionLedger")
+            .field("reference_currency_code", &self.reference_currency_code)
+            .field("accruals", &self.accruals)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestPriceSource;
+
+    impl PriceSource for TestPriceSource {
+        fn get_price(&self, from: CurrencyCode, to: CurrencyCode) -> Option<Price> {
+            (from == "bnb".into() && to == "usdt".into()).then(|| dec!(400))
+        }
+    }
+
+    #[test]
+    fn commissions_are_converted_to_reference_currency() {
+        let ledger = CommissionLedger::default();
+        ledger.setup_conversion("USDT".into(), Arc::new(TestPriceSource));
+
+        ledger.register_commission("BNB".into(), dec!(0.01));
+        ledger.register_commission("BNB".into(), dec!(0.02));
+        ledger.register_commission("USDT".into(), dec!(1.5));
+        ledger.register_commission("BTC".into(), dec!(0.0001));
+
+        let accruals = ledger.accruals();
+        assert_eq!(
+            accruals[&"BNB".into()],
+            CommissionAccrual {
+                amount: dec!(0.03),
+                reference_amount: dec!(12),
+                unconverted_amount: dec!(0),
+            }
+        );
+        assert_eq!(accruals[&"USDT".into()].reference_amount, dec!(1.5));
+        assert_eq!(
+            accruals[&"BTC".into()],
+            CommissionAccrual {
+                amount: dec!(0.0001),
+                reference_amount: dec!(0),
+                unconverted_amount: dec!(0.0001),
+            }
+        );
+        assert_eq!(ledger.total_reference_amount(), dec!(13.5));
+    }
+
+    #[test]
+    fn unconverted_commission_is_corrected() {
+        let ledger = CommissionLedger::default();
+        ledger.setup_conversion("USDT".into(), Arc::new(TestPriceSource));
+        ledger.register_commission("BTC".into(), dec!(0.0001));
+        ledger.register_commission("BTC".into(), dec!(0.0002));
+
+        ledger.correct_commission("BTC".into(), dec!(0.0001), "USDT".into(), dec!(3));
+
+        assert_eq!(
+            ledger.accruals()[&"BTC".into()],
+            CommissionAccrual {
+                amount: dec!(0.0003),
+                reference_amount: dec!(3),
+                unconverted_amount: dec!(0.0002),
+            }
+        );
+    }
+
+    #[test]
+    fn converted_commission_is_not_corrected_twice() {
+        let ledger = CommissionLedger::default();
+        ledger.setup_conversion("USDT".into(), Arc::new(TestPriceSource));
+        ledger.register_commission("BNB".into(), dec!(0.01));
+
+        ledger.correct_commission("BNB".into(), dec!(0.01), "USDT".into(), dec!(5));
+
+        let accrual = &ledger.accruals()[&"BNB".into()];
+        assert_eq!(accrual.reference_amount, dec!(4));
+        assert_eq!(accrual.unconverted_amount, dec!(0));
+    }
+
+    #[test]
+    fn commissions_are_not_converted_without_reference_currency() {
+        let ledger = CommissionLedger::default();
+
+        ledger.register_commission("BNB".into(), dec!(0.01));
+
+        assert_eq!(
+            ledger.accruals()[&"BNB".into()].unconverted_amount,
+            dec!(0.01)
+        );
+        assert_eq!(ledger.total_reference_amount(), dec!(0));
+    }
+}
@@ -1,20 +1,90 @@
-use std::fs::read_to_string;
-use std::{collections::HashMap, io::Write};
+use std::fs::{self, read_to_string};
+use std::path::{Path, PathBuf};
+use std::{collections::HashMap, env, io::Write};
 use std::{fmt::Debug, fs::File};
 
 use crate::lifecycle::launcher::InitSettings;
 use crate::settings::{AppSettings, BaseStrategySettings};
 use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
+use itertools::Itertools;
 use mmb_utils::hashmap;
 use mmb_utils::infrastructure::WithExpect;
 use serde::de::DeserializeOwned;
-use toml_edit::{value, ArrayOfTables, Document, Table};
+use toml_edit::{value, ArrayOfTables, Document, Item, Table, Value};
 
 pub static EXCHANGE_ACCOUNT_ID: &str = "exchange_account_id";
 pub static API_KEY: &str = "api_key";
 pub static SECRET_KEY: &str = "secret_key";
 pub static CONFIG_PATH: &str = "config.toml";
 pub static CREDENTIALS_PATH: &str = "credentials.toml";
+pub static PROFILE_ARG: &str = "--profile=";
+pub static PROFILE_ENV_VAR: &str = "MMB_PROFILE";
+/// Prefix of env variables which override settings, e.g. `MMB__CORE__EXCHANGES__0__IS_MARGIN_TRADING=true`
+pub static ENV_OVERRIDE_PREFIX: &str = "MMB__";
+/// Env variables overrides are applied only if they are enabled by `--env-overrides` argument
+/// or `MMB_ENV_OVERRIDES=true` env variable, so stray variables of host don't change settings silently
+pub static ENV_OVERRIDES_ARG: &str = "--env-overrides";
+pub static ENV_OVERRIDES_ENV_VAR: &str = "MMB_ENV_OVERRIDES";
+const MAX_CONFIG_BACKUPS_COUNT: usize = 10;
+
+/// Profile of environment (e.g. dev, staging, prod) from `--profile=<name>` argument or `MMB_PROFILE` env variable
+pub fn get_profile() -> Option<String> {
+    env::args()
+        .find_map(|arg| arg.strip_prefix(PROFILE_ARG).map(|x| x.to_owned()))
+        .or_else(|| env::var(PROFILE_ENV_VAR).ok())
+        .filter(|x| !x.is_empty())
+}
+
+/// Path of profile overlay of config, e.g. `config.prod.toml` for `config.toml`
+pub fn get_profile_config_path(config_path: &str, profile: &str) -> PathBuf {
+    Path::new(config_path).with_extension(format!("{profile}.toml"))
+}
+
+pub fn is_env_overrides_enabled() -> bool {
+    env::args().any(|arg| arg == ENV_OVERRIDES_ARG)
+        || env::var(ENV_OVERRIDES_ENV_VAR).map_or(false, |x| x == "true" || x == "1")
+}
+
+fn get_env_overrides() -> Vec<(String, String)> {
+    filter_env_overrides(env::vars(), is_env_overrides_enabled())
+}
+
+fn filter_env_overrides(
+    vars: impl Iterator<Item = (String, String)>,
+    is_enabled: bool,
+) -> Vec<(String, String)> {
+    let overrides = vars
+        .filter(|(name, _)| name.starts_with(ENV_OVERRIDE_PREFIX))
+        .sorted()
+        .collect_vec();
+
+    if !is_enabled && !overrides.is_empty() {
+        log::warn!(
+            "Settings overrides from env variables {} are ignored because they aren't enabled by {} argument or {} env variable",
+            overrides.iter().map(|(name, _)| name).join(", "),
+            ENV_OVERRIDES_ARG,
+            ENV_OVERRIDES_ENV_VAR
+        );
+        return vec![];
+    }
+
+    overrides
+}
+
+fn read_profile_settings(config_path: &str) -> Result<Option<String>> {
+    let profile = match get_profile() {
+        Some(profile) => profile,
+        None => return Ok(None),
+    };
+
+    let profile_path = get_profile_config_path(config_path, &profile);
+    let profile_settings = read_to_string(&profile_path).with_context(|| {
+        format!("Unable load settings file of profile {profile}: {profile_path:?}")
+    })?;
+
+    Ok(Some(profile_settings))
+}
 
 pub fn try_load_settings<TSettings>(
     config_path: &str,
@@ -25,10 +95,16 @@ where
 {
     let settings = read_to_string(config_path)
         .with_context(|| format!("Unable load settings file: {}", config_path))?;
+    let profile_settings = read_profile_settings(config_path)?;
     let credentials = read_to_string(credentials_path)
         .with_context(|| format!("Unable load credentials file: {}", credentials_path))?;
 
-    parse_settings(settings.as_str(), credentials.as_str())
+    parse_layered_settings(
+        &settings,
+        profile_settings.as_deref(),
+        &get_env_overrides(),
+        &credentials,
+    )
 }
 
 pub fn load_pretty_settings<StrategySettings>(
@@ -47,9 +123,14 @@ where
         } => {
             let settings = read_to_string(&config_path)
                 .with_expect(|| format!("Unable load settings file: {}", config_path));
+            let profile_settings =
+                read_profile_settings(&config_path).expect("Failed to load profile settings");
             let credentials = read_to_string(&credentials_path)
                 .with_expect(|| format!("Unable load credentials file: {}", credentials_path));
 
+            let settings =
+                merge_settings_layers(&settings, profile_settings.as_deref(), &get_env_overrides())
+                    .expect("Failed to merge settings layers");
             let settings =
                 parse_toml_settings(&settings, &credentials).expect("Failed to parse toml file");
             settings.to_string()
@@ -64,12 +145,117 @@ pub fn parse_settings<TSettings>(
 where
     TSettings: BaseStrategySettings + Clone + Debug + DeserializeOwned,
 {
+    parse_layered_settings(settings, None, &get_env_overrides(), credentials)
+}
+
+/// Settings are merged from base config, overlay of profile and env variables overrides (in order of priority increasing).
+/// Env variables overrides are taken only if they are enabled, see `ENV_OVERRIDES_ARG`.
+/// Tables are merged by keys, arrays of tables (e.g. `core.exchanges`) are merged by indexes
+pub fn parse_layered_settings<TSettings>(
+    settings: &str,
+    profile_settings: Option<&str>,
+    env_overrides: &[(String, String)],
+    credentials: &str,
+) -> Result<AppSettings<TSettings>>
+where
+    TSettings: BaseStrategySettings + Clone + Debug + DeserializeOwned,
+{
+    let settings = merge_settings_layers(settings, profile_settings, env_overrides)?;
     let settings =
-        parse_toml_settings(settings, credentials).context("Unable parse toml settings")?;
+        parse_toml_settings(&settings, credentials).context("Unable parse toml settings")?;
     toml_edit::de::from_document::<AppSettings<TSettings>>(settings)
         .context("Unable parse combined settings")
 }
 
+fn merge_settings_layers(
+    settings: &str,
+    profile_settings: Option<&str>,
+    env_overrides: &[(String, String)],
+) -> Result<String> {
+    let mut settings: Document = settings.parse().context("Unable parse settings")?;
+
+    if let Some(profile_settings) = profile_settings {
+        let profile_settings: Document = profile_settings
+            .parse()
+            .context("Unable parse profile settings")?;
+        merge_tables(settings.as_table_mut(), profile_settings.as_table());
+    }
+
+    for (name, raw_value) in env_overrides {
+        let path = name
+            .trim_start_matches(ENV_OVERRIDE_PREFIX)
+            .split("__")
+            .map(|x| x.to_lowercase())
+            .collect_vec();
+        // Not quoted strings aren't valid toml values, so they are taken as is
+        let override_value = raw_value
+            .parse::<Value>()
+            .unwrap_or_else(|_| Value::from(raw_value.as_str()));
+
+        set_by_path(settings.as_table_mut(), &path, value(override_value))
+            .with_context(|| format!("Unable apply settings override from env variable {name}"))?;
+        // Value isn't logged because it can be credential
+        log::info!(
+            "Setting {} is overridden by env variable {}",
+            path.join("."),
+            name
+        );
+    }
+
+    Ok(settings.to_string())
+}
+
+fn merge_tables(base: &mut Table, overlay: &Table) {
+    for (key, overlay_item) in overlay.iter() {
+        let is_merged = match (base.get_mut(key), overlay_item) {
+            (Some(Item::Table(base_table)), Item::Table(overlay_table)) => {
+                merge_tables(base_table, overlay_table);
+                true
+            }
+            (Some(Item::ArrayOfTables(base_array)), Item::ArrayOfTables(overlay_array)) => {
+                for (index, overlay_table) in overlay_array.iter().enumerate() {
+                    match base_array.get_mut(index) {
+                        Some(base_table) => merge_tables(base_table, overlay_table),
+                        None => base_array.push(overlay_table.clone()),
+                    }
+                }
+                true
+            }
+            _ => false,
+        };
+
+        if !is_merged {
+            let _ = base.insert(key, overlay_item.clone());
+        }
+    }
+}
+
+fn set_by_path(table: &mut Table, path: &[String], item: Item) -> Result<()> {
+    let (key, rest) = path.split_first().context("Settings path is empty")?;
+    if rest.is_empty() {
+        let _ = table.insert(key, item);
+        return Ok(());
+    }
+
+    match table.entry(key).or_insert(toml_edit::table()) {
+        Item::Table(child) => set_by_path(child, rest, item),
+        Item::ArrayOfTables(array) => {
+            let (index, rest) = rest
+                .split_first()
+                .with_context(|| format!("Index of '{key}' item isn't specified"))?;
+            let index: usize = index
+                .parse()
+                .with_context(|| format!("Invalid index {index} of '{key}' item"))?;
+            let child = array
+                .get_mut(index)
+                .with_context(|| format!("There is no item {index} in '{key}'"))?;
+
+            set_by_path(child, rest, item)
+        }
+        _ => bail!("Setting '{key}' isn't a table"),
+    }
+}
+
 pub fn save_settings(settings: &str, config_path: &str, credentials_path: &str) -> Result<()> {
     let mut serialized_settings: Document = settings.parse()?;
 
@@ -97,11 +283,72 @@ pub fn save_settings(settings: &str, config_path: &str, credentials_path: &str)
     }
 
     let serialized_creds = toml_edit::ser::to_string(&credentials_per_exchange)?;
-    let mut credentials_config = File::create(credentials_path)?;
-    credentials_config.write_all(&serialized_creds.as_bytes())?;
+    write_atomically(credentials_path, &serialized_creds)?;
 
-    let mut main_config = File::create(config_path)?;
-    main_config.write_all(&serialized_settings.to_string().as_bytes())?;
+    backup_config(config_path)?;
+    write_atomically(config_path, &serialized_settings.to_string())?;
+
+    Ok(())
+}
+
+/// File is written to temporary file and then renamed, so it is never left partially written
+fn write_atomically(path: &str, content: &str) -> Result<()> {
+    let temp_path = format!("{path}.tmp");
+    let mut file = File::create(&temp_path)
+        .with_context(|| format!("Unable create temporary file {temp_path}"))?;
+    file.write_all(content.as_bytes())?;
+    file.sync_all()?;
+
+    fs::rename(&temp_path, path).with_context(|| format!("Unable replace file {path}"))
+}
+
+/// Copies current config to `<config_path>.<timestamp>.bak` and removes the oldest backups
+/// over `MAX_CONFIG_BACKUPS_COUNT`
+fn backup_config(config_path: &str) -> Result<()> {
+    let config_path = Path::new(config_path);
+    if !config_path.exists() {
+        return Ok(());
+    }
+
+    let file_name = config_path
+        .file_name()
+        .and_then(|x| x.to_str())
+        .context("Invalid config path")?;
+    let backup_prefix = format!("{file_name}.");
+    let backup_path = config_path.with_file_name(format!(
+        "{backup_prefix}{}.bak",
+        Utc::now().format("%Y%m%d%H%M%S%3f")
+    ));
+    let _ = fs::copy(config_path, &backup_path)
+        .with_context(|| format!("Unable backup config to {backup_path:?}"))?;
+
+    let config_dir = match config_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let backups = fs::read_dir(config_dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|x| x.to_str())
+                .map_or(false, |x| {
+                    x.starts_with(&backup_prefix) && x.ends_with(".bak")
+                })
+        })
+        .sorted()
+        .collect_vec();
+
+    let outdated_count = backups.len().saturating_sub(MAX_CONFIG_BACKUPS_COUNT);
+    for outdated_backup in &backups[..outdated_count] {
+        if let Err(error) = fs::remove_file(outdated_backup) {
+            log::warn!(
+                "Unable remove outdated config backup {:?}: {}",
+                outdated_backup,
+                error
+            );
+        }
+    }
 
     Ok(())
 }
@@ -172,3 +419,145 @@ fn get_exchanges_mut(serialized: &mut Document) -> Option<&mut ArrayOfTables> {
         .get_mut("exchanges")?
         .as_array_of_tables_mut()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE_SETTINGS: &str = r#"
+[strategy]
+spread = 1000
+
+[[core.exchanges]]
+exchange_account_id = "Binance_0"
+is_margin_trading = false
+request_trades = false
+"#;
+
+    fn merge(profile_settings: Option<&str>, env_overrides: &[(&str, &str)]) -> Document {
+        let env_overrides = env_overrides
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect_vec();
+
+        merge_settings_layers(BASE_SETTINGS, profile_settings, &env_overrides)
+            .expect("in test")
+            .parse()
+            .expect("in test")
+    }
+
+    fn get_exchange_setting<'a>(settings: &'a Document, key: &str) -> &'a Item {
+        &settings["core"]["exchanges"][0][key]
+    }
+
+    #[test]
+    fn profile_overlay_overrides_base_settings() {
+        let settings = merge(
+            Some(
+                r#"
+[strategy]
+spread = 10
+
+[[core.exchanges]]
+is_margin_trading = true
+"#,
+            ),
+            &[],
+        );
+
+        assert_eq!(settings["strategy"]["spread"].as_integer(), Some(10));
+        assert_eq!(
+            get_exchange_setting(&settings, "is_margin_trading").as_bool(),
+            Some(true)
+        );
+        assert_eq!(
+            get_exchange_setting(&settings, "exchange_account_id").as_str(),
+            Some("Binance_0")
+        );
+    }
+
+    #[test]
+    fn env_overrides_have_highest_priority() {
+        let settings = merge(
+            Some("[strategy]\nspread = 10"),
+            &[
+                ("MMB__STRATEGY__SPREAD", "5"),
+                ("MMB__CORE__EXCHANGES__0__REQUEST_TRADES", "true"),
+                ("MMB__CORE__EXCHANGES__0__EXCHANGE_ACCOUNT_ID", "Binance_1"),
+            ],
+        );
+
+        assert_eq!(settings["strategy"]["spread"].as_integer(), Some(5));
+        assert_eq!(
+            get_exchange_setting(&settings, "request_trades").as_bool(),
+            Some(true)
+        );
+        assert_eq!(
+            get_exchange_setting(&settings, "exchange_account_id").as_str(),
+            Some("Binance_1")
+        );
+    }
+
+    #[test]
+    fn env_overrides_are_applied_only_if_enabled() {
+        let vars = || {
+            vec![
+                ("MMB__STRATEGY__SPREAD".to_owned(), "5".to_owned()),
+                ("PATH".to_owned(), "/usr/bin".to_owned()),
+            ]
+            .into_iter()
+        };
+
+        assert!(filter_env_overrides(vars(), false).is_empty());
+        assert_eq!(
+            filter_env_overrides(vars(), true),
+            vec![("MMB__STRATEGY__SPREAD".to_owned(), "5".to_owned())]
+        );
+    }
+
+    #[test]
+    fn env_override_of_missing_array_item_is_error() {
+        let error = merge_settings_layers(
+            BASE_SETTINGS,
+            None,
+            &[(
+                "MMB__CORE__EXCHANGES__1__REQUEST_TRADES".into(),
+                "true".into(),
+            )],
+        )
+        .expect_err("in test");
+
+        assert!(format!("{error:?}").contains("There is no item 1 in 'exchanges'"));
+    }
+
+    #[test]
+    fn config_is_backed_up_before_saving() {
+        let dir = env::temp_dir().join(format!("mmb_config_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).expect("in test");
+        let config_path = dir.join("config.toml");
+        let config_path = config_path.to_str().expect("in test");
+
+        write_atomically(config_path, "version = 1").expect("in test");
+        backup_config(config_path).expect("in test");
+        write_atomically(config_path, "version = 2").expect("in test");
+
+        let backups = fs::read_dir(&dir)
+            .expect("in test")
+            .map(|entry| entry.expect("in test").path())
+            .filter(|path| path.extension().map_or(false, |x| x == "bak"))
+            .collect_vec();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(read_to_string(&backups[0]).expect("in test"), "version = 1");
+        assert_eq!(read_to_string(config_path).expect("in test"), "version = 2");
+
+        fs::remove_dir_all(&dir).expect("in test");
+    }
+
+    #[test]
+    fn profile_config_path() {
+        assert_eq!(
+            get_profile_config_path("configs/config.toml", "prod"),
+            PathBuf::from("configs/config.prod.toml")
+        );
+    }
+}
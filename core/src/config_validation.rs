@@ -0,0 +1,748 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
+use std::sync::Arc;
+
+use itertools::Itertools;
+use mmb_rpc::rest_api::IPC_ADDRESS;
+use rust_decimal_macros::dec;
+use thiserror::Error;
+
+use crate::exchanges::common::{CurrencyPair, ExchangeId};
+use crate::exchanges::traits::ExchangeClientBuilder;
+use crate::settings::{
+    AppSettings, BaseStrategySettings, CoreSettings, CurrencyPairSetting, ExchangeSettings,
+    MaxAmountSettings,
+};
+
+/// Problem of settings with path to the setting in config
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigDiagnostic {
+    pub path: String,
+    pub message: String,
+}
+
+impl ConfigDiagnostic {
+    fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for ConfigDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// All problems found in settings, so they can be fixed at once instead of one per engine start
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Settings are invalid:\n{}", .diagnostics.iter().map(|x| format!("  - {x}")).join("\n"))]
+pub struct ConfigValidationError {
+    pub diagnostics: Vec<ConfigDiagnostic>,
+}
+
+/// Supported exchanges with websocket channels which can be requested in their settings
+pub type SupportedExchanges = HashMap<ExchangeId, HashSet<String>>;
+
+pub fn supported_exchanges(
+    exchange_client_builders: &HashMap<ExchangeId, Arc<dyn ExchangeClientBuilder>>,
+) -> SupportedExchanges {
+    exchange_client_builders
+        .iter()
+        .map(|(exchange_id, builder)| {
+            let channels = builder.get_supported_websocket_channels();
+            (*exchange_id, channels.into_iter().collect())
+        })
+        .collect()
+}
+
+/// Checks settings which are parsed successfully but can't work together
+pub fn validate_settings<StrategySettings>(
+    settings: &AppSettings<StrategySettings>,
+    supported_exchanges: &SupportedExchanges,
+) -> Result<(), ConfigValidationError>
+where
+    StrategySettings: BaseStrategySettings + Clone,
+{
+    let mut diagnostics = validate_core_settings(&settings.core, supported_exchanges);
+
+    let strategy_exchange_account_id = settings.strategy.exchange_account_id();
+    if !settings
+        .core
+        .exchanges
+        .iter()
+        .any(|x| x.exchange_account_id == strategy_exchange_account_id)
+    {
+        diagnostics.push(ConfigDiagnostic::new(
+            "strategy",
+            format!("exchange account {strategy_exchange_account_id} of strategy isn't specified in core.exchanges"),
+        ));
+    }
+
+    if let MaxAmountSettings::BalanceUtilization {
+        utilization_percent,
+    } = settings.strategy.max_amount_settings()
+    {
+        if utilization_percent <= dec!(0) || utilization_percent > dec!(100) {
+            diagnostics.push(ConfigDiagnostic::new(
+                "strategy",
+                format!("utilization percent {utilization_percent} of max amount should be in range (0, 100]"),
+            ));
+        }
+    }
+
+    if let Some(order_randomization) = settings.strategy.order_randomization() {
+        let percent = order_randomization.max_amount_reduction_percent;
+        if percent < dec!(0) || percent >= dec!(100) {
+            diagnostics.push(ConfigDiagnostic::new(
+                "strategy",
+                format!("max amount reduction percent {percent} of order randomization should be in range [0, 100)"),
+            ));
+        }
+    }
+
+    if let Some(inventory_skew) = settings.strategy.inventory_skew() {
+        if let Err(error) = inventory_skew.validate() {
+            diagnostics.push(ConfigDiagnostic::new("strategy", error.to_string()));
+        }
+    }
+
+    match diagnostics.is_empty() {
+        true => Ok(()),
+        false => Err(ConfigValidationError { diagnostics }),
+    }
+}
+
+/// Checks settings of exchange account which is added after engine start
+pub fn validate_added_exchange_settings(
+    exchange: &ExchangeSettings,
+    supported_exchanges: &SupportedExchanges,
+) -> Result<(), ConfigValidationError> {
+    let mut diagnostics = Vec::new();
+    let path = format!("exchange[{}]", exchange.exchange_account_id);
+    validate_exchange_settings(exchange, &path, supported_exchanges, &mut diagnostics);
+
+    match diagnostics.is_empty() {
+        true => Ok(()),
+        false => Err(ConfigValidationError { diagnostics }),
+    }
+}
+
+fn validate_core_settings(
+    settings: &CoreSettings,
+    supported_exchanges: &SupportedExchanges,
+) -> Vec<ConfigDiagnostic> {
+    let mut diagnostics = Vec::new();
+
+    if settings.exchanges.is_empty() {
+        diagnostics.push(ConfigDiagnostic::new(
+            "core.exchanges",
+            "at least one exchange should be specified",
+        ));
+    }
+
+    let mut exchange_account_ids = HashSet::new();
+    for exchange in &settings.exchanges {
+        let path = format!("core.exchanges[{}]", exchange.exchange_account_id);
+        if !exchange_account_ids.insert(exchange.exchange_account_id) {
+            diagnostics.push(ConfigDiagnostic::new(
+                &path,
+                "exchange account is specified more than once",
+            ));
+        }
+
+        validate_exchange_settings(exchange, &path, supported_exchanges, &mut diagnostics);
+    }
+
+    if let Some(treasury) = &settings.treasury {
+        for (index, refill) in treasury.refills.iter().enumerate() {
+            for exchange_account_id in [
+                refill.exchange_account_id,
+                refill.source_exchange_account_id,
+            ] {
+                if !exchange_account_ids.contains(&exchange_account_id) {
+                    diagnostics.push(ConfigDiagnostic::new(
+                        format!("core.treasury.refills[{index}]"),
+                        format!("exchange account {exchange_account_id} isn't specified in core.exchanges"),
+                    ));
+                }
+            }
+        }
+    }
+
+    let mut configuration_descriptors = HashSet::new();
+    for (index, allocation) in settings.capital_allocations.iter().flatten().enumerate() {
+        let path = format!("core.capital_allocations[{index}]");
+        if !configuration_descriptors.insert(allocation.configuration_descriptor()) {
+            diagnostics.push(ConfigDiagnostic::new(
+                &path,
+                "allocation is specified more than once for strategy configuration",
+            ));
+        }
+
+        if allocation.max_amount <= dec!(0) {
+            diagnostics.push(ConfigDiagnostic::new(
+                format!("{path}.max_amount"),
+                "max amount should be greater than 0",
+            ));
+        }
+    }
+
+    if let Some(exposure_limits) = &settings.exposure_limits {
+        let limits_by_name = [
+            (
+                "max_notional_by_currency",
+                &exposure_limits.max_notional_by_currency,
+            ),
+            (
+                "max_delta_by_underlying",
+                &exposure_limits.max_delta_by_underlying,
+            ),
+        ];
+        for (name, limits) in limits_by_name {
+            for (currency_code, limit) in limits {
+                if *limit <= dec!(0) {
+                    diagnostics.push(ConfigDiagnostic::new(
+                        format!("core.exposure_limits.{name}.{currency_code}"),
+                        "limit should be greater than 0",
+                    ));
+                }
+            }
+        }
+    }
+
+    if let Some(fill_anomaly) = &settings.fill_anomaly {
+        if fill_anomaly.max_price_deviation_percent <= dec!(0) {
+            diagnostics.push(ConfigDiagnostic::new(
+                "core.fill_anomaly.max_price_deviation_percent",
+                "percent should be greater than 0",
+            ));
+        }
+        if fill_anomaly.amount_tolerance_percent < dec!(0) {
+            diagnostics.push(ConfigDiagnostic::new(
+                "core.fill_anomaly.amount_tolerance_percent",
+                "percent shouldn't be negative",
+            ));
+        }
+    }
+
+    if let Some(event_loop_watchdog) = &settings.event_loop_watchdog {
+        if event_loop_watchdog.stall_threshold.is_zero() {
+            diagnostics.push(ConfigDiagnostic::new(
+                "core.event_loop_watchdog.stall_threshold",
+                "threshold should be greater than 0",
+            ));
+        }
+    }
+
+    if let Some(threshold) = settings.latency_degradation_threshold {
+        if threshold.is_zero() {
+            diagnostics.push(ConfigDiagnostic::new(
+                "core.latency_degradation_threshold",
+                "threshold should be greater than 0",
+            ));
+        }
+    }
+
+    if let Some(stats_snapshots) = &settings.stats_snapshots {
+        if stats_snapshots.interval.is_zero() {
+            diagnostics.push(ConfigDiagnostic::new(
+                "core.stats_snapshots.interval",
+                "interval should be greater than 0",
+            ));
+        }
+        if settings
+            .exchanges
+            .iter()
+            .all(|x| x.traffic_record_path.is_none())
+        {
+            diagnostics.push(ConfigDiagnostic::new(
+                "core.stats_snapshots",
+                "snapshots are recorded to traffic record files, so traffic_record_path should be set for some exchange",
+            ));
+        }
+    }
+
+    if let Some(min_profitable_spread) = &settings.min_profitable_spread {
+        let maker_fill_ratio = min_profitable_spread.maker_fill_ratio;
+        if maker_fill_ratio < dec!(0) || maker_fill_ratio > dec!(1) {
+            diagnostics.push(ConfigDiagnostic::new(
+                "core.min_profitable_spread.maker_fill_ratio",
+                "ratio should be between 0 and 1",
+            ));
+        }
+        if min_profitable_spread.conversion_cost_percent < dec!(0) {
+            diagnostics.push(ConfigDiagnostic::new(
+                "core.min_profitable_spread.conversion_cost_percent",
+                "percent shouldn't be negative",
+            ));
+        }
+    }
+
+    if let Some(event_channels) = &settings.event_channels {
+        if event_channels.exchange_events_capacity == 0 {
+            diagnostics.push(ConfigDiagnostic::new(
+                "core.event_channels.exchange_events_capacity",
+                "capacity should be greater than 0",
+            ));
+        }
+
+        if event_channels.market_events_warning_depth == 0 {
+            diagnostics.push(ConfigDiagnostic::new(
+                "core.event_channels.market_events_warning_depth",
+                "depth should be greater than 0",
+            ));
+        }
+    }
+
+    if let Some(handover) = &settings.handover {
+        let ipc_address = settings.rpc_ipc_address.as_deref().unwrap_or(IPC_ADDRESS);
+        if handover.source_ipc_address == ipc_address {
+            diagnostics.push(ConfigDiagnostic::new(
+                "core.handover.source_ipc_address",
+                "address of running engine should differ from core.rpc_ipc_address",
+            ));
+        }
+        if handover.request_timeout.is_zero() {
+            diagnostics.push(ConfigDiagnostic::new(
+                "core.handover.request_timeout",
+                "timeout should be greater than 0",
+            ));
+        }
+    }
+
+    let mut cancel_priority_strategies = HashSet::new();
+    for (index, priority) in settings.cancel_priorities.iter().flatten().enumerate() {
+        let path = format!("core.cancel_priorities[{index}]");
+        if !cancel_priority_strategies.insert(priority.strategy_name.as_deref()) {
+            diagnostics.push(ConfigDiagnostic::new(
+                &path,
+                "cancel priorities are specified more than once for strategy",
+            ));
+        }
+
+        if !priority.order.iter().all_unique() {
+            diagnostics.push(ConfigDiagnostic::new(
+                format!("{path}.order"),
+                "class of orders is specified more than once",
+            ));
+        }
+    }
+
+    if let Some(client_order_id) = &settings.client_order_id {
+        for problem in client_order_id.validate() {
+            diagnostics.push(ConfigDiagnostic::new("core.client_order_id", problem));
+        }
+    }
+
+    diagnostics
+}
+
+fn validate_exchange_settings(
+    exchange: &ExchangeSettings,
+    path: &str,
+    supported_exchanges: &SupportedExchanges,
+    diagnostics: &mut Vec<ConfigDiagnostic>,
+) {
+    let exchange_id = exchange.exchange_account_id.exchange_id;
+    match supported_exchanges.get(&exchange_id) {
+        Some(supported_channels) => {
+            for (index, channel) in exchange.websocket_channels.iter().enumerate() {
+                if !supported_channels.contains(channel) {
+                    let supported = supported_channels.iter().sorted();
+                    diagnostics.push(ConfigDiagnostic::new(
+                        format!("{path}.websocket_channels[{index}]"),
+                        format!(
+                            "websocket channel {channel} isn't supported, supported channels: {}",
+                            supported.format(", ")
+                        ),
+                    ));
+                }
+            }
+        }
+        None => {
+            let supported = supported_exchanges.keys().map(|x| x.to_string()).sorted();
+            diagnostics.push(ConfigDiagnostic::new(
+                path,
+                format!(
+                    "unknown exchange id {exchange_id}, supported exchanges: {}",
+                    supported.format(", ")
+                ),
+            ));
+        }
+    }
+
+    if matches!(&exchange.currency_pairs, Some(currency_pairs) if currency_pairs.is_empty()) {
+        diagnostics.push(ConfigDiagnostic::new(
+            format!("{path}.currency_pairs"),
+            "currency pairs are empty, remove setting to use all currency pairs of exchange",
+        ));
+    }
+
+    // `Specific` setting is matched with currency pair in format `base/quote`,
+    // so it can resolve to the same symbol as `Ordinary` one
+    let currency_pairs = exchange
+        .currency_pairs
+        .iter()
+        .flatten()
+        .map(|currency_pair| match currency_pair {
+            CurrencyPairSetting::Ordinary { base, quote } => {
+                CurrencyPair::from_codes(*base, *quote).to_string()
+            }
+            CurrencyPairSetting::Specific(currency_pair) => currency_pair.clone(),
+        });
+    for (index, currency_pair) in currency_pairs.enumerate().duplicates_by(|(_, x)| x.clone()) {
+        diagnostics.push(ConfigDiagnostic::new(
+            format!("{path}.currency_pairs[{index}]"),
+            format!("currency pair {currency_pair} is specified more than once"),
+        ));
+    }
+
+    if exchange.request_trades && !exchange.subscribe_to_market_data {
+        diagnostics.push(ConfigDiagnostic::new(
+            format!("{path}.request_trades"),
+            "trades can't be requested when subscribe_to_market_data = false",
+        ));
+    }
+
+    if !exchange.is_margin_trading {
+        for (setting, is_specified) in [
+            ("position_mode", exchange.position_mode.is_some()),
+            ("margin_monitoring", exchange.margin_monitoring.is_some()),
+        ] {
+            if is_specified {
+                diagnostics.push(ConfigDiagnostic::new(
+                    format!("{path}.{setting}"),
+                    "setting is available only when is_margin_trading = true",
+                ));
+            }
+        }
+    }
+
+    if exchange.traffic_record_path.is_some() && exchange.traffic_replay_path.is_some() {
+        diagnostics.push(ConfigDiagnostic::new(
+            format!("{path}.traffic_replay_path"),
+            "traffic can't be recorded and replayed at the same time",
+        ));
+    }
+
+    if let Some(trading_windows) = &exchange.trading_windows {
+        for (index, window) in trading_windows.iter().enumerate() {
+            let mut weekdays = window.weekdays.iter().flatten();
+            if let Some(weekday) = weekdays.find(|x| !(1..=7).contains(*x)) {
+                diagnostics.push(ConfigDiagnostic::new(
+                    format!("{path}.trading_windows[{index}].weekdays"),
+                    format!("weekday {weekday} should be from 1 (Monday) to 7 (Sunday)"),
+                ));
+            }
+        }
+    }
+
+    if let Some(order_book_polling) = &exchange.order_book_polling {
+        if order_book_polling.depth == 0 {
+            diagnostics.push(ConfigDiagnostic::new(
+                format!("{path}.order_book_polling.depth"),
+                "depth should be greater than 0",
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balance_manager::capital_allocation::CapitalAllocationSettings;
+    use crate::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId};
+    use crate::lifecycle::handover::HandoverSettings;
+    use crate::services::stats_snapshots::StatsSnapshotsSettings;
+    use crate::settings::EventChannelsSettings;
+    use crate::strategies::inventory_skew::InventorySkewSettings;
+
+    #[derive(Debug, Clone, Default)]
+    struct TestStrategySettings {
+        inventory_skew: Option<InventorySkewSettings>,
+    }
+
+    impl BaseStrategySettings for TestStrategySettings {
+        fn exchange_account_id(&self) -> ExchangeAccountId {
+            ExchangeAccountId::new("Binance".into(), 0)
+        }
+
+        fn currency_pair(&self) -> CurrencyPair {
+            CurrencyPair::from_codes("btc".into(), "usdt".into())
+        }
+
+        fn max_amount(&self) -> Amount {
+            dec!(1)
+        }
+
+        fn inventory_skew(&self) -> Option<InventorySkewSettings> {
+            self.inventory_skew.clone()
+        }
+    }
+
+    fn exchange_settings(exchange_account_id: ExchangeAccountId) -> ExchangeSettings {
+        ExchangeSettings {
+            exchange_account_id,
+            websocket_channels: vec!["depth20".into()],
+            ..ExchangeSettings::default()
+        }
+    }
+
+    fn supported_exchanges() -> SupportedExchanges {
+        HashMap::from([(
+            "Binance".into(),
+            HashSet::from(["depth20".to_owned(), "trade".to_owned()]),
+        )])
+    }
+
+    fn settings(exchanges: Vec<ExchangeSettings>) -> AppSettings<TestStrategySettings> {
+        AppSettings {
+            strategy: TestStrategySettings::default(),
+            core: CoreSettings {
+                exchanges,
+                ..CoreSettings::default()
+            },
+            shadow_strategy: None,
+        }
+    }
+
+    #[test]
+    fn valid_settings() {
+        let settings = settings(vec![exchange_settings(ExchangeAccountId::new(
+            "Binance".into(),
+            0,
+        ))]);
+
+        assert_eq!(validate_settings(&settings, &supported_exchanges()), Ok(()));
+    }
+
+    #[test]
+    fn all_diagnostics_are_aggregated() {
+        let mut binance = exchange_settings(ExchangeAccountId::new("Binance".into(), 0));
+        binance.subscribe_to_market_data = false;
+        binance.request_trades = true;
+        binance.currency_pairs = Some(vec![]);
+        let unknown = exchange_settings(ExchangeAccountId::new("Unknown".into(), 0));
+        let settings = settings(vec![binance, unknown]);
+
+        let error = validate_settings(&settings, &supported_exchanges()).expect_err("in test");
+
+        let paths = error
+            .diagnostics
+            .iter()
+            .map(|x| x.path.as_str())
+            .collect_vec();
+        assert_eq!(
+            paths,
+            [
+                "core.exchanges[Binance_0].currency_pairs",
+                "core.exchanges[Binance_0].request_trades",
+                "core.exchanges[Unknown_0]",
+            ]
+        );
+        assert!(error.to_string().contains("unknown exchange id Unknown"));
+    }
+
+    #[test]
+    fn stats_snapshots_require_traffic_recording() {
+        let mut binance = exchange_settings(ExchangeAccountId::new("Binance".into(), 0));
+        let mut settings = settings(vec![binance.clone()]);
+        settings.core.stats_snapshots = Some(StatsSnapshotsSettings::default());
+
+        let error = validate_settings(&settings, &supported_exchanges()).expect_err("in test");
+
+        assert_eq!(error.diagnostics.len(), 1);
+        assert_eq!(error.diagnostics[0].path, "core.stats_snapshots");
+
+        binance.traffic_record_path = Some("binance_traffic.jsonl".into());
+        settings.core.exchanges = vec![binance];
+        assert_eq!(validate_settings(&settings, &supported_exchanges()), Ok(()));
+    }
+
+    #[test]
+    fn strategy_exchange_account_should_be_specified() {
+        let settings = settings(vec![exchange_settings(ExchangeAccountId::new(
+            "Binance".into(),
+            1,
+        ))]);
+
+        let error = validate_settings(&settings, &supported_exchanges()).expect_err("in test");
+
+        assert_eq!(error.diagnostics.len(), 1);
+        assert_eq!(error.diagnostics[0].path, "strategy");
+    }
+
+    #[test]
+    fn inventory_skew_max_deviation_should_be_positive() {
+        let mut settings = settings(vec![exchange_settings(ExchangeAccountId::new(
+            "Binance".into(),
+            0,
+        ))]);
+        settings.strategy.inventory_skew = Some(InventorySkewSettings {
+            target_inventory: dec!(0),
+            max_deviation: dec!(-1),
+            max_price_skew: dec!(0.001),
+        });
+
+        let error = validate_settings(&settings, &supported_exchanges()).expect_err("in test");
+
+        assert_eq!(
+            error.diagnostics,
+            vec![ConfigDiagnostic::new(
+                "strategy",
+                "max deviation -1 of inventory skew should be positive",
+            )]
+        );
+    }
+
+    #[test]
+    fn capital_allocations() {
+        let mut settings = settings(vec![exchange_settings(ExchangeAccountId::new(
+            "Binance".into(),
+            0,
+        ))]);
+        let allocation = CapitalAllocationSettings {
+            service_name: "experimental".into(),
+            service_configuration_key: "btc_usdt".into(),
+            currency_code: "btc".into(),
+            max_amount: dec!(0),
+        };
+        settings.core.capital_allocations = Some(vec![allocation.clone(), allocation]);
+
+        let error = validate_settings(&settings, &supported_exchanges()).expect_err("in test");
+
+        let paths = error
+            .diagnostics
+            .iter()
+            .map(|x| x.path.as_str())
+            .collect_vec();
+        assert_eq!(
+            paths,
+            [
+                "core.capital_allocations[0].max_amount",
+                "core.capital_allocations[1]",
+                "core.capital_allocations[1].max_amount",
+            ]
+        );
+    }
+
+    #[test]
+    fn added_exchange_settings() {
+        let valid = exchange_settings(ExchangeAccountId::new("Binance".into(), 1));
+        assert_eq!(
+            validate_added_exchange_settings(&valid, &supported_exchanges()),
+            Ok(())
+        );
+
+        let mut invalid = exchange_settings(ExchangeAccountId::new("Binance".into(), 1));
+        invalid.currency_pairs = Some(vec![]);
+        let error = validate_added_exchange_settings(&invalid, &supported_exchanges())
+            .expect_err("in test");
+
+        assert_eq!(error.diagnostics.len(), 1);
+        assert_eq!(
+            error.diagnostics[0].path,
+            "exchange[Binance_1].currency_pairs"
+        );
+    }
+
+    #[test]
+    fn unsupported_websocket_channel() {
+        let mut exchange = exchange_settings(ExchangeAccountId::new("Binance".into(), 0));
+        exchange.websocket_channels = vec!["trade".into(), "kline".into()];
+        let error = validate_settings(&settings(vec![exchange]), &supported_exchanges())
+            .expect_err("in test");
+
+        assert_eq!(
+            error.diagnostics,
+            vec![ConfigDiagnostic::new(
+                "core.exchanges[Binance_0].websocket_channels[1]",
+                "websocket channel kline isn't supported, supported channels: depth20, trade",
+            )]
+        );
+    }
+
+    #[test]
+    fn handover_source_should_differ_from_own_address() {
+        let mut settings = settings(vec![exchange_settings(ExchangeAccountId::new(
+            "Binance".into(),
+            0,
+        ))]);
+        settings.core.handover = Some(HandoverSettings::default());
+
+        let error = validate_settings(&settings, &supported_exchanges()).expect_err("in test");
+
+        assert_eq!(error.diagnostics.len(), 1);
+        assert_eq!(
+            error.diagnostics[0].path,
+            "core.handover.source_ipc_address"
+        );
+
+        settings.core.rpc_ipc_address = Some("/tmp/mmb_core_green.ipc".to_owned());
+        assert_eq!(validate_settings(&settings, &supported_exchanges()), Ok(()));
+    }
+
+    #[test]
+    fn duplicated_currency_pairs_of_exchange() {
+        let mut binance = exchange_settings(ExchangeAccountId::new("Binance".into(), 0));
+        binance.currency_pairs = Some(vec![
+            CurrencyPairSetting::Ordinary {
+                base: "btc".into(),
+                quote: "usdt".into(),
+            },
+            CurrencyPairSetting::Specific("ETHUSDT".into()),
+            CurrencyPairSetting::Ordinary {
+                base: "btc".into(),
+                quote: "usdt".into(),
+            },
+            CurrencyPairSetting::Specific("btc/usdt".into()),
+        ]);
+
+        let error = validate_settings(&settings(vec![binance]), &supported_exchanges())
+            .expect_err("in test");
+
+        assert_eq!(
+            error.diagnostics,
+            [
+                ConfigDiagnostic::new(
+                    "core.exchanges[Binance_0].currency_pairs[2]",
+                    "currency pair btc/usdt is specified more than once",
+                ),
+                ConfigDiagnostic::new(
+                    "core.exchanges[Binance_0].currency_pairs[3]",
+                    "currency pair btc/usdt is specified more than once",
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn zero_sizes_of_event_channels() {
+        let mut settings = settings(vec![exchange_settings(ExchangeAccountId::new(
+            "Binance".into(),
+            0,
+        ))]);
+        settings.core.event_channels = Some(EventChannelsSettings {
+            exchange_events_capacity: 0,
+            market_events_warning_depth: 0,
+        });
+
+        let error = validate_settings(&settings, &supported_exchanges()).expect_err("in test");
+
+        let paths = error
+            .diagnostics
+            .iter()
+            .map(|x| x.path.as_str())
+            .collect_vec();
+        assert_eq!(
+            paths,
+            [
+                "core.event_channels.exchange_events_capacity",
+                "core.event_channels.market_events_warning_depth",
+            ]
+        );
+    }
+}
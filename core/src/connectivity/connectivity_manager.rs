@@ -10,22 +10,33 @@ use futures::Future;
 use log::log;
 use mmb_utils::{cancellation_token::CancellationToken, send_expected::SendExpectedByRef};
 use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
 use std::pin::Pin;
 use std::{
     borrow::Borrow,
     ops::DerefMut,
     sync::{Arc, Weak},
+    time::Duration,
 };
 use tokio::sync::broadcast;
 
 pub const MAX_RETRY_CONNECT_COUNT: u32 = 3;
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
 pub enum WebSocketRole {
     Main,
     Secondary,
 }
 
+/// State of websocket connection for diagnostics
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebSocketStatus {
+    Disconnected,
+    Connecting,
+    Connected,
+}
+
 struct WebSocketConnectivity {
     state: WebSocketState,
 }
@@ -86,6 +97,7 @@ pub struct ConnectivityManager {
     callback_connected: Mutex<Callback0>,
     callback_disconnected: Mutex<Callback1<bool, ()>>,
     callback_msg_received: Mutex<WSMessageReceived>,
+    reconnect_backoff: Mutex<Duration>,
 }
 
 impl ConnectivityManager {
@@ -107,9 +119,15 @@ impl ConnectivityManager {
             callback_msg_received: Mutex::new(Box::new(|_| {
                 panic!("callback_msg_received has to be set during ConnectivityManager::connect()")
             })),
+            reconnect_backoff: Mutex::new(Duration::ZERO),
         })
     }
 
+    /// Delay between failed attempts to open websocket connection
+    pub fn set_reconnect_backoff(&self, reconnect_backoff: Duration) {
+        *self.reconnect_backoff.lock() = reconnect_backoff;
+    }
+
     pub fn set_callback_connecting(&self, connecting: Callback0) {
         *self.callback_connecting.lock() = connecting;
     }
@@ -199,6 +217,17 @@ impl ConnectivityManager {
         let _ = finished_receiver.recv().await;
     }
 
+    pub async fn websocket_status(&self, role: WebSocketRole) -> WebSocketStatus {
+        match &self.websockets.get_websocket_state(role).lock().await.state {
+            Disconnected => WebSocketStatus::Disconnected,
+            WebSocketState::Connecting { .. } => WebSocketStatus::Connecting,
+            WebSocketState::Connected { websocket, .. } => match websocket.is_connected() {
+                true => WebSocketStatus::Connected,
+                false => WebSocketStatus::Disconnected,
+            },
+        }
+    }
+
     pub async fn send(&self, role: WebSocketRole, message: &str) {
         if let WebSocketState::Connected { ref websocket, .. } = self
             .websockets
@@ -342,6 +371,11 @@ impl ConnectivityManager {
                             self.exchange_account_id
                         );
                     }
+
+                    let reconnect_backoff = *self.reconnect_backoff.lock();
+                    if !reconnect_backoff.is_zero() {
+                        tokio::time::sleep(reconnect_backoff).await;
+                    }
                 }
                 Err(error) => log::warn!(
                     "Error while getting parameters for websocket {:?}: {:#}",
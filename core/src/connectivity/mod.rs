@@ -1,2 +1,5 @@
 pub mod connectivity_manager;
+pub mod proxy;
+pub mod websocket_channels;
 pub mod websocket_connection;
+pub mod websocket_message_router;
@@ -0,0 +1,85 @@
+use anyhow::{bail, Result};
+use itertools::Itertools;
+use parking_lot::RwLock;
+
+use crate::connectivity::websocket_message_router::WebSocketMessageRouter;
+
+/// Websocket channels which exchange client subscribes to on every connection of main websocket,
+/// so they are resubscribed after reconnection too. Only channels with handler in message router
+/// are accepted, so messages of every subscribed channel are routed
+pub struct WebSocketChannels {
+    channels: RwLock<Vec<String>>,
+}
+
+impl WebSocketChannels {
+    /// Requested channels which can't be handled by router are skipped. Channels of settings
+    /// are validated on settings loading, so it happens only for manually built settings
+    pub fn new<C>(requested_channels: &[String], router: &WebSocketMessageRouter<C>) -> Self {
+        let (channels, unsupported_channels): (Vec<_>, Vec<_>) = requested_channels
+            .iter()
+            .unique()
+            .cloned()
+            .partition(|channel| router.has_handler(channel));
+        if !unsupported_channels.is_empty() {
+            log::warn!(
+                "There are no handlers for websocket channels {:?}, they aren't subscribed",
+                unsupported_channels
+            );
+        }
+
+        Self {
+            channels: RwLock::new(channels),
+        }
+    }
+
+    pub fn channels(&self) -> Vec<String> {
+        self.channels.read().clone()
+    }
+
+    /// Returns `false` if channel is subscribed already
+    pub fn add<C>(&self, channel: &str, router: &WebSocketMessageRouter<C>) -> Result<bool> {
+        if !router.has_handler(channel) {
+            bail!("There is no handler for websocket channel {}", channel);
+        }
+
+        let mut channels = self.channels.write();
+        if channels.iter().any(|x| x == channel) {
+            return Ok(false);
+        }
+
+        channels.push(channel.to_owned());
+        Ok(true)
+    }
+
+    /// Returns `false` if channel isn't subscribed
+    pub fn remove(&self, channel: &str) -> bool {
+        let mut channels = self.channels.write();
+        let len = channels.len();
+        channels.retain(|x| x != channel);
+        channels.len() != len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channels_are_validated_by_router_handlers() {
+        let router = WebSocketMessageRouter::<()>::new();
+        router.register_handler("trade", |_, _, _| Ok(()));
+        router.register_handler("depth20", |_, _, _| Ok(()));
+
+        let channels = WebSocketChannels::new(&["kline".to_owned(), "depth20".to_owned()], &router);
+        assert_eq!(channels.channels(), vec!["depth20"]);
+
+        assert!(channels.add("trade", &router).expect("in test"));
+        assert!(!channels.add("trade", &router).expect("in test"));
+        assert!(channels.add("kline", &router).is_err());
+        assert_eq!(channels.channels(), vec!["depth20", "trade"]);
+
+        assert!(channels.remove("depth20"));
+        assert!(!channels.remove("depth20"));
+        assert_eq!(channels.channels(), vec!["trade"]);
+    }
+}
@@ -1,5 +1,8 @@
 use crate::connectivity::connectivity_manager::{ConnectivityManagerNotifier, WebSocketRole};
+use crate::connectivity::proxy::Proxy;
 use crate::exchanges::common::ExchangeAccountId;
+use crate::exchanges::latency::{LatencyChannel, LatencyStatistics};
+use crate::exchanges::transport::TrafficRecorder;
 
 use crate::infrastructure::spawn_future;
 use anyhow::{Context as AnyhowContext, Result};
@@ -12,25 +15,45 @@ use std::time::{Duration, Instant};
 use tokio::net::TcpStream;
 use tokio::time;
 use tokio_tungstenite::tungstenite::{Error, Message};
-use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tokio_tungstenite::{client_async_tls, connect_async, MaybeTlsStream, WebSocketStream};
 use url::Url;
 
-/// Time interval between heartbeat pings are sent
+/// Default time interval between heartbeat pings are sent
 const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
 
-/// Time interval before lack of client response causes a timeout
-const HEARTBEAT_FAIL_TIMEOUT: Duration = Duration::from_secs(10);
+/// Count of heartbeat intervals before lack of client response causes a timeout
+const HEARTBEAT_FAIL_INTERVALS_COUNT: u32 = 2;
 
 const PING_MESSAGE: &'static [u8; 9] = b"heartbeat";
 
 #[derive(Debug, Clone)]
 pub struct WebSocketParams {
     url: Url,
+    heartbeat_interval: Duration,
+    proxy: Option<Proxy>,
+    traffic_recorder: Option<Arc<TrafficRecorder>>,
 }
 
 impl WebSocketParams {
     pub fn new(url: Url) -> Self {
-        WebSocketParams { url }
+        Self::with_heartbeat_interval(url, HEARTBEAT_INTERVAL)
+    }
+
+    pub fn with_heartbeat_interval(url: Url, heartbeat_interval: Duration) -> Self {
+        WebSocketParams {
+            url,
+            heartbeat_interval,
+            proxy: None,
+            traffic_recorder: None,
+        }
+    }
+
+    pub fn set_proxy(&mut self, proxy: Proxy) {
+        self.proxy = Some(proxy);
+    }
+
+    pub fn set_traffic_recorder(&mut self, traffic_recorder: Arc<TrafficRecorder>) {
+        self.traffic_recorder = Some(traffic_recorder);
     }
 }
 
@@ -41,7 +64,11 @@ pub struct WebSocketConnection {
     exchange_account_id: ExchangeAccountId,
     role: WebSocketRole,
     writer: tokio::sync::Mutex<WebSocketWriter>,
+    heartbeat_interval: Duration,
+    traffic_recorder: Option<Arc<TrafficRecorder>>,
     last_heartbeat_time: Mutex<Instant>,
+    last_ping_time: Mutex<Option<Instant>>,
+    latency_statistics: Arc<LatencyStatistics>,
     connectivity_manager_notifier: ConnectivityManagerNotifier,
     is_connected: Mutex<bool>,
 }
@@ -53,9 +80,26 @@ impl WebSocketConnection {
         params: WebSocketParams,
         connectivity_manager_notifier: ConnectivityManagerNotifier,
     ) -> Result<Arc<Self>> {
-        let (ws_stream, response) = connect_async(params.url)
-            .await
-            .context("Error occurred during websocket connect")?;
+        let heartbeat_interval = params.heartbeat_interval;
+        let traffic_recorder = params.traffic_recorder.clone();
+        let connecting_result = match &params.proxy {
+            None => connect_async(params.url).await,
+            Some(proxy) => {
+                let host = params
+                    .url
+                    .host_str()
+                    .context("Host isn't specified in websocket url")?;
+                let port = params
+                    .url
+                    .port_or_known_default()
+                    .context("Port isn't specified in websocket url")?;
+
+                let stream = proxy.connect(host, port).await?;
+                client_async_tls(params.url, stream).await
+            }
+        };
+        let (ws_stream, response) =
+            connecting_result.context("Error occurred during websocket connect")?;
 
         log::trace!(
             "Websocket {} {:?} connecting status: {}",
@@ -70,6 +114,8 @@ impl WebSocketConnection {
             exchange_account_id,
             role,
             writer,
+            heartbeat_interval,
+            traffic_recorder,
             connectivity_manager_notifier,
             true,
         ));
@@ -117,6 +163,8 @@ impl WebSocketConnection {
         exchange_account_id: ExchangeAccountId,
         role: WebSocketRole,
         writer: WebSocketWriter,
+        heartbeat_interval: Duration,
+        traffic_recorder: Option<Arc<TrafficRecorder>>,
         connectivity_manager_notifier: ConnectivityManagerNotifier,
         is_connected: bool,
     ) -> Self {
@@ -124,7 +172,11 @@ impl WebSocketConnection {
             exchange_account_id,
             role,
             writer: tokio::sync::Mutex::new(writer),
+            heartbeat_interval,
+            traffic_recorder,
             last_heartbeat_time: Mutex::new(Instant::now()),
+            last_ping_time: Mutex::new(None),
+            latency_statistics: LatencyStatistics::get_or_create(exchange_account_id),
             connectivity_manager_notifier,
             is_connected: Mutex::new(is_connected),
         }
@@ -167,11 +219,12 @@ impl WebSocketConnection {
     }
 
     async fn heartbeat(this: Arc<WebSocketConnection>) -> Result<()> {
-        let mut heartbeat_interval = time::interval(HEARTBEAT_INTERVAL);
+        let heartbeat_fail_timeout = this.heartbeat_interval * HEARTBEAT_FAIL_INTERVALS_COUNT;
+        let mut heartbeat_interval = time::interval(this.heartbeat_interval);
         loop {
             heartbeat_interval.tick().await;
             let last_heartbeat_time = *this.last_heartbeat_time.lock();
-            if Instant::now().duration_since(last_heartbeat_time) > HEARTBEAT_FAIL_TIMEOUT {
+            if Instant::now().duration_since(last_heartbeat_time) > heartbeat_fail_timeout {
                 log::trace!(
                     "Websocket {} {:?} heartbeat failed, disconnecting!",
                     this.exchange_account_id,
@@ -181,6 +234,7 @@ impl WebSocketConnection {
                 break;
             }
 
+            *this.last_ping_time.lock() = Some(Instant::now());
             let sending_result = this.send(Message::Ping(PING_MESSAGE.to_vec())).await;
             if let Err(err) = sending_result {
                 this.close_websocket().await;
@@ -198,7 +252,13 @@ impl WebSocketConnection {
 
     async fn handle_websocket_message(&self, msg: Message) {
         match msg {
-            Message::Text(ref text) => self.connectivity_manager_notifier.message_received(text),
+            Message::Text(ref text) => {
+                if let Some(traffic_recorder) = &self.traffic_recorder {
+                    traffic_recorder.record_websocket_message(self.role, text);
+                }
+
+                self.connectivity_manager_notifier.message_received(text)
+            }
             Message::Binary(bytes) => log::trace!(
                 "Websocket {} {:?} got binary message: {:x?}",
                 self.exchange_account_id,
@@ -208,7 +268,15 @@ impl WebSocketConnection {
             Message::Ping(msg) => self.send_pong(msg).await,
             Message::Pong(msg) => {
                 if &msg[..] == PING_MESSAGE {
-                    *self.last_heartbeat_time.lock() = Instant::now();
+                    let now = Instant::now();
+                    *self.last_heartbeat_time.lock() = now;
+
+                    if let Some(last_ping_time) = self.last_ping_time.lock().take() {
+                        self.latency_statistics.record(
+                            LatencyChannel::WebSocket(self.role),
+                            now.duration_since(last_ping_time),
+                        );
+                    }
                 } else {
                     log::error!("Websocket {} {:?} received wrong pong message: {}. We are sending message '{}' only",
                         self.exchange_account_id,
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use parking_lot::{Mutex, RwLock};
+use serde_json::Value;
+
+/// Handler of websocket message with specified type. Receives exchange client, raw message and parsed message
+pub type MessageHandler<C> = Arc<dyn Fn(&C, &str, &Value) -> Result<()> + Send + Sync>;
+
+/// Dispatches websocket messages of exchange client to handlers registered by message type.
+/// Messages without registered handler are counted by type
+pub struct WebSocketMessageRouter<C> {
+    handlers: RwLock<HashMap<String, MessageHandler<C>>>,
+    unhandled_messages_counts: Mutex<HashMap<String, u64>>,
+}
+
+impl<C> WebSocketMessageRouter<C> {
+    pub fn new() -> Self {
+        Self {
+            handlers: Default::default(),
+            unhandled_messages_counts: Default::default(),
+        }
+    }
+
+    pub fn register_handler(
+        &self,
+        message_type: &str,
+        handler: impl Fn(&C, &str, &Value) -> Result<()> + Send + Sync + 'static,
+    ) {
+        let previous = self
+            .handlers
+            .write()
+            .insert(message_type.to_owned(), Arc::new(handler));
+
+        if previous.is_some() {
+            log::warn!(
+                "Handler for websocket message type {} was replaced",
+                message_type
+            );
+        }
+    }
+
+    pub fn has_handler(&self, message_type: &str) -> bool {
+        self.handlers.read().contains_key(message_type)
+    }
+
+    pub fn unregister_handler(&self, message_type: &str) -> bool {
+        self.handlers.write().remove(message_type).is_some()
+    }
+
+    /// Returns `false` if there is no handler for message type
+    pub fn route(&self, client: &C, message_type: &str, msg: &str, data: &Value) -> Result<bool> {
+        // Handler is cloned to allow registering of handlers inside other handlers
+        let handler = self.handlers.read().get(message_type).cloned();
+        match handler {
+            Some(handler) => {
+                handler(client, msg, data)?;
+                Ok(true)
+            }
+            None => {
+                *self
+                    .unhandled_messages_counts
+                    .lock()
+                    .entry(message_type.to_owned())
+                    .or_default() += 1;
+
+                Ok(false)
+            }
+        }
+    }
+
+    pub fn unhandled_messages_counts(&self) -> HashMap<String, u64> {
+        self.unhandled_messages_counts.lock().clone()
+    }
+}
+
+impl<C> Default for WebSocketMessageRouter<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[test]
+    fn route_messages_by_type() {
+        let router = WebSocketMessageRouter::<AtomicU64>::new();
+        router.register_handler("trade", |handled_count, _, data| {
+            assert_eq!(data["p"], "1.5");
+            handled_count.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        });
+
+        let handled_count = AtomicU64::new(0);
+        let msg = r#"{"p":"1.5"}"#;
+        let data: Value = serde_json::from_str(msg).expect("in test");
+
+        let route = |message_type| {
+            router
+                .route(&handled_count, message_type, msg, &data)
+                .expect("in test")
+        };
+
+        assert!(route("trade"));
+        assert!(!route("kline"));
+        assert!(!route("kline"));
+
+        assert_eq!(handled_count.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            router.unhandled_messages_counts(),
+            HashMap::from([("kline".to_owned(), 2)])
+        );
+
+        assert!(router.unregister_handler("trade"));
+        assert!(!route("trade"));
+    }
+}
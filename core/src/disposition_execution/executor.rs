@@ -1,3 +1,4 @@
+use std::cell::RefCell;
 use std::fmt::{Display, Formatter};
 use std::sync::Arc;
 
@@ -12,29 +13,34 @@ use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use tokio::sync::{broadcast, oneshot};
 
+use crate::disposition_execution::order_randomizer::OrderRandomizer;
+use crate::disposition_execution::quote_governor::QuoteGovernor;
 use crate::disposition_execution::trading_context_calculation::calculate_trading_context;
+use crate::error::{MmbError, RiskError};
 use crate::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId, MarketAccountId, Price};
 use crate::exchanges::events::ExchangeEvent;
 use crate::exchanges::general::exchange::Exchange;
 use crate::exchanges::general::request_type::RequestType;
-use crate::exchanges::general::symbol::Symbol;
+use crate::exchanges::general::symbol::{Round, Symbol};
 use crate::explanation::{Explanation, WithExplanation};
 use crate::lifecycle::trading_engine::{EngineContext, Service};
 use crate::misc::reserve_parameters::ReserveParameters;
 use crate::order_book::local_snapshot_service::LocalSnapshotsService;
 use crate::orders::event::OrderEventType;
 use crate::orders::order::{
-    ClientOrderId, OrderCreating, OrderExecutionType, OrderHeader, OrderSide, OrderSnapshot,
-    OrderStatus, OrderType,
+    ClientOrderId, OrderCreating, OrderExecutionType, OrderHeader, OrderRole, OrderSide,
+    OrderSnapshot, OrderStatus, OrderType,
 };
 use crate::orders::pool::OrderRef;
+use crate::services::event_loop_watchdog::LoopHeartbeat;
+use crate::settings::OrderRandomizationSettings;
 use crate::strategies::disposition_strategy::DispositionStrategy;
 use crate::{
     disposition_execution::trade_limit::is_enough_amount_and_cost, infrastructure::spawn_future,
 };
 use crate::{
     disposition_execution::{
-        CompositeOrder, OrderRecord, OrdersState, PriceSlot, TradeCycle, TradingContext,
+        CompositeOrder, OrderRecord, OrdersState, PriceSlot, SmallOrder, TradeCycle, TradingContext,
     },
     statistic_service::StatisticService,
 };
@@ -71,6 +77,7 @@ impl DispositionExecutorService {
         strategy: Box<dyn DispositionStrategy>,
         cancellation_token: CancellationToken,
         statistics: Arc<StatisticService>,
+        order_randomization: Option<OrderRandomizationSettings>,
     ) -> Arc<Self> {
         let (work_finished_sender, receiver) = oneshot::channel();
 
@@ -85,7 +92,8 @@ impl DispositionExecutorService {
                 work_finished_sender,
                 cancellation_token,
                 statistics,
-            );
+                order_randomization,
+            )?;
 
             disposition_executor.start().await
         };
@@ -127,6 +135,9 @@ struct DispositionExecutor {
     work_finished_sender: Option<oneshot::Sender<Result<()>>>,
     cancellation_token: CancellationToken,
     statistics: Arc<StatisticService>,
+    quote_governor: RefCell<QuoteGovernor>,
+    order_randomizer: RefCell<OrderRandomizer>,
+    heartbeat: Arc<LoopHeartbeat>,
 }
 
 impl DispositionExecutor {
@@ -140,15 +151,23 @@ impl DispositionExecutor {
         work_finished_sender: oneshot::Sender<Result<()>>,
         cancellation_token: CancellationToken,
         statistics: Arc<StatisticService>,
-    ) -> Self {
+        order_randomization: Option<OrderRandomizationSettings>,
+    ) -> Result<Self> {
         let symbol = engine_ctx
             .exchanges
             .get(&exchange_account_id)
-            .expect("Target exchange should exists")
+            .with_context(|| format!("Target exchange {exchange_account_id} isn't found"))?
             .get_symbol(currency_pair)
-            .expect("Currency pair symbol should exists for target trading place");
+            .context("Currency pair symbol should exists for target trading place")?;
+        let quote_governor = QuoteGovernor::new(engine_ctx.app_settings.quote_governor.as_ref());
+        engine_ctx
+            .register_strategy_market(MarketAccountId::new(exchange_account_id, currency_pair))?;
+        let heartbeat = engine_ctx.event_loop_watchdog.register(format!(
+            "Disposition executor {} {}",
+            exchange_account_id, currency_pair
+        ));
 
-        DispositionExecutor {
+        Ok(DispositionExecutor {
             engine_ctx,
             events_receiver,
             local_snapshots_service,
@@ -159,11 +178,15 @@ impl DispositionExecutor {
             work_finished_sender: Some(work_finished_sender),
             cancellation_token,
             statistics,
-        }
+            quote_governor: RefCell::new(quote_governor),
+            order_randomizer: RefCell::new(OrderRandomizer::new(order_randomization)),
+            heartbeat,
+        })
     }
 
     pub async fn start(&mut self) -> Result<()> {
         let mut trading_context: Option<TradingContext> = None;
+        let heartbeat = self.heartbeat.clone();
 
         loop {
             let event = tokio::select! {
@@ -174,6 +197,7 @@ impl DispositionExecutor {
                 }
             };
 
+            let _iteration = heartbeat.iteration(&event);
             self.handle_event(event, &mut trading_context)?;
         }
     }
@@ -273,6 +297,8 @@ impl DispositionExecutor {
                             client_order_id
                         );
                     }
+                    // Order is already finished by preceding CancelOrderSucceeded
+                    OrderEventType::OrderExpired => nothing_to_do(),
                     OrderEventType::CancelOrderFailed => {
                         //We should use WaitCancelOrder everywhere, so we don't need to
                         //manually call CancelOrder if CancelOrderFailed
@@ -291,17 +317,80 @@ impl DispositionExecutor {
             &self.local_snapshots_service,
             now,
         )?;
+        round_disposition_prices(&mut new_trading_context, &self.symbol);
+        if self
+            .exchange()
+            .is_market_disabled(self.symbol.currency_pair())
+        {
+            exclude_trade_cycles(&mut new_trading_context, "market is disabled by operator");
+        }
+        self.exclude_unprofitable_trade_cycles(&mut new_trading_context);
 
-        if last_trading_context == &mut new_trading_context {
+        // Suppressed requotes should be retried even if trading context isn't changed
+        if last_trading_context == &mut new_trading_context
+            && !self.quote_governor.borrow().is_requote_suppressed()
+        {
             return Ok(());
         }
 
+        self.quote_governor.borrow_mut().reset_suppression();
         self.synchronize_price_slots_for_trading_context(&mut new_trading_context, now)?;
         *last_trading_context = new_trading_context;
 
         Ok(())
     }
 
+    /// Maker orders should be at least half of min profitable spread away from mid price,
+    /// so fees and conversion costs are covered by round trip
+    fn exclude_unprofitable_trade_cycles(&self, trading_context: &mut Option<TradingContext>) {
+        let trading_context = match trading_context {
+            Some(trading_context) => trading_context,
+            None => return,
+        };
+
+        let market_id =
+            MarketAccountId::new(self.exchange_account_id, self.symbol.currency_pair()).market_id();
+        let snapshot = match self.local_snapshots_service.get_snapshot(market_id) {
+            Some(snapshot) => snapshot,
+            None => return,
+        };
+        let mid_price = match (snapshot.get_top_ask(), snapshot.get_top_bid()) {
+            (Some((ask, _)), Some((bid, _))) => (ask + bid) * dec!(0.5),
+            _ => return,
+        };
+
+        let min_spread = self
+            .engine_ctx
+            .min_profitable_spread
+            .min_spread(&self.exchange(), mid_price);
+        if min_spread <= dec!(0) {
+            return;
+        }
+
+        for (side, trading_context_by_side) in trading_context.by_side.iter_mut() {
+            let limit_price = match side {
+                OrderSide::Buy => mid_price - min_spread * dec!(0.5),
+                OrderSide::Sell => mid_price + min_spread * dec!(0.5),
+            };
+            for estimating in trading_context_by_side.estimating.iter_mut() {
+                let is_unprofitable = estimating.value.as_ref().map_or(false, |trade_cycle| {
+                    let price = trade_cycle.disposition.price();
+                    trade_cycle.order_role == OrderRole::Maker
+                        && match side {
+                            OrderSide::Buy => price > limit_price,
+                            OrderSide::Sell => price < limit_price,
+                        }
+                });
+                if is_unprofitable {
+                    estimating.value = None;
+                    estimating.explanation.add_reason(format!(
+                        "price is within min profitable spread {min_spread} around mid price {mid_price}"
+                    ));
+                }
+            }
+        }
+    }
+
     fn synchronize_price_slots_for_trading_context(
         &mut self,
         trading_context: &mut Option<TradingContext>,
@@ -426,6 +515,7 @@ impl DispositionExecutor {
                 composite_order_ref.price
             ));
 
+            let desired_amount = slot_desired_amount(desired_amount, &composite_order_ref);
             let remaining_amount = composite_order_ref.remaining_amount();
             if remaining_amount >= desired_amount {
                 let desired_amount_with_allowed_deviation =
@@ -437,6 +527,11 @@ impl DispositionExecutor {
                         remaining_amount, desired_amount_with_allowed_deviation
                     ));
 
+                    if let Err(reason) = self.try_requote(&composite_order_ref, now) {
+                        explanation.add_reason(format!("Requote is suppressed: {}", reason));
+                        return Ok(());
+                    }
+
                     drop(composite_order_ref);
                     let mut composite_order_mut = price_slot.order.borrow_mut();
                     let cancelling_order_records = get_cancelling_orders(
@@ -482,6 +577,8 @@ impl DispositionExecutor {
                     now,
                     explanation,
                 )?;
+            } else if let Err(reason) = self.try_requote(&composite_order_ref, now) {
+                explanation.add_reason(format!("Requote is suppressed: {}", reason));
             } else {
                 explanation.add_reason("Cancelling existing orders");
 
@@ -503,6 +600,27 @@ impl DispositionExecutor {
         Ok(())
     }
 
+    fn try_requote(&self, composite_order: &CompositeOrder, now: DateTime) -> Result<(), String> {
+        let last_order_time = match composite_order.last_order_time() {
+            Some(last_order_time) => last_order_time,
+            None => return Ok(()),
+        };
+
+        let result = self
+            .quote_governor
+            .borrow_mut()
+            .try_requote(last_order_time, now);
+        if result.is_err() {
+            self.statistics
+                .register_suppressed_requote(MarketAccountId::new(
+                    self.exchange_account_id,
+                    self.symbol.currency_pair(),
+                ));
+        }
+
+        result
+    }
+
     fn start_cancelling_all_orders(
         &self,
         cause: &str,
@@ -559,9 +677,24 @@ impl DispositionExecutor {
 
         let action = async move {
             log::trace!("Begin wait_cancel_order {}", client_order_id);
-            exchange
-                .wait_cancel_order(order, Some(request_group_id), false, cancellation_token)
-                .await?;
+            let result = exchange
+                .wait_cancel_order_confirmed(
+                    order,
+                    Some(request_group_id),
+                    false,
+                    cancellation_token,
+                )
+                .await;
+            match result {
+                Err(MmbError::Risk {
+                    error: RiskError::TradingDisabled(error),
+                    ..
+                }) => log::warn!("Order {} isn't cancelled: {}", client_order_id, error),
+                // Order which isn't cancelled after all attempts is escalated as critical failure
+                result => {
+                    let _ = result?;
+                }
+            }
             log::trace!("Finished wait_cancel_order {}", client_order_id);
 
             Ok(())
@@ -610,6 +743,15 @@ impl DispositionExecutor {
             return log_trace(msg, explanation);
         }
 
+        let currency_pair = new_disposition.market_account_id().currency_pair;
+        if self.exchange().is_market_halted(currency_pair) {
+            let msg = format!(
+                "Finished `try_create_order` because trading is halted for {}",
+                currency_pair
+            );
+            return log_trace(msg, explanation);
+        }
+
         let new_order_amount = self.calculate_new_order_amount(
             new_disposition.market_account_id(),
             side,
@@ -618,6 +760,34 @@ impl DispositionExecutor {
             explanation,
         );
 
+        let order = SmallOrder::new(new_price, new_order_amount);
+        let mut randomized_order =
+            self.order_randomizer
+                .borrow_mut()
+                .randomize(order, side, &self.symbol);
+        // Randomized amount is used only if it's still enough for order
+        if is_enough_amount_and_cost(
+            new_disposition,
+            randomized_order.amount,
+            false,
+            &self.symbol,
+        )
+        .is_err()
+        {
+            randomized_order.amount = new_order_amount;
+        }
+        let amount_reduction = order.amount - randomized_order.amount;
+        if randomized_order != order {
+            explanation.add_reason(format!(
+                "Order is randomized to price {} and amount {}",
+                randomized_order.price, randomized_order.amount
+            ));
+        }
+        let SmallOrder {
+            price: order_price,
+            amount: new_order_amount,
+        } = randomized_order;
+
         if let Err(reason) =
             is_enough_amount_and_cost(new_disposition, new_order_amount, true, &self.symbol)
         {
@@ -627,7 +797,7 @@ impl DispositionExecutor {
             );
         }
 
-        let new_client_order_id = ClientOrderId::unique_id();
+        let new_client_order_id = ClientOrderId::generate(&new_estimating.strategy_name);
 
         let requests_group_id = self.engine_ctx.timeout_manager.try_reserve_group(
             self.exchange_account_id,
@@ -650,7 +820,7 @@ impl DispositionExecutor {
             self.exchange_account_id,
             self.symbol.clone(),
             new_disposition.side(),
-            new_disposition.price(),
+            order_price,
             new_order_amount,
         );
 
@@ -742,16 +912,18 @@ impl DispositionExecutor {
 
         let new_order = exchange
             .orders
-            .add_simple_initial(new_order_header.clone(), Some(new_disposition.price()));
+            .add_simple_initial(new_order_header.clone(), Some(order_price));
 
         price_slot.add_order(
             new_disposition.side(),
             new_disposition.price(),
             new_order,
             requests_group_id,
+            amount_reduction,
         );
 
         explanation.add_reason(format!("Creating order {}", new_client_order_id));
+        self.register_queue_position(new_disposition.market_account_id(), side, order_price);
 
         self.cancellation_token.error_if_cancellation_requested()?;
 
@@ -764,7 +936,7 @@ impl DispositionExecutor {
 
                 let order_creating = OrderCreating {
                     header: new_order_header,
-                    price: new_price,
+                    price: order_price,
                 };
 
                 exchange
@@ -786,6 +958,23 @@ impl DispositionExecutor {
         Ok(())
     }
 
+    fn register_queue_position(
+        &self,
+        market_account_id: MarketAccountId,
+        side: OrderSide,
+        price: Price,
+    ) {
+        if let Some(snapshot) = self
+            .local_snapshots_service
+            .get_snapshot(market_account_id.market_id())
+        {
+            self.statistics.register_queue_position(
+                market_account_id,
+                snapshot.get_queue_amount_ahead(side, price),
+            );
+        }
+    }
+
     fn find_new_order_crossing_existing_orders(
         &self,
         new_order_price: Price,
@@ -944,6 +1133,22 @@ impl DispositionExecutor {
     }
 }
 
+impl Drop for DispositionExecutor {
+    fn drop(&mut self) {
+        self.engine_ctx
+            .unregister_strategy_market(MarketAccountId::new(
+                self.exchange_account_id,
+                self.symbol.currency_pair(),
+            ));
+    }
+}
+
+/// Desired amount of price slot at the same price. Randomized orders are smaller than desired
+/// amount, so slot isn't topped up by another order up to desired amount
+fn slot_desired_amount(disposition_amount: Amount, composite_order: &CompositeOrder) -> Amount {
+    disposition_amount - composite_order.amount_reduction()
+}
+
 fn estimate_trading_context(
     need_recalculate_trading_context: bool,
     strategy: &mut dyn DispositionStrategy,
@@ -961,6 +1166,43 @@ fn estimate_trading_context(
     ))
 }
 
+/// Prices of dispositions are rounded to the passive side, so rounding doesn't make
+/// orders crossing order book
+fn round_disposition_prices(trading_context: &mut Option<TradingContext>, symbol: &Symbol) {
+    let trading_context = match trading_context {
+        Some(trading_context) => trading_context,
+        None => return,
+    };
+
+    for (_, trading_context_by_side) in trading_context.by_side.iter_mut() {
+        for trade_cycle in trading_context_by_side
+            .estimating
+            .iter_mut()
+            .filter_map(|x| x.value.as_mut())
+        {
+            let side = trade_cycle.disposition.side();
+            let order = &mut trade_cycle.disposition.order;
+            order.price = symbol.price_round(order.price, Round::TowardPassive(side));
+        }
+    }
+}
+
+/// Orders of excluded trade cycles are cancelled by synchronization of price slots
+fn exclude_trade_cycles(trading_context: &mut Option<TradingContext>, reason: &str) {
+    let trading_context = match trading_context {
+        Some(trading_context) => trading_context,
+        None => return,
+    };
+
+    for (_, trading_context_by_side) in trading_context.by_side.iter_mut() {
+        for estimating in trading_context_by_side.estimating.iter_mut() {
+            if estimating.value.take().is_some() {
+                estimating.explanation.add_reason(reason);
+            }
+        }
+    }
+}
+
 fn get_cancelling_orders<'a>(
     order_records: impl Iterator<Item = &'a mut OrderRecord>,
     desired_amount: Amount,
@@ -1015,3 +1257,37 @@ fn log_trace<'a>(msg: impl AsRef<str>, explanation: &mut Explanation) -> Result<
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::test_helper::create_order_ref;
+    use crate::exchanges::timeouts::requests_timeout_manager::RequestGroupId;
+
+    #[test]
+    fn randomized_order_is_not_topped_up() {
+        let disposition_amount = dec!(10);
+        let mut composite_order = CompositeOrder::new(OrderSide::Buy);
+        let order = create_order_ref(
+            &ClientOrderId::new("randomized_order".into()),
+            Some(OrderRole::Maker),
+            ExchangeAccountId::new("Binance".into(), 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            dec!(100),
+            dec!(9.3),
+            OrderSide::Buy,
+        );
+        composite_order.add_order_record(order.clone(), RequestGroupId::generate(), dec!(0.7));
+
+        let desired_amount = slot_desired_amount(disposition_amount, &composite_order);
+        assert_eq!(desired_amount - composite_order.remaining_amount(), dec!(0));
+
+        // Finished order doesn't reduce desired amount of next orders
+        order.fn_mut(|x| x.set_status(OrderStatus::Canceled, Utc::now()));
+        let desired_amount = slot_desired_amount(disposition_amount, &composite_order);
+        assert_eq!(
+            desired_amount - composite_order.remaining_amount(),
+            dec!(10)
+        );
+    }
+}
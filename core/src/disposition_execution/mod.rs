@@ -1,4 +1,6 @@
 pub mod executor;
+mod order_randomizer;
+mod quote_governor;
 pub mod trade_limit;
 mod trading_context_calculation;
 
@@ -7,6 +9,7 @@ use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 
 use enum_map::{enum_map, EnumMap};
+use mmb_utils::DateTime;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 
@@ -171,14 +174,17 @@ pub struct OrderRecord {
     pub order: OrderRef,
     pub is_cancellation_requested: bool,
     pub request_group_id: RequestGroupId,
+    /// Part of desired amount which isn't placed because amount of order is randomized
+    pub amount_reduction: Amount,
 }
 
 impl OrderRecord {
-    fn new(order: OrderRef, request_group_id: RequestGroupId) -> Self {
+    fn new(order: OrderRef, request_group_id: RequestGroupId, amount_reduction: Amount) -> Self {
         OrderRecord {
             order,
             is_cancellation_requested: false,
             request_group_id,
+            amount_reduction,
         }
     }
 }
@@ -216,17 +222,40 @@ impl CompositeOrder {
             .sum()
     }
 
-    pub fn add_order_record(&mut self, order: OrderRef, request_group_id: RequestGroupId) {
+    /// Creation time of the newest order which isn't cancelling yet
+    pub fn last_order_time(&self) -> Option<DateTime> {
+        self.orders
+            .values()
+            .filter(|or| !or.is_cancellation_requested)
+            .map(|or| or.order.fn_ref(|x| x.header.init_time))
+            .max()
+    }
+
+    /// Amount of not finished randomized orders is less than desired amount by this value
+    pub fn amount_reduction(&self) -> Amount {
+        self.orders
+            .values()
+            .filter(|or| !or.order.is_finished())
+            .map(|or| or.amount_reduction)
+            .sum()
+    }
+
+    pub fn add_order_record(
+        &mut self,
+        order: OrderRef,
+        request_group_id: RequestGroupId,
+        amount_reduction: Amount,
+    ) {
         let client_order_id = order.client_order_id();
         log::info!(
             "Adding order clientOrderId {} in current state of DispositionExecutor",
             client_order_id
         );
 
-        if let Some(order) = self
-            .orders
-            .insert(client_order_id, OrderRecord::new(order, request_group_id))
-        {
+        if let Some(order) = self.orders.insert(
+            client_order_id,
+            OrderRecord::new(order, request_group_id, amount_reduction),
+        ) {
             log::error!("The order with clientOrderId {} already exists in CompositeOrder of DispositionExecutor state when adding order record", order.order.client_order_id())
         }
     }
@@ -279,11 +308,12 @@ impl PriceSlot {
         price: Price,
         order: OrderRef,
         requests_group_id: RequestGroupId,
+        amount_reduction: Amount,
     ) {
         let composite_order = &mut self.order.borrow_mut();
         composite_order.side = side;
         composite_order.price = price;
-        composite_order.add_order_record(order, requests_group_id);
+        composite_order.add_order_record(order, requests_group_id, amount_reduction);
     }
 }
 
@@ -0,0 +1,114 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rust_decimal::Decimal;
+
+use crate::disposition_execution::SmallOrder;
+use crate::exchanges::general::symbol::{Precision, Round, Symbol};
+use crate::math::ConvertPercentToRate;
+use crate::orders::order::OrderSide;
+use crate::settings::OrderRandomizationSettings;
+
+/// Granularity of random reduction of amount
+const AMOUNT_REDUCTION_STEPS: u32 = 10_000;
+
+/// Randomizes orders of strategy before submission, so sizes and prices of orders aren't predictable.
+/// Orders are only made smaller and more passive than disposition, so limits of strategy aren't exceeded
+pub(crate) struct OrderRandomizer {
+    settings: Option<OrderRandomizationSettings>,
+    rng: StdRng,
+}
+
+impl OrderRandomizer {
+    pub fn new(settings: Option<OrderRandomizationSettings>) -> Self {
+        OrderRandomizer {
+            settings,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Returns order as is if randomization isn't configured. Amount is rounded to amount precision
+    /// of symbol, so it should be checked for min amount again
+    pub fn randomize(&mut self, order: SmallOrder, side: OrderSide, symbol: &Symbol) -> SmallOrder {
+        let settings = match &self.settings {
+            Some(settings) => settings,
+            None => return order,
+        };
+
+        let reduction_steps = self.rng.gen_range(0..=AMOUNT_REDUCTION_STEPS);
+        let reduction_rate = settings.max_amount_reduction_percent.percent_to_rate()
+            * Decimal::from(reduction_steps)
+            / Decimal::from(AMOUNT_REDUCTION_STEPS);
+        let amount =
+            symbol.amount_round(order.amount * (Decimal::ONE - reduction_rate), Round::Floor);
+
+        let price = match symbol.price_precision {
+            Precision::ByTick { tick } if settings.max_price_shift_ticks > 0 => {
+                let shift_ticks = self.rng.gen_range(0..=settings.max_price_shift_ticks);
+                let shift = tick * Decimal::from(shift_ticks);
+                match side {
+                    OrderSide::Buy if order.price > shift => order.price - shift,
+                    OrderSide::Buy => order.price,
+                    OrderSide::Sell => order.price + shift,
+                }
+            }
+            _ => order.price,
+        };
+
+        SmallOrder::new(price, amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::test_util::SymbolBuilder;
+
+    fn symbol() -> Symbol {
+        SymbolBuilder::new("btc".into(), "usdt".into()).build()
+    }
+
+    fn randomizer(settings: Option<OrderRandomizationSettings>) -> OrderRandomizer {
+        OrderRandomizer {
+            settings,
+            rng: StdRng::seed_from_u64(42),
+        }
+    }
+
+    #[test]
+    fn order_is_unchanged_without_settings() {
+        let order = SmallOrder::new(dec!(100), dec!(1));
+
+        let randomized = randomizer(None).randomize(order, OrderSide::Buy, &symbol());
+
+        assert_eq!(randomized, order);
+    }
+
+    #[test]
+    fn orders_are_smaller_and_more_passive() {
+        let settings = OrderRandomizationSettings {
+            max_amount_reduction_percent: dec!(10),
+            max_price_shift_ticks: 3,
+        };
+        let mut randomizer = randomizer(Some(settings));
+        let symbol = symbol();
+        let order = SmallOrder::new(dec!(100), dec!(1));
+
+        let mut amounts = Vec::new();
+        for _ in 0..100 {
+            let buy = randomizer.randomize(order, OrderSide::Buy, &symbol);
+            assert!(buy.price <= dec!(100) && buy.price >= dec!(99.7));
+            assert!(buy.amount <= dec!(1) && buy.amount >= dec!(0.9));
+            assert_eq!(buy.amount, symbol.amount_round(buy.amount, Round::Floor));
+            amounts.push(buy.amount);
+
+            let sell = randomizer.randomize(order, OrderSide::Sell, &symbol);
+            assert!(sell.price >= dec!(100) && sell.price <= dec!(100.3));
+        }
+
+        amounts.sort();
+        amounts.dedup();
+        assert!(amounts.len() > 1);
+    }
+}
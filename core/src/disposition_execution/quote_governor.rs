@@ -0,0 +1,126 @@
+use std::collections::VecDeque;
+
+use chrono::Duration;
+use mmb_utils::DateTime;
+
+use crate::settings::QuoteGovernorSettings;
+
+const REQUOTES_RATE_PERIOD_SECS: i64 = 60;
+
+/// Reduces cancel/replace churn of disposition executor for one market: resting orders aren't requoted
+/// before min lifetime and count of requotes per minute is limited
+pub(crate) struct QuoteGovernor {
+    min_order_lifetime: Option<Duration>,
+    max_requotes_per_minute: Option<usize>,
+    requote_times: VecDeque<DateTime>,
+    is_requote_suppressed: bool,
+}
+
+impl QuoteGovernor {
+    pub fn new(settings: Option<&QuoteGovernorSettings>) -> Self {
+        QuoteGovernor {
+            min_order_lifetime: settings
+                .and_then(|x| x.min_order_lifetime)
+                .and_then(|x| Duration::from_std(x.duration()).ok()),
+            max_requotes_per_minute: settings.and_then(|x| x.max_requotes_per_minute),
+            requote_times: VecDeque::new(),
+            is_requote_suppressed: false,
+        }
+    }
+
+    /// True if some requote was suppressed since last reset, so trading context should be synchronized again
+    /// even if it isn't changed
+    pub fn is_requote_suppressed(&self) -> bool {
+        self.is_requote_suppressed
+    }
+
+    pub fn reset_suppression(&mut self) {
+        self.is_requote_suppressed = false;
+    }
+
+    /// Returns reason of suppression or registers requote of orders if it's allowed.
+    /// `last_order_time` is creation time of the newest order which should be cancelled
+    pub fn try_requote(&mut self, last_order_time: DateTime, now: DateTime) -> Result<(), String> {
+        let rate_period_start = now - Duration::seconds(REQUOTES_RATE_PERIOD_SECS);
+        while matches!(self.requote_times.front(), Some(time) if *time <= rate_period_start) {
+            let _ = self.requote_times.pop_front();
+        }
+
+        if let Some(min_order_lifetime) = self.min_order_lifetime {
+            if now - last_order_time < min_order_lifetime {
+                self.is_requote_suppressed = true;
+                return Err(format!(
+                    "order lifetime is less than {} ms",
+                    min_order_lifetime.num_milliseconds()
+                ));
+            }
+        }
+
+        if let Some(max_requotes_per_minute) = self.max_requotes_per_minute {
+            if self.requote_times.len() >= max_requotes_per_minute {
+                self.is_requote_suppressed = true;
+                return Err(format!(
+                    "limit {} requotes per minute is reached",
+                    max_requotes_per_minute
+                ));
+            }
+        }
+
+        self.requote_times.push_back(now);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::misc::human_duration::HumanDuration;
+
+    #[test]
+    fn young_orders_are_not_requoted() {
+        let mut governor = QuoteGovernor::new(Some(&QuoteGovernorSettings {
+            min_order_lifetime: Some(HumanDuration::from_millis(500)),
+            max_requotes_per_minute: None,
+        }));
+        let order_time = Utc.ymd(2022, 3, 1).and_hms(12, 0, 0);
+
+        assert!(governor
+            .try_requote(order_time, order_time + Duration::milliseconds(100))
+            .is_err());
+        assert!(governor.is_requote_suppressed());
+
+        governor.reset_suppression();
+        assert!(governor
+            .try_requote(order_time, order_time + Duration::milliseconds(500))
+            .is_ok());
+        assert!(!governor.is_requote_suppressed());
+    }
+
+    #[test]
+    fn requotes_rate_is_limited() {
+        let mut governor = QuoteGovernor::new(Some(&QuoteGovernorSettings {
+            min_order_lifetime: None,
+            max_requotes_per_minute: Some(2),
+        }));
+        let now = Utc.ymd(2022, 3, 1).and_hms(12, 0, 0);
+
+        assert!(governor.try_requote(now, now).is_ok());
+        assert!(governor.try_requote(now, now).is_ok());
+        assert!(governor.try_requote(now, now).is_err());
+        assert!(governor
+            .try_requote(now, now + Duration::seconds(REQUOTES_RATE_PERIOD_SECS))
+            .is_ok());
+    }
+
+    #[test]
+    fn requotes_are_not_limited_without_settings() {
+        let mut governor = QuoteGovernor::new(None);
+        let now = Utc.ymd(2022, 3, 1).and_hms(12, 0, 0);
+
+        for _ in 0..100 {
+            assert!(governor.try_requote(now, now).is_ok());
+        }
+    }
+}
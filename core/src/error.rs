@@ -0,0 +1,397 @@
+use std::error::Error as StdError;
+
+use thiserror::Error;
+use tokio::time::error::Elapsed;
+
+use crate::balance_manager::balance_manager::ReserveError;
+use crate::balance_manager::capital_allocation::CapitalAllocationError;
+use crate::exchanges::common::{ExchangeError, ExchangeErrorType};
+use crate::exchanges::general::market_data_only::TradingDisabledError;
+use crate::exchanges::general::order::rejection::{OrderRejectedError, RejectionReason};
+use crate::exchanges::general::trading_windows::OutsideTradingWindowError;
+use crate::orders::order::ReservationId;
+use crate::orders::order_builder::OrderBuildError;
+use crate::services::exposure_limits::ExposureLimitError;
+
+pub type MmbResult<T> = Result<T, MmbError>;
+
+/// Order or request has invalid parameters, so it shouldn't be retried as is
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    #[error(transparent)]
+    OrderBuild(OrderBuildError),
+    /// Order is rejected because of precision, min notional or other parameters
+    #[error(transparent)]
+    OrderRejected(OrderRejectedError),
+    #[error("Reservation {0} isn't found")]
+    ReservationNotFound(ReservationId),
+}
+
+/// Order is blocked by limits of engine or by state of account and market
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum RiskError {
+    /// Order is rejected because of insufficient balance, halted market or checks of engine
+    #[error(transparent)]
+    OrderRejected(OrderRejectedError),
+    #[error(transparent)]
+    Reserve(#[from] ReserveError),
+    #[error(transparent)]
+    CapitalAllocation(#[from] CapitalAllocationError),
+    #[error(transparent)]
+    ExposureLimit(#[from] ExposureLimitError),
+    #[error(transparent)]
+    TradingDisabled(#[from] TradingDisabledError),
+    #[error(transparent)]
+    OutsideTradingWindow(#[from] OutsideTradingWindowError),
+}
+
+/// Exchange is unavailable or doesn't accept requests now, so request can be retried later
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ConnectivityError {
+    #[error(transparent)]
+    Exchange(ExchangeError),
+    /// Order is rejected because of rate limit
+    #[error(transparent)]
+    OrderRejected(OrderRejectedError),
+}
+
+/// Error of strategy-facing API. Class of error can be matched instead of message,
+/// and message keeps context of error, e.g. `Unable to place first leg: Exchange error: ...`
+#[derive(Error, Debug)]
+pub enum MmbError {
+    #[error("{message}")]
+    Exchange {
+        error: ExchangeError,
+        message: String,
+    },
+    #[error("{message}")]
+    Validation {
+        error: ValidationError,
+        message: String,
+    },
+    #[error("{message}")]
+    Risk { error: RiskError, message: String },
+    #[error("{message}")]
+    Connectivity {
+        error: ConnectivityError,
+        message: String,
+    },
+    #[error("{message}")]
+    Timeout { message: String },
+    /// Error which doesn't belong to classes above, e.g. cancellation of operation
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl MmbError {
+    pub fn timeout(message: String) -> Self {
+        MmbError::Timeout { message }
+    }
+
+    /// Rejection of order by exchange or by checks of engine before sending
+    pub fn order_rejection(&self) -> Option<&OrderRejectedError> {
+        match self {
+            MmbError::Validation {
+                error: ValidationError::OrderRejected(rejection),
+                ..
+            }
+            | MmbError::Risk {
+                error: RiskError::OrderRejected(rejection),
+                ..
+            }
+            | MmbError::Connectivity {
+                error: ConnectivityError::OrderRejected(rejection),
+                ..
+            } => Some(rejection),
+            _ => None,
+        }
+    }
+
+    fn with_message(&self, message: String) -> Option<Self> {
+        let error = match self {
+            MmbError::Exchange { error, .. } => MmbError::Exchange {
+                error: error.clone(),
+                message,
+            },
+            MmbError::Validation { error, .. } => MmbError::Validation {
+                error: error.clone(),
+                message,
+            },
+            MmbError::Risk { error, .. } => MmbError::Risk {
+                error: error.clone(),
+                message,
+            },
+            MmbError::Connectivity { error, .. } => MmbError::Connectivity {
+                error: error.clone(),
+                message,
+            },
+            MmbError::Timeout { .. } => MmbError::Timeout { message },
+            MmbError::Other(_) => return None,
+        };
+        Some(error)
+    }
+
+    fn from_exchange_error(error: ExchangeError, message: String) -> Self {
+        match error.error_type {
+            ExchangeErrorType::SendError
+            | ExchangeErrorType::ServiceUnavailable
+            | ExchangeErrorType::RateLimit => MmbError::Connectivity {
+                error: ConnectivityError::Exchange(error),
+                message,
+            },
+            _ => MmbError::Exchange { error, message },
+        }
+    }
+
+    fn from_rejection(rejection: OrderRejectedError, message: String) -> Self {
+        match rejection.reason {
+            RejectionReason::InsufficientBalance
+            | RejectionReason::RiskBlocked
+            | RejectionReason::MarketHalted => MmbError::Risk {
+                error: RiskError::OrderRejected(rejection),
+                message,
+            },
+            RejectionReason::RateLimit => MmbError::Connectivity {
+                error: ConnectivityError::OrderRejected(rejection),
+                message,
+            },
+            RejectionReason::BadPrecision
+            | RejectionReason::InvalidOrder
+            | RejectionReason::Other => MmbError::Validation {
+                error: ValidationError::OrderRejected(rejection),
+                message,
+            },
+        }
+    }
+
+    fn from_order_build_error(error: OrderBuildError, message: String) -> Self {
+        match error {
+            OrderBuildError::CapitalAllocation(error) => MmbError::Risk {
+                error: RiskError::CapitalAllocation(error),
+                message,
+            },
+            error => MmbError::Validation {
+                error: ValidationError::OrderBuild(error),
+                message,
+            },
+        }
+    }
+
+    /// Returns typed error for known cause of error
+    fn classify(cause: &(dyn StdError + 'static), message: &str) -> Option<Self> {
+        let message = message.to_owned();
+        if let Some(error) = cause.downcast_ref::<MmbError>() {
+            return error.with_message(message);
+        }
+        if let Some(rejection) = cause.downcast_ref::<OrderRejectedError>() {
+            return Some(Self::from_rejection(rejection.clone(), message));
+        }
+        if let Some(error) = cause.downcast_ref::<ExchangeError>() {
+            return Some(Self::from_exchange_error(error.clone(), message));
+        }
+        if let Some(error) = cause.downcast_ref::<OrderBuildError>() {
+            return Some(Self::from_order_build_error(error.clone(), message));
+        }
+        if let Some(error) = cause.downcast_ref::<ValidationError>() {
+            return Some(MmbError::Validation {
+                error: error.clone(),
+                message,
+            });
+        }
+        if let Some(error) = Self::risk_error(cause) {
+            return Some(MmbError::Risk { error, message });
+        }
+        if let Some(error) = cause.downcast_ref::<ConnectivityError>() {
+            return Some(MmbError::Connectivity {
+                error: error.clone(),
+                message,
+            });
+        }
+        if cause.is::<Elapsed>() {
+            return Some(MmbError::Timeout { message });
+        }
+
+        None
+    }
+
+    fn risk_error(cause: &(dyn StdError + 'static)) -> Option<RiskError> {
+        if let Some(error) = cause.downcast_ref::<RiskError>() {
+            return Some(error.clone());
+        }
+        if let Some(error) = cause.downcast_ref::<ReserveError>() {
+            return Some(error.clone().into());
+        }
+        if let Some(error) = cause.downcast_ref::<CapitalAllocationError>() {
+            return Some(error.clone().into());
+        }
+        if let Some(error) = cause.downcast_ref::<ExposureLimitError>() {
+            return Some(error.clone().into());
+        }
+        if let Some(error) = cause.downcast_ref::<TradingDisabledError>() {
+            return Some((*error).into());
+        }
+        if let Some(error) = cause.downcast_ref::<OutsideTradingWindowError>() {
+            return Some((*error).into());
+        }
+
+        None
+    }
+}
+
+impl From<anyhow::Error> for MmbError {
+    /// Error is classified by the first known error in chain of causes, so context added
+    /// by `anyhow::Context` doesn't hide class of error
+    fn from(error: anyhow::Error) -> Self {
+        let message = format!("{:#}", error);
+        match error
+            .chain()
+            .find_map(|cause| Self::classify(cause, &message))
+        {
+            Some(mmb_error) => mmb_error,
+            None => MmbError::Other(error),
+        }
+    }
+}
+
+impl From<ExchangeError> for MmbError {
+    fn from(error: ExchangeError) -> Self {
+        let message = error.to_string();
+        Self::from_exchange_error(error, message)
+    }
+}
+
+impl From<OrderRejectedError> for MmbError {
+    fn from(rejection: OrderRejectedError) -> Self {
+        let message = rejection.to_string();
+        Self::from_rejection(rejection, message)
+    }
+}
+
+impl From<OrderBuildError> for MmbError {
+    fn from(error: OrderBuildError) -> Self {
+        let message = error.to_string();
+        Self::from_order_build_error(error, message)
+    }
+}
+
+impl From<ReserveError> for MmbError {
+    fn from(error: ReserveError) -> Self {
+        RiskError::from(error).into()
+    }
+}
+
+impl From<TradingDisabledError> for MmbError {
+    fn from(error: TradingDisabledError) -> Self {
+        RiskError::from(error).into()
+    }
+}
+
+impl From<ValidationError> for MmbError {
+    fn from(error: ValidationError) -> Self {
+        let message = error.to_string();
+        MmbError::Validation { error, message }
+    }
+}
+
+impl From<RiskError> for MmbError {
+    fn from(error: RiskError) -> Self {
+        let message = error.to_string();
+        MmbError::Risk { error, message }
+    }
+}
+
+impl From<ConnectivityError> for MmbError {
+    fn from(error: ConnectivityError) -> Self {
+        let message = error.to_string();
+        MmbError::Connectivity { error, message }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{anyhow, Context};
+
+    use super::*;
+
+    fn rejection(reason: RejectionReason) -> OrderRejectedError {
+        OrderRejectedError::new("test".into(), reason, "Exchange error: rejected".to_owned())
+    }
+
+    #[test]
+    fn rejection_is_classified_with_context() {
+        let error = Err::<(), _>(rejection(RejectionReason::InsufficientBalance))
+            .context("Unable to place first leg")
+            .expect_err("in test");
+
+        let error = MmbError::from(error);
+
+        assert!(matches!(
+            &error,
+            MmbError::Risk {
+                error: RiskError::OrderRejected(_),
+                ..
+            }
+        ));
+        assert_eq!(
+            error.order_rejection().map(|x| x.reason),
+            Some(RejectionReason::InsufficientBalance)
+        );
+        assert_eq!(
+            error.to_string(),
+            "Unable to place first leg: Exchange error: rejected"
+        );
+    }
+
+    #[test]
+    fn unavailable_exchange_is_connectivity_error() {
+        let exchange_error = ExchangeError::new(
+            ExchangeErrorType::ServiceUnavailable,
+            "Service unavailable".to_owned(),
+            None,
+        );
+
+        let error = MmbError::from(anyhow!(exchange_error));
+
+        assert!(matches!(error, MmbError::Connectivity { .. }));
+    }
+
+    #[test]
+    fn nested_error_keeps_class() {
+        let error = MmbError::from(rejection(RejectionReason::BadPrecision));
+        let error = Err::<(), _>(error)
+            .context("Unable to replace order")
+            .expect_err("in test");
+
+        let error = MmbError::from(error);
+
+        assert!(matches!(
+            error,
+            MmbError::Validation {
+                error: ValidationError::OrderRejected(_),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn reserve_error_is_risk_error() {
+        let error = MmbError::from(ReserveError::NotEnoughBalance);
+
+        assert!(matches!(
+            error,
+            MmbError::Risk {
+                error: RiskError::Reserve(ReserveError::NotEnoughBalance),
+                ..
+            }
+        ));
+        assert_eq!(error.to_string(), "Balance isn't enough for reservation");
+    }
+
+    #[test]
+    fn unknown_error_is_other() {
+        let error = MmbError::from(anyhow!("Operation cancelled"));
+
+        assert!(matches!(error, MmbError::Other(_)));
+        assert_eq!(error.to_string(), "Operation cancelled");
+    }
+}
@@ -8,3 +8,6 @@ pub static CREATE_ORDER_INSUFFICIENT_FUNDS: BlockReason =
 pub static REST_RATE_LIMIT: BlockReason = BlockReason::new("REST_RATE_LIMIT");
 pub static GRACEFUL_SHUTDOWN: BlockReason = BlockReason::new("GRACEFUL_SHUTDOWN");
 pub static EXCHANGE_UNAVAILABLE: BlockReason = BlockReason::new("EXCHANGE_UNAVAILABLE");
+pub static EXCHANGE_MAINTENANCE: BlockReason = BlockReason::new("EXCHANGE_MAINTENANCE");
+pub static EXCHANGE_STOPPED: BlockReason = BlockReason::new("EXCHANGE_STOPPED");
+pub static STATE_HANDED_OVER: BlockReason = BlockReason::new("STATE_HANDED_OVER");
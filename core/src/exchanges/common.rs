@@ -7,8 +7,11 @@ use once_cell::sync::Lazy;
 use regex::Regex;
 use rust_decimal::*;
 use rust_decimal_macros::dec;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
 use serde::de::{self, Deserializer, Visitor};
-use serde::ser::Serializer;
+use serde::ser::{SerializeStruct, Serializer};
 use serde::{Deserialize, Serialize};
 use smallstr::SmallString;
 use std::fmt::{self, Debug, Display, Formatter};
@@ -37,6 +40,9 @@ pub struct ExchangeAccountId {
 
     /// Exchange account number
     pub account_number: u8,
+
+    /// Number of sub-account inside exchange account. `None` for master account
+    pub sub_account_number: Option<u8>,
 }
 
 impl ExchangeAccountId {
@@ -45,19 +51,43 @@ impl ExchangeAccountId {
         ExchangeAccountId {
             exchange_id,
             account_number,
+            sub_account_number: None,
+        }
+    }
+
+    #[inline]
+    pub fn new_sub_account(
+        exchange_id: ExchangeId,
+        account_number: u8,
+        sub_account_number: u8,
+    ) -> Self {
+        ExchangeAccountId {
+            exchange_id,
+            account_number,
+            sub_account_number: Some(sub_account_number),
         }
     }
 
+    pub fn is_sub_account(&self) -> bool {
+        self.sub_account_number.is_some()
+    }
+
+    /// Master account of sub-account or the same account if it isn't sub-account
+    pub fn master_account(&self) -> Self {
+        ExchangeAccountId::new(self.exchange_id, self.account_number)
+    }
+
     pub fn to_string(&self) -> String {
         format!("{}", self)
     }
 }
 
+/// Supported formats: `{exchange_id}_{account_number}` and `{exchange_id}_{account_number}_{sub_account_number}`
 impl FromStr for ExchangeAccountId {
     type Err = ExchangeIdParseError;
 
     fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
-        let regex = Regex::new(r"(^[A-Za-z0-9\-\.]+)_(\d+$)")
+        let regex = Regex::new(r"(^[A-Za-z0-9\-\.]+)_(\d+)(?:_(\d+))?$")
             .map_err(|err| ExchangeIdParseError(err.to_string()))?;
 
         let captures = regex
@@ -79,7 +109,19 @@ impl FromStr for ExchangeAccountId {
                 ExchangeIdParseError(format!("Can't parse exchange account number: {}", x))
             })?;
 
-        Ok(ExchangeAccountId::new(exchange_id, number))
+        let sub_account_number = captures[3]
+            .map(|sub_account_number| {
+                sub_account_number.as_str().parse().map_err(|x| {
+                    ExchangeIdParseError(format!("Can't parse sub-account number: {}", x))
+                })
+            })
+            .transpose()?;
+
+        Ok(ExchangeAccountId {
+            exchange_id,
+            account_number: number,
+            sub_account_number,
+        })
     }
 }
 
@@ -126,13 +168,17 @@ impl Serialize for ExchangeAccountId {
 
 impl Display for ExchangeAccountId {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}_{}", self.exchange_id.as_str(), self.account_number)
+        write!(f, "{}_{}", self.exchange_id.as_str(), self.account_number)?;
+        match self.sub_account_number {
+            Some(sub_account_number) => write!(f, "_{}", sub_account_number),
+            None => Ok(()),
+        }
     }
 }
 
 impl Debug for ExchangeAccountId {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}_{}", self.exchange_id.as_str(), self.account_number)
+        write!(f, "{}", self)
     }
 }
 
@@ -155,6 +201,16 @@ impl CurrencyCode {
     }
 }
 
+impl JsonSchema for CurrencyCode {
+    fn schema_name() -> String {
+        "CurrencyCode".into()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        String::json_schema(gen)
+    }
+}
+
 impl From<&str> for CurrencyCode {
     fn from(value: &str) -> Self {
         CurrencyCode::new(value)
@@ -222,6 +278,24 @@ impl MarketAccountId {
     pub fn market_id(&self) -> MarketId {
         MarketId::new(self.exchange_account_id.exchange_id, self.currency_pair)
     }
+
+    /// Serializes market account id as object with fields, which is the format of `Deserialize`.
+    /// `Serialize` produces string to be usable as key of maps
+    pub fn serialize_as_struct<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("MarketAccountId", 2)?;
+        state.serialize_field("exchange_account_id", &self.exchange_account_id)?;
+        state.serialize_field("currency_pair", &self.currency_pair)?;
+        state.end()
+    }
+}
+
+impl Display for MarketAccountId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}|{}", self.exchange_account_id, self.currency_pair)
+    }
 }
 
 impl Serialize for MarketAccountId {
@@ -229,8 +303,7 @@ impl Serialize for MarketAccountId {
     where
         S: Serializer,
     {
-        let market_account_id = format!("{}|{}", self.exchange_account_id, self.currency_pair);
-        serializer.serialize_str(&market_account_id)
+        serializer.serialize_str(&self.to_string())
     }
 }
 
@@ -284,6 +357,7 @@ pub enum ExchangeErrorType {
     ParsingError,
     PendingError(Duration),
     ServiceUnavailable,
+    TradingHalted,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -485,6 +559,24 @@ mod tests {
             );
         }
 
+        #[test]
+        pub fn sub_account() {
+            let exchange_account_id = "Binance_0_2".parse::<ExchangeAccountId>();
+            assert_eq!(
+                exchange_account_id,
+                Ok(ExchangeAccountId::new_sub_account("Binance".into(), 0, 2))
+            );
+        }
+
+        #[test]
+        pub fn failed_because_invalid_sub_account_number() {
+            let exchange_account_id = "Binance_0_".parse::<ExchangeAccountId>();
+            assert_eq!(
+                exchange_account_id,
+                Err(ExchangeIdParseError("Invalid format".into()))
+            )
+        }
+
         #[test]
         pub fn failed_because_no_exchange_name() {
             let exchange_account_id = "123".parse::<ExchangeAccountId>();
@@ -526,5 +618,15 @@ mod tests {
             let result = exchange_account_id.to_string();
             assert_eq!(result, "Binance_1".to_string())
         }
+
+        #[test]
+        pub fn sub_account() {
+            let exchange_account_id = "Binance_1_3".parse::<ExchangeAccountId>().expect("in test");
+            assert_eq!(exchange_account_id.to_string(), "Binance_1_3");
+            assert_eq!(
+                exchange_account_id.master_account().to_string(),
+                "Binance_1"
+            );
+        }
     }
 }
@@ -0,0 +1,288 @@
+use std::fs::File;
+use std::io::BufWriter;
+
+use anyhow::{Context, Result};
+use mmb_utils::DateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::common::ExchangeAccountId;
+use crate::exchanges::transport::{read_traffic_records, TrafficRecord};
+
+/// Traffic record file of one exchange account written by `TrafficRecorder`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventLogSource {
+    pub exchange_account_id: ExchangeAccountId,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventLogKind {
+    Rest,
+    WebSocket,
+    StatsSnapshot,
+}
+
+/// Events of all exchanges ordered by time and stored by columns, so log can be loaded
+/// directly into dataframes for cross-venue research. Every column has one value per event
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventLog {
+    /// Position of event in merged log
+    pub sequence: Vec<u64>,
+    /// Position of event in record file of its exchange
+    pub venue_sequence: Vec<u64>,
+    /// Local time of receiving in nanoseconds since Unix epoch
+    pub timestamp_ns: Vec<i64>,
+    pub exchange_account_id: Vec<ExchangeAccountId>,
+    pub kind: Vec<EventLogKind>,
+    /// Websocket role, REST method with url path or `stats_snapshot`
+    pub channel: Vec<String>,
+    /// Websocket message, content of REST response or json of statistics snapshot
+    pub payload: Vec<String>,
+}
+
+impl EventLog {
+    pub fn len(&self) -> usize {
+        self.sequence.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sequence.is_empty()
+    }
+
+    fn push(&mut self, event: VenueEvent) {
+        self.sequence.push(self.sequence.len() as u64);
+        self.venue_sequence.push(event.venue_sequence);
+        self.timestamp_ns.push(event.timestamp.timestamp_nanos());
+        self.exchange_account_id.push(event.exchange_account_id);
+        self.kind.push(event.kind);
+        self.channel.push(event.channel);
+        self.payload.push(event.payload);
+    }
+}
+
+struct VenueEvent {
+    venue_sequence: u64,
+    timestamp: DateTime,
+    exchange_account_id: ExchangeAccountId,
+    kind: EventLogKind,
+    channel: String,
+    payload: String,
+}
+
+impl VenueEvent {
+    fn new(
+        exchange_account_id: ExchangeAccountId,
+        venue_sequence: u64,
+        record: TrafficRecord,
+    ) -> Self {
+        let (timestamp, kind, channel, payload) = match record {
+            TrafficRecord::Rest {
+                timestamp,
+                method,
+                url,
+                content,
+                ..
+            } => {
+                // Query is dropped because it contains signatures of requests
+                let path = url.split('?').next().unwrap_or_default();
+                let channel = format!("{:?} {}", method, path);
+                (timestamp, EventLogKind::Rest, channel, content)
+            }
+            TrafficRecord::RestError {
+                timestamp,
+                method,
+                url,
+                error,
+                ..
+            } => {
+                let path = url.split('?').next().unwrap_or_default();
+                let channel = format!("{:?} {}", method, path);
+                (timestamp, EventLogKind::Rest, channel, error)
+            }
+            TrafficRecord::WebSocket {
+                timestamp,
+                role,
+                message,
+            } => (
+                timestamp,
+                EventLogKind::WebSocket,
+                format!("{:?}", role),
+                message,
+            ),
+            TrafficRecord::StatsSnapshot {
+                timestamp,
+                snapshot,
+            } => (
+                timestamp,
+                EventLogKind::StatsSnapshot,
+                "stats_snapshot".to_owned(),
+                snapshot,
+            ),
+        };
+
+        Self {
+            venue_sequence,
+            timestamp,
+            exchange_account_id,
+            kind,
+            channel,
+            payload,
+        }
+    }
+}
+
+/// Merges records of exchanges into single log ordered by time. Events with the same time
+/// keep order of sources and order inside of their record file
+pub fn merge_traffic_records(
+    sources: Vec<(ExchangeAccountId, Vec<TrafficRecord>)>,
+    from: Option<DateTime>,
+    to: Option<DateTime>,
+) -> EventLog {
+    let mut events = Vec::new();
+    for (exchange_account_id, records) in sources {
+        for (venue_sequence, record) in records.into_iter().enumerate() {
+            let event = VenueEvent::new(exchange_account_id, venue_sequence as u64, record);
+            let is_after_from = from.map_or(true, |from| event.timestamp >= from);
+            let is_before_to = to.map_or(true, |to| event.timestamp < to);
+            if is_after_from && is_before_to {
+                events.push(event);
+            }
+        }
+    }
+
+    events.sort_by_key(|event| event.timestamp);
+
+    let mut event_log = EventLog::default();
+    for event in events {
+        event_log.push(event);
+    }
+
+    event_log
+}
+
+/// Exports events of period `[from, to)` from record files into json file with columnar log.
+/// Returns count of exported events
+pub fn export_event_log(
+    sources: &[EventLogSource],
+    from: Option<DateTime>,
+    to: Option<DateTime>,
+    output_path: &str,
+) -> Result<usize> {
+    let mut records = Vec::with_capacity(sources.len());
+    for source in sources {
+        records.push((
+            source.exchange_account_id,
+            read_traffic_records(&source.path)?,
+        ));
+    }
+
+    let event_log = merge_traffic_records(records, from, to);
+
+    let file = File::create(output_path)
+        .with_context(|| format!("Unable to create event log file {}", output_path))?;
+    serde_json::to_writer(BufWriter::new(file), &event_log)
+        .with_context(|| format!("Unable to write event log to {}", output_path))?;
+
+    Ok(event_log.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::connectivity::connectivity_manager::WebSocketRole;
+    use crate::exchanges::transport::RestMethod;
+
+    fn websocket_record(second: u32, message: &str) -> TrafficRecord {
+        TrafficRecord::WebSocket {
+            timestamp: Utc.ymd(2022, 3, 1).and_hms(0, 0, second),
+            role: WebSocketRole::Main,
+            message: message.to_owned(),
+        }
+    }
+
+    #[test]
+    fn records_of_exchanges_are_merged_by_time() {
+        let binance = ExchangeAccountId::new("Binance".into(), 0);
+        let serum = ExchangeAccountId::new("Serum".into(), 0);
+        let rest_record = TrafficRecord::Rest {
+            timestamp: Utc.ymd(2022, 3, 1).and_hms(0, 0, 2),
+            method: RestMethod::Get,
+            url: "https://api.binance.com/api/v3/openOrders?signature=abc".to_owned(),
+            body: String::new(),
+            status: 200,
+            content: "[]".to_owned(),
+        };
+
+        let event_log = merge_traffic_records(
+            vec![
+                (
+                    binance,
+                    vec![
+                        websocket_record(1, "b1"),
+                        rest_record,
+                        websocket_record(4, "b4"),
+                    ],
+                ),
+                (
+                    serum,
+                    vec![websocket_record(2, "s2"), websocket_record(3, "s3")],
+                ),
+            ],
+            None,
+            Some(Utc.ymd(2022, 3, 1).and_hms(0, 0, 4)),
+        );
+
+        assert_eq!(event_log.sequence, vec![0, 1, 2, 3]);
+        assert_eq!(event_log.venue_sequence, vec![0, 1, 0, 1]);
+        assert_eq!(
+            event_log.exchange_account_id,
+            vec![binance, binance, serum, serum]
+        );
+        assert_eq!(event_log.payload, vec!["b1", "[]", "s2", "s3"]);
+        assert_eq!(
+            event_log.channel[1],
+            "Get https://api.binance.com/api/v3/openOrders"
+        );
+        assert_eq!(event_log.kind[1], EventLogKind::Rest);
+        assert!(event_log
+            .timestamp_ns
+            .windows(2)
+            .all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn stats_snapshots_are_merged_with_traffic() {
+        let binance = ExchangeAccountId::new("Binance".into(), 0);
+        let stats_snapshot_record = TrafficRecord::StatsSnapshot {
+            timestamp: Utc.ymd(2022, 3, 1).and_hms(0, 0, 2),
+            snapshot: r#"{"exchanges":[],"markets":[]}"#.to_owned(),
+        };
+
+        let event_log = merge_traffic_records(
+            vec![(
+                binance,
+                vec![
+                    websocket_record(1, "b1"),
+                    stats_snapshot_record,
+                    websocket_record(3, "b3"),
+                ],
+            )],
+            None,
+            None,
+        );
+
+        assert_eq!(
+            event_log.kind,
+            vec![
+                EventLogKind::WebSocket,
+                EventLogKind::StatsSnapshot,
+                EventLogKind::WebSocket
+            ]
+        );
+        assert_eq!(event_log.channel[1], "stats_snapshot");
+        assert_eq!(event_log.payload[1], r#"{"exchanges":[],"markets":[]}"#);
+    }
+}
@@ -1,5 +1,6 @@
 use core::panic;
 use std::fmt::{Display, Formatter};
+use std::sync::Arc;
 
 use mmb_utils::DateTime;
 use rust_decimal::Decimal;
@@ -8,10 +9,13 @@ use serde_json::Value;
 use tokio::sync::broadcast;
 
 use crate::exchanges::common::{Amount, CurrencyCode, CurrencyPair, ExchangeAccountId, Price};
+use crate::exchanges::general::margin::{MarginInfo, MarginLevel};
+use crate::exchanges::general::symbol::Symbol;
+use crate::exchanges::lag_aware_receiver::{ExchangeEventsReceiver, LagAwareReceiver};
 use crate::misc::derivative_position::DerivativePosition;
 use crate::order_book::event::OrderBookEvent;
 use crate::orders::event::OrderEvent;
-use crate::orders::order::OrderSide;
+use crate::orders::order::{ClientOrderId, ExchangeOrderId, OrderSide, PositionSide};
 
 pub const CHANNEL_MAX_EVENTS_COUNT: usize = 200_000;
 
@@ -95,6 +99,14 @@ impl TradeId {
     }
 }
 
+/// Stream which trade is received from. Streams number trades independently,
+/// e.g. aggregated trades have their own ids
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TradeStream {
+    Trades,
+    AggregatedTrades,
+}
+
 impl From<Value> for TradeId {
     fn from(value: Value) -> Self {
         match value.as_u64() {
@@ -151,6 +163,181 @@ pub struct TradesEvent {
     pub receipt_time: DateTime,
 }
 
+/// Trades missed by websocket which weren't repaired by REST backfilling. Ids are inclusive
+#[derive(Debug, Clone)]
+pub struct TradeGapEvent {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub first_missed_id: u64,
+    pub last_missed_id: u64,
+    pub backfilled_count: u64,
+}
+
+/// Event loop of engine handles one event too long, so events aren't processed
+#[derive(Debug, Clone)]
+pub struct EventLoopStallEvent {
+    pub loop_name: String,
+    /// Kind of event which is being handled by stalled loop
+    pub handled_event_kind: &'static str,
+    pub stalled_for_ms: i64,
+    /// Count of events sent to channel since start of handling of stalled event
+    pub pending_events_count: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SymbolEvent {
+    pub exchange_account_id: ExchangeAccountId,
+    pub symbol: Arc<Symbol>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MarketTradingStatusEvent {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub is_halted: bool,
+}
+
+/// Forced liquidation order on derivative market
+#[derive(Debug, Clone)]
+pub struct LiquidationOrderEvent {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub side: OrderSide,
+    pub price: Price,
+    pub amount: Amount,
+    pub transaction_time: DateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct MarginCallPosition {
+    pub currency_pair: CurrencyPair,
+    pub position_side: Option<PositionSide>,
+    pub position: Amount,
+    pub mark_price: Price,
+    pub unrealized_pnl: Decimal,
+    pub maintenance_margin: Decimal,
+}
+
+/// Early warning from exchange that positions of account are close to liquidation
+#[derive(Debug, Clone)]
+pub struct MarginCallEvent {
+    pub exchange_account_id: ExchangeAccountId,
+    /// Ratio of maintenance margin to margin balance of cross margin positions. Liquidation starts at 1
+    pub margin_ratio: Option<Decimal>,
+    pub positions: Vec<MarginCallPosition>,
+}
+
+/// Result of polling of account margin by engine
+#[derive(Debug, Clone)]
+pub struct MarginRatioEvent {
+    pub exchange_account_id: ExchangeAccountId,
+    pub margin_info: MarginInfo,
+    pub margin_ratio: Decimal,
+    pub level: MarginLevel,
+}
+
+#[derive(Debug, Clone)]
+pub struct DustConversion {
+    pub currency_code: CurrencyCode,
+    pub amount: Amount,
+    /// Currency of dust conversion (BNB for Binance)
+    pub converted_to: CurrencyCode,
+    /// Received amount without commission
+    pub converted_amount: Amount,
+    pub commission: Amount,
+}
+
+/// Small balances converted by exchange to single currency
+#[derive(Debug, Clone)]
+pub struct DustConversionEvent {
+    pub exchange_account_id: ExchangeAccountId,
+    pub conversions: Vec<DustConversion>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BalanceChangeReason {
+    /// Currency is bought or sold by fill
+    Fill,
+    Commission,
+}
+
+/// Incremental change of balance caused by single fill. It allows downstream accounting
+/// not to diff successive balance snapshots
+#[derive(Debug, Clone)]
+pub struct BalanceDeltaEvent {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_code: CurrencyCode,
+    /// Positive value if balance is increased
+    pub delta: Amount,
+    pub reason: BalanceChangeReason,
+    pub client_order_id: ClientOrderId,
+    pub trade_id: Option<TradeId>,
+    pub time: DateTime,
+}
+
+#[derive(Debug, Clone)]
+pub enum PartialFillAction {
+    /// Remaining amount of order is cancelled
+    Cancelled,
+    /// Remaining amount of order is re-placed with new order
+    Replaced {
+        new_client_order_id: ClientOrderId,
+        price: Price,
+        amount: Amount,
+    },
+}
+
+/// Partially filled order had no fills during stagnation timeout of `PartialFillPolicy`
+#[derive(Debug, Clone)]
+pub struct PartialFillTimeoutEvent {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub client_order_id: ClientOrderId,
+    pub remaining_amount: Amount,
+    pub action: PartialFillAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FillAnomalyKind {
+    /// Fill price deviates from middle of the last order book top by more than allowed percent
+    PriceDeviation {
+        reference_price: Price,
+        deviation_percent: Decimal,
+    },
+    /// Filled amount of order exceeds order amount by more than allowed tolerance
+    OversizedFill {
+        order_amount: Amount,
+        filled_amount: Amount,
+    },
+    /// Fill is received for order which isn't in orders pool, so it's buffered
+    UnknownOrder,
+}
+
+/// Fill which looks wrong and should be checked by operator
+#[derive(Debug, Clone)]
+pub struct FillAnomalyEvent {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: Option<CurrencyPair>,
+    pub client_order_id: Option<ClientOrderId>,
+    pub exchange_order_id: Option<ExchangeOrderId>,
+    pub price: Price,
+    pub amount: Amount,
+    pub kind: FillAnomalyKind,
+}
+
+/// Commission of fill is converted to quote currency after it was added to order,
+/// because there was no order book top for commission currency at time of fill
+#[derive(Debug, Clone)]
+pub struct CommissionCorrectionEvent {
+    pub exchange_account_id: ExchangeAccountId,
+    pub client_order_id: ClientOrderId,
+    pub trade_id: Option<TradeId>,
+    pub commission_currency_code: CurrencyCode,
+    pub commission_amount: Amount,
+    pub converted_commission_currency_code: CurrencyCode,
+    pub converted_commission_amount: Amount,
+}
+
 #[derive(Debug, Clone)]
 pub enum ExchangeEvent {
     OrderBookEvent(OrderBookEvent),
@@ -158,6 +345,45 @@ pub enum ExchangeEvent {
     BalanceUpdate(BalanceUpdateEvent),
     LiquidationPrice(LiquidationPriceEvent),
     Trades(TradesEvent),
+    SymbolAdded(SymbolEvent),
+    SymbolUpdated(SymbolEvent),
+    MarketTradingStatus(MarketTradingStatusEvent),
+    LiquidationOrder(LiquidationOrderEvent),
+    MarginCall(MarginCallEvent),
+    MarginRatio(MarginRatioEvent),
+    DustConversion(DustConversionEvent),
+    PartialFillTimeout(PartialFillTimeoutEvent),
+    BalanceDelta(BalanceDeltaEvent),
+    FillAnomaly(FillAnomalyEvent),
+    CommissionCorrection(CommissionCorrectionEvent),
+    TradeGap(TradeGapEvent),
+    EventLoopStall(EventLoopStallEvent),
+}
+
+impl ExchangeEvent {
+    /// Name of event variant for logs and diagnostics
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            ExchangeEvent::OrderBookEvent(_) => "order_book",
+            ExchangeEvent::OrderEvent(_) => "order",
+            ExchangeEvent::BalanceUpdate(_) => "balance_update",
+            ExchangeEvent::LiquidationPrice(_) => "liquidation_price",
+            ExchangeEvent::Trades(_) => "trades",
+            ExchangeEvent::SymbolAdded(_) => "symbol_added",
+            ExchangeEvent::SymbolUpdated(_) => "symbol_updated",
+            ExchangeEvent::MarketTradingStatus(_) => "market_trading_status",
+            ExchangeEvent::LiquidationOrder(_) => "liquidation_order",
+            ExchangeEvent::MarginCall(_) => "margin_call",
+            ExchangeEvent::MarginRatio(_) => "margin_ratio",
+            ExchangeEvent::DustConversion(_) => "dust_conversion",
+            ExchangeEvent::PartialFillTimeout(_) => "partial_fill_timeout",
+            ExchangeEvent::BalanceDelta(_) => "balance_delta",
+            ExchangeEvent::FillAnomaly(_) => "fill_anomaly",
+            ExchangeEvent::CommissionCorrection(_) => "commission_correction",
+            ExchangeEvent::TradeGap(_) => "trade_gap",
+            ExchangeEvent::EventLoopStall(_) => "event_loop_stall",
+        }
+    }
 }
 
 pub(crate) struct ExchangeEvents {
@@ -172,6 +398,10 @@ impl ExchangeEvents {
     pub fn get_events_channel(&self) -> broadcast::Receiver<ExchangeEvent> {
         self.events_sender.subscribe()
     }
+
+    pub fn get_lag_aware_receiver(&self, name: &str) -> ExchangeEventsReceiver {
+        LagAwareReceiver::new(name, &self.events_sender)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Copy)]
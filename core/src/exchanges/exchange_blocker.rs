@@ -493,6 +493,24 @@ impl ExchangeBlocker {
         })
     }
 
+    /// Registers exchange account which is added after engine start
+    pub fn add_exchange(&self, exchange_account_id: ExchangeAccountId) {
+        let _ = self
+            .blockers
+            .write()
+            .entry(exchange_account_id)
+            .or_insert_with(HashMap::new);
+    }
+
+    /// Unregisters exchange account which failed to be added
+    pub fn remove_exchange(&self, exchange_account_id: ExchangeAccountId) {
+        let _ = self.blockers.write().remove(&exchange_account_id);
+    }
+
+    pub fn contains_exchange(&self, exchange_account_id: ExchangeAccountId) -> bool {
+        self.blockers.read().contains_key(&exchange_account_id)
+    }
+
     pub fn is_blocked(&self, exchange_account_id: ExchangeAccountId) -> bool {
         !self
             .blockers
@@ -515,6 +533,21 @@ impl ExchangeBlocker {
             .is_some()
     }
 
+    /// Reasons of active blocks of exchange account sorted by name
+    pub fn block_reasons(&self, exchange_account_id: ExchangeAccountId) -> Vec<BlockReason> {
+        self.blockers
+            .read()
+            .get(&exchange_account_id)
+            .map(|blockers| {
+                blockers
+                    .keys()
+                    .copied()
+                    .sorted_by_key(|x| x.0)
+                    .collect_vec()
+            })
+            .unwrap_or_default()
+    }
+
     pub fn is_blocked_except_reason(
         &self,
         exchange_account_id: ExchangeAccountId,
@@ -1,5 +1,6 @@
 use crate::orders::order::OrderRole;
 use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 
 pub type Percent = Decimal;
 
@@ -24,6 +25,25 @@ pub struct Commission {
     pub taker: CommissionForType,
 }
 
+/// Fees of exchange account. Negative fee is rebate
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FeeSettings {
+    pub maker_fee_percent: Percent,
+    pub taker_fee_percent: Percent,
+    /// Part of paid commission which is returned to account as referral reward
+    #[serde(default)]
+    pub referral_reward_percent: Percent,
+}
+
+impl From<&FeeSettings> for Commission {
+    fn from(settings: &FeeSettings) -> Self {
+        Commission::new(
+            CommissionForType::new(settings.maker_fee_percent, settings.referral_reward_percent),
+            CommissionForType::new(settings.taker_fee_percent, settings.referral_reward_percent),
+        )
+    }
+}
+
 impl Commission {
     pub fn new(maker: CommissionForType, taker: CommissionForType) -> Self {
         Self { maker, taker }
@@ -0,0 +1,392 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::FutureExt;
+use itertools::Itertools;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::send_expected::SendExpectedByRef;
+use uuid::Uuid;
+
+use crate::exchanges::common::{Amount, CurrencyCode, CurrencyPair, Price};
+use crate::exchanges::events::{CommissionCorrectionEvent, ExchangeEvent};
+use crate::exchanges::general::exchange::{Exchange, OrderBookTop};
+use crate::infrastructure::spawn_by_timer;
+use crate::orders::order::ClientOrderId;
+
+/// Period of requesting order book snapshots by REST for commissions which are still not converted
+const REST_CONVERSION_PERIOD: Duration = Duration::from_secs(30);
+
+/// Fill commission in currency other than base or quote (e.g. BNB discount on Binance) which
+/// couldn't be converted to quote currency because there was no order book top for it
+#[derive(Debug, Clone)]
+pub(crate) struct DeferredCommissionConversion {
+    pub client_order_id: ClientOrderId,
+    pub fill_id: Uuid,
+    pub commission_currency_code: CurrencyCode,
+    pub commission_amount: Amount,
+    pub quote_currency_code: CurrencyCode,
+}
+
+impl DeferredCommissionConversion {
+    fn direct_currency_pair(&self) -> CurrencyPair {
+        CurrencyPair::from_codes(self.commission_currency_code, self.quote_currency_code)
+    }
+
+    fn reverse_currency_pair(&self) -> CurrencyPair {
+        CurrencyPair::from_codes(self.quote_currency_code, self.commission_currency_code)
+    }
+}
+
+/// Best prices of market which are used for commission conversion
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TopPrices {
+    ask: Option<Price>,
+    bid: Option<Price>,
+}
+
+impl From<&OrderBookTop> for TopPrices {
+    fn from(top: &OrderBookTop) -> Self {
+        TopPrices {
+            ask: top.ask.as_ref().map(|level| level.price),
+            bid: top.bid.as_ref().map(|level| level.price),
+        }
+    }
+}
+
+/// Converts commission to quote currency by top bid of `commission/quote` market or
+/// top ask of `quote/commission` market. Returns `None` if there is no such top
+fn convert_to_quote(
+    commission_currency_code: CurrencyCode,
+    quote_currency_code: CurrencyCode,
+    commission_amount: Amount,
+    get_top_prices: impl Fn(CurrencyPair) -> Option<TopPrices>,
+) -> Option<Amount> {
+    let direct_pair = CurrencyPair::from_codes(commission_currency_code, quote_currency_code);
+    if let Some(bid) = get_top_prices(direct_pair).and_then(|top| top.bid) {
+        return Some(commission_amount * bid);
+    }
+
+    let reverse_pair = CurrencyPair::from_codes(quote_currency_code, commission_currency_code);
+    let ask = get_top_prices(reverse_pair)
+        .and_then(|top| top.ask)
+        .filter(|ask| !ask.is_zero())?;
+    Some(commission_amount / ask)
+}
+
+impl Exchange {
+    fn get_top_prices(&self, currency_pair: CurrencyPair) -> Option<TopPrices> {
+        self.order_book_top
+            .get(&currency_pair)
+            .map(|top| TopPrices::from(top.value()))
+    }
+
+    /// Converts commission to quote currency by order book tops of exchange.
+    /// Returns `None` if there is no suitable top
+    pub(super) fn convert_commission_to_quote(
+        &self,
+        commission_currency_code: CurrencyCode,
+        quote_currency_code: CurrencyCode,
+        commission_amount: Amount,
+    ) -> Option<Amount> {
+        convert_to_quote(
+            commission_currency_code,
+            quote_currency_code,
+            commission_amount,
+            |currency_pair| self.get_top_prices(currency_pair),
+        )
+    }
+
+    pub(super) fn defer_commission_conversion(&self, conversion: DeferredCommissionConversion) {
+        log::warn!(
+            "Commission {} {} of order {} on {} isn't converted to {} because there is no order book top, conversion is deferred",
+            conversion.commission_amount,
+            conversion.commission_currency_code,
+            conversion.client_order_id,
+            self.exchange_account_id,
+            conversion.quote_currency_code
+        );
+
+        self.deferred_commission_conversions.lock().push(conversion);
+    }
+
+    pub fn deferred_commission_conversions_count(&self) -> usize {
+        self.deferred_commission_conversions.lock().len()
+    }
+
+    /// Converts deferred commissions which can be converted by order book top of currency pair
+    pub(crate) fn convert_deferred_commissions(&self, currency_pair: CurrencyPair) {
+        self.convert_deferred_commissions_by_top(currency_pair, |currency_pair| {
+            self.get_top_prices(currency_pair)
+        });
+    }
+
+    fn convert_deferred_commissions_by_top(
+        &self,
+        currency_pair: CurrencyPair,
+        get_top_prices: impl Fn(CurrencyPair) -> Option<TopPrices>,
+    ) {
+        let conversions = {
+            let mut deferred = self.deferred_commission_conversions.lock();
+            if deferred.is_empty() {
+                return;
+            }
+
+            let (ready, waiting) = deferred.drain(..).partition::<Vec<_>, _>(|conversion| {
+                conversion.direct_currency_pair() == currency_pair
+                    || conversion.reverse_currency_pair() == currency_pair
+            });
+            *deferred = waiting;
+            ready
+        };
+
+        for conversion in conversions {
+            match convert_to_quote(
+                conversion.commission_currency_code,
+                conversion.quote_currency_code,
+                conversion.commission_amount,
+                &get_top_prices,
+            ) {
+                Some(converted_amount) => {
+                    self.correct_fill_commission(&conversion, converted_amount)
+                }
+                None => self.deferred_commission_conversions.lock().push(conversion),
+            }
+        }
+    }
+
+    fn correct_fill_commission(
+        &self,
+        conversion: &DeferredCommissionConversion,
+        converted_amount: Amount,
+    ) {
+        let order_ref = match self
+            .orders
+            .cache_by_client_id
+            .get(&conversion.client_order_id)
+        {
+            Some(order_ref) => order_ref.clone(),
+            None => {
+                log::warn!(
+                    "Commission of order {} on {} is converted, but order isn't found in cache",
+                    conversion.client_order_id,
+                    self.exchange_account_id
+                );
+                return;
+            }
+        };
+
+        let trade_id = order_ref.fn_mut(|order| {
+            let fill = order
+                .fills
+                .fills
+                .iter_mut()
+                .find(|fill| fill.id() == conversion.fill_id)?;
+            fill.set_converted_commission(conversion.quote_currency_code, converted_amount);
+            Some(fill.trade_id().cloned())
+        });
+        let trade_id = match trade_id {
+            Some(trade_id) => trade_id,
+            None => {
+                log::warn!(
+                    "Fill {} of order {} on {} isn't found for commission correction",
+                    conversion.fill_id,
+                    conversion.client_order_id,
+                    self.exchange_account_id
+                );
+                return;
+            }
+        };
+
+        log::info!(
+            "Deferred commission {} {} of order {} on {} is converted to {} {}",
+            conversion.commission_amount,
+            conversion.commission_currency_code,
+            conversion.client_order_id,
+            self.exchange_account_id,
+            converted_amount,
+            conversion.quote_currency_code
+        );
+
+        self.events_channel
+            .send_expected(ExchangeEvent::CommissionCorrection(
+                CommissionCorrectionEvent {
+                    exchange_account_id: self.exchange_account_id,
+                    client_order_id: conversion.client_order_id.clone(),
+                    trade_id,
+                    commission_currency_code: conversion.commission_currency_code,
+                    commission_amount: conversion.commission_amount,
+                    converted_commission_currency_code: conversion.quote_currency_code,
+                    converted_commission_amount: converted_amount,
+                },
+            ));
+    }
+
+    /// Requests order book snapshots by REST for commissions which weren't converted
+    /// by websocket order book, e.g. when market of commission currency isn't subscribed
+    async fn convert_deferred_commissions_by_rest(&self) {
+        let currency_pairs = self
+            .deferred_commission_conversions
+            .lock()
+            .iter()
+            .map(|conversion| {
+                (
+                    conversion.direct_currency_pair(),
+                    conversion.reverse_currency_pair(),
+                )
+            })
+            .unique()
+            .collect_vec();
+
+        for (direct_pair, reverse_pair) in currency_pairs {
+            let currency_pair = match (
+                self.symbols.contains_key(&direct_pair),
+                self.symbols.contains_key(&reverse_pair),
+            ) {
+                (true, _) => direct_pair,
+                (false, true) => reverse_pair,
+                (false, false) => {
+                    log::error!(
+                        "Commission can't be converted on {} because there are no markets {} and {}",
+                        self.exchange_account_id,
+                        direct_pair,
+                        reverse_pair
+                    );
+                    self.deferred_commission_conversions
+                        .lock()
+                        .retain(|x| x.direct_currency_pair() != direct_pair);
+                    continue;
+                }
+            };
+
+            let snapshot = match self
+                .get_order_book_snapshot(currency_pair, 1, self.lifetime_manager.stop_token())
+                .await
+            {
+                Ok(snapshot) => snapshot,
+                Err(error) => {
+                    log::warn!(
+                        "Unable to get order book of {} on {} for commission conversion: {:?}",
+                        currency_pair,
+                        self.exchange_account_id,
+                        error
+                    );
+                    continue;
+                }
+            };
+
+            // Snapshot is used only for conversion, because shared order book top
+            // of market should be updated only by its order book events
+            let top_prices = TopPrices {
+                ask: snapshot.asks.keys().next().copied(),
+                bid: snapshot.bids.keys().next_back().copied(),
+            };
+            self.convert_deferred_commissions_by_top(currency_pair, |x| {
+                (x == currency_pair).then(|| top_prices)
+            });
+        }
+    }
+
+    pub(crate) fn spawn_deferred_commissions_conversion(self: &Arc<Self>) {
+        let exchange_weak = Arc::downgrade(self);
+        let convert_commissions = move || {
+            let exchange_weak = exchange_weak.clone();
+            async move {
+                if let Some(exchange) = exchange_weak.upgrade() {
+                    exchange.convert_deferred_commissions_by_rest().await;
+                }
+            }
+            .boxed()
+        };
+
+        let _ = spawn_by_timer(
+            convert_commissions,
+            &format!(
+                "Convert deferred commissions for {}",
+                self.exchange_account_id
+            ),
+            REST_CONVERSION_PERIOD,
+            REST_CONVERSION_PERIOD,
+            SpawnFutureFlags::STOP_BY_TOKEN,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::exchanges::general::test_helper::get_test_exchange;
+
+    fn bnb() -> CurrencyCode {
+        "BNB".into()
+    }
+
+    fn btc() -> CurrencyCode {
+        "BTC".into()
+    }
+
+    #[test]
+    fn commission_is_converted_by_bid_of_direct_market() {
+        let top_prices = TopPrices {
+            ask: Some(dec!(0.5)),
+            bid: Some(dec!(0.3)),
+        };
+
+        let converted = convert_to_quote(bnb(), btc(), dec!(15), |currency_pair| {
+            (currency_pair == CurrencyPair::from_codes(bnb(), btc())).then(|| top_prices)
+        });
+
+        assert_eq!(converted, Some(dec!(4.5)));
+    }
+
+    #[test]
+    fn commission_is_converted_by_ask_of_reverse_market() {
+        let top_prices = TopPrices {
+            ask: Some(dec!(4)),
+            bid: Some(dec!(3)),
+        };
+
+        let converted = convert_to_quote(bnb(), btc(), dec!(10), |currency_pair| {
+            (currency_pair == CurrencyPair::from_codes(btc(), bnb())).then(|| top_prices)
+        });
+
+        assert_eq!(converted, Some(dec!(2.5)));
+    }
+
+    #[test]
+    fn commission_is_not_converted_without_prices() {
+        let zero_ask = TopPrices {
+            ask: Some(dec!(0)),
+            bid: None,
+        };
+
+        let converted = convert_to_quote(bnb(), btc(), dec!(10), |_| Some(zero_ask));
+
+        assert_eq!(converted, None);
+    }
+
+    #[test]
+    fn conversion_by_rest_snapshot_does_not_update_order_book_top() {
+        let (exchange, _event_receiver) = get_test_exchange(false);
+        let commission_pair = CurrencyPair::from_codes(bnb(), btc());
+        exchange.defer_commission_conversion(DeferredCommissionConversion {
+            client_order_id: ClientOrderId::unique_id(),
+            fill_id: Uuid::new_v4(),
+            commission_currency_code: bnb(),
+            commission_amount: dec!(15),
+            quote_currency_code: btc(),
+        });
+
+        let top_prices = TopPrices {
+            ask: None,
+            bid: Some(dec!(0.3)),
+        };
+        exchange.convert_deferred_commissions_by_top(commission_pair, |currency_pair| {
+            (currency_pair == commission_pair).then(|| top_prices)
+        });
+
+        assert_eq!(exchange.deferred_commission_conversions_count(), 0);
+        assert!(exchange.order_book_top.get(&commission_pair).is_none());
+    }
+}
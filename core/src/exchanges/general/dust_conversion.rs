@@ -0,0 +1,188 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use futures::FutureExt;
+use itertools::Itertools;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::send_expected::SendExpectedByRef;
+
+use crate::exchanges::common::{Amount, CurrencyCode};
+use crate::exchanges::events::{DustConversion, DustConversionEvent, ExchangeEvent};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::helpers::get_rest_error;
+use crate::infrastructure::spawn_by_timer;
+
+const DEFAULT_DUST_CONVERSION_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+/// Balance which can be converted by exchange to currency of dust conversion
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DustBalance {
+    pub currency_code: CurrencyCode,
+    pub amount: Amount,
+    /// Estimated amount in currency of dust conversion
+    pub converted_amount: Amount,
+}
+
+/// Currencies of dust balances which can be converted. Currencies which are used by engine
+/// aren't converted even if their balances are small
+fn select_dust_currencies(
+    dust_balances: Vec<DustBalance>,
+    threshold: Amount,
+    used_currency_codes: &HashSet<CurrencyCode>,
+) -> Vec<CurrencyCode> {
+    dust_balances
+        .into_iter()
+        .filter(|dust_balance| dust_balance.converted_amount < threshold)
+        .map(|dust_balance| dust_balance.currency_code)
+        .filter(|currency_code| !used_currency_codes.contains(currency_code))
+        .collect_vec()
+}
+
+impl Exchange {
+    /// Currencies of configured markets and currencies reserved for orders
+    fn get_used_currency_codes(&self) -> HashSet<CurrencyCode> {
+        let mut currency_codes = HashSet::new();
+        for symbol in self.symbols.iter() {
+            currency_codes.insert(symbol.base_currency_code());
+            currency_codes.insert(symbol.quote_currency_code());
+            currency_codes.insert(symbol.amount_currency_code);
+            currency_codes.extend(symbol.balance_currency_code);
+        }
+
+        if let Some(balance_manager) = self.get_balance_manager() {
+            currency_codes.extend(
+                balance_manager
+                    .lock()
+                    .get_reserved_currency_codes(self.exchange_account_id),
+            );
+        }
+
+        currency_codes
+    }
+
+    /// Converts balances worth less than threshold except currencies which are used by engine.
+    /// Returns performed conversions
+    pub async fn convert_dust(&self, threshold: Amount) -> Result<Vec<DustConversion>> {
+        let response = self.exchange_client.request_dust_balances().await?;
+        if let Some(error) = get_rest_error(&response, self.exchange_account_id, false) {
+            bail!(
+                "Unable to get dust balances on {}: {:?}",
+                self.exchange_account_id,
+                error
+            );
+        }
+
+        let currency_codes = select_dust_currencies(
+            self.exchange_client.parse_dust_balances(&response)?,
+            threshold,
+            &self.get_used_currency_codes(),
+        );
+
+        if currency_codes.is_empty() {
+            return Ok(vec![]);
+        }
+
+        log::info!(
+            "Converting dust balances {:?} on {}",
+            currency_codes,
+            self.exchange_account_id
+        );
+
+        let response = self
+            .exchange_client
+            .request_dust_conversion(&currency_codes)
+            .await?;
+        if let Some(error) = get_rest_error(&response, self.exchange_account_id, false) {
+            bail!(
+                "Unable to convert dust balances on {}: {:?}",
+                self.exchange_account_id,
+                error
+            );
+        }
+
+        let conversions = self.exchange_client.parse_dust_conversion(&response)?;
+
+        self.events_channel
+            .send_expected(ExchangeEvent::DustConversion(DustConversionEvent {
+                exchange_account_id: self.exchange_account_id,
+                conversions: conversions.clone(),
+            }));
+
+        // Balances are requested to actualize them in BalanceManager after conversion
+        if let Err(error) = self.get_balance(self.lifetime_manager.stop_token()).await {
+            log::warn!("Unable to get balance after dust conversion: {:?}", error);
+        }
+
+        Ok(conversions)
+    }
+
+    pub(crate) fn spawn_dust_conversion(
+        self: &Arc<Self>,
+        threshold: Amount,
+        period: Option<Duration>,
+    ) {
+        let period = period.unwrap_or(DEFAULT_DUST_CONVERSION_PERIOD);
+
+        let exchange_weak = Arc::downgrade(self);
+        let convert_dust = move || {
+            let exchange_weak = exchange_weak.clone();
+            async move {
+                let exchange = match exchange_weak.upgrade() {
+                    Some(exchange) => exchange,
+                    None => return,
+                };
+
+                if let Err(error) = exchange.convert_dust(threshold).await {
+                    log::warn!(
+                        "Unable to convert dust balances on {}: {:?}",
+                        exchange.exchange_account_id,
+                        error
+                    );
+                }
+            }
+            .boxed()
+        };
+
+        let _ = spawn_by_timer(
+            convert_dust,
+            &format!("Convert dust balances for {}", self.exchange_account_id),
+            period,
+            period,
+            SpawnFutureFlags::STOP_BY_TOKEN,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn dust_balance(currency_code: &str, converted_amount: Amount) -> DustBalance {
+        DustBalance {
+            currency_code: currency_code.into(),
+            amount: dec!(1),
+            converted_amount,
+        }
+    }
+
+    #[test]
+    fn used_currencies_are_not_converted() {
+        let dust_balances = vec![
+            dust_balance("DOGE", dec!(0.001)),
+            dust_balance("ETH", dec!(0.002)),
+            dust_balance("XRP", dec!(0.5)),
+            dust_balance("TRX", dec!(0.003)),
+        ];
+        let used_currency_codes: HashSet<CurrencyCode> =
+            HashSet::from(["ETH".into(), "TRX".into()]);
+
+        let currency_codes =
+            select_dust_currencies(dust_balances, dec!(0.01), &used_currency_codes);
+
+        assert_eq!(currency_codes, vec![CurrencyCode::from("DOGE")]);
+    }
+}
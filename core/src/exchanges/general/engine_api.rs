@@ -46,7 +46,21 @@ impl EngineApi {
             })
             .collect_vec();
 
-        let closed_positions = join_all(get_closed_positions_futures).await;
+        let closed_positions = join_all(get_closed_positions_futures)
+            .await
+            .into_iter()
+            .filter_map(|closed_position| match closed_position {
+                Ok(closed_position) => Some(closed_position),
+                Err(error) => {
+                    log::error!(
+                        "Unable to close active position for exchange {}: {:?}",
+                        self.exchange.exchange_account_id,
+                        error
+                    );
+                    None
+                }
+            })
+            .collect_vec();
 
         log::info!(
             "Closed active position for exchange {}",
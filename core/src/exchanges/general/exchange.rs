@@ -1,10 +1,13 @@
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Weak};
 
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use dashmap::DashMap;
 use futures::FutureExt;
 use itertools::Itertools;
 use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
 use mmb_utils::send_expected::SendExpectedByRef;
 use mmb_utils::{nothing_to_do, DateTime};
 use parking_lot::Mutex;
@@ -12,26 +15,41 @@ use rust_decimal::Decimal;
 use tokio::sync::{broadcast, oneshot};
 
 use super::commission::Commission;
+use super::commission_conversion::DeferredCommissionConversion;
 use super::polling_timeout_manager::PollingTimeoutManager;
 use super::symbol::Symbol;
-use crate::connectivity::connectivity_manager::GetWSParamsCallback;
+use super::trade_gaps::TradeIdsTracker;
+use crate::connectivity::connectivity_manager::{GetWSParamsCallback, WebSocketStatus};
+use crate::connectivity::proxy::Proxy;
+use crate::error::MmbResult;
 use crate::exchanges::common::{ActivePosition, ClosedPosition, MarketId, SpecificCurrencyPair};
 use crate::exchanges::events::{
     BalanceUpdateEvent, ExchangeBalance, ExchangeBalancesAndPositions, ExchangeEvent,
     LiquidationPriceEvent, Trade,
 };
 use crate::exchanges::general::features::{BalancePositionOption, ExchangeFeatures};
+use crate::exchanges::general::margin::MarginInfo;
+use crate::exchanges::general::market_queues::{MarketEventQueues, MarketQueueDepth};
 use crate::exchanges::general::order::cancel::CancelOrderResult;
 use crate::exchanges::general::order::create::CreateOrderResult;
+use crate::exchanges::general::order::wait_outcome::WaitOutcome;
+use crate::exchanges::general::order_ids::OrderIds;
+use crate::exchanges::general::received_trades::ReceivedTrades;
 use crate::exchanges::general::request_type::RequestType;
+use crate::exchanges::latency::LatencyStatistics;
+use crate::exchanges::time_sync::SERVER_TIME_SYNC_PERIOD;
 use crate::exchanges::timeouts::requests_timeout_manager_factory::RequestTimeoutArguments;
 use crate::exchanges::timeouts::timeout_manager::TimeoutManager;
+use crate::exchanges::transport::{TrafficRecorder, TrafficReplay};
 use crate::misc::derivative_position::DerivativePosition;
 use crate::misc::time::time_manager;
+use crate::misc::virtual_clock;
+use crate::order_tracing::OrderTraces;
 use crate::orders::buffered_fills::buffered_canceled_orders_manager::BufferedCanceledOrdersManager;
 use crate::orders::buffered_fills::buffered_fills_manager::BufferedFillsManager;
+use crate::orders::cancel_priority::{CancelClass, CancelPriorities};
 use crate::orders::event::OrderEventType;
-use crate::orders::order::OrderSide;
+use crate::orders::order::{OrderInfo, OrderSide};
 use crate::orders::pool::OrdersPool;
 use crate::orders::{order::ExchangeOrderId, pool::OrderRef};
 use crate::{
@@ -46,6 +64,10 @@ use crate::{
 
 use crate::balance_manager::balance_manager::BalanceManager;
 use crate::exchanges::general::helpers::is_rest_error_code;
+use crate::infrastructure::{spawn_by_timer, spawn_future};
+use crate::orders::order_filter::OrderFilter;
+use crate::services::exposure_limits::ExposureLimits;
+use crate::settings::CurrencyPairSetting;
 use crate::{
     connectivity::{
         connectivity_manager::ConnectivityManager, websocket_connection::WebSocketParams,
@@ -58,6 +80,9 @@ use crate::{
 };
 use std::fmt::Debug;
 
+/// Attempts of closing of position before its error is returned
+const CLOSE_POSITION_ATTEMPTS: u32 = 5;
+
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub enum RequestResult<T> {
     Success(T),
@@ -93,13 +118,24 @@ pub struct Exchange {
     pub currencies: Mutex<Vec<CurrencyCode>>,
     pub leverage_by_currency_pair: DashMap<CurrencyPair, Decimal>,
     pub order_book_top: DashMap<CurrencyPair, OrderBookTop>,
+    /// Markets where trading is halted or pair is delisted. New orders aren't created for them
+    pub(super) halted_markets: DashMap<CurrencyPair, String>,
+    /// Markets disabled by operator. Unlike halted markets they are kept after restart
+    pub(super) disabled_markets: DashMap<CurrencyPair, ()>,
+    /// Engine waits for another instance of engine to hand over open orders, so orders aren't
+    /// placed meanwhile
+    pub(super) is_awaiting_handover: AtomicBool,
+    /// Open orders are handed over to another instance of engine, so they aren't placed
+    /// or cancelled by this engine anymore
+    pub(super) is_state_handed_over: AtomicBool,
+    pub(super) currency_pair_settings: Mutex<Vec<CurrencyPairSetting>>,
     pub(super) exchange_client: Box<dyn ExchangeClient>,
-    pub(super) features: ExchangeFeatures,
+    pub(crate) features: ExchangeFeatures,
     pub(super) events_channel: broadcast::Sender<ExchangeEvent>,
     pub(super) lifetime_manager: Arc<AppLifetimeManager>,
     pub(super) commission: Commission,
-    pub(super) wait_cancel_order: DashMap<ClientOrderId, broadcast::Sender<()>>,
-    pub(super) wait_finish_order: DashMap<ClientOrderId, broadcast::Sender<OrderRef>>,
+    pub(super) wait_cancel_order: DashMap<ClientOrderId, broadcast::Sender<WaitOutcome>>,
+    pub(super) wait_finish_order: DashMap<ClientOrderId, broadcast::Sender<WaitOutcome>>,
     pub(super) polling_trades_counts: DashMap<ExchangeAccountId, u32>,
     pub(super) polling_timeout_manager: PollingTimeoutManager,
     pub(super) orders_finish_events: DashMap<ClientOrderId, oneshot::Sender<()>>,
@@ -108,8 +144,28 @@ pub struct Exchange {
     pub(super) last_trades: DashMap<MarketId, Trade>,
     pub(super) timeout_manager: Arc<TimeoutManager>,
     pub(crate) balance_manager: Mutex<Option<Weak<Mutex<BalanceManager>>>>,
+    pub(super) order_filter: Mutex<Option<Arc<OrderFilter>>>,
+    pub(super) exposure_limits: Mutex<Option<Weak<ExposureLimits>>>,
+    pub(super) cancel_priorities: Mutex<CancelPriorities>,
     pub(super) buffered_fills_manager: Mutex<BufferedFillsManager>,
     pub(super) buffered_canceled_orders_manager: Mutex<BufferedCanceledOrdersManager>,
+    /// Fill commissions waiting for order book top of commission currency to be converted
+    pub(super) deferred_commission_conversions: Mutex<Vec<DeferredCommissionConversion>>,
+    /// Last trade ids of markets received by websocket for detection of missed trades
+    pub(super) trade_ids_tracker: Mutex<TradeIdsTracker>,
+    /// Trade ids of applied fills for deduplication of fills received again
+    pub(super) received_trades: ReceivedTrades,
+    /// Client order ids by exchange order ids which are kept after restart
+    pub(super) order_ids: OrderIds,
+    /// Account margin received by the latest margin monitoring check
+    pub(super) margin_info: Mutex<Option<MarginInfo>>,
+    pub(super) order_traces: OrderTraces,
+    /// Websocket order events are processed by markets, so hot market doesn't delay other ones
+    market_event_queues: MarketEventQueues,
+    /// Time of websocket disconnection for filling gap of missed user data after reconnection
+    pub(super) websocket_disconnected_at: Mutex<Option<DateTime>>,
+    /// Time of the latest received websocket message for diagnostics of silent connections
+    last_websocket_message_at: Mutex<Option<DateTime>>,
     // It allows to send and receive notification about event in websocket channel
     // Websocket event is main source detecting order creation result
     // Rest response using only for unsuccessful operations as error
@@ -128,7 +184,7 @@ pub struct Exchange {
             Option<oneshot::Receiver<CancelOrderResult>>,
         ),
     >,
-    connectivity_manager: Arc<ConnectivityManager>,
+    pub(super) connectivity_manager: Arc<ConnectivityManager>,
 }
 
 pub type BoxExchangeClient = Box<dyn ExchangeClient + Send + Sync + 'static>;
@@ -162,6 +218,11 @@ impl Exchange {
             symbols: Default::default(),
             currencies: Default::default(),
             order_book_top: Default::default(),
+            currency_pair_settings: Default::default(),
+            halted_markets: DashMap::new(),
+            disabled_markets: DashMap::new(),
+            is_awaiting_handover: AtomicBool::new(false),
+            is_state_handed_over: AtomicBool::new(false),
             wait_cancel_order: DashMap::new(),
             wait_finish_order: DashMap::new(),
             polling_trades_counts: DashMap::new(),
@@ -172,8 +233,20 @@ impl Exchange {
             last_trades_update_time: DashMap::new(),
             last_trades: DashMap::new(),
             balance_manager: Mutex::new(None),
+            order_filter: Mutex::new(None),
+            exposure_limits: Mutex::new(None),
+            cancel_priorities: Mutex::new(CancelPriorities::default()),
             buffered_fills_manager: Mutex::new(BufferedFillsManager::new()),
             buffered_canceled_orders_manager: Mutex::new(BufferedCanceledOrdersManager::new()),
+            deferred_commission_conversions: Mutex::new(Vec::new()),
+            trade_ids_tracker: Mutex::new(TradeIdsTracker::default()),
+            received_trades: ReceivedTrades::new(exchange_account_id),
+            order_ids: OrderIds::new(exchange_account_id),
+            margin_info: Mutex::new(None),
+            order_traces: OrderTraces::default(),
+            websocket_disconnected_at: Mutex::new(None),
+            last_websocket_message_at: Mutex::new(None),
+            market_event_queues: MarketEventQueues::new(exchange_account_id),
         });
 
         exchange.clone().setup_connectivity_manager();
@@ -196,6 +269,20 @@ impl Exchange {
                 Some(exchange) => exchange.on_connecting(),
                 None => log::info!("Unable to upgrade weak reference to Exchange instance"),
             }));
+
+        let exchange_weak = Arc::downgrade(&self);
+        self.connectivity_manager
+            .set_callback_connected(Box::new(move || match exchange_weak.upgrade() {
+                Some(exchange) => exchange.on_connected(),
+                None => log::info!("Unable to upgrade weak reference to Exchange instance"),
+            }));
+
+        let exchange_weak = Arc::downgrade(&self);
+        self.connectivity_manager
+            .set_callback_disconnected(Box::new(move |_| match exchange_weak.upgrade() {
+                Some(exchange) => exchange.on_disconnected(),
+                None => log::info!("Unable to upgrade weak reference to Exchange instance"),
+            }));
     }
 
     fn setup_exchange_client(self: Arc<Self>) {
@@ -203,7 +290,15 @@ impl Exchange {
         self.exchange_client.set_order_created_callback(Box::new(
             move |client_order_id, exchange_order_id, source_type| match exchange_weak.upgrade() {
                 Some(exchange) => {
-                    exchange.raise_order_created(&client_order_id, &exchange_order_id, source_type)
+                    let currency_pair =
+                        exchange.order_currency_pair(Some(&client_order_id), &exchange_order_id);
+                    exchange.process_order_event(currency_pair, move |exchange| {
+                        exchange.raise_order_created(
+                            &client_order_id,
+                            &exchange_order_id,
+                            source_type,
+                        )
+                    });
                 }
                 None => log::info!("Unable to upgrade weak reference to Exchange instance"),
             },
@@ -213,7 +308,15 @@ impl Exchange {
         self.exchange_client.set_order_cancelled_callback(Box::new(
             move |client_order_id, exchange_order_id, source_type| match exchange_weak.upgrade() {
                 Some(exchange) => {
-                    exchange.raise_order_cancelled(client_order_id, exchange_order_id, source_type);
+                    let currency_pair =
+                        exchange.order_currency_pair(Some(&client_order_id), &exchange_order_id);
+                    exchange.process_order_event(currency_pair, move |exchange| {
+                        exchange.raise_order_cancelled(
+                            client_order_id,
+                            exchange_order_id,
+                            source_type,
+                        )
+                    });
                 }
                 None => log::info!("Unable to upgrade weak reference to Exchange instance"),
             },
@@ -223,16 +326,33 @@ impl Exchange {
         self.exchange_client
             .set_handle_order_filled_callback(Box::new(move |event_data| {
                 match exchange_weak.upgrade() {
-                    Some(exchange) => exchange.handle_order_filled(event_data),
+                    Some(exchange) => {
+                        let currency_pair = exchange
+                            .order_currency_pair(
+                                event_data.client_order_id.as_ref(),
+                                &event_data.exchange_order_id,
+                            )
+                            .or(event_data.trade_currency_pair);
+                        exchange.process_order_event(currency_pair, move |exchange| {
+                            exchange.handle_order_filled(event_data)
+                        });
+                    }
                     None => log::info!("Unable to upgrade weak reference to Exchange instance"),
                 }
             }));
 
         let exchange_weak = Arc::downgrade(&self);
         self.exchange_client.set_handle_trade_callback(Box::new(
-            move |currency_pair, trade_id, price, quantity, order_side, transaction_time| {
+            move |currency_pair,
+                  trade_stream,
+                  trade_id,
+                  price,
+                  quantity,
+                  order_side,
+                  transaction_time| {
                 match exchange_weak.upgrade() {
                     Some(exchange) => {
+                        exchange.check_trade_id_gap(currency_pair, trade_stream, &trade_id);
                         exchange.handle_trade(
                             currency_pair,
                             trade_id,
@@ -248,7 +368,76 @@ impl Exchange {
         ));
     }
 
+    /// Order event is processed in queue of its market if market is known, otherwise immediately
+    fn process_order_event(
+        self: Arc<Self>,
+        currency_pair: Option<CurrencyPair>,
+        action: impl FnOnce(&Exchange) + Send + 'static,
+    ) {
+        match currency_pair {
+            Some(currency_pair) => {
+                let exchange = self.clone();
+                self.market_event_queues
+                    .enqueue(currency_pair, Box::new(move || action(&exchange)));
+            }
+            None => action(&self),
+        }
+    }
+
+    fn order_currency_pair(
+        &self,
+        client_order_id: Option<&ClientOrderId>,
+        exchange_order_id: &ExchangeOrderId,
+    ) -> Option<CurrencyPair> {
+        client_order_id
+            .and_then(|id| {
+                self.orders
+                    .cache_by_client_id
+                    .get(id)
+                    .map(|x| x.currency_pair())
+            })
+            .or_else(|| {
+                self.orders
+                    .cache_by_exchange_id
+                    .get(exchange_order_id)
+                    .map(|x| x.currency_pair())
+            })
+    }
+
+    /// Counts of websocket order events waiting for processing by markets
+    pub fn market_queue_depths(&self) -> Vec<MarketQueueDepth> {
+        self.market_event_queues.depths()
+    }
+
+    /// Depth of queues of websocket order events by markets after which market is logged
+    /// as overloaded
+    pub fn setup_market_event_queues(&self, warning_depth: usize) {
+        self.market_event_queues.set_warning_depth(warning_depth);
+    }
+
+    /// Statuses of websockets which are used by exchange client
+    pub async fn websocket_statuses(&self) -> Vec<(WebSocketRole, WebSocketStatus)> {
+        let mut statuses = Vec::new();
+        for role in [WebSocketRole::Main, WebSocketRole::Secondary] {
+            if self.exchange_client.is_websocket_enabled(role) {
+                let status = self.connectivity_manager.websocket_status(role).await;
+                statuses.push((role, status));
+            }
+        }
+        statuses
+    }
+
+    /// Time of websocket disconnection if websocket isn't reconnected yet
+    pub fn websocket_disconnected_time(&self) -> Option<DateTime> {
+        *self.websocket_disconnected_at.lock()
+    }
+
+    pub fn last_websocket_message_time(&self) -> Option<DateTime> {
+        *self.last_websocket_message_at.lock()
+    }
+
     fn on_websocket_message(&self, msg: &str) {
+        *self.last_websocket_message_at.lock() = Some(time_manager::now());
         if self.exchange_client.should_log_message(msg) {
             self.log_websocket_message(msg);
         }
@@ -262,6 +451,29 @@ impl Exchange {
         }
     }
 
+    /// Counts of received websocket messages which exchange client is unable to handle by message type
+    pub fn get_unhandled_websocket_messages_counts(&self) -> HashMap<String, u64> {
+        self.exchange_client.get_unhandled_messages_counts()
+    }
+
+    /// Pass recorded websocket messages to exchange client as if they were received from exchange.
+    /// Virtual clock is set to time of each record and order events of message are processed before
+    /// the next message, so event sequence of incident is reproduced deterministically through real handlers.
+    /// REST responses are taken from the same `TrafficReplay` by replay transport if
+    /// `traffic_replay_path` is set, so message is passed after REST responses recorded before it
+    pub async fn replay_websocket_traffic(&self, path: &str) -> Result<()> {
+        let replay = TrafficReplay::get_or_load(path)?;
+
+        let _virtual_clock_guard = scopeguard::guard((), |_| virtual_clock::reset());
+        while let Some((timestamp, message)) = replay.next_websocket_message().await {
+            virtual_clock::set(timestamp);
+            self.on_websocket_message(&message);
+            self.market_event_queues.wait_processed().await;
+        }
+
+        Ok(())
+    }
+
     fn on_connecting(&self) {
         if self
             .lifetime_manager
@@ -280,6 +492,24 @@ impl Exchange {
         }
     }
 
+    fn on_connected(self: Arc<Self>) {
+        let disconnected_at = match self.take_user_data_gap() {
+            Some(disconnected_at) => disconnected_at,
+            None => return,
+        };
+
+        let action = async move { self.fill_user_data_gap(disconnected_at).await };
+        spawn_future(
+            "Fill user data gap after websocket reconnection",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action.boxed(),
+        );
+    }
+
+    fn on_disconnected(&self) {
+        self.start_user_data_gap();
+    }
+
     fn log_websocket_message(&self, msg: &str) {
         log::info!(
             "Websocket message from {}: {}",
@@ -292,11 +522,68 @@ impl Exchange {
         *self.balance_manager.lock() = Some(Arc::downgrade(&balance_manager));
     }
 
+    pub(crate) fn get_balance_manager(&self) -> Option<Arc<Mutex<BalanceManager>>> {
+        self.balance_manager.lock().as_ref().and_then(Weak::upgrade)
+    }
+
+    /// Round-trip latencies of REST requests and websockets of exchange account
+    pub fn latency_statistics(&self) -> Arc<LatencyStatistics> {
+        LatencyStatistics::get_or_create(self.exchange_account_id)
+    }
+
+    /// Fees of exchange account from settings
+    pub fn commission(&self) -> &Commission {
+        &self.commission
+    }
+
+    pub fn setup_order_filter(&self, order_filter: Arc<OrderFilter>) {
+        *self.order_filter.lock() = Some(order_filter);
+    }
+
+    pub fn setup_exposure_limits(&self, exposure_limits: &Arc<ExposureLimits>) {
+        *self.exposure_limits.lock() = Some(Arc::downgrade(exposure_limits));
+    }
+
+    pub fn setup_cancel_priorities(&self, cancel_priorities: CancelPriorities) {
+        *self.cancel_priorities.lock() = cancel_priorities;
+    }
+
     pub async fn connect(self: Arc<Self>) {
         self.try_connect().await;
         // TODO Reconnect
     }
 
+    pub async fn sync_server_time(&self) {
+        if let Err(error) = self.exchange_client.sync_server_time().await {
+            log::warn!(
+                "Unable to synchronize server time for {}: {:?}",
+                self.exchange_account_id,
+                error
+            );
+        }
+    }
+
+    pub(crate) fn spawn_server_time_sync(self: &Arc<Self>) {
+        let exchange_weak = Arc::downgrade(self);
+        let sync_server_time = move || {
+            let exchange_weak = exchange_weak.clone();
+            async move {
+                if let Some(exchange) = exchange_weak.upgrade() {
+                    exchange.sync_server_time().await;
+                }
+            }
+            .boxed()
+        };
+
+        let _ = spawn_by_timer(
+            sync_server_time,
+            &format!("Sync server time for {}", self.exchange_account_id),
+            SERVER_TIME_SYNC_PERIOD,
+            SERVER_TIME_SYNC_PERIOD,
+            SpawnFutureFlags::STOP_BY_TOKEN,
+        );
+    }
+
     pub async fn disconnect(self: Arc<Self>) {
         self.connectivity_manager.clone().disconnect().await
     }
@@ -343,7 +630,8 @@ impl Exchange {
         // TODO all other logs and finish_connected
     }
 
-    pub async fn cancel_all_orders(&self, currency_pair: CurrencyPair) -> Result<()> {
+    pub async fn cancel_all_orders(&self, currency_pair: CurrencyPair) -> MmbResult<()> {
+        self.check_orders_not_handed_over()?;
         self.exchange_client
             .cancel_all_orders(currency_pair)
             .await?;
@@ -356,7 +644,84 @@ impl Exchange {
         role: WebSocketRole,
     ) -> Result<WebSocketParams> {
         let ws_url = self.exchange_client.create_ws_url(role).await?;
-        Ok(WebSocketParams::new(ws_url))
+        let settings = self.exchange_client.get_settings();
+        let mut params = match settings.websocket_ping_interval {
+            Some(interval) => WebSocketParams::with_heartbeat_interval(ws_url, interval.duration()),
+            None => WebSocketParams::new(ws_url),
+        };
+
+        if let Some(proxy) = &settings.proxy {
+            params.set_proxy(Proxy::parse(proxy)?);
+        }
+
+        if let Some(traffic_recorder) = self.traffic_recorder()? {
+            params.set_traffic_recorder(traffic_recorder);
+        }
+
+        Ok(params)
+    }
+
+    /// Recorder of traffic file of exchange if `traffic_record_path` is set in exchange settings
+    pub fn traffic_recorder(&self) -> Result<Option<Arc<TrafficRecorder>>> {
+        self.exchange_client
+            .get_settings()
+            .traffic_record_path
+            .as_deref()
+            .map(TrafficRecorder::get_or_create)
+            .transpose()
+    }
+
+    pub fn websocket_channels(&self) -> Vec<String> {
+        self.exchange_client.get_websocket_channels()
+    }
+
+    /// Subscribes main websocket to additional channel. Messages of channel are routed to handler
+    /// registered in message router of exchange client, so channel without handler is rejected
+    pub async fn subscribe_websocket_channel(&self, channel: &str) -> Result<()> {
+        if !self.exchange_client.add_websocket_channel(channel)? {
+            return Ok(());
+        }
+
+        log::info!(
+            "Websocket channel {} is subscribed on {}",
+            channel,
+            self.exchange_account_id
+        );
+
+        if let Some(message) = self
+            .exchange_client
+            .build_ws_subscribe_message(&[channel.to_owned()])
+        {
+            self.connectivity_manager
+                .send(WebSocketRole::Main, &message)
+                .await;
+        }
+
+        Ok(())
+    }
+
+    /// Unsubscribes main websocket from channel, so it isn't resubscribed after reconnection too
+    pub async fn unsubscribe_websocket_channel(&self, channel: &str) -> Result<()> {
+        if !self.exchange_client.remove_websocket_channel(channel)? {
+            return Ok(());
+        }
+
+        log::info!(
+            "Websocket channel {} is unsubscribed on {}",
+            channel,
+            self.exchange_account_id
+        );
+
+        if let Some(message) = self
+            .exchange_client
+            .build_ws_unsubscribe_message(&[channel.to_owned()])
+        {
+            self.connectivity_manager
+                .send(WebSocketRole::Main, &message)
+                .await;
+        }
+
+        Ok(())
     }
 
     pub(crate) fn add_event_on_order_change(
@@ -364,8 +729,10 @@ impl Exchange {
         order_ref: &OrderRef,
         event_type: OrderEventType,
     ) -> Result<()> {
+        let mut is_expired = false;
         if let OrderEventType::CancelOrderSucceeded = event_type {
-            order_ref.fn_mut(|order| order.internal_props.was_cancellation_event_raised = true)
+            order_ref.fn_mut(|order| order.internal_props.was_cancellation_event_raised = true);
+            is_expired = order_ref.fn_ref(|order| order.header.is_expired(time_manager::now()));
         }
 
         if order_ref.is_finished() {
@@ -375,11 +742,23 @@ impl Exchange {
                 .remove(&order_ref.client_order_id());
         }
 
+        self.order_traces.record_event(order_ref, &event_type);
+
         let event = ExchangeEvent::OrderEvent(OrderEvent::new(order_ref.clone(), event_type));
         self.events_channel
             .send(event)
             .context("Unable to send event. Probably receiver is already dropped")?;
 
+        if is_expired {
+            let event = ExchangeEvent::OrderEvent(OrderEvent::new(
+                order_ref.clone(),
+                OrderEventType::OrderExpired,
+            ));
+            self.events_channel
+                .send(event)
+                .context("Unable to send event. Probably receiver is already dropped")?;
+        }
+
         Ok(())
     }
 
@@ -398,7 +777,7 @@ impl Exchange {
             }
             Ok(orders) => {
                 tokio::select! {
-                    _ = self.cancel_orders(orders.clone(), cancellation_token.clone()) => nothing_to_do(),
+                    _ = self.cancel_orders_by_priority(orders.clone(), cancellation_token.clone()) => nothing_to_do(),
                     _ = cancellation_token.when_cancelled() => {
                         log::error!(
                             "Opened orders canceling for exchange account id {} was interrupted by CancellationToken for list of orders {:?}",
@@ -415,6 +794,46 @@ impl Exchange {
         }
     }
 
+    /// Cancels orders in stages by `CancelPriorities`, so every stage starts after orders
+    /// of the previous one are cancelled. Orders which aren't in cache are cancelled first
+    async fn cancel_orders_by_priority(
+        &self,
+        orders: Vec<OrderInfo>,
+        cancellation_token: CancellationToken,
+    ) {
+        let (stages, kept_orders) = {
+            let cancel_priorities = self.cancel_priorities.lock();
+            CancelPriorities::split_by_stages(orders, |order| {
+                match self
+                    .orders
+                    .cache_by_exchange_id
+                    .get(&order.exchange_order_id)
+                {
+                    Some(order_ref) => order_ref.fn_ref(|x| {
+                        let class = CancelClass::of(x.header.order_type, x.header.reduce_only);
+                        cancel_priorities.stage(&x.header.strategy_name, class)
+                    }),
+                    None => Some(0),
+                }
+            })
+        };
+
+        if !kept_orders.is_empty() {
+            log::info!(
+                "Orders {} on {} are left open by cancel priorities",
+                kept_orders
+                    .iter()
+                    .map(|x| x.client_order_id.as_str())
+                    .join(", "),
+                self.exchange_account_id,
+            );
+        }
+
+        for orders in stages {
+            let _ = self.cancel_orders(orders, cancellation_token.clone()).await;
+        }
+    }
+
     pub fn get_balance_reservation_currency_code(
         &self,
         symbol: Arc<Symbol>,
@@ -428,12 +847,14 @@ impl Exchange {
         &self,
         position: &ActivePosition,
         price: Option<Price>,
-    ) -> Result<ClosedPosition> {
+    ) -> MmbResult<ClosedPosition> {
+        self.check_trading_enabled()?;
+
         let response = self
             .exchange_client
             .request_close_position(position, price)
             .await
-            .expect("request_close_position failed.");
+            .context("Request of closing position failed")?;
 
         log::info!(
             "Close position response for {:?} {:?} {:?}",
@@ -444,33 +865,57 @@ impl Exchange {
 
         is_rest_error_code(&response)?;
 
-        self.exchange_client.parse_close_position(&response)
+        Ok(self.exchange_client.parse_close_position(&response)?)
     }
 
+    /// Retries closing of position until it's closed, attempts are exhausted
+    /// or closing is cancelled
     pub async fn close_position_loop(
         &self,
         position: &ActivePosition,
         price: Option<Decimal>,
         cancellation_token: CancellationToken,
-    ) -> ClosedPosition {
+    ) -> Result<ClosedPosition> {
         log::info!("Closing position {}", position.id);
 
+        // Closing of position isn't retried if trading is disabled
+        self.check_trading_enabled()?;
+
+        let mut attempt = 0;
         loop {
+            attempt += 1;
             self.timeout_manager
                 .reserve_when_available(
                     self.exchange_account_id,
                     RequestType::GetActivePositions,
                     None,
                     cancellation_token.clone(),
-                )
-                .expect("Failed to reserve timeout_manager for close_position")
-                .await;
+                )?
+                .await
+                .into_result()
+                .with_context(|| format!("Closing position {} is cancelled", position.id))?;
 
             log::info!("Closing position request reserved {}", position.id);
 
-            if let Ok(closed_position) = self.close_position(position, price).await {
-                log::info!("Closed position {}", position.id);
-                return closed_position;
+            match self.close_position(position, price).await {
+                Ok(closed_position) => {
+                    log::info!("Closed position {}", position.id);
+                    return Ok(closed_position);
+                }
+                Err(error) if attempt < CLOSE_POSITION_ATTEMPTS => log::warn!(
+                    "Attempt {} of closing position {} failed: {:?}",
+                    attempt,
+                    position.id,
+                    error
+                ),
+                Err(error) => {
+                    return Err(error).with_context(|| {
+                        format!(
+                            "Unable to close position {} in {} attempts",
+                            position.id, attempt
+                        )
+                    })
+                }
             }
         }
     }
@@ -659,50 +1104,60 @@ impl Exchange {
         balances_and_positions
     }
 
+    /// Requests balances and positions with retries. Error of the last attempt is returned
+    /// when all attempts are failed
     pub async fn get_balance(
         &self,
         cancellation_token: CancellationToken,
-    ) -> Option<ExchangeBalancesAndPositions> {
-        let print_warn = |retry_attempt: i32, error: String| {
-            log::warn!(
-                "Failed to get balance for {} on retry {}: {}",
-                self.exchange_account_id,
-                retry_attempt,
-                error
-            )
-        };
+    ) -> MmbResult<ExchangeBalancesAndPositions> {
+        const GET_BALANCE_ATTEMPTS: i32 = 5;
 
-        for retry_attempt in 1..=5 {
+        let mut retry_attempt = 0;
+        loop {
+            retry_attempt += 1;
             let balances_and_positions = self
                 .get_balance_and_positions(cancellation_token.clone())
                 .await;
 
-            match balances_and_positions {
+            let error = match balances_and_positions {
                 Ok(ExchangeBalancesAndPositions {
                     positions,
                     balances,
                 }) => {
                     if balances.is_empty() {
-                        (print_warn)(retry_attempt, "balances is empty".into());
-                        continue;
+                        anyhow!("Balances are empty")
+                    } else {
+                        return Ok(self.handle_balances_and_positions(
+                            self.remove_unknown_currency_pairs(positions, balances),
+                        ));
                     }
-
-                    return Some(self.handle_balances_and_positions(
-                        self.remove_unknown_currency_pairs(positions, balances),
-                    ));
                 }
-                Err(error) => (print_warn)(retry_attempt, error.to_string()),
+                Err(error) => error,
             };
-        }
 
-        log::warn!(
-            "GetBalance for {} reached maximum retries - reconnecting",
-            self.exchange_account_id
-        );
+            if retry_attempt == GET_BALANCE_ATTEMPTS {
+                log::warn!(
+                    "GetBalance for {} reached maximum retries - reconnecting",
+                    self.exchange_account_id
+                );
+
+                // TODO: uncomment it after implementation reconnect function
+                // await Reconnect();
+                return Err(error
+                    .context(format!(
+                        "Unable to get balance for {} in {} attempts",
+                        self.exchange_account_id, retry_attempt
+                    ))
+                    .into());
+            }
 
-        // TODO: uncomment it after implementation reconnect function
-        // await Reconnect();
-        return None;
+            log::warn!(
+                "Failed to get balance for {} on retry {}: {:?}",
+                self.exchange_account_id,
+                retry_attempt,
+                error
+            );
+        }
     }
 
     fn handle_liquidation_price(
@@ -1,7 +1,10 @@
 use std::sync::Arc;
 
+use anyhow::Result;
+
 use super::commission::Commission;
 use crate::exchanges::events::ExchangeEvent;
+use crate::exchanges::traits::ExchangeClientBuilder;
 use crate::lifecycle::app_lifetime_manager::AppLifetimeManager;
 use crate::lifecycle::launcher::EngineBuildConfig;
 use crate::settings::ExchangeSettings;
@@ -44,12 +47,32 @@ pub async fn create_exchange(
     user_settings: &ExchangeSettings,
     build_settings: &EngineBuildConfig,
     events_channel: broadcast::Sender<ExchangeEvent>,
+    market_events_warning_depth: usize,
     lifetime_manager: Arc<AppLifetimeManager>,
     timeout_manager: Arc<TimeoutManager>,
-) -> Arc<Exchange> {
+) -> Result<Arc<Exchange>> {
     let exchange_client_builder =
         &build_settings.supported_exchange_clients[&user_settings.exchange_account_id.exchange_id];
 
+    create_exchange_with_builder(
+        user_settings,
+        exchange_client_builder.as_ref(),
+        events_channel,
+        market_events_warning_depth,
+        lifetime_manager,
+        timeout_manager,
+    )
+    .await
+}
+
+pub async fn create_exchange_with_builder(
+    user_settings: &ExchangeSettings,
+    exchange_client_builder: &dyn ExchangeClientBuilder,
+    events_channel: broadcast::Sender<ExchangeEvent>,
+    market_events_warning_depth: usize,
+    lifetime_manager: Arc<AppLifetimeManager>,
+    timeout_manager: Arc<TimeoutManager>,
+) -> Result<Arc<Exchange>> {
     let exchange_client = exchange_client_builder.create_exchange_client(
         user_settings.clone(),
         events_channel.clone(),
@@ -64,12 +87,42 @@ pub async fn create_exchange(
         events_channel,
         lifetime_manager,
         timeout_manager,
-        Commission::default(),
+        user_settings
+            .fees
+            .as_ref()
+            .map(Commission::from)
+            .unwrap_or_default(),
     );
+    exchange.setup_market_event_queues(market_events_warning_depth);
 
-    exchange.build_symbols(&user_settings.currency_pairs).await;
-
-    exchange.clone().connect().await;
+    exchange.sync_server_time().await;
+    exchange.spawn_server_time_sync();
+    exchange.spawn_deferred_commissions_conversion();
 
     exchange
+        .build_symbols(&user_settings.currency_pairs)
+        .await?;
+    if let Some(threshold) = user_settings.dust_conversion_threshold {
+        exchange.spawn_dust_conversion(
+            threshold,
+            user_settings.dust_conversion_period.map(|x| x.duration()),
+        );
+    }
+
+    if let Some(reconnect_backoff) = user_settings.websocket_reconnect_backoff {
+        exchange
+            .connectivity_manager
+            .set_reconnect_backoff(reconnect_backoff.duration());
+    }
+    // Websocket messages are taken from recorded traffic in replay mode
+    match &user_settings.traffic_replay_path {
+        Some(replay_path) => log::info!(
+            "Websockets of {} aren't connected because traffic is replayed from {}",
+            user_settings.exchange_account_id,
+            replay_path
+        ),
+        None => exchange.clone().connect().await,
+    }
+
+    Ok(exchange)
 }
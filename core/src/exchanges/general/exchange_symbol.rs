@@ -1,19 +1,26 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use dashmap::DashMap;
+use futures::FutureExt;
 use itertools::Itertools;
-use mmb_utils::infrastructure::WithExpect;
+use mmb_utils::send_expected::SendExpectedByRef;
 use rust_decimal_macros::dec;
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::exchanges::common::{CurrencyCode, CurrencyId, ExchangeAccountId};
+use crate::exchanges::events::{ExchangeEvent, SymbolEvent};
 use crate::exchanges::general::helpers::{get_rest_error, handle_parse_error};
+use crate::services::scheduler::{Schedule, Scheduler};
 use crate::settings::CurrencyPairSetting;
 
 use super::{exchange::Exchange, symbol::Symbol};
 
 impl Exchange {
-    pub async fn build_symbols(&self, currency_pair_settings: &Option<Vec<CurrencyPairSetting>>) {
-        let exchange_symbols = &self.request_symbols_with_retries().await;
+    pub async fn build_symbols(
+        &self,
+        currency_pair_settings: &Option<Vec<CurrencyPairSetting>>,
+    ) -> Result<()> {
+        let exchange_symbols = &self.request_symbols_with_retries().await?;
 
         let supported_currencies = get_supported_currencies(exchange_symbols);
         self.setup_supported_currencies(supported_currencies);
@@ -23,37 +30,165 @@ impl Exchange {
                 .insert(symbol.currency_pair(), dec!(1));
         }
 
-        let currency_pairs = currency_pair_settings.as_ref().with_expect(|| {
+        let currency_pairs = currency_pair_settings.as_ref().with_context(|| {
             format!(
                 "Settings `currency_pairs` should be specified for exchange {}",
                 self.exchange_account_id
             )
-        });
+        })?;
+        *self.currency_pair_settings.lock() = currency_pairs.clone();
+
+        let symbols = get_symbols(&currency_pairs, exchange_symbols, self.exchange_account_id);
+        // Different settings can match the same symbol, e.g. `base`/`quote` and exchange symbol name
+        if let Some(currency_pair) = symbols
+            .iter()
+            .map(|x| x.currency_pair())
+            .duplicates()
+            .next()
+        {
+            bail!(
+                "Currency pair {} is specified more than once in `currency_pairs` settings of exchange {}",
+                currency_pair,
+                self.exchange_account_id
+            );
+        }
+        self.setup_symbols(symbols);
+
+        for symbol in self.symbols.iter() {
+            self.update_market_trading_status(symbol.value());
+        }
+
+        Ok(())
+    }
+
+    /// Re-request all symbols from exchange and apply changes of their metadata (precision, limits, etc.)
+    /// Only currency pairs specified in settings can be added
+    pub async fn refresh_symbols(&self) -> Result<()> {
+        let exchange_symbols = self.build_all_symbols_core().await?;
 
-        self.setup_symbols(get_symbols(
-            &currency_pairs,
-            exchange_symbols,
+        let supported_currencies = get_supported_currencies(&exchange_symbols);
+        self.setup_supported_currencies(supported_currencies);
+
+        let currency_pair_settings = self.currency_pair_settings.lock().clone();
+        let symbols = get_symbols(
+            &currency_pair_settings,
+            &exchange_symbols,
             self.exchange_account_id,
-        ));
+        );
+
+        let delisted_currency_pairs = self
+            .symbols
+            .iter()
+            .map(|x| *x.key())
+            .filter(|currency_pair| !symbols.iter().any(|x| x.currency_pair() == *currency_pair))
+            .collect_vec();
+        for currency_pair in delisted_currency_pairs {
+            self.set_market_halted(currency_pair, "symbol is missing on exchange");
+        }
+
+        let mut is_symbol_added = false;
+        for symbol in symbols {
+            let currency_pair = symbol.currency_pair();
+            self.update_market_trading_status(&symbol);
+
+            let current_symbol = self.symbols.get(&currency_pair).map(|x| x.value().clone());
+
+            let create_event: fn(SymbolEvent) -> ExchangeEvent = match current_symbol {
+                None => {
+                    log::info!(
+                        "Symbol {} was added on {}: {:?}",
+                        currency_pair,
+                        self.exchange_account_id,
+                        symbol
+                    );
+
+                    self.leverage_by_currency_pair
+                        .entry(currency_pair)
+                        .or_insert(dec!(1));
+                    is_symbol_added = true;
+
+                    ExchangeEvent::SymbolAdded
+                }
+                Some(current_symbol) if !current_symbol.is_metadata_equal(&symbol) => {
+                    log::info!(
+                        "Symbol {} was updated on {}: {:?} -> {:?}",
+                        currency_pair,
+                        self.exchange_account_id,
+                        current_symbol,
+                        symbol
+                    );
+
+                    ExchangeEvent::SymbolUpdated
+                }
+                Some(_) => continue,
+            };
+
+            self.symbols.insert(currency_pair, symbol.clone());
+            self.events_channel.send_expected(create_event(SymbolEvent {
+                exchange_account_id: self.exchange_account_id,
+                symbol,
+            }));
+        }
+
+        if is_symbol_added {
+            let all_symbols = self.symbols.iter().map(|x| x.value().clone()).collect_vec();
+            self.setup_symbols(all_symbols);
+        }
+
+        Ok(())
     }
 
-    async fn request_symbols_with_retries(&self) -> Vec<Arc<Symbol>> {
+    /// Periodically refresh symbols while exchange is alive
+    pub(crate) fn schedule_symbols_refreshing(
+        self: &Arc<Self>,
+        scheduler: &Arc<Scheduler>,
+        period: Duration,
+    ) {
+        let exchange_weak = Arc::downgrade(self);
+        let refresh_symbols = move |_| {
+            let exchange_weak = exchange_weak.clone();
+            async move {
+                let exchange = match exchange_weak.upgrade() {
+                    Some(exchange) => exchange,
+                    None => return,
+                };
+
+                if let Err(error) = exchange.refresh_symbols().await {
+                    log::warn!(
+                        "Unable to refresh symbols for {}: {:?}",
+                        exchange.exchange_account_id,
+                        error
+                    );
+                }
+            }
+            .boxed()
+        };
+
+        let _ = scheduler.schedule(
+            &format!("Refresh symbols for {}", self.exchange_account_id),
+            Schedule::Every(period),
+            refresh_symbols,
+        );
+    }
+
+    async fn request_symbols_with_retries(&self) -> Result<Vec<Arc<Symbol>>> {
         const MAX_RETRIES: u8 = 5;
         let mut retry = 0;
         loop {
             match self.build_all_symbols_core().await {
-                Ok(result_symbols) => return result_symbols,
+                Ok(result_symbols) => return Ok(result_symbols),
                 Err(error) => {
-                    let error_message = format!(
+                    if retry >= MAX_RETRIES {
+                        return Err(error).with_context(|| {
+                            format!("Unable to get symbols for {}", self.exchange_account_id)
+                        });
+                    }
+
+                    log::warn!(
                         "Unable to get symbol for {}: {:?}",
-                        self.exchange_account_id, error
+                        self.exchange_account_id,
+                        error
                     );
-
-                    if retry < MAX_RETRIES {
-                        log::warn!("{}", error_message);
-                    } else {
-                        panic!("{}", error_message);
-                    }
                 }
             }
 
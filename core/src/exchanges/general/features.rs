@@ -66,6 +66,8 @@ pub struct OrderFeatures {
     pub order_was_completed_error_for_cancellation: bool,
     pub supports_already_cancelled_order: bool,
     pub supports_stop_loss_order: bool,
+    /// Exchange cancels orders with `expire_at` itself, so engine doesn't need to schedule cancellation
+    pub supports_good_till_date: bool,
 }
 
 impl OrderFeatures {
@@ -77,6 +79,7 @@ impl OrderFeatures {
         order_was_completed_error_for_cancellation: bool,
         supports_already_cancelled_order: bool,
         supports_stop_loss_order: bool,
+        supports_good_till_date: bool,
     ) -> Self {
         Self {
             maker_only,
@@ -86,6 +89,7 @@ impl OrderFeatures {
             order_was_completed_error_for_cancellation,
             supports_already_cancelled_order,
             supports_stop_loss_order,
+            supports_good_till_date,
         }
     }
 }
@@ -1,6 +1,7 @@
 use chrono::Utc;
 use mmb_utils::infrastructure::WithExpect;
 use mmb_utils::nothing_to_do;
+use mmb_utils::warn_limited;
 
 use crate::{
     exchanges::common::ExchangeError, exchanges::common::ExchangeErrorType,
@@ -25,8 +26,9 @@ impl Exchange {
 
         match self.orders.cache_by_exchange_id.get(&exchange_order_id) {
             None => {
-                log::error!("cancel_order_failed was called for an order which is not in the local order pool: {:?} on {}",
+                log::error!("cancel_order_failed was called for an order which is not in the local order pool: {:?} (client order id {:?}) on {}",
                     exchange_order_id,
+                    self.order_ids.get_client_order_id(exchange_order_id),
                     self.exchange_account_id);
             }
             Some(order) => self.react_based_on_order_status(
@@ -47,7 +49,7 @@ impl Exchange {
     ) {
         match order.status() {
             OrderStatus::Canceled => {
-                log::warn!(
+                warn_limited!(
                     "cancel_order_failed was called for already Canceled order: {} {:?} on {}",
                     order.client_order_id(),
                     order.exchange_order_id(),
@@ -55,7 +57,7 @@ impl Exchange {
                 );
             }
             OrderStatus::Completed => {
-                log::warn!(
+                warn_limited!(
                     "cancel_order_failed was called for already Completed order: {} {:?} on {}",
                     order.client_order_id(),
                     order.exchange_order_id(),
@@ -1,5 +1,6 @@
 use chrono::Utc;
 use mmb_utils::infrastructure::WithExpect;
+use mmb_utils::{info_limited, warn_limited};
 
 use crate::{
     exchanges::common::Amount,
@@ -26,7 +27,7 @@ impl Exchange {
         );
 
         if Self::should_ignore_event(self.features.allowed_cancel_event_source_type, source_type) {
-            log::info!("Ignoring fill {:?}", args_to_log);
+            info_limited!("Ignoring fill {:?}", args_to_log);
             return;
         }
 
@@ -43,9 +44,12 @@ impl Exchange {
                     .lock()
                     .add_order(self.exchange_account_id, exchange_order_id.clone());
 
+                let client_order_id = client_order_id
+                    .cloned()
+                    .or_else(|| self.order_ids.get_client_order_id(exchange_order_id));
                 match client_order_id {
                     Some(client_order_id) =>
-                        self.raise_order_created(&client_order_id, &exchange_order_id, source_type),
+                        self.raise_order_created(&client_order_id, exchange_order_id, source_type),
                     None =>
                         log::error!("cancel_order_succeeded was received for an order which is not in the system {} {:?}",
                             self.exchange_account_id,
@@ -70,7 +74,7 @@ impl Exchange {
             _ => return false,
         };
 
-        log::warn!(
+        warn_limited!(
             "CancelOrderSucceeded received for {} order {} {:?} {}",
             arg_to_log,
             client_order_id,
@@ -1,6 +1,8 @@
 use chrono::Utc;
 use mmb_utils::infrastructure::WithExpect;
+use mmb_utils::send_expected::SendExpectedByRef;
 use mmb_utils::DateTime;
+use mmb_utils::{info_limited, warn_limited};
 use parking_lot::RwLock;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
@@ -14,9 +16,14 @@ use crate::{
         common::CurrencyPair,
         common::ExchangeAccountId,
         common::Price,
-        events::{AllowedEventSourceType, TradeId},
+        events::{
+            AllowedEventSourceType, BalanceChangeReason, BalanceDeltaEvent, ExchangeEvent,
+            FillAnomalyEvent, FillAnomalyKind, TradeId,
+        },
         general::commission::Percent,
+        general::commission_conversion::DeferredCommissionConversion,
         general::exchange::Exchange,
+        general::received_trades::ReceivedFill,
         general::symbol::{Round, Symbol},
     },
     math::ConvertPercentToRate,
@@ -27,10 +34,12 @@ use crate::{
         fill::OrderFillType,
         order::ClientOrderId,
         order::ExchangeOrderId,
+        order::OrderAmountKind,
         order::OrderSide,
         order::OrderSnapshot,
         order::OrderStatus,
         order::OrderType,
+        order::PositionSide,
         order::{ClientOrderFillId, OrderRole},
         pool::OrderRef,
     },
@@ -64,6 +73,7 @@ pub struct FillEventData {
     pub order_side: Option<OrderSide>,
     pub order_amount: Option<Amount>,
     pub fill_date: Option<DateTime>,
+    pub position_side: Option<PositionSide>,
 }
 
 impl Exchange {
@@ -81,7 +91,7 @@ impl Exchange {
             self.features.allowed_fill_event_source_type,
             event_data.source_type,
         ) {
-            log::info!("Ignoring fill {:?}", args_to_log);
+            info_limited!("Ignoring fill {:?}", args_to_log);
             return;
         }
 
@@ -100,7 +110,22 @@ impl Exchange {
             .get(&event_data.exchange_order_id)
         {
             None => {
-                if let Some(client_order_id) = &event_data.client_order_id {
+                if event_data.client_order_id.is_none() {
+                    // Order could be created before restart, so it isn't in local orders pool
+                    event_data.client_order_id = self
+                        .order_ids
+                        .get_client_order_id(&event_data.exchange_order_id);
+                }
+
+                if let Some(order_ref) = self.restore_order_created_before_restart(&event_data) {
+                    return self.create_and_add_order_fill(&mut event_data, &order_ref);
+                }
+
+                if let Some(client_order_id) = event_data
+                    .client_order_id
+                    .as_ref()
+                    .filter(|x| self.orders.cache_by_client_id.contains_key(*x))
+                {
                     self.handle_create_order_succeeded(
                         self.exchange_account_id,
                         client_order_id,
@@ -116,7 +141,18 @@ impl Exchange {
                     return self.create_and_add_order_fill(&mut event_data, &order_ref);
                 }
 
-                log::info!("Received a fill for not existing order {:?}", &args_to_log);
+                log::error!("Received a fill for not existing order {:?}", &args_to_log);
+                let _ = self
+                    .events_channel
+                    .send(ExchangeEvent::FillAnomaly(FillAnomalyEvent {
+                        exchange_account_id: self.exchange_account_id,
+                        currency_pair: event_data.trade_currency_pair,
+                        client_order_id: event_data.client_order_id.clone(),
+                        exchange_order_id: Some(event_data.exchange_order_id.clone()),
+                        price: event_data.fill_price,
+                        amount: event_data.fill_amount,
+                        kind: FillAnomalyKind::UnknownOrder,
+                    }));
 
                 let source_type = event_data.source_type;
                 let exchange_order_id = event_data.exchange_order_id.clone();
@@ -171,7 +207,7 @@ impl Exchange {
             // It happens when WebSocket is glitchy and we miss update and the problem is we have no idea how to handle diff updates
             // after applying a non-diff one as there's no TradeId, so we have to ignore all the diff updates afterwards
             // relying only on fallbacks
-            log::warn!(
+            warn_limited!(
                 "Unable to process a diff fill after a non-diff one {:?}",
                 order_ref
             );
@@ -188,7 +224,7 @@ impl Exchange {
         order_ref: &OrderRef,
     ) -> bool {
         if !event_data.is_diff && order_filled_amount >= event_data.fill_amount {
-            log::warn!(
+            warn_limited!(
                 "order.filled_amount is {} >= received fill {}, so non-diff fill for {} {:?} should be ignored",
                 order_filled_amount,
                 event_data.fill_amount,
@@ -210,7 +246,7 @@ impl Exchange {
     ) -> bool {
         if let Some(total_filled_amount) = event_data.total_filled_amount {
             if order_filled_amount + last_fill_amount != total_filled_amount {
-                log::warn!(
+                warn_limited!(
                     "Fill was missed because {} != {} for {:?}",
                     order_filled_amount,
                     total_filled_amount,
@@ -233,11 +269,7 @@ impl Exchange {
     ) -> Option<(Price, Amount, Price)> {
         let mut last_fill_amount = event_data.fill_amount;
         let mut last_fill_price = event_data.fill_price;
-        let mut last_fill_cost = if !symbol.is_derivative() {
-            last_fill_amount * last_fill_price
-        } else {
-            last_fill_amount / last_fill_price
-        };
+        let mut last_fill_cost = symbol.get_cost(last_fill_amount, last_fill_price);
 
         if !event_data.is_diff && order_fills.len() > 0 {
             match Self::calculate_cost_diff(&order_fills, order_ref, last_fill_cost) {
@@ -259,7 +291,7 @@ impl Exchange {
         }
 
         if last_fill_amount.is_zero() {
-            log::warn!(
+            warn_limited!(
                 "last_fill_amount was received for 0 for {}, {:?}",
                 order_ref.client_order_id(),
                 order_ref.exchange_order_id()
@@ -280,7 +312,7 @@ impl Exchange {
         let total_filled_cost: Decimal = order_fills.iter().map(|fill| fill.cost()).sum();
         let cost_diff = last_fill_cost - total_filled_cost;
         if cost_diff <= dec!(0) {
-            log::warn!(
+            warn_limited!(
                 "cost_diff is {} which is <= 0 for {:?}",
                 cost_diff,
                 order_ref
@@ -299,11 +331,7 @@ impl Exchange {
         cost_diff: Price,
     ) -> (Price, Amount, Price) {
         let amount_diff = last_fill_amount - order_filled_amount;
-        let res_fill_price = if !symbol.is_derivative() {
-            cost_diff / amount_diff
-        } else {
-            amount_diff / cost_diff
-        };
+        let res_fill_price = symbol.get_price_by_cost(amount_diff, cost_diff);
         let last_fill_price = symbol.price_round(res_fill_price, Round::ToNearest);
 
         let last_fill_amount = amount_diff;
@@ -369,12 +397,11 @@ impl Exchange {
                     None => expected_commission_rate,
                 };
 
-                let last_fill_amount_in_currency_code = symbol
-                    .convert_amount_from_amount_currency_code(
-                        commission_currency_code,
-                        last_fill_amount,
-                        last_fill_price,
-                    );
+                let last_fill_amount_in_currency_code = symbol.get_amount_value_in_currency(
+                    commission_currency_code,
+                    last_fill_amount,
+                    last_fill_price,
+                );
                 last_fill_amount_in_currency_code * commission_rate
             }
         }
@@ -395,6 +422,7 @@ impl Exchange {
         expected_commission_rate
     }
 
+    /// Returns `true` if commission should be converted, but there is no order book top for it yet
     fn update_commission_for_bnb_case(
         &self,
         commission_currency_code: CurrencyCode,
@@ -402,46 +430,24 @@ impl Exchange {
         commission_amount: Amount,
         converted_commission_amount: &mut Amount,
         converted_commission_currency_code: &mut CurrencyCode,
-    ) {
-        if commission_currency_code != symbol.base_currency_code()
-            && commission_currency_code != symbol.quote_currency_code()
+    ) -> bool {
+        if commission_currency_code == symbol.base_currency_code()
+            || commission_currency_code == symbol.quote_currency_code()
         {
-            let mut currency_pair =
-                CurrencyPair::from_codes(commission_currency_code, symbol.quote_currency_code());
-            match self.order_book_top.get(&currency_pair) {
-                Some(top_prices) => {
-                    let bid = top_prices
-                        .bid
-                        .as_ref()
-                        .expect("There are no top bid in order book");
-                    let price_bnb_quote = bid.price;
-                    *converted_commission_amount = commission_amount * price_bnb_quote;
-                    *converted_commission_currency_code = symbol.quote_currency_code();
-                }
-                None => {
-                    currency_pair = CurrencyPair::from_codes(
-                        symbol.quote_currency_code(),
-                        commission_currency_code,
-                    );
+            return false;
+        }
 
-                    match self.order_book_top.get(&currency_pair) {
-                        Some(top_prices) => {
-                            let ask = top_prices
-                                .ask
-                                .as_ref()
-                                .expect("There are no top ask in order book");
-                            let price_quote_bnb = ask.price;
-                            *converted_commission_amount = commission_amount / price_quote_bnb;
-                            *converted_commission_currency_code = symbol.quote_currency_code();
-                        }
-                        None => log::error!(
-                            "Top bids and asks for {} and currency pair {:?} do not exist",
-                            self.exchange_account_id,
-                            currency_pair
-                        ),
-                    }
-                }
+        match self.convert_commission_to_quote(
+            commission_currency_code,
+            symbol.quote_currency_code(),
+            commission_amount,
+        ) {
+            Some(amount) => {
+                *converted_commission_amount = amount;
+                *converted_commission_currency_code = symbol.quote_currency_code();
+                false
             }
+            None => true,
         }
     }
 
@@ -478,6 +484,93 @@ impl Exchange {
         );
     }
 
+    fn send_balance_delta_events(
+        &self,
+        symbol: &Symbol,
+        order_ref: &OrderRef,
+        order_fill: &OrderFill,
+    ) {
+        let client_order_id = order_ref.client_order_id();
+        for (currency_code, delta, reason) in
+            Self::get_balance_deltas(symbol, order_ref.side(), order_fill)
+        {
+            self.events_channel
+                .send_expected(ExchangeEvent::BalanceDelta(BalanceDeltaEvent {
+                    exchange_account_id: self.exchange_account_id,
+                    currency_code,
+                    delta,
+                    reason,
+                    client_order_id: client_order_id.clone(),
+                    trade_id: order_fill.trade_id().cloned(),
+                    time: order_fill.receive_time(),
+                }));
+        }
+    }
+
+    /// Spot fill increases balance of bought currency and decreases balance of sold one.
+    /// Derivative fill changes position instead of balances, so only commission is taken into account
+    fn get_balance_deltas(
+        symbol: &Symbol,
+        side: OrderSide,
+        order_fill: &OrderFill,
+    ) -> Vec<(CurrencyCode, Amount, BalanceChangeReason)> {
+        let mut deltas = Vec::with_capacity(3);
+
+        if !symbol.is_derivative() {
+            let (base_delta, quote_delta) = match side {
+                OrderSide::Buy => (order_fill.amount(), -order_fill.cost()),
+                OrderSide::Sell => (-order_fill.amount(), order_fill.cost()),
+            };
+            deltas.push((
+                symbol.base_currency_code(),
+                base_delta,
+                BalanceChangeReason::Fill,
+            ));
+            deltas.push((
+                symbol.quote_currency_code(),
+                quote_delta,
+                BalanceChangeReason::Fill,
+            ));
+        }
+
+        if !order_fill.commission_amount().is_zero() {
+            deltas.push((
+                order_fill.commission_currency_code(),
+                -order_fill.commission_amount(),
+                BalanceChangeReason::Commission,
+            ));
+        }
+
+        deltas
+    }
+
+    /// Filled part of order in currency of order amount. Order with amount in quote currency is considered
+    /// filled when rest of quote amount isn't enough to buy or sell one more amount tick
+    fn get_filled_amount_in_order_units(
+        symbol: &Symbol,
+        order_filled_amount: Amount,
+        last_fill_price: Price,
+        order_ref: &OrderRef,
+    ) -> Amount {
+        match order_ref.amount_kind() {
+            OrderAmountKind::Base => order_filled_amount,
+            OrderAmountKind::Quote => {
+                let filled_cost = order_ref
+                    .fn_ref(|order| order.fills.fills.iter().map(|fill| fill.cost()).sum());
+                let order_amount = order_ref.amount();
+                if last_fill_price.is_zero() {
+                    return filled_cost;
+                }
+
+                let rest_amount = (order_amount - filled_cost) / last_fill_price;
+                match symbol.amount_round(rest_amount, Round::Floor).is_zero() {
+                    true => order_amount,
+                    false => filled_cost,
+                }
+            }
+        }
+    }
+
     fn react_if_order_completed(&self, order_filled_amount: Amount, order_ref: &OrderRef) {
         if order_filled_amount == order_ref.amount() {
             order_ref.fn_mut(|order| {
@@ -511,7 +604,7 @@ impl Exchange {
         converted_commission_amount: Amount,
     ) -> OrderFill {
         let last_fill_amount_in_converted_commission_currency_code = symbol
-            .convert_amount_from_amount_currency_code(
+            .get_amount_value_in_currency(
                 converted_commission_currency_code,
                 last_fill_amount,
                 last_fill_price,
@@ -556,6 +649,24 @@ impl Exchange {
             return;
         }
 
+        let received_fill = event_data.trade_id.as_ref().map(|trade_id| {
+            ReceivedFill::new(
+                order_ref.side(),
+                event_data.exchange_order_id.clone(),
+                trade_id,
+            )
+        });
+        if self.is_fill_received(order_ref.currency_pair(), &received_fill) {
+            log::info!(
+                "Trade {:?} was applied already to order of {} {}, so fill for {:?} is ignored",
+                event_data.trade_id,
+                self.exchange_account_id,
+                order_ref.currency_pair(),
+                order_ref
+            );
+            return;
+        }
+
         if Self::diff_fill_after_non_diff(&event_data, &order_fills, order_ref) {
             return;
         }
@@ -617,7 +728,7 @@ impl Exchange {
         let mut converted_commission_currency_code = commission_currency_code;
         let mut converted_commission_amount = commission_amount;
 
-        self.update_commission_for_bnb_case(
+        let is_conversion_deferred = self.update_commission_for_bnb_case(
             commission_currency_code,
             &symbol,
             commission_amount,
@@ -641,13 +752,32 @@ impl Exchange {
             commission_currency_code,
             converted_commission_amount,
         );
+        if let Some(received_fill) = received_fill {
+            self.received_trades
+                .add(order_ref.currency_pair(), received_fill);
+        }
+        if is_conversion_deferred {
+            self.defer_commission_conversion(DeferredCommissionConversion {
+                client_order_id: order_ref.client_order_id(),
+                fill_id: order_fill.id(),
+                commission_currency_code,
+                commission_amount,
+                quote_currency_code: symbol.quote_currency_code(),
+            });
+        }
 
         // This order fields updated, so let's use actual values
-        let order_filled_amount = order_ref.filled_amount();
+        let order_filled_amount = Self::get_filled_amount_in_order_units(
+            &symbol,
+            order_ref.filled_amount(),
+            last_fill_price,
+            order_ref,
+        );
 
         self.panic_if_fill_amounts_comformity(order_filled_amount, order_ref);
 
         self.send_order_filled_event(&event_data, order_ref, &order_fill);
+        self.send_balance_delta_events(&symbol, order_ref, &order_fill);
 
         if event_data.source_type == EventSourceType::RestFallback {
             // TODO some metrics
@@ -717,6 +847,61 @@ impl Exchange {
         }
     }
 
+    /// Order which is created by engine before restart isn't in orders pool if it was finished
+    /// or wasn't loaded with open orders, so it's restored from fill to attribute fill to it
+    fn restore_order_created_before_restart(&self, event_data: &FillEventData) -> Option<OrderRef> {
+        let client_order_id = self
+            .order_ids
+            .get_client_order_id(&event_data.exchange_order_id)?;
+        if self
+            .orders
+            .cache_by_client_id
+            .contains_key(&client_order_id)
+        {
+            return None;
+        }
+
+        let (currency_pair, order_side) =
+            match (event_data.trade_currency_pair, event_data.order_side) {
+                (Some(currency_pair), Some(order_side)) => (currency_pair, order_side),
+                _ => return None,
+            };
+
+        log::warn!(
+            "Order {} {} created before restart isn't in orders pool, it's restored from fill",
+            client_order_id,
+            event_data.exchange_order_id
+        );
+
+        let order = OrderSnapshot::with_params(
+            client_order_id.clone(),
+            OrderType::Unknown,
+            None,
+            self.exchange_account_id,
+            currency_pair,
+            event_data.fill_price,
+            event_data.order_amount.unwrap_or(event_data.fill_amount),
+            order_side,
+            None,
+            "Order created before restart",
+        );
+        let _ = self
+            .orders
+            .add_snapshot_initial(Arc::new(RwLock::new(order)));
+        self.handle_create_order_succeeded(
+            self.exchange_account_id,
+            &client_order_id,
+            &event_data.exchange_order_id,
+            &event_data.source_type,
+        )
+        .ok()?;
+
+        self.orders
+            .cache_by_exchange_id
+            .get(&event_data.exchange_order_id)
+            .map(|x| x.clone())
+    }
+
     fn create_order_in_pool(&self, event_data: &FillEventData, order_role: OrderRole) -> OrderRef {
         let currency_pair = event_data
             .trade_currency_pair
@@ -732,7 +917,7 @@ impl Exchange {
 
         let client_order_id = ClientOrderId::unique_id();
 
-        let order_instance = OrderSnapshot::with_params(
+        let mut order_instance = OrderSnapshot::with_params(
             client_order_id.clone(),
             OrderType::Liquidation,
             Some(order_role),
@@ -745,6 +930,10 @@ impl Exchange {
             "Unknown order from handle_order_filled()",
         );
 
+        if let Some(position_side) = event_data.position_side {
+            order_instance.header = order_instance.header.with_position_side(position_side);
+        }
+
         self.orders
             .add_snapshot_initial(Arc::new(RwLock::new(order_instance)))
     }
@@ -783,10 +972,8 @@ mod test {
         exchanges::general::exchange::PriceLevel, exchanges::general::test_helper,
         exchanges::general::test_helper::create_order_ref,
         exchanges::general::test_helper::get_test_exchange, orders::fill::OrderFill,
-        orders::order::OrderExecutionType, orders::order::OrderFillRole, orders::order::OrderFills,
-        orders::order::OrderHeader, orders::order::OrderSimpleProps,
-        orders::order::OrderStatusHistory, orders::order::SystemInternalOrderProps,
-        orders::pool::OrdersPool,
+        orders::order::OrderFillRole, orders::pool::OrdersPool, test_util::FillEventDataBuilder,
+        test_util::OrderSnapshotBuilder,
     };
 
     fn trade_id_from_str(str: &str) -> TradeId {
@@ -799,25 +986,10 @@ mod test {
         #[test]
         #[should_panic(expected = "Currency pair should be set for liquidation trad")]
         fn empty_currency_pair() {
-            let event_data = FillEventData {
-                source_type: EventSourceType::WebSocket,
-                trade_id: Some(trade_id_from_str("empty")),
-                client_order_id: None,
-                exchange_order_id: ExchangeOrderId::new("test".into()),
-                fill_price: dec!(0),
-                fill_amount: dec!(0),
-                is_diff: false,
-                total_filled_amount: None,
-                order_role: None,
-                commission_currency_code: None,
-                commission_rate: None,
-                commission_amount: None,
-                fill_type: OrderFillType::Liquidation,
-                trade_currency_pair: None,
-                order_side: None,
-                order_amount: None,
-                fill_date: None,
-            };
+            let event_data = FillEventDataBuilder::new()
+                .trade_id(trade_id_from_str("empty"))
+                .fill_type(OrderFillType::Liquidation)
+                .build();
 
             let (exchange, _) = get_test_exchange(false);
             exchange.handle_order_filled(event_data);
@@ -826,25 +998,11 @@ mod test {
         #[test]
         #[should_panic(expected = "Side should be set for liquidation or close position trade")]
         fn empty_order_side() {
-            let event_data = FillEventData {
-                source_type: EventSourceType::WebSocket,
-                trade_id: Some(trade_id_from_str("empty")),
-                client_order_id: None,
-                exchange_order_id: ExchangeOrderId::new("test".into()),
-                fill_price: dec!(0),
-                fill_amount: dec!(0),
-                is_diff: false,
-                total_filled_amount: None,
-                order_role: None,
-                commission_currency_code: None,
-                commission_rate: None,
-                commission_amount: None,
-                fill_type: OrderFillType::Liquidation,
-                trade_currency_pair: Some(CurrencyPair::from_codes("te".into(), "st".into())),
-                order_side: None,
-                order_amount: None,
-                fill_date: None,
-            };
+            let event_data = FillEventDataBuilder::new()
+                .trade_id(trade_id_from_str("empty"))
+                .fill_type(OrderFillType::Liquidation)
+                .trade_currency_pair(CurrencyPair::from_codes("te".into(), "st".into()))
+                .build();
 
             let (exchange, _) = get_test_exchange(false);
             exchange.handle_order_filled(event_data);
@@ -855,25 +1013,13 @@ mod test {
             expected = "Client order id cannot be set for liquidation or close position trade"
         )]
         fn not_empty_client_order_id() {
-            let event_data = FillEventData {
-                source_type: EventSourceType::WebSocket,
-                trade_id: Some(trade_id_from_str("empty")),
-                client_order_id: Some(ClientOrderId::unique_id()),
-                exchange_order_id: ExchangeOrderId::new("test".into()),
-                fill_price: dec!(0),
-                fill_amount: dec!(0),
-                is_diff: false,
-                total_filled_amount: None,
-                order_role: None,
-                commission_currency_code: None,
-                commission_rate: None,
-                commission_amount: None,
-                fill_type: OrderFillType::Liquidation,
-                trade_currency_pair: Some(CurrencyPair::from_codes("te".into(), "st".into())),
-                order_side: Some(OrderSide::Buy),
-                order_amount: None,
-                fill_date: None,
-            };
+            let event_data = FillEventDataBuilder::new()
+                .trade_id(trade_id_from_str("empty"))
+                .client_order_id(ClientOrderId::unique_id())
+                .fill_type(OrderFillType::Liquidation)
+                .trade_currency_pair(CurrencyPair::from_codes("te".into(), "st".into()))
+                .order_side(OrderSide::Buy)
+                .build();
 
             let (exchange, _) = get_test_exchange(false);
             exchange.handle_order_filled(event_data);
@@ -884,25 +1030,12 @@ mod test {
             expected = "Order amount should be set for liquidation or close position trade"
         )]
         fn not_empty_order_amount() {
-            let event_data = FillEventData {
-                source_type: EventSourceType::WebSocket,
-                trade_id: Some(trade_id_from_str("empty")),
-                client_order_id: None,
-                exchange_order_id: ExchangeOrderId::new("test".into()),
-                fill_price: dec!(0),
-                fill_amount: dec!(0),
-                is_diff: false,
-                total_filled_amount: None,
-                order_role: None,
-                commission_currency_code: None,
-                commission_rate: None,
-                commission_amount: None,
-                fill_type: OrderFillType::Liquidation,
-                trade_currency_pair: Some(CurrencyPair::from_codes("te".into(), "st".into())),
-                order_side: Some(OrderSide::Buy),
-                order_amount: None,
-                fill_date: None,
-            };
+            let event_data = FillEventDataBuilder::new()
+                .trade_id(trade_id_from_str("empty"))
+                .fill_type(OrderFillType::Liquidation)
+                .trade_currency_pair(CurrencyPair::from_codes("te".into(), "st".into()))
+                .order_side(OrderSide::Buy)
+                .build();
 
             let (exchange, _) = get_test_exchange(false);
             exchange.handle_order_filled(event_data);
@@ -913,29 +1046,18 @@ mod test {
             let currency_pair = CurrencyPair::from_codes("PHB".into(), "BTC".into());
             let order_side = OrderSide::Buy;
             let order_amount = dec!(12);
-            let order_role = None;
             let fill_price = dec!(0.2);
             let fill_amount = dec!(5);
 
-            let event_data = FillEventData {
-                source_type: EventSourceType::WebSocket,
-                trade_id: Some(trade_id_from_str("empty")),
-                client_order_id: None,
-                exchange_order_id: ExchangeOrderId::new("test".into()),
-                fill_price,
-                fill_amount,
-                is_diff: false,
-                total_filled_amount: None,
-                order_role,
-                commission_currency_code: None,
-                commission_rate: None,
-                commission_amount: None,
-                fill_type: OrderFillType::Liquidation,
-                trade_currency_pair: Some(currency_pair),
-                order_side: Some(order_side),
-                order_amount: Some(order_amount),
-                fill_date: None,
-            };
+            let event_data = FillEventDataBuilder::new()
+                .trade_id(trade_id_from_str("empty"))
+                .fill_price(fill_price)
+                .fill_amount(fill_amount)
+                .fill_type(OrderFillType::Liquidation)
+                .trade_currency_pair(currency_pair)
+                .order_side(order_side)
+                .order_amount(order_amount)
+                .build();
 
             let (exchange, _event_received) = get_test_exchange(false);
             exchange.handle_order_filled(event_data);
@@ -962,31 +1084,52 @@ mod test {
         #[test]
         #[should_panic(expected = "Received HandleOrderFilled with an empty exchangeOrderId")]
         fn empty_exchange_order_id() {
-            let event_data = FillEventData {
-                source_type: EventSourceType::WebSocket,
-                trade_id: Some(trade_id_from_str("empty")),
-                client_order_id: None,
-                exchange_order_id: ExchangeOrderId::new("".into()),
-                fill_price: dec!(0),
-                fill_amount: dec!(0),
-                is_diff: false,
-                total_filled_amount: None,
-                order_role: None,
-                commission_currency_code: None,
-                commission_rate: None,
-                commission_amount: None,
-                fill_type: OrderFillType::Liquidation,
-                trade_currency_pair: Some(CurrencyPair::from_codes("te".into(), "st".into())),
-                order_side: Some(OrderSide::Buy),
-                order_amount: Some(dec!(0)),
-                fill_date: None,
-            };
+            let event_data = FillEventDataBuilder::new()
+                .trade_id(trade_id_from_str("empty"))
+                .exchange_order_id(ExchangeOrderId::new("".into()))
+                .fill_type(OrderFillType::Liquidation)
+                .trade_currency_pair(CurrencyPair::from_codes("te".into(), "st".into()))
+                .order_side(OrderSide::Buy)
+                .order_amount(dec!(0))
+                .build();
 
             let (exchange, _event_receiver) = get_test_exchange(false);
             exchange.handle_order_filled(event_data);
         }
     }
 
+    #[test]
+    fn fill_of_order_created_before_restart_is_attributed() {
+        let (exchange, _event_receiver) = get_test_exchange(false);
+        let client_order_id = ClientOrderId::new("created_before_restart".into());
+        let exchange_order_id = ExchangeOrderId::new("before_restart".into());
+        exchange
+            .order_ids
+            .add(exchange_order_id.clone(), client_order_id.clone());
+
+        let event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id_from_str("trade_after_restart"))
+            .exchange_order_id(exchange_order_id.clone())
+            .fill_price(dec!(0.2))
+            .fill_amount(dec!(5))
+            .order_role(OrderRole::Maker)
+            .trade_currency_pair(CurrencyPair::from_codes("PHB".into(), "BTC".into()))
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(12))
+            .build();
+        exchange.handle_order_filled(event_data);
+
+        let order = exchange
+            .orders
+            .cache_by_exchange_id
+            .get(&exchange_order_id)
+            .expect("order should be restored")
+            .clone();
+        assert_eq!(order.client_order_id(), client_order_id);
+        assert_eq!(order.amount(), dec!(12));
+        assert_eq!(order.get_fills().1, dec!(5));
+    }
+
     #[test]
     fn ignore_if_trade_was_already_received() {
         let (exchange, _event_receiver) = get_test_exchange(false);
@@ -999,25 +1142,15 @@ mod test {
         let trade_id = trade_id_from_str("test_trade_id");
         let fill_amount = dec!(0.2);
 
-        let mut event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id: Some(trade_id.clone()),
-            client_order_id: None,
-            exchange_order_id: ExchangeOrderId::new("".into()),
-            fill_price: dec!(0),
-            fill_amount,
-            is_diff: false,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: None,
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(CurrencyPair::from_codes("te".into(), "st".into())),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let mut event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id.clone())
+            .exchange_order_id(ExchangeOrderId::new("".into()))
+            .fill_amount(fill_amount)
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(CurrencyPair::from_codes("te".into(), "st".into()))
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         let mut order = OrderSnapshot::with_params(
             client_order_id.clone(),
@@ -1075,25 +1208,16 @@ mod test {
         let order_amount = dec!(1);
         let trade_id = trade_id_from_str("test_trade_id");
 
-        let mut event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id: Some(trade_id.clone()),
-            client_order_id: None,
-            exchange_order_id: ExchangeOrderId::new("".into()),
-            fill_price: dec!(0),
-            fill_amount,
-            is_diff: true,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: None,
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(CurrencyPair::from_codes("te".into(), "st".into())),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let mut event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id.clone())
+            .exchange_order_id(ExchangeOrderId::new("".into()))
+            .fill_amount(fill_amount)
+            .is_diff(true)
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(CurrencyPair::from_codes("te".into(), "st".into()))
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         let mut order = OrderSnapshot::with_params(
             client_order_id.clone(),
@@ -1149,27 +1273,17 @@ mod test {
         let order_price = dec!(1);
         let fill_amount = dec!(0.2);
         let order_amount = dec!(1);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
-
-        let mut event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: ExchangeOrderId::new("".into()),
-            fill_price: dec!(0),
-            fill_amount,
-            is_diff: false,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: None,
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(CurrencyPair::from_codes("te".into(), "st".into())),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let trade_id = trade_id_from_str("test_trade_id");
+
+        let mut event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(ExchangeOrderId::new("".into()))
+            .fill_amount(fill_amount)
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(CurrencyPair::from_codes("te".into(), "st".into()))
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         let mut order = OrderSnapshot::with_params(
             client_order_id.clone(),
@@ -1225,27 +1339,19 @@ mod test {
         let order_price = dec!(1);
         let fill_amount = dec!(0);
         let order_amount = dec!(1);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
-
-        let mut event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: ExchangeOrderId::new("".into()),
-            fill_price: dec!(0.2),
-            fill_amount,
-            is_diff: true,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: None,
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let trade_id = trade_id_from_str("test_trade_id");
+
+        let mut event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(ExchangeOrderId::new("".into()))
+            .fill_price(dec!(0.2))
+            .fill_amount(fill_amount)
+            .is_diff(true)
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         let mut order = OrderSnapshot::with_params(
             client_order_id.clone(),
@@ -1301,27 +1407,19 @@ mod test {
         let order_side = OrderSide::Buy;
         let fill_amount = dec!(1);
         let order_amount = dec!(1);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
-
-        let mut event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: ExchangeOrderId::new("".into()),
-            fill_price: dec!(0.2),
-            fill_amount,
-            is_diff: true,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: None,
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let trade_id = trade_id_from_str("test_trade_id");
+
+        let mut event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(ExchangeOrderId::new("".into()))
+            .fill_price(dec!(0.2))
+            .fill_amount(fill_amount)
+            .is_diff(true)
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         let mut order = OrderSnapshot::with_params(
             client_order_id.clone(),
@@ -1353,27 +1451,19 @@ mod test {
         let order_side = OrderSide::Buy;
         let fill_amount = dec!(1);
         let order_amount = dec!(1);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
-
-        let mut event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: ExchangeOrderId::new("".into()),
-            fill_price: dec!(0.2),
-            fill_amount,
-            is_diff: true,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: None,
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let trade_id = trade_id_from_str("test_trade_id");
+
+        let mut event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(ExchangeOrderId::new("".into()))
+            .fill_price(dec!(0.2))
+            .fill_amount(fill_amount)
+            .is_diff(true)
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         let mut order = OrderSnapshot::with_params(
             client_order_id.clone(),
@@ -1405,28 +1495,20 @@ mod test {
         let order_side = OrderSide::Buy;
         let fill_amount = dec!(1);
         let order_amount = dec!(1);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
+        let trade_id = trade_id_from_str("test_trade_id");
         let fill_price = dec!(0.2);
 
-        let mut event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: ExchangeOrderId::new("".into()),
-            fill_price,
-            fill_amount,
-            is_diff: true,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: None,
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let mut event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(ExchangeOrderId::new("".into()))
+            .fill_price(fill_price)
+            .fill_amount(fill_amount)
+            .is_diff(true)
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         let mut order = OrderSnapshot::with_params(
             client_order_id.clone(),
@@ -1456,89 +1538,47 @@ mod test {
         let currency_pair = CurrencyPair::from_codes("PHB".into(), "BTC".into());
         let fill_amount = dec!(5);
         let order_amount = dec!(12);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
-        let client_order_id = ClientOrderId::unique_id();
+        let trade_id = trade_id_from_str("test_trade_id");
         let order_side = OrderSide::Buy;
         let order_price = dec!(0.2);
         let order_role = OrderRole::Maker;
         let exchange_order_id: ExchangeOrderId = "some_order_id".into();
 
-        // Add order manually for setting custom order.amount
-        let header = OrderHeader::new(
-            client_order_id.clone(),
-            Utc::now(),
-            exchange.exchange_account_id,
-            currency_pair,
-            OrderType::Limit,
-            OrderSide::Buy,
-            order_amount,
-            OrderExecutionType::None,
-            None,
-            None,
-            "FromTest".to_owned(),
-        );
-        let props = OrderSimpleProps::new(
-            Some(order_price),
-            Some(order_role),
-            Some(exchange_order_id.clone()),
-            Default::default(),
-            Default::default(),
-            Default::default(),
-            None,
-        );
-        let order = OrderSnapshot::new(
-            header,
-            props,
-            OrderFills::default(),
-            OrderStatusHistory::default(),
-            SystemInternalOrderProps::default(),
-        );
-
         let order_pool = OrdersPool::new();
-        let order_ref = order_pool.add_snapshot_initial(Arc::new(RwLock::new(order)));
+        let order_ref = OrderSnapshotBuilder::new(exchange.exchange_account_id, currency_pair)
+            .side(OrderSide::Buy)
+            .price(order_price)
+            .amount(order_amount)
+            .role(order_role)
+            .exchange_order_id(exchange_order_id.clone())
+            .build_ref(&order_pool);
         test_helper::try_add_snapshot_by_exchange_id(&exchange, &order_ref);
 
-        let first_event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: exchange_order_id.clone(),
-            fill_price: dec!(0.2),
-            fill_amount,
-            is_diff: false,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: Some(dec!(0.01)),
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(order_side),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let first_event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(exchange_order_id.clone())
+            .fill_price(dec!(0.2))
+            .fill_amount(fill_amount)
+            .commission_amount(dec!(0.01))
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(order_side)
+            .order_amount(dec!(0))
+            .build();
 
         exchange.handle_order_filled(first_event_data);
 
-        let second_event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id: Some(trade_id_from_str("another_trade_id")),
-            client_order_id: None,
-            exchange_order_id: exchange_order_id.clone(),
-            fill_price: dec!(0.3),
-            fill_amount: dec!(10),
-            is_diff: false,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: Some(dec!(0.03)),
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let second_event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id_from_str("another_trade_id"))
+            .exchange_order_id(exchange_order_id.clone())
+            .fill_price(dec!(0.3))
+            .fill_amount(dec!(10))
+            .commission_amount(dec!(0.03))
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         exchange.handle_order_filled(second_event_data);
 
@@ -1568,90 +1608,48 @@ mod test {
         let currency_pair = CurrencyPair::from_codes("PHB".into(), "BTC".into());
         let fill_amount = dec!(5);
         let order_amount = dec!(12);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
-        let client_order_id = ClientOrderId::unique_id();
+        let trade_id = trade_id_from_str("test_trade_id");
         let order_side = OrderSide::Buy;
         let order_price = dec!(0.2);
         let order_role = OrderRole::Maker;
         let exchange_order_id: ExchangeOrderId = "some_order_id".into();
 
-        // Add order manually for setting custom order.amount
-        let header = OrderHeader::new(
-            client_order_id.clone(),
-            Utc::now(),
-            exchange.exchange_account_id,
-            currency_pair,
-            OrderType::Limit,
-            OrderSide::Sell,
-            order_amount,
-            OrderExecutionType::None,
-            None,
-            None,
-            "FromTest".to_owned(),
-        );
-        let props = OrderSimpleProps::new(
-            Some(order_price),
-            Some(order_role),
-            Some(exchange_order_id.clone()),
-            Default::default(),
-            Default::default(),
-            Default::default(),
-            None,
-        );
-        let order = OrderSnapshot::new(
-            header,
-            props,
-            OrderFills::default(),
-            OrderStatusHistory::default(),
-            SystemInternalOrderProps::default(),
-        );
-
         let order_pool = OrdersPool::new();
-        let order_ref = order_pool.add_snapshot_initial(Arc::new(RwLock::new(order)));
+        let order_ref = OrderSnapshotBuilder::new(exchange.exchange_account_id, currency_pair)
+            .side(OrderSide::Sell)
+            .price(order_price)
+            .amount(order_amount)
+            .role(order_role)
+            .exchange_order_id(exchange_order_id.clone())
+            .build_ref(&order_pool);
 
         test_helper::try_add_snapshot_by_exchange_id(&exchange, &order_ref);
 
-        let first_event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: exchange_order_id.clone(),
-            fill_price: dec!(0.2),
-            fill_amount,
-            is_diff: false,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: Some(dec!(0.01)),
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(order_side),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let first_event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(exchange_order_id.clone())
+            .fill_price(dec!(0.2))
+            .fill_amount(fill_amount)
+            .commission_amount(dec!(0.01))
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(order_side)
+            .order_amount(dec!(0))
+            .build();
 
         exchange.handle_order_filled(first_event_data);
 
-        let second_event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id: Some(trade_id_from_str("another_trade_id")),
-            client_order_id: None,
-            exchange_order_id: exchange_order_id.clone(),
-            fill_price: dec!(0.3),
-            fill_amount: dec!(10),
-            is_diff: false,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: Some(dec!(0.03)),
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let second_event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id_from_str("another_trade_id"))
+            .exchange_order_id(exchange_order_id.clone())
+            .fill_price(dec!(0.3))
+            .fill_amount(dec!(10))
+            .commission_amount(dec!(0.03))
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         exchange.handle_order_filled(second_event_data);
 
@@ -1680,89 +1678,47 @@ mod test {
         let currency_pair = CurrencyPair::from_codes("PHB".into(), "BTC".into());
         let fill_amount = dec!(5);
         let order_amount = dec!(12);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
-        let client_order_id = ClientOrderId::unique_id();
+        let trade_id = trade_id_from_str("test_trade_id");
         let order_side = OrderSide::Buy;
         let order_price = dec!(0.2);
         let order_role = OrderRole::Maker;
         let exchange_order_id: ExchangeOrderId = "some_order_id".into();
 
-        // Add order manually for setting custom order.amount
-        let header = OrderHeader::new(
-            client_order_id.clone(),
-            Utc::now(),
-            exchange.exchange_account_id,
-            currency_pair,
-            OrderType::Limit,
-            OrderSide::Buy,
-            order_amount,
-            OrderExecutionType::None,
-            None,
-            None,
-            "FromTest".to_owned(),
-        );
-        let props = OrderSimpleProps::new(
-            Some(order_price),
-            Some(order_role),
-            Some(exchange_order_id.clone()),
-            Default::default(),
-            Default::default(),
-            Default::default(),
-            None,
-        );
-        let order = OrderSnapshot::new(
-            header,
-            props,
-            OrderFills::default(),
-            OrderStatusHistory::default(),
-            SystemInternalOrderProps::default(),
-        );
-
         let order_pool = OrdersPool::new();
-        let order_ref = order_pool.add_snapshot_initial(Arc::new(RwLock::new(order)));
+        let order_ref = OrderSnapshotBuilder::new(exchange.exchange_account_id, currency_pair)
+            .side(OrderSide::Buy)
+            .price(order_price)
+            .amount(order_amount)
+            .role(order_role)
+            .exchange_order_id(exchange_order_id.clone())
+            .build_ref(&order_pool);
         test_helper::try_add_snapshot_by_exchange_id(&exchange, &order_ref);
 
-        let first_event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: exchange_order_id.clone(),
-            fill_price: dec!(2000),
-            fill_amount,
-            is_diff: false,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: Some(dec!(0.01)),
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(order_side),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let first_event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(exchange_order_id.clone())
+            .fill_price(dec!(2000))
+            .fill_amount(fill_amount)
+            .commission_amount(dec!(0.01))
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(order_side)
+            .order_amount(dec!(0))
+            .build();
 
         exchange.handle_order_filled(first_event_data);
 
-        let second_event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id: Some(trade_id_from_str("another_trade_id")),
-            client_order_id: None,
-            exchange_order_id: exchange_order_id.clone(),
-            fill_price: dec!(3000),
-            fill_amount: dec!(10),
-            is_diff: false,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: Some(dec!(0.03)),
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let second_event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id_from_str("another_trade_id"))
+            .exchange_order_id(exchange_order_id.clone())
+            .fill_price(dec!(3000))
+            .fill_amount(dec!(10))
+            .commission_amount(dec!(0.03))
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         exchange.handle_order_filled(second_event_data);
 
@@ -1797,89 +1753,47 @@ mod test {
         let currency_pair = CurrencyPair::from_codes("PHB".into(), "BTC".into());
         let fill_amount = dec!(5);
         let order_amount = dec!(12);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
-        let client_order_id = ClientOrderId::unique_id();
+        let trade_id = trade_id_from_str("test_trade_id");
         let order_side = OrderSide::Buy;
         let order_price = dec!(0.2);
         let order_role = OrderRole::Maker;
         let exchange_order_id: ExchangeOrderId = "some_order_id".into();
 
-        // Add order manually for setting custom order.amount
-        let header = OrderHeader::new(
-            client_order_id.clone(),
-            Utc::now(),
-            exchange.exchange_account_id,
-            currency_pair,
-            OrderType::Limit,
-            OrderSide::Sell,
-            order_amount,
-            OrderExecutionType::None,
-            None,
-            None,
-            "FromTest".to_owned(),
-        );
-        let props = OrderSimpleProps::new(
-            Some(order_price),
-            Some(order_role),
-            Some(exchange_order_id.clone()),
-            Default::default(),
-            Default::default(),
-            Default::default(),
-            None,
-        );
-        let order = OrderSnapshot::new(
-            header,
-            props,
-            OrderFills::default(),
-            OrderStatusHistory::default(),
-            SystemInternalOrderProps::default(),
-        );
-
         let order_pool = OrdersPool::new();
-        let order_ref = order_pool.add_snapshot_initial(Arc::new(RwLock::new(order)));
+        let order_ref = OrderSnapshotBuilder::new(exchange.exchange_account_id, currency_pair)
+            .side(OrderSide::Sell)
+            .price(order_price)
+            .amount(order_amount)
+            .role(order_role)
+            .exchange_order_id(exchange_order_id.clone())
+            .build_ref(&order_pool);
         test_helper::try_add_snapshot_by_exchange_id(&exchange, &order_ref);
 
-        let first_event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: exchange_order_id.clone(),
-            fill_price: dec!(2000),
-            fill_amount,
-            is_diff: false,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: Some(dec!(0.01)),
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(order_side),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let first_event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(exchange_order_id.clone())
+            .fill_price(dec!(2000))
+            .fill_amount(fill_amount)
+            .commission_amount(dec!(0.01))
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(order_side)
+            .order_amount(dec!(0))
+            .build();
 
         exchange.handle_order_filled(first_event_data);
 
-        let second_event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id: Some(trade_id_from_str("another_trade_id")),
-            client_order_id: None,
-            exchange_order_id: exchange_order_id.clone(),
-            fill_price: dec!(3000),
-            fill_amount: dec!(10),
-            is_diff: false,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: Some(dec!(0.03)),
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let second_event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id_from_str("another_trade_id"))
+            .exchange_order_id(exchange_order_id.clone())
+            .fill_price(dec!(3000))
+            .fill_amount(dec!(10))
+            .commission_amount(dec!(0.03))
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         exchange.handle_order_filled(second_event_data);
 
@@ -1912,89 +1826,47 @@ mod test {
         let currency_pair = CurrencyPair::from_codes("PHB".into(), "BTC".into());
         let fill_amount = dec!(5);
         let order_amount = dec!(12);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
-        let client_order_id = ClientOrderId::unique_id();
+        let trade_id = trade_id_from_str("test_trade_id");
         let order_side = OrderSide::Buy;
         let order_price = dec!(0.2);
         let order_role = OrderRole::Maker;
         let exchange_order_id: ExchangeOrderId = "some_order_id".into();
 
-        // Add order manually for setting custom order.amount
-        let header = OrderHeader::new(
-            client_order_id.clone(),
-            Utc::now(),
-            exchange.exchange_account_id,
-            currency_pair,
-            OrderType::Limit,
-            OrderSide::Sell,
-            order_amount,
-            OrderExecutionType::None,
-            None,
-            None,
-            "FromTest".to_owned(),
-        );
-        let props = OrderSimpleProps::new(
-            Some(order_price),
-            Some(order_role),
-            Some(exchange_order_id.clone()),
-            Default::default(),
-            Default::default(),
-            Default::default(),
-            None,
-        );
-        let order = OrderSnapshot::new(
-            header,
-            props,
-            OrderFills::default(),
-            OrderStatusHistory::default(),
-            SystemInternalOrderProps::default(),
-        );
-
         let order_pool = OrdersPool::new();
-        let order_ref = order_pool.add_snapshot_initial(Arc::new(RwLock::new(order)));
+        let order_ref = OrderSnapshotBuilder::new(exchange.exchange_account_id, currency_pair)
+            .side(OrderSide::Sell)
+            .price(order_price)
+            .amount(order_amount)
+            .role(order_role)
+            .exchange_order_id(exchange_order_id.clone())
+            .build_ref(&order_pool);
         test_helper::try_add_snapshot_by_exchange_id(&exchange, &order_ref);
 
-        let first_event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: exchange_order_id.clone(),
-            fill_price: dec!(0.8),
-            fill_amount,
-            is_diff: false,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: Some(dec!(0.01)),
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(order_side),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let first_event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(exchange_order_id.clone())
+            .fill_price(dec!(0.8))
+            .fill_amount(fill_amount)
+            .commission_amount(dec!(0.01))
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(order_side)
+            .order_amount(dec!(0))
+            .build();
 
         exchange.handle_order_filled(first_event_data);
 
-        let second_event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id: Some(trade_id_from_str("another_trade_id")),
-            client_order_id: None,
-            exchange_order_id: exchange_order_id.clone(),
-            fill_price: dec!(0.3),
-            fill_amount: dec!(10),
-            is_diff: false,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: Some(dec!(0.03)),
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let second_event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id_from_str("another_trade_id"))
+            .exchange_order_id(exchange_order_id.clone())
+            .fill_price(dec!(0.3))
+            .fill_amount(dec!(10))
+            .commission_amount(dec!(0.03))
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         exchange.handle_order_filled(second_event_data);
 
@@ -2017,27 +1889,20 @@ mod test {
         let order_side = OrderSide::Buy;
         let fill_amount = dec!(5);
         let order_amount = dec!(1);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
-
-        let mut event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: ExchangeOrderId::new("".into()),
-            fill_price: dec!(0.8),
-            fill_amount,
-            is_diff: true,
-            total_filled_amount: Some(dec!(9)),
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: None,
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let trade_id = trade_id_from_str("test_trade_id");
+
+        let mut event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(ExchangeOrderId::new("".into()))
+            .fill_price(dec!(0.8))
+            .fill_amount(fill_amount)
+            .is_diff(true)
+            .total_filled_amount(dec!(9))
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         let mut order = OrderSnapshot::with_params(
             client_order_id.clone(),
@@ -2071,27 +1936,20 @@ mod test {
         let order_side = OrderSide::Buy;
         let fill_amount = dec!(5);
         let order_amount = dec!(12);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
-
-        let mut event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: ExchangeOrderId::new("".into()),
-            fill_price: dec!(0.8),
-            fill_amount,
-            is_diff: true,
-            total_filled_amount: None,
-            order_role: Some(OrderRole::Taker),
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: None,
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let trade_id = trade_id_from_str("test_trade_id");
+
+        let mut event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(ExchangeOrderId::new("".into()))
+            .fill_price(dec!(0.8))
+            .fill_amount(fill_amount)
+            .is_diff(true)
+            .order_role(OrderRole::Taker)
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         let mut order = OrderSnapshot::with_params(
             client_order_id.clone(),
@@ -2129,27 +1987,19 @@ mod test {
         let order_side = OrderSide::Buy;
         let fill_amount = dec!(5);
         let order_amount = dec!(12);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
-
-        let mut event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: ExchangeOrderId::new("".into()),
-            fill_price: dec!(0.8),
-            fill_amount,
-            is_diff: true,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: None,
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let trade_id = trade_id_from_str("test_trade_id");
+
+        let mut event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(ExchangeOrderId::new("".into()))
+            .fill_price(dec!(0.8))
+            .fill_amount(fill_amount)
+            .is_diff(true)
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         let mut order = OrderSnapshot::with_params(
             client_order_id.clone(),
@@ -2188,27 +2038,19 @@ mod test {
         let order_side = OrderSide::Buy;
         let fill_amount = dec!(5);
         let order_amount = dec!(12);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
-
-        let mut event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: ExchangeOrderId::new("".into()),
-            fill_price: dec!(0.8),
-            fill_amount,
-            is_diff: true,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: None,
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let trade_id = trade_id_from_str("test_trade_id");
+
+        let mut event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(ExchangeOrderId::new("".into()))
+            .fill_price(dec!(0.8))
+            .fill_amount(fill_amount)
+            .is_diff(true)
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         let mut order = OrderSnapshot::with_params(
             client_order_id.clone(),
@@ -2239,28 +2081,21 @@ mod test {
         let order_side = OrderSide::Buy;
         let fill_amount = dec!(5);
         let order_amount = dec!(12);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
+        let trade_id = trade_id_from_str("test_trade_id");
         let commission_currency_code = CurrencyCode::new("BTC".into());
 
-        let mut event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: ExchangeOrderId::new("".into()),
-            fill_price: dec!(0.8),
-            fill_amount,
-            is_diff: true,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: Some(commission_currency_code),
-            commission_rate: None,
-            commission_amount: None,
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let mut event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(ExchangeOrderId::new("".into()))
+            .fill_price(dec!(0.8))
+            .fill_amount(fill_amount)
+            .is_diff(true)
+            .commission_currency_code(commission_currency_code)
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         let mut order = OrderSnapshot::with_params(
             client_order_id.clone(),
@@ -2299,28 +2134,20 @@ mod test {
         let order_side = OrderSide::Buy;
         let fill_amount = dec!(5);
         let order_amount = dec!(12);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
+        let trade_id = trade_id_from_str("test_trade_id");
         let base_currency_code = CurrencyCode::new("PHB".into());
 
-        let mut event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: ExchangeOrderId::new("".into()),
-            fill_price: dec!(0.8),
-            fill_amount,
-            is_diff: true,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: None,
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let mut event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(ExchangeOrderId::new("".into()))
+            .fill_price(dec!(0.8))
+            .fill_amount(fill_amount)
+            .is_diff(true)
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         let mut order = OrderSnapshot::with_params(
             client_order_id.clone(),
@@ -2360,27 +2187,19 @@ mod test {
         let order_side = OrderSide::Sell;
         let fill_amount = dec!(5);
         let order_amount = dec!(12);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
-
-        let mut event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: ExchangeOrderId::new("".into()),
-            fill_price: dec!(0.8),
-            fill_amount,
-            is_diff: true,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: None,
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let trade_id = trade_id_from_str("test_trade_id");
+
+        let mut event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(ExchangeOrderId::new("".into()))
+            .fill_price(dec!(0.8))
+            .fill_amount(fill_amount)
+            .is_diff(true)
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         let mut order = OrderSnapshot::with_params(
             client_order_id.clone(),
@@ -2428,28 +2247,21 @@ mod test {
         let order_side = OrderSide::Sell;
         let fill_amount = dec!(5);
         let order_amount = dec!(12);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
+        let trade_id = trade_id_from_str("test_trade_id");
         let commission_amount = dec!(0.001);
 
-        let mut event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: ExchangeOrderId::new("".into()),
-            fill_price: dec!(0.8),
-            fill_amount,
-            is_diff: true,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: Some(commission_amount),
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let mut event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(ExchangeOrderId::new("".into()))
+            .fill_price(dec!(0.8))
+            .fill_amount(fill_amount)
+            .is_diff(true)
+            .commission_amount(commission_amount)
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         let mut order = OrderSnapshot::with_params(
             client_order_id.clone(),
@@ -2487,28 +2299,22 @@ mod test {
         let fill_price = dec!(0.8);
         let fill_amount = dec!(5);
         let order_amount = dec!(12);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
+        let trade_id = trade_id_from_str("test_trade_id");
         let commission_rate = dec!(0.3) / dec!(100);
 
-        let mut event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: ExchangeOrderId::new("".into()),
-            fill_price: fill_price.clone(),
-            fill_amount,
-            is_diff: true,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: Some("BTC".into()),
-            commission_rate: Some(commission_rate),
-            commission_amount: None,
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let mut event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(ExchangeOrderId::new("".into()))
+            .fill_price(fill_price.clone())
+            .fill_amount(fill_amount)
+            .is_diff(true)
+            .commission_currency_code("BTC".into())
+            .commission_rate(commission_rate)
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         let mut order = OrderSnapshot::with_params(
             client_order_id.clone(),
@@ -2546,27 +2352,20 @@ mod test {
         let fill_price = dec!(0.8);
         let fill_amount = dec!(5);
         let order_amount = dec!(12);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
-
-        let mut event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: ExchangeOrderId::new("".into()),
-            fill_price: fill_price.clone(),
-            fill_amount,
-            is_diff: true,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: Some("BTC".into()),
-            commission_rate: None,
-            commission_amount: None,
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let trade_id = trade_id_from_str("test_trade_id");
+
+        let mut event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(ExchangeOrderId::new("".into()))
+            .fill_price(fill_price.clone())
+            .fill_amount(fill_amount)
+            .is_diff(true)
+            .commission_currency_code("BTC".into())
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         let mut order = OrderSnapshot::with_params(
             client_order_id.clone(),
@@ -2604,27 +2403,19 @@ mod test {
         let fill_price = dec!(0.8);
         let fill_amount = dec!(5);
         let order_amount = dec!(12);
-        let trade_id = Some(trade_id_from_str("test_trade_id"));
-
-        let mut event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id,
-            client_order_id: None,
-            exchange_order_id: ExchangeOrderId::new("".into()),
-            fill_price: fill_price.clone(),
-            fill_amount,
-            is_diff: true,
-            total_filled_amount: None,
-            order_role: None,
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: None,
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(OrderSide::Buy),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let trade_id = trade_id_from_str("test_trade_id");
+
+        let mut event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id)
+            .exchange_order_id(ExchangeOrderId::new("".into()))
+            .fill_price(fill_price.clone())
+            .fill_amount(fill_amount)
+            .is_diff(true)
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(OrderSide::Buy)
+            .order_amount(dec!(0))
+            .build();
 
         let mut order = OrderSnapshot::with_params(
             client_order_id.clone(),
@@ -2945,6 +2736,88 @@ mod test {
         }
     }
 
+    mod get_balance_deltas {
+        use super::*;
+
+        fn order_fill(commission_amount: Amount) -> OrderFill {
+            OrderFill::new(
+                Uuid::new_v4(),
+                None,
+                Utc::now(),
+                OrderFillType::UserTrade,
+                Some(trade_id_from_str("test trade_id")),
+                dec!(0.8),
+                dec!(5),
+                dec!(4),
+                OrderFillRole::Taker,
+                CurrencyCode::new("PHB".into()),
+                commission_amount,
+                dec!(0),
+                CurrencyCode::new("PHB".into()),
+                commission_amount,
+                commission_amount,
+                true,
+                None,
+                None,
+            )
+        }
+
+        #[test]
+        fn spot_buy() -> Result<()> {
+            let (exchange, _event_receiver) = get_test_exchange(false);
+            let currency_pair = CurrencyPair::from_codes("PHB".into(), "BTC".into());
+            let symbol = exchange.get_symbol(currency_pair)?;
+
+            let deltas =
+                Exchange::get_balance_deltas(&symbol, OrderSide::Buy, &order_fill(dec!(0.005)));
+
+            assert_eq!(
+                deltas,
+                vec![
+                    ("PHB".into(), dec!(5), BalanceChangeReason::Fill),
+                    ("BTC".into(), dec!(-4), BalanceChangeReason::Fill),
+                    ("PHB".into(), dec!(-0.005), BalanceChangeReason::Commission),
+                ]
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn spot_sell_without_commission() -> Result<()> {
+            let (exchange, _event_receiver) = get_test_exchange(false);
+            let currency_pair = CurrencyPair::from_codes("PHB".into(), "BTC".into());
+            let symbol = exchange.get_symbol(currency_pair)?;
+
+            let deltas =
+                Exchange::get_balance_deltas(&symbol, OrderSide::Sell, &order_fill(dec!(0)));
+
+            assert_eq!(
+                deltas,
+                vec![
+                    ("PHB".into(), dec!(-5), BalanceChangeReason::Fill),
+                    ("BTC".into(), dec!(4), BalanceChangeReason::Fill),
+                ]
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn derivative_only_commission() -> Result<()> {
+            let (exchange, _event_receiver) = get_test_exchange(true);
+            let currency_pair = CurrencyPair::from_codes("PHB".into(), "BTC".into());
+            let symbol = exchange.get_symbol(currency_pair)?;
+
+            let deltas =
+                Exchange::get_balance_deltas(&symbol, OrderSide::Buy, &order_fill(dec!(0.005)));
+
+            assert_eq!(
+                deltas,
+                vec![("PHB".into(), dec!(-0.005), BalanceChangeReason::Commission)]
+            );
+            Ok(())
+        }
+    }
+
     mod react_if_order_completed {
         use super::*;
         use crate::exchanges::events::ExchangeEvent;
@@ -3011,6 +2884,75 @@ mod test {
 
             assert_ne!(order_status, OrderStatus::Completed);
         }
+
+        #[test]
+        fn quote_amount_order_filled_up_to_amount_tick() {
+            use crate::test_util::SymbolBuilder;
+
+            let symbol = Arc::new(
+                SymbolBuilder::new("PHB".into(), "BTC".into())
+                    .amount_tick(dec!(0.01))
+                    .build(),
+            );
+            let (exchange, _event_receiver) =
+                test_helper::get_test_exchange_with_symbol(symbol.clone());
+
+            let fill_price = dec!(10);
+            let order_ref = create_order_ref(
+                &ClientOrderId::unique_id(),
+                Some(OrderRole::Taker),
+                exchange.exchange_account_id,
+                symbol.currency_pair(),
+                fill_price,
+                dec!(100),
+                OrderSide::Buy,
+            );
+            order_ref.fn_mut(|order| {
+                order.header = order
+                    .header
+                    .clone()
+                    .with_amount_kind(OrderAmountKind::Quote)
+            });
+
+            let add_fill = |amount: Amount| {
+                let fill = OrderFill::new(
+                    Uuid::new_v4(),
+                    None,
+                    Utc::now(),
+                    OrderFillType::UserTrade,
+                    None,
+                    fill_price,
+                    amount,
+                    amount * fill_price,
+                    OrderFillRole::Taker,
+                    CurrencyCode::new("PHB".into()),
+                    dec!(0),
+                    dec!(0),
+                    CurrencyCode::new("PHB".into()),
+                    dec!(0),
+                    dec!(0),
+                    true,
+                    None,
+                    None,
+                );
+                order_ref.fn_mut(|order| order.add_fill(fill.clone()));
+            };
+            let filled_amount = || {
+                Exchange::get_filled_amount_in_order_units(
+                    &symbol,
+                    order_ref.filled_amount(),
+                    fill_price,
+                    &order_ref,
+                )
+            };
+
+            add_fill(dec!(5));
+            assert_eq!(filled_amount(), dec!(50));
+
+            // Rest 0.05 of quote amount isn't enough to buy one amount tick
+            add_fill(dec!(4.995));
+            assert_eq!(filled_amount(), dec!(100));
+        }
     }
 
     mod update_commission_for_bnb_case {
@@ -3119,7 +3061,7 @@ mod test {
             let mut converted_commission_amount = dec!(3);
             let mut converted_commission_currency_code = CurrencyCode::new("BTC".into());
 
-            exchange.update_commission_for_bnb_case(
+            let is_conversion_deferred = exchange.update_commission_for_bnb_case(
                 commission_currency_code,
                 &symbol,
                 commission_amount,
@@ -3127,12 +3069,88 @@ mod test {
                 &mut converted_commission_currency_code,
             );
 
+            assert!(is_conversion_deferred);
+
             let right_amount = dec!(3);
             assert_eq!(converted_commission_amount, right_amount);
 
             let right_currency_code = CurrencyCode::new("BTC".into());
             assert_eq!(converted_commission_currency_code, right_currency_code);
         }
+
+        #[test]
+        fn deferred_conversion_on_order_book_top_arrival() {
+            let (exchange, mut event_receiver) = get_test_exchange(false);
+
+            let commission_currency_code = CurrencyCode::new("BNB".into());
+            let currency_pair = CurrencyPair::from_codes("PHB".into(), "BTC".into());
+            let order = OrderSnapshot::with_params(
+                ClientOrderId::unique_id(),
+                OrderType::Limit,
+                Some(OrderRole::Maker),
+                exchange.exchange_account_id,
+                currency_pair,
+                dec!(0.8),
+                dec!(12),
+                OrderSide::Buy,
+                None,
+                "FromTest",
+            );
+            let order_ref = exchange
+                .orders
+                .add_snapshot_initial(Arc::new(RwLock::new(order)));
+
+            let mut event_data = FillEventDataBuilder::new()
+                .trade_id(trade_id_from_str("test_trade_id"))
+                .exchange_order_id(ExchangeOrderId::new("".into()))
+                .fill_price(dec!(0.8))
+                .fill_amount(dec!(5))
+                .is_diff(true)
+                .order_role(OrderRole::Maker)
+                .commission_currency_code(commission_currency_code)
+                .commission_amount(dec!(15))
+                .fill_type(OrderFillType::UserTrade)
+                .build();
+
+            exchange.create_and_add_order_fill(&mut event_data, &order_ref);
+
+            let (fills, _) = order_ref.get_fills();
+            assert_eq!(
+                fills[0].converted_commission_currency_code(),
+                commission_currency_code
+            );
+            assert_eq!(exchange.deferred_commission_conversions_count(), 1);
+
+            let commission_pair = CurrencyPair::from_codes(commission_currency_code, "BTC".into());
+            let order_book_top = OrderBookTop {
+                ask: None,
+                bid: Some(PriceLevel {
+                    price: dec!(0.3),
+                    amount: dec!(0.1),
+                }),
+            };
+            exchange
+                .order_book_top
+                .insert(commission_pair, order_book_top);
+            exchange.convert_deferred_commissions(commission_pair);
+
+            let (fills, _) = order_ref.get_fills();
+            assert_eq!(
+                fills[0].converted_commission_currency_code(),
+                CurrencyCode::new("BTC".into())
+            );
+            assert_eq!(fills[0].converted_commission_amount(), dec!(4.5));
+            assert_eq!(exchange.deferred_commission_conversions_count(), 0);
+
+            let mut is_correction_sent = false;
+            while let Ok(event) = event_receiver.try_recv() {
+                if let ExchangeEvent::CommissionCorrection(event) = event {
+                    assert_eq!(event.converted_commission_amount, dec!(4.5));
+                    is_correction_sent = true;
+                }
+            }
+            assert!(is_correction_sent);
+        }
     }
 
     #[test]
@@ -3163,25 +3181,19 @@ mod test {
         let order_pool = OrdersPool::new();
         let order_ref = order_pool.add_snapshot_initial(Arc::new(RwLock::new(order)));
 
-        let mut event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id: Some(trade_id_from_str("first_trade_id")),
-            client_order_id: Some(client_account_id.clone()),
-            exchange_order_id: exchange_order_id.clone(),
-            fill_price,
-            fill_amount: dec!(5),
-            is_diff: true,
-            total_filled_amount: None,
-            order_role: Some(OrderRole::Maker),
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: None,
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(order_side),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let mut event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id_from_str("first_trade_id"))
+            .client_order_id(client_account_id.clone())
+            .exchange_order_id(exchange_order_id.clone())
+            .fill_price(fill_price)
+            .fill_amount(dec!(5))
+            .is_diff(true)
+            .order_role(OrderRole::Maker)
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(order_side)
+            .order_amount(dec!(0))
+            .build();
 
         exchange.create_and_add_order_fill(&mut event_data, &order_ref);
 
@@ -3190,25 +3202,19 @@ mod test {
         let current_right_filled_amount = dec!(5);
         assert_eq!(filled_amount, current_right_filled_amount);
 
-        let mut second_event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id: Some(trade_id_from_str("second_trade_id")),
-            client_order_id: Some(client_account_id.clone()),
-            exchange_order_id: exchange_order_id.clone(),
-            fill_price,
-            fill_amount: dec!(2),
-            is_diff: true,
-            total_filled_amount: None,
-            order_role: Some(OrderRole::Maker),
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: None,
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(order_side),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let mut second_event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id_from_str("second_trade_id"))
+            .client_order_id(client_account_id.clone())
+            .exchange_order_id(exchange_order_id.clone())
+            .fill_price(fill_price)
+            .fill_amount(dec!(2))
+            .is_diff(true)
+            .order_role(OrderRole::Maker)
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(order_side)
+            .order_amount(dec!(0))
+            .build();
 
         exchange.create_and_add_order_fill(&mut second_event_data, &order_ref);
 
@@ -3217,25 +3223,19 @@ mod test {
         let right_filled_amount = dec!(7);
         assert_eq!(filled_amount, right_filled_amount);
 
-        let mut second_event_data = FillEventData {
-            source_type: EventSourceType::WebSocket,
-            trade_id: Some(trade_id_from_str("third_trade_id")),
-            client_order_id: Some(client_account_id.clone()),
-            exchange_order_id: exchange_order_id.clone(),
-            fill_price,
-            fill_amount: dec!(5),
-            is_diff: true,
-            total_filled_amount: None,
-            order_role: Some(OrderRole::Maker),
-            commission_currency_code: None,
-            commission_rate: None,
-            commission_amount: None,
-            fill_type: OrderFillType::Liquidation,
-            trade_currency_pair: Some(currency_pair),
-            order_side: Some(order_side),
-            order_amount: Some(dec!(0)),
-            fill_date: None,
-        };
+        let mut second_event_data = FillEventDataBuilder::new()
+            .trade_id(trade_id_from_str("third_trade_id"))
+            .client_order_id(client_account_id.clone())
+            .exchange_order_id(exchange_order_id.clone())
+            .fill_price(fill_price)
+            .fill_amount(dec!(5))
+            .is_diff(true)
+            .order_role(OrderRole::Maker)
+            .fill_type(OrderFillType::Liquidation)
+            .trade_currency_pair(currency_pair)
+            .order_side(order_side)
+            .order_amount(dec!(0))
+            .build();
 
         exchange.create_and_add_order_fill(&mut second_event_data, &order_ref);
 
@@ -0,0 +1,90 @@
+use std::sync::atomic::Ordering;
+
+use super::exchange::Exchange;
+use super::market_data_only::TradingDisabledError;
+
+impl Exchange {
+    pub fn set_awaiting_handover(&self, is_awaiting: bool) {
+        let was_awaiting = self
+            .is_awaiting_handover
+            .swap(is_awaiting, Ordering::SeqCst);
+        if was_awaiting == is_awaiting {
+            return;
+        }
+
+        match is_awaiting {
+            true => log::info!(
+                "Trading is suspended on {} until orders are handed over",
+                self.exchange_account_id
+            ),
+            false => log::info!(
+                "Trading is resumed on {} after handover of orders",
+                self.exchange_account_id
+            ),
+        }
+    }
+
+    /// Orders of account are taken over by another engine, so it stays in market data only mode
+    pub fn set_state_handed_over(&self) {
+        if !self.is_state_handed_over.swap(true, Ordering::SeqCst) {
+            log::info!(
+                "Orders of {} are handed over, trading is stopped",
+                self.exchange_account_id
+            );
+        }
+    }
+
+    /// Another engine failed to take over orders of account, so it trades them again
+    pub fn reset_state_handed_over(&self) {
+        if self.is_state_handed_over.swap(false, Ordering::SeqCst) {
+            log::info!(
+                "Handover of orders of {} is cancelled, trading is resumed",
+                self.exchange_account_id
+            );
+        }
+    }
+
+    /// Orders of account which handed over its state belong to another engine which took them
+    /// over, so they are kept open and this engine neither cancels nor probes them
+    pub fn are_orders_handed_over(&self) -> bool {
+        self.is_state_handed_over.load(Ordering::SeqCst)
+    }
+
+    /// Fails if orders of account are handed over to another engine
+    pub(crate) fn check_orders_not_handed_over(&self) -> Result<(), TradingDisabledError> {
+        match self.are_orders_handed_over() {
+            true => Err(TradingDisabledError {
+                exchange_account_id: self.exchange_account_id,
+            }),
+            false => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exchanges::general::test_helper::get_test_exchange;
+
+    #[tokio::test]
+    async fn trading_is_enabled_after_handover_is_cancelled() {
+        let (exchange, _rx) = get_test_exchange(false);
+
+        exchange.set_state_handed_over();
+        assert!(exchange.check_trading_enabled().is_err());
+
+        exchange.reset_state_handed_over();
+        assert!(exchange.check_trading_enabled().is_ok());
+    }
+
+    #[tokio::test]
+    async fn orders_of_market_data_only_account_are_managed_until_handover() {
+        let (exchange, _rx) = get_test_exchange(false);
+
+        exchange.set_awaiting_handover(true);
+        assert!(exchange.is_market_data_only());
+        assert!(exchange.check_orders_not_handed_over().is_ok());
+
+        exchange.set_state_handed_over();
+        assert!(exchange.check_orders_not_handed_over().is_err());
+    }
+}
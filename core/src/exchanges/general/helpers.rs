@@ -121,7 +121,9 @@ fn get_rest_error_main(
     write!(&mut msg, " {}", log_template).expect("Writing rest error");
 
     let log_level = match error.error_type {
-        RateLimit | Authentication | InsufficientFunds | InvalidOrder => log::Level::Error,
+        RateLimit | Authentication | InsufficientFunds | InvalidOrder | TradingHalted => {
+            log::Level::Error
+        }
         _ => log::Level::Warn,
     };
 
@@ -176,6 +178,7 @@ fn clarify_error_type(error: &mut ExchangeError) {
         | "Precision is over the maximum defined for this asset." => {
             ExchangeErrorType::InvalidOrder
         }
+        "Market is closed." | "Symbol is closed." => ExchangeErrorType::TradingHalted,
         msg if msg.contains("Too many requests;") => ExchangeErrorType::RateLimit,
         _ => ExchangeErrorType::Unknown,
     };
@@ -0,0 +1,231 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use futures::FutureExt;
+use mmb_utils::DateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::block_reasons;
+use crate::exchanges::exchange_blocker::{BlockType, ExchangeBlocker};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::helpers::get_rest_error;
+use crate::misc::time::time_manager;
+use crate::services::scheduler::{Schedule, Scheduler};
+
+/// How long before scheduled maintenance exchange account is blocked
+const MAINTENANCE_BLOCK_ADVANCE: Duration = Duration::from_secs(5 * 60);
+
+/// Maintenance announced by exchange or specified in settings. Times are in UTC
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ScheduledMaintenance {
+    pub start: DateTime,
+    pub end: DateTime,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SystemStatus {
+    /// Exchange is under maintenance right now
+    pub is_maintenance: bool,
+    pub scheduled_maintenances: Vec<ScheduledMaintenance>,
+}
+
+impl SystemStatus {
+    /// Trading should be stopped during maintenance and shortly before it's started
+    pub fn should_block(&self, now: DateTime) -> bool {
+        let advance = chrono::Duration::from_std(MAINTENANCE_BLOCK_ADVANCE)
+            .expect("MAINTENANCE_BLOCK_ADVANCE should be valid chrono::Duration");
+
+        self.is_maintenance
+            || self
+                .scheduled_maintenances
+                .iter()
+                .any(|maintenance| maintenance.start - advance <= now && now < maintenance.end)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MaintenanceBlockChange {
+    Block,
+    Unblock,
+}
+
+/// Block is lifted only by received system status, because maintenance can still go on
+/// while exchange doesn't respond
+fn get_maintenance_block_change(
+    should_block: bool,
+    is_blocked: bool,
+    is_status_received: bool,
+) -> Option<MaintenanceBlockChange> {
+    match (should_block, is_blocked) {
+        (true, false) => Some(MaintenanceBlockChange::Block),
+        (false, true) if is_status_received => Some(MaintenanceBlockChange::Unblock),
+        _ => None,
+    }
+}
+
+impl Exchange {
+    pub async fn get_system_status(&self) -> Result<SystemStatus> {
+        let response = self.exchange_client.request_system_status().await?;
+        if let Some(error) = get_rest_error(&response, self.exchange_account_id, false) {
+            bail!(
+                "Unable to get system status of {}: {:?}",
+                self.exchange_account_id,
+                error
+            );
+        }
+
+        self.exchange_client.parse_system_status(&response)
+    }
+
+    /// Periodically poll system status of exchange and block exchange account during maintenance
+    pub(crate) fn schedule_maintenance_checking(
+        self: &Arc<Self>,
+        scheduler: &Arc<Scheduler>,
+        exchange_blocker: Arc<ExchangeBlocker>,
+        period: Duration,
+    ) {
+        let scheduled_maintenances = self
+            .exchange_client
+            .get_settings()
+            .scheduled_maintenances
+            .clone()
+            .unwrap_or_default();
+
+        let exchange_weak = Arc::downgrade(self);
+        let check_maintenance = move |_| {
+            let exchange_weak = exchange_weak.clone();
+            let exchange_blocker = exchange_blocker.clone();
+            let scheduled_maintenances = scheduled_maintenances.clone();
+            async move {
+                let exchange = match exchange_weak.upgrade() {
+                    Some(exchange) => exchange,
+                    None => return,
+                };
+
+                let (mut system_status, is_status_received) =
+                    match exchange.get_system_status().await {
+                        Ok(system_status) => (system_status, true),
+                        Err(error) => {
+                            log::warn!(
+                                "Unable to check maintenance of {}: {:?}",
+                                exchange.exchange_account_id,
+                                error
+                            );
+                            // Scheduled maintenances from settings are applied anyway
+                            (SystemStatus::default(), false)
+                        }
+                    };
+                system_status
+                    .scheduled_maintenances
+                    .extend(scheduled_maintenances);
+
+                exchange.update_maintenance_block(
+                    &exchange_blocker,
+                    &system_status,
+                    is_status_received,
+                );
+            }
+            .boxed()
+        };
+
+        let _ = scheduler.schedule(
+            &format!("Check maintenance of {}", self.exchange_account_id),
+            Schedule::Every(period),
+            check_maintenance,
+        );
+    }
+
+    fn update_maintenance_block(
+        &self,
+        exchange_blocker: &Arc<ExchangeBlocker>,
+        system_status: &SystemStatus,
+        is_status_received: bool,
+    ) {
+        let should_block = system_status.should_block(time_manager::now());
+        let is_blocked = exchange_blocker.is_blocked_by_reason(
+            self.exchange_account_id,
+            block_reasons::EXCHANGE_MAINTENANCE,
+        );
+
+        if !should_block && is_blocked && !is_status_received {
+            log::warn!(
+                "Exchange account {} stays blocked because its system status is unknown",
+                self.exchange_account_id
+            );
+        }
+
+        match get_maintenance_block_change(should_block, is_blocked, is_status_received) {
+            Some(MaintenanceBlockChange::Block) => {
+                log::warn!(
+                    "Exchange account {} is blocked because of maintenance: {:?}",
+                    self.exchange_account_id,
+                    system_status
+                );
+                exchange_blocker.block(
+                    self.exchange_account_id,
+                    block_reasons::EXCHANGE_MAINTENANCE,
+                    BlockType::Manual,
+                );
+            }
+            Some(MaintenanceBlockChange::Unblock) => {
+                log::info!(
+                    "Exchange account {} is unblocked after maintenance",
+                    self.exchange_account_id
+                );
+                exchange_blocker.unblock(
+                    self.exchange_account_id,
+                    block_reasons::EXCHANGE_MAINTENANCE,
+                );
+            }
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    #[test]
+    fn block_before_scheduled_maintenance() {
+        let system_status = SystemStatus {
+            is_maintenance: false,
+            scheduled_maintenances: vec![ScheduledMaintenance {
+                start: Utc.ymd(2022, 3, 1).and_hms(12, 0, 0),
+                end: Utc.ymd(2022, 3, 1).and_hms(14, 0, 0),
+            }],
+        };
+
+        assert!(!system_status.should_block(Utc.ymd(2022, 3, 1).and_hms(11, 50, 0)));
+        assert!(system_status.should_block(Utc.ymd(2022, 3, 1).and_hms(11, 56, 0)));
+        assert!(system_status.should_block(Utc.ymd(2022, 3, 1).and_hms(13, 0, 0)));
+        assert!(!system_status.should_block(Utc.ymd(2022, 3, 1).and_hms(14, 0, 0)));
+    }
+
+    #[test]
+    fn block_during_current_maintenance() {
+        let system_status = SystemStatus {
+            is_maintenance: true,
+            scheduled_maintenances: vec![],
+        };
+
+        assert!(system_status.should_block(Utc::now()));
+    }
+
+    #[test]
+    fn block_is_kept_if_system_status_is_unknown() {
+        assert_eq!(get_maintenance_block_change(false, true, false), None);
+        assert_eq!(
+            get_maintenance_block_change(false, true, true),
+            Some(MaintenanceBlockChange::Unblock)
+        );
+        assert_eq!(
+            get_maintenance_block_change(true, false, false),
+            Some(MaintenanceBlockChange::Block)
+        );
+        assert_eq!(get_maintenance_block_change(true, true, true), None);
+    }
+}
@@ -0,0 +1,289 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use futures::FutureExt;
+use mmb_utils::cancellation_token::CancellationToken;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::common::Amount;
+use crate::exchanges::events::{ExchangeEvent, MarginRatioEvent};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::helpers::get_rest_error;
+use crate::misc::human_duration::HumanDuration;
+use crate::services::scheduler::{Schedule, Scheduler};
+
+/// Account-level margin of derivative account in margin currency
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarginInfo {
+    /// Wallet balance with unrealized profit and loss of positions
+    pub margin_balance: Amount,
+    /// Margin which is required to keep positions open
+    pub maintenance_margin: Amount,
+}
+
+impl MarginInfo {
+    /// Ratio of maintenance margin to margin balance. Exchange starts liquidation at 1
+    pub fn margin_ratio(&self) -> Decimal {
+        if self.maintenance_margin.is_zero() {
+            return dec!(0);
+        }
+
+        if self.margin_balance <= dec!(0) {
+            return Decimal::MAX;
+        }
+
+        self.maintenance_margin / self.margin_balance
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarginLevel {
+    Normal,
+    /// Margin ratio exceeds warning threshold
+    Warning,
+    /// Margin ratio exceeds deleveraging threshold, positions are closed by engine
+    Deleverage,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MarginMonitoringSettings {
+    /// Period of margin checking, e.g. `10s`
+    pub check_period: HumanDuration,
+    /// Margin ratio from which `MarginLevel::Warning` is reported
+    pub warning_ratio: Decimal,
+    /// Margin ratio from which positions are closed before liquidation by exchange.
+    /// Automatic deleveraging is disabled if it isn't specified
+    pub deleverage_ratio: Option<Decimal>,
+}
+
+impl MarginMonitoringSettings {
+    pub fn get_level(&self, margin_ratio: Decimal) -> MarginLevel {
+        match self.deleverage_ratio {
+            Some(deleverage_ratio) if margin_ratio >= deleverage_ratio => MarginLevel::Deleverage,
+            _ if margin_ratio >= self.warning_ratio => MarginLevel::Warning,
+            _ => MarginLevel::Normal,
+        }
+    }
+}
+
+impl Exchange {
+    pub async fn get_margin_info(&self) -> Result<MarginInfo> {
+        let response = self.exchange_client.request_margin_info().await?;
+        if let Some(error) = get_rest_error(&response, self.exchange_account_id, false) {
+            bail!(
+                "Unable to get margin info of {}: {:?}",
+                self.exchange_account_id,
+                error
+            );
+        }
+
+        let margin_info = self.exchange_client.parse_margin_info(&response)?;
+        *self.margin_info.lock() = Some(margin_info.clone());
+
+        Ok(margin_info)
+    }
+
+    /// Margin info received by the latest margin monitoring check
+    pub fn last_margin_info(&self) -> Option<MarginInfo> {
+        self.margin_info.lock().clone()
+    }
+
+    /// Periodically poll account margin, raise `MarginRatio` events and close positions
+    /// if margin ratio is close to liquidation
+    pub(crate) fn schedule_margin_monitoring(
+        self: &Arc<Self>,
+        scheduler: &Arc<Scheduler>,
+        settings: MarginMonitoringSettings,
+    ) {
+        let exchange_weak = Arc::downgrade(self);
+        let check_margin = move |cancellation_token| {
+            let exchange_weak = exchange_weak.clone();
+            let settings = settings.clone();
+            async move {
+                let exchange = match exchange_weak.upgrade() {
+                    Some(exchange) => exchange,
+                    None => return,
+                };
+
+                exchange.check_margin(&settings, cancellation_token).await;
+            }
+            .boxed()
+        };
+
+        let _ = scheduler.schedule(
+            &format!("Check margin of {}", self.exchange_account_id),
+            Schedule::Every(settings.check_period.duration()),
+            check_margin,
+        );
+    }
+
+    async fn check_margin(
+        &self,
+        settings: &MarginMonitoringSettings,
+        cancellation_token: CancellationToken,
+    ) {
+        let margin_info = match self.get_margin_info().await {
+            Ok(margin_info) => margin_info,
+            Err(error) => {
+                log::warn!(
+                    "Unable to check margin of {}: {:?}",
+                    self.exchange_account_id,
+                    error
+                );
+                return;
+            }
+        };
+
+        let margin_ratio = margin_info.margin_ratio();
+        let level = settings.get_level(margin_ratio);
+        match level {
+            MarginLevel::Normal => {}
+            MarginLevel::Warning => log::warn!(
+                "Margin ratio {} of {} exceeds warning threshold {}",
+                margin_ratio,
+                self.exchange_account_id,
+                settings.warning_ratio
+            ),
+            MarginLevel::Deleverage => log::error!(
+                "Margin ratio {} of {} exceeds deleveraging threshold {:?}",
+                margin_ratio,
+                self.exchange_account_id,
+                settings.deleverage_ratio
+            ),
+        }
+
+        let _ = self
+            .events_channel
+            .send(ExchangeEvent::MarginRatio(MarginRatioEvent {
+                exchange_account_id: self.exchange_account_id,
+                margin_info,
+                margin_ratio,
+                level,
+            }));
+
+        if level == MarginLevel::Deleverage {
+            if let Err(error) = self.deleverage(settings, cancellation_token).await {
+                log::error!(
+                    "Deleveraging of {} failed: {:?}",
+                    self.exchange_account_id,
+                    error
+                );
+            }
+        }
+    }
+
+    /// Closes positions one by one until margin ratio goes below deleveraging threshold.
+    /// Position which isn't closed doesn't stop deleveraging, but it is reported in error
+    async fn deleverage(
+        &self,
+        settings: &MarginMonitoringSettings,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        let positions = self
+            .get_active_positions_by_features()
+            .await
+            .with_context(|| {
+                format!(
+                    "Unable to get positions of {} for deleveraging",
+                    self.exchange_account_id
+                )
+            })?;
+
+        let mut failed_positions = Vec::new();
+        for position in positions {
+            if cancellation_token.is_cancellation_requested() {
+                break;
+            }
+
+            log::warn!(
+                "Closing position {:?} of {} for deleveraging",
+                position.derivative,
+                self.exchange_account_id
+            );
+            if let Err(error) = self
+                .close_position_loop(&position, None, cancellation_token.clone())
+                .await
+            {
+                log::error!(
+                    "Unable to close position {} of {} for deleveraging: {:?}",
+                    position.id,
+                    self.exchange_account_id,
+                    error
+                );
+                failed_positions.push(position.id.to_string());
+                continue;
+            }
+
+            match self.get_margin_info().await {
+                Ok(margin_info)
+                    if settings.get_level(margin_info.margin_ratio())
+                        != MarginLevel::Deleverage =>
+                {
+                    log::info!(
+                        "Deleveraging of {} is finished with margin ratio {}",
+                        self.exchange_account_id,
+                        margin_info.margin_ratio()
+                    );
+                    break;
+                }
+                Ok(_) => {}
+                Err(error) => log::warn!(
+                    "Unable to check margin of {} during deleveraging: {:?}",
+                    self.exchange_account_id,
+                    error
+                ),
+            }
+        }
+
+        if !failed_positions.is_empty() {
+            bail!(
+                "Positions {} of {} aren't closed",
+                failed_positions.join(", "),
+                self.exchange_account_id
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn margin_ratio() {
+        let margin_info = |margin_balance, maintenance_margin| MarginInfo {
+            margin_balance,
+            maintenance_margin,
+        };
+
+        assert_eq!(
+            margin_info(dec!(1000), dec!(250)).margin_ratio(),
+            dec!(0.25)
+        );
+        assert_eq!(margin_info(dec!(1000), dec!(0)).margin_ratio(), dec!(0));
+        assert_eq!(
+            margin_info(dec!(-10), dec!(250)).margin_ratio(),
+            Decimal::MAX
+        );
+    }
+
+    #[test]
+    fn margin_level_by_thresholds() {
+        let mut settings = MarginMonitoringSettings {
+            check_period: HumanDuration::from_secs(10),
+            warning_ratio: dec!(0.5),
+            deleverage_ratio: Some(dec!(0.8)),
+        };
+
+        assert_eq!(settings.get_level(dec!(0.3)), MarginLevel::Normal);
+        assert_eq!(settings.get_level(dec!(0.5)), MarginLevel::Warning);
+        assert_eq!(settings.get_level(dec!(0.9)), MarginLevel::Deleverage);
+
+        settings.deleverage_ratio = None;
+        assert_eq!(settings.get_level(dec!(0.9)), MarginLevel::Warning);
+    }
+}
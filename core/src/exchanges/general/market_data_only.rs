@@ -0,0 +1,109 @@
+use std::sync::atomic::Ordering;
+
+use thiserror::Error;
+
+use crate::exchanges::common::ExchangeAccountId;
+
+use super::exchange::Exchange;
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("Trading is disabled on {exchange_account_id} because it's in market data only mode")]
+pub struct TradingDisabledError {
+    pub exchange_account_id: ExchangeAccountId,
+}
+
+impl Exchange {
+    /// Exchange account in market data only mode receives market data and balances,
+    /// but doesn't place or cancel orders. Account is also in this mode while it's awaiting handover
+    /// of orders from another engine and after its orders are handed over to another engine
+    pub fn is_market_data_only(&self) -> bool {
+        self.is_awaiting_handover.load(Ordering::SeqCst)
+            || self.is_state_handed_over.load(Ordering::SeqCst)
+            || self
+                .exchange_client
+                .get_settings()
+                .is_market_data_only
+                .unwrap_or(false)
+    }
+
+    pub(crate) fn check_trading_enabled(&self) -> Result<(), TradingDisabledError> {
+        match self.is_market_data_only() {
+            true => Err(TradingDisabledError {
+                exchange_account_id: self.exchange_account_id,
+            }),
+            false => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use mmb_utils::cancellation_token::CancellationToken;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::error::{MmbError, MmbResult, RiskError};
+    use crate::exchanges::common::{ActivePosition, CurrencyPair};
+    use crate::exchanges::general::order::close_position::SmartCloseSettings;
+    use crate::exchanges::general::test_helper::get_test_exchange;
+    use crate::misc::derivative_position::DerivativePosition;
+
+    fn position(currency_pair: CurrencyPair) -> ActivePosition {
+        ActivePosition::new(DerivativePosition::new(
+            currency_pair,
+            dec!(1),
+            None,
+            None,
+            dec!(20000),
+            dec!(0),
+            dec!(1),
+        ))
+    }
+
+    fn is_trading_disabled<T>(result: MmbResult<T>) -> bool {
+        matches!(
+            result,
+            Err(MmbError::Risk {
+                error: RiskError::TradingDisabled(_),
+                ..
+            })
+        )
+    }
+
+    // Test client panics on any request, so positions are closed without requests to exchange
+    #[tokio::test]
+    async fn positions_are_not_closed_in_market_data_only_mode() {
+        let (exchange, _rx) = get_test_exchange(false);
+        let currency_pair = *exchange.symbols.iter().next().expect("in test").key();
+        let position = position(currency_pair);
+        exchange.set_state_handed_over();
+
+        let result = exchange.close_position(&position, None).await;
+        assert!(is_trading_disabled(result));
+
+        let result = exchange
+            .close_position_loop(&position, None, CancellationToken::default())
+            .await;
+        assert!(result
+            .expect_err("in test")
+            .downcast_ref::<TradingDisabledError>()
+            .is_some());
+
+        let result = exchange
+            .clone()
+            .close_position_smart(
+                &position,
+                &SmartCloseSettings {
+                    max_slippage: dec!(0.005),
+                    max_child_amount: None,
+                    order_book_depth: 20,
+                    limit_order_timeout: Duration::from_secs(1),
+                },
+                CancellationToken::default(),
+            )
+            .await;
+        assert!(is_trading_disabled(result));
+    }
+}
@@ -0,0 +1,202 @@
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use futures::future::join_all;
+use itertools::Itertools;
+use mmb_utils::cancellation_token::CancellationToken;
+
+use crate::exchanges::common::CurrencyPair;
+use crate::exchanges::general::exchange::Exchange;
+use crate::storage::Storage;
+
+/// Namespace of storage with markets disabled by operator by exchange account id
+const DISABLED_MARKETS_NAMESPACE: &str = "disabled_markets";
+
+impl Exchange {
+    /// Market is disabled by operator: strategies don't trade it and new orders are rejected
+    pub fn is_market_disabled(&self, currency_pair: CurrencyPair) -> bool {
+        self.disabled_markets.contains_key(&currency_pair)
+    }
+
+    pub fn disabled_markets(&self) -> Vec<CurrencyPair> {
+        self.disabled_markets
+            .iter()
+            .map(|x| *x.key())
+            .sorted_by_key(|x| x.to_string())
+            .collect_vec()
+    }
+
+    /// Disables trading on market and cancels open orders of engine on it. Override is saved to
+    /// storage, so market stays disabled after restart. Returns count of cancelled orders.
+    /// Cancellation is stopped after `cancel_timeout`, but market stays disabled
+    pub async fn disable_market(
+        &self,
+        currency_pair: CurrencyPair,
+        storage: &dyn Storage,
+        cancel_timeout: Duration,
+    ) -> Result<usize> {
+        let _ = self.get_symbol(currency_pair)?;
+
+        if self.disabled_markets.insert(currency_pair, ()).is_none() {
+            log::warn!(
+                "Trading is disabled for {} on {} by operator",
+                currency_pair,
+                self.exchange_account_id
+            );
+        }
+        self.save_disabled_markets(storage).await?;
+
+        let orders = self
+            .orders
+            .not_finished
+            .iter()
+            .filter(|order| order.currency_pair() == currency_pair)
+            .map(|order| order.value().clone())
+            .collect_vec();
+        let cancellation_token = CancellationToken::default();
+        let cancellations = orders.iter().map(|order| {
+            self.wait_cancel_order_confirmed(order.clone(), None, true, cancellation_token.clone())
+        });
+        let results = tokio::select! {
+            results = join_all(cancellations) => results,
+            _ = tokio::time::sleep(cancel_timeout) => {
+                cancellation_token.cancel();
+                bail!(
+                    "Market {} on {} is disabled, but its orders aren't cancelled in {:?}",
+                    currency_pair,
+                    self.exchange_account_id,
+                    cancel_timeout
+                );
+            }
+        };
+        let failed_count = results
+            .into_iter()
+            .zip(&orders)
+            .filter_map(|(result, order)| result.err().map(|error| (order, error)))
+            .inspect(|(order, error)| {
+                log::error!(
+                    "Unable to cancel order {} of disabled market: {:?}",
+                    order.client_order_id(),
+                    error
+                )
+            })
+            .count();
+
+        Ok(orders.len() - failed_count)
+    }
+
+    /// Returns trading on market disabled by `disable_market`
+    pub async fn enable_market(
+        &self,
+        currency_pair: CurrencyPair,
+        storage: &dyn Storage,
+    ) -> Result<()> {
+        let _ = self.get_symbol(currency_pair)?;
+
+        if self.disabled_markets.remove(&currency_pair).is_some() {
+            log::info!(
+                "Trading is enabled for {} on {} by operator",
+                currency_pair,
+                self.exchange_account_id
+            );
+        }
+        self.save_disabled_markets(storage).await
+    }
+
+    pub async fn restore_disabled_markets(&self, storage: &dyn Storage) -> Result<()> {
+        let currency_pairs: Vec<CurrencyPair> = storage
+            .get_deserialized(
+                DISABLED_MARKETS_NAMESPACE,
+                &self.exchange_account_id.to_string(),
+            )
+            .await?
+            .unwrap_or_default();
+
+        for currency_pair in currency_pairs {
+            log::warn!(
+                "Trading for {} on {} is disabled by operator before restart",
+                currency_pair,
+                self.exchange_account_id
+            );
+            let _ = self.disabled_markets.insert(currency_pair, ());
+        }
+
+        Ok(())
+    }
+
+    async fn save_disabled_markets(&self, storage: &dyn Storage) -> Result<()> {
+        let currency_pairs = self.disabled_markets();
+        storage
+            .put_serialized(
+                DISABLED_MARKETS_NAMESPACE,
+                &self.exchange_account_id.to_string(),
+                &currency_pairs,
+            )
+            .await
+            .with_context(|| {
+                format!(
+                    "Unable to save disabled markets of {}",
+                    self.exchange_account_id
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::test_helper::get_test_exchange;
+    use crate::storage::memory::MemoryStorage;
+
+    const CANCEL_TIMEOUT: Duration = Duration::from_secs(1);
+
+    #[tokio::test]
+    async fn disabled_markets_are_restored_from_storage() {
+        let storage = MemoryStorage::default();
+        let (exchange, _rx) = get_test_exchange(false);
+        let currency_pair = CurrencyPair::from_codes("phb".into(), "btc".into());
+
+        let cancelled_count = exchange
+            .disable_market(currency_pair, &storage, CANCEL_TIMEOUT)
+            .await
+            .expect("in test");
+
+        assert_eq!(cancelled_count, 0);
+        assert!(exchange.is_market_disabled(currency_pair));
+        assert_eq!(exchange.disabled_markets(), vec![currency_pair]);
+
+        let (restarted_exchange, _rx) = get_test_exchange(false);
+        restarted_exchange
+            .restore_disabled_markets(&storage)
+            .await
+            .expect("in test");
+        assert!(restarted_exchange.is_market_disabled(currency_pair));
+
+        exchange
+            .enable_market(currency_pair, &storage)
+            .await
+            .expect("in test");
+        assert!(!exchange.is_market_disabled(currency_pair));
+
+        let (restarted_exchange, _rx) = get_test_exchange(false);
+        restarted_exchange
+            .restore_disabled_markets(&storage)
+            .await
+            .expect("in test");
+        assert!(!restarted_exchange.is_market_disabled(currency_pair));
+    }
+
+    #[tokio::test]
+    async fn unknown_market_is_not_disabled() {
+        let storage = MemoryStorage::default();
+        let (exchange, _rx) = get_test_exchange(false);
+        let currency_pair = CurrencyPair::from_codes("eth".into(), "btc".into());
+
+        assert!(exchange
+            .disable_market(currency_pair, &storage, CANCEL_TIMEOUT)
+            .await
+            .is_err());
+        assert!(!exchange.is_market_disabled(currency_pair));
+        assert!(exchange.disabled_markets().is_empty());
+    }
+}
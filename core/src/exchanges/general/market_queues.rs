@@ -0,0 +1,319 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use dashmap::DashMap;
+use futures::future::join_all;
+use futures::FutureExt;
+use itertools::Itertools;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::{oneshot, Notify};
+
+use crate::exchanges::common::{CurrencyPair, ExchangeAccountId, MarketAccountId};
+use crate::infrastructure::{spawn_supervised, RestartPolicy};
+
+/// Default queue depth after which market is logged as overloaded
+pub const MARKET_EVENTS_WARNING_DEPTH: usize = 1000;
+
+/// Panicked event is lost, so worker is restarted without delay to process the next events
+const WORKER_RESTART_POLICY: RestartPolicy = RestartPolicy {
+    max_restarts: 5,
+    window: Duration::from_secs(60),
+    restart_delay: Duration::ZERO,
+};
+
+pub type MarketEventAction = Box<dyn FnOnce() + Send>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MarketQueueDepth {
+    pub market_account_id: MarketAccountId,
+    /// Count of events waiting for processing
+    pub depth: usize,
+    pub max_depth: usize,
+}
+
+#[derive(Default)]
+struct MarketQueue {
+    events: Mutex<VecDeque<MarketEventAction>>,
+    /// Notifies worker about new events
+    event_added: Notify,
+    max_depth: AtomicUsize,
+    is_closed: AtomicBool,
+}
+
+impl MarketQueue {
+    /// Returns depth of queue after adding of event
+    fn push(&self, action: MarketEventAction) -> usize {
+        let mut events = self.events.lock();
+        events.push_back(action);
+        let depth = events.len();
+        drop(events);
+
+        let _ = self.max_depth.fetch_max(depth, Ordering::AcqRel);
+        self.event_added.notify_one();
+
+        depth
+    }
+
+    /// Returns `None` when queue is closed and all events are taken
+    async fn pop(&self) -> Option<MarketEventAction> {
+        loop {
+            if let Some(action) = self.events.lock().pop_front() {
+                return Some(action);
+            }
+
+            if self.is_closed.load(Ordering::Acquire) {
+                return None;
+            }
+
+            self.event_added.notified().await;
+        }
+    }
+
+    fn close(&self) {
+        self.is_closed.store(true, Ordering::Release);
+        self.event_added.notify_one();
+    }
+}
+
+/// Processing of order events partitioned by markets. Every market has own worker,
+/// so burst of fills on one market doesn't delay order management on other markets.
+/// Events of the same market are processed sequentially in order of arrival.
+/// If handler of event panics, worker is restarted and continues with the next event.
+/// Queues are unbounded, because order events can't be lost and websocket can't wait
+pub struct MarketEventQueues {
+    exchange_account_id: ExchangeAccountId,
+    queues: DashMap<CurrencyPair, Arc<MarketQueue>>,
+    warning_depth: AtomicUsize,
+}
+
+impl MarketEventQueues {
+    pub fn new(exchange_account_id: ExchangeAccountId) -> Self {
+        Self {
+            exchange_account_id,
+            queues: DashMap::new(),
+            warning_depth: AtomicUsize::new(MARKET_EVENTS_WARNING_DEPTH),
+        }
+    }
+
+    /// Depth of queue after which market is logged as overloaded
+    pub fn set_warning_depth(&self, warning_depth: usize) {
+        self.warning_depth.store(warning_depth, Ordering::Release);
+    }
+
+    pub fn enqueue(&self, currency_pair: CurrencyPair, action: MarketEventAction) {
+        let queue = self
+            .queues
+            .entry(currency_pair)
+            .or_insert_with(|| self.start_worker(currency_pair))
+            .clone();
+
+        let depth = queue.push(action);
+        if depth == self.warning_depth.load(Ordering::Acquire) {
+            log::warn!(
+                "Order events queue of {:?} reached {} events",
+                MarketAccountId::new(self.exchange_account_id, currency_pair),
+                depth
+            );
+        }
+    }
+
+    /// Waits until events which were enqueued before the call are processed on all markets
+    pub async fn wait_processed(&self) {
+        let currency_pairs = self.queues.iter().map(|queue| *queue.key()).collect_vec();
+        let receivers = currency_pairs
+            .into_iter()
+            .map(|currency_pair| {
+                let (tx, rx) = oneshot::channel();
+                self.enqueue(
+                    currency_pair,
+                    Box::new(move || {
+                        let _ = tx.send(());
+                    }),
+                );
+                rx
+            })
+            .collect_vec();
+
+        let _ = join_all(receivers).await;
+    }
+
+    pub fn depths(&self) -> Vec<MarketQueueDepth> {
+        self.queues
+            .iter()
+            .map(|queue| MarketQueueDepth {
+                market_account_id: MarketAccountId::new(self.exchange_account_id, *queue.key()),
+                depth: queue.events.lock().len(),
+                max_depth: queue.max_depth.load(Ordering::Acquire),
+            })
+            .collect()
+    }
+
+    fn start_worker(&self, currency_pair: CurrencyPair) -> Arc<MarketQueue> {
+        let queue = Arc::new(MarketQueue::default());
+
+        let worker_queue = queue.clone();
+        let action_factory = move || {
+            let worker_queue = worker_queue.clone();
+            async move {
+                while let Some(action) = worker_queue.pop().await {
+                    action();
+                }
+
+                Ok(())
+            }
+            .boxed()
+        };
+        let _ = spawn_supervised(
+            &format!(
+                "Order events queue of {} {}",
+                self.exchange_account_id, currency_pair
+            ),
+            WORKER_RESTART_POLICY,
+            action_factory,
+        );
+
+        queue
+    }
+}
+
+impl Drop for MarketEventQueues {
+    /// Workers process remaining events and stop
+    fn drop(&mut self) {
+        for queue in self.queues.iter() {
+            queue.close();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::timeout;
+
+    use super::*;
+    use crate::infrastructure::init_lifetime_manager;
+
+    fn create_queues() -> MarketEventQueues {
+        let _ = init_lifetime_manager();
+        MarketEventQueues::new(ExchangeAccountId::new("Binance".into(), 0))
+    }
+
+    #[tokio::test]
+    async fn events_of_market_are_processed_in_order() {
+        let queues = create_queues();
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        let processed = Arc::new(Mutex::new(Vec::new()));
+
+        for i in 0..10 {
+            let processed = processed.clone();
+            queues.enqueue(currency_pair, Box::new(move || processed.lock().push(i)));
+        }
+
+        let (tx, rx) = oneshot::channel();
+        queues.enqueue(
+            currency_pair,
+            Box::new(move || {
+                let _ = tx.send(());
+            }),
+        );
+        rx.await.expect("in test");
+
+        assert_eq!(*processed.lock(), (0..10).collect::<Vec<_>>());
+
+        let depths = queues.depths();
+        assert_eq!(depths.len(), 1);
+        assert_eq!(depths[0].depth, 0);
+        assert!(depths[0].max_depth > 0);
+    }
+
+    #[tokio::test]
+    async fn wait_processed_waits_events_of_all_markets() {
+        let queues = create_queues();
+        let processed = Arc::new(Mutex::new(Vec::new()));
+
+        for (i, base) in ["btc", "eth", "bnb"].iter().enumerate() {
+            let currency_pair = CurrencyPair::from_codes((*base).into(), "usdt".into());
+            let processed = processed.clone();
+            queues.enqueue(currency_pair, Box::new(move || processed.lock().push(i)));
+        }
+
+        queues.wait_processed().await;
+
+        assert_eq!(processed.lock().len(), 3);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn blocked_market_does_not_delay_other_markets() {
+        let queues = create_queues();
+        let hot_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        let other_pair = CurrencyPair::from_codes("eth".into(), "usdt".into());
+
+        let (unblock_tx, unblock_rx) = std::sync::mpsc::channel::<()>();
+        queues.enqueue(
+            hot_pair,
+            Box::new(move || {
+                let _ = unblock_rx.recv();
+            }),
+        );
+
+        let (tx, rx) = oneshot::channel();
+        queues.enqueue(
+            other_pair,
+            Box::new(move || {
+                let _ = tx.send(());
+            }),
+        );
+        rx.await.expect("in test");
+
+        unblock_tx.send(()).expect("in test");
+    }
+
+    #[tokio::test]
+    async fn worker_continues_processing_after_panic_in_handler() {
+        let queues = create_queues();
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        let processed = Arc::new(Mutex::new(Vec::new()));
+
+        for i in 0..3 {
+            let processed = processed.clone();
+            queues.enqueue(
+                currency_pair,
+                Box::new(move || {
+                    if i == 1 {
+                        panic!("test panic in handler");
+                    }
+                    processed.lock().push(i);
+                }),
+            );
+        }
+
+        timeout(Duration::from_secs(5), queues.wait_processed())
+            .await
+            .expect("worker should be restarted after panic");
+
+        assert_eq!(*processed.lock(), vec![0, 2]);
+    }
+
+    #[tokio::test]
+    async fn events_are_not_dropped_after_warning_depth() {
+        let queues = create_queues();
+        queues.set_warning_depth(3);
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        let processed = Arc::new(Mutex::new(Vec::new()));
+
+        // Worker doesn't take events until test awaits
+        for i in 0..5 {
+            let processed = processed.clone();
+            queues.enqueue(currency_pair, Box::new(move || processed.lock().push(i)));
+        }
+
+        assert_eq!(queues.depths()[0].depth, 5);
+
+        queues.wait_processed().await;
+
+        assert_eq!(*processed.lock(), (0..5).collect::<Vec<_>>());
+        assert_eq!(queues.depths()[0].max_depth, 6);
+    }
+}
@@ -1,16 +1,33 @@
 pub mod commission;
+pub mod commission_conversion;
 pub mod currency_pair_to_symbol_converter;
+pub mod dust_conversion;
 pub mod engine_api;
 pub mod exchange;
 pub mod exchange_creation;
 pub mod exchange_symbol;
 pub mod features;
+pub mod handover_state;
 pub mod handlers;
 pub mod helpers;
+pub mod maintenance;
+pub mod margin;
+pub mod market_data_only;
+pub mod market_overrides;
+pub mod market_queues;
 pub mod order;
+pub mod order_book_polling;
+pub mod order_ids;
+pub mod pagination;
 pub mod polling_timeout_manager;
+pub mod received_trades;
 pub mod request_type;
+pub mod sub_account;
 pub mod symbol;
+pub mod trade_gaps;
+pub mod trading_halt;
+pub mod trading_windows;
+pub mod withdrawal;
 
 #[cfg(test)]
 pub mod test_helper;
@@ -0,0 +1,146 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use parking_lot::RwLock;
+
+use crate::exchanges::general::exchange::Exchange;
+use crate::orders::order::OrderSnapshot;
+use crate::orders::pool::OrderRef;
+
+impl Exchange {
+    /// Adds open order which was created by another instance of engine (e.g. engine which is moved
+    /// to another host) to orders pool, so events of order are handled as for own orders
+    pub fn adopt_order(&self, order: OrderSnapshot) -> Result<OrderRef> {
+        let client_order_id = order.header.client_order_id.clone();
+        if order.header.exchange_account_id != self.exchange_account_id {
+            bail!(
+                "Order {} belongs to {} instead of {}",
+                client_order_id,
+                order.header.exchange_account_id,
+                self.exchange_account_id
+            );
+        }
+
+        if order.props.is_finished() {
+            bail!(
+                "Order {} is already finished with status {:?}",
+                client_order_id,
+                order.status()
+            );
+        }
+
+        if self
+            .orders
+            .cache_by_client_id
+            .contains_key(&client_order_id)
+        {
+            bail!(
+                "Order {} is already in orders pool of {}",
+                client_order_id,
+                self.exchange_account_id
+            );
+        }
+
+        let exchange_order_id = order.props.exchange_order_id.clone();
+        let order_ref = self
+            .orders
+            .add_snapshot_initial(Arc::new(RwLock::new(order)));
+        if let Some(exchange_order_id) = exchange_order_id {
+            let _ = self
+                .orders
+                .cache_by_exchange_id
+                .insert(exchange_order_id.clone(), order_ref.clone());
+            self.order_ids
+                .add(exchange_order_id, client_order_id.clone());
+        }
+
+        log::info!(
+            "Order {} is adopted by {}",
+            client_order_id,
+            self.exchange_account_id
+        );
+
+        Ok(order_ref)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use mmb_utils::cancellation_token::CancellationToken;
+    use rust_decimal_macros::dec;
+
+    use crate::error::{MmbError, MmbResult, RiskError};
+    use crate::exchanges::general::order::wait_outcome::WaitOutcome;
+    use crate::exchanges::general::test_helper::get_test_exchange;
+    use crate::orders::order::{
+        ClientOrderId, ExchangeOrderId, OrderSide, OrderSnapshot, OrderStatus, OrderType,
+    };
+
+    #[tokio::test]
+    async fn handed_over_order_is_adopted_and_kept_open() {
+        let (source, _source_rx) = get_test_exchange(false);
+        let (target, _target_rx) = get_test_exchange(false);
+        let currency_pair = *source.symbols.iter().next().expect("in test").key();
+        let client_order_id = ClientOrderId::new("handed_over_order".into());
+
+        let mut snapshot = OrderSnapshot::with_params(
+            client_order_id.clone(),
+            OrderType::Limit,
+            None,
+            source.exchange_account_id,
+            currency_pair,
+            dec!(0.2),
+            dec!(10),
+            OrderSide::Buy,
+            None,
+            "StrategyInUnitTests",
+        );
+        snapshot.props.exchange_order_id = Some(ExchangeOrderId::new("1".into()));
+        snapshot.set_status(OrderStatus::Created, Utc::now());
+        let order = source.adopt_order(snapshot).expect("in test");
+
+        source.set_state_handed_over();
+        let adopted_order = target.adopt_order(order.deep_clone()).expect("in test");
+
+        // Test client panics on any request, so orders are kept open without requests to exchange
+        let is_trading_disabled = |result: MmbResult<WaitOutcome>| {
+            matches!(
+                result,
+                Err(MmbError::Risk {
+                    error: RiskError::TradingDisabled(_),
+                    ..
+                })
+            )
+        };
+        assert!(is_trading_disabled(
+            source
+                .wait_cancel_order(order.clone(), None, true, CancellationToken::default())
+                .await
+        ));
+        assert!(is_trading_disabled(
+            source
+                .wait_cancel_order_confirmed(
+                    order.clone(),
+                    None,
+                    true,
+                    CancellationToken::default()
+                )
+                .await
+        ));
+        assert!(source
+            .start_cancel_order(&order, CancellationToken::default())
+            .await
+            .is_err());
+        assert!(source.cancel_all_orders(currency_pair).await.is_err());
+        assert_eq!(order.status(), OrderStatus::Created);
+
+        assert!(source.is_market_data_only());
+        assert_eq!(adopted_order.status(), OrderStatus::Created);
+        assert_eq!(adopted_order.exchange_order_id(), order.exchange_order_id());
+        assert!(target
+            .orders
+            .cache_by_exchange_id
+            .contains_key(&ExchangeOrderId::new("1".into())));
+    }
+}
@@ -4,7 +4,9 @@ use futures::future::join_all;
 use itertools::Itertools;
 use mmb_utils::cancellation_token::CancellationToken;
 use tokio::sync::oneshot;
+use tracing::Instrument;
 
+use crate::error::MmbResult;
 use crate::exchanges::general::helpers::get_rest_error_order;
 use crate::{
     exchanges::common::Amount,
@@ -13,6 +15,7 @@ use crate::{
     exchanges::common::RestRequestOutcome,
     exchanges::general::exchange::Exchange,
     exchanges::general::exchange::RequestResult,
+    exchanges::general::request_type::RequestType,
     orders::order::ClientOrderId,
     orders::order::ExchangeOrderId,
     orders::order::OrderInfo,
@@ -56,7 +59,9 @@ impl Exchange {
         &self,
         order: &OrderRef,
         cancellation_token: CancellationToken,
-    ) -> Result<Option<CancelOrderResult>> {
+    ) -> MmbResult<Option<CancelOrderResult>> {
+        self.check_orders_not_handed_over()?;
+
         match order.status() {
             OrderStatus::Canceled => {
                 log::info!(
@@ -153,7 +158,13 @@ impl Exchange {
         self.order_cancellation_events
             .insert(exchange_order_id.clone(), (tx, None));
 
-        let order_cancel_future = self.exchange_client.request_cancel_order(&order);
+        let order_cancel_future = self
+            .exchange_client
+            .request_cancel_order(&order)
+            .instrument(
+                self.order_traces
+                    .request_span(&order.header.client_order_id, RequestType::CancelOrder),
+            );
 
         tokio::select! {
             rest_request_outcome = order_cancel_future => {
@@ -250,13 +261,14 @@ impl Exchange {
         }
     }
 
+    /// Returns count of orders which are cancelled with confirmation of exchange
     pub(crate) async fn cancel_orders(
         &self,
         orders: Vec<OrderInfo>,
         cancellation_token: CancellationToken,
-    ) {
+    ) -> usize {
         if orders.len() == 0 {
-            return;
+            return 0;
         }
 
         let mut futures = Vec::new();
@@ -269,12 +281,21 @@ impl Exchange {
                 .get(&order.exchange_order_id)
             {
                 None => not_found_orders.push(order.exchange_order_id.clone()),
-                Some(order_ref) => futures.push(self.wait_cancel_order(
-                    order_ref.clone(),
-                    None,
-                    true,
-                    cancellation_token.clone(),
-                )),
+                Some(order_ref) => {
+                    let order_ref = order_ref.clone();
+                    let cancellation_token = cancellation_token.clone();
+                    futures.push(async move {
+                        let result = self
+                            .wait_cancel_order_confirmed(
+                                order_ref.clone(),
+                                None,
+                                true,
+                                cancellation_token,
+                            )
+                            .await;
+                        (order_ref, result)
+                    })
+                }
             }
         }
 
@@ -286,6 +307,23 @@ impl Exchange {
             );
         }
 
-        join_all(futures).await;
+        let mut cancelled_count = 0;
+        for (order, result) in join_all(futures).await {
+            match result {
+                Ok(outcome) => {
+                    if outcome.is_cancelled() {
+                        cancelled_count += 1;
+                    }
+                }
+                Err(error) => log::error!(
+                    "Unable to cancel order {} on {}: {:?}",
+                    order.client_order_id(),
+                    self.exchange_account_id,
+                    error
+                ),
+            }
+        }
+
+        cancelled_count
     }
 }
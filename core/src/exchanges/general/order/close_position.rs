@@ -0,0 +1,390 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use mmb_utils::cancellation_token::CancellationToken;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::error::MmbResult;
+use crate::exchanges::common::{ActivePosition, Amount, Price};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::symbol::{Round, Symbol};
+use crate::order_book::order_book_data::OrderBookData;
+use crate::orders::fill::OrderFill;
+use crate::orders::order::{ClientOrderId, OrderSide};
+use crate::orders::order_builder::OrderBuilder;
+use crate::orders::pool::OrderRef;
+
+/// Strategy name of orders which close positions
+pub const CLOSE_POSITION_STRATEGY_NAME: &str = "ClosePosition";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmartCloseSettings {
+    /// Max deviation of fill price from top of order book at start of closing as part of price
+    /// (e.g. 0.005 is 0.5%). Position isn't closed at worse prices
+    pub max_slippage: Decimal,
+    /// Position is split into child orders with amount not greater than this one.
+    /// Position is closed by single order if it isn't specified
+    pub max_child_amount: Option<Amount>,
+    /// Depth of order book which is requested before every child order
+    pub order_book_depth: usize,
+    /// Unfilled part of limit child order is cancelled after this time
+    pub limit_order_timeout: Duration,
+}
+
+/// Result of `close_position_smart`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmartCloseReport {
+    pub closed_amount: Amount,
+    /// Amount which isn't closed because there is no liquidity within slippage cap
+    pub remaining_amount: Amount,
+    /// Top price of order book on side of counterparties at start of closing
+    pub reference_price: Price,
+    pub average_price: Option<Price>,
+    /// Loss from slippage against reference price plus commissions in quote currency
+    pub closing_cost: Amount,
+    pub client_order_ids: Vec<ClientOrderId>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CloseOrderType {
+    Market,
+    Limit(Price),
+}
+
+impl Exchange {
+    /// Closes position by reduce-only child orders without slippage beyond `settings.max_slippage`.
+    /// Child order is market order if order book has enough liquidity within slippage cap,
+    /// otherwise it's limit order at the worst acceptable price. Closing is stopped when child
+    /// order isn't filled at all, so the rest of position is reported as remaining instead of
+    /// chasing price
+    pub async fn close_position_smart(
+        self: Arc<Self>,
+        position: &ActivePosition,
+        settings: &SmartCloseSettings,
+        cancellation_token: CancellationToken,
+    ) -> MmbResult<SmartCloseReport> {
+        Ok(self
+            .close_position_smart_work(position, settings, cancellation_token)
+            .await?)
+    }
+
+    async fn close_position_smart_work(
+        self: Arc<Self>,
+        position: &ActivePosition,
+        settings: &SmartCloseSettings,
+        cancellation_token: CancellationToken,
+    ) -> Result<SmartCloseReport> {
+        self.check_trading_enabled()?;
+
+        let currency_pair = position.derivative.currency_pair;
+        let symbol = self.get_symbol(currency_pair)?;
+        let close_side = get_close_side(position);
+
+        let mut order_book = Some(
+            self.get_order_book_snapshot(
+                currency_pair,
+                settings.order_book_depth,
+                cancellation_token.clone(),
+            )
+            .await?,
+        );
+        let reference_price = order_book
+            .as_ref()
+            .and_then(|order_book| get_reference_price(order_book, close_side))
+            .with_context(|| {
+                format!(
+                    "Unable to close position {}: there are no orders for {} {} on {}",
+                    position.id, close_side, currency_pair, self.exchange_account_id
+                )
+            })?;
+        let limit_price = get_limit_price(&symbol, close_side, reference_price, settings);
+
+        log::info!(
+            "Closing position {} on {} by {} orders with limit price {}",
+            position.id,
+            self.exchange_account_id,
+            close_side,
+            limit_price
+        );
+
+        let mut remaining_amount =
+            symbol.amount_round(position.derivative.position.abs(), Round::Floor);
+        let mut filled_cost = dec!(0);
+        let mut report = SmartCloseReport {
+            closed_amount: dec!(0),
+            remaining_amount,
+            reference_price,
+            average_price: None,
+            closing_cost: dec!(0),
+            client_order_ids: Vec::new(),
+        };
+
+        while remaining_amount > dec!(0) {
+            let child_amount = match settings.max_child_amount {
+                Some(max_child_amount) => {
+                    symbol.amount_round(remaining_amount.min(max_child_amount), Round::Floor)
+                }
+                None => remaining_amount,
+            };
+            if child_amount.is_zero() {
+                break;
+            }
+
+            // The first child order uses snapshot which the reference price is taken from
+            let order_book = match order_book.take() {
+                Some(order_book) => order_book,
+                None => {
+                    self.get_order_book_snapshot(
+                        currency_pair,
+                        settings.order_book_depth,
+                        cancellation_token.clone(),
+                    )
+                    .await?
+                }
+            };
+            let order_type =
+                choose_close_order_type(&order_book, close_side, child_amount, limit_price);
+
+            let order = self
+                .create_close_order(
+                    position,
+                    close_side,
+                    child_amount,
+                    order_type,
+                    reference_price,
+                    cancellation_token.clone(),
+                )
+                .await?;
+            report.client_order_ids.push(order.client_order_id());
+            self.clone()
+                .wait_close_order(
+                    &order,
+                    order_type,
+                    settings.limit_order_timeout,
+                    cancellation_token.clone(),
+                )
+                .await?;
+
+            let (fills, filled_amount) = order.get_fills();
+            for fill in &fills {
+                filled_cost += fill.price() * fill.amount();
+                report.closing_cost +=
+                    get_fill_closing_cost(&symbol, close_side, reference_price, fill);
+            }
+            report.closed_amount += filled_amount;
+            remaining_amount -= filled_amount;
+
+            if filled_amount.is_zero() {
+                log::warn!(
+                    "Closing of position {} on {} is stopped: order {} isn't filled at price within slippage cap",
+                    position.id,
+                    self.exchange_account_id,
+                    order.client_order_id()
+                );
+                break;
+            }
+        }
+
+        report.remaining_amount = remaining_amount;
+        if !report.closed_amount.is_zero() {
+            report.average_price = Some(filled_cost / report.closed_amount);
+        }
+
+        log::info!(
+            "Closing of position {} on {} is finished: {:?}",
+            position.id,
+            self.exchange_account_id,
+            report
+        );
+
+        Ok(report)
+    }
+
+    async fn create_close_order(
+        &self,
+        position: &ActivePosition,
+        close_side: OrderSide,
+        amount: Amount,
+        order_type: CloseOrderType,
+        reference_price: Price,
+        cancellation_token: CancellationToken,
+    ) -> Result<OrderRef> {
+        let mut builder =
+            OrderBuilder::new(self.exchange_account_id, position.derivative.currency_pair)
+                .side(close_side)
+                .amount(amount)
+                .reduce_only()
+                .strategy_name(CLOSE_POSITION_STRATEGY_NAME);
+        builder = match order_type {
+            CloseOrderType::Market => builder.market(Some(reference_price)),
+            CloseOrderType::Limit(price) => builder.limit(price),
+        };
+        if let Some(position_side) = position.derivative.position_side {
+            builder = builder.position_side(position_side);
+        }
+
+        Ok(builder.create(self, cancellation_token).await?)
+    }
+
+    async fn wait_close_order(
+        self: Arc<Self>,
+        order: &OrderRef,
+        order_type: CloseOrderType,
+        limit_order_timeout: Duration,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        if order_type == CloseOrderType::Market {
+            let _ = self
+                .wait_order_finish(order, None, cancellation_token)
+                .await?
+                .into_finished(&order.client_order_id())?;
+            return Ok(());
+        }
+
+        tokio::select! {
+            outcome = self.clone().wait_order_finish(order, None, cancellation_token.clone()) => {
+                let _ = outcome?.into_finished(&order.client_order_id())?;
+            }
+            _ = tokio::time::sleep(limit_order_timeout) => {
+                let _ = self
+                    .wait_cancel_order_confirmed(order.clone(), None, true, cancellation_token)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Side of order which decreases position
+fn get_close_side(position: &ActivePosition) -> OrderSide {
+    match position.derivative.side {
+        Some(side) => side.change_side(),
+        None if position.derivative.position > dec!(0) => OrderSide::Sell,
+        None => OrderSide::Buy,
+    }
+}
+
+/// Best price of counterparties of order with `close_side`
+fn get_reference_price(order_book: &OrderBookData, close_side: OrderSide) -> Option<Price> {
+    match close_side {
+        OrderSide::Sell => order_book.bids.keys().next_back().copied(),
+        OrderSide::Buy => order_book.asks.keys().next().copied(),
+    }
+}
+
+/// The worst acceptable price of closing rounded inside of slippage cap
+fn get_limit_price(
+    symbol: &Symbol,
+    close_side: OrderSide,
+    reference_price: Price,
+    settings: &SmartCloseSettings,
+) -> Price {
+    let price = match close_side {
+        OrderSide::Sell => reference_price * (dec!(1) - settings.max_slippage),
+        OrderSide::Buy => reference_price * (dec!(1) + settings.max_slippage),
+    };
+
+    symbol.price_round(price, Round::TowardPassive(close_side))
+}
+
+/// Market order is used only if it's filled completely by levels of order book within limit price
+fn choose_close_order_type(
+    order_book: &OrderBookData,
+    close_side: OrderSide,
+    amount: Amount,
+    limit_price: Price,
+) -> CloseOrderType {
+    let available_amount: Amount = match close_side {
+        OrderSide::Sell => order_book
+            .bids
+            .iter()
+            .rev()
+            .take_while(|(price, _)| **price >= limit_price)
+            .map(|(_, amount)| *amount)
+            .sum(),
+        OrderSide::Buy => order_book
+            .asks
+            .iter()
+            .take_while(|(price, _)| **price <= limit_price)
+            .map(|(_, amount)| *amount)
+            .sum(),
+    };
+
+    if available_amount >= amount {
+        CloseOrderType::Market
+    } else {
+        CloseOrderType::Limit(limit_price)
+    }
+}
+
+fn get_fill_closing_cost(
+    symbol: &Symbol,
+    close_side: OrderSide,
+    reference_price: Price,
+    fill: &OrderFill,
+) -> Amount {
+    let price_slippage = match close_side {
+        OrderSide::Sell => reference_price - fill.price(),
+        OrderSide::Buy => fill.price() - reference_price,
+    };
+
+    let commission = if fill.converted_commission_currency_code() == symbol.quote_currency_code {
+        fill.converted_commission_amount()
+    } else {
+        fill.converted_commission_amount() * fill.price()
+    };
+
+    price_slippage * fill.amount() + commission
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::SortedOrderData;
+    use crate::test_util::SymbolBuilder;
+
+    #[test]
+    fn close_order_type_depends_on_liquidity_within_slippage_cap() {
+        let symbol = SymbolBuilder::new("btc".into(), "usdt".into()).build();
+        let settings = SmartCloseSettings {
+            max_slippage: dec!(0.01),
+            max_child_amount: None,
+            order_book_depth: 20,
+            limit_order_timeout: Duration::from_secs(5),
+        };
+        let order_book = OrderBookData::new(
+            SortedOrderData::from([(dec!(101), dec!(1)), (dec!(110), dec!(10))]),
+            SortedOrderData::from([
+                (dec!(100), dec!(1)),
+                (dec!(99.5), dec!(2)),
+                (dec!(90), dec!(10)),
+            ]),
+        );
+
+        let reference_price = get_reference_price(&order_book, OrderSide::Sell).expect("in test");
+        assert_eq!(reference_price, dec!(100));
+        let limit_price = get_limit_price(&symbol, OrderSide::Sell, reference_price, &settings);
+        assert_eq!(limit_price, dec!(99));
+
+        assert_eq!(
+            choose_close_order_type(&order_book, OrderSide::Sell, dec!(3), limit_price),
+            CloseOrderType::Market
+        );
+        assert_eq!(
+            choose_close_order_type(&order_book, OrderSide::Sell, dec!(4), limit_price),
+            CloseOrderType::Limit(dec!(99))
+        );
+
+        // Buy limit price 102.01 is rounded down to stay within slippage cap
+        let reference_price = get_reference_price(&order_book, OrderSide::Buy).expect("in test");
+        let limit_price = get_limit_price(&symbol, OrderSide::Buy, reference_price, &settings);
+        assert_eq!(limit_price, dec!(102));
+        assert_eq!(
+            choose_close_order_type(&order_book, OrderSide::Buy, dec!(2), limit_price),
+            CloseOrderType::Limit(dec!(102))
+        );
+    }
+}
@@ -1,20 +1,26 @@
+use std::sync::Weak;
+
 use anyhow::{anyhow, bail, Context, Result};
 use chrono::Utc;
 use mmb_utils::cancellation_token::CancellationToken;
 use mmb_utils::{nothing_to_do, OPERATION_CANCELED_MSG};
 use tokio::sync::oneshot;
 
+use crate::error::MmbResult;
 use crate::exchanges::general::exchange::RequestResult::{Error, Success};
+use crate::exchanges::general::order::rejection::{OrderRejectedError, RejectionReason};
 use crate::exchanges::timeouts::requests_timeout_manager::RequestGroupId;
 use crate::orders::event::OrderEventType;
 use crate::{
     exchanges::common::ExchangeAccountId,
     exchanges::common::ExchangeError,
     exchanges::common::ExchangeErrorType,
+    exchanges::common::Price,
     exchanges::general::exchange::Exchange,
     exchanges::general::exchange::RequestResult,
     orders::order::ClientOrderId,
     orders::order::ExchangeOrderId,
+    orders::order::OrderSide,
     orders::order::OrderStatus,
     orders::order::OrderType,
     orders::pool::OrderRef,
@@ -25,6 +31,8 @@ use crate::{
 pub struct CreateOrderResult {
     pub outcome: RequestResult<ExchangeOrderId>,
     pub source_type: EventSourceType,
+    /// Class of error if order is rejected by exchange
+    pub rejection_reason: Option<RejectionReason>,
 }
 
 impl CreateOrderResult {
@@ -32,11 +40,13 @@ impl CreateOrderResult {
         CreateOrderResult {
             outcome: Success(order_id.clone()),
             source_type,
+            rejection_reason: None,
         }
     }
 
     pub fn failed(error: ExchangeError, source_type: EventSourceType) -> Self {
         CreateOrderResult {
+            rejection_reason: Some(RejectionReason::from_exchange_error(&error)),
             outcome: Error(error),
             source_type,
         }
@@ -44,15 +54,105 @@ impl CreateOrderResult {
 }
 
 impl Exchange {
+    /// Creates order and waits until it's created or rejected. Rejection is returned
+    /// as `MmbError` with `OrderRejectedError`
     pub async fn create_order(
         &self,
         order_to_create: &OrderCreating,
         pre_reservation_group_id: Option<RequestGroupId>,
         cancellation_token: CancellationToken,
+    ) -> MmbResult<OrderRef> {
+        Ok(self
+            .create_order_work(
+                order_to_create,
+                pre_reservation_group_id,
+                cancellation_token,
+            )
+            .await?)
+    }
+
+    async fn create_order_work(
+        &self,
+        order_to_create: &OrderCreating,
+        pre_reservation_group_id: Option<RequestGroupId>,
+        cancellation_token: CancellationToken,
     ) -> Result<OrderRef> {
         log::info!("Submitting order {:?}", order_to_create);
+
+        let client_order_id = &order_to_create.header.client_order_id;
+        let rejected =
+            |reason, message| OrderRejectedError::new(client_order_id.clone(), reason, message);
+
+        self.check_trading_enabled().map_err(|error| {
+            rejected(
+                RejectionReason::RiskBlocked,
+                format!("Unable to create order {client_order_id}: {error}"),
+            )
+        })?;
+
+        let currency_pair = order_to_create.header.currency_pair;
+        if self.is_market_halted(currency_pair) {
+            bail!(rejected(
+                RejectionReason::MarketHalted,
+                format!(
+                    "Unable to create order {} because trading is halted for {} on {}",
+                    client_order_id, currency_pair, self.exchange_account_id
+                ),
+            ));
+        }
+
+        if self.is_market_disabled(currency_pair) {
+            bail!(rejected(
+                RejectionReason::RiskBlocked,
+                format!(
+                    "Unable to create order {} because trading is disabled for {} on {} by operator",
+                    client_order_id, currency_pair, self.exchange_account_id
+                ),
+            ));
+        }
+
+        self.check_trading_window(currency_pair).map_err(|error| {
+            rejected(
+                RejectionReason::RiskBlocked,
+                format!("Unable to create order {client_order_id}: {error}"),
+            )
+        })?;
+
+        let estimated_price = self.get_estimated_price(order_to_create);
+        let order_filter = self.order_filter.lock().clone();
+        if let Some(order_filter) = order_filter {
+            order_filter
+                .check(order_to_create, estimated_price)
+                .map_err(|error| {
+                    rejected(
+                        RejectionReason::RiskBlocked,
+                        format!(
+                            "Unable to create order {} on {}: {:#}",
+                            client_order_id, self.exchange_account_id, error
+                        ),
+                    )
+                })?;
+        }
+
+        let exposure_limits = self.exposure_limits.lock().as_ref().and_then(Weak::upgrade);
+        let symbol = self.symbols.get(&currency_pair).map(|x| x.clone());
+        if let (Some(exposure_limits), Some(symbol)) = (exposure_limits, symbol) {
+            exposure_limits
+                .check_order(&symbol, order_to_create, estimated_price)
+                .map_err(|error| {
+                    rejected(
+                        RejectionReason::RiskBlocked,
+                        format!(
+                            "Unable to create order {} on {}: {}",
+                            client_order_id, self.exchange_account_id, error
+                        ),
+                    )
+                })?;
+        }
+
         self.orders
             .add_simple_initial(order_to_create.header.clone(), Some(order_to_create.price));
+        self.order_traces.start(order_to_create);
 
         let linked_cancellation_token = cancellation_token.create_linked_token();
 
@@ -66,7 +166,7 @@ impl Exchange {
             created_order_outcome = create_order_future => {
                 match created_order_outcome {
                     Ok(created_order_result) => {
-                        self.match_created_order_outcome( &created_order_result.outcome, pre_reservation_group_id, cancellation_token).await
+                        self.match_created_order_outcome(client_order_id, &created_order_result, pre_reservation_group_id, cancellation_token).await
                     }
                     Err(exchange_error) => {
                         bail!("Exchange error: {:?}", exchange_error)
@@ -79,11 +179,12 @@ impl Exchange {
 
     async fn match_created_order_outcome(
         &self,
-        outcome: &RequestResult<ExchangeOrderId>,
+        client_order_id: &ClientOrderId,
+        created_order_result: &CreateOrderResult,
         pre_reservation_group_id: Option<RequestGroupId>,
         cancellation_token: CancellationToken,
     ) -> Result<OrderRef> {
-        match outcome {
+        match &created_order_result.outcome {
             Success(exchange_order_id) => {
                 let result_order = &*self
                     .orders
@@ -136,8 +237,13 @@ impl Exchange {
                     // TODO strange order handling there
                     // self.check_order_creation().await?;
                 }
-                // TODO delete it in the future
-                bail!("Exchange error: {}", exchange_error.message)
+                bail!(OrderRejectedError::new(
+                    client_order_id.clone(),
+                    created_order_result
+                        .rejection_reason
+                        .unwrap_or(RejectionReason::Other),
+                    format!("Exchange error: {}", exchange_error.message),
+                ))
             }
         }
     }
@@ -162,6 +268,11 @@ impl Exchange {
                     )?;
                 }
                 Error(exchange_error) => {
+                    self.check_trading_halt_error(
+                        order_to_create.header.currency_pair,
+                        exchange_error,
+                    );
+
                     if exchange_error.error_type != ExchangeErrorType::ParsingError {
                         self.handle_create_order_failed(
                             self.exchange_account_id,
@@ -179,7 +290,7 @@ impl Exchange {
         bail!(OPERATION_CANCELED_MSG)
     }
 
-    fn handle_create_order_failed(
+    pub(super) fn handle_create_order_failed(
         &self,
         exchange_account_id: ExchangeAccountId,
         client_order_id: &ClientOrderId,
@@ -218,6 +329,25 @@ impl Exchange {
         self.react_on_status_when_failed(&order_ref, args_to_log, source_type, exchange_error)
     }
 
+    /// Price of order which is used by pre-trade checks. Market orders are estimated by
+    /// top of order book on their side, because they are filled by opposite orders
+    fn get_estimated_price(&self, order: &OrderCreating) -> Price {
+        if order.header.order_type != OrderType::Market {
+            return order.price;
+        }
+
+        self.order_book_top
+            .get(&order.header.currency_pair)
+            .and_then(|top| {
+                let level = match order.header.side {
+                    OrderSide::Buy => top.ask.as_ref(),
+                    OrderSide::Sell => top.bid.as_ref(),
+                };
+                level.map(|level| level.price)
+            })
+            .unwrap_or(order.price)
+    }
+
     fn react_on_status_when_failed(
         &self,
         order_ref: &OrderRef,
@@ -321,6 +451,8 @@ impl Exchange {
                 order_ref.fn_mut(|order| {
                     order.props.exchange_order_id = Some(exchange_order_id.clone());
                 });
+                self.order_ids
+                    .add(exchange_order_id.clone(), client_order_id.clone());
                 self.react_on_status_when_succeed(&order_ref, args_to_log, source_type)
             }
         }
@@ -386,11 +518,19 @@ impl Exchange {
                             let bm_lock = self.balance_manager.lock();
                             match bm_lock.as_ref().expect("BalanceManager should be initialized before receiving order events").upgrade() {
                                 None => log::warn!("BalanceManager ref can't be upgraded in handler create order succeeded event"),
-                                Some(balance_manager) => balance_manager.lock().approve_reservation(
-                                    reservation_id,
-                                    &client_order_id,
-                                    header.amount,
-                                )
+                                // Quote amount of order is converted to base currency of reservation
+                                Some(balance_manager) => match order_ref.base_amount() {
+                                    Some(amount) => balance_manager.lock().approve_reservation(
+                                        reservation_id,
+                                        &client_order_id,
+                                        amount,
+                                    ),
+                                    None => log::error!(
+                                        "Unable to approve reservation {} of order {} without price",
+                                        reservation_id,
+                                        client_order_id
+                                    ),
+                                },
                             }
                         }
                     };
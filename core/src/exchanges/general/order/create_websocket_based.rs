@@ -1,6 +1,7 @@
 use anyhow::Result;
 use mmb_utils::cancellation_token::CancellationToken;
 use tokio::sync::oneshot;
+use tracing::Instrument;
 
 use crate::exchanges::general::helpers::get_rest_error_order;
 use crate::{
@@ -9,6 +10,7 @@ use crate::{
     exchanges::common::RestRequestOutcome,
     exchanges::general::exchange::Exchange,
     exchanges::general::exchange::RequestResult,
+    exchanges::general::request_type::RequestType,
     orders::order::ClientOrderId,
     orders::order::ExchangeOrderId,
     orders::{fill::EventSourceType, order::OrderCreating},
@@ -30,7 +32,10 @@ impl Exchange {
         self.order_creation_events
             .insert(client_order_id.clone(), (tx, None));
 
-        let order_create_future = self.exchange_client.create_order(&order);
+        let order_create_future = self.exchange_client.create_order(&order).instrument(
+            self.order_traces
+                .request_span(&client_order_id, RequestType::CreateOrder),
+        );
 
         tokio::select! {
             rest_request_outcome = order_create_future => {
@@ -0,0 +1,355 @@
+use anyhow::{Context, Result};
+use itertools::Itertools;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::{nothing_to_do, DateTime};
+
+use crate::exchanges::general::exchange::{Exchange, RequestResult};
+use crate::exchanges::general::features::RestFillsType;
+use crate::exchanges::general::order::get_order_trades::OrderTrade;
+use crate::exchanges::general::request_type::RequestType;
+use crate::misc::time::time_manager;
+use crate::orders::fill::EventSourceType;
+use crate::orders::order::OrderStatus;
+use crate::orders::pool::OrderRef;
+
+/// Trades of orders which weren't received before. Trades of other orders are skipped
+fn missed_order_trades<'a>(
+    orders: &[&'a OrderRef],
+    order_trades: &'a [OrderTrade],
+) -> Vec<(&'a OrderRef, &'a OrderTrade)> {
+    order_trades
+        .iter()
+        .filter_map(|order_trade| {
+            let order = orders.iter().find(|order| {
+                order.exchange_order_id().as_ref() == Some(&order_trade.exchange_order_id)
+            })?;
+
+            let is_fill_received = order
+                .get_fills()
+                .0
+                .iter()
+                .any(|fill| fill.trade_id() == Some(&order_trade.trade_id));
+
+            match is_fill_received {
+                true => None,
+                false => Some((*order, order_trade)),
+            }
+        })
+        .collect()
+}
+
+impl Exchange {
+    /// Gap in user data stream lasts since the first disconnection of websocket
+    /// until it's connected again
+    pub(crate) fn start_user_data_gap(&self) {
+        let mut disconnected_at = self.websocket_disconnected_at.lock();
+        if disconnected_at.is_none() {
+            *disconnected_at = Some(time_manager::now());
+        }
+    }
+
+    /// Beginning of gap in user data stream if websocket was disconnected after the last connection
+    pub(crate) fn take_user_data_gap(&self) -> Option<DateTime> {
+        self.websocket_disconnected_at.lock().take()
+    }
+
+    /// Orders which could be filled or cancelled during gap. Orders which aren't created
+    /// on exchange yet get their state from responses of creation
+    fn orders_to_check_after_gap(&self) -> Vec<OrderRef> {
+        self.orders
+            .not_finished
+            .iter()
+            .map(|order| order.value().clone())
+            .filter(|order| {
+                order.status() != OrderStatus::Creating && order.exchange_order_id().is_some()
+            })
+            .collect_vec()
+    }
+
+    /// Synthesize fill and cancel events which were missed while user data websocket was disconnected.
+    /// All not finished orders are checked through REST, already received fills are skipped by trade id
+    pub(crate) async fn fill_user_data_gap(&self, disconnected_at: DateTime) -> Result<()> {
+        let orders = self.orders_to_check_after_gap();
+        if orders.is_empty() {
+            return Ok(());
+        }
+
+        log::info!(
+            "Filling gap in user data stream since {} for {} orders on {}",
+            disconnected_at,
+            orders.len(),
+            self.exchange_account_id
+        );
+
+        let cancellation_token = self.lifetime_manager.stop_token();
+
+        match self.features.rest_fills_features.fills_type {
+            RestFillsType::None => nothing_to_do(),
+            RestFillsType::MyTrades => {
+                self.fill_trades_gap(&orders, disconnected_at, cancellation_token.clone())
+                    .await?
+            }
+            RestFillsType::GetOrderInfo => {
+                for order in &orders {
+                    let currency_pair = order.currency_pair();
+                    let symbol = self
+                        .symbols
+                        .get(&currency_pair)
+                        .with_context(|| format!("No such symbol for {}", currency_pair))?
+                        .clone();
+
+                    self.check_order_fills_using_request_type(
+                        order,
+                        &symbol,
+                        RequestType::GetOrderInfo,
+                        None,
+                        cancellation_token.clone(),
+                    )
+                    .await?;
+                }
+            }
+        }
+
+        for order in orders.iter().filter(|order| !order.is_finished()) {
+            self.check_order_cancelled_during_gap(order, cancellation_token.clone())
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn fill_trades_gap(
+        &self,
+        orders: &[OrderRef],
+        disconnected_at: DateTime,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        let orders_by_currency_pair = orders
+            .iter()
+            .into_group_map_by(|order| order.currency_pair());
+
+        for (currency_pair, orders) in orders_by_currency_pair {
+            let symbol = self
+                .symbols
+                .get(&currency_pair)
+                .with_context(|| format!("No such symbol for {}", currency_pair))?
+                .clone();
+
+            self.timeout_manager
+                .reserve_when_available(
+                    self.exchange_account_id,
+                    RequestType::GetOrderTrades,
+                    None,
+                    cancellation_token.clone(),
+                )?
+                .await;
+
+            let order_trades = match self.get_my_trades(&symbol, Some(disconnected_at)).await? {
+                RequestResult::Success(order_trades) => order_trades,
+                RequestResult::Error(error) => {
+                    log::warn!(
+                        "Unable to get trades for {} on {} while filling user data gap: {:?}",
+                        currency_pair,
+                        self.exchange_account_id,
+                        error
+                    );
+                    continue;
+                }
+            };
+
+            for (order, order_trade) in missed_order_trades(&orders, &order_trades) {
+                self.handle_order_filled_for_restfallback(order, order_trade);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn check_order_cancelled_during_gap(
+        &self,
+        order: &OrderRef,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        self.timeout_manager
+            .reserve_when_available(
+                self.exchange_account_id,
+                RequestType::GetOrderInfo,
+                None,
+                cancellation_token,
+            )?
+            .await;
+
+        match self.get_order_info(order).await {
+            Ok(order_info) if order_info.order_status == OrderStatus::Canceled => {
+                let exchange_order_id = order.exchange_order_id().with_context(|| {
+                    format!("No exchange_order_id in order {}", order.client_order_id())
+                })?;
+
+                self.handle_cancel_order_succeeded(
+                    Some(&order.client_order_id()),
+                    &exchange_order_id,
+                    Some(order_info.filled_amount),
+                    EventSourceType::RestFallback,
+                );
+            }
+            Ok(_) => nothing_to_do(),
+            Err(error) => log::warn!(
+                "Unable to get order info for {} on {} while filling user data gap: {:?}",
+                order.client_order_id(),
+                self.exchange_account_id,
+                error
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::exchanges::common::CurrencyPair;
+    use crate::exchanges::events::TradeId;
+    use crate::exchanges::general::test_helper::get_test_exchange;
+    use crate::orders::fill::{OrderFill, OrderFillType};
+    use crate::orders::order::{ExchangeOrderId, OrderFillRole, OrderRole};
+    use crate::orders::pool::OrdersPool;
+    use crate::test_util::OrderSnapshotBuilder;
+
+    fn currency_pair(exchange: &Exchange) -> CurrencyPair {
+        *exchange.symbols.iter().next().expect("in test").key()
+    }
+
+    fn fill(trade_id: u64) -> OrderFill {
+        OrderFill::new(
+            Uuid::new_v4(),
+            None,
+            Utc::now(),
+            OrderFillType::UserTrade,
+            Some(TradeId::Number(trade_id)),
+            dec!(1),
+            dec!(0.1),
+            dec!(0.1),
+            OrderFillRole::Maker,
+            "btc".into(),
+            dec!(0),
+            dec!(0),
+            "btc".into(),
+            dec!(0),
+            dec!(0),
+            false,
+            None,
+            None,
+        )
+    }
+
+    fn order_trade(exchange_order_id: &str, trade_id: u64) -> OrderTrade {
+        OrderTrade::new(
+            exchange_order_id.into(),
+            TradeId::Number(trade_id),
+            Utc::now(),
+            dec!(1),
+            dec!(0.1),
+            OrderRole::Maker,
+            "btc".into(),
+            None,
+            None,
+            OrderFillType::UserTrade,
+        )
+    }
+
+    #[test]
+    fn user_data_gap_starts_at_first_disconnection() {
+        let (exchange, _rx) = get_test_exchange(false);
+        assert_eq!(exchange.take_user_data_gap(), None);
+
+        exchange.start_user_data_gap();
+        let disconnected_at = exchange
+            .websocket_disconnected_time()
+            .expect("gap should be started");
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        exchange.start_user_data_gap();
+
+        assert_eq!(exchange.take_user_data_gap(), Some(disconnected_at));
+        // Gap is filled once after reconnection
+        assert_eq!(exchange.take_user_data_gap(), None);
+        assert_eq!(exchange.websocket_disconnected_time(), None);
+    }
+
+    #[test]
+    fn only_orders_created_on_exchange_are_checked_after_gap() {
+        let (exchange, _rx) = get_test_exchange(false);
+        let currency_pair = currency_pair(&exchange);
+        let order = |exchange_order_id: Option<&str>, status| {
+            let mut builder =
+                OrderSnapshotBuilder::new(exchange.exchange_account_id, currency_pair)
+                    .status(status, Utc::now());
+            if let Some(exchange_order_id) = exchange_order_id {
+                builder = builder.exchange_order_id(exchange_order_id.into());
+            }
+            builder.build_ref(&exchange.orders)
+        };
+
+        let created = order(Some("1"), OrderStatus::Created);
+        let _creating = order(None, OrderStatus::Creating);
+        let _without_exchange_order_id = order(None, OrderStatus::Created);
+
+        let orders = exchange.orders_to_check_after_gap();
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].client_order_id(), created.client_order_id());
+    }
+
+    // Test client panics on any request, so nothing is requested if there are no orders to check
+    #[tokio::test]
+    async fn gap_without_created_orders_is_filled_without_requests() {
+        let (exchange, _rx) = get_test_exchange(false);
+        let _creating =
+            OrderSnapshotBuilder::new(exchange.exchange_account_id, currency_pair(&exchange))
+                .status(OrderStatus::Creating, Utc::now())
+                .build_ref(&exchange.orders);
+
+        exchange
+            .fill_user_data_gap(Utc::now())
+            .await
+            .expect("in test");
+    }
+
+    #[test]
+    fn missed_trades_are_detected_by_trade_id() {
+        let orders_pool = OrdersPool::new();
+        let exchange_account_id = "Binance_0".parse().expect("in test");
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        let first = OrderSnapshotBuilder::new(exchange_account_id, currency_pair)
+            .exchange_order_id(ExchangeOrderId::new("1".into()))
+            .fill(fill(1))
+            .build_ref(&orders_pool);
+        let second = OrderSnapshotBuilder::new(exchange_account_id, currency_pair)
+            .exchange_order_id(ExchangeOrderId::new("2".into()))
+            .build_ref(&orders_pool);
+
+        let order_trades = vec![
+            // Received by websocket before disconnection
+            order_trade("1", 1),
+            order_trade("1", 2),
+            order_trade("2", 3),
+            // Order isn't checked, e.g. it's created by another engine
+            order_trade("3", 4),
+        ];
+
+        let missed = missed_order_trades(&[&first, &second], &order_trades)
+            .into_iter()
+            .map(|(order, order_trade)| (order.client_order_id(), order_trade.trade_id.clone()))
+            .collect_vec();
+        assert_eq!(
+            missed,
+            vec![
+                (first.client_order_id(), TradeId::Number(2)),
+                (second.client_order_id(), TradeId::Number(3)),
+            ]
+        );
+    }
+}
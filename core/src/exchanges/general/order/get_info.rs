@@ -1,8 +1,10 @@
 use crate::{
     exchanges::common::ExchangeError, exchanges::common::ExchangeErrorType,
-    exchanges::general::exchange::Exchange, orders::order::OrderInfo, orders::pool::OrderRef,
+    exchanges::general::exchange::Exchange, exchanges::general::request_type::RequestType,
+    orders::order::OrderInfo, orders::pool::OrderRef,
 };
 use anyhow::*;
+use tracing::Instrument;
 
 impl Exchange {
     pub async fn get_order_info(&self, order: &OrderRef) -> Result<OrderInfo, ExchangeError> {
@@ -25,6 +27,12 @@ impl Exchange {
             self.exchange_account_id
         );
 
-        self.exchange_client.get_order_info(order).await
+        let request_span = self
+            .order_traces
+            .request_span(&order.client_order_id(), RequestType::GetOrderInfo);
+        self.exchange_client
+            .get_order_info(order)
+            .instrument(request_span)
+            .await
     }
 }
@@ -1,3 +1,4 @@
+use crate::error::MmbResult;
 use crate::exchanges::general::request_type::RequestType;
 use crate::orders::order::{
     ClientOrderId, OrderExecutionType, OrderHeader, OrderInfo, OrderSimpleProps, OrderSnapshot,
@@ -13,11 +14,14 @@ use itertools::Itertools;
 use std::sync::Arc;
 use tokio::time::Duration;
 
+/// Strategy of open orders which were found on exchange but are unknown for engine
+const MISSED_OPEN_ORDER_STRATEGY_NAME: &str = "MissedOpenOrder";
+
 impl Exchange {
     pub async fn get_open_orders(
         &self,
         add_missing_open_orders: bool,
-    ) -> anyhow::Result<Vec<OrderInfo>> {
+    ) -> MmbResult<Vec<OrderInfo>> {
         // Bugs on exchange server can lead to Err even if order was opened
         const MAX_COUNT: i32 = 5;
         let mut count = 0;
@@ -29,7 +33,7 @@ impl Exchange {
                     if count < MAX_COUNT {
                         log::warn!("{}", error);
                     } else {
-                        return Err(error);
+                        return Err(error.into());
                     }
                 }
             }
@@ -116,9 +120,13 @@ impl Exchange {
                 continue;
             }
 
+            // Order created before restart keeps its client order id
             let id_for_new_header: ClientOrderId;
             if order.client_order_id.as_str().is_empty() {
-                id_for_new_header = ClientOrderId::unique_id();
+                id_for_new_header = self
+                    .order_ids
+                    .get_client_order_id(&order.exchange_order_id)
+                    .unwrap_or_else(|| ClientOrderId::generate(MISSED_OPEN_ORDER_STRATEGY_NAME));
             } else {
                 id_for_new_header = order.client_order_id.clone();
             }
@@ -133,7 +141,7 @@ impl Exchange {
                 OrderExecutionType::None,
                 None,
                 None,
-                "MissedOpenOrder".to_string(),
+                MISSED_OPEN_ORDER_STRATEGY_NAME.to_string(),
             );
 
             let props = OrderSimpleProps::new(
@@ -1,3 +1,4 @@
+use crate::error::MmbResult;
 use crate::exchanges::common::{Amount, CurrencyCode, ExchangeError, Price};
 use crate::exchanges::events::TradeId;
 use crate::exchanges::general::exchange::RequestResult;
@@ -9,7 +10,7 @@ use crate::{
     exchanges::general::{exchange::Exchange, features::RestFillsType},
     orders::pool::OrderRef,
 };
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, Context, Result};
 use itertools::Itertools;
 use mmb_utils::DateTime;
 use serde::{Deserialize, Serialize};
@@ -61,11 +62,11 @@ impl Exchange {
         &self,
         symbol: &Symbol,
         order: &OrderRef,
-    ) -> Result<RequestResult<Vec<OrderTrade>>> {
+    ) -> MmbResult<RequestResult<Vec<OrderTrade>>> {
         let fills_type = &self.features.rest_fills_features.fills_type;
         match fills_type {
-            RestFillsType::MyTrades => self.get_my_trades_with_filter(symbol, order).await,
-            _ => bail!("Fills type {:?} is not supported", fills_type),
+            RestFillsType::MyTrades => Ok(self.get_my_trades_with_filter(symbol, order).await?),
+            _ => Err(anyhow!("Fills type {:?} is not supported", fills_type).into()),
         }
     }
 
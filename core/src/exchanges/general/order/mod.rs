@@ -1,8 +1,15 @@
+pub mod adopt;
 pub mod cancel;
+pub mod close_position;
 pub mod create;
 pub mod create_websocket_based;
+pub mod gap_fill;
 pub mod get_info;
 pub mod get_open_orders;
 pub mod get_order_trades;
+pub mod partial_fill_policy;
+pub mod rejection;
+pub mod status_probe;
 pub mod wait_cancel;
 pub mod wait_finish;
+pub mod wait_outcome;
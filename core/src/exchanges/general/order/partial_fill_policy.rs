@@ -0,0 +1,432 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::send_expected::SendExpectedByRef;
+use mmb_utils::{nothing_to_do, OPERATION_CANCELED_MSG};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::error::{MmbError, MmbResult};
+use crate::exchanges::common::{Amount, Price};
+use crate::exchanges::events::{ExchangeEvent, PartialFillAction, PartialFillTimeoutEvent};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::order::wait_outcome::WaitOutcome;
+use crate::exchanges::general::symbol::{Round, Symbol};
+use crate::misc::time::time_manager;
+use crate::orders::order::{ClientOrderId, OrderAmountKind, OrderCreating, OrderHeader, OrderSide};
+use crate::orders::order_builder::{create_order_with_reservation, reserve_order};
+use crate::orders::pool::OrderRef;
+use crate::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+
+/// Execution policy for partially filled orders which stopped being filled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialFillPolicy {
+    /// Time without new fills after which the rest of partially filled order is cancelled
+    pub stagnation_timeout: Duration,
+    /// Remaining amount is re-placed with price moved towards market by this part of price
+    /// (e.g. 0.001 moves price by 0.1%). Remaining amount isn't re-placed if it isn't specified
+    pub replace_price_offset: Option<Decimal>,
+    pub max_replaces_count: u32,
+}
+
+impl PartialFillPolicy {
+    pub fn cancel_after(stagnation_timeout: Duration) -> Self {
+        Self {
+            stagnation_timeout,
+            replace_price_offset: None,
+            max_replaces_count: 0,
+        }
+    }
+
+    pub fn replace_after(
+        stagnation_timeout: Duration,
+        price_offset: Decimal,
+        max_replaces_count: u32,
+    ) -> Self {
+        Self {
+            stagnation_timeout,
+            replace_price_offset: Some(price_offset),
+            max_replaces_count,
+        }
+    }
+}
+
+impl Exchange {
+    /// Waits for finish of order like `wait_order_finish`, but applies `policy` if order is partially filled
+    /// and there are no new fills during stagnation timeout. Returns the last order placed by policy.
+    /// Re-placed order reserves balance for strategy of reservation of replaced order if it has one
+    pub async fn wait_order_finish_with_policy(
+        self: Arc<Self>,
+        order: OrderRef,
+        policy: &PartialFillPolicy,
+        cancellation_token: CancellationToken,
+    ) -> MmbResult<OrderRef> {
+        let mut order = order;
+        let mut replaces_count = 0;
+
+        loop {
+            tokio::select! {
+                outcome = self.clone().wait_order_finish(&order, None, cancellation_token.clone()) => {
+                    let _ = outcome?;
+                    return Ok(order);
+                }
+                _ = wait_order_stagnation(&order, policy.stagnation_timeout) => {}
+                _ = cancellation_token.when_cancelled() => {
+                    return Err(anyhow!(OPERATION_CANCELED_MSG).into());
+                }
+            }
+
+            log::info!(
+                "Order {} on {} is partially filled and has no fills during {:?}",
+                order.client_order_id(),
+                self.exchange_account_id,
+                policy.stagnation_timeout
+            );
+
+            // Stagnant order is kept as is if orders can't be cancelled and re-placed
+            self.check_trading_enabled()?;
+
+            // Reservation of order can be released after cancellation
+            let configuration_descriptor = self.get_reservation_configuration(&order);
+            let outcome = self
+                .wait_cancel_order(order.clone(), None, true, cancellation_token.clone())
+                .await?;
+            match outcome {
+                WaitOutcome::FilledWhileCanceling { .. } => return Ok(order),
+                WaitOutcome::Timeout => {
+                    return Err(MmbError::timeout(format!(
+                        "Unable to cancel stagnant order {} on {}",
+                        order.client_order_id(),
+                        self.exchange_account_id
+                    )))
+                }
+                _ => nothing_to_do(),
+            }
+
+            let remaining_amount = order.remaining_base_amount().ok_or_else(|| {
+                MmbError::Other(anyhow!(
+                    "Unable to get remaining amount of order {} without price",
+                    order.client_order_id()
+                ))
+            })?;
+
+            let price_offset = match policy.replace_price_offset {
+                Some(price_offset) if replaces_count < policy.max_replaces_count => price_offset,
+                _ => {
+                    self.send_partial_fill_timeout(
+                        &order,
+                        remaining_amount,
+                        PartialFillAction::Cancelled,
+                    );
+                    return Ok(order);
+                }
+            };
+
+            let new_order = self
+                .replace_order_remainder(
+                    &order,
+                    remaining_amount,
+                    price_offset,
+                    configuration_descriptor,
+                    cancellation_token.clone(),
+                )
+                .await?;
+            let new_order = match new_order {
+                Some(new_order) => new_order,
+                None => {
+                    self.send_partial_fill_timeout(
+                        &order,
+                        remaining_amount,
+                        PartialFillAction::Cancelled,
+                    );
+                    return Ok(order);
+                }
+            };
+
+            self.send_partial_fill_timeout(
+                &order,
+                remaining_amount,
+                PartialFillAction::Replaced {
+                    new_client_order_id: new_order.client_order_id(),
+                    price: new_order.price(),
+                    amount: new_order.amount(),
+                },
+            );
+
+            order = new_order;
+            replaces_count += 1;
+        }
+    }
+
+    /// Configuration of strategy which reserved balance for order
+    fn get_reservation_configuration(&self, order: &OrderRef) -> Option<ConfigurationDescriptor> {
+        let reservation_id = order.reservation_id()?;
+        let balance_manager = self.get_balance_manager()?;
+        let balance_manager = balance_manager.lock();
+        balance_manager
+            .get_reservation(reservation_id)
+            .map(|x| x.configuration_descriptor.clone())
+    }
+
+    /// Returns `None` if remaining amount is less than min amount or min cost of symbol
+    async fn replace_order_remainder(
+        &self,
+        order: &OrderRef,
+        remaining_amount: Amount,
+        price_offset: Decimal,
+        configuration_descriptor: Option<ConfigurationDescriptor>,
+        cancellation_token: CancellationToken,
+    ) -> Result<Option<OrderRef>> {
+        self.check_trading_enabled()?;
+
+        let header = order.fn_ref(|x| x.header.clone());
+        let symbol = self.get_symbol(header.currency_pair)?;
+
+        let replacement = get_replacement_price_and_amount(
+            &symbol,
+            header.side,
+            order.price(),
+            remaining_amount,
+            price_offset,
+        );
+        let (price, amount) = match replacement {
+            Some(replacement) => replacement,
+            None => {
+                log::info!(
+                    "Remaining amount {} of order {} on {} is less than min amount and isn't re-placed",
+                    remaining_amount,
+                    header.client_order_id,
+                    self.exchange_account_id
+                );
+                return Ok(None);
+            }
+        };
+
+        let new_order = replacement_order(&header, price, amount);
+        let (new_order, reservation_id) = match configuration_descriptor {
+            Some(configuration_descriptor) => {
+                let (new_order, reservation_id) =
+                    reserve_order(self, &new_order, configuration_descriptor)?;
+                (new_order, Some(reservation_id))
+            }
+            None => (new_order, None),
+        };
+
+        let new_order =
+            create_order_with_reservation(self, &new_order, reservation_id, cancellation_token)
+                .await?;
+        Ok(Some(new_order))
+    }
+
+    fn send_partial_fill_timeout(
+        &self,
+        order: &OrderRef,
+        remaining_amount: Amount,
+        action: PartialFillAction,
+    ) {
+        self.events_channel
+            .send_expected(ExchangeEvent::PartialFillTimeout(PartialFillTimeoutEvent {
+                exchange_account_id: self.exchange_account_id,
+                currency_pair: order.currency_pair(),
+                client_order_id: order.client_order_id(),
+                remaining_amount,
+                action,
+            }));
+    }
+}
+
+/// Order with all properties of replaced order except id, amount and reservation
+fn replacement_order(header: &OrderHeader, price: Price, amount: Amount) -> OrderCreating {
+    let mut header = header.clone();
+    header.client_order_id = ClientOrderId::generate(&header.strategy_name);
+    header.init_time = chrono::Utc::now();
+    header.amount = amount;
+    header.amount_kind = OrderAmountKind::Base;
+    header.reservation_id = None;
+
+    OrderCreating {
+        header: Arc::new(header),
+        price,
+    }
+}
+
+/// Price moved towards market by `price_offset` and rounded remaining amount of replacing order.
+/// Returns `None` if rounded amount is less than min amount of symbol at the new price
+fn get_replacement_price_and_amount(
+    symbol: &Symbol,
+    side: OrderSide,
+    price: Price,
+    remaining_amount: Amount,
+    price_offset: Decimal,
+) -> Option<(Price, Amount)> {
+    let price = match side {
+        OrderSide::Buy => price * (dec!(1) + price_offset),
+        OrderSide::Sell => price * (dec!(1) - price_offset),
+    };
+    let price = symbol.price_round(price, Round::TowardAggressive(side));
+    let amount = symbol.amount_round(remaining_amount, Round::Floor);
+
+    // Symbols without any amount restrictions accept any positive amount
+    let min_amount = symbol.get_min_amount(price).unwrap_or_default();
+    match amount > dec!(0) && amount >= min_amount {
+        true => Some((price, amount)),
+        false => None,
+    }
+}
+
+/// Completes when order is partially filled and there are no new fills during timeout
+async fn wait_order_stagnation(order: &OrderRef, timeout: Duration) {
+    loop {
+        let last_fill_time = order.fn_ref(|x| x.fills.last_fill_received_time());
+        let delay = match last_fill_time {
+            None => timeout,
+            Some(last_fill_time) => {
+                let elapsed = (time_manager::now() - last_fill_time)
+                    .to_std()
+                    .unwrap_or_default();
+                if elapsed < timeout {
+                    timeout - elapsed
+                } else if order.is_finished() {
+                    // Finished order is handled by `wait_order_finish`
+                    timeout
+                } else {
+                    return;
+                }
+            }
+        };
+
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::CurrencyPair;
+    use crate::orders::order::{PositionSide, ReservationId};
+    use crate::orders::order_builder::OrderBuilder;
+    use crate::test_util::SymbolBuilder;
+
+    fn symbol(min_amount: Option<Amount>, min_cost: Option<Price>) -> Symbol {
+        let mut symbol = SymbolBuilder::new("btc".into(), "usdt".into()).build();
+        symbol.min_amount = min_amount;
+        symbol.min_cost = min_cost;
+        symbol
+    }
+
+    #[test]
+    fn replacement_price_is_moved_towards_market() {
+        let symbol = symbol(Some(dec!(0.01)), None);
+
+        let buy = get_replacement_price_and_amount(
+            &symbol,
+            OrderSide::Buy,
+            dec!(100),
+            dec!(0.5555),
+            dec!(0.0015),
+        );
+        assert_eq!(buy, Some((dec!(100.2), dec!(0.555))));
+
+        let sell = get_replacement_price_and_amount(
+            &symbol,
+            OrderSide::Sell,
+            dec!(100),
+            dec!(0.5555),
+            dec!(0.0015),
+        );
+        assert_eq!(sell, Some((dec!(99.8), dec!(0.555))));
+    }
+
+    #[test]
+    fn remainder_less_than_min_amount_is_not_replaced() {
+        let symbol = symbol(Some(dec!(0.01)), None);
+
+        let replacement = get_replacement_price_and_amount(
+            &symbol,
+            OrderSide::Buy,
+            dec!(100),
+            dec!(0.0099),
+            dec!(0.001),
+        );
+        assert_eq!(replacement, None);
+    }
+
+    #[test]
+    fn remainder_less_than_min_cost_is_not_replaced() {
+        let symbol = symbol(None, Some(dec!(10)));
+
+        let below_min_cost = get_replacement_price_and_amount(
+            &symbol,
+            OrderSide::Sell,
+            dec!(100),
+            dec!(0.09),
+            dec!(0),
+        );
+        assert_eq!(below_min_cost, None);
+
+        let above_min_cost = get_replacement_price_and_amount(
+            &symbol,
+            OrderSide::Sell,
+            dec!(100),
+            dec!(0.1),
+            dec!(0),
+        );
+        assert_eq!(above_min_cost, Some((dec!(100), dec!(0.1))));
+    }
+
+    #[test]
+    fn remainder_rounded_to_zero_is_not_replaced() {
+        let symbol = symbol(None, None);
+
+        let replacement = get_replacement_price_and_amount(
+            &symbol,
+            OrderSide::Buy,
+            dec!(100),
+            dec!(0.0009),
+            dec!(0.001),
+        );
+        assert_eq!(replacement, None);
+    }
+
+    #[test]
+    fn replacement_order_keeps_properties_of_replaced_order() {
+        let replaced_order = OrderBuilder::new(
+            "Binance_0".parse().expect("in test"),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        )
+        .sell()
+        .limit(dec!(100))
+        .amount(dec!(2))
+        .maker_only()
+        .reservation_id(ReservationId::generate())
+        .signal_id("signal")
+        .strategy_name("TestStrategy")
+        .position_side(PositionSide::Short)
+        .expire_at(time_manager::now())
+        .reduce_only()
+        .build()
+        .expect("in test");
+        let replaced = &replaced_order.header;
+
+        let replacement = replacement_order(replaced, dec!(99.9), dec!(0.5));
+        let header = &replacement.header;
+
+        assert_eq!(replacement.price, dec!(99.9));
+        assert_eq!(header.amount, dec!(0.5));
+        assert_ne!(header.client_order_id, replaced.client_order_id);
+        assert_eq!(header.reservation_id, None);
+
+        assert_eq!(header.exchange_account_id, replaced.exchange_account_id);
+        assert_eq!(header.currency_pair, replaced.currency_pair);
+        assert_eq!(header.order_type, replaced.order_type);
+        assert_eq!(header.side, replaced.side);
+        assert_eq!(header.execution_type, replaced.execution_type);
+        assert_eq!(header.signal_id, replaced.signal_id);
+        assert_eq!(header.strategy_name, replaced.strategy_name);
+        assert_eq!(header.position_side, replaced.position_side);
+        assert_eq!(header.expire_at, replaced.expire_at);
+        assert!(header.reduce_only);
+    }
+}
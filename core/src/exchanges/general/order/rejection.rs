@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use mmb_utils::cancellation_token::CancellationToken;
+use thiserror::Error;
+
+use crate::error::MmbResult;
+use crate::exchanges::common::{ExchangeError, ExchangeErrorType};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::symbol::{Round, Symbol};
+use crate::exchanges::timeouts::requests_timeout_manager::RequestGroupId;
+use crate::orders::order::{ClientOrderId, OrderAmountKind, OrderCreating};
+use crate::orders::pool::OrderRef;
+
+/// Class of create order rejection, which is the same for all exchanges
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RejectionReason {
+    InsufficientBalance,
+    /// Price or amount doesn't fit tick, lot size or precision of market
+    BadPrecision,
+    RateLimit,
+    /// Order is blocked by engine before sending, e.g. by order filter or trading window
+    RiskBlocked,
+    MarketHalted,
+    /// Order is invalid by other reason, e.g. it's less than min notional of market
+    InvalidOrder,
+    Other,
+}
+
+/// Parts of messages of Binance filters and errors of other exchanges about precision
+const PRECISION_ERROR_MARKERS: [&str; 5] =
+    ["precision", "lot_size", "price_filter", "tick", "step"];
+
+impl RejectionReason {
+    pub fn from_exchange_error(exchange_error: &ExchangeError) -> Self {
+        match exchange_error.error_type {
+            ExchangeErrorType::InsufficientFunds => RejectionReason::InsufficientBalance,
+            ExchangeErrorType::RateLimit => RejectionReason::RateLimit,
+            ExchangeErrorType::TradingHalted => RejectionReason::MarketHalted,
+            ExchangeErrorType::InvalidOrder => {
+                let message = exchange_error.message.to_lowercase();
+                match PRECISION_ERROR_MARKERS.iter().any(|x| message.contains(x)) {
+                    true => RejectionReason::BadPrecision,
+                    false => RejectionReason::InvalidOrder,
+                }
+            }
+            _ => RejectionReason::Other,
+        }
+    }
+}
+
+/// Error of `Exchange::create_order()` if order is rejected by exchange or by checks of engine
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{message}")]
+pub struct OrderRejectedError {
+    pub client_order_id: ClientOrderId,
+    pub reason: RejectionReason,
+    pub message: String,
+}
+
+impl OrderRejectedError {
+    pub fn new(client_order_id: ClientOrderId, reason: RejectionReason, message: String) -> Self {
+        Self {
+            client_order_id,
+            reason,
+            message,
+        }
+    }
+}
+
+pub trait RemediationHook: Send + Sync {
+    /// Returns amended order which is created instead of rejected one or `None` if order
+    /// shouldn't be retried. Client order id of amended order is replaced with a new one
+    fn remediate(
+        &self,
+        order: &OrderCreating,
+        symbol: &Symbol,
+        rejection: &OrderRejectedError,
+    ) -> Option<OrderCreating>;
+}
+
+/// Rounds price of order to the passive side and amount down to precision of symbol.
+/// Order with amount in quote currency isn't remediated, because amount precision of symbol
+/// is in base currency
+pub struct RoundToPrecision;
+
+impl RemediationHook for RoundToPrecision {
+    fn remediate(
+        &self,
+        order: &OrderCreating,
+        symbol: &Symbol,
+        _rejection: &OrderRejectedError,
+    ) -> Option<OrderCreating> {
+        if order.header.amount_kind == OrderAmountKind::Quote {
+            return None;
+        }
+
+        let price = symbol.price_round(order.price, Round::TowardPassive(order.header.side));
+        let amount = symbol.amount_round(order.header.amount, Round::Floor);
+        if (price == order.price && amount == order.header.amount) || amount.is_zero() {
+            return None;
+        }
+
+        let mut header = (*order.header).clone();
+        header.amount = amount;
+        Some(OrderCreating {
+            header: Arc::new(header),
+            price,
+        })
+    }
+}
+
+/// Remediation hooks of strategy by rejection reasons
+#[derive(Default, Clone)]
+pub struct RemediationHooks {
+    hooks: HashMap<RejectionReason, Arc<dyn RemediationHook>>,
+}
+
+impl RemediationHooks {
+    pub fn register(&mut self, reason: RejectionReason, hook: Arc<dyn RemediationHook>) {
+        let _ = self.hooks.insert(reason, hook);
+    }
+
+    fn remediate(
+        &self,
+        order: &OrderCreating,
+        symbol: &Symbol,
+        rejection: &OrderRejectedError,
+    ) -> Option<OrderCreating> {
+        let mut amended = self
+            .hooks
+            .get(&rejection.reason)?
+            .remediate(order, symbol, rejection)?;
+
+        let mut header = (*amended.header).clone();
+        header.client_order_id = ClientOrderId::generate(&header.strategy_name);
+        amended.header = Arc::new(header);
+        Some(amended)
+    }
+}
+
+impl Exchange {
+    /// Creates order like `create_order()`, but if order is rejected and there is hook for
+    /// rejection reason, amended order is created once. Reservation of rejected order is kept
+    /// by amended order, so it should be released by caller only if retry fails too
+    pub async fn create_order_with_remediation(
+        &self,
+        order_to_create: &OrderCreating,
+        remediation_hooks: &RemediationHooks,
+        pre_reservation_group_id: Option<RequestGroupId>,
+        cancellation_token: CancellationToken,
+    ) -> MmbResult<OrderRef> {
+        let error = match self
+            .create_order(
+                order_to_create,
+                pre_reservation_group_id,
+                cancellation_token.clone(),
+            )
+            .await
+        {
+            Ok(order) => return Ok(order),
+            Err(error) => error,
+        };
+
+        let rejection = match error.order_rejection() {
+            Some(rejection) => rejection,
+            None => return Err(error),
+        };
+        let symbol = self.get_symbol(order_to_create.header.currency_pair)?;
+        let amended_order = match remediation_hooks.remediate(order_to_create, &symbol, rejection) {
+            Some(amended_order) => amended_order,
+            None => return Err(error),
+        };
+
+        log::info!(
+            "Order {} is rejected on {} by reason {:?}, creating amended order {}",
+            rejection.client_order_id,
+            self.exchange_account_id,
+            rejection.reason,
+            amended_order.header.client_order_id
+        );
+
+        self.create_order(&amended_order, pre_reservation_group_id, cancellation_token)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::ExchangeAccountId;
+    use crate::orders::order::{OrderExecutionType, OrderHeader, OrderSide, OrderType};
+    use crate::test_util::SymbolBuilder;
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+
+    fn exchange_error(error_type: ExchangeErrorType, message: &str) -> ExchangeError {
+        ExchangeError::new(error_type, message.to_owned(), None)
+    }
+
+    #[test]
+    fn exchange_errors_are_classified() {
+        let cases = [
+            (
+                ExchangeErrorType::InvalidOrder,
+                "Precision is over the maximum defined for this asset.",
+                RejectionReason::BadPrecision,
+            ),
+            (
+                ExchangeErrorType::InvalidOrder,
+                "Filter failure: LOT_SIZE",
+                RejectionReason::BadPrecision,
+            ),
+            (
+                ExchangeErrorType::InvalidOrder,
+                "Filter failure: MIN_NOTIONAL",
+                RejectionReason::InvalidOrder,
+            ),
+            (
+                ExchangeErrorType::InsufficientFunds,
+                "Account has insufficient balance for requested action.",
+                RejectionReason::InsufficientBalance,
+            ),
+            (
+                ExchangeErrorType::RateLimit,
+                "Too many requests;",
+                RejectionReason::RateLimit,
+            ),
+            (
+                ExchangeErrorType::TradingHalted,
+                "Market is closed.",
+                RejectionReason::MarketHalted,
+            ),
+            (ExchangeErrorType::Unknown, "", RejectionReason::Other),
+        ];
+
+        for (error_type, message, expected) in cases {
+            assert_eq!(
+                RejectionReason::from_exchange_error(&exchange_error(error_type, message)),
+                expected,
+                "{message}"
+            );
+        }
+    }
+
+    #[test]
+    fn precision_rejection_is_remediated_by_rounding() {
+        let symbol = SymbolBuilder::new("btc".into(), "usdt".into()).build();
+        let header = OrderHeader::new(
+            "rejected".into(),
+            Utc::now(),
+            ExchangeAccountId::new("Binance".into(), 0),
+            symbol.currency_pair(),
+            OrderType::Limit,
+            OrderSide::Buy,
+            dec!(0.12345),
+            OrderExecutionType::MakerOnly,
+            None,
+            None,
+            "test".to_owned(),
+        );
+        let order = OrderCreating {
+            header,
+            price: dec!(50000.15),
+        };
+        let rejection = OrderRejectedError::new(
+            "rejected".into(),
+            RejectionReason::BadPrecision,
+            "Exchange error: Filter failure: PRICE_FILTER".to_owned(),
+        );
+
+        let mut hooks = RemediationHooks::default();
+        assert!(hooks.remediate(&order, &symbol, &rejection).is_none());
+
+        hooks.register(RejectionReason::BadPrecision, Arc::new(RoundToPrecision));
+        let amended = hooks
+            .remediate(&order, &symbol, &rejection)
+            .expect("in test");
+        assert_eq!(amended.price, dec!(50000.1));
+        assert_eq!(amended.header.amount, dec!(0.123));
+        assert_ne!(amended.header.client_order_id, order.header.client_order_id);
+
+        // Already rounded order isn't retried
+        assert!(RoundToPrecision
+            .remediate(&amended, &symbol, &rejection)
+            .is_none());
+
+        let quote_order = OrderCreating {
+            header: order
+                .header
+                .clone()
+                .with_amount_kind(OrderAmountKind::Quote),
+            price: order.price,
+        };
+        assert!(RoundToPrecision
+            .remediate(&quote_order, &symbol, &rejection)
+            .is_none());
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::Result;
+use mmb_utils::cancellation_token::CancellationToken;
+
+use crate::exchanges::common::{ExchangeError, ExchangeErrorType};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::request_type::RequestType;
+use crate::orders::fill::EventSourceType;
+use crate::orders::order::OrderStatus;
+use crate::orders::pool::OrderRef;
+
+impl Exchange {
+    /// Requests order info through REST when request is allowed by timeout manager and applies
+    /// missed creation, fills and cancellation. Buffered fills and cancellations of order
+    /// are applied on its creation. Returns `true` if status of order isn't ambiguous anymore
+    pub(crate) async fn probe_order_status(
+        &self,
+        order: &OrderRef,
+        cancellation_token: CancellationToken,
+    ) -> Result<bool> {
+        self.timeout_manager
+            .reserve_when_available(
+                self.exchange_account_id,
+                RequestType::GetOrderInfo,
+                None,
+                cancellation_token.clone(),
+            )?
+            .await;
+
+        let client_order_id = order.client_order_id();
+        let order_info = match self.get_order_info(order).await {
+            Ok(order_info) => order_info,
+            Err(error) => {
+                log::warn!(
+                    "Unable to get order info for probing status of order {} on {}: {:?}",
+                    client_order_id,
+                    self.exchange_account_id,
+                    error
+                );
+                return Ok(false);
+            }
+        };
+
+        if order.status() == OrderStatus::Creating {
+            match order_info.order_status {
+                OrderStatus::FailedToCreate => {
+                    let exchange_error = ExchangeError::new(
+                        ExchangeErrorType::Unknown,
+                        "Order creation failed according to order info".to_owned(),
+                        None,
+                    );
+                    self.handle_create_order_failed(
+                        self.exchange_account_id,
+                        &client_order_id,
+                        &exchange_error,
+                        &EventSourceType::RestFallback,
+                    )?;
+                }
+                _ => self.handle_create_order_succeeded(
+                    self.exchange_account_id,
+                    &client_order_id,
+                    &order_info.exchange_order_id,
+                    &EventSourceType::RestFallback,
+                )?,
+            }
+        }
+
+        if !order.is_finished() && order_info.filled_amount > order.filled_amount() {
+            self.check_order_fills(order, false, None, cancellation_token)
+                .await?;
+        }
+
+        if !order.is_finished() && order_info.order_status == OrderStatus::Canceled {
+            self.handle_cancel_order_succeeded(
+                Some(&client_order_id),
+                &order_info.exchange_order_id,
+                Some(order_info.filled_amount),
+                EventSourceType::RestFallback,
+            );
+        }
+
+        Ok(!is_order_status_ambiguous(order))
+    }
+}
+
+/// Result of creation or cancellation of not finished order isn't received yet
+pub(crate) fn is_order_status_ambiguous(order: &OrderRef) -> bool {
+    matches!(
+        order.status(),
+        OrderStatus::Creating | OrderStatus::Canceling
+    )
+}
@@ -1,6 +1,6 @@
 use std::time::Duration;
 
-use anyhow::{bail, Context, Result};
+use anyhow::{Context, Result};
 use chrono::Utc;
 use dashmap::mapref::entry::Entry::{Occupied, Vacant};
 use log::log;
@@ -11,6 +11,8 @@ use tokio::sync::broadcast;
 use tokio::time::sleep;
 
 use super::cancel::CancelOrderResult;
+use super::wait_outcome::WaitOutcome;
+use crate::error::MmbResult;
 use crate::exchanges::{
     general::request_type::RequestType, timeouts::requests_timeout_manager::RequestGroupId,
 };
@@ -24,14 +26,56 @@ use crate::{
     },
 };
 
+/// Attempts of `wait_cancel_order_confirmed` to cancel order which isn't cancelled in time
+const CONFIRMED_CANCEL_ATTEMPTS: u32 = 3;
+
 impl Exchange {
+    /// Cancels order like `wait_cancel_order`, but repeats cancellation until exchange confirms it.
+    /// Returns `MmbError::Timeout` if order is still open after all attempts or waiting is cancelled
+    pub async fn wait_cancel_order_confirmed(
+        &self,
+        order: OrderRef,
+        pre_reservation_group_id: Option<RequestGroupId>,
+        check_order_fills: bool,
+        cancellation_token: CancellationToken,
+    ) -> MmbResult<WaitOutcome> {
+        let mut outcome = WaitOutcome::Timeout;
+        for attempt in 1..=CONFIRMED_CANCEL_ATTEMPTS {
+            // Requests of pre-reserved group are used only once
+            let pre_reservation_group_id = pre_reservation_group_id.filter(|_| attempt == 1);
+            outcome = self
+                .wait_cancel_order(
+                    order.clone(),
+                    pre_reservation_group_id,
+                    check_order_fills,
+                    cancellation_token.clone(),
+                )
+                .await?;
+
+            if outcome.is_finished() || cancellation_token.is_cancellation_requested() {
+                break;
+            }
+
+            log::warn!(
+                "Cancellation of order {} on {} isn't confirmed in time (attempt {} of {})",
+                order.client_order_id(),
+                self.exchange_account_id,
+                attempt,
+                CONFIRMED_CANCEL_ATTEMPTS
+            );
+        }
+
+        outcome.into_finished(&order.client_order_id())
+    }
+
+    /// Cancels order and waits until it's finished. Order can be filled completely instead of cancellation
     pub async fn wait_cancel_order(
         &self,
         order: OrderRef,
         pre_reservation_group_id: Option<RequestGroupId>,
         check_order_fills: bool,
         cancellation_token: CancellationToken,
-    ) -> Result<()> {
+    ) -> MmbResult<WaitOutcome> {
         log::info!(
             "Executing wait_cancel_order() with order: {} {:?} {}",
             order.client_order_id(),
@@ -40,8 +84,8 @@ impl Exchange {
         );
 
         // we move rx out of the closure to unlock Dashmap while waiting
-        let rx = match self.wait_cancel_order.entry(order.client_order_id()) {
-            Occupied(entry) => Some(entry.get().subscribe()),
+        let mut rx = match self.wait_cancel_order.entry(order.client_order_id()) {
+            Occupied(entry) => entry.get().subscribe(),
             Vacant(vacant_entry) => {
                 // Be sure value will be removed anyway
                 let _guard = scopeguard::guard((), |_| {
@@ -59,18 +103,21 @@ impl Exchange {
                     )
                     .await?;
 
-                let _ = tx.send(outcome);
-                None
+                let _ = tx.send(outcome.clone());
+                return Ok(outcome);
             }
         };
 
-        if let Some(mut rx) = rx {
-            tokio::select! {
-                _ = rx.recv() => nothing_to_do(),
-                _ = cancellation_token.when_cancelled() => nothing_to_do()
+        tokio::select! {
+            outcome = rx.recv() => {
+                if let Ok(outcome) = outcome {
+                    return Ok(outcome);
+                }
             }
+            _ = cancellation_token.when_cancelled() => nothing_to_do()
         }
-        Ok(())
+
+        Ok(WaitOutcome::from_order(&order, true))
     }
 
     async fn wait_cancel_order_work(
@@ -79,14 +126,16 @@ impl Exchange {
         pre_reservation_group_id: Option<RequestGroupId>,
         check_order_fills: bool,
         cancellation_token: CancellationToken,
-    ) -> Result<()> {
+    ) -> Result<WaitOutcome> {
+        self.check_orders_not_handed_over()?;
+
         if order.status() == OrderStatus::Creating {
             self.create_order_created_task(order, cancellation_token.clone())
                 .await?;
         }
 
         if order.is_finished() {
-            return Ok(());
+            return Ok(WaitOutcome::from_order(order, true));
         }
 
         let is_canceling_from_wait_cancel_order = order.fn_mut(|order| {
@@ -102,7 +151,7 @@ impl Exchange {
                 order.exchange_order_id()
             );
 
-            return Ok(());
+            return Ok(WaitOutcome::from_order(order, true));
         }
 
         let order_is_finished_token = cancellation_token.create_linked_token();
@@ -156,7 +205,12 @@ impl Exchange {
                 }
                 _ = sleep(Duration::from_secs(10)) => {
                     if self.features.allowed_cancel_event_source_type != AllowedEventSourceType::All {
-                        bail!("Order was expected to cancel explicitly via Rest or Web Socket but got timeout instead")
+                        log::warn!("Order {} {:?} on {} was expected to cancel explicitly via Rest or Web Socket but got timeout instead",
+                            order.client_order_id(),
+                            order.exchange_order_id(),
+                            self.exchange_account_id);
+
+                        return Ok(WaitOutcome::Timeout);
                     }
 
                    log::warn!("Cancel response TimedOut - re-cancelling order {} {:?} {}",
@@ -228,7 +282,7 @@ impl Exchange {
             self.add_event_on_order_change(order, OrderEventType::CancelOrderSucceeded)?;
         }
 
-        Ok(())
+        Ok(WaitOutcome::from_order(order, true))
     }
 
     async fn order_cancelled(
@@ -10,6 +10,7 @@ use dashmap::mapref::entry::Entry::{Occupied, Vacant};
 use mmb_utils::nothing_to_do;
 use tokio::sync::{broadcast, oneshot};
 
+use crate::error::MmbResult;
 use crate::exchanges::common::ToStdExpected;
 use crate::exchanges::common::{CurrencyCode, ExchangeErrorType};
 use crate::exchanges::general::exchange::RequestResult;
@@ -24,19 +25,21 @@ use crate::orders::order::{OrderExecutionType, OrderInfo, OrderStatus, OrderType
 use crate::{exchanges::general::exchange::Exchange, orders::pool::OrderRef};
 
 use super::get_order_trades::OrderTrade;
+use super::wait_outcome::WaitOutcome;
 
 impl Exchange {
+    /// Waits until order is filled or cancelled
     pub async fn wait_order_finish(
         self: Arc<Self>,
         order: &OrderRef,
         pre_reservation_group_id: Option<RequestGroupId>,
         cancellation_token: CancellationToken,
-    ) -> Result<OrderRef> {
+    ) -> MmbResult<WaitOutcome> {
         // TODO make MetricsRegistry.Metrics.Measure.Timer.Time(MetricsRegistry.Timers.WaitOrderFinishTimer,
         //     MetricsRegistry.Timers.CreateExchangeTimerTags(order.ExchangeId));
 
         if order.status() == OrderStatus::FailedToCreate {
-            return Ok(order.clone());
+            return Ok(WaitOutcome::FailedToCreate);
         }
 
         match self.wait_finish_order.entry(order.client_order_id()) {
@@ -45,11 +48,15 @@ impl Exchange {
                 let mut rx = tx.subscribe();
                 // Just wait until order finishing future completed or operation cancelled
                 tokio::select! {
-                    _ = rx.recv() => nothing_to_do(),
+                    outcome = rx.recv() => {
+                        if let Ok(outcome) = outcome {
+                            return Ok(outcome);
+                        }
+                    }
                     _ = cancellation_token.when_cancelled() => nothing_to_do()
                 }
 
-                Ok(order.clone())
+                Ok(WaitOutcome::from_order(order, false))
             }
             Vacant(vacant_entry) => {
                 // Be sure value will be removed anyway
@@ -65,9 +72,9 @@ impl Exchange {
                     .wait_finish_order_work(order, pre_reservation_group_id, cancellation_token)
                     .await?;
 
-                let _ = tx.send(outcome);
+                let _ = tx.send(outcome.clone());
 
-                Ok(order.clone())
+                Ok(outcome)
             }
         }
     }
@@ -77,7 +84,7 @@ impl Exchange {
         order: &OrderRef,
         pre_reservation_group_id: Option<RequestGroupId>,
         cancellation_token: CancellationToken,
-    ) -> Result<OrderRef> {
+    ) -> Result<WaitOutcome> {
         let has_websocket_notification = self.features.websocket_options.execution_notification;
 
         if !has_websocket_notification {
@@ -124,10 +131,11 @@ impl Exchange {
                 .and_modify(|value| *value -= 1)
                 .or_insert(0);
         } else {
-            let _ = self.create_order_finish_future(order, linked_cancellation_token.clone());
+            self.create_order_finish_future(order, linked_cancellation_token.clone())
+                .await?;
         }
 
-        Ok(order.clone())
+        Ok(WaitOutcome::from_order(order, false))
     }
 
     pub(crate) async fn poll_order_fills(
@@ -451,6 +459,7 @@ impl Exchange {
                             order_side: None,
                             order_amount: None,
                             fill_date: None,
+                            position_side: None,
                         };
                         self.handle_order_filled(event_data);
 
@@ -497,6 +506,7 @@ impl Exchange {
             order_side: None,
             order_amount: None,
             fill_date: Some(order_trade.datetime),
+            position_side: None,
         };
 
         self.handle_order_filled(event_data)
@@ -0,0 +1,81 @@
+use crate::error::{MmbError, MmbResult};
+use crate::orders::fill::{EventSourceType, OrderFill};
+use crate::orders::order::{ClientOrderId, OrderStatus};
+use crate::orders::pool::OrderRef;
+
+/// Result of `wait_cancel_order` and `wait_order_finish`. Order can be still open if it's `Timeout`
+#[must_use]
+#[derive(Debug, Clone)]
+pub enum WaitOutcome {
+    /// Order is cancelled and cancellation is received via web socket
+    CancelledViaWs,
+    /// Order is cancelled and cancellation is received as response of cancel request
+    CancelledViaRest,
+    /// Order is cancelled and cancellation is found by fallback request of order status
+    CancelledViaFallback,
+    /// Order is completely filled before it was cancelled
+    FilledWhileCanceling {
+        fills: Vec<OrderFill>,
+    },
+    /// Order is completely filled
+    Filled {
+        fills: Vec<OrderFill>,
+    },
+    FailedToCreate,
+    /// Order isn't finished: exchange didn't confirm cancellation in time or waiting was cancelled
+    Timeout,
+}
+
+impl WaitOutcome {
+    pub(crate) fn from_order(order: &OrderRef, is_canceling: bool) -> Self {
+        match order.status() {
+            OrderStatus::Canceled => {
+                match order.fn_ref(|x| x.internal_props.cancellation_event_source_type) {
+                    Some(EventSourceType::WebSocket) => WaitOutcome::CancelledViaWs,
+                    Some(EventSourceType::RestFallback) => WaitOutcome::CancelledViaFallback,
+                    Some(EventSourceType::Rest) | None => WaitOutcome::CancelledViaRest,
+                }
+            }
+            OrderStatus::Completed => {
+                let (fills, _) = order.get_fills();
+                if is_canceling {
+                    WaitOutcome::FilledWhileCanceling { fills }
+                } else {
+                    WaitOutcome::Filled { fills }
+                }
+            }
+            OrderStatus::FailedToCreate => WaitOutcome::FailedToCreate,
+            _ => WaitOutcome::Timeout,
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        matches!(
+            self,
+            WaitOutcome::CancelledViaWs
+                | WaitOutcome::CancelledViaRest
+                | WaitOutcome::CancelledViaFallback
+        )
+    }
+
+    pub fn is_filled(&self) -> bool {
+        matches!(
+            self,
+            WaitOutcome::FilledWhileCanceling { .. } | WaitOutcome::Filled { .. }
+        )
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, WaitOutcome::Timeout)
+    }
+
+    /// `Timeout` becomes error, so order which is still open isn't treated as finished
+    pub fn into_finished(self, client_order_id: &ClientOrderId) -> MmbResult<Self> {
+        match self {
+            WaitOutcome::Timeout => Err(MmbError::timeout(format!(
+                "Order {client_order_id} isn't finished in time"
+            ))),
+            outcome => Ok(outcome),
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use futures::FutureExt;
+use itertools::Itertools;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::send_expected::SendExpectedByRef;
+
+use crate::exchanges::common::CurrencyPair;
+use crate::exchanges::events::ExchangeEvent;
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::helpers::get_rest_error;
+use crate::exchanges::general::request_type::RequestType;
+use crate::infrastructure::{spawn_supervised, RestartPolicy};
+use crate::misc::time::time_manager;
+use crate::order_book::event::{EventType, OrderBookEvent};
+use crate::order_book::order_book_data::OrderBookData;
+use crate::settings::OrderBookPollingSettings;
+
+const DEFAULT_REQUEST_RANGE_PERCENT: f64 = 20.;
+
+impl Exchange {
+    pub async fn get_order_book_snapshot(
+        &self,
+        currency_pair: CurrencyPair,
+        depth: usize,
+        cancellation_token: CancellationToken,
+    ) -> Result<OrderBookData> {
+        self.timeout_manager
+            .reserve_when_available(
+                self.exchange_account_id,
+                RequestType::GetOrderBook,
+                None,
+                cancellation_token,
+            )?
+            .await
+            .into_result()?;
+
+        let response = self
+            .exchange_client
+            .request_order_book_snapshot(currency_pair, depth)
+            .await?;
+        if let Some(error) = get_rest_error(&response, self.exchange_account_id, false) {
+            bail!(
+                "Unable to get order book snapshot of {} on {}: {:?}",
+                currency_pair,
+                self.exchange_account_id,
+                error
+            );
+        }
+
+        self.exchange_client.parse_order_book_snapshot(&response)
+    }
+
+    /// Currency pairs are polled in turn with interval derived from request limit of exchange.
+    /// Snapshots are sent as usual order book events, so local snapshots and strategies work the same
+    /// way as with websocket depth stream
+    pub(crate) fn start_order_book_polling(
+        self: &Arc<Self>,
+        settings: OrderBookPollingSettings,
+        cancellation_token: CancellationToken,
+    ) {
+        let currency_pairs = match &settings.currency_pairs {
+            Some(currency_pairs) => currency_pairs.clone(),
+            None => self.symbols.iter().map(|x| *x.key()).collect_vec(),
+        };
+
+        if currency_pairs.is_empty() {
+            log::warn!(
+                "There are no currency pairs for order book polling on {}",
+                self.exchange_account_id
+            );
+            return;
+        }
+
+        let request_range = settings
+            .request_range_percent
+            .unwrap_or(DEFAULT_REQUEST_RANGE_PERCENT);
+
+        let exchange_weak = Arc::downgrade(self);
+        let action_factory = move || {
+            let exchange_weak = exchange_weak.clone();
+            let currency_pairs = currency_pairs.clone();
+            let settings = settings.clone();
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                let mut last_request_time = None;
+                for currency_pair in currency_pairs.iter().cycle() {
+                    let exchange = match exchange_weak.upgrade() {
+                        Some(exchange) => exchange,
+                        None => return Ok(()),
+                    };
+
+                    exchange
+                        .polling_timeout_manager
+                        .wait(last_request_time, request_range, cancellation_token.clone())
+                        .await;
+                    if cancellation_token.is_cancellation_requested() {
+                        return Ok(());
+                    }
+
+                    last_request_time = Some(time_manager::now());
+                    match exchange
+                        .get_order_book_snapshot(
+                            *currency_pair,
+                            settings.depth,
+                            cancellation_token.clone(),
+                        )
+                        .await
+                    {
+                        Ok(order_book_data) => {
+                            exchange.send_polled_order_book(*currency_pair, order_book_data)
+                        }
+                        Err(error) => log::warn!("{:?}", error),
+                    }
+                }
+
+                Ok(())
+            }
+            .boxed()
+        };
+
+        let _ = spawn_supervised(
+            &format!("Order book polling of {}", self.exchange_account_id),
+            RestartPolicy::default(),
+            action_factory,
+        );
+    }
+
+    fn send_polled_order_book(&self, currency_pair: CurrencyPair, order_book_data: OrderBookData) {
+        let order_book_event = OrderBookEvent::new(
+            time_manager::now(),
+            self.exchange_account_id,
+            currency_pair,
+            String::new(),
+            EventType::Snapshot,
+            Arc::new(order_book_data),
+        );
+
+        self.events_channel
+            .send_expected(ExchangeEvent::OrderBookEvent(order_book_event));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::exchanges::general::test_helper::get_test_exchange;
+    use crate::exchanges::timeouts::requests_timeout_manager_factory::{
+        RequestTimeoutArguments, RequestsTimeoutManagerFactory,
+    };
+    use crate::infrastructure::init_lifetime_manager;
+    use crate::order_book_data;
+
+    #[test]
+    fn polled_order_book_is_sent_as_snapshot() {
+        let (exchange, mut rx) = get_test_exchange(false);
+        let currency_pair = CurrencyPair::from_codes("phb".into(), "btc".into());
+        let order_book_data = order_book_data![
+            dec!(1.1) => dec!(2),
+            ;
+            dec!(0.9) => dec!(3),
+        ];
+
+        exchange.send_polled_order_book(currency_pair, order_book_data.clone());
+
+        let order_book_event = match rx.try_recv().expect("event should be sent") {
+            ExchangeEvent::OrderBookEvent(order_book_event) => order_book_event,
+            event => panic!("Unexpected event {:?}", event),
+        };
+        assert!(matches!(order_book_event.event_type, EventType::Snapshot));
+        assert_eq!(
+            order_book_event.exchange_account_id,
+            exchange.exchange_account_id
+        );
+        assert_eq!(order_book_event.currency_pair, currency_pair);
+        assert_eq!(order_book_event.data.asks, order_book_data.asks);
+        assert_eq!(order_book_event.data.bids, order_book_data.bids);
+    }
+
+    #[tokio::test]
+    async fn snapshot_request_fails_if_exchange_does_not_support_it() {
+        let _ = init_lifetime_manager();
+        let (exchange, mut rx) = get_test_exchange(false);
+        exchange.timeout_manager.add_exchange(
+            exchange.exchange_account_id,
+            RequestsTimeoutManagerFactory::from_requests_per_period(
+                RequestTimeoutArguments::from_requests_per_minute(1200),
+                exchange.exchange_account_id,
+            ),
+        );
+        let currency_pair = CurrencyPair::from_codes("phb".into(), "btc".into());
+
+        let result = exchange
+            .get_order_book_snapshot(currency_pair, 20, CancellationToken::default())
+            .await;
+
+        assert!(result.is_err());
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use futures::FutureExt;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::common::ExchangeAccountId;
+use crate::exchanges::general::exchange::Exchange;
+use crate::orders::order::{ClientOrderId, ExchangeOrderId};
+use crate::services::scheduler::{Schedule, Scheduler};
+use crate::storage::Storage;
+
+/// Count of the latest created orders which ids are kept for exchange account
+const MAX_ORDER_IDS: usize = 10_000;
+const ORDER_IDS_NAMESPACE: &str = "order_ids";
+const ORDER_IDS_SAVING_PERIOD: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct OrderIdsRecord {
+    exchange_order_id: ExchangeOrderId,
+    client_order_id: ClientOrderId,
+}
+
+#[derive(Default)]
+struct OrderIdsState {
+    by_exchange_id: HashMap<ExchangeOrderId, ClientOrderId>,
+    /// Exchange order ids in order of creation for removing of the oldest ones
+    queue: VecDeque<ExchangeOrderId>,
+}
+
+impl OrderIdsState {
+    fn add(&mut self, exchange_order_id: ExchangeOrderId, client_order_id: ClientOrderId) {
+        if self
+            .by_exchange_id
+            .insert(exchange_order_id.clone(), client_order_id)
+            .is_some()
+        {
+            return;
+        }
+
+        self.queue.push_back(exchange_order_id);
+        while self.queue.len() > MAX_ORDER_IDS {
+            if let Some(oldest) = self.queue.pop_front() {
+                let _ = self.by_exchange_id.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// Reverse lookup of client order ids by exchange order ids of created orders.
+/// Ids are saved to storage, so late fills and cancellations of orders created before restart
+/// are attributed to them instead of being treated as events of unknown external orders
+pub struct OrderIds {
+    exchange_account_id: ExchangeAccountId,
+    state: Mutex<OrderIdsState>,
+}
+
+impl OrderIds {
+    pub fn new(exchange_account_id: ExchangeAccountId) -> Self {
+        Self {
+            exchange_account_id,
+            state: Mutex::new(OrderIdsState::default()),
+        }
+    }
+
+    pub fn add(&self, exchange_order_id: ExchangeOrderId, client_order_id: ClientOrderId) {
+        self.state.lock().add(exchange_order_id, client_order_id);
+    }
+
+    pub fn get_client_order_id(
+        &self,
+        exchange_order_id: &ExchangeOrderId,
+    ) -> Option<ClientOrderId> {
+        self.state
+            .lock()
+            .by_exchange_id
+            .get(exchange_order_id)
+            .cloned()
+    }
+
+    pub async fn save(&self, storage: &dyn Storage) -> Result<()> {
+        let records = {
+            let state = self.state.lock();
+            state
+                .queue
+                .iter()
+                .filter_map(|exchange_order_id| {
+                    state
+                        .by_exchange_id
+                        .get(exchange_order_id)
+                        .map(|client_order_id| OrderIdsRecord {
+                            exchange_order_id: exchange_order_id.clone(),
+                            client_order_id: client_order_id.clone(),
+                        })
+                })
+                .collect::<Vec<_>>()
+        };
+
+        storage
+            .put_serialized(
+                ORDER_IDS_NAMESPACE,
+                &self.exchange_account_id.to_string(),
+                &records,
+            )
+            .await
+    }
+
+    pub async fn restore(&self, storage: &dyn Storage) -> Result<()> {
+        let records: Vec<OrderIdsRecord> = match storage
+            .get_deserialized(ORDER_IDS_NAMESPACE, &self.exchange_account_id.to_string())
+            .await?
+        {
+            Some(records) => records,
+            None => return Ok(()),
+        };
+
+        let mut state = self.state.lock();
+        for record in records {
+            state.add(record.exchange_order_id, record.client_order_id);
+        }
+
+        Ok(())
+    }
+}
+
+impl Exchange {
+    /// Client order id of order created by engine, including orders created before restart
+    pub fn get_client_order_id(
+        &self,
+        exchange_order_id: &ExchangeOrderId,
+    ) -> Option<ClientOrderId> {
+        match self.orders.cache_by_exchange_id.get(exchange_order_id) {
+            Some(order_ref) => Some(order_ref.client_order_id()),
+            None => self.order_ids.get_client_order_id(exchange_order_id),
+        }
+    }
+
+    pub async fn restore_order_ids(&self, storage: &dyn Storage) -> Result<()> {
+        self.order_ids.restore(storage).await
+    }
+
+    pub(crate) fn schedule_order_ids_saving(
+        self: &Arc<Self>,
+        scheduler: &Arc<Scheduler>,
+        storage: Arc<dyn Storage>,
+    ) {
+        let exchange_weak = Arc::downgrade(self);
+        let save_order_ids = move |_| {
+            let exchange_weak = exchange_weak.clone();
+            let storage = storage.clone();
+            async move {
+                let exchange = match exchange_weak.upgrade() {
+                    Some(exchange) => exchange,
+                    None => return,
+                };
+
+                if let Err(error) = exchange.order_ids.save(storage.as_ref()).await {
+                    log::warn!(
+                        "Unable to save order ids of {}: {:?}",
+                        exchange.exchange_account_id,
+                        error
+                    );
+                }
+            }
+            .boxed()
+        };
+
+        let _ = scheduler.schedule(
+            &format!("Save order ids of {}", self.exchange_account_id),
+            Schedule::Every(ORDER_IDS_SAVING_PERIOD),
+            save_order_ids,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::memory::MemoryStorage;
+
+    fn exchange_account_id() -> ExchangeAccountId {
+        ExchangeAccountId::new("Binance".into(), 0)
+    }
+
+    #[test]
+    fn oldest_order_ids_are_removed() {
+        let mut state = OrderIdsState::default();
+        for i in 0..MAX_ORDER_IDS + 1 {
+            state.add(i.to_string().as_str().into(), i.to_string().as_str().into());
+        }
+
+        assert_eq!(state.by_exchange_id.len(), MAX_ORDER_IDS);
+        assert!(!state.by_exchange_id.contains_key(&"0".into()));
+    }
+
+    #[tokio::test]
+    async fn order_ids_are_restored_from_storage() {
+        let storage = MemoryStorage::default();
+
+        let order_ids = OrderIds::new(exchange_account_id());
+        order_ids.add("exchange_1".into(), "client_1".into());
+        order_ids.save(&storage).await.expect("in test");
+
+        let restored = OrderIds::new(exchange_account_id());
+        restored.restore(&storage).await.expect("in test");
+
+        assert_eq!(
+            restored.get_client_order_id(&"exchange_1".into()),
+            Some("client_1".into())
+        );
+        assert_eq!(restored.get_client_order_id(&"exchange_2".into()), None);
+    }
+}
@@ -0,0 +1,239 @@
+use std::future::Future;
+
+use anyhow::{bail, Context, Result};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::DateTime;
+
+use crate::exchanges::common::{CurrencyPair, RestRequestOutcome};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::helpers::get_rest_error;
+use crate::exchanges::general::order::get_order_trades::OrderTrade;
+use crate::exchanges::general::request_type::RequestType;
+use crate::exchanges::general::symbol::Symbol;
+use crate::orders::order::OrderInfo;
+
+/// Protection from endless requesting if exchange ignores cursor of page
+const MAX_PAGES_COUNT: usize = 1000;
+
+/// Position from which page of listing endpoint is requested
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PageCursor {
+    /// Items with exchange id greater than specified one
+    AfterId(String),
+    /// Items created at specified time or later
+    FromTime(DateTime),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageRequest {
+    /// Page is requested from the oldest available items if cursor isn't specified
+    pub cursor: Option<PageCursor>,
+    pub limit: usize,
+}
+
+/// Item of listing endpoint which is used as cursor of the next page
+pub trait PageItem {
+    fn page_id(&self) -> String;
+}
+
+impl PageItem for OrderTrade {
+    fn page_id(&self) -> String {
+        self.trade_id.to_string()
+    }
+}
+
+impl PageItem for OrderInfo {
+    fn page_id(&self) -> String {
+        self.exchange_order_id.to_string()
+    }
+}
+
+/// Requests pages in ascending order until incomplete page is received and stitches them together.
+/// `request_page` should return items of page sorted by id
+pub async fn request_all_pages<T, F, Fut>(
+    start: Option<PageCursor>,
+    limit: usize,
+    mut request_page: F,
+) -> Result<Vec<T>>
+where
+    T: PageItem,
+    F: FnMut(PageRequest) -> Fut,
+    Fut: Future<Output = Result<Vec<T>>>,
+{
+    let mut items = Vec::new();
+    let mut cursor = start;
+    for _ in 0..MAX_PAGES_COUNT {
+        let page = request_page(PageRequest {
+            cursor: cursor.clone(),
+            limit,
+        })
+        .await?;
+
+        let is_last_page = page.len() < limit;
+        match page.last() {
+            Some(last_item) => cursor = Some(PageCursor::AfterId(last_item.page_id())),
+            None => return Ok(items),
+        }
+        items.extend(page);
+
+        if is_last_page {
+            return Ok(items);
+        }
+    }
+
+    bail!(
+        "Pagination is stopped after {} pages with cursor {:?}",
+        MAX_PAGES_COUNT,
+        cursor
+    )
+}
+
+impl Exchange {
+    /// All own trades for symbol since `from_time`, requested page by page
+    pub async fn get_all_my_trades(
+        &self,
+        symbol: &Symbol,
+        from_time: Option<DateTime>,
+        cancellation_token: CancellationToken,
+    ) -> Result<Vec<OrderTrade>> {
+        let limit = self.exchange_client.page_limit();
+        request_all_pages(from_time.map(PageCursor::FromTime), limit, |page| {
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                self.reserve_page_request(RequestType::GetMyTrades, cancellation_token)
+                    .await?;
+
+                let response = self
+                    .exchange_client
+                    .request_my_trades_page(symbol, &page)
+                    .await?;
+                self.check_page_response(&response, &page)?;
+
+                self.exchange_client
+                    .parse_get_my_trades(&response, None)
+                    .with_context(|| format!("Unable to parse my trades page {:?}", page))
+            }
+        })
+        .await
+    }
+
+    /// All orders (open and finished) for currency pair since `from_time`, requested page by page
+    pub async fn get_all_orders(
+        &self,
+        currency_pair: CurrencyPair,
+        from_time: Option<DateTime>,
+        cancellation_token: CancellationToken,
+    ) -> Result<Vec<OrderInfo>> {
+        let limit = self.exchange_client.page_limit();
+        request_all_pages(from_time.map(PageCursor::FromTime), limit, |page| {
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                self.reserve_page_request(RequestType::GetOrdersHistory, cancellation_token)
+                    .await?;
+
+                let response = self
+                    .exchange_client
+                    .request_all_orders_page(currency_pair, &page)
+                    .await?;
+                self.check_page_response(&response, &page)?;
+
+                self.exchange_client
+                    .parse_all_orders(&response)
+                    .with_context(|| format!("Unable to parse orders page {:?}", page))
+            }
+        })
+        .await
+    }
+
+    async fn reserve_page_request(
+        &self,
+        request_type: RequestType,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        self.timeout_manager
+            .reserve_when_available(
+                self.exchange_account_id,
+                request_type,
+                None,
+                cancellation_token,
+            )?
+            .await
+            .into_result()?;
+
+        Ok(())
+    }
+
+    fn check_page_response(&self, response: &RestRequestOutcome, page: &PageRequest) -> Result<()> {
+        if let Some(error) = get_rest_error(
+            response,
+            self.exchange_account_id,
+            self.features.empty_response_is_ok,
+        ) {
+            bail!(
+                "Unable to get page {:?} on {}: {:?}",
+                page,
+                self.exchange_account_id,
+                error
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestItem(u64);
+
+    impl PageItem for TestItem {
+        fn page_id(&self) -> String {
+            self.0.to_string()
+        }
+    }
+
+    #[tokio::test]
+    async fn pages_are_stitched() {
+        let mut requests = Vec::new();
+        let items = request_all_pages(None, 2, |page| {
+            requests.push(page.cursor.clone());
+            let first_id = match page.cursor {
+                None => 1,
+                Some(PageCursor::AfterId(id)) => id.parse::<u64>().expect("in test") + 1,
+                Some(PageCursor::FromTime(_)) => unreachable!(),
+            };
+            let page_items: Vec<_> = (first_id..6).take(page.limit).map(TestItem).collect();
+            async move { Ok(page_items) }
+        })
+        .await
+        .expect("in test");
+
+        assert_eq!(
+            items.iter().map(|x| x.0).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5]
+        );
+        assert_eq!(
+            requests,
+            vec![
+                None,
+                Some(PageCursor::AfterId("2".to_owned())),
+                Some(PageCursor::AfterId("4".to_owned())),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn empty_page_stops_pagination() {
+        let items = request_all_pages(None, 2, |page| async move {
+            Ok(match page.cursor {
+                None => vec![TestItem(1), TestItem(2)],
+                Some(_) => vec![],
+            })
+        })
+        .await
+        .expect("in test");
+
+        assert_eq!(items.len(), 2);
+    }
+}
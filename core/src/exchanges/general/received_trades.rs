@@ -0,0 +1,379 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use futures::FutureExt;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+use crate::exchanges::common::{CurrencyPair, ExchangeAccountId};
+use crate::exchanges::events::TradeId;
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_future;
+use crate::orders::order::{ExchangeOrderId, OrderSide};
+use crate::storage::Storage;
+
+/// Count of the latest fills which are kept for every market
+const MAX_TRADE_IDS_PER_MARKET: usize = 10_000;
+const RECEIVED_TRADES_NAMESPACE: &str = "received_trades";
+const RECEIVED_TRADES_SAVING_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Both orders of self-trade are filled by the same trade id, so fill is identified
+/// by side and order too
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ReceivedFill {
+    pub side: OrderSide,
+    pub exchange_order_id: ExchangeOrderId,
+    pub trade_id: String,
+}
+
+impl ReceivedFill {
+    pub fn new(side: OrderSide, exchange_order_id: ExchangeOrderId, trade_id: &TradeId) -> Self {
+        Self {
+            side,
+            exchange_order_id,
+            trade_id: trade_id.to_string(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct MarketTradeIds {
+    ids: HashSet<ReceivedFill>,
+    /// Fills in order of receiving for removing of the oldest ones
+    queue: VecDeque<ReceivedFill>,
+}
+
+impl MarketTradeIds {
+    /// Returns `false` if fill was already added
+    fn add(&mut self, fill: ReceivedFill) -> bool {
+        if !self.ids.insert(fill.clone()) {
+            return false;
+        }
+
+        self.queue.push_back(fill);
+        while self.queue.len() > MAX_TRADE_IDS_PER_MARKET {
+            if let Some(oldest) = self.queue.pop_front() {
+                let _ = self.ids.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct MarketTradeIdsRecord {
+    currency_pair: CurrencyPair,
+    fills: Vec<ReceivedFill>,
+}
+
+/// Fills which were applied to orders of exchange account.
+/// Trade ids are unique only within exchange and sometimes only within symbol, so they are kept by markets.
+/// Fills are saved to storage right after adding and on graceful shutdown, so fills received again
+/// by gap-fill queries after restart aren't applied twice
+pub struct ReceivedTrades {
+    exchange_account_id: ExchangeAccountId,
+    markets: Mutex<HashMap<CurrencyPair, MarketTradeIds>>,
+    /// Fills are added after the last saving
+    is_changed: AtomicBool,
+    changed: Notify,
+}
+
+impl ReceivedTrades {
+    pub fn new(exchange_account_id: ExchangeAccountId) -> Self {
+        Self {
+            exchange_account_id,
+            markets: Mutex::new(HashMap::new()),
+            is_changed: AtomicBool::new(false),
+            changed: Notify::new(),
+        }
+    }
+
+    pub fn contains(&self, currency_pair: CurrencyPair, fill: &ReceivedFill) -> bool {
+        self.markets
+            .lock()
+            .get(&currency_pair)
+            .map_or(false, |market| market.ids.contains(fill))
+    }
+
+    pub fn add(&self, currency_pair: CurrencyPair, fill: ReceivedFill) {
+        let is_added = self
+            .markets
+            .lock()
+            .entry(currency_pair)
+            .or_default()
+            .add(fill);
+
+        if is_added {
+            self.is_changed.store(true, Ordering::SeqCst);
+            self.changed.notify_one();
+        }
+    }
+
+    /// Saves fills if they are changed after the last saving
+    pub async fn save_if_changed(&self, storage: &dyn Storage) -> Result<()> {
+        if !self.is_changed.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let result = self.save(storage).await;
+        if result.is_err() {
+            self.is_changed.store(true, Ordering::SeqCst);
+        }
+        result
+    }
+
+    pub async fn save(&self, storage: &dyn Storage) -> Result<()> {
+        let records = self
+            .markets
+            .lock()
+            .iter()
+            .map(|(currency_pair, market)| MarketTradeIdsRecord {
+                currency_pair: *currency_pair,
+                fills: market.queue.iter().cloned().collect(),
+            })
+            .collect::<Vec<_>>();
+
+        storage
+            .put_serialized(
+                RECEIVED_TRADES_NAMESPACE,
+                &self.exchange_account_id.to_string(),
+                &records,
+            )
+            .await
+    }
+
+    pub async fn restore(&self, storage: &dyn Storage) -> Result<()> {
+        let records: Vec<MarketTradeIdsRecord> = match storage
+            .get_deserialized(
+                RECEIVED_TRADES_NAMESPACE,
+                &self.exchange_account_id.to_string(),
+            )
+            .await?
+        {
+            Some(records) => records,
+            None => return Ok(()),
+        };
+
+        let mut markets = self.markets.lock();
+        for record in records {
+            let market = markets.entry(record.currency_pair).or_default();
+            for fill in record.fills {
+                market.add(fill);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Exchange {
+    /// Fill with trade id which was already applied to the same side of order
+    pub(super) fn is_fill_received(
+        &self,
+        currency_pair: CurrencyPair,
+        fill: &Option<ReceivedFill>,
+    ) -> bool {
+        fill.as_ref().map_or(false, |fill| {
+            self.received_trades.contains(currency_pair, fill)
+        })
+    }
+
+    pub async fn restore_received_trades(&self, storage: &dyn Storage) -> Result<()> {
+        self.received_trades.restore(storage).await
+    }
+
+    /// Saves fills which aren't saved yet. It's called on graceful shutdown after saving task
+    /// is stopped
+    pub(crate) async fn save_received_trades(&self, storage: &dyn Storage) {
+        if let Err(error) = self.received_trades.save_if_changed(storage).await {
+            log::error!(
+                "Unable to save received trades of {}: {:?}",
+                self.exchange_account_id,
+                error
+            );
+        }
+    }
+
+    /// Saves fills as soon as they are added. Fills which are added during saving
+    /// are saved by the next iteration
+    pub(crate) fn spawn_received_trades_saving(self: &Arc<Self>, storage: Arc<dyn Storage>) {
+        let exchange = self.clone();
+        let action = async move {
+            loop {
+                exchange.received_trades.changed.notified().await;
+
+                if let Err(error) = exchange
+                    .received_trades
+                    .save_if_changed(storage.as_ref())
+                    .await
+                {
+                    log::warn!(
+                        "Unable to save received trades of {}: {:?}",
+                        exchange.exchange_account_id,
+                        error
+                    );
+                    tokio::time::sleep(RECEIVED_TRADES_SAVING_RETRY_DELAY).await;
+                    exchange.received_trades.changed.notify_one();
+                }
+            }
+        };
+
+        let _ = spawn_future(
+            &format!("Save received trades of {}", self.exchange_account_id),
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action.boxed(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::memory::MemoryStorage;
+
+    fn exchange_account_id() -> ExchangeAccountId {
+        ExchangeAccountId::new("Binance".into(), 0)
+    }
+
+    fn fill(side: OrderSide, exchange_order_id: &str, trade_id: TradeId) -> ReceivedFill {
+        ReceivedFill::new(side, exchange_order_id.into(), &trade_id)
+    }
+
+    #[test]
+    fn trade_ids_are_namespaced_by_market() {
+        let btc_usdt = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        let eth_usdt = CurrencyPair::from_codes("eth".into(), "usdt".into());
+        let received_trades = ReceivedTrades::new(exchange_account_id());
+
+        received_trades.add(btc_usdt, fill(OrderSide::Buy, "1", TradeId::Number(1)));
+
+        let same_fill = fill(OrderSide::Buy, "1", TradeId::Number(1));
+        assert!(received_trades.contains(btc_usdt, &same_fill));
+        assert!(!received_trades.contains(eth_usdt, &same_fill));
+        assert!(!received_trades.contains(
+            btc_usdt,
+            &fill(OrderSide::Buy, "1", TradeId::String("1a".into()))
+        ));
+    }
+
+    #[test]
+    fn both_sides_of_self_trade_are_received() {
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        let received_trades = ReceivedTrades::new(exchange_account_id());
+
+        received_trades.add(currency_pair, fill(OrderSide::Buy, "1", TradeId::Number(7)));
+
+        assert!(!received_trades.contains(
+            currency_pair,
+            &fill(OrderSide::Sell, "2", TradeId::Number(7))
+        ));
+        assert!(!received_trades.contains(
+            currency_pair,
+            &fill(OrderSide::Sell, "1", TradeId::Number(7))
+        ));
+        assert!(!received_trades.contains(
+            currency_pair,
+            &fill(OrderSide::Buy, "2", TradeId::Number(7))
+        ));
+    }
+
+    #[test]
+    fn oldest_trade_ids_are_removed() {
+        let mut market = MarketTradeIds::default();
+        for i in 0..MAX_TRADE_IDS_PER_MARKET + 1 {
+            market.add(fill(OrderSide::Buy, "1", TradeId::Number(i as u64)));
+        }
+
+        assert_eq!(market.ids.len(), MAX_TRADE_IDS_PER_MARKET);
+        assert!(!market
+            .ids
+            .contains(&fill(OrderSide::Buy, "1", TradeId::Number(0))));
+        assert!(market.ids.contains(&fill(
+            OrderSide::Buy,
+            "1",
+            TradeId::Number(MAX_TRADE_IDS_PER_MARKET as u64)
+        )));
+    }
+
+    #[tokio::test]
+    async fn trade_ids_are_restored_from_storage() {
+        let storage = MemoryStorage::default();
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+
+        let received_trades = ReceivedTrades::new(exchange_account_id());
+        received_trades.add(
+            currency_pair,
+            fill(OrderSide::Sell, "5", TradeId::Number(42)),
+        );
+        received_trades.save(&storage).await.expect("in test");
+
+        let restored = ReceivedTrades::new(exchange_account_id());
+        restored.restore(&storage).await.expect("in test");
+
+        assert!(restored.contains(
+            currency_pair,
+            &fill(OrderSide::Sell, "5", TradeId::Number(42))
+        ));
+        assert!(!restored.contains(
+            currency_pair,
+            &fill(OrderSide::Sell, "5", TradeId::Number(43))
+        ));
+    }
+
+    #[tokio::test]
+    async fn new_fill_triggers_saving() {
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        let received_trades = ReceivedTrades::new(exchange_account_id());
+
+        received_trades.add(currency_pair, fill(OrderSide::Buy, "1", TradeId::Number(1)));
+
+        let notified = received_trades.changed.notified();
+        tokio::time::timeout(Duration::from_secs(1), notified)
+            .await
+            .expect("saving should be triggered by new fill");
+        assert!(received_trades.is_changed.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn same_fill_does_not_trigger_saving() {
+        let storage = MemoryStorage::default();
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        let received_trades = ReceivedTrades::new(exchange_account_id());
+
+        received_trades.add(currency_pair, fill(OrderSide::Buy, "1", TradeId::Number(1)));
+        received_trades
+            .save_if_changed(&storage)
+            .await
+            .expect("in test");
+        received_trades.add(currency_pair, fill(OrderSide::Buy, "1", TradeId::Number(1)));
+
+        assert!(!received_trades.is_changed.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn changed_trade_ids_are_saved() {
+        let storage = MemoryStorage::default();
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+
+        let received_trades = ReceivedTrades::new(exchange_account_id());
+        received_trades.add(currency_pair, fill(OrderSide::Buy, "3", TradeId::Number(9)));
+        received_trades
+            .save_if_changed(&storage)
+            .await
+            .expect("in test");
+        assert!(!received_trades.is_changed.load(Ordering::SeqCst));
+
+        let restored = ReceivedTrades::new(exchange_account_id());
+        restored.restore(&storage).await.expect("in test");
+
+        assert!(restored.contains(
+            currency_pair,
+            &fill(OrderSide::Buy, "3", TradeId::Number(9))
+        ));
+    }
+}
@@ -1,4 +1,6 @@
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize)]
 pub enum RequestType {
     CreateOrder,
     CancelOrder,
@@ -21,5 +23,6 @@ pub enum RequestType {
     GetLastPrints,
     GetProfileId,
     GetMyTrades,
+    GetOrdersHistory,
     SetLeverage,
 }
@@ -0,0 +1,71 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::common::{Amount, CurrencyCode};
+use crate::exchanges::events::ExchangeBalance;
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::helpers::get_rest_error;
+use crate::exchanges::general::withdrawal::WalletType;
+
+/// Transfer of funds between master account and its sub-accounts or between sub-accounts.
+/// Sub-accounts are specified by identifiers on exchange (see `ExchangeSettings::sub_account`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubAccountTransfer {
+    pub currency_code: CurrencyCode,
+    pub amount: Amount,
+    /// Master account is used if it isn't specified
+    pub from_sub_account: Option<String>,
+    /// Master account is used if it isn't specified
+    pub to_sub_account: Option<String>,
+    pub from_wallet: WalletType,
+    pub to_wallet: WalletType,
+}
+
+impl Exchange {
+    /// Balances of sub-account requested by master account
+    pub async fn get_sub_account_balances(
+        &self,
+        sub_account: &str,
+    ) -> Result<Vec<ExchangeBalance>> {
+        let response = self
+            .exchange_client
+            .request_sub_account_balances(sub_account)
+            .await?;
+        if let Some(error) = get_rest_error(&response, self.exchange_account_id, false) {
+            bail!(
+                "Unable to get balances of sub-account {} on {}: {:?}",
+                sub_account,
+                self.exchange_account_id,
+                error
+            );
+        }
+
+        self.exchange_client.parse_sub_account_balances(&response)
+    }
+
+    /// Returns exchange id of transfer
+    pub async fn sub_account_transfer(&self, transfer: &SubAccountTransfer) -> Result<String> {
+        self.ensure_withdrawal_enabled()?;
+
+        log::warn!(
+            "Sub-account transfer {:?} requested on {}",
+            transfer,
+            self.exchange_account_id
+        );
+
+        let response = self
+            .exchange_client
+            .request_sub_account_transfer(transfer)
+            .await?;
+        if let Some(error) = get_rest_error(&response, self.exchange_account_id, false) {
+            bail!(
+                "Sub-account transfer {:?} failed on {}: {:?}",
+                transfer,
+                self.exchange_account_id,
+                error
+            );
+        }
+
+        self.exchange_client.parse_internal_transfer(&response)
+    }
+}
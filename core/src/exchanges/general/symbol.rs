@@ -17,10 +17,16 @@ use crate::{
 
 use super::exchange::Exchange;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Round {
     Floor,
     Ceiling,
     ToNearest,
+    /// Away from the opposite side of order book: down for buy orders and up for sell orders,
+    /// so rounded order doesn't cross order book more than before rounding
+    TowardPassive(OrderSide),
+    /// Toward the opposite side of order book: up for buy orders and down for sell orders
+    TowardAggressive(OrderSide),
 }
 
 // TODO Change to Maker-Taker
@@ -54,6 +60,17 @@ impl Precision {
     }
 }
 
+/// How cost of currency pair amount is calculated
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ContractType {
+    /// Amount is in base currency and cost is in quote currency: `cost = amount * price`.
+    /// Spot currency pairs and derivatives margined in quote currency
+    Linear,
+    /// Amount is in quote currency and cost is in base currency: `cost = amount / price`.
+    /// Derivatives margined in base currency
+    Inverse,
+}
+
 /// Metadata for a currency pair
 #[derive(Debug, Clone, Hash, Eq)]
 pub struct Symbol {
@@ -76,6 +93,12 @@ pub struct Symbol {
 
     pub price_precision: Precision,
     pub amount_precision: Precision,
+
+    pub contract_type: ContractType,
+    /// Amount of underlying currency in one contract, 1 for spot currency pairs
+    pub contract_size: Decimal,
+    /// Currency in which profit and loss of derivative is settled
+    pub settlement_currency_code: Option<CurrencyCode>,
 }
 
 impl Symbol {
@@ -121,6 +144,127 @@ impl Symbol {
             amount_multiplier: dec!(1),
             price_precision,
             amount_precision,
+            // Derivatives were considered inverse before exchange clients started to specify contracts
+            contract_type: match is_derivative {
+                true => ContractType::Inverse,
+                false => ContractType::Linear,
+            },
+            contract_size: dec!(1),
+            settlement_currency_code: None,
+        }
+    }
+
+    /// Contract specification of derivative currency pair
+    pub fn with_contract(
+        mut self,
+        contract_type: ContractType,
+        contract_size: Decimal,
+        settlement_currency_code: CurrencyCode,
+    ) -> Self {
+        self.contract_type = contract_type;
+        self.contract_size = contract_size;
+        self.settlement_currency_code = Some(settlement_currency_code);
+        self
+    }
+
+    pub fn is_inverse(&self) -> bool {
+        self.contract_type == ContractType::Inverse
+    }
+
+    /// Currency of profit and loss. If it isn't specified by exchange, it's derived from contract type
+    pub fn get_settlement_currency_code(&self) -> CurrencyCode {
+        self.settlement_currency_code
+            .unwrap_or_else(|| self.get_cost_currency_code())
+    }
+
+    /// Currency of value returned by `get_cost`
+    pub fn get_cost_currency_code(&self) -> CurrencyCode {
+        match self.contract_type {
+            ContractType::Linear => self.quote_currency_code,
+            ContractType::Inverse => self.base_currency_code,
+        }
+    }
+
+    /// Value of amount in specified currency, e.g. for calculation of commission or reservation.
+    /// Unlike `convert_amount_from_amount_currency_code` takes contract type and size into account
+    pub fn get_amount_value_in_currency(
+        &self,
+        currency_code: CurrencyCode,
+        amount: Amount,
+        price: Price,
+    ) -> Amount {
+        if currency_code == self.amount_currency_code {
+            return amount;
+        }
+
+        if currency_code == self.get_cost_currency_code() {
+            return self.get_cost(amount, price);
+        }
+
+        self.convert_amount_from_amount_currency_code(currency_code, amount, price)
+    }
+
+    /// Amount which has specified value in specified currency. Inverse of `get_amount_value_in_currency`
+    pub fn get_amount_by_value_in_currency(
+        &self,
+        currency_code: CurrencyCode,
+        value: Amount,
+        price: Price,
+    ) -> Amount {
+        if currency_code == self.amount_currency_code {
+            return value;
+        }
+
+        if currency_code == self.get_cost_currency_code() {
+            return match self.contract_type {
+                ContractType::Linear => value / (price * self.contract_size),
+                ContractType::Inverse => value * price / self.contract_size,
+            };
+        }
+
+        self.convert_amount_into_amount_currency_code(currency_code, value, price)
+    }
+
+    /// Profit and loss in settlement currency of position opened at `entry_price` if it's closed at `exit_price`.
+    /// Position is positive for long and negative for short
+    pub fn get_position_pnl(
+        &self,
+        position: Amount,
+        entry_price: Price,
+        exit_price: Price,
+    ) -> Price {
+        match self.contract_type {
+            ContractType::Linear => position * self.contract_size * (exit_price - entry_price),
+            ContractType::Inverse => {
+                position * self.contract_size * (dec!(1) / entry_price - dec!(1) / exit_price)
+            }
+        }
+    }
+
+    /// Cost of amount with specified price in the contract terms
+    pub fn get_cost(&self, amount: Amount, price: Price) -> Price {
+        match self.contract_type {
+            ContractType::Linear => amount * price * self.contract_size,
+            ContractType::Inverse => amount * self.contract_size / price,
+        }
+    }
+
+    /// Change of cost of one contract when price moves by one tick from specified price.
+    /// `None` if price precision isn't specified by tick
+    pub fn get_tick_value(&self, price: Price) -> Option<Price> {
+        let tick = match self.price_precision {
+            Precision::ByTick { tick } => tick,
+            Precision::ByMantissa { .. } => return None,
+        };
+
+        Some((self.get_cost(dec!(1), price + tick) - self.get_cost(dec!(1), price)).abs())
+    }
+
+    /// Price at which amount has specified cost. Inverse of `get_cost`
+    pub fn get_price_by_cost(&self, amount: Amount, cost: Price) -> Price {
+        match self.contract_type {
+            ContractType::Linear => cost / (amount * self.contract_size),
+            ContractType::Inverse => amount * self.contract_size / cost,
         }
     }
 
@@ -207,8 +351,10 @@ impl Symbol {
         let ceil = (value / tick).ceil() * tick;
 
         match round {
-            Round::Floor => floor,
-            Round::Ceiling => ceil,
+            Round::Floor | Round::TowardPassive(OrderSide::Buy) => floor,
+            Round::Ceiling | Round::TowardPassive(OrderSide::Sell) => ceil,
+            Round::TowardAggressive(OrderSide::Buy) => ceil,
+            Round::TowardAggressive(OrderSide::Sell) => floor,
             Round::ToNearest => {
                 if ceil - value <= value - floor {
                     ceil
@@ -351,7 +497,7 @@ impl Symbol {
                     .min_amount
                     .context("Can't calculate min amount: missing min_amount and min_cost");
 
-                if self.is_derivative {
+                if self.is_inverse() {
                     return min_amount;
                 }
 
@@ -360,9 +506,10 @@ impl Symbol {
             Some(v) => v,
         };
 
-        let min_amount_from_cost = match self.is_derivative {
-            true => min_cost,
-            false => min_cost / price,
+        // Amount of inverse contracts is in quote currency, so it's comparable with cost
+        let min_amount_from_cost = match self.contract_type {
+            ContractType::Inverse => min_cost,
+            ContractType::Linear => min_cost / (price * self.contract_size),
         };
 
         let rounded_amount = self.amount_round(min_amount_from_cost, Round::Ceiling);
@@ -381,6 +528,29 @@ impl Symbol {
             }
         }
     }
+
+    /// Compare all exchange metadata of symbols (PartialEq compares only currency pairs)
+    pub fn is_metadata_equal(&self, other: &Symbol) -> bool {
+        self.is_active == other.is_active
+            && self.is_derivative == other.is_derivative
+            && self.base_currency_id == other.base_currency_id
+            && self.base_currency_code == other.base_currency_code
+            && self.quote_currency_id == other.quote_currency_id
+            && self.quote_currency_code == other.quote_currency_code
+            && self.min_price == other.min_price
+            && self.max_price == other.max_price
+            && self.min_amount == other.min_amount
+            && self.max_amount == other.max_amount
+            && self.min_cost == other.min_cost
+            && self.amount_currency_code == other.amount_currency_code
+            && self.balance_currency_code == other.balance_currency_code
+            && self.amount_multiplier == other.amount_multiplier
+            && self.price_precision == other.price_precision
+            && self.amount_precision == other.amount_precision
+            && self.contract_type == other.contract_type
+            && self.contract_size == other.contract_size
+            && self.settlement_currency_code == other.settlement_currency_code
+    }
 }
 
 impl PartialEq for Symbol {
@@ -406,33 +576,17 @@ impl Exchange {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::test_util::SymbolBuilder;
     use rust_decimal_macros::dec;
 
     #[test]
     fn get_commission_currency_code_from_balance() {
-        let base_currency = "PHB";
-        let quote_currency = "PHB";
-        let price_tick = dec!(0.1);
-        let is_derivative = false;
         let balance_currency_code = CurrencyCode::new("ETH".into());
 
-        let symbol = Symbol::new(
-            false,
-            is_derivative,
-            base_currency.into(),
-            base_currency.into(),
-            quote_currency.into(),
-            quote_currency.into(),
-            None,
-            None,
-            None,
-            None,
-            None,
-            base_currency.into(),
-            Some(balance_currency_code),
-            Precision::ByTick { tick: price_tick },
-            Precision::ByTick { tick: dec!(0) },
-        );
+        let symbol = SymbolBuilder::new("PHB".into(), "PHB".into())
+            .balance_currency_code(balance_currency_code)
+            .amount_tick(dec!(0))
+            .build();
 
         let gotten = symbol.get_commission_currency_code(OrderSide::Buy);
         assert_eq!(gotten, balance_currency_code);
@@ -518,6 +672,11 @@ mod test {
     #[case(dec!(123.456), dec!(0.01), Round::ToNearest, dec!(123.46))]
     #[case(dec!(123.456), dec!(2), Round::ToNearest, dec!(124))]
     #[case(dec!(0), dec!(0.03), Round::ToNearest, dec!(0))]
+    #[case(dec!(123.456), dec!(0.1), Round::TowardPassive(OrderSide::Buy), dec!(123.4))]
+    #[case(dec!(123.456), dec!(0.1), Round::TowardPassive(OrderSide::Sell), dec!(123.5))]
+    #[case(dec!(123.456), dec!(0.1), Round::TowardAggressive(OrderSide::Buy), dec!(123.5))]
+    #[case(dec!(123.456), dec!(0.1), Round::TowardAggressive(OrderSide::Sell), dec!(123.4))]
+    #[case(dec!(123.4), dec!(0.1), Round::TowardPassive(OrderSide::Sell), dec!(123.4))]
     fn round_by_tick(
         #[case] value: Decimal,
         #[case] tick: Decimal,
@@ -533,31 +692,12 @@ mod test {
 
     #[test]
     pub fn get_trade_code() {
-        let base_currency = "PHB";
-        let quote_currency = "BTC";
-        let price_tick = dec!(0.1);
-        let is_derivative = false;
-        let balance_currency_code = CurrencyCode::new("ETH".into());
-
-        let base_code = CurrencyCode::new(base_currency.into());
-        let quote_code = CurrencyCode::new(quote_currency.into());
-        let symbol = Symbol::new(
-            false,
-            is_derivative,
-            base_currency.into(),
-            base_code,
-            quote_currency.into(),
-            quote_code,
-            None,
-            None,
-            None,
-            None,
-            None,
-            base_code,
-            Some(balance_currency_code),
-            Precision::ByTick { tick: price_tick },
-            Precision::ByTick { tick: dec!(0) },
-        );
+        let base_code = CurrencyCode::new("PHB".into());
+        let quote_code = CurrencyCode::new("BTC".into());
+        let symbol = SymbolBuilder::new(base_code, quote_code)
+            .balance_currency_code("ETH".into())
+            .amount_tick(dec!(0))
+            .build();
 
         assert_eq!(
             symbol.get_trade_code(OrderSide::Buy, BeforeAfter::After),
@@ -576,4 +716,143 @@ mod test {
             base_code
         );
     }
+
+    #[test]
+    pub fn is_metadata_equal() {
+        let create_symbol = |price_tick| {
+            SymbolBuilder::new("PHB".into(), "BTC".into())
+                .is_active(true)
+                .price_tick(price_tick)
+                .build()
+        };
+
+        let symbol = create_symbol(dec!(0.1));
+        let same_symbol = create_symbol(dec!(0.1));
+        let updated_symbol = create_symbol(dec!(0.01));
+
+        assert!(symbol.is_metadata_equal(&same_symbol));
+        assert!(!symbol.is_metadata_equal(&updated_symbol));
+        assert_eq!(symbol, updated_symbol);
+    }
+
+    #[test]
+    pub fn cost_by_contract_type() {
+        let create_symbol = |is_derivative| {
+            SymbolBuilder::new("BTC".into(), "USD".into())
+                .is_active(true)
+                .is_derivative(is_derivative)
+                .amount_currency_code("USD".into())
+                .amount_tick(dec!(1))
+                .build()
+        };
+
+        let spot = create_symbol(false);
+        assert_eq!(spot.get_cost(dec!(2), dec!(100)), dec!(200));
+        assert_eq!(spot.get_price_by_cost(dec!(2), dec!(200)), dec!(100));
+        assert_eq!(spot.get_settlement_currency_code(), "USD".into());
+        assert_eq!(spot.get_tick_value(dec!(100)), Some(dec!(0.1)));
+
+        let inverse =
+            create_symbol(true).with_contract(ContractType::Inverse, dec!(100), "BTC".into());
+        assert_eq!(inverse.get_cost(dec!(10), dec!(50000)), dec!(0.02));
+        assert_eq!(inverse.get_price_by_cost(dec!(10), dec!(0.02)), dec!(50000));
+        assert_eq!(inverse.get_settlement_currency_code(), "BTC".into());
+
+        let linear =
+            create_symbol(true).with_contract(ContractType::Linear, dec!(0.001), "USD".into());
+        assert_eq!(linear.get_cost(dec!(10), dec!(50000)), dec!(500));
+        assert_eq!(linear.get_price_by_cost(dec!(10), dec!(500)), dec!(50000));
+    }
+
+    fn create_futures_symbol(
+        contract_type: ContractType,
+        contract_size: Decimal,
+        amount_currency_code: &str,
+    ) -> Symbol {
+        let settlement_currency_code = match contract_type {
+            ContractType::Linear => "USDT",
+            ContractType::Inverse => "BTC",
+        };
+
+        SymbolBuilder::new("BTC".into(), "USDT".into())
+            .is_active(true)
+            .is_derivative(true)
+            .amount_currency_code(amount_currency_code.into())
+            .balance_currency_code(settlement_currency_code.into())
+            .amount_tick(dec!(1))
+            .build()
+            .with_contract(
+                contract_type,
+                contract_size,
+                settlement_currency_code.into(),
+            )
+    }
+
+    #[test]
+    pub fn linear_position_pnl() {
+        // USDⓈ-M futures: PnL = position * (exit price - entry price)
+        let symbol = create_futures_symbol(ContractType::Linear, dec!(1), "BTC");
+
+        assert_eq!(
+            symbol.get_position_pnl(dec!(1), dec!(50000), dec!(55000)),
+            dec!(5000)
+        );
+        assert_eq!(
+            symbol.get_position_pnl(dec!(-1), dec!(50000), dec!(55000)),
+            dec!(-5000)
+        );
+    }
+
+    #[test]
+    pub fn inverse_position_pnl() {
+        // COIN-M futures: PnL = position * contract size * (1 / entry price - 1 / exit price)
+        let symbol = create_futures_symbol(ContractType::Inverse, dec!(100), "USDT");
+
+        let long_pnl = symbol.get_position_pnl(dec!(10), dec!(50000), dec!(55000));
+        assert_eq!(long_pnl.round_dp(8), dec!(0.00181818));
+
+        let short_pnl = symbol.get_position_pnl(dec!(-10), dec!(50000), dec!(40000));
+        assert_eq!(short_pnl, dec!(0.005));
+    }
+
+    #[test]
+    pub fn amount_value_by_contract_type() {
+        let linear = create_futures_symbol(ContractType::Linear, dec!(1), "BTC");
+        assert_eq!(
+            linear.get_amount_value_in_currency("USDT".into(), dec!(0.5), dec!(50000)),
+            dec!(25000)
+        );
+        assert_eq!(
+            linear.get_amount_value_in_currency("BTC".into(), dec!(0.5), dec!(50000)),
+            dec!(0.5)
+        );
+
+        // 10 contracts of 100 USD
+        let inverse = create_futures_symbol(ContractType::Inverse, dec!(100), "USDT");
+        assert_eq!(
+            inverse.get_amount_value_in_currency("BTC".into(), dec!(10), dec!(50000)),
+            dec!(0.02)
+        );
+        assert_eq!(inverse.get_cost_currency_code(), "BTC".into());
+    }
+
+    #[test]
+    pub fn amount_by_value_by_contract_type() {
+        // Contracts of 0.001 BTC
+        let linear = create_futures_symbol(ContractType::Linear, dec!(0.001), "BTC");
+        assert_eq!(
+            linear.get_amount_by_value_in_currency("USDT".into(), dec!(25), dec!(50000)),
+            dec!(0.5)
+        );
+
+        let inverse = create_futures_symbol(ContractType::Inverse, dec!(100), "USDT");
+        assert_eq!(
+            inverse.get_amount_by_value_in_currency("BTC".into(), dec!(0.02), dec!(50000)),
+            dec!(10)
+        );
+        assert_eq!(
+            inverse.get_amount_by_value_in_currency("USDT".into(), dec!(10), dec!(50000)),
+            dec!(10)
+        );
+    }
 }
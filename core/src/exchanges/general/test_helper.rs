@@ -8,7 +8,10 @@ use crate::{
             ActivePosition, Amount, ClosedPosition, CurrencyCode, CurrencyId, CurrencyPair,
             ExchangeAccountId, ExchangeError, Price, RestRequestOutcome, SpecificCurrencyPair,
         },
-        events::{AllowedEventSourceType, ExchangeBalancesAndPositions, ExchangeEvent, TradeId},
+        events::{
+            AllowedEventSourceType, ExchangeBalancesAndPositions, ExchangeEvent, TradeId,
+            TradeStream,
+        },
         general::{
             commission::{Commission, CommissionForType},
             exchange::Exchange,
@@ -16,8 +19,10 @@ use crate::{
                 ExchangeFeatures, OpenOrdersType, OrderFeatures, OrderTradeOption,
                 RestFillsFeatures, WebSocketOptions,
             },
-            symbol::{Precision, Symbol},
+            pagination::{PageCursor, PageRequest},
+            symbol::Symbol,
         },
+        market_data_downloader::HistoricalMessage,
         timeouts::{
             requests_timeout_manager_factory::RequestTimeoutArguments,
             timeout_manager::TimeoutManager,
@@ -34,10 +39,12 @@ use crate::{
         pool::{OrderRef, OrdersPool},
     },
     settings::ExchangeSettings,
+    test_util::SymbolBuilder,
 };
 use anyhow::Result;
 use async_trait::async_trait;
 use dashmap::DashMap;
+use hyper::StatusCode;
 use parking_lot::RwLock;
 use rust_decimal_macros::dec;
 use tokio::sync::broadcast;
@@ -50,7 +57,28 @@ use super::{
     symbol::BeforeAfter,
 };
 
-pub struct TestClient;
+pub struct TestClient {
+    settings: ExchangeSettings,
+    historical_trades: Vec<HistoricalMessage>,
+}
+
+impl TestClient {
+    pub(crate) fn new(settings: ExchangeSettings) -> Self {
+        Self::with_historical_trades(settings, Vec::new())
+    }
+
+    /// Client which returns specified trades as historical trades of any currency pair.
+    /// Trades should be sorted by time
+    pub(crate) fn with_historical_trades(
+        settings: ExchangeSettings,
+        historical_trades: Vec<HistoricalMessage>,
+    ) -> Self {
+        Self {
+            settings,
+            historical_trades,
+        }
+    }
+}
 
 #[async_trait]
 impl ExchangeClient for TestClient {
@@ -112,6 +140,40 @@ impl ExchangeClient for TestClient {
     ) -> Result<RestRequestOutcome> {
         unimplemented!("doesn't need in UT")
     }
+
+    /// Response contains ids of trades of requested page
+    async fn request_historical_trades(
+        &self,
+        _currency_pair: CurrencyPair,
+        page: &PageRequest,
+    ) -> Result<RestRequestOutcome> {
+        let start = match &page.cursor {
+            None => 0,
+            Some(PageCursor::FromTime(from)) => self
+                .historical_trades
+                .iter()
+                .take_while(|x| x.time < *from)
+                .count(),
+            Some(PageCursor::AfterId(id)) => {
+                self.historical_trades
+                    .iter()
+                    .take_while(|x| &x.id != id)
+                    .count()
+                    + 1
+            }
+        };
+
+        let ids = self
+            .historical_trades
+            .iter()
+            .skip(start)
+            .take(page.limit)
+            .map(|x| x.id.as_str())
+            .collect::<Vec<_>>();
+        let content = serde_json::to_string(&ids)?;
+
+        Ok(RestRequestOutcome::new(content, StatusCode::OK))
+    }
 }
 
 #[async_trait]
@@ -148,7 +210,9 @@ impl Support for TestClient {
     fn set_handle_trade_callback(
         &self,
         _callback: Box<
-            dyn FnMut(CurrencyPair, TradeId, Price, Amount, OrderSide, DateTime) + Send + Sync,
+            dyn FnMut(CurrencyPair, TradeStream, TradeId, Price, Amount, OrderSide, DateTime)
+                + Send
+                + Sync,
         >,
     ) {
     }
@@ -200,7 +264,7 @@ impl Support for TestClient {
     }
 
     fn get_settings(&self) -> &ExchangeSettings {
-        unimplemented!("doesn't need in UT")
+        &self.settings
     }
 
     fn parse_get_position(&self, _response: &RestRequestOutcome) -> Vec<ActivePosition> {
@@ -214,6 +278,20 @@ impl Support for TestClient {
     fn parse_get_balance(&self, _response: &RestRequestOutcome) -> ExchangeBalancesAndPositions {
         unimplemented!("doesn't need in UT")
     }
+
+    fn parse_historical_trades(
+        &self,
+        response: &RestRequestOutcome,
+        _currency_pair: CurrencyPair,
+    ) -> Result<Vec<HistoricalMessage>> {
+        let ids: Vec<String> = serde_json::from_str(&response.content)?;
+        Ok(self
+            .historical_trades
+            .iter()
+            .filter(|x| ids.contains(&x.id))
+            .cloned()
+            .collect())
+    }
 }
 
 pub(crate) fn get_test_exchange(
@@ -230,27 +308,30 @@ pub(crate) fn get_test_exchange_by_currency_codes_and_amount_code(
     quote_currency_code: &str,
     amount_currency_code: &str,
 ) -> (Arc<Exchange>, broadcast::Receiver<ExchangeEvent>) {
-    let price_tick = dec!(0.1);
-    let symbol = Arc::new(Symbol::new(
-        false,
+    let symbol = get_test_symbol(
         is_derivative,
-        base_currency_code.into(),
-        base_currency_code.into(),
-        quote_currency_code.into(),
-        quote_currency_code.into(),
-        None,
-        None,
-        None,
-        None,
-        None,
-        amount_currency_code.into(),
-        None,
-        Precision::ByTick { tick: price_tick },
-        Precision::ByTick { tick: dec!(0) },
-    ));
+        base_currency_code,
+        quote_currency_code,
+        amount_currency_code,
+    );
     get_test_exchange_with_symbol(symbol)
 }
 
+fn get_test_symbol(
+    is_derivative: bool,
+    base_currency_code: &str,
+    quote_currency_code: &str,
+    amount_currency_code: &str,
+) -> Arc<Symbol> {
+    Arc::new(
+        SymbolBuilder::new(base_currency_code.into(), quote_currency_code.into())
+            .is_derivative(is_derivative)
+            .amount_currency_code(amount_currency_code.into())
+            .amount_tick(dec!(0))
+            .build(),
+    )
+}
+
 pub(crate) fn get_test_exchange_by_currency_codes(
     is_derivative: bool,
     base_currency_code: &str,
@@ -278,11 +359,31 @@ pub(crate) fn get_test_exchange_with_symbol(
 pub(crate) fn get_test_exchange_with_symbol_and_id(
     symbol: Arc<Symbol>,
     exchange_account_id: ExchangeAccountId,
+) -> (Arc<Exchange>, broadcast::Receiver<ExchangeEvent>) {
+    let settings = ExchangeSettings {
+        exchange_account_id,
+        ..ExchangeSettings::default()
+    };
+    get_test_exchange_with_symbol_and_settings(symbol, settings)
+}
+
+/// Test exchange with symbol of `get_test_exchange` and specified settings
+pub(crate) fn get_test_exchange_with_settings(
+    settings: ExchangeSettings,
+) -> (Arc<Exchange>, broadcast::Receiver<ExchangeEvent>) {
+    let symbol = get_test_symbol(false, "PHB", "BTC", "PHB");
+    get_test_exchange_with_symbol_and_settings(symbol, settings)
+}
+
+fn get_test_exchange_with_symbol_and_settings(
+    symbol: Arc<Symbol>,
+    settings: ExchangeSettings,
 ) -> (Arc<Exchange>, broadcast::Receiver<ExchangeEvent>) {
     let lifetime_manager = AppLifetimeManager::new(CancellationToken::new());
     let (tx, rx) = broadcast::channel(10);
 
-    let exchange_client = Box::new(TestClient);
+    let exchange_account_id = settings.exchange_account_id;
+    let exchange_client = Box::new(TestClient::new(settings));
     let referral_reward = dec!(40);
     let commission = Commission::new(
         CommissionForType::new(dec!(0.1), referral_reward),
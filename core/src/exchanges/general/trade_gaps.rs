@@ -0,0 +1,294 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use futures::FutureExt;
+use itertools::Itertools;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::send_expected::SendExpectedByRef;
+
+use crate::exchanges::common::CurrencyPair;
+use crate::exchanges::events::{ExchangeEvent, TradeGapEvent, TradeId, TradeStream, TradesEvent};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::helpers::get_rest_error;
+use crate::exchanges::general::request_type::RequestType;
+use crate::exchanges::timeouts::timeout_manager;
+use crate::infrastructure::spawn_future;
+
+/// Count of trades requested by one REST request during backfilling
+const BACKFILL_PAGE_LIMIT: usize = 1000;
+/// Longer gaps aren't backfilled to avoid exhausting of request limits after long disconnection
+const MAX_BACKFILLED_TRADES_COUNT: u64 = 10_000;
+
+/// Trades which weren't received by websocket. Ids are inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TradeIdGap {
+    pub first_missed_id: u64,
+    pub last_missed_id: u64,
+}
+
+impl TradeIdGap {
+    pub fn missed_count(&self) -> u64 {
+        self.last_missed_id - self.first_missed_id + 1
+    }
+}
+
+/// Last trade ids of markets received by websocket. Exchanges with incremented trade ids
+/// number trades of market one after another, so skipped ids mean missed trades.
+/// Every stream has its own numbering, so ids are tracked by market and stream
+#[derive(Default)]
+pub(crate) struct TradeIdsTracker {
+    last_trade_ids: HashMap<(CurrencyPair, TradeStream), u64>,
+}
+
+impl TradeIdsTracker {
+    /// Returns gap between the last known trade of market stream and received one.
+    /// Trades which are older than the last known one don't change tracking
+    pub fn on_trade(
+        &mut self,
+        currency_pair: CurrencyPair,
+        trade_stream: TradeStream,
+        trade_id: u64,
+    ) -> Option<TradeIdGap> {
+        match self.last_trade_ids.entry((currency_pair, trade_stream)) {
+            Entry::Vacant(entry) => {
+                let _ = entry.insert(trade_id);
+                None
+            }
+            Entry::Occupied(mut entry) => {
+                let last_trade_id = *entry.get();
+                if trade_id <= last_trade_id {
+                    return None;
+                }
+
+                let _ = entry.insert(trade_id);
+                (trade_id > last_trade_id + 1).then(|| TradeIdGap {
+                    first_missed_id: last_trade_id + 1,
+                    last_missed_id: trade_id - 1,
+                })
+            }
+        }
+    }
+}
+
+impl Exchange {
+    /// Checks that trade received by websocket follows the previous trade of market and
+    /// starts backfilling of missed trades by REST if it doesn't
+    pub(super) fn check_trade_id_gap(
+        self: &Arc<Self>,
+        currency_pair: CurrencyPair,
+        trade_stream: TradeStream,
+        trade_id: &TradeId,
+    ) {
+        if !self.features.trade_option.supports_trade_incremented_id {
+            return;
+        }
+
+        let trade_id = match trade_id {
+            TradeId::Number(trade_id) => *trade_id,
+            TradeId::String(_) => return,
+        };
+
+        let gap = self
+            .trade_ids_tracker
+            .lock()
+            .on_trade(currency_pair, trade_stream, trade_id);
+        let gap = match gap {
+            Some(gap) => gap,
+            None => return,
+        };
+
+        log::warn!(
+            "Missed {} trades {}..={} of {:?} stream {} on {}, backfilling by REST",
+            gap.missed_count(),
+            gap.first_missed_id,
+            gap.last_missed_id,
+            trade_stream,
+            currency_pair,
+            self.exchange_account_id
+        );
+
+        let exchange = self.clone();
+        let action = async move {
+            let backfilled_count = match exchange
+                .backfill_trades(currency_pair, trade_stream, gap)
+                .await
+            {
+                Ok(backfilled_count) => backfilled_count,
+                Err(error) => {
+                    log::error!(
+                        "Unable to backfill trades of {} on {}: {:?}",
+                        currency_pair,
+                        exchange.exchange_account_id,
+                        error
+                    );
+                    0
+                }
+            };
+
+            if backfilled_count < gap.missed_count() {
+                exchange.send_trade_gap(currency_pair, gap, backfilled_count);
+            }
+
+            Ok(())
+        };
+        let _ = spawn_future(
+            "Backfill trades missed by websocket",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action.boxed(),
+        );
+    }
+
+    /// Requests trades of gap by REST and sends them as usual trades event.
+    /// Returns count of backfilled trades
+    async fn backfill_trades(
+        &self,
+        currency_pair: CurrencyPair,
+        trade_stream: TradeStream,
+        gap: TradeIdGap,
+    ) -> Result<u64> {
+        if gap.missed_count() > MAX_BACKFILLED_TRADES_COUNT {
+            bail!(
+                "Gap of {} trades is too long for backfilling",
+                gap.missed_count()
+            );
+        }
+
+        let mut trades = Vec::new();
+        let mut from_id = gap.first_missed_id;
+        while from_id <= gap.last_missed_id {
+            self.timeout_manager
+                .reserve_when_available(
+                    self.exchange_account_id,
+                    RequestType::GetLastTrades,
+                    None,
+                    self.lifetime_manager.stop_token(),
+                )?
+                .await
+                .into_result()?;
+
+            let response = self
+                .exchange_client
+                .request_trades_from_id(currency_pair, trade_stream, from_id, BACKFILL_PAGE_LIMIT)
+                .await?;
+            if let Some(error) = get_rest_error(&response, self.exchange_account_id, false) {
+                bail!("Unable to request trades: {:?}", error);
+            }
+
+            let page = self
+                .exchange_client
+                .parse_trades_from_id(&response)?
+                .into_iter()
+                .filter(|trade| {
+                    let trade_id = trade.trade_id.get_number();
+                    trade_id >= from_id && trade_id <= gap.last_missed_id
+                })
+                .sorted_by_key(|trade| trade.trade_id.get_number())
+                .collect_vec();
+
+            match page.last() {
+                Some(last_trade) => from_id = last_trade.trade_id.get_number() + 1,
+                None => break,
+            }
+            trades.extend(page);
+        }
+
+        let backfilled_count = trades.len() as u64;
+        if !trades.is_empty() {
+            self.events_channel
+                .send_expected(ExchangeEvent::Trades(TradesEvent {
+                    exchange_account_id: self.exchange_account_id,
+                    currency_pair,
+                    trades,
+                    receipt_time: timeout_manager::now(),
+                }));
+        }
+
+        log::info!(
+            "Backfilled {} of {} missed trades of {} on {}",
+            backfilled_count,
+            gap.missed_count(),
+            currency_pair,
+            self.exchange_account_id
+        );
+
+        Ok(backfilled_count)
+    }
+
+    fn send_trade_gap(&self, currency_pair: CurrencyPair, gap: TradeIdGap, backfilled_count: u64) {
+        log::error!(
+            "Trades {}..={} of {} on {} aren't repaired, backfilled {} of {}",
+            gap.first_missed_id,
+            gap.last_missed_id,
+            currency_pair,
+            self.exchange_account_id,
+            backfilled_count,
+            gap.missed_count()
+        );
+
+        self.events_channel
+            .send_expected(ExchangeEvent::TradeGap(TradeGapEvent {
+                exchange_account_id: self.exchange_account_id,
+                currency_pair,
+                first_missed_id: gap.first_missed_id,
+                last_missed_id: gap.last_missed_id,
+                backfilled_count,
+            }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gaps_of_trade_ids_are_detected() {
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        let mut tracker = TradeIdsTracker::default();
+
+        let stream = TradeStream::Trades;
+
+        assert_eq!(tracker.on_trade(currency_pair, stream, 10), None);
+        assert_eq!(tracker.on_trade(currency_pair, stream, 11), None);
+        assert_eq!(tracker.on_trade(currency_pair, stream, 11), None);
+        assert_eq!(
+            tracker.on_trade(currency_pair, stream, 15),
+            Some(TradeIdGap {
+                first_missed_id: 12,
+                last_missed_id: 14,
+            })
+        );
+        assert_eq!(tracker.on_trade(currency_pair, stream, 13), None);
+        assert_eq!(tracker.on_trade(currency_pair, stream, 16), None);
+
+        let other_currency_pair = CurrencyPair::from_codes("eth".into(), "usdt".into());
+        assert_eq!(tracker.on_trade(other_currency_pair, stream, 100), None);
+    }
+
+    #[test]
+    fn streams_of_market_are_tracked_separately() {
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        let mut tracker = TradeIdsTracker::default();
+
+        assert_eq!(
+            tracker.on_trade(currency_pair, TradeStream::Trades, 1000),
+            None
+        );
+        assert_eq!(
+            tracker.on_trade(currency_pair, TradeStream::AggregatedTrades, 200),
+            None
+        );
+        assert_eq!(
+            tracker.on_trade(currency_pair, TradeStream::Trades, 1001),
+            None
+        );
+        assert_eq!(
+            tracker.on_trade(currency_pair, TradeStream::AggregatedTrades, 203),
+            Some(TradeIdGap {
+                first_missed_id: 201,
+                last_missed_id: 202,
+            })
+        );
+    }
+}
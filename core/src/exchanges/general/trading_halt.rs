@@ -0,0 +1,214 @@
+use mmb_utils::send_expected::SendExpectedByRef;
+
+use crate::exchanges::common::{CurrencyPair, ExchangeError, ExchangeErrorType};
+use crate::exchanges::events::{ExchangeEvent, MarketTradingStatusEvent};
+
+use super::exchange::Exchange;
+use super::symbol::Symbol;
+
+impl Exchange {
+    pub fn is_market_halted(&self, currency_pair: CurrencyPair) -> bool {
+        self.halted_markets.contains_key(&currency_pair)
+    }
+
+    /// Halted markets with reasons of halt
+    pub fn halted_markets(&self) -> Vec<(CurrencyPair, String)> {
+        self.halted_markets
+            .iter()
+            .map(|x| (*x.key(), x.value().clone()))
+            .collect()
+    }
+
+    /// Mark market as halted: new orders for the currency pair will be rejected until trading is resumed
+    pub fn set_market_halted(&self, currency_pair: CurrencyPair, reason: &str) {
+        if self
+            .halted_markets
+            .insert(currency_pair, reason.to_owned())
+            .is_some()
+        {
+            return;
+        }
+
+        log::error!(
+            "Trading is halted for {} on {}: {}",
+            currency_pair,
+            self.exchange_account_id,
+            reason
+        );
+
+        self.send_market_trading_status(currency_pair, true);
+    }
+
+    pub fn set_market_resumed(&self, currency_pair: CurrencyPair) {
+        if self.halted_markets.remove(&currency_pair).is_none() {
+            return;
+        }
+
+        log::info!(
+            "Trading is resumed for {} on {}",
+            currency_pair,
+            self.exchange_account_id
+        );
+
+        self.send_market_trading_status(currency_pair, false);
+    }
+
+    pub(super) fn update_market_trading_status(&self, symbol: &Symbol) {
+        let currency_pair = symbol.currency_pair();
+        match symbol.is_active {
+            true => self.set_market_resumed(currency_pair),
+            false => self.set_market_halted(currency_pair, "symbol is inactive on exchange"),
+        }
+    }
+
+    pub(super) fn check_trading_halt_error(
+        &self,
+        currency_pair: CurrencyPair,
+        exchange_error: &ExchangeError,
+    ) {
+        if exchange_error.error_type == ExchangeErrorType::TradingHalted {
+            self.set_market_halted(currency_pair, &exchange_error.message);
+        }
+    }
+
+    fn send_market_trading_status(&self, currency_pair: CurrencyPair, is_halted: bool) {
+        self.events_channel
+            .send_expected(ExchangeEvent::MarketTradingStatus(
+                MarketTradingStatusEvent {
+                    exchange_account_id: self.exchange_account_id,
+                    currency_pair,
+                    is_halted,
+                },
+            ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mmb_utils::cancellation_token::CancellationToken;
+    use rust_decimal_macros::dec;
+    use tokio::sync::broadcast;
+
+    use super::*;
+    use crate::exchanges::general::order::rejection::RejectionReason;
+    use crate::exchanges::general::test_helper::get_test_exchange;
+    use crate::orders::order::OrderSide;
+    use crate::orders::order_builder::OrderBuilder;
+
+    /// Halt flags of received events of market trading status
+    fn received_trading_statuses(rx: &mut broadcast::Receiver<ExchangeEvent>) -> Vec<bool> {
+        let mut statuses = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if let ExchangeEvent::MarketTradingStatus(event) = event {
+                statuses.push(event.is_halted);
+            }
+        }
+        statuses
+    }
+
+    fn currency_pair(exchange: &Exchange) -> CurrencyPair {
+        *exchange.symbols.iter().next().expect("in test").key()
+    }
+
+    #[test]
+    fn market_is_halted_until_resumed() {
+        let (exchange, mut rx) = get_test_exchange(false);
+        let currency_pair = currency_pair(&exchange);
+        assert!(!exchange.is_market_halted(currency_pair));
+
+        exchange.set_market_halted(currency_pair, "maintenance");
+        exchange.set_market_halted(currency_pair, "maintenance");
+
+        assert!(exchange.is_market_halted(currency_pair));
+        assert_eq!(
+            exchange.halted_markets(),
+            vec![(currency_pair, "maintenance".to_owned())]
+        );
+        // Status is reported only on transition
+        assert_eq!(received_trading_statuses(&mut rx), vec![true]);
+
+        exchange.set_market_resumed(currency_pair);
+        exchange.set_market_resumed(currency_pair);
+
+        assert!(!exchange.is_market_halted(currency_pair));
+        assert!(exchange.halted_markets().is_empty());
+        assert_eq!(received_trading_statuses(&mut rx), vec![false]);
+    }
+
+    #[test]
+    fn market_is_halted_while_symbol_is_inactive() {
+        let (exchange, mut rx) = get_test_exchange(false);
+        let currency_pair = currency_pair(&exchange);
+        let mut symbol = exchange
+            .symbols
+            .get(&currency_pair)
+            .expect("in test")
+            .value()
+            .as_ref()
+            .clone();
+
+        // Symbol of test exchange is inactive by default
+        symbol.is_active = true;
+        exchange.update_market_trading_status(&symbol);
+        assert!(!exchange.is_market_halted(currency_pair));
+
+        symbol.is_active = false;
+        exchange.update_market_trading_status(&symbol);
+        assert!(exchange.is_market_halted(currency_pair));
+
+        symbol.is_active = true;
+        exchange.update_market_trading_status(&symbol);
+        assert!(!exchange.is_market_halted(currency_pair));
+
+        assert_eq!(received_trading_statuses(&mut rx), vec![true, false]);
+    }
+
+    #[test]
+    fn market_is_halted_by_trading_halted_error() {
+        let (exchange, mut rx) = get_test_exchange(false);
+        let currency_pair = currency_pair(&exchange);
+
+        let error = ExchangeError::new(
+            ExchangeErrorType::InsufficientFunds,
+            "insufficient funds".to_owned(),
+            None,
+        );
+        exchange.check_trading_halt_error(currency_pair, &error);
+        assert!(!exchange.is_market_halted(currency_pair));
+
+        let error = ExchangeError::new(
+            ExchangeErrorType::TradingHalted,
+            "market is closed".to_owned(),
+            None,
+        );
+        exchange.check_trading_halt_error(currency_pair, &error);
+        assert_eq!(
+            exchange.halted_markets(),
+            vec![(currency_pair, "market is closed".to_owned())]
+        );
+        assert_eq!(received_trading_statuses(&mut rx), vec![true]);
+    }
+
+    // Test client panics on any request, so order is rejected before sending
+    #[tokio::test]
+    async fn orders_are_rejected_for_halted_market() {
+        let (exchange, _rx) = get_test_exchange(false);
+        let currency_pair = currency_pair(&exchange);
+        exchange.set_market_halted(currency_pair, "maintenance");
+
+        let order = OrderBuilder::new(exchange.exchange_account_id, currency_pair)
+            .side(OrderSide::Buy)
+            .limit(dec!(1))
+            .amount(dec!(1))
+            .build()
+            .expect("in test");
+        let error = exchange
+            .create_order(&order, None, CancellationToken::default())
+            .await
+            .expect_err("in test");
+
+        let rejection = error.order_rejection().expect("in test");
+        assert_eq!(rejection.reason, RejectionReason::MarketHalted);
+        assert_eq!(rejection.client_order_id, order.header.client_order_id);
+    }
+}
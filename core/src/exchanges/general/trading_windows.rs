@@ -0,0 +1,291 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Datelike;
+use futures::FutureExt;
+use itertools::Itertools;
+use mmb_utils::DateTime;
+use mockall_double::double;
+use thiserror::Error;
+
+use crate::exchanges::common::{CurrencyPair, ExchangeAccountId};
+#[double]
+use crate::misc::time::time_manager;
+use crate::services::scheduler::{Schedule, Scheduler};
+use crate::settings::TradingWindowSettings;
+
+use super::exchange::Exchange;
+
+/// Period of checking that orders are placed only in trading windows
+const TRADING_WINDOWS_CHECK_PERIOD: Duration = Duration::from_secs(60);
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("Trading for {currency_pair} on {exchange_account_id} is outside of trading windows")]
+pub struct OutsideTradingWindowError {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+}
+
+impl TradingWindowSettings {
+    fn is_applied_to(&self, currency_pair: CurrencyPair) -> bool {
+        self.currency_pair
+            .map_or(true, |window_pair| window_pair == currency_pair)
+    }
+
+    /// Day of week is checked for start of window, so overnight window belongs to day when it's started
+    pub fn contains(&self, time: DateTime) -> bool {
+        let time_of_day = time.time();
+        let (is_inside, start_date) = match self.start <= self.end {
+            true => (
+                self.start <= time_of_day && time_of_day < self.end,
+                time.date(),
+            ),
+            false if time_of_day >= self.start => (true, time.date()),
+            false => (time_of_day < self.end, time.date().pred()),
+        };
+
+        is_inside
+            && self.weekdays.as_ref().map_or(true, |weekdays| {
+                weekdays.contains(&start_date.weekday().number_from_at())
+            })
+    }
+}
+
+impl Exchange {
+    /// Trading is allowed if there are no trading windows for currency pair or current time is inside one of them
+    pub fn is_trading_allowed(&self, currency_pair: CurrencyPair) -> bool {
+        let windows = match &self.exchange_client.get_settings().trading_windows {
+            Some(windows) => windows,
+            None => return true,
+        };
+
+        let now = time_manager::now();
+        let mut applied_windows = windows
+            .iter()
+            .filter(|window| window.is_applied_to(currency_pair))
+            .peekable();
+
+        applied_windows.peek().is_none() || applied_windows.any(|window| window.contains(now))
+    }
+
+    pub(crate) fn check_trading_window(
+        &self,
+        currency_pair: CurrencyPair,
+    ) -> Result<(), OutsideTradingWindowError> {
+        match self.is_trading_allowed(currency_pair) {
+            true => Ok(()),
+            false => Err(OutsideTradingWindowError {
+                exchange_account_id: self.exchange_account_id,
+                currency_pair,
+            }),
+        }
+    }
+
+    /// Periodically cancel orders of markets which are outside of trading windows
+    pub(crate) fn schedule_trading_windows_checking(self: &Arc<Self>, scheduler: &Arc<Scheduler>) {
+        let exchange_weak = Arc::downgrade(self);
+        let check_trading_windows = move |_| {
+            let exchange_weak = exchange_weak.clone();
+            async move {
+                if let Some(exchange) = exchange_weak.upgrade() {
+                    exchange.cancel_orders_outside_trading_windows().await;
+                }
+            }
+            .boxed()
+        };
+
+        let _ = scheduler.schedule(
+            &format!("Check trading windows for {}", self.exchange_account_id),
+            Schedule::Every(TRADING_WINDOWS_CHECK_PERIOD),
+            check_trading_windows,
+        );
+    }
+
+    /// Currency pairs of not finished orders which are outside of trading windows
+    fn currency_pairs_outside_trading_windows(&self) -> Vec<CurrencyPair> {
+        self.orders
+            .not_finished
+            .iter()
+            .map(|order| order.value().currency_pair())
+            .unique()
+            .filter(|currency_pair| !self.is_trading_allowed(*currency_pair))
+            .collect_vec()
+    }
+
+    async fn cancel_orders_outside_trading_windows(&self) {
+        for currency_pair in self.currency_pairs_outside_trading_windows() {
+            log::info!(
+                "Cancelling orders for {} on {} because trading window is closed",
+                currency_pair,
+                self.exchange_account_id
+            );
+
+            if let Err(error) = self.cancel_all_orders(currency_pair).await {
+                log::warn!(
+                    "Unable to cancel orders for {} on {} outside of trading window: {:?}",
+                    currency_pair,
+                    self.exchange_account_id,
+                    error
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveTime, TimeZone, Utc};
+    use mmb_utils::cancellation_token::CancellationToken;
+    use parking_lot::{Mutex, ReentrantMutexGuard};
+
+    use super::*;
+    use crate::exchanges::general::test_helper::get_test_exchange_with_settings;
+    use crate::infrastructure::init_lifetime_manager;
+    use crate::orders::order::OrderStatus;
+    use crate::settings::ExchangeSettings;
+    use crate::test_util::OrderSnapshotBuilder;
+
+    /// Time of `time_manager::now` is mocked until returned guards are dropped
+    fn mock_now(
+        now: Arc<Mutex<DateTime>>,
+    ) -> (
+        time_manager::__now::Context,
+        ReentrantMutexGuard<'static, ()>,
+    ) {
+        let mock_locker = crate::MOCK_MUTEX.lock();
+        let now_context = time_manager::now_context();
+        now_context.expect().returning(move || *now.lock());
+
+        (now_context, mock_locker)
+    }
+
+    fn at(hour: u32) -> DateTime {
+        Utc.ymd(2021, 9, 20).and_hms(hour, 0, 0)
+    }
+
+    fn exchange_with_windows(windows: Vec<TradingWindowSettings>) -> Arc<Exchange> {
+        let (exchange, _rx) = get_test_exchange_with_settings(ExchangeSettings {
+            trading_windows: Some(windows),
+            ..ExchangeSettings::default()
+        });
+        exchange
+    }
+
+    fn currency_pair(exchange: &Exchange) -> CurrencyPair {
+        *exchange.symbols.iter().next().expect("in test").key()
+    }
+
+    fn window(
+        start: (u32, u32),
+        end: (u32, u32),
+        weekdays: Option<Vec<u32>>,
+    ) -> TradingWindowSettings {
+        TradingWindowSettings {
+            currency_pair: None,
+            start: NaiveTime::from_hms(start.0, start.1, 0),
+            end: NaiveTime::from_hms(end.0, end.1, 0),
+            weekdays,
+        }
+    }
+
+    #[test]
+    fn daytime_window() {
+        let window = window((8, 0), (20, 0), None);
+
+        assert!(window.contains(Utc.ymd(2022, 3, 1).and_hms(8, 0, 0)));
+        assert!(window.contains(Utc.ymd(2022, 3, 1).and_hms(19, 59, 59)));
+        assert!(!window.contains(Utc.ymd(2022, 3, 1).and_hms(20, 0, 0)));
+        assert!(!window.contains(Utc.ymd(2022, 3, 1).and_hms(7, 0, 0)));
+    }
+
+    #[test]
+    fn overnight_window_belongs_to_start_day() {
+        // 2022-03-04 is Friday
+        let window = window((22, 0), (2, 0), Some(vec![5]));
+
+        assert!(window.contains(Utc.ymd(2022, 3, 4).and_hms(23, 0, 0)));
+        assert!(window.contains(Utc.ymd(2022, 3, 5).and_hms(1, 0, 0)));
+        assert!(!window.contains(Utc.ymd(2022, 3, 5).and_hms(23, 0, 0)));
+        assert!(!window.contains(Utc.ymd(2022, 3, 4).and_hms(1, 0, 0)));
+        assert!(!window.contains(Utc.ymd(2022, 3, 4).and_hms(12, 0, 0)));
+    }
+
+    #[test]
+    fn trading_is_disabled_and_enabled_by_window() {
+        let now = Arc::new(Mutex::new(at(10)));
+        let (_mock, _locker) = mock_now(now.clone());
+        let exchange = exchange_with_windows(vec![window((8, 0), (20, 0), None)]);
+        let currency_pair = currency_pair(&exchange);
+
+        assert!(exchange.is_trading_allowed(currency_pair));
+        assert_eq!(exchange.check_trading_window(currency_pair), Ok(()));
+
+        *now.lock() = at(20);
+        assert!(!exchange.is_trading_allowed(currency_pair));
+        assert_eq!(
+            exchange.check_trading_window(currency_pair),
+            Err(OutsideTradingWindowError {
+                exchange_account_id: exchange.exchange_account_id,
+                currency_pair,
+            })
+        );
+
+        *now.lock() = at(8);
+        assert!(exchange.is_trading_allowed(currency_pair));
+    }
+
+    #[test]
+    fn windows_of_other_currency_pairs_are_not_applied() {
+        let (_mock, _locker) = mock_now(Arc::new(Mutex::new(at(1))));
+        let mut other_pair_window = window((8, 0), (20, 0), None);
+        other_pair_window.currency_pair =
+            Some(CurrencyPair::from_codes("eth".into(), "btc".into()));
+        let exchange = exchange_with_windows(vec![other_pair_window]);
+
+        assert!(exchange.is_trading_allowed(currency_pair(&exchange)));
+    }
+
+    #[test]
+    fn orders_are_cancelled_only_after_window_is_closed() {
+        let now = Arc::new(Mutex::new(at(10)));
+        let (_mock, _locker) = mock_now(now.clone());
+        let exchange = exchange_with_windows(vec![window((8, 0), (20, 0), None)]);
+        let currency_pair = currency_pair(&exchange);
+        let _order = OrderSnapshotBuilder::new(exchange.exchange_account_id, currency_pair)
+            .status(OrderStatus::Created, Utc::now())
+            .build_ref(&exchange.orders);
+
+        assert!(exchange.currency_pairs_outside_trading_windows().is_empty());
+
+        *now.lock() = at(21);
+        assert_eq!(
+            exchange.currency_pairs_outside_trading_windows(),
+            vec![currency_pair]
+        );
+    }
+
+    #[tokio::test]
+    async fn trading_windows_checking_is_scheduled() {
+        let _ = init_lifetime_manager();
+        let exchange = exchange_with_windows(vec![window((8, 0), (20, 0), None)]);
+        let stop_token = CancellationToken::new();
+        let scheduler = Scheduler::new(stop_token.clone());
+
+        exchange.schedule_trading_windows_checking(&scheduler);
+
+        let job_names = scheduler
+            .jobs()
+            .into_iter()
+            .map(|(_, name)| name)
+            .collect_vec();
+        assert_eq!(
+            job_names,
+            vec![format!(
+                "Check trading windows for {}",
+                exchange.exchange_account_id
+            )]
+        );
+
+        stop_token.cancel();
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::common::{Amount, CurrencyCode};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::helpers::get_rest_error;
+
+/// Withdrawal of funds from exchange account to external address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Withdrawal {
+    pub currency_code: CurrencyCode,
+    pub amount: Amount,
+    pub address: String,
+    /// Blockchain network of address. Default network of currency is used if it isn't specified
+    pub network: Option<String>,
+    /// Secondary address identifier (memo, tag) required by some currencies
+    pub address_tag: Option<String>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WalletType {
+    Spot,
+    Margin,
+    Futures,
+}
+
+/// Transfer of funds between wallets of the same exchange account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InternalTransfer {
+    pub currency_code: CurrencyCode,
+    pub amount: Amount,
+    pub from: WalletType,
+    pub to: WalletType,
+}
+
+impl Exchange {
+    /// Returns exchange id of withdrawal
+    pub async fn withdraw(&self, withdrawal: &Withdrawal) -> Result<String> {
+        self.ensure_withdrawal_enabled()?;
+
+        log::warn!(
+            "Withdrawal {:?} requested on {}",
+            withdrawal,
+            self.exchange_account_id
+        );
+
+        let response = self.exchange_client.request_withdraw(withdrawal).await?;
+        if let Some(error) = get_rest_error(&response, self.exchange_account_id, false) {
+            bail!(
+                "Withdrawal {:?} failed on {}: {:?}",
+                withdrawal,
+                self.exchange_account_id,
+                error
+            );
+        }
+
+        self.exchange_client.parse_withdraw(&response)
+    }
+
+    /// Returns exchange id of transfer
+    pub async fn internal_transfer(&self, transfer: &InternalTransfer) -> Result<String> {
+        self.ensure_withdrawal_enabled()?;
+
+        log::warn!(
+            "Internal transfer {:?} requested on {}",
+            transfer,
+            self.exchange_account_id
+        );
+
+        let response = self
+            .exchange_client
+            .request_internal_transfer(transfer)
+            .await?;
+        if let Some(error) = get_rest_error(&response, self.exchange_account_id, false) {
+            bail!(
+                "Internal transfer {:?} failed on {}: {:?}",
+                transfer,
+                self.exchange_account_id,
+                error
+            );
+        }
+
+        self.exchange_client.parse_internal_transfer(&response)
+    }
+
+    pub(super) fn ensure_withdrawal_enabled(&self) -> Result<()> {
+        let settings = self.exchange_client.get_settings();
+        if !settings.is_withdrawal_enabled.unwrap_or(false) {
+            bail!(
+                "Withdrawals aren't enabled in settings for {}",
+                self.exchange_account_id
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
+use dashmap::DashMap;
 use mmb_utils::cancellation_token::CancellationToken;
 use mmb_utils::infrastructure::WithExpect;
 use mmb_utils::nothing_to_do;
@@ -16,24 +17,39 @@ use crate::order_book::event::OrderBookEvent;
 use crate::order_book::local_snapshot_service::LocalSnapshotsService;
 use crate::orders::event::OrderEventType;
 use crate::orders::order::OrderType;
+use crate::services::event_loop_watchdog::LoopHeartbeat;
 
 pub(crate) struct InternalEventsLoop {
     work_finished_receiver: Mutex<Option<oneshot::Receiver<Result<()>>>>,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
 }
 
 impl InternalEventsLoop {
     pub(crate) fn new() -> Arc<Self> {
         Arc::new(InternalEventsLoop {
             work_finished_receiver: Default::default(),
+            exchanges: DashMap::new(),
         })
     }
 
+    /// Events of exchange account which is added after engine start are handled too
+    pub(crate) fn add_exchange(&self, exchange: Arc<Exchange>) {
+        let _ = self
+            .exchanges
+            .insert(exchange.exchange_account_id, exchange);
+    }
+
     pub async fn start(
         self: Arc<Self>,
         mut events_receiver: broadcast::Receiver<ExchangeEvent>,
         exchanges_map: HashMap<ExchangeAccountId, Arc<Exchange>>,
         cancellation_token: CancellationToken,
+        heartbeat: Arc<LoopHeartbeat>,
     ) -> Result<()> {
+        for (exchange_account_id, exchange) in exchanges_map {
+            let _ = self.exchanges.insert(exchange_account_id, exchange);
+        }
+
         let mut local_snapshots_service = LocalSnapshotsService::default();
         let (work_finished_sender, receiver) = oneshot::channel();
         *self.work_finished_receiver.lock() = Some(receiver);
@@ -47,17 +63,19 @@ impl InternalEventsLoop {
                 }
             };
 
+            let _iteration = heartbeat.iteration(&event);
             match event {
                 ExchangeEvent::OrderBookEvent(order_book_event) => {
                     update_order_book_top_for_exchange(
                         order_book_event,
                         &mut local_snapshots_service,
-                        &exchanges_map,
+                        &self.exchanges,
                     )
                 }
                 ExchangeEvent::OrderEvent(order_event) => {
                     let target_eai = order_event.order.exchange_account_id();
-                    let exchange = exchanges_map
+                    let exchange = self
+                        .exchanges
                         .get(&target_eai)
                         .with_expect(|| format!("Failed to get Exchange for {}", target_eai));
 
@@ -81,9 +99,17 @@ impl InternalEventsLoop {
                 }
                 ExchangeEvent::BalanceUpdate(order_event) => {
                     let target_eai = order_event.exchange_account_id;
-                    let exchange = exchanges_map
-                        .get(&target_eai)
-                        .with_expect(|| format!("Failed to get Exchange for {}", target_eai));
+                    // Exchange which is being added at runtime sends updates before it's set up,
+                    // its balances are requested at the end of setup
+                    let exchange = match self.exchanges.get(&target_eai) {
+                        Some(exchange) => exchange,
+                        None => {
+                            log::info!(
+                                "Balance update of not added exchange {target_eai} is skipped"
+                            );
+                            continue;
+                        }
+                    };
 
                     exchange
                         .balance_manager
@@ -105,6 +131,22 @@ impl InternalEventsLoop {
                 }
                 ExchangeEvent::LiquidationPrice(_) => {}
                 ExchangeEvent::Trades(_) => {}
+                ExchangeEvent::SymbolAdded(_) | ExchangeEvent::SymbolUpdated(_) => {}
+                ExchangeEvent::MarketTradingStatus(_) => {}
+                ExchangeEvent::LiquidationOrder(_) | ExchangeEvent::MarginCall(_) => {}
+                ExchangeEvent::MarginRatio(_) => {}
+                // Balances are requested by exchange after conversion,
+                // commissions are registered by StatisticEventHandler
+                ExchangeEvent::DustConversion(_) => {}
+                ExchangeEvent::PartialFillTimeout(_) => {}
+                ExchangeEvent::BalanceDelta(_) => {}
+                ExchangeEvent::FillAnomaly(_) => {}
+                ExchangeEvent::CommissionCorrection(_) => {
+                    // Fill of order is corrected by exchange before sending event,
+                    // commission ledger is corrected by StatisticEventHandler
+                }
+                ExchangeEvent::TradeGap(_) => {}
+                ExchangeEvent::EventLoopStall(_) => {}
             }
         }
     }
@@ -113,7 +155,7 @@ impl InternalEventsLoop {
 fn update_order_book_top_for_exchange(
     order_book_event: OrderBookEvent,
     local_snapshots_service: &mut LocalSnapshotsService,
-    exchanges_map: &HashMap<ExchangeAccountId, Arc<Exchange>>,
+    exchanges: &DashMap<ExchangeAccountId, Arc<Exchange>>,
 ) {
     let market_account_id = local_snapshots_service.update(order_book_event);
     if let Some(market_account_id) = &market_account_id {
@@ -128,13 +170,12 @@ fn update_order_book_top_for_exchange(
                 .map(|(price, amount)| PriceLevel { price, amount }),
         };
 
-        exchanges_map
-            .get(&market_account_id.exchange_account_id)
-            .map(|exchange| {
-                exchange
-                    .order_book_top
-                    .insert(market_account_id.currency_pair, order_book_top)
-            });
+        if let Some(exchange) = exchanges.get(&market_account_id.exchange_account_id) {
+            let _ = exchange
+                .order_book_top
+                .insert(market_account_id.currency_pair, order_book_top);
+            exchange.convert_deferred_commissions(market_account_id.currency_pair);
+        }
     }
 }
 
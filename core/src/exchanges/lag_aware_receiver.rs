@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use futures::Stream;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::exchanges::events::ExchangeEvent;
+use crate::misc::time::time_manager;
+
+pub type ExchangeEventsReceiver = LagAwareReceiver<ExchangeEvent>;
+
+/// Item of `LagAwareReceiver`
+#[derive(Debug, Clone)]
+pub enum ReceivedEvent<T> {
+    Event(T),
+    /// Receiver didn't keep up with channel, so `count` events were overwritten before receiving.
+    /// Receiving is continued from the oldest event which is still buffered
+    EventsDropped {
+        count: u64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct LagMetrics {
+    pub received_count: u64,
+    /// Count of times when receiver lagged behind channel
+    pub lags_count: u64,
+    pub dropped_count: u64,
+    pub last_lag_time: Option<DateTime>,
+}
+
+/// Receiver of broadcast channel which handles `RecvError::Lagged` in one place: lag is logged
+/// and counted in metrics, and consumer gets `ReceivedEvent::EventsDropped` to resynchronize
+/// its state, because state built from events with a gap can be wrong
+pub struct LagAwareReceiver<T> {
+    name: String,
+    receiver: broadcast::Receiver<T>,
+    metrics: Arc<Mutex<LagMetrics>>,
+}
+
+impl<T: Clone> LagAwareReceiver<T> {
+    /// `name` of consumer is used in logs
+    pub fn new(name: impl Into<String>, sender: &broadcast::Sender<T>) -> Self {
+        Self {
+            name: name.into(),
+            receiver: sender.subscribe(),
+            metrics: Default::default(),
+        }
+    }
+
+    /// Next event or notification about dropped events. `None` if channel is closed
+    pub async fn recv(&mut self) -> Option<ReceivedEvent<T>> {
+        match self.receiver.recv().await {
+            Ok(event) => {
+                self.metrics.lock().received_count += 1;
+                Some(ReceivedEvent::Event(event))
+            }
+            Err(RecvError::Lagged(count)) => {
+                self.on_lagged(count);
+                Some(ReceivedEvent::EventsDropped { count })
+            }
+            Err(RecvError::Closed) => None,
+        }
+    }
+
+    pub fn metrics(&self) -> LagMetrics {
+        *self.metrics.lock()
+    }
+
+    /// Metrics which stay available after receiver is moved to consuming task
+    pub fn metrics_handle(&self) -> Arc<Mutex<LagMetrics>> {
+        self.metrics.clone()
+    }
+
+    pub fn into_stream(self) -> impl Stream<Item = ReceivedEvent<T>>
+    where
+        T: Send,
+    {
+        futures::stream::unfold(self, |mut receiver| async move {
+            let item = receiver.recv().await?;
+            Some((item, receiver))
+        })
+    }
+
+    fn on_lagged(&self, count: u64) {
+        let mut metrics = self.metrics.lock();
+        metrics.lags_count += 1;
+        metrics.dropped_count += count;
+        metrics.last_lag_time = Some(time_manager::now());
+
+        log::warn!(
+            "{} lagged behind events channel and skipped {} events, {} events are skipped in total",
+            self.name,
+            count,
+            metrics.dropped_count
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn recv_event(receiver: &mut LagAwareReceiver<i32>) -> Option<i32> {
+        match receiver.recv().await? {
+            ReceivedEvent::Event(event) => Some(event),
+            ReceivedEvent::EventsDropped { count } => panic!("Unexpected drop of {} events", count),
+        }
+    }
+
+    #[tokio::test]
+    async fn dropped_events_are_reported_to_consumer() {
+        let (sender, _) = broadcast::channel(2);
+        let mut receiver = LagAwareReceiver::new("Test receiver", &sender);
+
+        for i in 0..5 {
+            let _ = sender.send(i);
+        }
+        assert!(matches!(
+            receiver.recv().await,
+            Some(ReceivedEvent::EventsDropped { count: 3 })
+        ));
+
+        // Buffered events are received after notification about dropped ones
+        assert_eq!(recv_event(&mut receiver).await, Some(3));
+        assert_eq!(recv_event(&mut receiver).await, Some(4));
+
+        drop(sender);
+        assert!(receiver.recv().await.is_none());
+
+        let metrics = receiver.metrics();
+        assert_eq!(metrics.received_count, 2);
+        assert_eq!(metrics.lags_count, 1);
+        assert_eq!(metrics.dropped_count, 3);
+        assert!(metrics.last_lag_time.is_some());
+    }
+}
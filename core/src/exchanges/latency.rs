@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use itertools::Itertools;
+use mmb_utils::DateTime;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::connectivity::connectivity_manager::WebSocketRole;
+use crate::exchanges::common::ExchangeAccountId;
+use crate::misc::time::time_manager;
+
+/// Weight of new sample in exponential moving average of latency
+const SMOOTHING_FACTOR: f64 = 0.2;
+/// Sample is logged as latency spike if it exceeds average latency this many times
+const SPIKE_FACTOR: u32 = 5;
+
+static LATENCY_STATISTICS: Lazy<Mutex<HashMap<ExchangeAccountId, Arc<LatencyStatistics>>>> =
+    Lazy::new(Default::default);
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum LatencyChannel {
+    Rest,
+    WebSocket(WebSocketRole),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LatencySnapshot {
+    pub last: Duration,
+    /// Exponential moving average of round-trip time
+    pub average: Duration,
+    pub max: Duration,
+    pub samples_count: u64,
+    pub last_update_time: DateTime,
+}
+
+impl LatencySnapshot {
+    fn new(latency: Duration, now: DateTime) -> Self {
+        Self {
+            last: latency,
+            average: latency,
+            max: latency,
+            samples_count: 1,
+            last_update_time: now,
+        }
+    }
+
+    fn add(&mut self, latency: Duration, now: DateTime) {
+        self.last = latency;
+        self.average =
+            self.average.mul_f64(1. - SMOOTHING_FACTOR) + latency.mul_f64(SMOOTHING_FACTOR);
+        self.max = self.max.max(latency);
+        self.samples_count += 1;
+        self.last_update_time = now;
+    }
+}
+
+/// Average and max latency of channel in milliseconds for status reports
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LatencyRecord {
+    pub channel: LatencyChannel,
+    pub average_ms: u64,
+    pub max_ms: u64,
+}
+
+/// Round-trip latencies of REST requests and websocket heartbeats of an exchange account.
+/// Statistics are shared by exchange account id, so they are available for strategies through `Exchange`
+#[derive(Debug)]
+pub struct LatencyStatistics {
+    exchange_account_id: ExchangeAccountId,
+    channels: Mutex<HashMap<LatencyChannel, LatencySnapshot>>,
+}
+
+impl LatencyStatistics {
+    pub fn get_or_create(exchange_account_id: ExchangeAccountId) -> Arc<Self> {
+        LATENCY_STATISTICS
+            .lock()
+            .entry(exchange_account_id)
+            .or_insert_with(|| {
+                Arc::new(Self {
+                    exchange_account_id,
+                    channels: Default::default(),
+                })
+            })
+            .clone()
+    }
+
+    pub fn record(&self, channel: LatencyChannel, latency: Duration) {
+        let now = time_manager::now();
+        let mut channels = self.channels.lock();
+        match channels.get_mut(&channel) {
+            None => {
+                let _ = channels.insert(channel, LatencySnapshot::new(latency, now));
+            }
+            Some(snapshot) => {
+                if latency > snapshot.average * SPIKE_FACTOR {
+                    log::warn!(
+                        "Latency spike of {:?} on {}: {:?} (average {:?})",
+                        channel,
+                        self.exchange_account_id,
+                        latency,
+                        snapshot.average
+                    );
+                }
+                snapshot.add(latency, now);
+            }
+        }
+    }
+
+    pub fn get(&self, channel: LatencyChannel) -> Option<LatencySnapshot> {
+        self.channels.lock().get(&channel).cloned()
+    }
+
+    pub fn get_all(&self) -> HashMap<LatencyChannel, LatencySnapshot> {
+        self.channels.lock().clone()
+    }
+
+    /// Connectivity is degraded if average latency of any channel exceeds threshold
+    pub fn is_degraded(&self, threshold: Duration) -> bool {
+        self.channels
+            .lock()
+            .values()
+            .any(|snapshot| snapshot.average > threshold)
+    }
+
+    pub fn records(&self) -> Vec<LatencyRecord> {
+        self.get_all()
+            .into_iter()
+            .map(|(channel, snapshot)| LatencyRecord {
+                channel,
+                average_ms: snapshot.average.as_millis() as u64,
+                max_ms: snapshot.max.as_millis() as u64,
+            })
+            .sorted_by_key(|x| format!("{:?}", x.channel))
+            .collect_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn average_latency_is_smoothed() {
+        let statistics = LatencyStatistics::get_or_create("Binance_10".parse().expect("in test"));
+
+        statistics.record(LatencyChannel::Rest, Duration::from_millis(100));
+        statistics.record(LatencyChannel::Rest, Duration::from_millis(200));
+
+        let snapshot = statistics.get(LatencyChannel::Rest).expect("in test");
+        assert_eq!(snapshot.last, Duration::from_millis(200));
+        assert_eq!(snapshot.average, Duration::from_millis(120));
+        assert_eq!(snapshot.max, Duration::from_millis(200));
+        assert_eq!(snapshot.samples_count, 2);
+        assert!(statistics.is_degraded(Duration::from_millis(110)));
+        assert!(!statistics.is_degraded(Duration::from_millis(150)));
+    }
+
+    #[test]
+    fn records_are_ordered_by_channel() {
+        let statistics = LatencyStatistics::get_or_create("Binance_11".parse().expect("in test"));
+
+        statistics.record(
+            LatencyChannel::WebSocket(WebSocketRole::Main),
+            Duration::from_millis(30),
+        );
+        statistics.record(LatencyChannel::Rest, Duration::from_millis(10));
+
+        assert_eq!(
+            statistics.records(),
+            vec![
+                LatencyRecord {
+                    channel: LatencyChannel::Rest,
+                    average_ms: 10,
+                    max_ms: 10,
+                },
+                LatencyRecord {
+                    channel: LatencyChannel::WebSocket(WebSocketRole::Main),
+                    average_ms: 30,
+                    max_ms: 30,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use mmb_utils::DateTime;
+
+use crate::connectivity::connectivity_manager::WebSocketRole;
+use crate::exchanges::common::{CurrencyPair, RestRequestOutcome};
+use crate::exchanges::general::exchange::BoxExchangeClient;
+use crate::exchanges::general::helpers::get_rest_error;
+use crate::exchanges::general::pagination::{PageCursor, PageItem, PageRequest};
+use crate::exchanges::transport::TrafficRecorder;
+
+/// Historical market data item converted to websocket message of exchange
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoricalMessage {
+    /// Exchange id of item which is used as cursor of the next page
+    pub id: String,
+    pub time: DateTime,
+    pub message: String,
+}
+
+impl PageItem for HistoricalMessage {
+    fn page_id(&self) -> String {
+        self.id.clone()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarketDataKind {
+    Trades,
+    /// Klines with interval in exchange format, e.g. "1m"
+    Klines {
+        interval: String,
+    },
+}
+
+/// Backfills historical market data from REST API of exchange into traffic record file,
+/// so backtests can replay it like recorded websocket traffic
+pub struct MarketDataDownloader {
+    exchange_client: BoxExchangeClient,
+    recorder: Arc<TrafficRecorder>,
+    /// Min delay between requests to stay within rate limits of exchange
+    request_period: Duration,
+}
+
+impl MarketDataDownloader {
+    pub fn new(
+        exchange_client: BoxExchangeClient,
+        recorder: Arc<TrafficRecorder>,
+        request_period: Duration,
+    ) -> Self {
+        Self {
+            exchange_client,
+            recorder,
+            request_period,
+        }
+    }
+
+    /// Loads symbols of exchange, which are needed to convert currency pairs to exchange format
+    pub async fn init(&self) -> Result<()> {
+        let response = self.exchange_client.request_all_symbols().await?;
+        self.check_response(&response)?;
+        let _ = self.exchange_client.parse_all_symbols(&response)?;
+
+        Ok(())
+    }
+
+    /// Records market data of currency pair for period `[from, to)`. Returns count of recorded messages
+    pub async fn download(
+        &self,
+        currency_pair: CurrencyPair,
+        kind: &MarketDataKind,
+        from: DateTime,
+        to: DateTime,
+    ) -> Result<usize> {
+        let limit = self.exchange_client.page_limit();
+        let mut cursor = PageCursor::FromTime(from);
+        let mut recorded_count = 0;
+
+        loop {
+            let page = PageRequest {
+                cursor: Some(cursor.clone()),
+                limit,
+            };
+            let messages = self.request_page(currency_pair, kind, &page).await?;
+            let is_last_page = messages.len() < limit;
+
+            let last_message = match messages.last() {
+                Some(last_message) => last_message.clone(),
+                None => return Ok(recorded_count),
+            };
+
+            for message in messages.iter().filter(|x| x.time < to) {
+                self.recorder.record_websocket_message_at(
+                    message.time,
+                    WebSocketRole::Main,
+                    &message.message,
+                );
+                recorded_count += 1;
+            }
+
+            log::info!(
+                "Recorded {} {:?} messages of {} until {}",
+                recorded_count,
+                kind,
+                currency_pair,
+                last_message.time
+            );
+
+            if is_last_page || last_message.time >= to {
+                return Ok(recorded_count);
+            }
+
+            cursor = PageCursor::AfterId(last_message.page_id());
+            tokio::time::sleep(self.request_period).await;
+        }
+    }
+
+    async fn request_page(
+        &self,
+        currency_pair: CurrencyPair,
+        kind: &MarketDataKind,
+        page: &PageRequest,
+    ) -> Result<Vec<HistoricalMessage>> {
+        match kind {
+            MarketDataKind::Trades => {
+                let response = self
+                    .exchange_client
+                    .request_historical_trades(currency_pair, page)
+                    .await?;
+                self.check_response(&response)?;
+
+                self.exchange_client
+                    .parse_historical_trades(&response, currency_pair)
+            }
+            MarketDataKind::Klines { interval } => {
+                let response = self
+                    .exchange_client
+                    .request_klines(currency_pair, interval, page)
+                    .await?;
+                self.check_response(&response)?;
+
+                self.exchange_client
+                    .parse_klines(&response, currency_pair, interval)
+            }
+        }
+    }
+
+    fn check_response(&self, response: &RestRequestOutcome) -> Result<()> {
+        let exchange_account_id = self.exchange_client.get_settings().exchange_account_id;
+        if let Some(error) = get_rest_error(response, exchange_account_id, false) {
+            bail!(
+                "Unable to download market data from {}: {:?}",
+                exchange_account_id,
+                error
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::exchanges::general::test_helper::TestClient;
+    use crate::exchanges::transport::{read_traffic_records, TrafficRecord};
+    use crate::settings::ExchangeSettings;
+
+    fn start_time() -> DateTime {
+        Utc.ymd(2022, 3, 1).and_hms(0, 0, 0)
+    }
+
+    /// Trades with one second interval since `start_time`
+    fn historical_trades(count: i64) -> Vec<HistoricalMessage> {
+        (0..count)
+            .map(|i| HistoricalMessage {
+                id: i.to_string(),
+                time: start_time() + chrono::Duration::seconds(i),
+                message: format!("trade {}", i),
+            })
+            .collect()
+    }
+
+    /// Downloads market data since `start_time` into temporary traffic record file.
+    /// Returns result of download and recorded messages
+    async fn download(
+        historical_trades: Vec<HistoricalMessage>,
+        kind: MarketDataKind,
+        to: DateTime,
+    ) -> (Result<usize>, Vec<String>) {
+        let path =
+            std::env::temp_dir().join(format!("mmb_market_data_{}.jsonl", uuid::Uuid::new_v4()));
+        let path = path.to_str().expect("in test").to_owned();
+        let recorder = TrafficRecorder::get_or_create(&path).expect("in test");
+        let exchange_client = Box::new(TestClient::with_historical_trades(
+            ExchangeSettings::default(),
+            historical_trades,
+        ));
+        let downloader =
+            MarketDataDownloader::new(exchange_client, recorder.clone(), Duration::ZERO);
+
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        let result = downloader
+            .download(currency_pair, &kind, start_time(), to)
+            .await;
+        recorder.flush();
+        let records = read_traffic_records(&path);
+
+        std::fs::remove_file(&path).expect("in test");
+        let messages = records
+            .expect("in test")
+            .into_iter()
+            .map(|record| match record {
+                TrafficRecord::WebSocket {
+                    timestamp, message, ..
+                } => {
+                    assert!(timestamp >= start_time() && timestamp < to);
+                    message
+                }
+                _ => panic!("unexpected record {:?}", record),
+            })
+            .collect();
+
+        (result, messages)
+    }
+
+    #[tokio::test]
+    async fn trades_are_downloaded_by_pages_until_end_of_period() {
+        // Default page limit is 500, so trades are requested by 2 pages
+        let trades = historical_trades(600);
+        let to = start_time() + chrono::Duration::seconds(550);
+
+        let (result, messages) = download(trades.clone(), MarketDataKind::Trades, to).await;
+
+        assert_eq!(result.expect("in test"), 550);
+        let expected = trades[..550]
+            .iter()
+            .map(|x| x.message.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, expected);
+    }
+
+    #[tokio::test]
+    async fn download_is_finished_at_last_page() {
+        let to = start_time() + chrono::Duration::days(1);
+
+        let (result, messages) = download(historical_trades(3), MarketDataKind::Trades, to).await;
+
+        assert_eq!(result.expect("in test"), 3);
+        assert_eq!(messages, ["trade 0", "trade 1", "trade 2"]);
+    }
+
+    #[tokio::test]
+    async fn unsupported_market_data_is_not_recorded() {
+        let kind = MarketDataKind::Klines {
+            interval: "1m".to_owned(),
+        };
+        let to = start_time() + chrono::Duration::days(1);
+
+        let (result, messages) = download(historical_trades(3), kind, to).await;
+
+        assert!(result.is_err());
+        assert!(messages.is_empty());
+    }
+}
@@ -1,10 +1,17 @@
 pub mod block_reasons;
 pub mod common;
+pub mod event_log;
 pub mod events;
 pub mod exchange_blocker;
 pub mod general;
 pub mod hosts;
 pub(crate) mod internal_events_loop;
+pub mod lag_aware_receiver;
+pub mod latency;
+pub mod market_data_downloader;
 pub mod rest_client;
+pub mod simulation;
+pub mod time_sync;
 pub mod timeouts;
 pub mod traits;
+pub mod transport;
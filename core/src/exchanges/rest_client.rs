@@ -1,35 +1,81 @@
 use super::common::*;
+use super::latency::{LatencyChannel, LatencyStatistics};
+use super::transport::{
+    RecordingTransport, ReplayTransport, RestMethod, RestRequest, TrafficRecorder, Transport,
+};
+use crate::connectivity::proxy::Proxy;
+use crate::settings::ExchangeSettings;
 use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use futures::FutureExt;
 use hyper::client::HttpConnector;
+use hyper::service::Service;
 use hyper::{Body, Client, Error, Request, Response, Uri};
 use hyper_tls::HttpsConnector;
 use std::convert::TryInto;
+use std::future::Future;
+use std::sync::Arc;
+use std::task::{self, Poll};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
 
 pub type HttpParams = Vec<(String, String)>;
 
 pub struct RestClient {
-    client: Client<HttpsConnector<HttpConnector>>,
+    transport: Arc<dyn Transport>,
+    latency_statistics: Option<Arc<LatencyStatistics>>,
 }
 
 const KEEP_ALIVE: &'static str = "keep-alive";
 
 impl RestClient {
     pub fn new() -> Self {
+        Self::with_transport(Arc::new(HyperTransport::new(None, None, None)))
+    }
+
+    pub fn with_transport(transport: Arc<dyn Transport>) -> Self {
         Self {
-            client: create_client(),
+            transport,
+            latency_statistics: None,
         }
     }
 
-    pub async fn get(&self, url: Uri, api_key: &str) -> Result<RestRequestOutcome> {
-        let req = Request::get(url)
-            .header(hyper::header::CONNECTION, KEEP_ALIVE)
-            .header("X-MBX-APIKEY", api_key)
-            .body(Body::empty())
-            .context("Error during creation of http GET request")?;
+    pub fn from_settings(settings: &ExchangeSettings) -> Result<Self> {
+        if let Some(replay_path) = &settings.traffic_replay_path {
+            return Ok(Self::with_transport(Arc::new(ReplayTransport::load(
+                replay_path,
+            )?)));
+        }
+
+        let proxy = settings
+            .proxy
+            .as_ref()
+            .map(|proxy| Proxy::parse(proxy))
+            .transpose()?;
 
-        let response = self.client.request(req).await;
+        let mut transport: Arc<dyn Transport> = Arc::new(HyperTransport::new(
+            settings.rest_connect_timeout.map(|x| x.duration()),
+            settings.rest_request_timeout.map(|x| x.duration()),
+            proxy,
+        ));
 
-        handle_response(response, "GET").await
+        if let Some(record_path) = &settings.traffic_record_path {
+            let recorder = TrafficRecorder::get_or_create(record_path)?;
+            transport = Arc::new(RecordingTransport::new(transport, recorder));
+        }
+
+        let mut rest_client = Self::with_transport(transport);
+        rest_client.latency_statistics = Some(LatencyStatistics::get_or_create(
+            settings.exchange_account_id,
+        ));
+
+        Ok(rest_client)
+    }
+
+    pub async fn get(&self, url: Uri, api_key: &str) -> Result<RestRequestOutcome> {
+        self.send(RestMethod::Get, url, api_key, String::new())
+            .await
     }
 
     pub async fn post(
@@ -42,35 +88,154 @@ impl RestClient {
             .extend_pairs(http_params)
             .finish();
 
-        let req = Request::post(url)
-            .header(hyper::header::CONNECTION, KEEP_ALIVE)
-            .header("X-MBX-APIKEY", api_key)
-            .body(Body::from(form_encoded))
-            .context("Error during creation of http delete request")?;
+        self.send(RestMethod::Post, url, api_key, form_encoded)
+            .await
+    }
+
+    pub async fn delete(&self, url: Uri, api_key: &str) -> Result<RestRequestOutcome> {
+        self.send(RestMethod::Delete, url, api_key, String::new())
+            .await
+    }
+
+    async fn send(
+        &self,
+        method: RestMethod,
+        url: Uri,
+        api_key: &str,
+        body: String,
+    ) -> Result<RestRequestOutcome> {
+        let request = RestRequest {
+            method,
+            url,
+            api_key: api_key.to_owned(),
+            body,
+        };
 
-        let response = self.client.request(req).await;
+        let started = Instant::now();
+        let outcome = self.transport.send(request).await?;
 
-        handle_response(response, "POST").await
+        if let Some(latency_statistics) = &self.latency_statistics {
+            latency_statistics.record(LatencyChannel::Rest, started.elapsed());
+        }
+
+        Ok(outcome)
     }
+}
 
-    pub async fn delete(&self, url: Uri, api_key: &str) -> Result<RestRequestOutcome> {
-        let req = Request::delete(url)
+/// Sends requests to exchange through network
+pub struct HyperTransport {
+    client: Client<HttpsConnector<TcpConnector>>,
+    // Timeout for the whole request including reading of response body
+    request_timeout: Option<Duration>,
+}
+
+impl HyperTransport {
+    pub fn new(
+        connect_timeout: Option<Duration>,
+        request_timeout: Option<Duration>,
+        proxy: Option<Proxy>,
+    ) -> Self {
+        Self {
+            client: create_client(connect_timeout, proxy),
+            request_timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for HyperTransport {
+    async fn send(&self, request: RestRequest) -> Result<RestRequestOutcome> {
+        let (builder, rest_action) = match request.method {
+            RestMethod::Get => (Request::get(request.url), "GET"),
+            RestMethod::Post => (Request::post(request.url), "POST"),
+            RestMethod::Delete => (Request::delete(request.url), "DELETE"),
+        };
+
+        let req = builder
             .header(hyper::header::CONNECTION, KEEP_ALIVE)
-            .header("X-MBX-APIKEY", api_key)
-            .body(Body::empty())
-            .context("Error during creation of http delete request")?;
+            .header("X-MBX-APIKEY", request.api_key)
+            .body(Body::from(request.body))
+            .with_context(|| format!("Error during creation of http {} request", rest_action))?;
 
-        let response = self.client.request(req).await;
+        let sending = async {
+            let response = self.client.request(req).await;
+            handle_response(response, rest_action).await
+        };
+
+        with_request_timeout(self.request_timeout, rest_action, sending).await
+    }
+}
 
-        handle_response(response, "DELETE").await
+async fn with_request_timeout(
+    request_timeout: Option<Duration>,
+    rest_action: &str,
+    request: impl Future<Output = Result<RestRequestOutcome>>,
+) -> Result<RestRequestOutcome> {
+    match request_timeout {
+        None => request.await,
+        Some(timeout) => tokio::time::timeout(timeout, request)
+            .await
+            .with_context(|| {
+                format!("Timeout {:?} exceeded for {} request", timeout, rest_action)
+            })?,
     }
 }
 
-fn create_client() -> Client<HttpsConnector<HttpConnector>> {
-    let https = HttpsConnector::new();
+fn create_client(
+    connect_timeout: Option<Duration>,
+    proxy: Option<Proxy>,
+) -> Client<HttpsConnector<TcpConnector>> {
+    let mut http = HttpConnector::new();
+    http.enforce_http(false);
+    http.set_connect_timeout(connect_timeout);
+
+    let connector = TcpConnector {
+        http,
+        proxy: proxy.map(Arc::new),
+    };
+
+    let https = HttpsConnector::new_with_connector(connector);
     Client::builder().build::<_, Body>(https)
 }
 
+/// Opens tcp connections directly or through proxy if it's specified
+#[derive(Clone)]
+pub struct TcpConnector {
+    http: HttpConnector,
+    proxy: Option<Arc<Proxy>>,
+}
+
+impl Service<Uri> for TcpConnector {
+    type Response = TcpStream;
+    type Error = anyhow::Error;
+    type Future = BoxFuture<'static, Result<TcpStream>>;
+
+    fn poll_ready(&mut self, cx: &mut task::Context<'_>) -> Poll<Result<()>> {
+        self.http.poll_ready(cx).map_err(anyhow::Error::from)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        match self.proxy.clone() {
+            None => self
+                .http
+                .call(uri)
+                .map(|stream| stream.map_err(anyhow::Error::from))
+                .boxed(),
+            Some(proxy) => async move {
+                let host = uri.host().context("Host isn't specified in uri")?;
+                let port = match (uri.port_u16(), uri.scheme_str()) {
+                    (Some(port), _) => port,
+                    (None, Some("https")) => 443,
+                    (None, _) => 80,
+                };
+
+                proxy.connect(host, port).await
+            }
+            .boxed(),
+        }
+    }
+}
+
 // Inner Hyper types. Needed just for unified response handling in handle_response()
 type ResponseType = std::result::Result<Response<Body>, Error>;
 async fn handle_response(response: ResponseType, rest_action: &str) -> Result<RestRequestOutcome> {
@@ -0,0 +1,323 @@
+use std::f64::consts::PI;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::common::{Amount, Price};
+use crate::order_book::order_book_data::OrderBookData;
+use crate::orders::order::OrderSide;
+
+/// Delay between sending of request and its processing by simulated exchange
+pub trait LatencyModel: Send + Sync {
+    fn latency(&self) -> Duration;
+}
+
+/// Price of simulated taker execution
+pub trait SlippageModel: Send + Sync {
+    /// `price` is price of book level which is executed, `order_book` is book before execution
+    fn execution_price(
+        &self,
+        side: OrderSide,
+        price: Price,
+        amount: Amount,
+        order_book: &OrderBookData,
+    ) -> Price;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Distribution {
+    Uniform { min: f64, max: f64 },
+    Normal { mean: f64, std_dev: f64 },
+}
+
+impl Distribution {
+    fn sample(&self, rng: &mut StdRng) -> f64 {
+        match *self {
+            Distribution::Uniform { min, max } => match min < max {
+                true => rng.gen_range(min..max),
+                false => min,
+            },
+            Distribution::Normal { mean, std_dev } => {
+                // Box-Muller transform, first uniform value is in (0, 1] to avoid ln(0)
+                let u1 = 1. - rng.gen::<f64>();
+                let u2 = rng.gen::<f64>();
+                mean + std_dev * (-2. * u1.ln()).sqrt() * (2. * PI * u2).cos()
+            }
+        }
+    }
+}
+
+pub struct ConstantLatency(pub Duration);
+
+impl LatencyModel for ConstantLatency {
+    fn latency(&self) -> Duration {
+        self.0
+    }
+}
+
+/// Latency in milliseconds sampled from distribution. Negative samples are treated as zero latency
+pub struct SampledLatency {
+    distribution_ms: Distribution,
+    rng: Mutex<StdRng>,
+}
+
+impl SampledLatency {
+    pub fn new(distribution_ms: Distribution, rng: StdRng) -> Self {
+        Self {
+            distribution_ms,
+            rng: Mutex::new(rng),
+        }
+    }
+}
+
+impl LatencyModel for SampledLatency {
+    fn latency(&self) -> Duration {
+        let latency_ms = self.distribution_ms.sample(&mut self.rng.lock());
+        Duration::from_secs_f64(latency_ms.max(0.) / 1000.)
+    }
+}
+
+/// Level price is executed as is
+pub struct NoSlippage;
+
+impl SlippageModel for NoSlippage {
+    fn execution_price(&self, _: OrderSide, price: Price, _: Amount, _: &OrderBookData) -> Price {
+        price
+    }
+}
+
+/// Execution price is worse than level price by fixed basis points
+pub struct ConstantSlippage {
+    pub bps: Decimal,
+}
+
+impl SlippageModel for ConstantSlippage {
+    fn execution_price(
+        &self,
+        side: OrderSide,
+        price: Price,
+        _: Amount,
+        _: &OrderBookData,
+    ) -> Price {
+        apply_slippage_bps(side, price, self.bps)
+    }
+}
+
+/// Slippage in basis points sampled from distribution. Negative samples mean price improvement
+pub struct SampledSlippage {
+    distribution_bps: Distribution,
+    rng: Mutex<StdRng>,
+}
+
+impl SampledSlippage {
+    pub fn new(distribution_bps: Distribution, rng: StdRng) -> Self {
+        Self {
+            distribution_bps,
+            rng: Mutex::new(rng),
+        }
+    }
+}
+
+impl SlippageModel for SampledSlippage {
+    fn execution_price(
+        &self,
+        side: OrderSide,
+        price: Price,
+        _: Amount,
+        _: &OrderBookData,
+    ) -> Price {
+        let bps = self.distribution_bps.sample(&mut self.rng.lock());
+        apply_slippage_bps(side, price, Decimal::from_f64(bps).unwrap_or_default())
+    }
+}
+
+/// Slippage proportional to share of visible liquidity which is taken by execution:
+/// `impact_bps * amount / depth`, where depth is amount of first `levels_count` levels of opposite side.
+/// Models impact of hidden reaction of other participants which isn't visible in L2 book
+pub struct BookImpactSlippage {
+    pub impact_bps: Decimal,
+    pub levels_count: usize,
+}
+
+impl SlippageModel for BookImpactSlippage {
+    fn execution_price(
+        &self,
+        side: OrderSide,
+        price: Price,
+        amount: Amount,
+        order_book: &OrderBookData,
+    ) -> Price {
+        let depth: Amount = match side {
+            OrderSide::Buy => order_book.asks.values().take(self.levels_count).sum(),
+            OrderSide::Sell => order_book.bids.values().rev().take(self.levels_count).sum(),
+        };
+
+        if depth.is_zero() {
+            return price;
+        }
+
+        apply_slippage_bps(side, price, self.impact_bps * amount / depth)
+    }
+}
+
+fn apply_slippage_bps(side: OrderSide, price: Price, bps: Decimal) -> Price {
+    let slippage = price * bps / dec!(10000);
+    match side {
+        OrderSide::Buy => price + slippage,
+        OrderSide::Sell => price - slippage,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LatencyModelSettings {
+    Constant { latency_ms: u64 },
+    Sampled { distribution_ms: Distribution },
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SlippageModelSettings {
+    None,
+    Constant {
+        bps: Decimal,
+    },
+    Sampled {
+        distribution_bps: Distribution,
+    },
+    BookImpact {
+        impact_bps: Decimal,
+        levels_count: usize,
+    },
+}
+
+/// Execution assumptions of one backtest run
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ExecutionModelsSettings {
+    pub latency: LatencyModelSettings,
+    pub slippage: SlippageModelSettings,
+    /// Seed of random generator, so runs with sampled models are reproducible.
+    /// Generator is seeded from entropy if it isn't specified
+    pub seed: Option<u64>,
+}
+
+impl Default for ExecutionModelsSettings {
+    fn default() -> Self {
+        Self {
+            latency: LatencyModelSettings::Constant { latency_ms: 0 },
+            slippage: SlippageModelSettings::None,
+            seed: None,
+        }
+    }
+}
+
+impl ExecutionModelsSettings {
+    pub fn create_latency_model(&self) -> Box<dyn LatencyModel> {
+        match &self.latency {
+            LatencyModelSettings::Constant { latency_ms } => {
+                Box::new(ConstantLatency(Duration::from_millis(*latency_ms)))
+            }
+            LatencyModelSettings::Sampled { distribution_ms } => {
+                Box::new(SampledLatency::new(*distribution_ms, self.create_rng()))
+            }
+        }
+    }
+
+    pub fn create_slippage_model(&self) -> Box<dyn SlippageModel> {
+        match &self.slippage {
+            SlippageModelSettings::None => Box::new(NoSlippage),
+            SlippageModelSettings::Constant { bps } => Box::new(ConstantSlippage { bps: *bps }),
+            SlippageModelSettings::Sampled { distribution_bps } => {
+                Box::new(SampledSlippage::new(*distribution_bps, self.create_rng()))
+            }
+            SlippageModelSettings::BookImpact {
+                impact_bps,
+                levels_count,
+            } => Box::new(BookImpactSlippage {
+                impact_bps: *impact_bps,
+                levels_count: *levels_count,
+            }),
+        }
+    }
+
+    fn create_rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_book_data;
+
+    #[test]
+    fn constant_slippage_is_adverse_for_both_sides() {
+        let model = ConstantSlippage { bps: dec!(10) };
+        let order_book = order_book_data![];
+
+        assert_eq!(
+            model.execution_price(OrderSide::Buy, dec!(100), dec!(1), &order_book),
+            dec!(100.1)
+        );
+        assert_eq!(
+            model.execution_price(OrderSide::Sell, dec!(100), dec!(1), &order_book),
+            dec!(99.9)
+        );
+    }
+
+    #[test]
+    fn book_impact_slippage_depends_on_taken_share_of_depth() {
+        let model = BookImpactSlippage {
+            impact_bps: dec!(100),
+            levels_count: 2,
+        };
+        let order_book = order_book_data![
+            dec!(101) => dec!(1),
+            dec!(102) => dec!(3),
+            dec!(103) => dec!(100),
+            ;
+        ];
+
+        // 1 of 4 visible amount is taken, so slippage is 25 bps
+        assert_eq!(
+            model.execution_price(OrderSide::Buy, dec!(100), dec!(1), &order_book),
+            dec!(100.25)
+        );
+        // No visible liquidity on bids side
+        assert_eq!(
+            model.execution_price(OrderSide::Sell, dec!(100), dec!(1), &order_book),
+            dec!(100)
+        );
+    }
+
+    #[test]
+    fn sampled_latency_is_reproducible_with_seed() {
+        let settings = ExecutionModelsSettings {
+            latency: LatencyModelSettings::Sampled {
+                distribution_ms: Distribution::Uniform { min: 10., max: 50. },
+            },
+            slippage: SlippageModelSettings::None,
+            seed: Some(42),
+        };
+
+        let sample =
+            |model: Box<dyn LatencyModel>| (0..10).map(|_| model.latency()).collect::<Vec<_>>();
+        let first = sample(settings.create_latency_model());
+        let second = sample(settings.create_latency_model());
+
+        assert_eq!(first, second);
+        assert!(first
+            .iter()
+            .all(|x| Duration::from_millis(10) <= *x && *x <= Duration::from_millis(50)));
+    }
+}
@@ -0,0 +1,597 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use itertools::Itertools;
+use mmb_utils::DateTime;
+use rust_decimal_macros::dec;
+use thiserror::Error;
+
+use crate::exchanges::common::{Amount, Price, SortedOrderData};
+use crate::exchanges::events::Trade;
+use crate::exchanges::simulation::execution_models::{
+    ConstantLatency, ExecutionModelsSettings, LatencyModel, NoSlippage, SlippageModel,
+};
+use crate::order_book::order_book_data::OrderBookData;
+use crate::orders::order::{ClientOrderId, OrderExecutionType, OrderRole, OrderSide};
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MatchingError {
+    #[error("Order {0} already exists")]
+    DuplicateOrder(ClientOrderId),
+    #[error("Maker only order {0} would be executed as taker")]
+    MakerOnlyOrderWouldTake(ClientOrderId),
+}
+
+/// Limit order resting in simulated book
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulatedOrder {
+    pub client_order_id: ClientOrderId,
+    pub side: OrderSide,
+    pub price: Price,
+    pub amount: Amount,
+    pub execution_type: OrderExecutionType,
+    pub filled_amount: Amount,
+    /// Estimated amount of other orders at the same price which should be executed before this order
+    pub queue_ahead: Amount,
+    pub creation_time: DateTime,
+}
+
+impl SimulatedOrder {
+    pub fn new(
+        client_order_id: ClientOrderId,
+        side: OrderSide,
+        price: Price,
+        amount: Amount,
+        execution_type: OrderExecutionType,
+        creation_time: DateTime,
+    ) -> Self {
+        Self {
+            client_order_id,
+            side,
+            price,
+            amount,
+            execution_type,
+            filled_amount: dec!(0),
+            queue_ahead: dec!(0),
+            creation_time,
+        }
+    }
+
+    pub fn remaining_amount(&self) -> Amount {
+        self.amount - self.filled_amount
+    }
+
+    /// Order price is reached by opposite price, e.g. buy order is crossed by ask at the same or lower price
+    fn is_crossed_by(&self, price: Price) -> bool {
+        match self.side {
+            OrderSide::Buy => price <= self.price,
+            OrderSide::Sell => price >= self.price,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulatedFill {
+    pub client_order_id: ClientOrderId,
+    pub price: Price,
+    pub amount: Amount,
+    pub role: OrderRole,
+    pub time: DateTime,
+    /// Order is fully filled and removed from simulator
+    pub is_completed: bool,
+}
+
+/// Matching of limit orders of paper trading against real L2 book and trade prints of one market.
+/// Marketable part of order is executed as taker by walking the book, the rest joins the end of queue
+/// of its price level. Queue position is moved forward by trades at the level and by decrease of level
+/// amount (cancellations are assumed to be ahead of order), so maker fills happen only when traded
+/// volume went through the estimated queue
+pub struct MatchingSimulator {
+    order_book: OrderBookData,
+    orders: HashMap<ClientOrderId, SimulatedOrder>,
+    /// Submitted orders which haven't reached simulated exchange yet, with their arrival time
+    pending_orders: Vec<(DateTime, SimulatedOrder)>,
+    rejected_orders: Vec<MatchingError>,
+    latency_model: Box<dyn LatencyModel>,
+    slippage_model: Box<dyn SlippageModel>,
+}
+
+impl MatchingSimulator {
+    pub fn new() -> Self {
+        Self::with_execution_models(
+            Box::new(ConstantLatency(Duration::ZERO)),
+            Box::new(NoSlippage),
+        )
+    }
+
+    pub fn from_settings(settings: &ExecutionModelsSettings) -> Self {
+        Self::with_execution_models(
+            settings.create_latency_model(),
+            settings.create_slippage_model(),
+        )
+    }
+
+    pub fn with_execution_models(
+        latency_model: Box<dyn LatencyModel>,
+        slippage_model: Box<dyn SlippageModel>,
+    ) -> Self {
+        Self {
+            order_book: OrderBookData::new(SortedOrderData::new(), SortedOrderData::new()),
+            orders: HashMap::new(),
+            pending_orders: Vec::new(),
+            rejected_orders: Vec::new(),
+            latency_model,
+            slippage_model,
+        }
+    }
+
+    pub fn order_book(&self) -> &OrderBookData {
+        &self.order_book
+    }
+
+    pub fn get_order(&self, client_order_id: &ClientOrderId) -> Option<&SimulatedOrder> {
+        self.orders.get(client_order_id)
+    }
+
+    /// Order rests in book or is submitted and hasn't arrived yet
+    pub fn is_order_active(&self, client_order_id: &ClientOrderId) -> bool {
+        self.orders.contains_key(client_order_id)
+            || self
+                .pending_orders
+                .iter()
+                .any(|(_, order)| &order.client_order_id == client_order_id)
+    }
+
+    pub fn open_orders(&self) -> impl Iterator<Item = &SimulatedOrder> {
+        self.orders.values()
+    }
+
+    /// Order reaches simulated exchange after latency of latency model and is placed by the first
+    /// market data event after arrival time. Rejections of such orders are available in `take_rejected_orders`
+    pub fn submit_order(&mut self, order: SimulatedOrder, time: DateTime) {
+        let latency = chrono::Duration::from_std(self.latency_model.latency())
+            .expect("Unable to convert latency");
+        self.pending_orders.push((time + latency, order));
+    }
+
+    pub fn take_rejected_orders(&mut self) -> Vec<MatchingError> {
+        std::mem::take(&mut self.rejected_orders)
+    }
+
+    /// Order is placed immediately without latency
+    pub fn place_order(
+        &mut self,
+        mut order: SimulatedOrder,
+        time: DateTime,
+    ) -> Result<Vec<SimulatedFill>, MatchingError> {
+        if self.orders.contains_key(&order.client_order_id) {
+            return Err(MatchingError::DuplicateOrder(order.client_order_id));
+        }
+
+        let crossed_levels = match order.side {
+            OrderSide::Buy => self
+                .order_book
+                .asks
+                .range(..=order.price)
+                .map(|(&price, &amount)| (price, amount))
+                .collect_vec(),
+            OrderSide::Sell => self
+                .order_book
+                .bids
+                .range(order.price..)
+                .rev()
+                .map(|(&price, &amount)| (price, amount))
+                .collect_vec(),
+        };
+
+        if !crossed_levels.is_empty() && order.execution_type == OrderExecutionType::MakerOnly {
+            return Err(MatchingError::MakerOnlyOrderWouldTake(
+                order.client_order_id,
+            ));
+        }
+
+        let mut executions = Vec::new();
+        for (price, level_amount) in crossed_levels {
+            let amount = order.remaining_amount().min(level_amount);
+            if amount.is_zero() {
+                break;
+            }
+
+            order.filled_amount += amount;
+            executions.push((price, amount));
+        }
+
+        let mut fills = executions
+            .iter()
+            .map(|&(price, amount)| SimulatedFill {
+                client_order_id: order.client_order_id.clone(),
+                price: self.slippage_model.execution_price(
+                    order.side,
+                    price,
+                    amount,
+                    &self.order_book,
+                ),
+                amount,
+                role: OrderRole::Taker,
+                time,
+                is_completed: false,
+            })
+            .collect_vec();
+
+        let opposite_levels = match order.side {
+            OrderSide::Buy => &mut self.order_book.asks,
+            OrderSide::Sell => &mut self.order_book.bids,
+        };
+        for (price, amount) in executions {
+            let level_amount = opposite_levels.entry(price).or_default();
+            *level_amount -= amount;
+            if level_amount.is_zero() {
+                let _ = opposite_levels.remove(&price);
+            }
+        }
+
+        match order.remaining_amount().is_zero() {
+            true => {
+                if let Some(last_fill) = fills.last_mut() {
+                    last_fill.is_completed = true;
+                }
+            }
+            false => {
+                order.queue_ahead = self.level_amount(order.side, order.price);
+                let _ = self.orders.insert(order.client_order_id.clone(), order);
+            }
+        }
+
+        Ok(fills)
+    }
+
+    /// Order is removed immediately, including submitted orders which haven't arrived yet
+    pub fn cancel_order(&mut self, client_order_id: &ClientOrderId) -> Option<SimulatedOrder> {
+        let pending_position = self
+            .pending_orders
+            .iter()
+            .position(|(_, order)| &order.client_order_id == client_order_id);
+        if let Some(position) = pending_position {
+            return Some(self.pending_orders.remove(position).1);
+        }
+
+        self.orders.remove(client_order_id)
+    }
+
+    /// Book is replaced by snapshot. Resting orders crossed by new book are filled as makers,
+    /// because the market has moved through their price
+    pub fn on_order_book_snapshot(
+        &mut self,
+        order_book: OrderBookData,
+        time: DateTime,
+    ) -> Vec<SimulatedFill> {
+        self.order_book = order_book;
+        let mut fills = self.on_order_book_changed(time);
+        fills.extend(self.place_arrived_orders(time));
+        fills
+    }
+
+    pub fn on_order_book_update(
+        &mut self,
+        update: &OrderBookData,
+        time: DateTime,
+    ) -> Vec<SimulatedFill> {
+        OrderBookData::apply_update(&mut self.order_book.asks, &mut self.order_book.bids, update);
+        let mut fills = self.on_order_book_changed(time);
+        fills.extend(self.place_arrived_orders(time));
+        fills
+    }
+
+    /// Trade prints consume queue of the level, so resting orders are partially filled
+    /// when traded volume goes through their queue position
+    pub fn on_trade(&mut self, trade: &Trade) -> Vec<SimulatedFill> {
+        // Side of trade is side of taker, so buy trades execute resting sell orders
+        let maker_side = trade.side.change_side();
+        let mut traded_amount = trade.quantity;
+
+        let mut fills = self.place_arrived_orders(trade.transaction_time);
+        for client_order_id in self.orders_by_priority(maker_side) {
+            if traded_amount.is_zero() {
+                break;
+            }
+
+            let order = self.orders.get_mut(&client_order_id).expect("order exists");
+            if !order.is_crossed_by(trade.price) {
+                continue;
+            }
+
+            // Trade at better price than order price means the whole level of order was executed,
+            // otherwise traded volume is consumed by queue ahead of order first
+            if trade.price == order.price {
+                let consumed = order.queue_ahead.min(traded_amount);
+                order.queue_ahead -= consumed;
+                traded_amount -= consumed;
+            } else {
+                order.queue_ahead = dec!(0);
+            }
+
+            let amount = order.remaining_amount().min(traded_amount);
+            if amount.is_zero() {
+                continue;
+            }
+
+            traded_amount -= amount;
+            fills.push(self.fill_order(&client_order_id, amount, trade.transaction_time));
+        }
+
+        fills
+    }
+
+    fn place_arrived_orders(&mut self, time: DateTime) -> Vec<SimulatedFill> {
+        let (arrived_orders, pending_orders) = std::mem::take(&mut self.pending_orders)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(arrival_time, _)| *arrival_time <= time);
+        self.pending_orders = pending_orders;
+
+        let mut fills = Vec::new();
+        for (arrival_time, order) in arrived_orders
+            .into_iter()
+            .sorted_by_key(|(arrival_time, _)| *arrival_time)
+        {
+            match self.place_order(order, arrival_time) {
+                Ok(order_fills) => fills.extend(order_fills),
+                Err(error) => self.rejected_orders.push(error),
+            }
+        }
+
+        fills
+    }
+
+    fn on_order_book_changed(&mut self, time: DateTime) -> Vec<SimulatedFill> {
+        let best_ask = self.order_book.asks.keys().next().copied();
+        let best_bid = self.order_book.bids.keys().next_back().copied();
+
+        let mut fills = Vec::new();
+        for client_order_id in self.orders.keys().cloned().collect_vec() {
+            let order = &self.orders[&client_order_id];
+            let opposite_price = match order.side {
+                OrderSide::Buy => best_ask,
+                OrderSide::Sell => best_bid,
+            };
+
+            if opposite_price.map_or(false, |price| order.is_crossed_by(price)) {
+                let amount = order.remaining_amount();
+                fills.push(self.fill_order(&client_order_id, amount, time));
+                continue;
+            }
+
+            let level_amount = self.level_amount(order.side, order.price);
+            let order = self.orders.get_mut(&client_order_id).expect("order exists");
+            order.queue_ahead = order.queue_ahead.min(level_amount);
+        }
+
+        fills
+    }
+
+    fn fill_order(
+        &mut self,
+        client_order_id: &ClientOrderId,
+        amount: Amount,
+        time: DateTime,
+    ) -> SimulatedFill {
+        let order = self.orders.get_mut(client_order_id).expect("order exists");
+        order.filled_amount += amount;
+
+        let fill = SimulatedFill {
+            client_order_id: client_order_id.clone(),
+            price: order.price,
+            amount,
+            role: OrderRole::Maker,
+            time,
+            is_completed: order.remaining_amount().is_zero(),
+        };
+
+        if fill.is_completed {
+            let _ = self.orders.remove(client_order_id);
+        }
+
+        fill
+    }
+
+    /// Orders of side sorted from best price, orders with the same price sorted by creation time
+    fn orders_by_priority(&self, side: OrderSide) -> Vec<ClientOrderId> {
+        self.orders
+            .values()
+            .filter(|order| order.side == side)
+            .sorted_by(|a, b| {
+                let by_price = match side {
+                    OrderSide::Buy => b.price.cmp(&a.price),
+                    OrderSide::Sell => a.price.cmp(&b.price),
+                };
+                by_price.then(a.creation_time.cmp(&b.creation_time))
+            })
+            .map(|order| order.client_order_id.clone())
+            .collect_vec()
+    }
+
+    fn level_amount(&self, side: OrderSide, price: Price) -> Amount {
+        let levels = match side {
+            OrderSide::Buy => &self.order_book.bids,
+            OrderSide::Sell => &self.order_book.asks,
+        };
+        levels.get(&price).copied().unwrap_or_default()
+    }
+}
+
+impl Default for MatchingSimulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::exchanges::events::{TickDirection, TradeId};
+    use crate::order_book_data;
+
+    fn order(id: &str, side: OrderSide, price: Price, amount: Amount) -> SimulatedOrder {
+        SimulatedOrder::new(
+            id.into(),
+            side,
+            price,
+            amount,
+            OrderExecutionType::None,
+            Utc::now(),
+        )
+    }
+
+    fn trade(side: OrderSide, price: Price, quantity: Amount) -> Trade {
+        Trade {
+            trade_id: TradeId::Number(1),
+            price,
+            quantity,
+            side,
+            transaction_time: Utc::now(),
+            tick_direction: TickDirection::None,
+        }
+    }
+
+    fn simulator() -> MatchingSimulator {
+        let mut simulator = MatchingSimulator::new();
+        let _ = simulator.on_order_book_snapshot(
+            order_book_data![
+                dec!(101) => dec!(1),
+                dec!(102) => dec!(2),
+                ;
+                dec!(99) => dec!(3),
+                dec!(100) => dec!(2),
+            ],
+            Utc::now(),
+        );
+        simulator
+    }
+
+    #[test]
+    fn marketable_order_walks_book_as_taker() {
+        let mut simulator = simulator();
+
+        let fills = simulator
+            .place_order(order("1", OrderSide::Buy, dec!(102), dec!(4)), Utc::now())
+            .expect("in test");
+
+        let executed = fills
+            .iter()
+            .map(|x| (x.price, x.amount, x.role))
+            .collect_vec();
+        assert_eq!(
+            executed,
+            vec![
+                (dec!(101), dec!(1), OrderRole::Taker),
+                (dec!(102), dec!(2), OrderRole::Taker)
+            ]
+        );
+        let rest = simulator.get_order(&"1".into()).expect("in test");
+        assert_eq!(rest.remaining_amount(), dec!(1));
+        assert!(simulator.order_book().asks.is_empty());
+    }
+
+    #[test]
+    fn maker_only_order_is_rejected_if_it_would_take() {
+        let mut simulator = simulator();
+        let mut maker_order = order("1", OrderSide::Sell, dec!(100), dec!(1));
+        maker_order.execution_type = OrderExecutionType::MakerOnly;
+
+        let result = simulator.place_order(maker_order, Utc::now());
+
+        assert_eq!(
+            result,
+            Err(MatchingError::MakerOnlyOrderWouldTake("1".into()))
+        );
+    }
+
+    #[test]
+    fn resting_order_is_filled_after_queue_ahead() {
+        let mut simulator = simulator();
+        let fills = simulator
+            .place_order(order("1", OrderSide::Buy, dec!(100), dec!(1)), Utc::now())
+            .expect("in test");
+        assert!(fills.is_empty());
+        assert_eq!(
+            simulator
+                .get_order(&"1".into())
+                .expect("in test")
+                .queue_ahead,
+            dec!(2)
+        );
+
+        let fills = simulator.on_trade(&trade(OrderSide::Sell, dec!(100), dec!(2.5)));
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].amount, dec!(0.5));
+        assert_eq!(fills[0].role, OrderRole::Maker);
+        assert!(!fills[0].is_completed);
+
+        let fills = simulator.on_trade(&trade(OrderSide::Sell, dec!(99), dec!(3)));
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].amount, dec!(0.5));
+        assert!(fills[0].is_completed);
+        assert!(simulator.get_order(&"1".into()).is_none());
+    }
+
+    #[test]
+    fn submitted_order_is_placed_after_latency() {
+        let start = Utc::now();
+        let mut simulator = MatchingSimulator::with_execution_models(
+            Box::new(ConstantLatency(Duration::from_millis(100))),
+            Box::new(NoSlippage),
+        );
+        let _ = simulator.on_order_book_snapshot(order_book_data![dec!(101) => dec!(1),;], start);
+
+        simulator.submit_order(order("1", OrderSide::Buy, dec!(101), dec!(1)), start);
+        let fills = simulator.on_order_book_update(
+            &order_book_data![dec!(101) => dec!(0.5),;],
+            start + chrono::Duration::milliseconds(50),
+        );
+        assert!(fills.is_empty());
+        assert!(simulator.get_order(&"1".into()).is_none());
+
+        // Book has changed before arrival of order, so only remaining amount is taken
+        let fills = simulator.on_order_book_update(
+            &order_book_data![dec!(102) => dec!(1),;],
+            start + chrono::Duration::milliseconds(100),
+        );
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].amount, dec!(0.5));
+        assert_eq!(fills[0].role, OrderRole::Taker);
+        assert_eq!(
+            simulator
+                .get_order(&"1".into())
+                .expect("in test")
+                .remaining_amount(),
+            dec!(0.5)
+        );
+    }
+
+    #[test]
+    fn queue_ahead_is_reduced_by_level_decrease_and_crossing_book_fills_order() {
+        let mut simulator = simulator();
+        let _ = simulator
+            .place_order(order("1", OrderSide::Sell, dec!(102), dec!(1)), Utc::now())
+            .expect("in test");
+
+        let fills =
+            simulator.on_order_book_update(&order_book_data![dec!(102) => dec!(0.5),;], Utc::now());
+        assert!(fills.is_empty());
+        assert_eq!(
+            simulator
+                .get_order(&"1".into())
+                .expect("in test")
+                .queue_ahead,
+            dec!(0.5)
+        );
+
+        let fills =
+            simulator.on_order_book_update(&order_book_data![; dec!(102) => dec!(1),], Utc::now());
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].amount, dec!(1));
+        assert_eq!(fills[0].price, dec!(102));
+        assert!(fills[0].is_completed);
+    }
+}
@@ -0,0 +1,2 @@
+pub mod execution_models;
+pub mod matching;
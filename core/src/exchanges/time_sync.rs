@@ -0,0 +1,77 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+
+use mmb_utils::time::get_current_milliseconds;
+
+use crate::exchanges::common::ExchangeAccountId;
+
+pub const SERVER_TIME_SYNC_PERIOD: Duration = Duration::from_secs(60);
+/// Drift between local and exchange server time that is bigger than this value is reported as an error
+pub const MAX_ALLOWED_TIME_DRIFT_MS: i64 = 1000;
+
+/// Offset between exchange server time and local time used for timestamps of signed requests
+#[derive(Debug, Default)]
+pub struct ServerTimeOffset {
+    offset_ms: AtomicI64,
+}
+
+impl ServerTimeOffset {
+    pub fn offset_ms(&self) -> i64 {
+        self.offset_ms.load(Ordering::Relaxed)
+    }
+
+    /// Current local time in milliseconds adjusted by offset to exchange server time
+    pub fn get_server_milliseconds(&self) -> u128 {
+        (get_current_milliseconds() as i128 + self.offset_ms() as i128) as u128
+    }
+
+    /// Server time is considered as measured in the middle between local request start and end times
+    pub fn update(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        request_start_ms: u128,
+        request_end_ms: u128,
+        server_time_ms: u128,
+    ) -> i64 {
+        let local_time_ms = (request_start_ms + request_end_ms) / 2;
+        let offset_ms = (server_time_ms as i128 - local_time_ms as i128) as i64;
+        self.offset_ms.store(offset_ms, Ordering::Relaxed);
+
+        if offset_ms.abs() > MAX_ALLOWED_TIME_DRIFT_MS {
+            log::error!(
+                "Local time drifts from server time of {} by {}ms. Check time synchronization of the host",
+                exchange_account_id,
+                offset_ms
+            );
+        } else {
+            log::trace!(
+                "Server time offset for {} is {}ms",
+                exchange_account_id,
+                offset_ms
+            );
+        }
+
+        offset_ms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_offset_by_middle_of_request() {
+        let time_offset = ServerTimeOffset::default();
+        let exchange_account_id = "Binance_0".parse().expect("in test");
+
+        let offset = time_offset.update(exchange_account_id, 1000, 1200, 1600);
+
+        assert_eq!(offset, 500);
+        assert_eq!(time_offset.offset_ms(), 500);
+
+        let offset = time_offset.update(exchange_account_id, 1000, 1200, 900);
+
+        assert_eq!(offset, -200);
+        assert_eq!(time_offset.offset_ms(), -200);
+    }
+}
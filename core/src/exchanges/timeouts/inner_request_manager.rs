@@ -2,20 +2,29 @@ use std::collections::HashMap;
 
 use super::{
     more_or_equals_available_requests_count_trigger_scheduler::MoreOrEqualsAvailableRequestsCountTriggerScheduler,
-    pre_reserved_group::PreReservedGroup, request::Request,
+    pre_reserved_group::PreReservedGroup,
+    request::{QueuedRequest, QueuedRequestId, Request, RequestPriority},
+    request_metrics::{QueuedRequestStats, RequestMetrics, RequestsLimitStats},
     triggers::handle_trigger_trait::TriggerHandler,
 };
+use crate::exchanges::common::ToStdExpected;
 use crate::exchanges::timeouts::requests_timeout_manager::RequestGroupId;
 use crate::{exchanges::common::ExchangeAccountId, exchanges::general::request_type::RequestType};
 use anyhow::{anyhow, bail, Result};
 use chrono::Duration;
+use itertools::Itertools;
 use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use tokio::time::Instant;
 
 pub(super) struct InnerRequestsTimeoutManager {
     pub(super) requests_per_period: usize,
     pub(super) period_duration: Duration,
     pub(super) exchange_account_id: ExchangeAccountId,
     pub(super) requests: Vec<Request>,
+    /// Requests reserved by `reserve_when_available` which wait for their start time
+    pub(super) queued_requests: Vec<QueuedRequest>,
     pub(super) pre_reserved_groups: Vec<PreReservedGroup>,
     pub(super) last_time: Option<DateTime>,
 
@@ -27,6 +36,7 @@ pub(super) struct InnerRequestsTimeoutManager {
     pub(super) more_or_equals_available_requests_count_trigger_scheduler:
         MoreOrEqualsAvailableRequestsCountTriggerScheduler,
     pub(super) delay_to_next_time_period: Duration,
+    pub(super) metrics: RequestMetrics,
     // data_recorder
 }
 
@@ -44,11 +54,13 @@ impl InnerRequestsTimeoutManager {
 
         if available_requests_count == 0 {
             // TODO save to DataRecorder
+            self.metrics.add_rejected(request_type);
 
             return Ok(false);
         }
 
         let request = self.add_request(request_type, current_time, None)?;
+        self.metrics.add_reserved(request_type);
         self.last_time = Some(current_time);
 
         log::info!(
@@ -64,6 +76,58 @@ impl InnerRequestsTimeoutManager {
         Ok(true)
     }
 
+    /// Queued request with lower priority which starts before `start_time`.
+    /// Requests with the lowest priority are preempted first, then the earliest ones
+    pub(super) fn find_preemptable_request(
+        &self,
+        priority: RequestPriority,
+        start_time: DateTime,
+    ) -> Option<usize> {
+        self.queued_requests
+            .iter()
+            .enumerate()
+            .filter(|(_, queued)| {
+                queued.priority < priority && queued.request.allowed_start_time < start_time
+            })
+            .min_by_key(|(_, queued)| (queued.priority, queued.request.allowed_start_time))
+            .map(|(index, _)| index)
+    }
+
+    /// Moves queued request to new start time. Waiter of request is notified about new deadline
+    pub(super) fn reschedule_queued_request(
+        &mut self,
+        index: usize,
+        start_time: DateTime,
+        current_time: DateTime,
+    ) -> Result<()> {
+        let old_request = self.queued_requests[index].request.clone();
+        if let Some(position) = self.requests.iter().position(|x| *x == old_request) {
+            self.requests.remove(position);
+        }
+
+        let request =
+            self.add_request(old_request.request_type, start_time, old_request.group_id)?;
+        let delay = (start_time - current_time).to_std_expected();
+
+        let queued = &mut self.queued_requests[index];
+        queued.request = request;
+        let _ = queued.deadline_sender.send(Instant::now() + delay);
+
+        log::info!(
+            "Queued request {:?} is preempted and moved to {}",
+            old_request.request_type,
+            start_time
+        );
+
+        Ok(())
+    }
+
+    /// Removes request from queue and returns its actual state
+    pub(super) fn take_queued_request(&mut self, id: QueuedRequestId) -> Option<Request> {
+        let index = self.queued_requests.iter().position(|x| x.id == id)?;
+        Some(self.queued_requests.remove(index).request)
+    }
+
     pub(super) fn get_reserved_request_count_for_group_to_now(
         &self,
         group_id: RequestGroupId,
@@ -276,6 +340,65 @@ impl InnerRequestsTimeoutManager {
         }
     }
 
+    pub(super) fn stats(&mut self, current_time: DateTime) -> Result<RequestsLimitStats> {
+        let current_time = self.get_non_decreasing_time(current_time);
+        self.remove_outdated_requests(current_time)?;
+
+        let used_requests_count = self
+            .requests_per_period
+            .saturating_sub(self.get_available_requests_count_at_present(current_time));
+        let budget_utilization_percent = match self.requests_per_period {
+            0 => Decimal::ZERO,
+            requests_per_period => {
+                Decimal::from(used_requests_count) * dec!(100) / Decimal::from(requests_per_period)
+            }
+        };
+
+        let queue = self
+            .queued_requests
+            .iter()
+            .map(|queued| QueuedRequestStats {
+                request_type: queued.request.request_type,
+                priority: queued.priority,
+                start_time: queued.request.allowed_start_time,
+                wait_ms: (queued.request.allowed_start_time - current_time)
+                    .num_milliseconds()
+                    .max(0),
+            })
+            .sorted_by_key(|x| x.start_time)
+            .collect_vec();
+
+        let request_types = self
+            .metrics
+            .request_types()
+            .sorted_by_key(|x| format!("{:?}", x))
+            .map(|request_type| {
+                let used_count = self
+                    .requests
+                    .iter()
+                    .filter(|x| x.request_type == request_type)
+                    .filter(|x| x.allowed_start_time <= current_time)
+                    .count();
+                let pending_count = queue
+                    .iter()
+                    .filter(|x| x.request_type == request_type)
+                    .count();
+                self.metrics.stats(request_type, used_count, pending_count)
+            })
+            .collect_vec();
+
+        Ok(RequestsLimitStats {
+            exchange_account_id: self.exchange_account_id,
+            requests_per_period: self.requests_per_period,
+            period_ms: self.period_duration.num_milliseconds(),
+            used_requests_count,
+            budget_utilization_percent,
+            pre_reserved_groups_count: self.pre_reserved_groups.len(),
+            queue,
+            request_types,
+        })
+    }
+
     pub(super) fn check_threshold(&self, count_threshold: usize) -> Result<()> {
         if self.requests_per_period < count_threshold {
             bail!("Unable to register trigger with count threshold more then available request for period. {} > {} for {}",
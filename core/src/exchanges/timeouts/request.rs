@@ -1,4 +1,8 @@
 use mmb_utils::DateTime;
+use serde::Serialize;
+use tokio::sync::watch;
+use tokio::time::Instant;
+use uuid::Uuid;
 
 use crate::exchanges::general::request_type::RequestType;
 use crate::exchanges::timeouts::requests_timeout_manager::RequestGroupId;
@@ -23,3 +27,40 @@ impl Request {
         }
     }
 }
+
+/// Order of serving requests which wait for availability when requests limit is exhausted.
+/// Queued request with lower priority gives its slot to request with higher priority
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord, Serialize)]
+pub enum RequestPriority {
+    Query,
+    CreateOrder,
+    CancelOrder,
+}
+
+impl From<RequestType> for RequestPriority {
+    fn from(request_type: RequestType) -> Self {
+        match request_type {
+            RequestType::CancelOrder => RequestPriority::CancelOrder,
+            RequestType::CreateOrder | RequestType::ClosePosition => RequestPriority::CreateOrder,
+            _ => RequestPriority::Query,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub(crate) struct QueuedRequestId(Uuid);
+
+impl QueuedRequestId {
+    pub fn generate() -> Self {
+        QueuedRequestId(Uuid::new_v4())
+    }
+}
+
+/// Request reserved for future time which waits for its start
+pub(crate) struct QueuedRequest {
+    pub(crate) id: QueuedRequestId,
+    pub(crate) request: Request,
+    pub(crate) priority: RequestPriority,
+    /// Moment when waiting of request is finished. Changed if request is preempted
+    pub(crate) deadline_sender: watch::Sender<Instant>,
+}
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use chrono::Duration;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::exchanges::common::ExchangeAccountId;
+use crate::exchanges::general::request_type::RequestType;
+use crate::exchanges::timeouts::request::RequestPriority;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct RequestTypeCounters {
+    reserved_count: u64,
+    rejected_count: u64,
+    delayed_count: u64,
+    waits_count: u64,
+    total_wait_ms: i64,
+    max_wait_ms: i64,
+}
+
+/// Counters of reservations of requests since start of engine
+#[derive(Debug, Default)]
+pub(crate) struct RequestMetrics {
+    counters: HashMap<RequestType, RequestTypeCounters>,
+}
+
+impl RequestMetrics {
+    /// Request is reserved for current time, so it doesn't wait
+    pub fn add_reserved(&mut self, request_type: RequestType) {
+        let counters = self.counters.entry(request_type).or_default();
+        counters.reserved_count += 1;
+        counters.waits_count += 1;
+    }
+
+    /// Request is reserved for future time. Its wait is added when request starts,
+    /// because queued request can be preempted by request with higher priority
+    pub fn add_queued(&mut self, request_type: RequestType) {
+        let counters = self.counters.entry(request_type).or_default();
+        counters.reserved_count += 1;
+        counters.delayed_count += 1;
+    }
+
+    pub fn add_rejected(&mut self, request_type: RequestType) {
+        self.counters
+            .entry(request_type)
+            .or_default()
+            .rejected_count += 1;
+    }
+
+    pub fn add_wait(&mut self, request_type: RequestType, wait: Duration) {
+        let wait_ms = wait.num_milliseconds();
+        let counters = self.counters.entry(request_type).or_default();
+        counters.waits_count += 1;
+        counters.total_wait_ms += wait_ms;
+        counters.max_wait_ms = counters.max_wait_ms.max(wait_ms);
+    }
+
+    pub fn request_types(&self) -> impl Iterator<Item = RequestType> + '_ {
+        self.counters.keys().copied()
+    }
+
+    pub fn stats(
+        &self,
+        request_type: RequestType,
+        used_count: usize,
+        pending_count: usize,
+    ) -> RequestTypeStats {
+        let counters = self
+            .counters
+            .get(&request_type)
+            .copied()
+            .unwrap_or_default();
+        let average_wait_ms = match counters.waits_count {
+            0 => 0,
+            waits_count => counters.total_wait_ms / waits_count as i64,
+        };
+
+        RequestTypeStats {
+            request_type,
+            used_count,
+            pending_count,
+            reserved_count: counters.reserved_count,
+            rejected_count: counters.rejected_count,
+            delayed_count: counters.delayed_count,
+            average_wait_ms,
+            max_wait_ms: counters.max_wait_ms,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RequestTypeStats {
+    pub request_type: RequestType,
+    /// Requests which are reserved in current period
+    pub used_count: usize,
+    /// Requests which wait for free slot of requests limit
+    pub pending_count: usize,
+    pub reserved_count: u64,
+    /// Instant reservations which failed because requests limit is exhausted
+    pub rejected_count: u64,
+    /// Reservations which waited for free slot of requests limit
+    pub delayed_count: u64,
+    /// Wait between reservation and start of request. Instant reservations wait 0 ms
+    pub average_wait_ms: i64,
+    pub max_wait_ms: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QueuedRequestStats {
+    pub request_type: RequestType,
+    pub priority: RequestPriority,
+    pub start_time: DateTime,
+    /// Time left until start of request
+    pub wait_ms: i64,
+}
+
+/// State of requests limit of exchange account. Long queue and high budget utilization mean
+/// that requests are slowed down by engine itself rather than by exchange
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RequestsLimitStats {
+    pub exchange_account_id: ExchangeAccountId,
+    pub requests_per_period: usize,
+    pub period_ms: i64,
+    /// Requests reserved in current period including vacant slots of pre-reserved groups
+    pub used_requests_count: usize,
+    /// Part of requests limit which is used in current period, in percents
+    pub budget_utilization_percent: Decimal,
+    pub pre_reserved_groups_count: usize,
+    /// Requests which wait for free slot of requests limit in order of their start
+    pub queue: Vec<QueuedRequestStats>,
+    pub request_types: Vec<RequestTypeStats>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn instant_reservations_are_included_in_average_wait() {
+        let mut metrics = RequestMetrics::default();
+        metrics.add_reserved(RequestType::CreateOrder);
+        metrics.add_queued(RequestType::CreateOrder);
+        metrics.add_wait(RequestType::CreateOrder, Duration::milliseconds(100));
+
+        // Instant reservation waits 0 ms
+
+        assert_eq!(
+            metrics.stats(RequestType::CreateOrder, 2, 0),
+            RequestTypeStats {
+                request_type: RequestType::CreateOrder,
+                used_count: 2,
+                pending_count: 0,
+                reserved_count: 2,
+                rejected_count: 0,
+                delayed_count: 1,
+                average_wait_ms: 50,
+                max_wait_ms: 100,
+            }
+        );
+    }
+
+    #[test]
+    fn counters_are_separated_by_request_type() {
+        let mut metrics = RequestMetrics::default();
+        metrics.add_reserved(RequestType::CreateOrder);
+        metrics.add_rejected(RequestType::CancelOrder);
+        metrics.add_rejected(RequestType::CancelOrder);
+
+        assert_eq!(
+            metrics.request_types().collect::<HashSet<_>>(),
+            HashSet::from([RequestType::CreateOrder, RequestType::CancelOrder])
+        );
+
+        let create_order_stats = metrics.stats(RequestType::CreateOrder, 1, 0);
+        assert_eq!(create_order_stats.reserved_count, 1);
+        assert_eq!(create_order_stats.rejected_count, 0);
+
+        let cancel_order_stats = metrics.stats(RequestType::CancelOrder, 0, 0);
+        assert_eq!(cancel_order_stats.reserved_count, 0);
+        assert_eq!(cancel_order_stats.rejected_count, 2);
+    }
+
+    #[test]
+    fn stats_are_empty_for_unknown_request_type() {
+        let metrics = RequestMetrics::default();
+
+        let stats = metrics.stats(RequestType::GetBalance, 0, 3);
+
+        assert_eq!(stats.pending_count, 3);
+        assert_eq!(stats.reserved_count, 0);
+        assert_eq!(stats.average_wait_ms, 0);
+        assert_eq!(stats.max_wait_ms, 0);
+    }
+}
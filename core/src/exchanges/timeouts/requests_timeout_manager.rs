@@ -8,14 +8,17 @@ use mmb_utils::cancellation_token::CancellationToken;
 use mmb_utils::infrastructure::{FutureOutcome, SpawnFutureFlags};
 use mmb_utils::{DateTime, OPERATION_CANCELED_MSG};
 use parking_lot::Mutex;
+use tokio::sync::watch;
 use tokio::task::JoinHandle;
-use tokio::time::sleep;
+use tokio::time::{sleep_until, Instant};
 use uuid::Uuid;
 
 use super::{
     inner_request_manager::InnerRequestsTimeoutManager,
     more_or_equals_available_requests_count_trigger_scheduler::MoreOrEqualsAvailableRequestsCountTriggerScheduler,
-    pre_reserved_group::PreReservedGroup, request::Request,
+    pre_reserved_group::PreReservedGroup,
+    request::{QueuedRequest, QueuedRequestId, Request, RequestPriority},
+    request_metrics::RequestsLimitStats,
     triggers::every_requests_count_change_trigger::EveryRequestsCountChangeTrigger,
     triggers::less_or_equals_requests_count_trigger::LessOrEqualsRequestsCountTrigger,
 };
@@ -56,6 +59,7 @@ impl RequestsTimeoutManager {
             period_duration,
             exchange_account_id,
             requests: Default::default(),
+            queued_requests: Default::default(),
             pre_reserved_groups: Default::default(),
             last_time: None,
             delay_to_next_time_period: Duration::milliseconds(1),
@@ -64,6 +68,7 @@ impl RequestsTimeoutManager {
             time_has_come_for_request: Box::new(|_| Ok(())),
             less_or_equals_requests_count_triggers: Default::default(),
             more_or_equals_available_requests_count_trigger_scheduler,
+            metrics: Default::default(),
         };
 
         Arc::new(Self {
@@ -205,11 +210,13 @@ impl RequestsTimeoutManager {
 
                 if available_requests_count == 0 {
                     // TODO save to DataRecorder
+                    inner.metrics.add_rejected(request_type);
 
                     return Ok(false);
                 }
 
                 let request = inner.add_request(request_type, current_time, Some(group.id))?;
+                inner.metrics.add_reserved(request_type);
 
                 log::info!(
                     "Request {:?} reserved for group with pre_reserved_group_id {},
@@ -254,6 +261,8 @@ impl RequestsTimeoutManager {
         // Algorithm:
         // 1. We check: can we do request now
         // 2. if not form schedule for request where put at start period by requestsPerPeriod requests
+        // 3. if there is queued request with lower priority before scheduled time, take its slot
+        //    and move that request to scheduled time
 
         let mut inner = self.inner.lock();
 
@@ -282,10 +291,33 @@ impl RequestsTimeoutManager {
             };
 
             request_start_time = std::cmp::max(request_start_time, current_time);
+
+            let priority = RequestPriority::from(request_type);
+            if let Some(index) = inner.find_preemptable_request(priority, request_start_time) {
+                let slot_time = inner.queued_requests[index].request.allowed_start_time;
+                inner.reschedule_queued_request(index, request_start_time, current_time)?;
+                request_start_time = slot_time;
+            }
+
             delay = request_start_time - current_time;
             inner.add_request(request_type, request_start_time, None)?
         };
 
+        let (deadline_sender, deadline_receiver) =
+            watch::channel(Instant::now() + delay.to_std_expected());
+        let queued_request_id = QueuedRequestId::generate();
+        if delay > Duration::zero() {
+            inner.queued_requests.push(QueuedRequest {
+                id: queued_request_id,
+                request: request.clone(),
+                priority: RequestPriority::from(request_type),
+                deadline_sender,
+            });
+            inner.metrics.add_queued(request_type);
+        } else {
+            inner.metrics.add_reserved(request_type);
+        }
+
         log::info!(
             "Request {:?} reserved, available in request_start_time {}",
             request_type,
@@ -302,7 +334,9 @@ impl RequestsTimeoutManager {
         let action = Self::wait_for_request_availability(
             Arc::downgrade(&self),
             request,
-            delay,
+            current_time,
+            queued_request_id,
+            deadline_receiver,
             cancellation_token,
         );
         let request_availability = spawn_future(
@@ -317,33 +351,51 @@ impl RequestsTimeoutManager {
     async fn wait_for_request_availability(
         weak_self: Weak<Self>,
         request: Request,
-        delay: Duration,
+        reservation_time: DateTime,
+        queued_request_id: QueuedRequestId,
+        mut deadline_receiver: watch::Receiver<Instant>,
         cancellation_token: CancellationToken,
     ) -> Result<()> {
-        // Should never panic, because function wait_for_request_availability
-        // has one call with guaranteed non-negative delay.
-        let delay: std::time::Duration = delay.to_std_expected();
-
-        let sleep_future = sleep(delay);
-        let cancellation_token = cancellation_token.when_cancelled();
+        // Deadline is moved if request is preempted by request with higher priority
+        loop {
+            let deadline = *deadline_receiver.borrow();
+
+            tokio::select! {
+                _ = sleep_until(deadline) => break,
+
+                changed = deadline_receiver.changed() => {
+                    if changed.is_err() {
+                        // Request isn't queued anymore, so deadline can't be changed
+                        sleep_until(deadline).await;
+                        break;
+                    }
+                }
 
-        tokio::select! {
-            _ = sleep_future => {
-                let strong_self = Self::try_get_strong(weak_self)?;
-                (strong_self.inner.lock().time_has_come_for_request)(request)?;
-            }
+                _ = cancellation_token.when_cancelled() => {
+                    let strong_self = Self::try_get_strong(weak_self)?;
+                    let mut inner = strong_self.inner.lock();
+                    let request = inner.take_queued_request(queued_request_id).unwrap_or(request);
+                    (inner.time_has_come_for_request)(request.clone())?;
+                    if let Some(position) = inner.requests.iter().position(|stored_request| *stored_request == request) {
+                        inner.requests.remove(position);
+                    }
 
-            _ = cancellation_token => {
-                let strong_self = Self::try_get_strong(weak_self)?;
-                let mut inner = strong_self.inner.lock();
-                (inner.time_has_come_for_request)(request.clone())?;
-                if let Some(position) = inner.requests.iter().position(|stored_request| *stored_request == request) {
-                    inner.requests.remove(position);
+                    bail!(OPERATION_CANCELED_MSG)
                 }
+            };
+        }
 
-                bail!(OPERATION_CANCELED_MSG)
+        let strong_self = Self::try_get_strong(weak_self)?;
+        let mut inner = strong_self.inner.lock();
+        let request = match inner.take_queued_request(queued_request_id) {
+            Some(request) => {
+                let wait = request.allowed_start_time - reservation_time;
+                inner.metrics.add_wait(request.request_type, wait);
+                request
             }
+            None => request,
         };
+        (inner.time_has_come_for_request)(request)?;
 
         Ok(())
     }
@@ -389,6 +441,11 @@ impl RequestsTimeoutManager {
         Ok(())
     }
 
+    /// Current usage of requests limit, queue of waiting requests and metrics of request types
+    pub fn stats(&self, current_time: DateTime) -> Result<RequestsLimitStats> {
+        self.inner.lock().stats(current_time)
+    }
+
     pub fn register_trigger_on_every_change(
         &self,
         handler: Box<dyn Fn(usize) -> Result<()> + Send>,
@@ -408,6 +465,7 @@ mod test {
         RequestTimeoutArguments, RequestsTimeoutManagerFactory,
     };
     use chrono::Utc;
+    use tokio::time::sleep;
 
     use super::*;
     use rstest::{fixture, rstest};
@@ -1817,6 +1875,73 @@ mod test {
             Ok(())
         }
 
+        #[rstest]
+        #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+        async fn cancel_preempts_queued_requests_with_lower_priority(
+            timeout_manager: Arc<RequestsTimeoutManager>,
+        ) -> Result<()> {
+            let _ = init_lifetime_manager();
+
+            // Arrange
+            timeout_manager.inner.lock().requests_per_period = 2;
+            let current_time = Utc::now();
+            let next_period_delay = Duration::milliseconds(1);
+            let next_period = current_time + Duration::seconds(60) + next_period_delay;
+            let period_after_next = next_period + Duration::seconds(60) + next_period_delay;
+
+            for _ in 0..3 {
+                let _ = timeout_manager.clone().reserve_when_available(
+                    RequestType::CreateOrder,
+                    current_time,
+                    CancellationToken::default(),
+                )?;
+            }
+            let _ = timeout_manager.clone().reserve_when_available(
+                RequestType::GetBalance,
+                current_time,
+                CancellationToken::default(),
+            )?;
+
+            // Act
+            let (_, available_start_time, delay) = timeout_manager.clone().reserve_when_available(
+                RequestType::CancelOrder,
+                current_time,
+                CancellationToken::default(),
+            )?;
+
+            // Assert
+            assert_eq!(available_start_time, next_period);
+            assert_eq!(delay, next_period - current_time);
+
+            let inner = timeout_manager.inner.lock();
+            assert_eq!(inner.requests.len(), 5);
+
+            let start_time_of = |request_type| {
+                inner
+                    .requests
+                    .iter()
+                    .find(|request| request.request_type == request_type)
+                    .map(|request| request.allowed_start_time)
+            };
+            assert_eq!(start_time_of(RequestType::CancelOrder), Some(next_period));
+            assert_eq!(
+                start_time_of(RequestType::GetBalance),
+                Some(period_after_next)
+            );
+
+            let queued_balance_request = inner
+                .queued_requests
+                .iter()
+                .find(|queued| queued.priority == RequestPriority::Query)
+                .expect("in test");
+            assert_eq!(
+                queued_balance_request.request.allowed_start_time,
+                period_after_next
+            );
+
+            Ok(())
+        }
+
         #[rstest]
         #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
         async fn with_cancel_at_beginning(
@@ -2319,6 +2444,80 @@ mod test {
         }
     }
 
+    mod stats {
+        use rust_decimal_macros::dec;
+
+        use crate::infrastructure::init_lifetime_manager;
+
+        use super::*;
+
+        #[rstest]
+        #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+        async fn used_limit_queue_and_metrics_by_request_type(
+            timeout_manager: Arc<RequestsTimeoutManager>,
+        ) -> Result<()> {
+            let _ = init_lifetime_manager();
+
+            // Arrange
+            timeout_manager.inner.lock().requests_per_period = 3;
+            let current_time = Utc::now();
+
+            for request_type in [
+                RequestType::CreateOrder,
+                RequestType::CreateOrder,
+                RequestType::GetBalance,
+            ] {
+                assert!(timeout_manager.try_reserve_instant(request_type, current_time, None)?);
+            }
+            let (_, _, delay) = timeout_manager.clone().reserve_when_available(
+                RequestType::CancelOrder,
+                current_time,
+                CancellationToken::default(),
+            )?;
+            assert!(!timeout_manager.try_reserve_instant(
+                RequestType::GetOrderInfo,
+                current_time,
+                None
+            )?);
+
+            // Act
+            let stats = timeout_manager.stats(current_time)?;
+
+            // Assert
+            assert_eq!(stats.used_requests_count, 3);
+            assert_eq!(stats.budget_utilization_percent, dec!(100));
+            assert_eq!(stats.queue.len(), 1);
+            assert_eq!(stats.queue[0].request_type, RequestType::CancelOrder);
+            assert_eq!(stats.queue[0].wait_ms, delay.num_milliseconds());
+
+            let request_types = stats
+                .request_types
+                .iter()
+                .map(|x| {
+                    (
+                        x.request_type,
+                        x.used_count,
+                        x.pending_count,
+                        x.reserved_count,
+                        x.rejected_count,
+                        x.delayed_count,
+                    )
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                request_types,
+                [
+                    (RequestType::CancelOrder, 0, 1, 1, 0, 1),
+                    (RequestType::CreateOrder, 2, 0, 2, 0, 0),
+                    (RequestType::GetBalance, 1, 0, 1, 0, 0),
+                    (RequestType::GetOrderInfo, 0, 0, 0, 1, 0),
+                ]
+            );
+
+            Ok(())
+        }
+    }
+
     mod triggers {
         use parking_lot::Mutex;
 
@@ -1,9 +1,11 @@
 use futures::future::ready;
 use futures::future::Either;
 use futures::FutureExt;
+use itertools::Itertools;
 use mmb_utils::cancellation_token::CancellationToken;
 use mmb_utils::infrastructure::{CompletionReason, FutureOutcome};
 use mmb_utils::DateTime;
+use parking_lot::RwLock;
 use std::collections::HashMap;
 use std::future::Future;
 use std::sync::Arc;
@@ -15,6 +17,7 @@ use chrono::Utc;
 
 use crate::exchanges::common::ExchangeAccountId;
 use crate::exchanges::general::request_type::RequestType;
+use crate::exchanges::timeouts::request_metrics::RequestsLimitStats;
 use crate::exchanges::timeouts::requests_timeout_manager::{
     RequestGroupId, RequestsTimeoutManager,
 };
@@ -22,7 +25,7 @@ use crate::exchanges::timeouts::requests_timeout_manager::{
 pub type BoxFuture = Box<dyn Future<Output = Result<()>> + Sync + Send>;
 
 pub struct TimeoutManager {
-    inner: HashMap<ExchangeAccountId, Arc<RequestsTimeoutManager>>,
+    inner: RwLock<HashMap<ExchangeAccountId, Arc<RequestsTimeoutManager>>>,
 }
 
 impl TimeoutManager {
@@ -30,17 +33,38 @@ impl TimeoutManager {
         timeout_managers: HashMap<ExchangeAccountId, Arc<RequestsTimeoutManager>>,
     ) -> Arc<Self> {
         Arc::new(TimeoutManager {
-            inner: timeout_managers,
+            inner: RwLock::new(timeout_managers),
         })
     }
 
+    /// Registers requests limits of exchange account which is added after engine start
+    pub fn add_exchange(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        timeout_manager: Arc<RequestsTimeoutManager>,
+    ) {
+        let _ = self
+            .inner
+            .write()
+            .insert(exchange_account_id, timeout_manager);
+    }
+
+    /// Unregisters requests limits of exchange account which failed to be added
+    pub fn remove_exchange(&self, exchange_account_id: ExchangeAccountId) {
+        let _ = self.inner.write().remove(&exchange_account_id);
+    }
+
+    pub fn contains_exchange(&self, exchange_account_id: ExchangeAccountId) -> bool {
+        self.inner.read().contains_key(&exchange_account_id)
+    }
+
     pub fn try_reserve_group(
         &self,
         exchange_account_id: ExchangeAccountId,
         requests_count: usize,
         group_type: String,
     ) -> Result<Option<RequestGroupId>> {
-        self.inner[&exchange_account_id].try_reserve_group(group_type, now(), requests_count)
+        self.inner.read()[&exchange_account_id].try_reserve_group(group_type, now(), requests_count)
     }
 
     pub fn remove_group(
@@ -48,7 +72,7 @@ impl TimeoutManager {
         exchange_account_id: ExchangeAccountId,
         group_id: RequestGroupId,
     ) -> Result<bool> {
-        self.inner[&exchange_account_id].remove_group(group_id, now())
+        self.inner.read()[&exchange_account_id].remove_group(group_id, now())
     }
 
     pub fn try_reserve_instant(
@@ -56,7 +80,7 @@ impl TimeoutManager {
         exchange_account_id: ExchangeAccountId,
         request_type: RequestType,
     ) -> Result<bool> {
-        self.inner[&exchange_account_id].try_reserve_instant(request_type, now(), None)
+        self.inner.read()[&exchange_account_id].try_reserve_instant(request_type, now(), None)
     }
 
     pub fn try_reserve_group_instant(
@@ -65,13 +89,15 @@ impl TimeoutManager {
         request_type: RequestType,
         pre_reserved_group_id: Option<RequestGroupId>,
     ) -> Result<bool> {
-        self.inner[&exchange_account_id].try_reserve_instant(
+        self.inner.read()[&exchange_account_id].try_reserve_instant(
             request_type,
             now(),
             pre_reserved_group_id,
         )
     }
 
+    /// Reserves request at the earliest available time. If requests limit is exhausted, queued
+    /// requests with lower `RequestPriority` (queries < creates < cancels) give their slots
     pub fn reserve_when_available(
         &self,
         exchange_account_id: ExchangeAccountId,
@@ -79,7 +105,7 @@ impl TimeoutManager {
         pre_reservation_group_id: Option<RequestGroupId>,
         cancellation_token: CancellationToken,
     ) -> Result<impl Future<Output = FutureOutcome> + Send + Sync> {
-        let inner = (&self.inner[&exchange_account_id]).clone();
+        let inner = self.inner.read()[&exchange_account_id].clone();
 
         let convert = |handle: JoinHandle<FutureOutcome>| {
             handle.map(|res| match res {
@@ -113,6 +139,18 @@ impl TimeoutManager {
         let result = inner.reserve_when_available(request_type, now, cancellation_token)?;
         Ok(Either::Left(convert(result.0)))
     }
+
+    /// States of requests limits of all exchange accounts, so it can be distinguished whether
+    /// requests are slow because of exchange or because of waiting for requests limit
+    pub fn stats(&self) -> Result<Vec<RequestsLimitStats>> {
+        let now = now();
+        self.inner
+            .read()
+            .iter()
+            .sorted_by_key(|(exchange_account_id, _)| exchange_account_id.to_string())
+            .map(|(_, timeout_manager)| timeout_manager.stats(now))
+            .collect()
+    }
 }
 
 pub fn now() -> DateTime {
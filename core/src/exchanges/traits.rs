@@ -1,6 +1,7 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use async_trait::async_trait;
 use dashmap::DashMap;
 use mmb_utils::DateTime;
@@ -13,15 +14,25 @@ use super::{
         SpecificCurrencyPair,
     },
     common::{Amount, ClosedPosition, CurrencyId, Price},
-    events::{ExchangeBalancesAndPositions, TradeId},
+    events::{
+        DustConversion, ExchangeBalance, ExchangeBalancesAndPositions, Trade, TradeId, TradeStream,
+    },
+    general::dust_conversion::DustBalance,
     general::handlers::handle_order_filled::FillEventData,
+    general::maintenance::SystemStatus,
+    general::margin::MarginInfo,
+    general::pagination::PageRequest,
+    general::sub_account::SubAccountTransfer,
     general::symbol::BeforeAfter,
+    general::withdrawal::{InternalTransfer, Withdrawal},
     general::{order::get_order_trades::OrderTrade, symbol::Symbol},
     timeouts::requests_timeout_manager_factory::RequestTimeoutArguments,
 };
 use crate::exchanges::events::ExchangeEvent;
 use crate::exchanges::general::features::ExchangeFeatures;
+use crate::exchanges::market_data_downloader::HistoricalMessage;
 use crate::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use crate::order_book::order_book_data::OrderBookData;
 use crate::orders::fill::EventSourceType;
 use crate::orders::order::{
     ClientOrderId, ExchangeOrderId, OrderCancelling, OrderCreating, OrderInfo,
@@ -68,6 +79,114 @@ pub trait ExchangeClient: Support {
         position: &ActivePosition,
         price: Option<Price>,
     ) -> Result<RestRequestOutcome>;
+
+    /// Synchronize local time with exchange server time if exchange requires it for signed requests
+    async fn sync_server_time(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn request_withdraw(&self, _withdrawal: &Withdrawal) -> Result<RestRequestOutcome> {
+        bail!("Withdrawal isn't supported by exchange")
+    }
+
+    async fn request_internal_transfer(
+        &self,
+        _transfer: &InternalTransfer,
+    ) -> Result<RestRequestOutcome> {
+        bail!("Internal transfer isn't supported by exchange")
+    }
+
+    async fn request_sub_account_balances(&self, _sub_account: &str) -> Result<RestRequestOutcome> {
+        bail!("Sub-accounts aren't supported by exchange")
+    }
+
+    async fn request_sub_account_transfer(
+        &self,
+        _transfer: &SubAccountTransfer,
+    ) -> Result<RestRequestOutcome> {
+        bail!("Sub-accounts aren't supported by exchange")
+    }
+
+    async fn request_dust_balances(&self) -> Result<RestRequestOutcome> {
+        bail!("Dust conversion isn't supported by exchange")
+    }
+
+    async fn request_dust_conversion(
+        &self,
+        _currency_codes: &[CurrencyCode],
+    ) -> Result<RestRequestOutcome> {
+        bail!("Dust conversion isn't supported by exchange")
+    }
+
+    async fn request_my_trades_page(
+        &self,
+        _symbol: &Symbol,
+        _page: &PageRequest,
+    ) -> Result<RestRequestOutcome> {
+        bail!("Paginated my trades requests aren't supported by exchange")
+    }
+
+    async fn request_all_orders_page(
+        &self,
+        _currency_pair: CurrencyPair,
+        _page: &PageRequest,
+    ) -> Result<RestRequestOutcome> {
+        bail!("Orders history isn't supported by exchange")
+    }
+
+    /// Page of public trades of currency pair starting from cursor
+    async fn request_historical_trades(
+        &self,
+        _currency_pair: CurrencyPair,
+        _page: &PageRequest,
+    ) -> Result<RestRequestOutcome> {
+        bail!("Historical trades aren't supported by exchange")
+    }
+
+    /// Public trades of currency pair starting from specified trade id for backfilling
+    /// of trades missed by websocket
+    async fn request_trades_from_id(
+        &self,
+        _currency_pair: CurrencyPair,
+        _trade_stream: TradeStream,
+        _from_id: u64,
+        _limit: usize,
+    ) -> Result<RestRequestOutcome> {
+        bail!("Requesting of trades by id isn't supported by exchange")
+    }
+
+    /// Page of klines (candles) with specified interval in exchange format, e.g. "1m"
+    async fn request_klines(
+        &self,
+        _currency_pair: CurrencyPair,
+        _interval: &str,
+        _page: &PageRequest,
+    ) -> Result<RestRequestOutcome> {
+        bail!("Klines aren't supported by exchange")
+    }
+
+    /// Snapshot of order book with specified count of price levels on each side
+    async fn request_order_book_snapshot(
+        &self,
+        _currency_pair: CurrencyPair,
+        _depth: usize,
+    ) -> Result<RestRequestOutcome> {
+        bail!("Order book snapshots aren't supported by exchange")
+    }
+
+    /// Max count of items in one page of listing endpoints
+    fn page_limit(&self) -> usize {
+        500
+    }
+
+    async fn request_system_status(&self) -> Result<RestRequestOutcome> {
+        bail!("System status isn't supported by exchange")
+    }
+
+    /// Account-level margin of derivative account
+    async fn request_margin_info(&self) -> Result<RestRequestOutcome> {
+        bail!("Margin info isn't supported by exchange")
+    }
 }
 
 #[async_trait]
@@ -95,7 +214,9 @@ pub trait Support: Send + Sync {
     fn set_handle_trade_callback(
         &self,
         callback: Box<
-            dyn FnMut(CurrencyPair, TradeId, Price, Amount, OrderSide, DateTime) + Send + Sync,
+            dyn FnMut(CurrencyPair, TradeStream, TradeId, Price, Amount, OrderSide, DateTime)
+                + Send
+                + Sync,
         >,
     );
 
@@ -105,6 +226,35 @@ pub trait Support: Send + Sync {
 
     async fn create_ws_url(&self, role: WebSocketRole) -> Result<Url>;
 
+    /// Channels which main websocket is subscribed to on every connection
+    fn get_websocket_channels(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Adds channel to subscriptions of main websocket after validation.
+    /// Returns `false` if channel is subscribed already
+    fn add_websocket_channel(&self, _channel: &str) -> Result<bool> {
+        bail!("Subscription to additional websocket channels isn't supported")
+    }
+
+    /// Removes channel from subscriptions of main websocket.
+    /// Returns `false` if channel isn't subscribed
+    fn remove_websocket_channel(&self, _channel: &str) -> Result<bool> {
+        bail!("Subscription to additional websocket channels isn't supported")
+    }
+
+    /// Message for subscription to channels on opened main websocket. Channels are subscribed
+    /// since the next connection only if it's `None`
+    fn build_ws_subscribe_message(&self, _channels: &[String]) -> Option<String> {
+        None
+    }
+
+    /// Message for unsubscription from channels on opened main websocket. Channels are
+    /// unsubscribed since the next connection only if it's `None`
+    fn build_ws_unsubscribe_message(&self, _channels: &[String]) -> Option<String> {
+        None
+    }
+
     fn get_specific_currency_pair(&self, currency_pair: CurrencyPair) -> SpecificCurrencyPair;
 
     fn get_supported_currencies(&self) -> &DashMap<CurrencyId, CurrencyCode>;
@@ -115,6 +265,11 @@ pub trait Support: Send + Sync {
         log::info!("Unknown message for {}: {}", exchange_account_id, message);
     }
 
+    /// Counts of received websocket messages without handler by message type
+    fn get_unhandled_messages_counts(&self) -> HashMap<String, u64> {
+        HashMap::new()
+    }
+
     fn parse_all_symbols(&self, response: &RestRequestOutcome) -> Result<Vec<Arc<Symbol>>>;
 
     fn get_balance_reservation_currency_code(
@@ -138,6 +293,70 @@ pub trait Support: Send + Sync {
     fn parse_close_position(&self, response: &RestRequestOutcome) -> Result<ClosedPosition>;
 
     fn parse_get_balance(&self, response: &RestRequestOutcome) -> ExchangeBalancesAndPositions;
+
+    /// Returns exchange id of withdrawal
+    fn parse_withdraw(&self, _response: &RestRequestOutcome) -> Result<String> {
+        bail!("Withdrawal isn't supported by exchange")
+    }
+
+    /// Returns exchange id of transfer. Used for internal and sub-account transfers
+    fn parse_internal_transfer(&self, _response: &RestRequestOutcome) -> Result<String> {
+        bail!("Internal transfer isn't supported by exchange")
+    }
+
+    fn parse_sub_account_balances(
+        &self,
+        _response: &RestRequestOutcome,
+    ) -> Result<Vec<ExchangeBalance>> {
+        bail!("Sub-accounts aren't supported by exchange")
+    }
+
+    fn parse_dust_balances(&self, _response: &RestRequestOutcome) -> Result<Vec<DustBalance>> {
+        bail!("Dust conversion isn't supported by exchange")
+    }
+
+    fn parse_dust_conversion(&self, _response: &RestRequestOutcome) -> Result<Vec<DustConversion>> {
+        bail!("Dust conversion isn't supported by exchange")
+    }
+
+    fn parse_system_status(&self, _response: &RestRequestOutcome) -> Result<SystemStatus> {
+        bail!("System status isn't supported by exchange")
+    }
+
+    fn parse_margin_info(&self, _response: &RestRequestOutcome) -> Result<MarginInfo> {
+        bail!("Margin info isn't supported by exchange")
+    }
+
+    fn parse_trades_from_id(&self, _response: &RestRequestOutcome) -> Result<Vec<Trade>> {
+        bail!("Requesting of trades by id isn't supported by exchange")
+    }
+
+    fn parse_all_orders(&self, _response: &RestRequestOutcome) -> Result<Vec<OrderInfo>> {
+        bail!("Orders history isn't supported by exchange")
+    }
+
+    /// Historical trades converted to messages of websocket trades stream
+    fn parse_historical_trades(
+        &self,
+        _response: &RestRequestOutcome,
+        _currency_pair: CurrencyPair,
+    ) -> Result<Vec<HistoricalMessage>> {
+        bail!("Historical trades aren't supported by exchange")
+    }
+
+    /// Klines converted to messages of websocket klines stream
+    fn parse_klines(
+        &self,
+        _response: &RestRequestOutcome,
+        _currency_pair: CurrencyPair,
+        _interval: &str,
+    ) -> Result<Vec<HistoricalMessage>> {
+        bail!("Klines aren't supported by exchange")
+    }
+
+    fn parse_order_book_snapshot(&self, _response: &RestRequestOutcome) -> Result<OrderBookData> {
+        bail!("Order book snapshots aren't supported by exchange")
+    }
 }
 
 pub struct ExchangeClientBuilderResult {
@@ -145,7 +364,7 @@ pub struct ExchangeClientBuilderResult {
     pub features: ExchangeFeatures,
 }
 
-pub trait ExchangeClientBuilder {
+pub trait ExchangeClientBuilder: Send + Sync {
     fn create_exchange_client(
         &self,
         exchange_settings: ExchangeSettings,
@@ -154,4 +373,9 @@ pub trait ExchangeClientBuilder {
     ) -> ExchangeClientBuilderResult;
 
     fn get_timeout_arguments(&self) -> RequestTimeoutArguments;
+
+    /// Websocket channels which can be requested in settings of exchange
+    fn get_supported_websocket_channels(&self) -> Vec<String> {
+        Vec::new()
+    }
 }
@@ -0,0 +1,674 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use hyper::{StatusCode, Uri};
+use mmb_utils::DateTime;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, Notify};
+
+use crate::connectivity::connectivity_manager::WebSocketRole;
+use crate::exchanges::common::RestRequestOutcome;
+use crate::misc::time::time_manager;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RestMethod {
+    Get,
+    Post,
+    Delete,
+}
+
+#[derive(Debug, Clone)]
+pub struct RestRequest {
+    pub method: RestMethod,
+    pub url: Uri,
+    pub api_key: String,
+    pub body: String,
+}
+
+/// Low level sending of REST requests. Allows to substitute network for recording or replaying of traffic
+#[async_trait]
+pub trait Transport: Send + Sync {
+    async fn send(&self, request: RestRequest) -> Result<RestRequestOutcome>;
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum TrafficRecord {
+    Rest {
+        timestamp: DateTime,
+        method: RestMethod,
+        url: String,
+        body: String,
+        status: u16,
+        content: String,
+    },
+    /// REST request which failed without response, e.g. by timeout or connection error
+    RestError {
+        timestamp: DateTime,
+        method: RestMethod,
+        url: String,
+        body: String,
+        error: String,
+    },
+    WebSocket {
+        timestamp: DateTime,
+        role: WebSocketRole,
+        message: String,
+    },
+    /// Periodic snapshot of engine statistics in json
+    StatsSnapshot {
+        timestamp: DateTime,
+        snapshot: String,
+    },
+}
+
+static TRAFFIC_RECORDERS: Lazy<Mutex<HashMap<String, Arc<TrafficRecorder>>>> =
+    Lazy::new(Default::default);
+
+/// How long websocket message waits in replay for REST responses which were recorded before it
+const REST_RESPONSES_WAITING_TIMEOUT: Duration = Duration::from_secs(1);
+
+enum RecorderCommand {
+    Record(TrafficRecord),
+    Flush(std::sync::mpsc::SyncSender<()>),
+}
+
+/// Writes exchange traffic to file as json lines. Records are written by own thread,
+/// so websocket handlers aren't blocked by file system
+pub struct TrafficRecorder {
+    commands_sender: mpsc::UnboundedSender<RecorderCommand>,
+}
+
+impl TrafficRecorder {
+    /// Recorders are shared by path, so REST and websocket traffic of an exchange are written in one file
+    pub fn get_or_create(path: &str) -> Result<Arc<Self>> {
+        let mut recorders = TRAFFIC_RECORDERS.lock();
+        if let Some(recorder) = recorders.get(path) {
+            return Ok(recorder.clone());
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Unable to open file {} for traffic recording", path))?;
+
+        let (commands_sender, commands_receiver) = mpsc::unbounded_channel();
+        let _ = std::thread::Builder::new()
+            .name("traffic_recorder".to_owned())
+            .spawn(move || Self::write_records(file, commands_receiver))
+            .context("Unable to start thread for traffic recording")?;
+
+        let recorder = Arc::new(TrafficRecorder { commands_sender });
+        recorders.insert(path.to_owned(), recorder.clone());
+
+        Ok(recorder)
+    }
+
+    /// Waits until records of all recorders are written, e.g. before exit
+    pub fn flush_all() {
+        let recorders = TRAFFIC_RECORDERS
+            .lock()
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        for recorder in recorders {
+            recorder.flush();
+        }
+    }
+
+    /// Waits until records which are already passed to recorder are written to file
+    pub fn flush(&self) {
+        let (done_sender, done_receiver) = std::sync::mpsc::sync_channel(1);
+        if self
+            .commands_sender
+            .send(RecorderCommand::Flush(done_sender))
+            .is_ok()
+        {
+            let _ = done_receiver.recv();
+        }
+    }
+
+    pub fn record_websocket_message(&self, role: WebSocketRole, message: &str) {
+        self.record_websocket_message_at(time_manager::now(), role, message);
+    }
+
+    /// Records message with specified time, e.g. historical market data with time of exchange
+    pub fn record_websocket_message_at(
+        &self,
+        timestamp: DateTime,
+        role: WebSocketRole,
+        message: &str,
+    ) {
+        self.record(TrafficRecord::WebSocket {
+            timestamp,
+            role,
+            message: message.to_owned(),
+        });
+    }
+
+    pub fn record_stats_snapshot(&self, timestamp: DateTime, snapshot: &str) {
+        self.record(TrafficRecord::StatsSnapshot {
+            timestamp,
+            snapshot: snapshot.to_owned(),
+        });
+    }
+
+    fn record(&self, record: TrafficRecord) {
+        if self
+            .commands_sender
+            .send(RecorderCommand::Record(record))
+            .is_err()
+        {
+            log::error!("Failed to record traffic: writer of traffic records is stopped");
+        }
+    }
+
+    /// Buffer is flushed when there are no more queued records, so bursts of websocket messages
+    /// are written by a few system calls
+    fn write_records(file: File, mut commands_receiver: mpsc::UnboundedReceiver<RecorderCommand>) {
+        let mut writer = BufWriter::new(file);
+        while let Some(command) = commands_receiver.blocking_recv() {
+            let mut next_command = Some(command);
+            while let Some(command) = next_command {
+                match command {
+                    RecorderCommand::Record(record) => Self::write_record(&mut writer, &record),
+                    RecorderCommand::Flush(done_sender) => {
+                        Self::flush_writer(&mut writer);
+                        let _ = done_sender.send(());
+                    }
+                }
+                next_command = commands_receiver.try_recv().ok();
+            }
+
+            Self::flush_writer(&mut writer);
+        }
+    }
+
+    fn write_record(writer: &mut BufWriter<File>, record: &TrafficRecord) {
+        let write_result = serde_json::to_string(record)
+            .context("Unable to serialize traffic record")
+            .and_then(|line| {
+                writeln!(writer, "{}", line).context("Unable to write traffic record")
+            });
+
+        if let Err(error) = write_result {
+            log::error!("Failed to record traffic: {:?}", error);
+        }
+    }
+
+    fn flush_writer(writer: &mut BufWriter<File>) {
+        if let Err(error) = writer.flush() {
+            log::error!("Failed to flush traffic records: {:?}", error);
+        }
+    }
+}
+
+pub fn read_traffic_records(path: &str) -> Result<Vec<TrafficRecord>> {
+    let mut records = Vec::new();
+    for_each_traffic_record(path, |record| records.push(record))?;
+    Ok(records)
+}
+
+/// Passes records to `action` one by one, so file isn't loaded into memory.
+/// The last line is skipped if it can't be parsed, because it can be torn by running recorder
+pub fn for_each_traffic_record(path: &str, mut action: impl FnMut(TrafficRecord)) -> Result<()> {
+    let file =
+        File::open(path).with_context(|| format!("Unable to open traffic record file {}", path))?;
+
+    let mut lines = BufReader::new(file).lines().peekable();
+    while let Some(line) = lines.next() {
+        let line = line.context("Unable to read traffic record")?;
+        match serde_json::from_str(&line) {
+            Ok(record) => action(record),
+            Err(error) if lines.peek().is_none() => log::warn!(
+                "The last traffic record of {} is skipped because it's incomplete: {:?}",
+                path,
+                error
+            ),
+            Err(error) => {
+                return Err(error)
+                    .with_context(|| format!("Unable to parse traffic record {}", line))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Records all REST requests passed through inner transport with their responses or errors
+pub struct RecordingTransport {
+    inner: Arc<dyn Transport>,
+    recorder: Arc<TrafficRecorder>,
+}
+
+impl RecordingTransport {
+    pub fn new(inner: Arc<dyn Transport>, recorder: Arc<TrafficRecorder>) -> Self {
+        Self { inner, recorder }
+    }
+}
+
+#[async_trait]
+impl Transport for RecordingTransport {
+    async fn send(&self, request: RestRequest) -> Result<RestRequestOutcome> {
+        let method = request.method;
+        let url = request.url.to_string();
+        let body = request.body.clone();
+
+        let result = self.inner.send(request).await;
+
+        let timestamp = time_manager::now();
+        self.recorder.record(match &result {
+            Ok(outcome) => TrafficRecord::Rest {
+                timestamp,
+                method,
+                url,
+                body,
+                status: outcome.status.as_u16(),
+                content: outcome.content.clone(),
+            },
+            Err(error) => TrafficRecord::RestError {
+                timestamp,
+                method,
+                url,
+                body,
+                error: format!("{:?}", error),
+            },
+        });
+
+        result
+    }
+}
+
+static TRAFFIC_REPLAYS: Lazy<Mutex<HashMap<String, Arc<TrafficReplay>>>> =
+    Lazy::new(Default::default);
+
+/// Recorded response of REST request or error of failed request
+type RecordedResponse = Result<RestRequestOutcome, String>;
+
+struct RecordedRest {
+    /// Position of record in traffic file
+    index: usize,
+    method: RestMethod,
+    path: String,
+    response: RecordedResponse,
+}
+
+struct RecordedWebSocketMessage {
+    index: usize,
+    timestamp: DateTime,
+    message: String,
+}
+
+/// Recorded traffic of exchange which is replayed instead of network. REST responses are taken
+/// by `ReplayTransport` and websocket messages by `Exchange::replay_websocket_traffic`.
+/// Websocket message is passed only after REST responses recorded before it are taken by engine,
+/// so both kinds of traffic are replayed in recorded order
+pub struct TrafficReplay {
+    rest_records: Mutex<VecDeque<RecordedRest>>,
+    websocket_messages: Mutex<VecDeque<RecordedWebSocketMessage>>,
+    rest_record_taken: Notify,
+    /// REST records before this index aren't waited anymore, because engine didn't request them
+    /// in time
+    waited_rest_index: AtomicUsize,
+}
+
+impl TrafficReplay {
+    pub fn new(records: Vec<TrafficRecord>) -> Result<Arc<Self>> {
+        let mut rest_records = VecDeque::new();
+        let mut websocket_messages = VecDeque::new();
+        for (index, record) in records.into_iter().enumerate() {
+            let (method, url, response) = match record {
+                TrafficRecord::Rest {
+                    method,
+                    url,
+                    status,
+                    content,
+                    ..
+                } => {
+                    let outcome = RestRequestOutcome {
+                        content,
+                        status: StatusCode::from_u16(status).context("Invalid recorded status")?,
+                    };
+                    (method, url, Ok(outcome))
+                }
+                TrafficRecord::RestError {
+                    method, url, error, ..
+                } => (method, url, Err(error)),
+                TrafficRecord::WebSocket {
+                    timestamp, message, ..
+                } => {
+                    websocket_messages.push_back(RecordedWebSocketMessage {
+                        index,
+                        timestamp,
+                        message,
+                    });
+                    continue;
+                }
+                TrafficRecord::StatsSnapshot { .. } => continue,
+            };
+
+            let url: Uri = url.parse().context("Unable to parse recorded url")?;
+            rest_records.push_back(RecordedRest {
+                index,
+                method,
+                path: url.path().to_owned(),
+                response,
+            });
+        }
+
+        Ok(Arc::new(Self {
+            rest_records: Mutex::new(rest_records),
+            websocket_messages: Mutex::new(websocket_messages),
+            rest_record_taken: Notify::new(),
+            waited_rest_index: AtomicUsize::new(0),
+        }))
+    }
+
+    /// Replays are shared by path, so REST and websocket traffic of an exchange are replayed
+    /// from one file
+    pub fn get_or_load(path: &str) -> Result<Arc<Self>> {
+        let mut replays = TRAFFIC_REPLAYS.lock();
+        if let Some(replay) = replays.get(path) {
+            return Ok(replay.clone());
+        }
+
+        let replay = Self::new(read_traffic_records(path)?)?;
+        replays.insert(path.to_owned(), replay.clone());
+
+        Ok(replay)
+    }
+
+    /// Requests are matched with records by method and url path in order of recording,
+    /// because query parameters contain timestamps and signatures
+    fn take_rest_response(&self, method: RestMethod, path: &str) -> Option<RecordedResponse> {
+        let response = {
+            let mut rest_records = self.rest_records.lock();
+            let position = rest_records
+                .iter()
+                .position(|x| x.method == method && x.path == path)?;
+            rest_records.remove(position)?.response
+        };
+
+        self.rest_record_taken.notify_waiters();
+        Some(response)
+    }
+
+    fn has_rest_records_before(&self, index: usize) -> bool {
+        let waited_rest_index = self.waited_rest_index.load(Ordering::SeqCst);
+        self.rest_records
+            .lock()
+            .iter()
+            .any(|x| x.index >= waited_rest_index && x.index < index)
+    }
+
+    /// Next recorded websocket message with time of its receiving. REST responses recorded before
+    /// message are waited for limited time only, because engine can skip some requests in replay
+    pub async fn next_websocket_message(&self) -> Option<(DateTime, String)> {
+        let RecordedWebSocketMessage {
+            index,
+            timestamp,
+            message,
+        } = self.websocket_messages.lock().pop_front()?;
+
+        let wait_rest_responses = async {
+            loop {
+                let rest_record_taken = self.rest_record_taken.notified();
+                if !self.has_rest_records_before(index) {
+                    return;
+                }
+                rest_record_taken.await;
+            }
+        };
+        if tokio::time::timeout(REST_RESPONSES_WAITING_TIMEOUT, wait_rest_responses)
+            .await
+            .is_err()
+        {
+            log::warn!(
+                "REST responses recorded before websocket message {} aren't requested in replay",
+                index
+            );
+            self.waited_rest_index.store(index, Ordering::SeqCst);
+        }
+
+        Some((timestamp, message))
+    }
+}
+
+/// Returns recorded responses instead of sending requests to exchange.
+/// Failed requests are replayed as errors
+pub struct ReplayTransport {
+    replay: Arc<TrafficReplay>,
+}
+
+impl ReplayTransport {
+    pub fn new(replay: Arc<TrafficReplay>) -> Self {
+        Self { replay }
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        Ok(Self::new(TrafficReplay::get_or_load(path)?))
+    }
+}
+
+#[async_trait]
+impl Transport for ReplayTransport {
+    async fn send(&self, request: RestRequest) -> Result<RestRequestOutcome> {
+        match self
+            .replay
+            .take_rest_response(request.method, request.url.path())
+        {
+            Some(Ok(outcome)) => Ok(outcome),
+            Some(Err(error)) => bail!(
+                "Recorded request {:?} {} failed: {}",
+                request.method,
+                request.url,
+                error
+            ),
+            None => bail!(
+                "There is no recorded response for {:?} {}",
+                request.method,
+                request.url
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rest_record(url: &str, content: &str) -> TrafficRecord {
+        TrafficRecord::Rest {
+            timestamp: time_manager::now(),
+            method: RestMethod::Get,
+            url: url.to_owned(),
+            body: String::new(),
+            status: 200,
+            content: content.to_owned(),
+        }
+    }
+
+    fn get_request(url: &str) -> RestRequest {
+        RestRequest {
+            method: RestMethod::Get,
+            url: url.parse().expect("in test"),
+            api_key: String::new(),
+            body: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn replay_responses_in_recorded_order() {
+        let replay = TrafficReplay::new(vec![
+            rest_record("https://host.com/order?timestamp=1", "first"),
+            rest_record("https://host.com/time", "time"),
+            rest_record("https://host.com/order?timestamp=2", "second"),
+        ])
+        .expect("in test");
+        let transport = ReplayTransport::new(replay);
+
+        let request = get_request("https://host.com/order?timestamp=3");
+        let first = transport.send(request.clone()).await.expect("in test");
+        let second = transport.send(request.clone()).await.expect("in test");
+
+        assert_eq!(first.content, "first");
+        assert_eq!(second.content, "second");
+        assert!(transport.send(request).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn failed_request_is_replayed_as_error() {
+        let replay = TrafficReplay::new(vec![TrafficRecord::RestError {
+            timestamp: time_manager::now(),
+            method: RestMethod::Get,
+            url: "https://host.com/time".to_owned(),
+            body: String::new(),
+            error: "Connection reset".to_owned(),
+        }])
+        .expect("in test");
+        let transport = ReplayTransport::new(replay);
+
+        let error = transport
+            .send(get_request("https://host.com/time"))
+            .await
+            .expect_err("in test");
+
+        assert!(error.to_string().contains("Connection reset"));
+    }
+
+    fn websocket_record(message: &str) -> TrafficRecord {
+        TrafficRecord::WebSocket {
+            timestamp: time_manager::now(),
+            role: WebSocketRole::Main,
+            message: message.to_owned(),
+        }
+    }
+
+    #[tokio::test]
+    async fn websocket_message_waits_rest_responses_recorded_before_it() {
+        let replay = TrafficReplay::new(vec![
+            websocket_record("first"),
+            rest_record("https://host.com/balance", "balance"),
+            websocket_record("second"),
+        ])
+        .expect("in test");
+        let transport = ReplayTransport::new(replay.clone());
+
+        let (_, first) = replay.next_websocket_message().await.expect("in test");
+        assert_eq!(first, "first");
+
+        let mut second = tokio::spawn({
+            let replay = replay.clone();
+            async move { replay.next_websocket_message().await }
+        });
+        let waiting = tokio::time::timeout(Duration::from_millis(100), &mut second).await;
+        assert!(
+            waiting.is_err(),
+            "second message should wait for REST response"
+        );
+
+        let balance = transport
+            .send(get_request("https://host.com/balance"))
+            .await
+            .expect("in test");
+        assert_eq!(balance.content, "balance");
+
+        let (_, second) = second.await.expect("in test").expect("in test");
+        assert_eq!(second, "second");
+        assert!(replay.next_websocket_message().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn websocket_message_is_passed_if_rest_response_is_not_requested() {
+        let replay = TrafficReplay::new(vec![
+            rest_record("https://host.com/balance", "balance"),
+            websocket_record("first"),
+            websocket_record("second"),
+        ])
+        .expect("in test");
+
+        let (_, first) = replay.next_websocket_message().await.expect("in test");
+        let started_at = std::time::Instant::now();
+        let (_, second) = replay.next_websocket_message().await.expect("in test");
+
+        assert_eq!(first, "first");
+        // Skipped REST response isn't waited again
+        assert_eq!(second, "second");
+        assert!(started_at.elapsed() < REST_RESPONSES_WAITING_TIMEOUT);
+    }
+
+    #[test]
+    fn traffic_is_written_to_file() {
+        let path = std::env::temp_dir().join(format!("mmb_traffic_{}.jsonl", uuid::Uuid::new_v4()));
+        let path = path.to_str().expect("in test").to_owned();
+        let recorder = TrafficRecorder::get_or_create(&path).expect("in test");
+
+        recorder.record_websocket_message(WebSocketRole::Main, "first");
+        recorder.record_websocket_message(WebSocketRole::Secondary, "second");
+        recorder.flush();
+
+        let records = read_traffic_records(&path);
+
+        std::fs::remove_file(&path).expect("in test");
+        let messages = records
+            .expect("in test")
+            .into_iter()
+            .map(|record| match record {
+                TrafficRecord::WebSocket { message, .. } => message,
+                _ => panic!("unexpected record {:?}", record),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(messages, ["first", "second"]);
+    }
+
+    #[test]
+    fn serialize_traffic_record() {
+        let record = TrafficRecord::WebSocket {
+            timestamp: time_manager::now(),
+            role: WebSocketRole::Main,
+            message: r#"{"stream":"btcusdt@trade"}"#.to_owned(),
+        };
+
+        let serialized = serde_json::to_string(&record).expect("in test");
+        let deserialized: TrafficRecord = serde_json::from_str(&serialized).expect("in test");
+
+        assert_eq!(deserialized, record);
+    }
+
+    #[test]
+    fn torn_last_traffic_record_is_skipped() {
+        let path = std::env::temp_dir().join(format!("mmb_traffic_{}.jsonl", uuid::Uuid::new_v4()));
+        let path = path.to_str().expect("in test").to_owned();
+        let record = rest_record("https://host.com/time", "time");
+        let line = serde_json::to_string(&record).expect("in test");
+        std::fs::write(&path, format!("{}\n{}", line, &line[..line.len() / 2])).expect("in test");
+
+        let records = read_traffic_records(&path);
+
+        std::fs::remove_file(&path).expect("in test");
+        assert_eq!(records.expect("in test"), [record]);
+    }
+
+    #[test]
+    fn torn_traffic_record_in_middle_of_file_is_error() {
+        let path = std::env::temp_dir().join(format!("mmb_traffic_{}.jsonl", uuid::Uuid::new_v4()));
+        let path = path.to_str().expect("in test").to_owned();
+        let line =
+            serde_json::to_string(&rest_record("https://host.com/time", "time")).expect("in test");
+        std::fs::write(&path, format!("{}\n{}", &line[..line.len() / 2], line)).expect("in test");
+
+        let records = read_traffic_records(&path);
+
+        std::fs::remove_file(&path).expect("in test");
+        assert!(records.is_err());
+    }
+}
@@ -1,12 +1,17 @@
+use anyhow::{bail, Result};
 use futures::future::BoxFuture;
+use futures::FutureExt;
 use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::CompletionReason;
 use mmb_utils::infrastructure::CustomSpawnFuture;
 use mmb_utils::infrastructure::FutureOutcome;
 use mmb_utils::infrastructure::SpawnFutureFlags;
 use once_cell::sync::OnceCell;
 use parking_lot::Mutex;
+use std::collections::VecDeque;
 use std::panic;
 use std::sync::Arc;
+use std::time::Instant;
 use std::{pin::Pin, time::Duration};
 use tokio::task::JoinHandle;
 
@@ -124,6 +129,106 @@ pub fn spawn_by_timer(
     )
 }
 
+/// How many times a supervised future can be restarted after panics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestartPolicy {
+    /// Max count of restarts within `window`. Graceful shutdown is started when it is exceeded
+    pub max_restarts: usize,
+    pub window: Duration,
+    /// Delay before restarting panicked future
+    pub restart_delay: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: 5,
+            window: Duration::from_secs(60),
+            restart_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+struct RestartTracker {
+    policy: RestartPolicy,
+    restart_times: VecDeque<Instant>,
+}
+
+impl RestartTracker {
+    fn new(policy: RestartPolicy) -> Self {
+        Self {
+            policy,
+            restart_times: VecDeque::new(),
+        }
+    }
+
+    /// Registers restart and returns `false` if restarts within window are exhausted
+    fn try_register_restart(&mut self, now: Instant) -> bool {
+        while let Some(&oldest) = self.restart_times.front() {
+            if now.saturating_duration_since(oldest) < self.policy.window {
+                break;
+            }
+            let _ = self.restart_times.pop_front();
+        }
+
+        if self.restart_times.len() >= self.policy.max_restarts {
+            return false;
+        }
+
+        self.restart_times.push_back(now);
+        true
+    }
+}
+
+/// Spawn long-running future which is restarted by `restart_policy` if it panics.
+/// Future is created by `action_factory` on every start. Errors and cancellation finish the future as usual.
+/// Graceful shutdown is started only if restarts are exhausted
+pub fn spawn_supervised(
+    action_name: &str,
+    restart_policy: RestartPolicy,
+    action_factory: impl Fn() -> Pin<CustomSpawnFuture> + Send + Sync + 'static,
+) -> JoinHandle<FutureOutcome> {
+    let name = action_name.to_owned();
+    let supervisor = async move {
+        let mut restart_tracker = RestartTracker::new(restart_policy);
+        loop {
+            let outcome =
+                spawn_future(&name, SpawnFutureFlags::STOP_BY_TOKEN, action_factory()).await?;
+
+            if outcome.completion_reason() != CompletionReason::Panicked {
+                return outcome.into_result();
+            }
+
+            if !restart_tracker.try_register_restart(Instant::now()) {
+                let error_message = format!(
+                    "Future {} panicked more than {} times within {:?}",
+                    name, restart_policy.max_restarts, restart_policy.window
+                );
+                spawn_graceful_shutdown(format!("Supervisor of '{}'", name), error_message.clone());
+                bail!(error_message);
+            }
+
+            log::warn!(
+                "Future {} panicked and will be restarted in {:?}",
+                name,
+                restart_policy.restart_delay
+            );
+
+            let cancellation_token = get_futures_cancellation_token();
+            tokio::select! {
+                _ = tokio::time::sleep(restart_policy.restart_delay) => {}
+                _ = cancellation_token.when_cancelled() => return Ok(()),
+            }
+        }
+    };
+
+    spawn_future(
+        &format!("Supervisor of {}", action_name),
+        SpawnFutureFlags::STOP_BY_TOKEN,
+        supervisor.boxed(),
+    )
+}
+
 #[cfg(test)]
 mod test {
     use mmb_utils::{cancellation_token::CancellationToken, OPERATION_CANCELED_MSG};
@@ -157,4 +262,61 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn restarts_are_limited_within_window() {
+        let mut restart_tracker = RestartTracker::new(RestartPolicy {
+            max_restarts: 2,
+            window: Duration::from_secs(10),
+            restart_delay: Duration::ZERO,
+        });
+        let start = Instant::now();
+
+        assert!(restart_tracker.try_register_restart(start));
+        assert!(restart_tracker.try_register_restart(start + Duration::from_secs(1)));
+        assert!(!restart_tracker.try_register_restart(start + Duration::from_secs(2)));
+
+        // the first restart is out of window
+        assert!(restart_tracker.try_register_restart(start + Duration::from_secs(10)));
+        assert!(!restart_tracker.try_register_restart(start + Duration::from_secs(10)));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn supervised_future_restarted_after_panic() -> Result<()> {
+        let manager = AppLifetimeManager::new(CancellationToken::new());
+        keep_lifetime_manager(manager);
+
+        let starts_count = Arc::new(Mutex::new(0));
+        let starts_count_clone = starts_count.clone();
+        let future_outcome = spawn_supervised(
+            "test_supervised_action",
+            RestartPolicy {
+                restart_delay: Duration::ZERO,
+                ..RestartPolicy::default()
+            },
+            move || {
+                let starts_count = starts_count_clone.clone();
+                async move {
+                    let starts = {
+                        let mut starts_count = starts_count.lock();
+                        *starts_count += 1;
+                        *starts_count
+                    };
+
+                    if starts < 3 {
+                        panic!("test panic");
+                    }
+
+                    Ok(())
+                }
+                .boxed()
+            },
+        )
+        .await?;
+
+        future_outcome.into_result()?;
+        assert_eq!(*starts_count.lock(), 3);
+
+        Ok(())
+    }
 }
@@ -17,6 +17,7 @@
 pub(crate) mod balance_changes;
 pub mod balance_manager;
 mod balances;
+pub mod commission_ledger;
 pub mod connectivity;
 pub mod exchanges;
 pub mod infrastructure;
@@ -28,13 +29,20 @@ pub mod statistic_service;
 pub mod strategies;
 
 pub mod config;
+pub mod config_validation;
 pub mod disposition_execution;
+pub mod error;
 pub mod explanation;
 pub mod lifecycle;
 pub mod math;
 pub mod order_book;
+pub mod order_tracing;
+pub mod prelude;
 pub(crate) mod services;
 pub mod settings;
+pub mod storage;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
 pub mod text;
 
 #[cfg(test)]
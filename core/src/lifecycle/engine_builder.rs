@@ -0,0 +1,341 @@
+use std::collections::hash_map::Entry;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::exchanges::traits::ExchangeClientBuilder;
+use crate::lifecycle::launcher::{
+    launch_recover_only, launch_trading_engine_with_options, EngineBuildConfig, EngineOptions,
+    InitSettings,
+};
+use crate::lifecycle::reconciliation::ReconciliationReport;
+use crate::lifecycle::trading_engine::{EngineContext, TradingEngine};
+use crate::settings::{AppSettings, BaseStrategySettings, CoreSettings, ExchangeSettings};
+use crate::strategies::disposition_strategy::DispositionStrategy;
+
+type BuildStrategy<StrategySettings> =
+    Box<dyn Fn(&AppSettings<StrategySettings>, Arc<EngineContext>) -> Box<dyn DispositionStrategy>>;
+
+/// Fluent configuration of trading engine for embedding it into another application
+/// without configuration files
+///
+/// ```ignore
+/// let engine = TradingEngineBuilder::new()
+///     .add_exchange(Box::new(BinanceBuilder), exchange_settings)
+///     .add_exchange_account(second_account_settings)
+///     .add_strategy(strategy_settings, |settings, engine_context| {
+///         Box::new(ExampleStrategy::new(settings, engine_context))
+///     })
+///     .with_control_panel(false)
+///     .build()
+///     .await?;
+///
+/// let balance_manager = engine.balance_manager();
+/// let action = engine.run().await;
+/// ```
+pub struct TradingEngineBuilder<StrategySettings>
+where
+    StrategySettings: BaseStrategySettings + Clone,
+{
+    build_config: EngineBuildConfig,
+    exchanges: Vec<ExchangeSettings>,
+    strategy: Option<(StrategySettings, BuildStrategy<StrategySettings>)>,
+    core_settings: CoreSettings,
+    traffic_record_path: Option<String>,
+    options: EngineOptions,
+    /// Errors of fluent configuration which are returned by `build`
+    configuration_errors: Vec<String>,
+}
+
+impl<StrategySettings> TradingEngineBuilder<StrategySettings>
+where
+    StrategySettings: BaseStrategySettings + Clone + Debug + DeserializeOwned + Serialize,
+{
+    pub fn new() -> Self {
+        Self {
+            build_config: EngineBuildConfig {
+                supported_exchange_clients: Default::default(),
+                strategy_settings_schema: None,
+            },
+            exchanges: vec![],
+            strategy: None,
+            core_settings: Default::default(),
+            traffic_record_path: None,
+            options: EngineOptions::default(),
+            configuration_errors: vec![],
+        }
+    }
+
+    /// Adds exchange account. Client builder is registered for exchange id of account,
+    /// so other accounts of the same exchange are added by `add_exchange_account`.
+    /// `build` fails if client builder of exchange is already added
+    pub fn add_exchange(
+        mut self,
+        client_builder: Box<dyn ExchangeClientBuilder>,
+        settings: ExchangeSettings,
+    ) -> Self {
+        let exchange_id = settings.exchange_account_id.exchange_id;
+        match self
+            .build_config
+            .supported_exchange_clients
+            .entry(exchange_id)
+        {
+            Entry::Occupied(_) => self.configuration_errors.push(format!(
+                "Client builder of exchange {} is already added, \
+                 account {} should be added by add_exchange_account()",
+                exchange_id, settings.exchange_account_id
+            )),
+            Entry::Vacant(entry) => {
+                let _ = entry.insert(Arc::from(client_builder));
+            }
+        }
+
+        self.add_exchange_account(settings)
+    }
+
+    /// Adds exchange account with client builder which is added by `add_exchange`.
+    /// `build` fails if the same account is added twice
+    pub fn add_exchange_account(mut self, settings: ExchangeSettings) -> Self {
+        let exchange_account_id = settings.exchange_account_id;
+        if self
+            .exchanges
+            .iter()
+            .any(|x| x.exchange_account_id == exchange_account_id)
+        {
+            self.configuration_errors.push(format!(
+                "Exchange account {} is already added",
+                exchange_account_id
+            ));
+        }
+
+        self.exchanges.push(settings);
+        self
+    }
+
+    /// Sets strategy of engine. Only one strategy per engine is supported, so previous one is replaced
+    pub fn add_strategy<F>(mut self, settings: StrategySettings, build_strategy: F) -> Self
+    where
+        F: Fn(&AppSettings<StrategySettings>, Arc<EngineContext>) -> Box<dyn DispositionStrategy>
+            + 'static,
+    {
+        self.strategy = Some((settings, Box::new(build_strategy)));
+        self
+    }
+
+    /// Settings of core services except exchanges which are added by `add_exchange`
+    pub fn with_core_settings(mut self, core_settings: CoreSettings) -> Self {
+        self.core_settings = core_settings;
+        self
+    }
+
+    /// RPC control panel is started by default. It should be disabled if engine is managed by host application
+    pub fn with_control_panel(mut self, is_enabled: bool) -> Self {
+        self.options.is_control_panel_enabled = is_enabled;
+        self
+    }
+
+    /// Records traffic of all exchanges without own `traffic_record_path` to specified file
+    pub fn with_recorder(mut self, record_path: impl Into<String>) -> Self {
+        self.traffic_record_path = Some(record_path.into());
+        self
+    }
+
+    /// Launches engine. Returns `None` if graceful shutdown was requested during launch
+    pub async fn build(self) -> Result<Option<TradingEngine>> {
+        let (app_settings, build_strategy) = self.app_settings()?;
+
+        launch_trading_engine_with_options(
+            &self.build_config,
+            InitSettings::Directly(app_settings),
+            build_strategy,
+            self.options,
+        )
+        .await
+    }
+
+    /// Prints state of exchange accounts and exits without starting strategy. See `launch_recover_only`
+    pub async fn recover(self) -> Result<Option<ReconciliationReport>> {
+        let (app_settings, _) = self.app_settings()?;
+
+        launch_recover_only(&self.build_config, InitSettings::Directly(app_settings)).await
+    }
+
+    fn app_settings(
+        &self,
+    ) -> Result<(
+        AppSettings<StrategySettings>,
+        &BuildStrategy<StrategySettings>,
+    )> {
+        if !self.configuration_errors.is_empty() {
+            bail!(
+                "Invalid configuration of TradingEngine: {}",
+                self.configuration_errors.join("; ")
+            );
+        }
+
+        let (strategy_settings, build_strategy) = match &self.strategy {
+            Some(strategy) => strategy,
+            None => bail!("Strategy isn't specified for TradingEngine"),
+        };
+
+        if self.exchanges.is_empty() {
+            bail!("There are no exchanges for TradingEngine");
+        }
+
+        if let Some(settings) = self.exchanges.iter().find(|x| {
+            !self
+                .build_config
+                .supported_exchange_clients
+                .contains_key(&x.exchange_account_id.exchange_id)
+        }) {
+            bail!(
+                "Client builder isn't added for exchange account {}",
+                settings.exchange_account_id
+            );
+        }
+
+        let mut core = self.core_settings.clone();
+        core.exchanges = self
+            .exchanges
+            .iter()
+            .cloned()
+            .map(|mut settings| {
+                if settings.traffic_record_path.is_none() {
+                    settings.traffic_record_path = self.traffic_record_path.clone();
+                }
+                settings
+            })
+            .collect();
+
+        Ok((
+            AppSettings {
+                strategy: strategy_settings.clone(),
+                core,
+                shadow_strategy: None,
+            },
+            build_strategy,
+        ))
+    }
+}
+
+impl<StrategySettings> Default for TradingEngineBuilder<StrategySettings>
+where
+    StrategySettings: BaseStrategySettings + Clone + Debug + DeserializeOwned + Serialize,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use tokio::sync::broadcast;
+
+    use super::*;
+    use crate::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId};
+    use crate::exchanges::events::ExchangeEvent;
+    use crate::exchanges::timeouts::requests_timeout_manager_factory::RequestTimeoutArguments;
+    use crate::exchanges::traits::ExchangeClientBuilderResult;
+    use crate::lifecycle::app_lifetime_manager::AppLifetimeManager;
+
+    struct TestClientBuilder;
+
+    impl ExchangeClientBuilder for TestClientBuilder {
+        fn create_exchange_client(
+            &self,
+            _exchange_settings: ExchangeSettings,
+            _events_channel: broadcast::Sender<ExchangeEvent>,
+            _lifetime_manager: Arc<AppLifetimeManager>,
+        ) -> ExchangeClientBuilderResult {
+            unimplemented!("doesn't need in UT")
+        }
+
+        fn get_timeout_arguments(&self) -> RequestTimeoutArguments {
+            RequestTimeoutArguments::from_requests_per_minute(1200)
+        }
+    }
+
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    struct TestStrategySettings;
+
+    impl BaseStrategySettings for TestStrategySettings {
+        fn exchange_account_id(&self) -> ExchangeAccountId {
+            exchange_settings("Binance_0").exchange_account_id
+        }
+
+        fn currency_pair(&self) -> CurrencyPair {
+            CurrencyPair::from_codes("btc".into(), "usdt".into())
+        }
+
+        fn max_amount(&self) -> Amount {
+            Amount::ONE
+        }
+    }
+
+    fn exchange_settings(exchange_account_id: &str) -> ExchangeSettings {
+        ExchangeSettings::new_short(
+            exchange_account_id.parse().expect("in test"),
+            String::new(),
+            String::new(),
+            false,
+            false,
+        )
+    }
+
+    fn builder() -> TradingEngineBuilder<TestStrategySettings> {
+        TradingEngineBuilder::new().add_strategy(TestStrategySettings, |_, _| {
+            unimplemented!("doesn't need in UT")
+        })
+    }
+
+    fn exchange_account_ids(builder: &TradingEngineBuilder<TestStrategySettings>) -> Vec<String> {
+        let (app_settings, _) = builder.app_settings().expect("in test");
+        app_settings
+            .core
+            .exchanges
+            .iter()
+            .map(|x| x.exchange_account_id.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn accounts_of_the_same_exchange_share_client_builder() {
+        let builder = builder()
+            .add_exchange(Box::new(TestClientBuilder), exchange_settings("Binance_0"))
+            .add_exchange_account(exchange_settings("Binance_1"));
+
+        assert_eq!(exchange_account_ids(&builder), ["Binance_0", "Binance_1"]);
+        assert_eq!(builder.build_config.supported_exchange_clients.len(), 1);
+    }
+
+    #[test]
+    fn duplicate_client_builder_is_error() {
+        let builder = builder()
+            .add_exchange(Box::new(TestClientBuilder), exchange_settings("Binance_0"))
+            .add_exchange(Box::new(TestClientBuilder), exchange_settings("Binance_1"));
+
+        assert!(builder.app_settings().is_err());
+    }
+
+    #[test]
+    fn duplicate_exchange_account_is_error() {
+        let builder = builder()
+            .add_exchange(Box::new(TestClientBuilder), exchange_settings("Binance_0"))
+            .add_exchange_account(exchange_settings("Binance_0"));
+
+        assert!(builder.app_settings().is_err());
+    }
+
+    #[test]
+    fn account_without_client_builder_is_error() {
+        let builder = builder()
+            .add_exchange(Box::new(TestClientBuilder), exchange_settings("Binance_0"))
+            .add_exchange_account(exchange_settings("Serum_0"));
+
+        assert!(builder.app_settings().is_err());
+    }
+}
@@ -0,0 +1,302 @@
+use std::time::Duration;
+
+use itertools::Itertools;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::connectivity::connectivity_manager::{WebSocketRole, WebSocketStatus};
+use crate::exchanges::common::{CurrencyPair, ExchangeAccountId, MarketAccountId};
+use crate::exchanges::latency::LatencyRecord;
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::services::event_loop_watchdog::LoopState;
+use crate::services::fill_anomaly::FillAnomalyMetrics;
+use crate::services::trade_flow::TradeFlowMetrics;
+
+/// Volatility of markets is reported for the same period, so it's comparable between markets
+const STATUS_VOLATILITY_PERIOD: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WebSocketConnectionStatus {
+    pub role: WebSocketRole,
+    pub status: WebSocketStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HaltedMarket {
+    pub currency_pair: CurrencyPair,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExchangeStatus {
+    pub exchange_account_id: ExchangeAccountId,
+    pub websockets: Vec<WebSocketConnectionStatus>,
+    /// Time of websocket disconnection if websocket isn't reconnected yet
+    pub websocket_disconnected_at: Option<DateTime>,
+    pub last_websocket_message_at: Option<DateTime>,
+    /// Reasons of active blocks of exchange blocker. New orders aren't placed while it isn't empty
+    pub block_reasons: Vec<String>,
+    pub is_market_data_only: bool,
+    pub disabled_markets: Vec<CurrencyPair>,
+    pub halted_markets: Vec<HaltedMarket>,
+    pub open_orders_count: usize,
+    pub latencies: Vec<LatencyRecord>,
+    /// Average latency of some channel exceeds `latency_degradation_threshold` of core settings
+    pub is_connectivity_degraded: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategyState {
+    Running,
+    /// Strategy doesn't place orders until all reasons of pause are gone
+    Paused,
+    /// Engine is shutting down or exchange of strategy is stopped
+    Stopped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StrategyStatus {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub state: StrategyState,
+    pub reasons: Vec<String>,
+}
+
+/// Analytics of market traded by strategy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MarketAnalytics {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    /// `None` if there were no trades of market in window of trade flow
+    pub trade_flow: Option<TradeFlowMetrics>,
+    /// Standard deviation of log returns for 1 minute. `None` if there isn't enough prices yet
+    pub volatility: Option<Decimal>,
+}
+
+/// Aggregated state of engine for dashboards and health checks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EngineStatus {
+    pub is_graceful_shutdown_started: bool,
+    pub is_state_handed_over: bool,
+    pub exchanges: Vec<ExchangeStatus>,
+    pub strategies: Vec<StrategyStatus>,
+    pub markets: Vec<MarketAnalytics>,
+    /// States of main event loops with time of the latest handled event
+    pub event_loops: Vec<LoopState>,
+    /// Counters of checked fills and detected fill anomalies
+    pub fill_anomalies: FillAnomalyMetrics,
+}
+
+impl EngineStatus {
+    pub async fn collect(engine_context: &EngineContext) -> Self {
+        let exchanges = engine_context
+            .exchanges
+            .iter()
+            .map(|x| x.value().clone())
+            .sorted_by_key(|x| x.exchange_account_id.to_string())
+            .collect_vec();
+
+        let mut exchange_statuses = Vec::with_capacity(exchanges.len());
+        for exchange in exchanges {
+            let exchange_account_id = exchange.exchange_account_id;
+            let websockets = exchange
+                .websocket_statuses()
+                .await
+                .into_iter()
+                .map(|(role, status)| WebSocketConnectionStatus { role, status })
+                .collect_vec();
+            let latency_statistics = exchange.latency_statistics();
+            let is_connectivity_degraded = engine_context
+                .app_settings
+                .latency_degradation_threshold
+                .map_or(false, |threshold| {
+                    latency_statistics.is_degraded(threshold.duration())
+                });
+
+            exchange_statuses.push(ExchangeStatus {
+                exchange_account_id,
+                websockets,
+                websocket_disconnected_at: exchange.websocket_disconnected_time(),
+                last_websocket_message_at: exchange.last_websocket_message_time(),
+                block_reasons: engine_context
+                    .exchange_blocker
+                    .block_reasons(exchange_account_id)
+                    .iter()
+                    .map(|reason| reason.to_string())
+                    .collect_vec(),
+                is_market_data_only: exchange.is_market_data_only(),
+                disabled_markets: exchange.disabled_markets(),
+                halted_markets: exchange
+                    .halted_markets()
+                    .into_iter()
+                    .map(|(currency_pair, reason)| HaltedMarket {
+                        currency_pair,
+                        reason,
+                    })
+                    .sorted_by_key(|x| x.currency_pair.to_string())
+                    .collect_vec(),
+                open_orders_count: exchange.orders.not_finished.len(),
+                latencies: latency_statistics.records(),
+                is_connectivity_degraded,
+            });
+        }
+
+        let is_graceful_shutdown_started = engine_context.is_graceful_shutdown_started();
+        let strategy_markets = engine_context.strategy_markets();
+        let strategies = strategy_markets
+            .iter()
+            .copied()
+            .map(|market_account_id| {
+                let exchange = exchange_statuses
+                    .iter()
+                    .find(|x| x.exchange_account_id == market_account_id.exchange_account_id);
+                strategy_status(market_account_id, exchange, is_graceful_shutdown_started)
+            })
+            .collect_vec();
+        let markets = strategy_markets
+            .into_iter()
+            .map(|market_account_id| MarketAnalytics {
+                exchange_account_id: market_account_id.exchange_account_id,
+                currency_pair: market_account_id.currency_pair,
+                trade_flow: engine_context.trade_flow.metrics(market_account_id),
+                volatility: engine_context
+                    .volatility
+                    .volatility(market_account_id, STATUS_VOLATILITY_PERIOD),
+            })
+            .collect_vec();
+
+        Self {
+            is_graceful_shutdown_started,
+            is_state_handed_over: engine_context.is_state_handed_over(),
+            exchanges: exchange_statuses,
+            strategies,
+            markets,
+            event_loops: engine_context.event_loop_watchdog.loop_states(),
+            fill_anomalies: engine_context.fill_anomaly_detector.metrics(),
+        }
+    }
+}
+
+/// Strategy is paused by the same conditions which stop disposition executor from placing orders
+fn strategy_status(
+    market_account_id: MarketAccountId,
+    exchange: Option<&ExchangeStatus>,
+    is_graceful_shutdown_started: bool,
+) -> StrategyStatus {
+    let currency_pair = market_account_id.currency_pair;
+    let stopped = |reason: &str| StrategyStatus {
+        exchange_account_id: market_account_id.exchange_account_id,
+        currency_pair,
+        state: StrategyState::Stopped,
+        reasons: vec![reason.to_owned()],
+    };
+
+    if is_graceful_shutdown_started {
+        return stopped("graceful shutdown is started");
+    }
+
+    let exchange = match exchange {
+        Some(exchange) => exchange,
+        None => return stopped("exchange is stopped"),
+    };
+
+    let mut reasons = Vec::new();
+    if !exchange.block_reasons.is_empty() {
+        reasons.push(format!(
+            "exchange is blocked: {}",
+            exchange.block_reasons.join(", ")
+        ));
+    }
+    if exchange.is_market_data_only {
+        reasons.push("exchange is in market data only mode".to_owned());
+    }
+    if exchange.disabled_markets.contains(&currency_pair) {
+        reasons.push("market is disabled by operator".to_owned());
+    }
+    if let Some(halted) = exchange
+        .halted_markets
+        .iter()
+        .find(|x| x.currency_pair == currency_pair)
+    {
+        reasons.push(format!("trading is halted: {}", halted.reason));
+    }
+
+    StrategyStatus {
+        exchange_account_id: market_account_id.exchange_account_id,
+        currency_pair,
+        state: match reasons.is_empty() {
+            true => StrategyState::Running,
+            false => StrategyState::Paused,
+        },
+        reasons,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn market_account_id() -> MarketAccountId {
+        MarketAccountId::new(
+            "Binance_0".parse().expect("in test"),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        )
+    }
+
+    fn exchange_status() -> ExchangeStatus {
+        ExchangeStatus {
+            exchange_account_id: "Binance_0".parse().expect("in test"),
+            websockets: vec![],
+            websocket_disconnected_at: None,
+            last_websocket_message_at: None,
+            block_reasons: vec![],
+            is_market_data_only: false,
+            disabled_markets: vec![],
+            halted_markets: vec![],
+            open_orders_count: 0,
+            latencies: vec![],
+            is_connectivity_degraded: false,
+        }
+    }
+
+    #[test]
+    fn strategy_is_running_without_reasons_of_pause() {
+        let status = strategy_status(market_account_id(), Some(&exchange_status()), false);
+
+        assert_eq!(status.state, StrategyState::Running);
+        assert!(status.reasons.is_empty());
+    }
+
+    #[test]
+    fn strategy_is_paused_with_all_reasons() {
+        let market_account_id = market_account_id();
+        let mut exchange = exchange_status();
+        exchange.block_reasons = vec!["rest_rate_limit".to_owned()];
+        exchange.disabled_markets = vec![market_account_id.currency_pair];
+        exchange.halted_markets = vec![HaltedMarket {
+            currency_pair: market_account_id.currency_pair,
+            reason: "delisted".to_owned(),
+        }];
+
+        let status = strategy_status(market_account_id, Some(&exchange), false);
+
+        assert_eq!(status.state, StrategyState::Paused);
+        assert_eq!(
+            status.reasons,
+            vec![
+                "exchange is blocked: rest_rate_limit",
+                "market is disabled by operator",
+                "trading is halted: delisted",
+            ]
+        );
+    }
+
+    #[test]
+    fn strategy_is_stopped_without_exchange() {
+        let status = strategy_status(market_account_id(), None, false);
+
+        assert_eq!(status.state, StrategyState::Stopped);
+    }
+}
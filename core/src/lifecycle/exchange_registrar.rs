@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+
+use anyhow::{bail, Context, Result};
+use dashmap::DashMap;
+use mmb_utils::logger::print_info;
+use tokio::sync::{broadcast, Mutex};
+
+use crate::config_validation::{supported_exchanges, validate_added_exchange_settings};
+use crate::exchanges::block_reasons;
+use crate::exchanges::common::{ExchangeAccountId, ExchangeId};
+use crate::exchanges::events::ExchangeEvent;
+use crate::exchanges::exchange_blocker::ExchangeBlocker;
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::exchange_creation::create_exchange_with_builder;
+use crate::exchanges::internal_events_loop::InternalEventsLoop;
+use crate::exchanges::timeouts::requests_timeout_manager::RequestsTimeoutManager;
+use crate::exchanges::timeouts::requests_timeout_manager_factory::RequestsTimeoutManagerFactory;
+use crate::exchanges::timeouts::timeout_manager::TimeoutManager;
+use crate::exchanges::traits::ExchangeClientBuilder;
+use crate::lifecycle::launcher::{
+    schedule_maintenance_checking, schedule_margin_monitoring, schedule_symbols_refreshing,
+    schedule_trading_windows_checking, setup_exchanges_persistence, start_order_book_polling,
+};
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::orders::cancel_priority::CancelPriorities;
+use crate::settings::{CoreSettings, ExchangeSettings};
+
+/// Adds exchange accounts to running engine, so they are available for strategies without restart.
+/// Exchange is bootstrapped the same way as exchanges from config: symbols are built, websockets
+/// are connected and balances are requested before exchange is added to `EngineContext::exchanges`,
+/// which is shared with services like kill switch and treasury
+pub struct ExchangeRegistrar {
+    engine_context: Weak<EngineContext>,
+    exchange_client_builders: HashMap<ExchangeId, Arc<dyn ExchangeClientBuilder>>,
+    events_sender: broadcast::Sender<ExchangeEvent>,
+    internal_events_loop: Arc<InternalEventsLoop>,
+    /// Exchanges are added one by one, so the same exchange account can't be added twice
+    adding_lock: Mutex<()>,
+}
+
+impl ExchangeRegistrar {
+    pub(crate) fn new(
+        engine_context: Weak<EngineContext>,
+        exchange_client_builders: HashMap<ExchangeId, Arc<dyn ExchangeClientBuilder>>,
+        events_sender: broadcast::Sender<ExchangeEvent>,
+        internal_events_loop: Arc<InternalEventsLoop>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            engine_context,
+            exchange_client_builders,
+            events_sender,
+            internal_events_loop,
+            adding_lock: Mutex::new(()),
+        })
+    }
+
+    pub async fn add_exchange(&self, exchange_settings: ExchangeSettings) -> Result<Arc<Exchange>> {
+        let _adding_guard = self.adding_lock.lock().await;
+
+        let engine_context = self
+            .engine_context
+            .upgrade()
+            .context("Engine context is already dropped")?;
+        let exchange_account_id = exchange_settings.exchange_account_id;
+
+        if engine_context
+            .lifetime_manager
+            .stop_token()
+            .is_cancellation_requested()
+        {
+            bail!("Unable to add exchange {exchange_account_id}: engine is stopping");
+        }
+
+        if engine_context.is_state_handed_over() {
+            bail!("Unable to add exchange {exchange_account_id}: state of engine is handed over");
+        }
+
+        if engine_context.exchanges.contains_key(&exchange_account_id) {
+            bail!("Exchange {exchange_account_id} is already added");
+        }
+
+        // Services of stopped exchange can be still alive, so account is reused only after restart
+        if engine_context
+            .exchange_blocker
+            .block_reasons(exchange_account_id)
+            .contains(&block_reasons::EXCHANGE_STOPPED)
+        {
+            bail!("Exchange {exchange_account_id} was stopped, it can be added only after restart");
+        }
+
+        let supported_exchanges = supported_exchanges(&self.exchange_client_builders);
+        validate_added_exchange_settings(&exchange_settings, &supported_exchanges)?;
+        let exchange_client_builder =
+            &self.exchange_client_builders[&exchange_account_id.exchange_id];
+
+        print_info(format!("Adding of exchange {exchange_account_id} started"));
+
+        let registration = ExchangeRegistration::new(
+            &engine_context.timeout_manager,
+            &engine_context.exchange_blocker,
+            exchange_account_id,
+            RequestsTimeoutManagerFactory::from_requests_per_period(
+                exchange_client_builder.get_timeout_arguments(),
+                exchange_account_id,
+            ),
+        );
+
+        let exchange = create_exchange_with_builder(
+            &exchange_settings,
+            exchange_client_builder.as_ref(),
+            self.events_sender.clone(),
+            engine_context
+                .app_settings
+                .event_channels
+                .clone()
+                .unwrap_or_default()
+                .market_events_warning_depth,
+            engine_context.lifetime_manager.clone(),
+            engine_context.timeout_manager.clone(),
+        )
+        .await?;
+
+        // Exchange is connected already, so it's torn down if it can't be initialized
+        if let Err(err) = Self::initialize_exchange(&engine_context, &exchange).await {
+            exchange.clone().disconnect().await;
+            engine_context
+                .balance_manager
+                .lock()
+                .remove_exchange(exchange_account_id);
+            return Err(err);
+        }
+        registration.complete();
+
+        let core_settings = CoreSettings {
+            exchanges: vec![exchange_settings],
+            ..CoreSettings::default()
+        };
+        let exchanges_map = DashMap::from_iter([(exchange_account_id, exchange.clone())]);
+        setup_exchanges_persistence(
+            &exchanges_map,
+            &engine_context.scheduler,
+            &engine_context.storage,
+        )
+        .await;
+        schedule_symbols_refreshing(&core_settings, &exchanges_map, &engine_context.scheduler);
+        schedule_trading_windows_checking(
+            &core_settings,
+            &exchanges_map,
+            &engine_context.scheduler,
+        );
+        start_order_book_polling(
+            &core_settings,
+            &exchanges_map,
+            &engine_context.lifetime_manager,
+        );
+
+        self.internal_events_loop.add_exchange(exchange.clone());
+        let _ = engine_context
+            .exchanges
+            .insert(exchange_account_id, exchange.clone());
+
+        schedule_maintenance_checking(&core_settings, &engine_context);
+        schedule_margin_monitoring(&core_settings, &engine_context);
+
+        print_info(format!("Exchange {exchange_account_id} is added"));
+        Ok(exchange)
+    }
+
+    /// Sets up services of exchange and requests its balances
+    async fn initialize_exchange(
+        engine_context: &EngineContext,
+        exchange: &Arc<Exchange>,
+    ) -> Result<()> {
+        let exchange_account_id = exchange.exchange_account_id;
+        exchange.setup_balance_manager(engine_context.balance_manager.clone());
+        exchange.setup_order_filter(engine_context.order_filter.clone());
+        exchange.setup_exposure_limits(&engine_context.exposure_limits);
+        exchange.setup_cancel_priorities(CancelPriorities::new(
+            engine_context
+                .app_settings
+                .cancel_priorities
+                .as_deref()
+                .unwrap_or_default(),
+        ));
+
+        let balances_and_positions = exchange
+            .get_balance(engine_context.lifetime_manager.stop_token())
+            .await
+            .with_context(|| format!("Failed to get balance for {exchange_account_id}"))?;
+        {
+            let mut balance_manager = engine_context.balance_manager.lock();
+            balance_manager.add_exchange(exchange.clone());
+            balance_manager
+                .update_exchange_balance(exchange_account_id, &balances_and_positions)
+                .with_context(|| format!("Failed to update balance of {exchange_account_id}"))?;
+        }
+
+        // Markets have to be disabled before the exchange becomes available for strategies
+        exchange
+            .restore_disabled_markets(engine_context.storage.as_ref())
+            .await
+            .with_context(|| {
+                format!("Failed to restore disabled markets of {exchange_account_id}")
+            })?;
+
+        Ok(())
+    }
+}
+
+/// Entries of exchange account in timeout manager and exchange blocker, which are needed
+/// to create exchange. They are removed on drop if adding of exchange isn't completed,
+/// so failed adding doesn't leave entries of unknown exchange account
+struct ExchangeRegistration<'a> {
+    timeout_manager: &'a TimeoutManager,
+    exchange_blocker: &'a ExchangeBlocker,
+    exchange_account_id: ExchangeAccountId,
+    is_completed: bool,
+}
+
+impl<'a> ExchangeRegistration<'a> {
+    fn new(
+        timeout_manager: &'a TimeoutManager,
+        exchange_blocker: &'a ExchangeBlocker,
+        exchange_account_id: ExchangeAccountId,
+        requests_timeout_manager: Arc<RequestsTimeoutManager>,
+    ) -> Self {
+        timeout_manager.add_exchange(exchange_account_id, requests_timeout_manager);
+        exchange_blocker.add_exchange(exchange_account_id);
+
+        Self {
+            timeout_manager,
+            exchange_blocker,
+            exchange_account_id,
+            is_completed: false,
+        }
+    }
+
+    fn complete(mut self) {
+        self.is_completed = true;
+    }
+}
+
+impl Drop for ExchangeRegistration<'_> {
+    fn drop(&mut self) {
+        if self.is_completed {
+            return;
+        }
+
+        log::warn!(
+            "Adding of exchange {} failed, its registration is removed",
+            self.exchange_account_id
+        );
+        self.timeout_manager
+            .remove_exchange(self.exchange_account_id);
+        self.exchange_blocker
+            .remove_exchange(self.exchange_account_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::timeouts::requests_timeout_manager_factory::RequestTimeoutArguments;
+    use crate::infrastructure::init_lifetime_manager;
+
+    fn exchange_account_id() -> ExchangeAccountId {
+        "Binance_1".parse().expect("in test")
+    }
+
+    fn register<'a>(
+        timeout_manager: &'a TimeoutManager,
+        exchange_blocker: &'a ExchangeBlocker,
+    ) -> ExchangeRegistration<'a> {
+        ExchangeRegistration::new(
+            timeout_manager,
+            exchange_blocker,
+            exchange_account_id(),
+            RequestsTimeoutManagerFactory::from_requests_per_period(
+                RequestTimeoutArguments::from_requests_per_minute(1200),
+                exchange_account_id(),
+            ),
+        )
+    }
+
+    #[tokio::test]
+    async fn registration_is_removed_if_adding_failed() {
+        let _ = init_lifetime_manager();
+        let timeout_manager = TimeoutManager::new(HashMap::new());
+        let exchange_blocker = ExchangeBlocker::new(vec![]);
+
+        let registration = register(&timeout_manager, &exchange_blocker);
+        assert!(timeout_manager.contains_exchange(exchange_account_id()));
+        assert!(exchange_blocker.contains_exchange(exchange_account_id()));
+        drop(registration);
+
+        assert!(!timeout_manager.contains_exchange(exchange_account_id()));
+        assert!(!exchange_blocker.contains_exchange(exchange_account_id()));
+    }
+
+    #[tokio::test]
+    async fn registration_is_kept_if_adding_completed() {
+        let _ = init_lifetime_manager();
+        let timeout_manager = TimeoutManager::new(HashMap::new());
+        let exchange_blocker = ExchangeBlocker::new(vec![]);
+
+        register(&timeout_manager, &exchange_blocker).complete();
+
+        assert!(timeout_manager.contains_exchange(exchange_account_id()));
+        assert!(exchange_blocker.contains_exchange(exchange_account_id()));
+    }
+}
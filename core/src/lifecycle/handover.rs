@@ -0,0 +1,409 @@
+use std::future::Future;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use futures::FutureExt;
+use jsonrpc_core_client::transports::ipc;
+use jsonrpc_core_client::RpcError;
+use mmb_rpc::rest_api::{MmbRpcClient, IPC_ADDRESS};
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::logger::print_info;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::infrastructure::spawn_future;
+use crate::lifecycle::state_archive::{ImportReport, StateArchive};
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::misc::human_duration::HumanDuration;
+
+/// Operator of RPC requests to running engine in its audit log
+const HANDOVER_OPERATOR: &str = "handover";
+
+/// Takeover of open orders from running instance of engine (blue/green deployment).
+/// New engine trades in market data only mode until the running engine hands over its state
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HandoverSettings {
+    /// IPC address of control panel API of running engine
+    pub source_ipc_address: String,
+    /// Max time of every request to running engine
+    pub request_timeout: HumanDuration,
+}
+
+impl Default for HandoverSettings {
+    fn default() -> Self {
+        Self {
+            source_ipc_address: IPC_ADDRESS.to_owned(),
+            request_timeout: HumanDuration::from_secs(30),
+        }
+    }
+}
+
+/// Suspends trading on all exchanges until open orders are taken over from running engine
+pub(crate) fn suspend_trading_until_handover(engine_context: &EngineContext) {
+    for exchange in engine_context.exchanges.iter() {
+        exchange.set_awaiting_handover(true);
+    }
+}
+
+#[derive(Error, Debug)]
+enum HandoverError {
+    /// Running engine keeps or resumed trading of its orders
+    #[error("{0:?}")]
+    SourceIsTrading(anyhow::Error),
+    /// Running engine handed over its orders and didn't resume trading after failed import
+    #[error("{error:?}. Running engine didn't resume trading: {rollback_error:?}")]
+    OrdersAreNotManaged {
+        error: anyhow::Error,
+        rollback_error: anyhow::Error,
+    },
+}
+
+/// Takes over state of running engine in background. Engine is stopped by graceful shutdown
+/// if handover fails and running engine keeps trading. If running engine has handed over orders
+/// and can't resume trading, engine isn't stopped, so state can be imported manually
+pub(crate) fn spawn_handover(settings: HandoverSettings, engine_context: Arc<EngineContext>) {
+    let action = async move {
+        let source = &settings.source_ipc_address;
+        match take_over(&settings, &engine_context).await {
+            Ok(()) => {}
+            Err(HandoverError::SourceIsTrading(error)) => {
+                log::error!("Handover from engine {} failed: {:?}", source, error);
+                let _ = engine_context
+                    .lifetime_manager
+                    .spawn_graceful_shutdown("Handover failed".to_owned());
+            }
+            Err(error @ HandoverError::OrdersAreNotManaged { .. }) => log::error!(
+                "Handover from engine {} failed and its orders aren't managed by any engine: {}. Cancel handover of engine {} or import its state",
+                source,
+                error,
+                source
+            ),
+        }
+
+        Ok(())
+    };
+    let _ = spawn_future(
+        "Handover from running engine",
+        SpawnFutureFlags::STOP_BY_TOKEN,
+        action.boxed(),
+    );
+}
+
+async fn take_over(
+    settings: &HandoverSettings,
+    engine_context: &EngineContext,
+) -> Result<(), HandoverError> {
+    let source = &settings.source_ipc_address;
+    print_info(format!("Handover from engine {source} started"));
+
+    let client = request(settings, "connect", ipc::connect::<_, MmbRpcClient>(source))
+        .await
+        .map_err(HandoverError::SourceIsTrading)?;
+    take_over_from(&RpcHandoverSource { settings, client }, engine_context).await
+}
+
+/// Running engine which hands over its state
+#[async_trait]
+trait HandoverSource: Sync {
+    fn address(&self) -> &str;
+    async fn export_state(&self, hand_over: bool) -> Result<String>;
+    /// Resumes trading of running engine after `export_state(true)`
+    async fn cancel_handover(&self) -> Result<()>;
+    async fn stop(&self) -> Result<()>;
+}
+
+/// Engine which takes over state of running engine
+#[async_trait]
+trait HandoverTarget: Sync {
+    async fn import(&self, archive: StateArchive) -> Result<ImportReport>;
+    fn resume_trading(&self);
+}
+
+struct RpcHandoverSource<'a> {
+    settings: &'a HandoverSettings,
+    client: MmbRpcClient,
+}
+
+#[async_trait]
+impl HandoverSource for RpcHandoverSource<'_> {
+    fn address(&self) -> &str {
+        &self.settings.source_ipc_address
+    }
+
+    async fn export_state(&self, hand_over: bool) -> Result<String> {
+        let export_state = self
+            .client
+            .export_state(hand_over, Some(HANDOVER_OPERATOR.to_owned()));
+        request(self.settings, "export_state", export_state).await
+    }
+
+    async fn cancel_handover(&self) -> Result<()> {
+        let cancel_handover = self
+            .client
+            .cancel_handover(Some(HANDOVER_OPERATOR.to_owned()));
+        request(self.settings, "cancel_handover", cancel_handover)
+            .await
+            .map(drop)
+    }
+
+    async fn stop(&self) -> Result<()> {
+        let stop = self.client.stop(Some(HANDOVER_OPERATOR.to_owned()));
+        request(self.settings, "stop", stop).await.map(drop)
+    }
+}
+
+#[async_trait]
+impl HandoverTarget for EngineContext {
+    async fn import(&self, archive: StateArchive) -> Result<ImportReport> {
+        archive.import(self).await
+    }
+
+    fn resume_trading(&self) {
+        for exchange in self.exchanges.iter() {
+            exchange.set_awaiting_handover(false);
+        }
+    }
+}
+
+/// Trading is resumed only after state is imported, so orders of running engine which hands over
+/// them aren't placed or cancelled by both engines. Running engine resumes trading if its state
+/// isn't imported
+async fn take_over_from(
+    source: &impl HandoverSource,
+    target: &impl HandoverTarget,
+) -> Result<(), HandoverError> {
+    let address = source.address();
+
+    // Archive is checked before running engine stops trading, so engine with incompatible
+    // version of archive doesn't interrupt trading
+    check_archive(source)
+        .await
+        .map_err(HandoverError::SourceIsTrading)?;
+
+    // Running engine stops placing and cancelling orders and keeps its open orders from here
+    let report = match take_over_orders(source, target).await {
+        Ok(report) => report,
+        Err(error) => {
+            return Err(match source.cancel_handover().await {
+                Ok(()) => {
+                    HandoverError::SourceIsTrading(error.context("Running engine resumed trading"))
+                }
+                Err(rollback_error) => HandoverError::OrdersAreNotManaged {
+                    error,
+                    rollback_error,
+                },
+            });
+        }
+    };
+    for error in &report.errors {
+        log::error!("Handover: {}", error);
+    }
+
+    target.resume_trading();
+    print_info(format!(
+        "Handover from engine {address} finished: {} orders are adopted",
+        report.adopted_orders.len()
+    ));
+
+    // Trading is already taken over, so failure to stop running engine isn't fatal
+    if let Err(error) = source.stop().await {
+        log::error!("Unable to stop engine {}: {:?}", address, error);
+    }
+
+    Ok(())
+}
+
+async fn check_archive(source: &impl HandoverSource) -> Result<()> {
+    let archive = source.export_state(false).await?;
+    let _ = StateArchive::parse(&archive).context("State of running engine can't be imported")?;
+    Ok(())
+}
+
+async fn take_over_orders(
+    source: &impl HandoverSource,
+    target: &impl HandoverTarget,
+) -> Result<ImportReport> {
+    let archive = source.export_state(true).await?;
+    target
+        .import(StateArchive::parse(&archive)?)
+        .await
+        .context("State of running engine isn't imported")
+}
+
+async fn request<T>(
+    settings: &HandoverSettings,
+    name: &str,
+    future: impl Future<Output = Result<T, RpcError>>,
+) -> Result<T> {
+    tokio::time::timeout(settings.request_timeout.duration(), future)
+        .await
+        .map_err(|_| {
+            anyhow!(
+                "Request {name} to {} exceeded timeout {}",
+                settings.source_ipc_address,
+                settings.request_timeout
+            )
+        })?
+        .map_err(|error| {
+            anyhow!(
+                "Request {name} to {} failed: {error}",
+                settings.source_ipc_address
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use anyhow::bail;
+    use parking_lot::Mutex;
+
+    use super::*;
+    use crate::exchanges::timeouts::timeout_manager;
+    use crate::lifecycle::state_archive::STATE_ARCHIVE_VERSION;
+
+    struct TestSource {
+        version: u32,
+        is_cancel_handover_failed: bool,
+        requests: Mutex<Vec<String>>,
+    }
+
+    impl TestSource {
+        fn new(version: u32) -> Self {
+            Self {
+                version,
+                is_cancel_handover_failed: false,
+                requests: Mutex::new(vec![]),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl HandoverSource for TestSource {
+        fn address(&self) -> &str {
+            "test_engine"
+        }
+
+        async fn export_state(&self, hand_over: bool) -> Result<String> {
+            self.requests
+                .lock()
+                .push(format!("export_state(hand_over: {hand_over})"));
+            let archive = StateArchive {
+                version: self.version,
+                created_at: timeout_manager::now(),
+                orders: vec![],
+                positions: vec![],
+                reservations: vec![],
+                strategy_states: BTreeMap::new(),
+            };
+            Ok(serde_json::to_string(&archive)?)
+        }
+
+        async fn cancel_handover(&self) -> Result<()> {
+            self.requests.lock().push("cancel_handover".to_owned());
+            if self.is_cancel_handover_failed {
+                bail!("Cancel handover failed");
+            }
+
+            Ok(())
+        }
+
+        async fn stop(&self) -> Result<()> {
+            self.requests.lock().push("stop".to_owned());
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct TestTarget {
+        is_import_failed: bool,
+        is_trading_resumed: Mutex<bool>,
+    }
+
+    #[async_trait]
+    impl HandoverTarget for TestTarget {
+        async fn import(&self, _archive: StateArchive) -> Result<ImportReport> {
+            if self.is_import_failed {
+                bail!("Import failed");
+            }
+
+            Ok(ImportReport::default())
+        }
+
+        fn resume_trading(&self) {
+            *self.is_trading_resumed.lock() = true;
+        }
+    }
+
+    #[tokio::test]
+    async fn state_is_taken_over_and_source_is_stopped() {
+        let source = TestSource::new(STATE_ARCHIVE_VERSION);
+        let target = TestTarget::default();
+
+        take_over_from(&source, &target).await.expect("in test");
+
+        assert_eq!(
+            *source.requests.lock(),
+            [
+                "export_state(hand_over: false)",
+                "export_state(hand_over: true)",
+                "stop",
+            ]
+        );
+        assert!(*target.is_trading_resumed.lock());
+    }
+
+    #[tokio::test]
+    async fn source_keeps_trading_if_archive_is_incompatible() {
+        let source = TestSource::new(STATE_ARCHIVE_VERSION + 1);
+        let target = TestTarget::default();
+
+        let error = take_over_from(&source, &target).await.expect_err("in test");
+
+        assert!(matches!(error, HandoverError::SourceIsTrading(_)));
+        assert_eq!(*source.requests.lock(), ["export_state(hand_over: false)"]);
+        assert!(!*target.is_trading_resumed.lock());
+    }
+
+    #[tokio::test]
+    async fn source_resumes_trading_if_import_failed() {
+        let source = TestSource::new(STATE_ARCHIVE_VERSION);
+        let target = TestTarget {
+            is_import_failed: true,
+            ..TestTarget::default()
+        };
+
+        let error = take_over_from(&source, &target).await.expect_err("in test");
+
+        assert!(matches!(error, HandoverError::SourceIsTrading(_)));
+        assert_eq!(
+            *source.requests.lock(),
+            [
+                "export_state(hand_over: false)",
+                "export_state(hand_over: true)",
+                "cancel_handover",
+            ]
+        );
+        assert!(!*target.is_trading_resumed.lock());
+    }
+
+    #[tokio::test]
+    async fn orders_are_not_managed_if_source_does_not_resume_trading() {
+        let source = TestSource {
+            is_cancel_handover_failed: true,
+            ..TestSource::new(STATE_ARCHIVE_VERSION)
+        };
+        let target = TestTarget {
+            is_import_failed: true,
+            ..TestTarget::default()
+        };
+
+        let error = take_over_from(&source, &target).await.expect_err("in test");
+
+        assert!(matches!(error, HandoverError::OrdersAreNotManaged { .. }));
+        assert!(!*target.is_trading_resumed.lock());
+    }
+}
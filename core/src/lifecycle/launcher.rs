@@ -1,24 +1,52 @@
 use crate::balance_manager::balance_manager::BalanceManager;
+use crate::balance_manager::capital_allocation::CapitalAllocations;
+use crate::commission_ledger::OrderBookPriceSource;
 use crate::config::{load_pretty_settings, try_load_settings};
-use crate::exchanges::common::{ExchangeAccountId, ExchangeId};
-use crate::exchanges::events::{ExchangeEvent, ExchangeEvents, CHANNEL_MAX_EVENTS_COUNT};
+use crate::config_validation::{supported_exchanges, validate_settings, ConfigValidationError};
+use crate::exchanges::common::{ExchangeAccountId, ExchangeId, MarketAccountId};
+use crate::exchanges::events::{ExchangeEvent, ExchangeEvents};
 use crate::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
 use crate::exchanges::general::exchange::Exchange;
 use crate::exchanges::general::exchange_creation::create_exchange;
 use crate::exchanges::general::exchange_creation::create_timeout_manager;
 use crate::exchanges::internal_events_loop::InternalEventsLoop;
+use crate::exchanges::lag_aware_receiver::LagAwareReceiver;
 use crate::exchanges::timeouts::timeout_manager::TimeoutManager;
 use crate::exchanges::traits::ExchangeClientBuilder;
 use crate::infrastructure::init_lifetime_manager;
 use crate::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use crate::lifecycle::exchange_registrar::ExchangeRegistrar;
+use crate::lifecycle::handover::{spawn_handover, suspend_trading_until_handover};
+use crate::lifecycle::reconciliation::{reconcile_exchanges, ReconciliationReport};
 use crate::lifecycle::trading_engine::{EngineContext, TradingEngine};
 use crate::order_book::local_snapshot_service::LocalSnapshotsService;
+use crate::order_tracing::init_order_tracing;
+use crate::orders::cancel_priority::CancelPriorities;
+use crate::orders::client_order_id::init_client_order_id_generator;
+use crate::orders::order_filter::OrderFilter;
+use crate::rpc::config_editor::ConfigEditor;
 use crate::rpc::config_waiter::ConfigWaiter;
 use crate::rpc::core_api::CoreApi;
-use crate::settings::{AppSettings, BaseStrategySettings, CoreSettings};
+use crate::services::event_loop_watchdog::EventLoopWatchdog;
+use crate::services::exposure_limits::ExposureLimits;
+use crate::services::fill_anomaly::FillAnomalyDetector;
+use crate::services::kill_switch::KillSwitch;
+use crate::services::min_profitable_spread::MinProfitableSpreadService;
+use crate::services::order_expiry::OrderExpiryService;
+use crate::services::order_status_prober::OrderStatusProber;
+use crate::services::scheduler::{Schedule, Scheduler};
+use crate::services::stale_order_reaper::StaleOrderReaper;
+use crate::services::stats_snapshots::StatsSnapshotRecorder;
+use crate::services::trade_flow::{TradeFlowService, DEFAULT_TRADE_FLOW_WINDOW};
+use crate::services::treasury::TreasuryService;
+use crate::services::volatility::VolatilityService;
+use crate::settings::{AppSettings, BaseStrategySettings, CoreSettings, ShadowStrategySettings};
 use crate::statistic_service::StatisticEventHandler;
 use crate::statistic_service::StatisticService;
+use crate::storage::order_history::OrderHistoryRecorder;
+use crate::storage::{create_storage, Storage};
 use crate::strategies::disposition_strategy::DispositionStrategy;
+use crate::strategies::shadow::ShadowStrategyService;
 use crate::{
     disposition_execution::executor::DispositionExecutorService, infrastructure::spawn_future,
 };
@@ -26,9 +54,12 @@ use anyhow::{anyhow, Result};
 use core::fmt::Debug;
 use dashmap::DashMap;
 use futures::{future::join_all, FutureExt};
+use itertools::Itertools;
+use mmb_rpc::rest_api::IPC_ADDRESS;
 use mmb_utils::infrastructure::{init_infrastructure, SpawnFutureFlags};
 use mmb_utils::logger::print_info;
 use mmb_utils::{hashmap, nothing_to_do};
+use schemars::{schema_for, JsonSchema};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::any::Any;
@@ -42,17 +73,58 @@ use tokio::sync::{broadcast, mpsc, oneshot};
 
 use super::app_lifetime_manager::ActionAfterGracefulShutdown;
 
+const STATISTICS_NAMESPACE: &str = "statistics";
+const STATISTICS_KEY: &str = "state";
+const STATISTICS_SAVING_PERIOD: Duration = Duration::from_secs(60);
+
 pub struct EngineBuildConfig {
-    pub supported_exchange_clients: HashMap<ExchangeId, Box<dyn ExchangeClientBuilder + 'static>>,
+    pub supported_exchange_clients: HashMap<ExchangeId, Arc<dyn ExchangeClientBuilder + 'static>>,
+    /// JSON schema of strategy settings which is served to control panel for rendering of settings form
+    pub strategy_settings_schema: Option<String>,
 }
 
 impl EngineBuildConfig {
     pub fn standard(client_builder: Box<dyn ExchangeClientBuilder>) -> Self {
         let exchange_name = "Binance".into();
-        let supported_exchange_clients = hashmap![exchange_name => client_builder];
+        let supported_exchange_clients = hashmap![exchange_name => Arc::from(client_builder)];
 
         EngineBuildConfig {
             supported_exchange_clients,
+            strategy_settings_schema: None,
+        }
+    }
+
+    pub fn with_strategy_settings_schema<StrategySettings: JsonSchema>(mut self) -> Self {
+        let schema = schema_for!(StrategySettings);
+        self.strategy_settings_schema = Some(
+            serde_json::to_string_pretty(&schema)
+                .expect("Unable serialize strategy settings schema"),
+        );
+        self
+    }
+
+    fn validate_settings<StrategySettings>(
+        &self,
+        settings: &AppSettings<StrategySettings>,
+    ) -> Result<(), ConfigValidationError>
+    where
+        StrategySettings: BaseStrategySettings + Clone,
+    {
+        let supported_exchanges = supported_exchanges(&self.supported_exchange_clients);
+        validate_settings(settings, &supported_exchanges)
+    }
+}
+
+/// Optional parts of engine which can be disabled when engine is embedded into another application
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct EngineOptions {
+    pub is_control_panel_enabled: bool,
+}
+
+impl Default for EngineOptions {
+    fn default() -> Self {
+        Self {
+            is_control_panel_enabled: true,
         }
     }
 }
@@ -70,6 +142,7 @@ where
 }
 
 pub async fn load_settings_or_wait<StrategySettings>(
+    build_settings: &EngineBuildConfig,
     config_path: &str,
     credentials_path: &str,
 ) -> Option<AppSettings<StrategySettings>>
@@ -78,7 +151,8 @@ where
 {
     let (wait_config_tx, mut wait_config_rx) = mpsc::channel::<()>(10);
 
-    let wait_for_config = ConfigWaiter::create_and_start(wait_config_tx)
+    let config_editor = ConfigEditor::new::<StrategySettings>(build_settings);
+    let wait_for_config = ConfigWaiter::create_and_start(wait_config_tx, config_editor)
         .expect("Failed to start RPC server to waiting for config");
 
     let mut work_finished_receiver = wait_for_config
@@ -139,14 +213,35 @@ where
             config_path,
             credentials_path,
         } => {
-            match load_settings_or_wait::<StrategySettings>(&config_path, &credentials_path).await {
+            match load_settings_or_wait::<StrategySettings>(
+                build_settings,
+                &config_path,
+                &credentials_path,
+            )
+            .await
+            {
                 Some(settings) => settings,
                 None => return Ok(None),
             }
         }
     };
 
-    let (events_sender, events_receiver) = broadcast::channel(CHANNEL_MAX_EVENTS_COUNT);
+    if let Err(error) = build_settings.validate_settings(&settings) {
+        log::error!("{}", error);
+        return Err(error.into());
+    }
+
+    init_client_order_id_generator(settings.core.client_order_id.as_ref());
+
+    if let Some(tracing_settings) = &settings.core.tracing {
+        if let Err(error) = init_order_tracing(tracing_settings) {
+            log::warn!("Order tracing is disabled: {:?}", error);
+        }
+    }
+
+    let event_channels = settings.core.event_channels.clone().unwrap_or_default();
+    let (events_sender, events_receiver) =
+        broadcast::channel(event_channels.exchange_events_capacity);
 
     let timeout_manager = create_timeout_manager(&settings.core, &build_settings);
 
@@ -157,7 +252,7 @@ where
         lifetime_manager.clone(),
         &timeout_manager,
     )
-    .await;
+    .await?;
 
     let exchanges_map: DashMap<_, _> = exchanges
         .into_iter()
@@ -172,6 +267,12 @@ where
     let currency_pair_to_symbol_converter = CurrencyPairToSymbolConverter::new(exchanges_hashmap);
 
     let balance_manager = BalanceManager::new(currency_pair_to_symbol_converter);
+    // Services get the same map as engine context, so they see exchanges added at runtime
+    let shared_exchanges = Arc::new(exchanges_map.clone());
+    balance_manager.lock().set_capital_allocations(
+        CapitalAllocations::new(settings.core.capital_allocations.iter().flatten())
+            .with_price_source(OrderBookPriceSource::new(shared_exchanges.clone())),
+    );
 
     BalanceManager::update_balances_for_exchanges(
         balance_manager.clone(),
@@ -179,22 +280,120 @@ where
     )
     .await;
 
+    let order_filter = Arc::new(OrderFilter::new(settings.core.order_filter_script.clone())?);
+    let scheduler = Scheduler::new(lifetime_manager.stop_token());
+    let storage = create_storage(settings.core.storage.as_ref()).await?;
+    let trade_flow = TradeFlowService::new(
+        settings
+            .core
+            .trade_flow_window
+            .map_or(DEFAULT_TRADE_FLOW_WINDOW, |x| x.duration()),
+        LagAwareReceiver::new("Trade flow service", &events_sender),
+    );
+    let volatility = VolatilityService::new(
+        settings.core.volatility.clone().unwrap_or_default(),
+        LagAwareReceiver::new("Volatility service", &events_sender),
+    );
+    if let Err(error) = volatility.restore(storage.as_ref()).await {
+        log::warn!("Unable to restore volatility from storage: {:?}", error);
+    }
+    volatility.schedule_saving(&scheduler, storage.clone());
+    let statistics = StatisticService::new();
+    let _ = OrderExpiryService::new(
+        shared_exchanges.clone(),
+        scheduler.clone(),
+        LagAwareReceiver::new("Order expiry service", &events_sender),
+    );
+    let order_status_prober = OrderStatusProber::new(
+        shared_exchanges.clone(),
+        scheduler.clone(),
+        LagAwareReceiver::new("Order status prober", &events_sender),
+    );
+    if let Some(reaper_settings) = &settings.core.stale_order_reaper {
+        let _ = StaleOrderReaper::new(
+            reaper_settings.clone(),
+            shared_exchanges.clone(),
+            scheduler.clone(),
+        );
+    }
+    let exposure_limits = ExposureLimits::new(
+        settings.core.exposure_limits.clone().unwrap_or_default(),
+        shared_exchanges.clone(),
+        balance_manager.clone(),
+        scheduler.clone(),
+        LagAwareReceiver::new("Exposure limits", &events_sender),
+    );
+    let fill_anomaly_detector = FillAnomalyDetector::new(
+        settings.core.fill_anomaly.clone().unwrap_or_default(),
+        events_sender.clone(),
+        LagAwareReceiver::new("Fill anomaly detector", &events_sender),
+    );
+    let event_loop_watchdog = EventLoopWatchdog::new(
+        settings
+            .core
+            .event_loop_watchdog
+            .clone()
+            .unwrap_or_default(),
+        events_sender.clone(),
+        lifetime_manager.clone(),
+    );
+    let min_profitable_spread = MinProfitableSpreadService::new(
+        settings
+            .core
+            .min_profitable_spread
+            .clone()
+            .unwrap_or_default(),
+    );
+    setup_exchanges_persistence(&exchanges_map, &scheduler, &storage).await;
+    restore_disabled_markets(&exchanges_map, &storage).await;
+    schedule_symbols_refreshing(&settings.core, &exchanges_map, &scheduler);
+    schedule_trading_windows_checking(&settings.core, &exchanges_map, &scheduler);
+    start_order_book_polling(&settings.core, &exchanges_map, &lifetime_manager);
+
+    let cancel_priorities = CancelPriorities::new(
+        settings
+            .core
+            .cancel_priorities
+            .as_deref()
+            .unwrap_or_default(),
+    );
     for exchange in &exchanges_map {
         exchange
             .value()
-            .setup_balance_manager(balance_manager.clone())
+            .setup_balance_manager(balance_manager.clone());
+        exchange.value().setup_order_filter(order_filter.clone());
+        exchange.value().setup_exposure_limits(&exposure_limits);
+        exchange
+            .value()
+            .setup_cancel_priorities(cancel_priorities.clone());
     }
 
     let (finish_graceful_shutdown_tx, finish_graceful_shutdown_rx) = oneshot::channel();
     let engine_context = EngineContext::new(
         settings.core.clone(),
-        exchanges_map.clone(),
+        shared_exchanges,
         exchange_events,
         finish_graceful_shutdown_tx,
         timeout_manager,
         lifetime_manager.clone(),
         balance_manager,
+        order_filter,
+        exposure_limits,
+        scheduler,
+        storage,
+        trade_flow,
+        volatility,
+        statistics,
+        order_status_prober,
+        fill_anomaly_detector,
+        event_loop_watchdog,
+        min_profitable_spread,
     );
+    schedule_maintenance_checking(&settings.core, &engine_context);
+    schedule_margin_monitoring(&settings.core, &engine_context);
+    if settings.core.handover.is_some() {
+        suspend_trading_until_handover(&engine_context);
+    }
 
     Ok(Some((
         events_sender,
@@ -218,6 +417,9 @@ fn run_services<'a, StrategySettings>(
         Arc<EngineContext>,
     ) -> Box<dyn DispositionStrategy + 'static>,
     finish_graceful_shutdown_rx: oneshot::Receiver<ActionAfterGracefulShutdown>,
+    config_editor: ConfigEditor,
+    exchange_client_builders: HashMap<ExchangeId, Arc<dyn ExchangeClientBuilder>>,
+    options: EngineOptions,
 ) -> TradingEngine
 where
     StrategySettings: BaseStrategySettings + Clone + Debug + Deserialize<'a> + Serialize,
@@ -226,20 +428,75 @@ where
     engine_context
         .shutdown_service
         .register_core_service(internal_events_loop.clone());
+    let exchange_registrar = ExchangeRegistrar::new(
+        Arc::downgrade(&engine_context),
+        exchange_client_builders,
+        events_sender.clone(),
+        internal_events_loop.clone(),
+    );
 
     let exchange_events = ExchangeEvents::new(events_sender.clone());
-    let statistic_service = StatisticService::new();
+    let statistic_service = engine_context.statistics.clone();
+    if let Some(reference_currency_code) = settings.core.commission_reference_currency_code {
+        statistic_service.setup_commission_conversion(
+            reference_currency_code,
+            OrderBookPriceSource::new(engine_context.exchanges.clone()),
+        );
+    }
+    let _ = OrderHistoryRecorder::new(
+        exchange_events.get_events_channel(),
+        engine_context.storage.clone(),
+    );
     let statistic_event_handler =
         create_statistic_event_handler(exchange_events, statistic_service.clone());
-    let control_panel = CoreApi::create_and_start(
+    schedule_statistics_saving(&engine_context, statistic_service.clone());
+    if let Some(stats_snapshots_settings) = &settings.core.stats_snapshots {
+        let _ = StatsSnapshotRecorder::new(
+            stats_snapshots_settings.clone(),
+            engine_context.exchanges.clone(),
+            engine_context.balance_manager.clone(),
+            statistic_service.clone(),
+            engine_context.scheduler.clone(),
+        );
+    }
+    let treasury = TreasuryService::new(
+        engine_context.exchanges.clone(),
+        settings.core.treasury.clone(),
         engine_context.lifetime_manager.clone(),
-        load_pretty_settings(init_user_settings),
-        statistic_service,
-    )
-    .expect("Unable to start control panel");
-    engine_context
-        .shutdown_service
-        .register_core_service(control_panel.clone());
+    );
+    let kill_switch = KillSwitch::new(
+        engine_context.exchanges.clone(),
+        engine_context.exchange_blocker.clone(),
+        engine_context.lifetime_manager.clone(),
+    );
+    let shadow_strategy = settings
+        .shadow_strategy
+        .as_ref()
+        .map(|x| start_shadow_strategy(x, &settings, &build_strategy, &engine_context));
+
+    if options.is_control_panel_enabled {
+        let control_panel = CoreApi::create_and_start(
+            engine_context.lifetime_manager.clone(),
+            load_pretty_settings(init_user_settings),
+            config_editor,
+            statistic_service,
+            treasury,
+            engine_context.order_filter.clone(),
+            kill_switch,
+            Arc::downgrade(&engine_context),
+            exchange_registrar,
+            shadow_strategy,
+            settings
+                .core
+                .rpc_ipc_address
+                .as_deref()
+                .unwrap_or(IPC_ADDRESS),
+        )
+        .expect("Unable to start control panel");
+        engine_context
+            .shutdown_service
+            .register_core_service(control_panel.clone());
+    }
 
     {
         let local_exchanges_map = exchanges_map.into_iter().map(identity).collect();
@@ -247,6 +504,9 @@ where
             events_receiver,
             local_exchanges_map,
             engine_context.lifetime_manager.stop_token(),
+            engine_context
+                .event_loop_watchdog
+                .register("Internal events loop"),
         );
         let _ = spawn_future(
             "internal_events_loop start",
@@ -266,10 +526,232 @@ where
         .shutdown_service
         .register_user_service(disposition_executor_service);
 
+    if let Some(handover_settings) = &settings.core.handover {
+        spawn_handover(handover_settings.clone(), engine_context.clone());
+    }
+    spawn_traffic_replay(&settings.core, &engine_context);
+
     log::info!("TradingEngine started");
     TradingEngine::new(engine_context.clone(), finish_graceful_shutdown_rx)
 }
 
+fn start_shadow_strategy<StrategySettings>(
+    shadow_settings: &ShadowStrategySettings<StrategySettings>,
+    settings: &AppSettings<StrategySettings>,
+    build_strategy: &impl Fn(
+        &AppSettings<StrategySettings>,
+        Arc<EngineContext>,
+    ) -> Box<dyn DispositionStrategy + 'static>,
+    engine_context: &Arc<EngineContext>,
+) -> Arc<ShadowStrategyService>
+where
+    StrategySettings: BaseStrategySettings + Clone,
+{
+    let candidate_settings = AppSettings {
+        strategy: shadow_settings.strategy.clone(),
+        core: settings.core.clone(),
+        shadow_strategy: None,
+    };
+    let strategy = build_strategy(&candidate_settings, engine_context.clone());
+    let market_account_id = MarketAccountId::new(
+        shadow_settings.strategy.exchange_account_id(),
+        shadow_settings.strategy.currency_pair(),
+    );
+
+    ShadowStrategyService::start(
+        &shadow_settings.name,
+        engine_context,
+        market_account_id,
+        strategy,
+        &shadow_settings.execution_models,
+    )
+}
+
+/// Recorded websocket traffic is passed to exchanges with `traffic_replay_path` after strategy
+/// is started, so incident is reproduced through the same handlers and strategy as in live run
+fn spawn_traffic_replay(core_settings: &CoreSettings, engine_context: &Arc<EngineContext>) {
+    for exchange_settings in &core_settings.exchanges {
+        let replay_path = match &exchange_settings.traffic_replay_path {
+            Some(replay_path) => replay_path.clone(),
+            None => continue,
+        };
+        let exchange = match engine_context
+            .exchanges
+            .get(&exchange_settings.exchange_account_id)
+        {
+            Some(exchange) => exchange.value().clone(),
+            None => continue,
+        };
+
+        let action = async move {
+            let exchange_account_id = exchange.exchange_account_id;
+            log::info!("Replay of traffic of {exchange_account_id} from {replay_path} started");
+            match exchange.replay_websocket_traffic(&replay_path).await {
+                Ok(()) => log::info!("Replay of traffic of {exchange_account_id} is finished"),
+                Err(error) => log::error!(
+                    "Replay of traffic of {} from {} failed: {:?}",
+                    exchange_account_id,
+                    replay_path,
+                    error
+                ),
+            }
+
+            Ok(())
+        };
+        let _ = spawn_future(
+            "Replay of recorded traffic",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action.boxed(),
+        );
+    }
+}
+
+pub(crate) fn schedule_symbols_refreshing(
+    core_settings: &CoreSettings,
+    exchanges_map: &DashMap<ExchangeAccountId, Arc<Exchange>>,
+    scheduler: &Arc<Scheduler>,
+) {
+    for exchange_settings in &core_settings.exchanges {
+        let period = match exchange_settings.symbols_refresh_period {
+            Some(period) => period.duration(),
+            None => continue,
+        };
+
+        if let Some(exchange) = exchanges_map.get(&exchange_settings.exchange_account_id) {
+            exchange.schedule_symbols_refreshing(scheduler, period);
+        }
+    }
+}
+
+/// Trade ids of fills are restored, so fills of previous run received by gap-fill queries aren't applied again
+/// and saved as soon as they are received
+pub(crate) async fn setup_exchanges_persistence(
+    exchanges_map: &DashMap<ExchangeAccountId, Arc<Exchange>>,
+    scheduler: &Arc<Scheduler>,
+    storage: &Arc<dyn Storage>,
+) {
+    let exchanges = exchanges_map
+        .iter()
+        .map(|exchange| exchange.value().clone())
+        .collect_vec();
+
+    for exchange in exchanges {
+        if let Err(error) = exchange.restore_received_trades(storage.as_ref()).await {
+            log::warn!(
+                "Unable to restore received trades of {}: {:?}",
+                exchange.exchange_account_id,
+                error
+            );
+        }
+        exchange.spawn_received_trades_saving(storage.clone());
+
+        if let Err(error) = exchange.restore_order_ids(storage.as_ref()).await {
+            log::warn!(
+                "Unable to restore order ids of {}: {:?}",
+                exchange.exchange_account_id,
+                error
+            );
+        }
+        exchange.schedule_order_ids_saving(scheduler, storage.clone());
+    }
+}
+
+/// Markets disabled by RPC stay disabled after restart
+pub(crate) async fn restore_disabled_markets(
+    exchanges_map: &DashMap<ExchangeAccountId, Arc<Exchange>>,
+    storage: &Arc<dyn Storage>,
+) {
+    let exchanges = exchanges_map
+        .iter()
+        .map(|exchange| exchange.value().clone())
+        .collect_vec();
+
+    for exchange in exchanges {
+        if let Err(error) = exchange.restore_disabled_markets(storage.as_ref()).await {
+            log::warn!(
+                "Unable to restore disabled markets of {}: {:?}",
+                exchange.exchange_account_id,
+                error
+            );
+        }
+    }
+}
+
+pub(crate) fn schedule_trading_windows_checking(
+    core_settings: &CoreSettings,
+    exchanges_map: &DashMap<ExchangeAccountId, Arc<Exchange>>,
+    scheduler: &Arc<Scheduler>,
+) {
+    for exchange_settings in &core_settings.exchanges {
+        if exchange_settings.trading_windows.is_none() {
+            continue;
+        }
+
+        if let Some(exchange) = exchanges_map.get(&exchange_settings.exchange_account_id) {
+            exchange.schedule_trading_windows_checking(scheduler);
+        }
+    }
+}
+
+pub(crate) fn start_order_book_polling(
+    core_settings: &CoreSettings,
+    exchanges_map: &DashMap<ExchangeAccountId, Arc<Exchange>>,
+    lifetime_manager: &AppLifetimeManager,
+) {
+    for exchange_settings in &core_settings.exchanges {
+        let polling_settings = match &exchange_settings.order_book_polling {
+            Some(polling_settings) => polling_settings.clone(),
+            None => continue,
+        };
+
+        if let Some(exchange) = exchanges_map.get(&exchange_settings.exchange_account_id) {
+            exchange.start_order_book_polling(polling_settings, lifetime_manager.stop_token());
+        }
+    }
+}
+
+pub(crate) fn schedule_maintenance_checking(
+    core_settings: &CoreSettings,
+    engine_context: &EngineContext,
+) {
+    for exchange_settings in &core_settings.exchanges {
+        let period = match exchange_settings.maintenance_check_period {
+            Some(period) => period.duration(),
+            None => continue,
+        };
+
+        if let Some(exchange) = engine_context
+            .exchanges
+            .get(&exchange_settings.exchange_account_id)
+        {
+            exchange.schedule_maintenance_checking(
+                &engine_context.scheduler,
+                engine_context.exchange_blocker.clone(),
+                period,
+            );
+        }
+    }
+}
+
+pub(crate) fn schedule_margin_monitoring(
+    core_settings: &CoreSettings,
+    engine_context: &EngineContext,
+) {
+    for exchange_settings in &core_settings.exchanges {
+        let margin_monitoring = match &exchange_settings.margin_monitoring {
+            Some(margin_monitoring) => margin_monitoring.clone(),
+            None => continue,
+        };
+
+        if let Some(exchange) = engine_context
+            .exchanges
+            .get(&exchange_settings.exchange_account_id)
+        {
+            exchange.schedule_margin_monitoring(&engine_context.scheduler, margin_monitoring);
+        }
+    }
+}
+
 pub(crate) fn unwrap_or_handle_panic<T>(
     action_outcome: Result<T, Box<dyn Any + Send>>,
     message_template: &str,
@@ -293,6 +775,27 @@ pub async fn launch_trading_engine<StrategySettings>(
         Arc<EngineContext>,
     ) -> Box<dyn DispositionStrategy + 'static>,
 ) -> Result<Option<TradingEngine>>
+where
+    StrategySettings: BaseStrategySettings + Clone + Debug + DeserializeOwned + Serialize,
+{
+    launch_trading_engine_with_options(
+        build_settings,
+        init_user_settings,
+        build_strategy,
+        EngineOptions::default(),
+    )
+    .await
+}
+
+pub(crate) async fn launch_trading_engine_with_options<StrategySettings>(
+    build_settings: &EngineBuildConfig,
+    init_user_settings: InitSettings<StrategySettings>,
+    build_strategy: impl Fn(
+        &AppSettings<StrategySettings>,
+        Arc<EngineContext>,
+    ) -> Box<dyn DispositionStrategy + 'static>,
+    options: EngineOptions,
+) -> Result<Option<TradingEngine>>
 where
     StrategySettings: BaseStrategySettings + Clone + Debug + DeserializeOwned + Serialize,
 {
@@ -333,6 +836,7 @@ where
         action.boxed(),
     );
 
+    let config_editor = ConfigEditor::new::<StrategySettings>(build_settings);
     let action_outcome = panic::catch_unwind(AssertUnwindSafe(|| {
         run_services(
             engine_context.clone(),
@@ -343,6 +847,9 @@ where
             init_user_settings,
             build_strategy,
             finish_graceful_shutdown_rx,
+            config_editor,
+            build_settings.supported_exchange_clients.clone(),
+            options,
         )
     }));
 
@@ -359,6 +866,62 @@ where
     result
 }
 
+/// Recover-only mode: connects to exchanges, prints state of accounts (open orders, positions, balances)
+/// and shuts down without starting strategy and cancelling orders.
+/// Returns `None` if graceful shutdown was requested during launch
+pub async fn launch_recover_only<StrategySettings>(
+    build_settings: &EngineBuildConfig,
+    init_user_settings: InitSettings<StrategySettings>,
+) -> Result<Option<ReconciliationReport>>
+where
+    StrategySettings: BaseStrategySettings + Clone + Debug + DeserializeOwned + Serialize,
+{
+    print_info("The TradingEngine is going to start in recover-only mode...");
+    let action_outcome = AssertUnwindSafe(before_engine_context_init(
+        build_settings,
+        init_user_settings,
+    ))
+    .catch_unwind()
+    .await;
+
+    let message_template = "Panic happened during EngineContext initialization";
+    let (_, _, _, _, engine_context, finish_graceful_shutdown_rx) =
+        match unwrap_or_handle_panic(action_outcome, message_template, None)?? {
+            Some(result_tuple) => result_tuple,
+            None => return Ok(None),
+        };
+
+    let report = reconcile_exchanges(&engine_context).await;
+    report.print();
+
+    engine_context.shutdown_keeping_orders().await;
+    let _ = finish_graceful_shutdown_rx.await;
+
+    Ok(Some(report))
+}
+
+/// Loads and validates settings without starting of engine. Diagnostics are printed for every found problem
+pub fn check_config<StrategySettings>(
+    build_settings: &EngineBuildConfig,
+    init_user_settings: InitSettings<StrategySettings>,
+) -> Result<()>
+where
+    StrategySettings: BaseStrategySettings + Clone + Debug + DeserializeOwned,
+{
+    let settings = match init_user_settings {
+        InitSettings::Directly(settings) => settings,
+        InitSettings::Load {
+            config_path,
+            credentials_path,
+        } => try_load_settings::<StrategySettings>(&config_path, &credentials_path)?,
+    };
+
+    build_settings.validate_settings(&settings)?;
+    print_info("Settings are valid");
+
+    Ok(())
+}
+
 fn create_disposition_executor_service(
     base_settings: &dyn BaseStrategySettings,
     engine_context: &Arc<EngineContext>,
@@ -374,9 +937,40 @@ fn create_disposition_executor_service(
         disposition_strategy,
         engine_context.lifetime_manager.stop_token(),
         statistics.clone(),
+        base_settings.order_randomization(),
     )
 }
 
+fn schedule_statistics_saving(
+    engine_context: &EngineContext,
+    statistic_service: Arc<StatisticService>,
+) {
+    let storage = engine_context.storage.clone();
+    let save_statistics = move |_| {
+        let storage = storage.clone();
+        let statistic_service = statistic_service.clone();
+        async move {
+            if let Err(error) = storage
+                .put_serialized(
+                    STATISTICS_NAMESPACE,
+                    STATISTICS_KEY,
+                    &statistic_service.statistic_service_state,
+                )
+                .await
+            {
+                log::warn!("Unable to save statistics to storage: {:?}", error);
+            }
+        }
+        .boxed()
+    };
+
+    let _ = engine_context.scheduler.schedule(
+        "Statistics saving",
+        Schedule::Every(STATISTICS_SAVING_PERIOD),
+        save_statistics,
+    );
+}
+
 fn create_statistic_event_handler(
     events: ExchangeEvents,
     statistic_service: Arc<StatisticService>,
@@ -390,15 +984,23 @@ pub async fn create_exchanges(
     events_channel: broadcast::Sender<ExchangeEvent>,
     lifetime_manager: Arc<AppLifetimeManager>,
     timeout_manager: &Arc<TimeoutManager>,
-) -> Vec<Arc<Exchange>> {
+) -> Result<Vec<Arc<Exchange>>> {
+    let market_events_warning_depth = core_settings
+        .event_channels
+        .clone()
+        .unwrap_or_default()
+        .market_events_warning_depth;
     join_all(core_settings.exchanges.iter().map(|x| {
         create_exchange(
             x,
             build_settings,
             events_channel.clone(),
+            market_events_warning_depth,
             lifetime_manager.clone(),
             timeout_manager.clone(),
         )
     }))
     .await
+    .into_iter()
+    .collect()
 }
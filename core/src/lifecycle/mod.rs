@@ -1,4 +1,10 @@
 pub mod app_lifetime_manager;
+pub mod engine_builder;
+pub mod engine_status;
+pub mod exchange_registrar;
+pub mod handover;
 pub mod launcher;
+pub mod reconciliation;
 pub mod shutdown;
+pub mod state_archive;
 pub mod trading_engine;
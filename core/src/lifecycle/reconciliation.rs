@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use futures::future::join_all;
+use itertools::Itertools;
+use mmb_utils::logger::print_info;
+
+use crate::exchanges::common::{Amount, CurrencyCode, ExchangeAccountId};
+use crate::exchanges::general::exchange::Exchange;
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::misc::derivative_position::DerivativePosition;
+use crate::orders::order::OrderInfo;
+
+/// State of exchange account which is left after previous run of engine
+#[derive(Debug, Clone)]
+pub struct ExchangeReconciliation {
+    pub exchange_account_id: ExchangeAccountId,
+    pub open_orders: Vec<OrderInfo>,
+    pub positions: Vec<DerivativePosition>,
+    pub balances: HashMap<CurrencyCode, Amount>,
+    /// Errors of requests, so report can be incomplete if it isn't empty
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReconciliationReport {
+    pub exchanges: Vec<ExchangeReconciliation>,
+}
+
+impl ReconciliationReport {
+    pub fn print(&self) {
+        print_info("Reconciliation report:");
+        for exchange in &self.exchanges {
+            print_info(format!("Exchange account {}", exchange.exchange_account_id));
+
+            print_info(format!("  Open orders: {}", exchange.open_orders.len()));
+            for order in &exchange.open_orders {
+                print_info(format!(
+                    "    {} {} {:?} {} @ {} (filled {}, exchange order id {})",
+                    order.client_order_id,
+                    order.currency_pair,
+                    order.order_side,
+                    order.amount,
+                    order.price,
+                    order.filled_amount,
+                    order.exchange_order_id,
+                ));
+            }
+
+            print_info(format!("  Positions: {}", exchange.positions.len()));
+            for position in &exchange.positions {
+                print_info(format!(
+                    "    {} {} {:?} entry price {} liquidation price {} leverage {}",
+                    position.currency_pair,
+                    position.position,
+                    position.side,
+                    position.average_entry_price,
+                    position.liquidation_price,
+                    position.leverage,
+                ));
+            }
+
+            print_info("  Balances:");
+            for (currency_code, amount) in exchange.balances.iter().sorted_by_key(|x| x.0.as_str())
+            {
+                print_info(format!("    {} {}", currency_code, amount));
+            }
+
+            for error in &exchange.errors {
+                print_info(format!("  Error: {}", error));
+            }
+        }
+    }
+}
+
+/// Requests open orders, positions and balances of all exchanges of engine.
+/// Nothing is changed on exchanges, so it's safe to inspect account state after crash
+pub async fn reconcile_exchanges(engine_context: &EngineContext) -> ReconciliationReport {
+    let balances = engine_context
+        .balance_manager
+        .lock()
+        .get_balances()
+        .balances_by_exchange_id
+        .unwrap_or_default();
+
+    let exchanges = engine_context
+        .exchanges
+        .iter()
+        .map(|exchange| exchange.value().clone())
+        .collect_vec();
+
+    let exchanges = join_all(exchanges.into_iter().map(|exchange| {
+        let balances = balances
+            .get(&exchange.exchange_account_id)
+            .cloned()
+            .unwrap_or_default();
+        reconcile_exchange(exchange, balances)
+    }))
+    .await;
+
+    ReconciliationReport { exchanges }
+}
+
+async fn reconcile_exchange(
+    exchange: Arc<Exchange>,
+    balances: HashMap<CurrencyCode, Amount>,
+) -> ExchangeReconciliation {
+    let mut errors = Vec::new();
+
+    let open_orders = exchange
+        .get_open_orders(false)
+        .await
+        .unwrap_or_else(|error| {
+            errors.push(format!("Unable to get open orders: {:?}", error));
+            Vec::new()
+        });
+
+    let positions = exchange
+        .get_active_positions_by_features()
+        .await
+        .map(|positions| {
+            positions
+                .into_iter()
+                .map(|position| position.derivative)
+                .filter(|position| !position.position.is_zero())
+                .collect_vec()
+        })
+        .unwrap_or_else(|error| {
+            errors.push(format!("Unable to get positions: {:?}", error));
+            Vec::new()
+        });
+
+    ExchangeReconciliation {
+        exchange_account_id: exchange.exchange_account_id,
+        open_orders,
+        positions,
+        balances,
+        errors,
+    }
+}
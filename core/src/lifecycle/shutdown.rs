@@ -8,6 +8,8 @@ use parking_lot::Mutex;
 use std::sync::Arc;
 use tokio::time::{sleep, Duration};
 
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+
 /// User side - for services that may be optional depending on the user's preference
 /// Core side - for the core services that provide the TradingEngine to work
 #[derive(Clone, Copy)]
@@ -38,9 +40,16 @@ impl State {
     }
 }
 
-#[derive(Default)]
 pub struct ShutdownService {
     state: Mutex<State>,
+    /// Max time of waiting for services to finish their work
+    timeout: Duration,
+}
+
+impl Default for ShutdownService {
+    fn default() -> Self {
+        Self::new(DEFAULT_TIMEOUT)
+    }
 }
 
 fn service_has_been_registered_msg(name: &str, side: &str) -> String {
@@ -48,6 +57,13 @@ fn service_has_been_registered_msg(name: &str, side: &str) -> String {
 }
 
 impl ShutdownService {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            state: Default::default(),
+            timeout,
+        }
+    }
+
     pub fn register_user_service(self: &Arc<Self>, service: Arc<dyn Service>) {
         print_info(service_has_been_registered_msg(service.name(), "user"));
         self.state.lock().user_services.push(service);
@@ -129,10 +145,9 @@ impl ShutdownService {
             })
             .collect_vec();
 
-        const TIMEOUT: Duration = Duration::from_secs(3);
         tokio::select! {
             _ = join_all(finishing_services_futures) =>log::trace!("All services sent finished marker at given time"),
-            _ = sleep(TIMEOUT) =>log::error!("Not all services finished after timeout ({} sec)", TIMEOUT.as_secs()),
+            _ = sleep(self.timeout) =>log::error!("Not all services finished after timeout ({} ms)", self.timeout.as_millis()),
         }
 
         log::trace!("Prepare to drop services in ShutdownService finished");
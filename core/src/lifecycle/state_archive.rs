@@ -0,0 +1,373 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use itertools::Itertools;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::balance_manager::balance_manager::BalanceManager;
+use crate::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId, MarketAccountId, Price};
+use crate::exchanges::timeouts::timeout_manager;
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::misc::reserve_parameters::ReserveParameters;
+use crate::orders::order::{ClientOrderId, OrderSide, OrderSnapshot, ReservationId};
+use crate::service_configuration::configuration_descriptor::{
+    ConfigurationDescriptor, ServiceConfigurationKey, ServiceName,
+};
+
+/// Version of format of state archive. Archives of other versions aren't imported
+pub const STATE_ARCHIVE_VERSION: u32 = 1;
+
+/// Namespace of storage in which strategies keep their state by strategy name.
+/// Values of namespace are moved to another host within state archive
+pub const STRATEGY_STATE_NAMESPACE: &str = "strategy_state";
+
+/// Position by filled amount of derivative market in amount currency
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionState {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub position: Decimal,
+}
+
+/// Part of reservation which is approved for open order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApprovedPartState {
+    pub client_order_id: ClientOrderId,
+    pub amount: Amount,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReservationState {
+    /// Id of reservation in exported engine. Reservation gets new id on import
+    pub reservation_id: ReservationId,
+    pub service_name: ServiceName,
+    pub service_configuration_key: ServiceConfigurationKey,
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub order_side: OrderSide,
+    pub price: Price,
+    /// Amount which is still reserved in amount currency
+    pub amount: Amount,
+    pub approved_parts: Vec<ApprovedPartState>,
+}
+
+/// State of engine which is needed to continue trading on another host: open orders, positions
+/// of derivatives, balance reservations and state of strategies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateArchive {
+    pub version: u32,
+    pub created_at: DateTime,
+    pub orders: Vec<OrderSnapshot>,
+    pub positions: Vec<PositionState>,
+    pub reservations: Vec<ReservationState>,
+    /// Values of `STRATEGY_STATE_NAMESPACE` by strategy name
+    pub strategy_states: BTreeMap<String, Value>,
+}
+
+/// Items of archive which can't be imported are skipped with error, so the rest of state is
+/// imported anyway
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub adopted_orders: Vec<ClientOrderId>,
+    pub restored_positions: usize,
+    pub restored_reservations: usize,
+    pub restored_strategy_states: usize,
+    pub errors: Vec<String>,
+}
+
+impl ImportReport {
+    fn add_error(&mut self, item: String, error: anyhow::Error) {
+        log::warn!("Unable to import {} from state archive: {:?}", item, error);
+        self.errors.push(format!("{item}: {error:#}"));
+    }
+}
+
+#[derive(Deserialize)]
+struct ArchiveVersion {
+    version: u32,
+}
+
+impl StateArchive {
+    /// Collects state of engine. Only not finished orders are exported
+    pub async fn export(engine_context: &EngineContext) -> Result<Self> {
+        let orders = engine_context
+            .exchanges
+            .iter()
+            .flat_map(|exchange| {
+                exchange
+                    .orders
+                    .not_finished
+                    .iter()
+                    .map(|order| order.deep_clone())
+                    .collect_vec()
+            })
+            .sorted_by_key(|order| order.header.init_time)
+            .collect_vec();
+
+        let balances = engine_context.balance_manager.lock().get_balances();
+
+        let positions = balances
+            .position_by_fill_amount
+            .iter()
+            .flat_map(|x| x.positions())
+            .filter(|(market_account_id, position)| {
+                !position.is_zero() && is_derivative(engine_context, market_account_id)
+            })
+            .map(|(market_account_id, position)| PositionState {
+                exchange_account_id: market_account_id.exchange_account_id,
+                currency_pair: market_account_id.currency_pair,
+                position: *position,
+            })
+            .collect_vec();
+
+        let reservations = balances
+            .balance_reservations_by_reservation_id
+            .iter()
+            .flatten()
+            .filter(|(_, reservation)| !reservation.unreserved_amount.is_zero())
+            .map(|(reservation_id, reservation)| ReservationState {
+                reservation_id: *reservation_id,
+                service_name: reservation.configuration_descriptor.service_name,
+                service_configuration_key: reservation
+                    .configuration_descriptor
+                    .service_configuration_key,
+                exchange_account_id: reservation.exchange_account_id,
+                currency_pair: reservation.symbol.currency_pair(),
+                order_side: reservation.order_side,
+                price: reservation.price,
+                amount: reservation.unreserved_amount,
+                approved_parts: reservation
+                    .approved_parts
+                    .iter()
+                    .filter(|(_, part)| !part.is_canceled && !part.unreserved_amount.is_zero())
+                    .map(|(client_order_id, part)| ApprovedPartState {
+                        client_order_id: client_order_id.clone(),
+                        amount: part.unreserved_amount,
+                    })
+                    .collect_vec(),
+            })
+            .sorted_by_key(|reservation| reservation.reservation_id)
+            .collect_vec();
+
+        let storage = &engine_context.storage;
+        let mut strategy_states = BTreeMap::new();
+        for strategy_name in storage.keys(STRATEGY_STATE_NAMESPACE).await? {
+            if let Some(state) = storage
+                .get(STRATEGY_STATE_NAMESPACE, &strategy_name)
+                .await?
+            {
+                let _ = strategy_states.insert(strategy_name, state);
+            }
+        }
+
+        Ok(Self {
+            version: STATE_ARCHIVE_VERSION,
+            created_at: timeout_manager::now(),
+            orders,
+            positions,
+            reservations,
+            strategy_states,
+        })
+    }
+
+    /// Fails if archive is created by engine with other version of archive format
+    pub fn parse(archive: &str) -> Result<Self> {
+        let version = serde_json::from_str::<ArchiveVersion>(archive)
+            .context("Unable to parse version of state archive")?
+            .version;
+        if version != STATE_ARCHIVE_VERSION {
+            bail!(
+                "Unsupported version {version} of state archive, expected {STATE_ARCHIVE_VERSION}"
+            );
+        }
+
+        serde_json::from_str(archive).context("Unable to parse state archive")
+    }
+
+    /// Restores state in running engine: saves state of strategies to storage, restores positions,
+    /// reserves balance for reservations and adopts open orders
+    pub async fn import(self, engine_context: &EngineContext) -> Result<ImportReport> {
+        if engine_context.is_state_handed_over() {
+            bail!("State of engine is handed over to another engine, so it can't import state");
+        }
+
+        let mut report = ImportReport::default();
+
+        for (strategy_name, state) in self.strategy_states {
+            match engine_context
+                .storage
+                .put(STRATEGY_STATE_NAMESPACE, &strategy_name, state)
+                .await
+            {
+                Ok(()) => report.restored_strategy_states += 1,
+                Err(error) => report.add_error(format!("State of strategy {strategy_name}"), error),
+            }
+        }
+
+        let mut balance_manager = engine_context.balance_manager.lock();
+
+        for position in &self.positions {
+            match balance_manager.restore_position(
+                position.exchange_account_id,
+                position.currency_pair,
+                position.position,
+            ) {
+                Ok(()) => report.restored_positions += 1,
+                Err(error) => report.add_error(
+                    format!(
+                        "Position of {} on {}",
+                        position.currency_pair, position.exchange_account_id
+                    ),
+                    error,
+                ),
+            }
+        }
+
+        let mut reservation_ids = HashMap::new();
+        for reservation in &self.reservations {
+            match reserve(engine_context, &mut balance_manager, reservation) {
+                Ok(reservation_id) => {
+                    let _ = reservation_ids.insert(reservation.reservation_id, reservation_id);
+                    report.restored_reservations += 1;
+                }
+                Err(error) => {
+                    report.add_error(format!("Reservation {}", reservation.reservation_id), error)
+                }
+            }
+        }
+
+        let mut adopted_orders = HashSet::new();
+        for mut order in self.orders {
+            let client_order_id = order.header.client_order_id.clone();
+            if let Some(reservation_id) = order.header.reservation_id {
+                let mut header = (*order.header).clone();
+                header.reservation_id = reservation_ids.get(&reservation_id).copied();
+                order.header = Arc::new(header);
+            }
+
+            let exchange_account_id = order.header.exchange_account_id;
+            let result = engine_context
+                .exchanges
+                .get(&exchange_account_id)
+                .with_context(|| format!("Exchange {exchange_account_id} isn't found"))
+                .and_then(|exchange| exchange.adopt_order(order));
+            match result {
+                Ok(_) => {
+                    let _ = adopted_orders.insert(client_order_id.clone());
+                    report.adopted_orders.push(client_order_id);
+                }
+                Err(error) => report.add_error(format!("Order {client_order_id}"), error),
+            }
+        }
+
+        for reservation in &self.reservations {
+            let reservation_id = match reservation_ids.get(&reservation.reservation_id) {
+                Some(reservation_id) => *reservation_id,
+                None => continue,
+            };
+
+            for part in &reservation.approved_parts {
+                if adopted_orders.contains(&part.client_order_id) {
+                    balance_manager.approve_reservation(
+                        reservation_id,
+                        &part.client_order_id,
+                        part.amount,
+                    );
+                }
+            }
+        }
+
+        log::info!(
+            "State archive created at {} is imported: {} orders, {} positions, {} reservations, {} strategy states, {} errors",
+            self.created_at,
+            report.adopted_orders.len(),
+            report.restored_positions,
+            report.restored_reservations,
+            report.restored_strategy_states,
+            report.errors.len()
+        );
+
+        Ok(report)
+    }
+}
+
+fn is_derivative(engine_context: &EngineContext, market_account_id: &MarketAccountId) -> bool {
+    engine_context
+        .exchanges
+        .get(&market_account_id.exchange_account_id)
+        .and_then(|exchange| {
+            exchange
+                .symbols
+                .get(&market_account_id.currency_pair)
+                .map(|symbol| symbol.is_derivative)
+        })
+        .unwrap_or(false)
+}
+
+fn reserve(
+    engine_context: &EngineContext,
+    balance_manager: &mut BalanceManager,
+    reservation: &ReservationState,
+) -> Result<ReservationId> {
+    let symbol = engine_context
+        .exchanges
+        .get(&reservation.exchange_account_id)
+        .with_context(|| format!("Exchange {} isn't found", reservation.exchange_account_id))?
+        .get_symbol(reservation.currency_pair)?;
+
+    let reserve_parameters = ReserveParameters::new(
+        ConfigurationDescriptor::new(
+            reservation.service_name,
+            reservation.service_configuration_key,
+        ),
+        reservation.exchange_account_id,
+        symbol,
+        reservation.order_side,
+        reservation.price,
+        reservation.amount,
+    );
+
+    Ok(balance_manager.try_reserve_checked(&reserve_parameters, &mut None)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_archive(version: u32) -> String {
+        serde_json::to_string(&StateArchive {
+            version,
+            created_at: timeout_manager::now(),
+            orders: vec![],
+            positions: vec![PositionState {
+                exchange_account_id: "Binance_0".parse().expect("in test"),
+                currency_pair: CurrencyPair::from_codes("btc".into(), "usdt".into()),
+                position: Decimal::ONE,
+            }],
+            reservations: vec![],
+            strategy_states: BTreeMap::from([("strategy".to_owned(), Value::from(1))]),
+        })
+        .expect("in test")
+    }
+
+    #[test]
+    fn archive_of_current_version_is_parsed() {
+        let archive = StateArchive::parse(&empty_archive(STATE_ARCHIVE_VERSION)).expect("in test");
+
+        assert_eq!(archive.positions.len(), 1);
+        assert_eq!(archive.positions[0].position, Decimal::ONE);
+        assert_eq!(archive.strategy_states["strategy"], Value::from(1));
+    }
+
+    #[test]
+    fn archive_of_other_version_is_rejected() {
+        let error =
+            StateArchive::parse(&empty_archive(STATE_ARCHIVE_VERSION + 1)).expect_err("in test");
+
+        assert!(error.to_string().contains("Unsupported version"));
+    }
+}
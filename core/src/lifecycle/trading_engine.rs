@@ -1,30 +1,44 @@
 use futures::FutureExt;
 use mmb_utils::logger::print_info;
 use mmb_utils::send_expected::SendExpected;
+use std::collections::HashSet;
 use std::panic;
 use std::panic::AssertUnwindSafe;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use dashmap::DashMap;
 use futures::future::join_all;
 use itertools::Itertools;
 use mmb_utils::cancellation_token::CancellationToken;
 use tokio::sync::{broadcast, oneshot};
-use tokio::time::Duration;
 
 use crate::balance_manager::balance_manager::BalanceManager;
 use crate::exchanges::block_reasons;
-use crate::exchanges::common::ExchangeAccountId;
+use crate::exchanges::common::{ExchangeAccountId, MarketAccountId};
 use crate::exchanges::events::{ExchangeEvent, ExchangeEvents};
 use crate::exchanges::exchange_blocker::BlockType;
 use crate::exchanges::exchange_blocker::ExchangeBlocker;
 use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::lag_aware_receiver::ExchangeEventsReceiver;
 use crate::exchanges::timeouts::timeout_manager::TimeoutManager;
+use crate::exchanges::transport::TrafficRecorder;
 use crate::lifecycle::shutdown::ShutdownService;
+use crate::order_tracing::shutdown_order_tracing;
+use crate::orders::order_filter::OrderFilter;
+use crate::services::event_loop_watchdog::EventLoopWatchdog;
+use crate::services::exposure_limits::ExposureLimits;
+use crate::services::fill_anomaly::FillAnomalyDetector;
+use crate::services::min_profitable_spread::MinProfitableSpreadService;
+use crate::services::order_status_prober::OrderStatusProber;
+use crate::services::scheduler::Scheduler;
+use crate::services::trade_flow::TradeFlowService;
+use crate::services::volatility::VolatilityService;
 use crate::settings::CoreSettings;
+use crate::statistic_service::StatisticService;
+use crate::storage::Storage;
 use crate::{
     infrastructure::unset_lifetime_manager, lifecycle::app_lifetime_manager::AppLifetimeManager,
 };
@@ -41,13 +55,29 @@ pub trait Service: Send + Sync + 'static {
 
 pub struct EngineContext {
     pub app_settings: CoreSettings,
-    pub exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    /// Shared with services, so exchanges added at runtime are visible to them
+    pub exchanges: Arc<DashMap<ExchangeAccountId, Arc<Exchange>>>,
     pub shutdown_service: Arc<ShutdownService>,
     pub exchange_blocker: Arc<ExchangeBlocker>,
     pub lifetime_manager: Arc<AppLifetimeManager>,
     pub timeout_manager: Arc<TimeoutManager>,
     pub balance_manager: Arc<Mutex<BalanceManager>>,
+    pub order_filter: Arc<OrderFilter>,
+    pub exposure_limits: Arc<ExposureLimits>,
+    pub scheduler: Arc<Scheduler>,
+    pub storage: Arc<dyn Storage>,
+    pub trade_flow: Arc<TradeFlowService>,
+    pub volatility: Arc<VolatilityService>,
+    pub statistics: Arc<StatisticService>,
+    pub order_status_prober: Arc<OrderStatusProber>,
+    pub fill_anomaly_detector: Arc<FillAnomalyDetector>,
+    pub event_loop_watchdog: Arc<EventLoopWatchdog>,
+    pub min_profitable_spread: Arc<MinProfitableSpreadService>,
     is_graceful_shutdown_started: AtomicBool,
+    /// Open orders are managed by another instance of engine, so they aren't cancelled on shutdown
+    is_state_handed_over: AtomicBool,
+    /// Markets traded by disposition executors of strategies
+    strategy_markets: Mutex<Vec<MarketAccountId>>,
     exchange_events: ExchangeEvents,
     finish_graceful_shutdown_sender: Mutex<Option<oneshot::Sender<ActionAfterGracefulShutdown>>>,
 }
@@ -55,12 +85,23 @@ pub struct EngineContext {
 impl EngineContext {
     pub(crate) fn new(
         app_settings: CoreSettings,
-        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        exchanges: Arc<DashMap<ExchangeAccountId, Arc<Exchange>>>,
         exchange_events: ExchangeEvents,
         finish_graceful_shutdown_sender: oneshot::Sender<ActionAfterGracefulShutdown>,
         timeout_manager: Arc<TimeoutManager>,
         lifetime_manager: Arc<AppLifetimeManager>,
         balance_manager: Arc<Mutex<BalanceManager>>,
+        order_filter: Arc<OrderFilter>,
+        exposure_limits: Arc<ExposureLimits>,
+        scheduler: Arc<Scheduler>,
+        storage: Arc<dyn Storage>,
+        trade_flow: Arc<TradeFlowService>,
+        volatility: Arc<VolatilityService>,
+        statistics: Arc<StatisticService>,
+        order_status_prober: Arc<OrderStatusProber>,
+        fill_anomaly_detector: Arc<FillAnomalyDetector>,
+        event_loop_watchdog: Arc<EventLoopWatchdog>,
+        min_profitable_spread: Arc<MinProfitableSpreadService>,
     ) -> Arc<Self> {
         let exchange_account_ids = app_settings
             .exchanges
@@ -68,15 +109,30 @@ impl EngineContext {
             .map(|x| x.exchange_account_id)
             .collect_vec();
 
+        let services_shutdown_timeout = app_settings.timeouts.services_shutdown.duration();
+
         let engine_context = Arc::new(EngineContext {
             app_settings,
             exchanges,
-            shutdown_service: Default::default(),
+            shutdown_service: Arc::new(ShutdownService::new(services_shutdown_timeout)),
             exchange_blocker: ExchangeBlocker::new(exchange_account_ids),
             lifetime_manager: lifetime_manager.clone(),
             timeout_manager,
             balance_manager,
+            order_filter,
+            exposure_limits,
+            scheduler,
+            storage,
+            trade_flow,
+            volatility,
+            statistics,
+            order_status_prober,
+            fill_anomaly_detector,
+            event_loop_watchdog,
+            min_profitable_spread,
             is_graceful_shutdown_started: Default::default(),
+            is_state_handed_over: Default::default(),
+            strategy_markets: Default::default(),
             exchange_events,
             finish_graceful_shutdown_sender: Mutex::new(Some(finish_graceful_shutdown_sender)),
         });
@@ -90,6 +146,26 @@ impl EngineContext {
         self: Arc<Self>,
         action: ActionAfterGracefulShutdown,
         futures_cancellation_token: CancellationToken,
+    ) {
+        self.shutdown(action, futures_cancellation_token, true)
+            .await
+    }
+
+    /// Shutdown of engine which leaves open orders on exchanges untouched
+    pub(crate) async fn shutdown_keeping_orders(self: Arc<Self>) {
+        self.shutdown(
+            ActionAfterGracefulShutdown::Nothing,
+            CancellationToken::default(),
+            false,
+        )
+        .await
+    }
+
+    async fn shutdown(
+        self: Arc<Self>,
+        action: ActionAfterGracefulShutdown,
+        futures_cancellation_token: CancellationToken,
+        cancel_orders: bool,
     ) {
         if self
             .is_graceful_shutdown_started
@@ -114,17 +190,19 @@ impl EngineContext {
         self.shutdown_service.user_lvl_shutdown().await;
         self.exchange_blocker.stop_blocker().await;
 
-        let cancellation_token = CancellationToken::default();
-        const TIMEOUT: Duration = Duration::from_secs(5);
-
-        tokio::select! {
-            _ = cancel_opened_orders(&self.exchanges, cancellation_token.clone(), true) => (),
-            _ = tokio::time::sleep(TIMEOUT) => {
-                cancellation_token.cancel();
-                log::error!(
-                    "Timeout {} secs is exceeded: cancel open orders has been stopped",
-                    TIMEOUT.as_secs(),
-                );
+        if cancel_orders && !self.is_state_handed_over() {
+            let cancellation_token = CancellationToken::default();
+            let timeout = self.app_settings.timeouts.cancel_orders;
+
+            tokio::select! {
+                _ = cancel_opened_orders(&self.exchanges, cancellation_token.clone(), true) => (),
+                _ = tokio::time::sleep(timeout.duration()) => {
+                    cancellation_token.cancel();
+                    log::error!(
+                        "Timeout {} is exceeded: cancel open orders has been stopped",
+                        timeout,
+                    );
+                }
             }
         }
 
@@ -136,6 +214,27 @@ impl EngineContext {
             .map(|exchange| exchange.clone().disconnect());
         join_all(disconnect_websockets).await;
 
+        // Fills of cancelled orders can be received after saving task is stopped
+        let save_received_trades = self
+            .exchanges
+            .iter()
+            .map(|exchange| exchange.clone())
+            .collect_vec();
+        for exchange in save_received_trades {
+            exchange.save_received_trades(self.storage.as_ref()).await;
+        }
+
+        if let Err(error) = tokio::task::spawn_blocking(TrafficRecorder::flush_all).await {
+            log::error!("Unable to flush recorded traffic: {:?}", error);
+        }
+
+        // Tracing is used again by restarted engine
+        if !matches!(action, ActionAfterGracefulShutdown::Restart) {
+            if let Err(error) = tokio::task::spawn_blocking(shutdown_order_tracing).await {
+                log::error!("Unable to export remaining order traces: {:?}", error);
+            }
+        }
+
         self.finish_graceful_shutdown_sender
             .lock()
             .take()
@@ -154,6 +253,145 @@ impl EngineContext {
     pub fn get_events_channel(&self) -> broadcast::Receiver<ExchangeEvent> {
         self.exchange_events.get_events_channel()
     }
+
+    /// Events receiver which reports events dropped because of lagging behind events channel.
+    /// `name` of consumer is used in logs
+    pub fn get_lag_aware_events_receiver(&self, name: &str) -> ExchangeEventsReceiver {
+        self.exchange_events.get_lag_aware_receiver(name)
+    }
+
+    /// Stops trading of engine after its state is exported to another instance of engine:
+    /// all exchanges are blocked for new orders, open orders aren't cancelled anymore
+    /// (by strategies, services or graceful shutdown) and they are kept open
+    pub fn hand_over_state(&self) {
+        if self.is_state_handed_over.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        print_info("State of engine is handed over, trading is stopped");
+        self.exchanges.iter().for_each(|x| {
+            x.set_state_handed_over();
+            self.exchange_blocker.block(
+                x.exchange_account_id,
+                block_reasons::STATE_HANDED_OVER,
+                BlockType::Manual,
+            )
+        });
+    }
+
+    /// Resumes trading of engine if another engine failed to take over its state.
+    /// Returns `false` if state isn't handed over
+    pub fn cancel_handover(&self) -> bool {
+        if !self.is_state_handed_over.swap(false, Ordering::SeqCst) {
+            return false;
+        }
+
+        print_info("Handover of engine state is cancelled, trading is resumed");
+        self.exchanges.iter().for_each(|x| {
+            x.reset_state_handed_over();
+            self.exchange_blocker
+                .unblock(x.exchange_account_id, block_reasons::STATE_HANDED_OVER)
+        });
+        true
+    }
+
+    pub fn is_state_handed_over(&self) -> bool {
+        self.is_state_handed_over.load(Ordering::SeqCst)
+    }
+
+    pub fn is_graceful_shutdown_started(&self) -> bool {
+        self.is_graceful_shutdown_started.load(Ordering::SeqCst)
+    }
+
+    /// Market can be traded by one strategy only, otherwise strategies cancel and
+    /// replace orders of each other
+    pub(crate) fn register_strategy_market(
+        &self,
+        market_account_id: MarketAccountId,
+    ) -> Result<()> {
+        let mut strategy_markets = self.strategy_markets.lock();
+        if strategy_markets.contains(&market_account_id) {
+            bail!("Market {market_account_id} is already traded by another strategy");
+        }
+
+        strategy_markets.push(market_account_id);
+        Ok(())
+    }
+
+    /// Market is released when strategy which trades it is stopped
+    pub(crate) fn unregister_strategy_market(&self, market_account_id: MarketAccountId) {
+        self.strategy_markets
+            .lock()
+            .retain(|x| *x != market_account_id);
+    }
+
+    pub fn strategy_markets(&self) -> Vec<MarketAccountId> {
+        self.strategy_markets.lock().clone()
+    }
+
+    /// open orders are cancelled, websockets are disconnected and reservations of finished orders
+    /// are released.
+    /// open orders are cancelled, websockets are disconnected and reservations are released.
+    /// Exchange is removed from `exchanges`, so it's unavailable for strategies until restart of engine
+    pub async fn stop_exchange(&self, exchange_account_id: ExchangeAccountId) -> Result<()> {
+        if self.is_graceful_shutdown_started() {
+            bail!("Unable to stop exchange {exchange_account_id}: graceful shutdown is started");
+        }
+
+        let (_, exchange) = self
+            .exchanges
+            .remove(&exchange_account_id)
+            .with_context(|| format!("Exchange {exchange_account_id} isn't found"))?;
+
+        print_info(format!(
+            "Stopping of exchange {exchange_account_id} started"
+        ));
+
+        self.exchange_blocker.block(
+            exchange_account_id,
+            block_reasons::EXCHANGE_STOPPED,
+            BlockType::Manual,
+        );
+
+        let cancellation_token = CancellationToken::default();
+        let timeout = self.app_settings.timeouts.cancel_orders;
+        tokio::select! {
+            _ = exchange.clone().cancel_opened_orders(cancellation_token.clone(), true) => (),
+            _ = tokio::time::sleep(timeout.duration()) => {
+                cancellation_token.cancel();
+                log::error!(
+                    "Timeout {} is exceeded: cancel open orders of {} has been stopped",
+                    timeout,
+                    exchange_account_id
+                );
+            }
+        }
+
+        exchange.clone().disconnect().await;
+
+        // Orders which are left open by cancel priorities or aren't cancelled because of timeout
+        // or errors can still be filled, so their reservations are kept
+        let not_finished_orders: HashSet<_> = exchange
+            .orders
+            .not_finished
+            .iter()
+            .map(|x| x.key().clone())
+            .collect();
+        if !not_finished_orders.is_empty() {
+            log::warn!(
+                "Reservations of not finished orders {} of {exchange_account_id} are kept",
+                not_finished_orders.iter().join(", ")
+            );
+        }
+
+        self.balance_manager
+            .lock()
+            .unreserve_by_exchange_account_id(exchange_account_id, &not_finished_orders)
+            .with_context(|| format!("Unable to release reservations of {exchange_account_id}"))?;
+
+        print_info(format!("Exchange {exchange_account_id} is stopped"));
+        Ok(())
+    }
 }
 
 async fn cancel_opened_orders(
@@ -163,10 +401,22 @@ async fn cancel_opened_orders(
 ) {
     log::info!("Canceling opened orders started");
 
-    join_all(exchanges.iter().map(|x| {
-        x.clone()
-            .cancel_opened_orders(cancellation_token.clone(), add_missing_open_orders)
-    }))
+    let (handed_over, managed): (Vec<_>, Vec<_>) = exchanges
+        .iter()
+        .map(|x| x.value().clone())
+        .partition(|x| x.are_orders_handed_over());
+    for exchange in handed_over {
+        log::info!(
+            "Open orders of {} aren't cancelled because they are handed over",
+            exchange.exchange_account_id
+        );
+    }
+
+    join_all(
+        managed
+            .into_iter()
+            .map(|x| x.cancel_opened_orders(cancellation_token.clone(), add_missing_open_orders)),
+    )
     .await;
 
     log::info!("Canceling opened orders finished");
@@ -192,6 +442,43 @@ impl TradingEngine {
         self.context.clone()
     }
 
+    pub fn exchange(&self, exchange_account_id: ExchangeAccountId) -> Option<Arc<Exchange>> {
+        self.context
+            .exchanges
+            .get(&exchange_account_id)
+            .map(|exchange| exchange.value().clone())
+    }
+
+    pub fn exchanges(&self) -> Vec<Arc<Exchange>> {
+        self.context
+            .exchanges
+            .iter()
+            .map(|exchange| exchange.value().clone())
+            .collect_vec()
+    }
+
+    pub fn balance_manager(&self) -> Arc<Mutex<BalanceManager>> {
+        self.context.balance_manager.clone()
+    }
+
+    /// Subscription to events of all exchanges of engine
+    pub fn events(&self) -> broadcast::Receiver<ExchangeEvent> {
+        self.context.get_events_channel()
+    }
+
+    /// Stops one exchange account without stopping of engine (see `EngineContext::stop_exchange`)
+    pub async fn stop_exchange(&self, exchange_account_id: ExchangeAccountId) -> Result<()> {
+        self.context.stop_exchange(exchange_account_id).await
+    }
+
+    /// Starts graceful shutdown. Completion can be awaited by `run`
+    pub fn stop(&self, reason: impl Into<String>) {
+        let _ = self
+            .context
+            .lifetime_manager
+            .spawn_graceful_shutdown(reason.into());
+    }
+
     pub async fn run(self) -> ActionAfterGracefulShutdown {
         let action_outcome = AssertUnwindSafe(self.finished_graceful_shutdown)
             .catch_unwind()
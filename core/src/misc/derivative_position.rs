@@ -1,5 +1,6 @@
-use crate::exchanges::common::{CurrencyPair, Price};
-use crate::orders::order::OrderSide;
+use crate::exchanges::common::{Amount, CurrencyPair, Price};
+use crate::exchanges::general::symbol::Symbol;
+use crate::orders::order::{OrderSide, PositionSide};
 
 use rust_decimal::Decimal;
 
@@ -8,6 +9,8 @@ pub struct DerivativePosition {
     pub currency_pair: CurrencyPair,
     pub position: Decimal,
     pub side: Option<OrderSide>,
+    /// Position side on accounts in hedge mode
+    pub position_side: Option<PositionSide>,
     pub average_entry_price: Price,
     pub liquidation_price: Price,
     pub leverage: Decimal,
@@ -18,6 +21,7 @@ impl DerivativePosition {
         currency_pair: CurrencyPair,
         position: Decimal,
         side: Option<OrderSide>,
+        position_side: Option<PositionSide>,
         average_entry_price: Price,
         liquidation_price: Price,
         leverage: Decimal,
@@ -26,9 +30,20 @@ impl DerivativePosition {
             currency_pair,
             position,
             side,
+            position_side,
             average_entry_price,
             liquidation_price,
             leverage,
         }
     }
+
+    /// Profit and loss in settlement currency if position is closed at `mark_price`.
+    /// `None` if entry price isn't known
+    pub fn get_unrealized_pnl(&self, symbol: &Symbol, mark_price: Price) -> Option<Amount> {
+        if self.average_entry_price.is_zero() || mark_price.is_zero() {
+            return None;
+        }
+
+        Some(symbol.get_position_pnl(self.position, self.average_entry_price, mark_price))
+    }
 }
@@ -0,0 +1,198 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+const UNITS: [(&str, u64); 5] = [
+    ("d", 24 * 60 * 60 * 1000),
+    ("h", 60 * 60 * 1000),
+    ("m", 60 * 1000),
+    ("s", 1000),
+    ("ms", 1),
+];
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Unable to parse duration '{text}': {reason}")]
+pub struct HumanDurationParseError {
+    pub text: String,
+    pub reason: String,
+}
+
+/// Duration in settings written in human-readable form, e.g. `500ms`, `5s`, `2m`, `1h30m`.
+/// Supported units are `d`, `h`, `m`, `s` and `ms`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HumanDuration(Duration);
+
+impl HumanDuration {
+    pub const fn from_secs(secs: u64) -> Self {
+        Self(Duration::from_secs(secs))
+    }
+
+    pub const fn from_millis(millis: u64) -> Self {
+        Self(Duration::from_millis(millis))
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+}
+
+impl From<Duration> for HumanDuration {
+    fn from(duration: Duration) -> Self {
+        Self(duration)
+    }
+}
+
+impl From<HumanDuration> for Duration {
+    fn from(duration: HumanDuration) -> Self {
+        duration.0
+    }
+}
+
+impl FromStr for HumanDuration {
+    type Err = HumanDurationParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = |reason: &str| HumanDurationParseError {
+            text: text.to_owned(),
+            reason: reason.to_owned(),
+        };
+
+        let mut rest = text.trim();
+        if rest.is_empty() {
+            return Err(error("duration is empty"));
+        }
+
+        let mut millis: u64 = 0;
+        while !rest.is_empty() {
+            let number_len = rest
+                .find(|x: char| !x.is_ascii_digit())
+                .ok_or_else(|| error("unit is missing after number"))?;
+            if number_len == 0 {
+                return Err(error("number is expected before unit"));
+            }
+            let number: u64 = rest[..number_len]
+                .parse()
+                .map_err(|_| error("number is too big"))?;
+            rest = &rest[number_len..];
+
+            let unit_len = rest
+                .find(|x: char| x.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let unit_millis = UNITS
+                .iter()
+                .find(|(unit, _)| *unit == &rest[..unit_len])
+                .map(|(_, unit_millis)| *unit_millis)
+                .ok_or_else(|| error("unknown unit, expected one of d, h, m, s, ms"))?;
+            rest = &rest[unit_len..];
+
+            millis = number
+                .checked_mul(unit_millis)
+                .and_then(|x| x.checked_add(millis))
+                .ok_or_else(|| error("duration is too long"))?;
+        }
+
+        Ok(Self::from_millis(millis))
+    }
+}
+
+/// Duration is written with the largest units, e.g. `1h30m` instead of `90m`
+impl Display for HumanDuration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut millis = self.0.as_millis() as u64;
+        if millis == 0 {
+            return write!(f, "0s");
+        }
+
+        for (unit, unit_millis) in UNITS {
+            if millis >= unit_millis {
+                write!(f, "{}{}", millis / unit_millis, unit)?;
+                millis %= unit_millis;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct HumanDurationVisitor;
+
+impl<'de> Visitor<'de> for HumanDurationVisitor {
+    type Value = HumanDuration;
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "duration as a string, e.g. '5s' or '2m'")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        v.parse().map_err(de::Error::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for HumanDuration {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(HumanDurationVisitor)
+    }
+}
+
+impl Serialize for HumanDuration {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_are_parsed_and_displayed() {
+        for (text, millis, displayed) in [
+            ("500ms", 500, "500ms"),
+            ("5s", 5_000, "5s"),
+            ("2m", 120_000, "2m"),
+            ("90m", 5_400_000, "1h30m"),
+            ("1h30m", 5_400_000, "1h30m"),
+            ("1d", 86_400_000, "1d"),
+            ("1s500ms", 1_500, "1s500ms"),
+            ("0s", 0, "0s"),
+        ] {
+            let duration: HumanDuration = text.parse().expect("in test");
+            assert_eq!(duration, HumanDuration::from_millis(millis), "{}", text);
+            assert_eq!(duration.to_string(), displayed);
+        }
+
+        for text in ["", "5", "s", "5x", "-5s", "1.5s"] {
+            assert!(text.parse::<HumanDuration>().is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn duration_is_deserialized_from_string() {
+        #[derive(Deserialize)]
+        struct Settings {
+            timeout: HumanDuration,
+        }
+
+        let settings: Settings = toml_edit::de::from_str("timeout = \"2m\"").expect("in test");
+        assert_eq!(settings.timeout.duration(), Duration::from_secs(120));
+
+        assert!(toml_edit::de::from_str::<Settings>("timeout = \"2 minutes\"").is_err());
+    }
+}
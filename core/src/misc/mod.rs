@@ -1,4 +1,5 @@
 pub mod derivative_position;
+pub mod human_duration;
 pub(crate) mod position_helper;
 pub mod price_by_order_side;
 pub(crate) mod price_source_model;
@@ -6,3 +7,4 @@ pub mod reserve_parameters;
 pub(crate) mod service_value_tree;
 pub(crate) mod time;
 pub mod traits;
+pub mod virtual_clock;
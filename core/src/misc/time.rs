@@ -10,9 +10,9 @@ pub(crate) mod time_manager {
 
     use mmb_utils::DateTime;
 
-    /// Return current date in UTC
+    /// Return current date in UTC or virtual time if it's set
     pub(crate) fn now() -> DateTime {
-        chrono::Utc::now()
+        crate::misc::virtual_clock::now().unwrap_or_else(chrono::Utc::now)
     }
 }
 
@@ -0,0 +1,26 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use chrono::{TimeZone, Utc};
+use mmb_utils::DateTime;
+
+/// Nanoseconds since epoch of virtual time or `NOT_SET`
+static VIRTUAL_TIME_NANOS: AtomicI64 = AtomicI64::new(NOT_SET);
+const NOT_SET: i64 = i64::MIN;
+
+/// Current time of engine is replaced by virtual time while it's set, e.g. during replay of recorded
+/// traffic, so handlers see time of recording instead of system time
+pub fn set(time: DateTime) {
+    VIRTUAL_TIME_NANOS.store(time.timestamp_nanos(), Ordering::Release);
+}
+
+/// Engine returns to system time
+pub fn reset() {
+    VIRTUAL_TIME_NANOS.store(NOT_SET, Ordering::Release);
+}
+
+pub fn now() -> Option<DateTime> {
+    match VIRTUAL_TIME_NANOS.load(Ordering::Acquire) {
+        NOT_SET => None,
+        nanos => Some(Utc.timestamp_nanos(nanos)),
+    }
+}
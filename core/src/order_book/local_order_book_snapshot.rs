@@ -1,3 +1,4 @@
+use mmb_utils::warn_limited;
 use mmb_utils::DateTime;
 use rust_decimal_macros::dec;
 
@@ -90,6 +91,17 @@ impl LocalOrderBookSnapshot {
         self.bids.iter().rev()
     }
 
+    /// Estimated queue position of new order: amount which is already placed at its price level
+    /// on its side of order book and is filled before the order by price-time priority
+    pub fn get_queue_amount_ahead(&self, side: OrderSide, price: Price) -> Amount {
+        let book_side = match side {
+            OrderSide::Buy => &self.bids,
+            OrderSide::Sell => &self.asks,
+        };
+
+        book_side.get(&price).copied().unwrap_or(dec!(0))
+    }
+
     fn try_remove_order(&mut self, order: DataToExcludeOrder) {
         let book_side = self.get_order_book_side(order.side);
 
@@ -123,7 +135,7 @@ impl LocalOrderBookSnapshot {
         let top_ask = match prices.top_ask {
             Some(top_ask) => top_ask,
             None => {
-                log::warn!(
+                warn_limited!(
                 "Can't get top ask price in {:?} in LocalOrderBookSnapshot::calculate_middle_price() {:?}",
                 market_id,
                 self
@@ -135,7 +147,7 @@ impl LocalOrderBookSnapshot {
         let top_bid = match prices.top_bid {
             Some(top_bid) => top_bid,
             None => {
-                log::warn!(
+                warn_limited!(
                 "Can't get top bid price in {:?} in LocalOrderBookSnapshot::calculate_middle_price() {:?}",
                 market_id,
                 self
@@ -314,4 +326,28 @@ mod tests {
         // Still exists
         assert_eq!(asks.next().expect("in test"), (&dec!(3.0), &dec!(4.2)));
     }
+
+    #[test]
+    fn get_queue_amount_ahead() {
+        let mut asks = SortedOrderData::new();
+        asks.insert(dec!(3.0), dec!(4.2));
+        let mut bids = SortedOrderData::new();
+        bids.insert(dec!(1.0), dec!(0.1));
+        bids.insert(dec!(2.0), dec!(0.5));
+
+        let order_book_snapshot = LocalOrderBookSnapshot::new(asks, bids, Utc::now());
+
+        assert_eq!(
+            order_book_snapshot.get_queue_amount_ahead(OrderSide::Buy, dec!(1.0)),
+            dec!(0.1)
+        );
+        assert_eq!(
+            order_book_snapshot.get_queue_amount_ahead(OrderSide::Sell, dec!(3.0)),
+            dec!(4.2)
+        );
+        assert_eq!(
+            order_book_snapshot.get_queue_amount_ahead(OrderSide::Sell, dec!(2.0)),
+            dec!(0)
+        );
+    }
 }
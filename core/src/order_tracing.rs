@@ -0,0 +1,203 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tracing::{info_span, Span};
+use tracing_subscriber::layer::SubscriberExt;
+
+use crate::exchanges::general::request_type::RequestType;
+use crate::orders::event::OrderEventType;
+use crate::orders::fill::EventSourceType;
+use crate::orders::order::{ClientOrderId, OrderCreating};
+use crate::orders::pool::OrderRef;
+
+const DEFAULT_SERVICE_NAME: &str = "mmb";
+
+/// Global subscriber can be set once, so it's kept between restarts of engine
+static IS_INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TracingSettings {
+    /// Address of Jaeger agent which receives spans by UDP, e.g. "127.0.0.1:6831"
+    pub jaeger_agent_endpoint: String,
+    /// Name of service in Jaeger. Default is "mmb"
+    pub service_name: Option<String>,
+}
+
+/// Installs exporter of spans to Jaeger agent as global `tracing` subscriber.
+/// Logging through `log` isn't affected
+pub fn init_order_tracing(settings: &TracingSettings) -> Result<()> {
+    if IS_INITIALIZED.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    let tracer = opentelemetry_jaeger::new_pipeline()
+        .with_agent_endpoint(settings.jaeger_agent_endpoint.as_str())
+        .with_service_name(
+            settings
+                .service_name
+                .as_deref()
+                .unwrap_or(DEFAULT_SERVICE_NAME),
+        )
+        .install_batch(opentelemetry::runtime::Tokio)
+        .context("Unable to install Jaeger exporter")?;
+
+    let subscriber =
+        tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer));
+    tracing::subscriber::set_global_default(subscriber)
+        .context("Unable to set global tracing subscriber")?;
+
+    IS_INITIALIZED.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Exports spans which aren't exported yet. It blocks current thread until export is finished
+pub fn shutdown_order_tracing() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+/// Root spans of traced orders. Every order trace contains spans of REST requests and
+/// order events with their source (REST or websocket). Trace is closed when order is finished.
+/// Spans aren't created if tracing isn't initialized
+#[derive(Default)]
+pub(crate) struct OrderTraces {
+    spans: DashMap<ClientOrderId, Span>,
+}
+
+impl OrderTraces {
+    pub(crate) fn start(&self, order: &OrderCreating) {
+        let header = &order.header;
+        let span = info_span!(
+            parent: None,
+            "order",
+            client_order_id = %header.client_order_id,
+            exchange_account_id = %header.exchange_account_id,
+            currency_pair = %header.currency_pair,
+            side = ?header.side,
+            amount = %header.amount,
+            price = %order.price,
+            strategy_name = %header.strategy_name,
+        );
+
+        if !span.is_disabled() {
+            let _ = self.spans.insert(header.client_order_id.clone(), span);
+        }
+    }
+
+    /// Span of REST request for order. It's `Span::none()` if order isn't traced
+    pub(crate) fn request_span(
+        &self,
+        client_order_id: &ClientOrderId,
+        request_type: RequestType,
+    ) -> Span {
+        match self.spans.get(client_order_id) {
+            Some(order_span) => {
+                info_span!(parent: order_span.value(), "rest_request", request_type = ?request_type)
+            }
+            None => Span::none(),
+        }
+    }
+
+    pub(crate) fn record_event(&self, order: &OrderRef, event_type: &OrderEventType) {
+        let client_order_id = order.client_order_id();
+        if let Some(order_span) = self.spans.get(&client_order_id) {
+            let (event_name, source_type) = event_info(order, event_type);
+            let _ = info_span!(
+                parent: order_span.value(),
+                "order_event",
+                event = event_name,
+                source = ?source_type,
+                status = ?order.status(),
+                exchange_order_id = ?order.exchange_order_id(),
+                filled_amount = %order.filled_amount(),
+            );
+        }
+
+        if order.is_finished() {
+            let _ = self.spans.remove(&client_order_id);
+        }
+    }
+}
+
+fn event_info(
+    order: &OrderRef,
+    event_type: &OrderEventType,
+) -> (&'static str, Option<EventSourceType>) {
+    let creation_source = || order.fn_ref(|x| x.internal_props.creation_event_source_type);
+    let cancellation_source = || order.fn_ref(|x| x.internal_props.cancellation_event_source_type);
+
+    match event_type {
+        OrderEventType::CreateOrderSucceeded => ("create_order_succeeded", creation_source()),
+        OrderEventType::CreateOrderFailed => ("create_order_failed", creation_source()),
+        OrderEventType::OrderFilled { cloned_order } => (
+            "order_filled",
+            cloned_order
+                .fills
+                .fills
+                .last()
+                .and_then(|x| x.event_source_type()),
+        ),
+        OrderEventType::OrderCompleted { cloned_order } => (
+            "order_completed",
+            cloned_order
+                .fills
+                .fills
+                .last()
+                .and_then(|x| x.event_source_type()),
+        ),
+        OrderEventType::CancelOrderSucceeded => ("cancel_order_succeeded", cancellation_source()),
+        OrderEventType::CancelOrderFailed => ("cancel_order_failed", cancellation_source()),
+        OrderEventType::OrderExpired => ("order_expired", cancellation_source()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::{CurrencyPair, ExchangeAccountId};
+    use crate::exchanges::general::test_helper::create_order_ref;
+    use crate::orders::order::{OrderSide, OrderStatus};
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn order_trace_is_closed_when_order_is_finished() {
+        let order = create_order_ref(
+            &"traced".into(),
+            None,
+            ExchangeAccountId::new("Binance".into(), 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            dec!(1),
+            dec!(1),
+            OrderSide::Buy,
+        );
+        let order_creating = OrderCreating {
+            header: order.fn_ref(|x| x.header.clone()),
+            price: order.price(),
+        };
+        let order_traces = OrderTraces::default();
+
+        // Spans are disabled without subscriber
+        order_traces.start(&order_creating);
+        assert!(order_traces.spans.is_empty());
+
+        tracing::subscriber::with_default(tracing_subscriber::registry(), || {
+            order_traces.start(&order_creating);
+            assert!(!order_traces
+                .request_span(&order.client_order_id(), RequestType::CreateOrder)
+                .is_none());
+
+            order.fn_mut(|x| x.set_status(OrderStatus::Created, Utc::now()));
+            order_traces.record_event(&order, &OrderEventType::CreateOrderSucceeded);
+            assert_eq!(order_traces.spans.len(), 1);
+
+            order.fn_mut(|x| x.set_status(OrderStatus::Canceled, Utc::now()));
+            order_traces.record_event(&order, &OrderEventType::CancelOrderSucceeded);
+            assert!(order_traces.spans.is_empty());
+            assert!(order_traces
+                .request_span(&order.client_order_id(), RequestType::CancelOrder)
+                .is_none());
+        });
+    }
+}
@@ -95,6 +95,7 @@ impl BufferedFill {
             order_side: self.side,
             order_amount,
             fill_date: self.fill_date,
+            position_side: None,
         }
     }
 }
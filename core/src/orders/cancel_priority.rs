@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::orders::order::OrderType;
+
+/// Kind of order by its role in strategy which defines when order is cancelled on graceful shutdown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CancelClass {
+    /// Resting limit orders which provide liquidity
+    Quote,
+    /// Working stop and trailing orders
+    Algo,
+    /// Reduce-only and position closing orders which protect open positions
+    Hedge,
+}
+
+impl CancelClass {
+    pub fn of(order_type: OrderType, reduce_only: bool) -> Self {
+        if reduce_only || order_type == OrderType::ClosePosition {
+            return CancelClass::Hedge;
+        }
+
+        match order_type {
+            OrderType::StopLoss | OrderType::TrailingStop => CancelClass::Algo,
+            _ => CancelClass::Quote,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CancelPrioritySettings {
+    /// Strategy which orders are cancelled in this order. Order is used for all strategies
+    /// without own settings if it isn't specified
+    pub strategy_name: Option<String>,
+    /// Classes of orders in order of cancellation. Orders of classes which aren't listed
+    /// are left open on graceful shutdown
+    pub order: Vec<CancelClass>,
+}
+
+/// Order of cancellation of open orders on graceful shutdown, so protective orders
+/// aren't cancelled before risky ones
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CancelPriorities {
+    default_order: Vec<CancelClass>,
+    order_by_strategy: HashMap<String, Vec<CancelClass>>,
+}
+
+impl Default for CancelPriorities {
+    fn default() -> Self {
+        Self {
+            default_order: vec![CancelClass::Quote, CancelClass::Algo, CancelClass::Hedge],
+            order_by_strategy: HashMap::new(),
+        }
+    }
+}
+
+impl CancelPriorities {
+    pub fn new(settings: &[CancelPrioritySettings]) -> Self {
+        let mut priorities = Self::default();
+        for strategy_settings in settings {
+            match &strategy_settings.strategy_name {
+                Some(strategy_name) => {
+                    let _ = priorities
+                        .order_by_strategy
+                        .insert(strategy_name.clone(), strategy_settings.order.clone());
+                }
+                None => priorities.default_order = strategy_settings.order.clone(),
+            }
+        }
+
+        priorities
+    }
+
+    /// Stage of cancellation of order starting from 0 or `None` if order shouldn't be cancelled
+    pub fn stage(&self, strategy_name: &str, class: CancelClass) -> Option<usize> {
+        self.order_by_strategy
+            .get(strategy_name)
+            .unwrap_or(&self.default_order)
+            .iter()
+            .position(|x| *x == class)
+    }
+
+    /// Splits items into groups which should be cancelled one after another
+    /// and items which should be left open
+    pub fn split_by_stages<T>(
+        items: Vec<T>,
+        get_stage: impl Fn(&T) -> Option<usize>,
+    ) -> (Vec<Vec<T>>, Vec<T>) {
+        let mut stages: Vec<Vec<T>> = Vec::new();
+        let mut kept = Vec::new();
+        for item in items {
+            match get_stage(&item) {
+                Some(stage) => {
+                    if stages.len() <= stage {
+                        stages.resize_with(stage + 1, Vec::new);
+                    }
+                    stages[stage].push(item);
+                }
+                None => kept.push(item),
+            }
+        }
+
+        stages.retain(|x| !x.is_empty());
+        (stages, kept)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_are_split_by_cancellation_stages_of_strategies() {
+        let priorities = CancelPriorities::new(&[CancelPrioritySettings {
+            strategy_name: Some("hedged".to_owned()),
+            order: vec![CancelClass::Algo, CancelClass::Quote],
+        }]);
+
+        assert_eq!(CancelClass::of(OrderType::Limit, true), CancelClass::Hedge);
+        assert_eq!(
+            CancelClass::of(OrderType::ClosePosition, false),
+            CancelClass::Hedge
+        );
+        assert_eq!(
+            CancelClass::of(OrderType::StopLoss, false),
+            CancelClass::Algo
+        );
+        assert_eq!(CancelClass::of(OrderType::Limit, false), CancelClass::Quote);
+
+        let orders = vec![
+            ("other", CancelClass::Hedge),
+            ("hedged", CancelClass::Hedge),
+            ("hedged", CancelClass::Quote),
+            ("other", CancelClass::Quote),
+            ("hedged", CancelClass::Algo),
+        ];
+        let (stages, kept) = CancelPriorities::split_by_stages(orders, |(strategy, class)| {
+            priorities.stage(strategy, *class)
+        });
+
+        assert_eq!(
+            stages,
+            vec![
+                vec![("other", CancelClass::Quote), ("hedged", CancelClass::Algo)],
+                vec![("hedged", CancelClass::Quote)],
+                vec![("other", CancelClass::Hedge)],
+            ]
+        );
+        assert_eq!(kept, vec![("hedged", CancelClass::Hedge)]);
+    }
+}
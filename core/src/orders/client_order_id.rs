@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use chrono::{TimeZone, Utc};
+use mmb_utils::DateTime;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use crate::orders::order::ClientOrderId;
+
+/// Max length of client order id on Binance which is the strictest limit among supported exchanges
+pub const MAX_CLIENT_ORDER_ID_LEN: usize = 36;
+/// Length of session part (base36 of session start time in milliseconds) which is enough until year 2059
+const SESSION_LEN: usize = 8;
+/// Counter of session is expected to fit 8 base36 digits (2.8 trillion orders)
+const MAX_COUNTER_LEN: usize = 8;
+/// Rest of id after separators, session, counter and checksum
+pub const MAX_PREFIX_AND_STRATEGY_CODE_LEN: usize =
+    MAX_CLIENT_ORDER_ID_LEN - 3 - SESSION_LEN - MAX_COUNTER_LEN - 1;
+
+const SEPARATOR: char = '-';
+const BASE36_DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+static GENERATOR: Lazy<RwLock<Option<ClientOrderIdGenerator>>> = Lazy::new(|| RwLock::new(None));
+
+/// Scheme of client order ids `<prefix>-<strategy_code>-<session>-<counter><checksum>`,
+/// e.g. `mmb-mm1-lbq0xk2p-1z7`. Session is start time of engine, so ids don't collide after restarts
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ClientOrderIdSettings {
+    /// Common prefix which separates orders of engine from orders created manually
+    pub prefix: String,
+    /// Short codes by strategy name. Code of other strategies is derived from their name
+    #[serde(default)]
+    pub strategy_codes: HashMap<String, String>,
+}
+
+impl ClientOrderIdSettings {
+    /// Problems of settings which don't allow to generate ids accepted by exchanges
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !is_valid_part(&self.prefix) {
+            problems.push(
+                "prefix should be non-empty and contain only ASCII letters and digits".to_owned(),
+            );
+        }
+        // At least 1 character is left for derived strategy code
+        if self.prefix.len() >= MAX_PREFIX_AND_STRATEGY_CODE_LEN {
+            problems.push(format!(
+                "length of prefix should be less than {MAX_PREFIX_AND_STRATEGY_CODE_LEN}"
+            ));
+        }
+
+        for (strategy_name, strategy_code) in &self.strategy_codes {
+            if !is_valid_part(strategy_code) {
+                problems.push(format!(
+                    "strategy code of {strategy_name} should be non-empty and contain only ASCII letters and digits"
+                ));
+            }
+            if self.prefix.len() + strategy_code.len() > MAX_PREFIX_AND_STRATEGY_CODE_LEN {
+                problems.push(format!(
+                    "total length of prefix and strategy code of {strategy_name} should be at most {MAX_PREFIX_AND_STRATEGY_CODE_LEN}"
+                ));
+            }
+        }
+
+        problems
+    }
+}
+
+fn is_valid_part(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(|x| x.is_ascii_alphanumeric())
+}
+
+/// Lowercase ASCII letters and digits of strategy name, e.g. `mm1` for `MM_1`
+fn derive_strategy_code(strategy_name: &str, max_len: usize) -> String {
+    let strategy_code: String = strategy_name
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|x| x.to_ascii_lowercase())
+        .take(max_len)
+        .collect();
+
+    match strategy_code.is_empty() {
+        true => "s".to_owned(),
+        false => strategy_code,
+    }
+}
+
+/// Metadata which is embedded into client order id generated by `ClientOrderIdSettings` scheme
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientOrderIdMetadata {
+    pub prefix: String,
+    pub strategy_code: String,
+    pub session_start: DateTime,
+    pub counter: u64,
+}
+
+impl ClientOrderIdMetadata {
+    /// Returns `None` if id isn't generated by the scheme or it is corrupted
+    pub fn parse(client_order_id: &ClientOrderId) -> Option<Self> {
+        let id = client_order_id.as_str();
+        let (body, checksum) = id.split_at(id.len().checked_sub(1)?);
+        if checksum != get_checksum(body).to_string() {
+            return None;
+        }
+
+        let mut parts = body.split(SEPARATOR);
+        let (prefix, strategy_code, session, counter) =
+            (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        if parts.next().is_some() {
+            return None;
+        }
+
+        let session_millis = i64::try_from(from_base36(session)?).ok()?;
+        Some(Self {
+            prefix: prefix.to_owned(),
+            strategy_code: strategy_code.to_owned(),
+            session_start: Utc.timestamp_millis_opt(session_millis).single()?,
+            counter: from_base36(counter)?,
+        })
+    }
+}
+
+pub struct ClientOrderIdGenerator {
+    prefix: String,
+    strategy_codes: HashMap<String, String>,
+    session: String,
+    /// Counter is common for all strategies, so ids are unique even if strategy codes are equal
+    counter: AtomicU64,
+}
+
+impl ClientOrderIdGenerator {
+    pub fn new(settings: &ClientOrderIdSettings, session_start: DateTime) -> Self {
+        Self {
+            prefix: settings.prefix.clone(),
+            strategy_codes: settings.strategy_codes.clone(),
+            session: to_base36(session_start.timestamp_millis().max(0) as u64),
+            counter: AtomicU64::new(0),
+        }
+    }
+
+    pub fn generate(&self, strategy_name: &str) -> ClientOrderId {
+        let strategy_code = match self.strategy_codes.get(strategy_name) {
+            Some(strategy_code) => strategy_code.clone(),
+            None => derive_strategy_code(
+                strategy_name,
+                MAX_PREFIX_AND_STRATEGY_CODE_LEN.saturating_sub(self.prefix.len()),
+            ),
+        };
+
+        let counter = self.counter.fetch_add(1, Ordering::AcqRel);
+        let mut id = format!(
+            "{}{SEPARATOR}{strategy_code}{SEPARATOR}{}{SEPARATOR}{}",
+            self.prefix,
+            self.session,
+            to_base36(counter)
+        );
+        id.push(get_checksum(&id));
+        id.as_str().into()
+    }
+}
+
+/// Sets scheme of ids which are returned by `ClientOrderId::generate()`. Every call starts a new session.
+/// Numeric unique ids are generated if scheme isn't specified
+pub fn init_client_order_id_generator(settings: Option<&ClientOrderIdSettings>) {
+    *GENERATOR.write() = settings.map(|settings| ClientOrderIdGenerator::new(settings, Utc::now()));
+}
+
+impl ClientOrderId {
+    /// Id for new order of strategy by scheme which is set in `init_client_order_id_generator`
+    pub fn generate(strategy_name: &str) -> Self {
+        match &*GENERATOR.read() {
+            Some(generator) => generator.generate(strategy_name),
+            None => ClientOrderId::unique_id(),
+        }
+    }
+}
+
+fn to_base36(mut value: u64) -> String {
+    let mut digits = Vec::new();
+    loop {
+        digits.push(BASE36_DIGITS[(value % 36) as usize]);
+        value /= 36;
+        if value == 0 {
+            break;
+        }
+    }
+
+    digits.iter().rev().map(|&x| x as char).collect()
+}
+
+fn from_base36(value: &str) -> Option<u64> {
+    if value.is_empty() {
+        return None;
+    }
+
+    value.chars().try_fold(0u64, |acc, x| {
+        let digit = x.to_digit(36)?;
+        acc.checked_mul(36)?.checked_add(digit as u64)
+    })
+}
+
+/// Position-weighted sum of bytes, so swapped and mistyped characters are detected
+fn get_checksum(body: &str) -> char {
+    let sum = body.bytes().enumerate().fold(0usize, |acc, (index, byte)| {
+        (acc + (index + 1) * byte as usize) % BASE36_DIGITS.len()
+    });
+
+    BASE36_DIGITS[sum] as char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> ClientOrderIdSettings {
+        ClientOrderIdSettings {
+            prefix: "mmb".into(),
+            strategy_codes: HashMap::from([("MarketMaker".to_owned(), "mm1".to_owned())]),
+        }
+    }
+
+    #[test]
+    fn generated_id_contains_metadata() {
+        let session_start = Utc
+            .timestamp_millis_opt(1_700_000_000_123)
+            .single()
+            .expect("in test");
+        let generator = ClientOrderIdGenerator::new(&settings(), session_start);
+
+        let _ = generator.generate("MarketMaker");
+        let id = generator.generate("MarketMaker");
+
+        assert!(id.as_str().starts_with("mmb-mm1-"));
+        assert_eq!(
+            ClientOrderIdMetadata::parse(&id),
+            Some(ClientOrderIdMetadata {
+                prefix: "mmb".into(),
+                strategy_code: "mm1".into(),
+                session_start,
+                counter: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn strategy_code_is_derived_from_strategy_name() {
+        let generator = ClientOrderIdGenerator::new(&settings(), Utc::now());
+
+        let id = generator.generate("Spread_Order");
+        assert!(id.as_str().starts_with("mmb-spreadorder-"));
+        let metadata = ClientOrderIdMetadata::parse(&id).expect("in test");
+        assert_eq!(metadata.strategy_code, "spreadorder");
+        assert_eq!(metadata.counter, 0);
+
+        let id = generator.generate("VeryLongStrategyName");
+        assert!(id.as_str().starts_with("mmb-verylongstrat-"));
+        assert_eq!(
+            ClientOrderIdMetadata::parse(&id).expect("in test").counter,
+            1
+        );
+
+        assert!(generator.generate("__").as_str().starts_with("mmb-s-"));
+    }
+
+    #[test]
+    fn max_id_fits_exchange_limit() {
+        let settings = ClientOrderIdSettings {
+            prefix: "p".repeat(8),
+            strategy_codes: HashMap::from([(
+                "Strategy".to_owned(),
+                "s".repeat(MAX_PREFIX_AND_STRATEGY_CODE_LEN - 8),
+            )]),
+        };
+        assert!(settings.validate().is_empty());
+
+        let generator = ClientOrderIdGenerator::new(&settings, Utc::now());
+        generator
+            .counter
+            .store(36u64.pow(MAX_COUNTER_LEN as u32) - 1, Ordering::SeqCst);
+
+        for strategy_name in ["Strategy", "OtherVeryLongStrategyName"] {
+            assert_eq!(
+                generator.generate(strategy_name).as_str().len(),
+                MAX_CLIENT_ORDER_ID_LEN
+            );
+        }
+    }
+
+    #[test]
+    fn corrupted_id_is_not_parsed() {
+        let id = ClientOrderIdGenerator::new(&settings(), Utc::now()).generate("MarketMaker");
+        let corrupted = id.as_str().replacen("mm1", "mm2", 1);
+
+        assert_eq!(
+            ClientOrderIdMetadata::parse(&corrupted.as_str().into()),
+            None
+        );
+        assert_eq!(ClientOrderIdMetadata::parse(&"12345".into()), None);
+    }
+
+    #[test]
+    fn invalid_settings() {
+        let settings = ClientOrderIdSettings {
+            prefix: "mmb_".into(),
+            strategy_codes: HashMap::from([(
+                "MarketMaker".to_owned(),
+                "a".repeat(MAX_PREFIX_AND_STRATEGY_CODE_LEN),
+            )]),
+        };
+        assert_eq!(settings.validate().len(), 2);
+
+        let settings = ClientOrderIdSettings {
+            prefix: "p".repeat(MAX_PREFIX_AND_STRATEGY_CODE_LEN),
+            strategy_codes: HashMap::from([("MarketMaker".to_owned(), "mm-1".to_owned())]),
+        };
+        assert_eq!(settings.validate().len(), 3);
+    }
+}
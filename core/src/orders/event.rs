@@ -9,10 +9,17 @@ use crate::orders::pool::OrderRef;
 pub enum OrderEventType {
     CreateOrderSucceeded,
     CreateOrderFailed,
-    OrderFilled { cloned_order: Arc<OrderSnapshot> },
-    OrderCompleted { cloned_order: Arc<OrderSnapshot> },
+    OrderFilled {
+        cloned_order: Arc<OrderSnapshot>,
+    },
+    OrderCompleted {
+        cloned_order: Arc<OrderSnapshot>,
+    },
     CancelOrderSucceeded,
     CancelOrderFailed,
+    /// Order was cancelled because of reaching its `expire_at` time.
+    /// Raised right after `CancelOrderSucceeded` for the same order
+    OrderExpired,
 }
 
 #[derive(Debug, Clone)]
@@ -150,6 +150,16 @@ impl OrderFill {
         &self.client_order_fill_id
     }
 
+    /// Commission which couldn't be converted at time of fill is corrected later
+    pub(crate) fn set_converted_commission(
+        &mut self,
+        currency_code: CurrencyCode,
+        amount: Decimal,
+    ) {
+        self.converted_commission_currency_code = currency_code;
+        self.converted_commission_amount = amount;
+    }
+
     #[cfg(test)]
     pub fn set_client_order_fill_id(&mut self, input: ClientOrderFillId) {
         self.client_order_fill_id = Some(input);
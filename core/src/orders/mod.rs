@@ -1,5 +1,11 @@
 pub mod buffered_fills;
+pub mod cancel_priority;
+pub mod client_order_id;
 pub mod event;
 pub mod fill;
 pub mod order;
+pub mod order_builder;
+pub mod order_filter;
 pub mod pool;
+pub mod spread_order;
+pub mod timeline;
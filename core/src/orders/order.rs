@@ -59,6 +59,28 @@ impl OptionOrderSideExt for Option<OrderSide> {
     }
 }
 
+/// Side of derivative position which order or fill belongs to.
+/// In hedge mode long and short positions of the same currency pair are opened separately,
+/// in one-way mode there is only position `Both`
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize, Deserialize, Hash)]
+pub enum PositionSide {
+    Both,
+    Long,
+    Short,
+}
+
+impl Display for PositionSide {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let side = match self {
+            PositionSide::Both => "Both",
+            PositionSide::Long => "Long",
+            PositionSide::Short => "Short",
+        };
+
+        write!(f, "{}", side)
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize, Deserialize, Hash)]
 pub enum OrderRole {
     Maker = 1,
@@ -99,6 +121,30 @@ pub enum OrderExecutionType {
     MakerOnly = 1,
 }
 
+/// Currency in which order amount is specified
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize, Deserialize, Hash)]
+pub enum OrderAmountKind {
+    /// Amount in base currency of currency pair
+    Base,
+    /// Amount in quote currency of currency pair. Supported only for market orders:
+    /// exchange buys or sells as much base currency as it can for specified quote amount
+    Quote,
+}
+
+impl Default for OrderAmountKind {
+    fn default() -> Self {
+        OrderAmountKind::Base
+    }
+}
+
+fn to_base_amount(amount_kind: OrderAmountKind, amount: Amount, price: Price) -> Option<Amount> {
+    match amount_kind {
+        OrderAmountKind::Base => Some(amount),
+        OrderAmountKind::Quote if price <= Decimal::ZERO => None,
+        OrderAmountKind::Quote => Some(amount / price),
+    }
+}
+
 impl_str_id!(ClientOrderId);
 impl_str_id!(ClientOrderFillId);
 impl_str_id!(ExchangeOrderId);
@@ -166,6 +212,23 @@ pub struct OrderHeader {
 
     pub signal_id: Option<String>,
     pub strategy_name: String,
+
+    /// Position side for derivative orders on accounts in hedge mode
+    #[serde(default)]
+    pub position_side: Option<PositionSide>,
+
+    /// Currency of `amount`. Filled amounts of order are always in base currency
+    #[serde(default)]
+    pub amount_kind: OrderAmountKind,
+
+    /// Time after which order should be cancelled (good-till-date). Order is cancelled by exchange
+    /// if it supports such orders natively, otherwise by engine
+    #[serde(default)]
+    pub expire_at: Option<DateTime>,
+
+    /// Order can only decrease position of derivative. It's ignored by exchanges without derivatives
+    #[serde(default)]
+    pub reduce_only: bool,
 }
 
 impl OrderHeader {
@@ -195,9 +258,43 @@ impl OrderHeader {
             reservation_id,
             signal_id,
             strategy_name,
+            position_side: None,
+            amount_kind: OrderAmountKind::Base,
+            expire_at: None,
+            reduce_only: false,
         })
     }
 
+    pub fn with_position_side(mut self: Arc<Self>, position_side: PositionSide) -> Arc<Self> {
+        Arc::make_mut(&mut self).position_side = Some(position_side);
+        self
+    }
+
+    pub fn with_amount_kind(mut self: Arc<Self>, amount_kind: OrderAmountKind) -> Arc<Self> {
+        Arc::make_mut(&mut self).amount_kind = amount_kind;
+        self
+    }
+
+    pub fn with_expire_at(mut self: Arc<Self>, expire_at: DateTime) -> Arc<Self> {
+        Arc::make_mut(&mut self).expire_at = Some(expire_at);
+        self
+    }
+
+    pub fn with_reduce_only(mut self: Arc<Self>) -> Arc<Self> {
+        Arc::make_mut(&mut self).reduce_only = true;
+        self
+    }
+
+    pub fn is_expired(&self, now: DateTime) -> bool {
+        self.expire_at.map_or(false, |expire_at| expire_at <= now)
+    }
+
+    /// Amount of order in base currency. Quote amount is converted by specified price,
+    /// so `None` is returned for order with quote amount and non-positive price
+    pub fn base_amount(&self, price: Price) -> Option<Amount> {
+        to_base_amount(self.amount_kind, self.amount, price)
+    }
+
     pub fn version(&self) -> u32 {
         self.version
     }
@@ -288,11 +385,28 @@ pub struct OrderStatusChange {
     time: DateTime,
 }
 
+impl OrderStatusChange {
+    pub fn status(&self) -> OrderStatus {
+        self.status
+    }
+
+    pub fn time(&self) -> DateTime {
+        self.time
+    }
+}
+
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct OrderStatusHistory {
     status_changes: Vec<OrderStatusChange>,
 }
 
+impl OrderStatusHistory {
+    /// Changes of order status in order of their time
+    pub fn status_changes(&self) -> &[OrderStatusChange] {
+        &self.status_changes
+    }
+}
+
 /// Helping properties for trading engine internal use
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct SystemInternalOrderProps {
@@ -472,7 +586,105 @@ impl OrderSnapshot {
     pub fn filled_amount(&self) -> Amount {
         self.fills.filled_amount
     }
+    /// Amount of order in base currency. Quote amount is converted by price of order
+    pub fn base_amount(&self) -> Option<Amount> {
+        self.header.base_amount(self.props.raw_price.unwrap_or_default())
+    }
+    /// Not filled part of order in base currency. Quote amount is converted by price of order
+    pub fn remaining_base_amount(&self) -> Option<Amount> {
+        match self.header.amount_kind {
+            OrderAmountKind::Base => Some(self.header.amount - self.fills.filled_amount),
+            OrderAmountKind::Quote => {
+                let filled_cost: Decimal = self.fills.fills.iter().map(|fill| fill.cost()).sum();
+                let price = self.props.raw_price?;
+                to_base_amount(
+                    OrderAmountKind::Quote,
+                    self.header.amount - filled_cost,
+                    price,
+                )
+            }
+        }
+    }
     pub fn status(&self) -> OrderStatus {
         self.props.status
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::orders::fill::OrderFillType;
+    use crate::test_util::OrderSnapshotBuilder;
+
+    fn order_builder() -> OrderSnapshotBuilder {
+        OrderSnapshotBuilder::new(
+            ExchangeAccountId::new("Binance".into(), 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        )
+    }
+
+    fn fill(price: Price, amount: Amount) -> OrderFill {
+        OrderFill::new(
+            Uuid::new_v4(),
+            None,
+            Utc::now(),
+            OrderFillType::UserTrade,
+            None,
+            price,
+            amount,
+            price * amount,
+            OrderFillRole::Taker,
+            "usdt".into(),
+            dec!(0),
+            dec!(0),
+            "usdt".into(),
+            dec!(0),
+            dec!(0),
+            false,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn remaining_base_amount_of_base_order() {
+        let order = order_builder()
+            .price(dec!(20))
+            .amount(dec!(5))
+            .fill(fill(dec!(20), dec!(2)))
+            .build();
+
+        assert_eq!(order.base_amount(), Some(dec!(5)));
+        assert_eq!(order.remaining_base_amount(), Some(dec!(3)));
+    }
+
+    #[test]
+    fn remaining_base_amount_of_quote_order() {
+        let order = order_builder()
+            .order_type(OrderType::Market)
+            .price(dec!(20))
+            .amount(dec!(100))
+            .amount_kind(OrderAmountKind::Quote)
+            .fill(fill(dec!(25), dec!(2)))
+            .build();
+
+        assert_eq!(order.base_amount(), Some(dec!(5)));
+        // 50 of 100 USDT are spent, rest is converted by price of order
+        assert_eq!(order.remaining_base_amount(), Some(dec!(2.5)));
+    }
+
+    #[test]
+    fn quote_amount_is_not_converted_by_zero_price() {
+        let order = order_builder()
+            .order_type(OrderType::Market)
+            .price(dec!(0))
+            .amount(dec!(100))
+            .amount_kind(OrderAmountKind::Quote)
+            .build();
+
+        assert_eq!(order.base_amount(), None);
+        assert_eq!(order.remaining_base_amount(), None);
+    }
+}
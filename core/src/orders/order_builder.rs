@@ -0,0 +1,649 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal_macros::dec;
+use thiserror::Error;
+
+use crate::balance_manager::balance_manager::{BalanceManager, ReserveError};
+use crate::balance_manager::capital_allocation::CapitalAllocationError;
+use crate::error::MmbResult;
+use crate::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId, Price};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::symbol::{Round, Symbol};
+use crate::explanation::Explanation;
+use crate::misc::reserve_parameters::ReserveParameters;
+use crate::orders::order::{
+    ClientOrderId, OrderAmountKind, OrderCreating, OrderExecutionType, OrderHeader, OrderSide,
+    OrderStatus, OrderType, PositionSide, ReservationId,
+};
+use crate::orders::pool::OrderRef;
+use crate::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+
+/// Strategy name of orders if it isn't specified in builder
+pub const DEFAULT_STRATEGY_NAME: &str = "Manual";
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum OrderBuildError {
+    #[error("Order side isn't specified")]
+    SideIsNotSpecified,
+    #[error("Order amount isn't specified")]
+    AmountIsNotSpecified,
+    #[error("Order amount should be positive: {0}")]
+    NonPositiveAmount(Amount),
+    #[error("Order price isn't specified")]
+    PriceIsNotSpecified,
+    #[error("Order price should be positive: {0}")]
+    NonPositivePrice(Price),
+    #[error("Order amount {amount} is less than min amount {min_amount} of symbol")]
+    AmountIsLessThanMin { amount: Amount, min_amount: Amount },
+    #[error("Order cost {cost} is less than min cost {min_cost} of symbol")]
+    CostIsLessThanMin { cost: Price, min_cost: Price },
+    #[error("Order price {price} doesn't match precision of symbol, nearest passive price is {rounded_price}")]
+    PriceIsNotRounded { price: Price, rounded_price: Price },
+    #[error("Amount in quote currency is supported only for market orders")]
+    QuoteAmountForNonMarketOrder,
+    #[error("Unable to reserve balance for order: {0}")]
+    UnableToReserveBalance(String),
+    #[error(transparent)]
+    CapitalAllocation(#[from] CapitalAllocationError),
+}
+
+/// Fluent construction of orders for strategies
+///
+/// ```no_run
+/// use mmb_core::exchanges::common::{CurrencyPair, ExchangeAccountId};
+/// use mmb_core::exchanges::general::exchange::Exchange;
+/// use mmb_core::orders::order_builder::OrderBuilder;
+/// use mmb_utils::cancellation_token::CancellationToken;
+/// use rust_decimal_macros::dec;
+///
+/// async fn example(exchange: &Exchange, currency_pair: CurrencyPair) -> anyhow::Result<()> {
+///     let order = OrderBuilder::new(exchange.exchange_account_id, currency_pair)
+///         .buy()
+///         .limit(dec!(0.5))
+///         .amount(dec!(10))
+///         .maker_only()
+///         .strategy_name("ExampleStrategy")
+///         .create(exchange, CancellationToken::default())
+///         .await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct OrderBuilder {
+    exchange_account_id: ExchangeAccountId,
+    currency_pair: CurrencyPair,
+    side: Option<OrderSide>,
+    order_type: OrderType,
+    price: Option<Price>,
+    amount: Option<Amount>,
+    amount_kind: OrderAmountKind,
+    execution_type: OrderExecutionType,
+    reservation_id: Option<ReservationId>,
+    /// Reservation is made by `reserve`, so it's released by builder if order isn't created
+    owns_reservation: bool,
+    signal_id: Option<String>,
+    strategy_name: Option<String>,
+    position_side: Option<PositionSide>,
+    expire_at: Option<DateTime>,
+    reduce_only: bool,
+}
+
+impl OrderBuilder {
+    pub fn new(exchange_account_id: ExchangeAccountId, currency_pair: CurrencyPair) -> Self {
+        Self {
+            exchange_account_id,
+            currency_pair,
+            side: None,
+            order_type: OrderType::Limit,
+            price: None,
+            amount: None,
+            amount_kind: OrderAmountKind::Base,
+            execution_type: OrderExecutionType::None,
+            reservation_id: None,
+            owns_reservation: false,
+            signal_id: None,
+            strategy_name: None,
+            position_side: None,
+            expire_at: None,
+            reduce_only: false,
+        }
+    }
+
+    pub fn side(mut self, side: OrderSide) -> Self {
+        self.side = Some(side);
+        self
+    }
+
+    pub fn buy(self) -> Self {
+        self.side(OrderSide::Buy)
+    }
+
+    pub fn sell(self) -> Self {
+        self.side(OrderSide::Sell)
+    }
+
+    pub fn limit(mut self, price: Price) -> Self {
+        self.order_type = OrderType::Limit;
+        self.price = Some(price);
+        self
+    }
+
+    /// Price of market order is used only for balance reservation
+    pub fn market(mut self, estimated_price: Option<Price>) -> Self {
+        self.order_type = OrderType::Market;
+        self.price = estimated_price;
+        self
+    }
+
+    pub fn amount(mut self, amount: Amount) -> Self {
+        self.amount = Some(amount);
+        self.amount_kind = OrderAmountKind::Base;
+        self
+    }
+
+    /// Amount in quote currency which should be spent or received by market order
+    pub fn quote_amount(mut self, amount: Amount) -> Self {
+        self.amount = Some(amount);
+        self.amount_kind = OrderAmountKind::Quote;
+        self
+    }
+
+    pub fn execution_type(mut self, execution_type: OrderExecutionType) -> Self {
+        self.execution_type = execution_type;
+        self
+    }
+
+    pub fn maker_only(self) -> Self {
+        self.execution_type(OrderExecutionType::MakerOnly)
+    }
+
+    pub fn reservation_id(mut self, reservation_id: ReservationId) -> Self {
+        self.reservation_id = Some(reservation_id);
+        self.owns_reservation = false;
+        self
+    }
+
+    pub fn signal_id(mut self, signal_id: impl Into<String>) -> Self {
+        self.signal_id = Some(signal_id.into());
+        self
+    }
+
+    pub fn strategy_name(mut self, strategy_name: impl Into<String>) -> Self {
+        self.strategy_name = Some(strategy_name.into());
+        self
+    }
+
+    pub fn position_side(mut self, position_side: PositionSide) -> Self {
+        self.position_side = Some(position_side);
+        self
+    }
+
+    /// Order is cancelled at specified time if it isn't finished before
+    pub fn expire_at(mut self, expire_at: DateTime) -> Self {
+        self.expire_at = Some(expire_at);
+        self
+    }
+
+    /// Order can only decrease position, e.g. for closing of position
+    pub fn reduce_only(mut self) -> Self {
+        self.reduce_only = true;
+        self
+    }
+
+    /// Checks order amount and price of limit order against symbol restrictions
+    pub fn validate_by_symbol(&self, symbol: &Symbol) -> Result<(), OrderBuildError> {
+        if let (OrderType::Limit, Some(side), Some(price)) =
+            (self.order_type, self.side, self.price)
+        {
+            let rounded_price = symbol.price_round(price, Round::TowardPassive(side));
+            if rounded_price != price {
+                return Err(OrderBuildError::PriceIsNotRounded {
+                    price,
+                    rounded_price,
+                });
+            }
+        }
+
+        let amount = self.amount.ok_or(OrderBuildError::AmountIsNotSpecified)?;
+        match self.amount_kind {
+            OrderAmountKind::Base => {
+                if let Some(min_amount) = symbol.min_amount {
+                    if amount < min_amount {
+                        return Err(OrderBuildError::AmountIsLessThanMin { amount, min_amount });
+                    }
+                }
+            }
+            OrderAmountKind::Quote => {
+                if let Some(min_cost) = symbol.min_cost {
+                    if amount < min_cost {
+                        return Err(OrderBuildError::CostIsLessThanMin {
+                            cost: amount,
+                            min_cost,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reserves balance for order. Strategy name is taken from configuration descriptor if it isn't specified
+    pub fn reserve(
+        mut self,
+        balance_manager: &Mutex<BalanceManager>,
+        configuration_descriptor: ConfigurationDescriptor,
+        symbol: Arc<Symbol>,
+    ) -> Result<Self, OrderBuildError> {
+        let side = self.side.ok_or(OrderBuildError::SideIsNotSpecified)?;
+        let amount = self.validated_amount()?;
+        let price = self.price.ok_or(OrderBuildError::PriceIsNotSpecified)?;
+
+        // Balance is reserved in base currency, so quote amount is converted by estimated price
+        let amount = match self.amount_kind {
+            OrderAmountKind::Base => amount,
+            OrderAmountKind::Quote if price <= dec!(0) => {
+                return Err(OrderBuildError::NonPositivePrice(price))
+            }
+            OrderAmountKind::Quote => amount / price,
+        };
+
+        let reserve_parameters = ReserveParameters::new(
+            configuration_descriptor,
+            self.exchange_account_id,
+            symbol,
+            side,
+            price,
+            amount,
+        );
+
+        let reservation_id = try_reserve(balance_manager, &reserve_parameters)?;
+
+        self.reservation_id = Some(reservation_id);
+        self.owns_reservation = true;
+        if self.strategy_name.is_none() {
+            self.strategy_name = Some(configuration_descriptor.service_name.as_str().to_owned());
+        }
+
+        Ok(self)
+    }
+
+    pub fn build(self) -> Result<OrderCreating, OrderBuildError> {
+        let side = self.side.ok_or(OrderBuildError::SideIsNotSpecified)?;
+        let amount = self.validated_amount()?;
+        if self.amount_kind == OrderAmountKind::Quote && self.order_type != OrderType::Market {
+            return Err(OrderBuildError::QuoteAmountForNonMarketOrder);
+        }
+
+        let price = match (self.order_type, self.price) {
+            (_, Some(price)) if price <= dec!(0) => {
+                return Err(OrderBuildError::NonPositivePrice(price))
+            }
+            (_, Some(price)) => price,
+            (OrderType::Market, None) => dec!(0),
+            (_, None) => return Err(OrderBuildError::PriceIsNotSpecified),
+        };
+
+        let strategy_name = self
+            .strategy_name
+            .unwrap_or_else(|| DEFAULT_STRATEGY_NAME.to_owned());
+        let mut header = OrderHeader::new(
+            ClientOrderId::generate(&strategy_name),
+            chrono::Utc::now(),
+            self.exchange_account_id,
+            self.currency_pair,
+            self.order_type,
+            side,
+            amount,
+            self.execution_type,
+            self.reservation_id,
+            self.signal_id,
+            strategy_name,
+        );
+
+        if let Some(position_side) = self.position_side {
+            header = header.with_position_side(position_side);
+        }
+
+        if let Some(expire_at) = self.expire_at {
+            header = header.with_expire_at(expire_at);
+        }
+
+        if self.amount_kind != OrderAmountKind::Base {
+            header = header.with_amount_kind(self.amount_kind);
+        }
+
+        if self.reduce_only {
+            header = header.with_reduce_only();
+        }
+
+        Ok(OrderCreating { header, price })
+    }
+
+    /// Builds order and submits it to exchange. Reservation made by `reserve` is released
+    /// if order isn't created
+    pub async fn create(
+        self,
+        exchange: &Exchange,
+        cancellation_token: CancellationToken,
+    ) -> MmbResult<OrderRef> {
+        let owned_reservation_id = self.reservation_id.filter(|_| self.owns_reservation);
+        let order_to_create = match self.build() {
+            Ok(order_to_create) => order_to_create,
+            Err(error) => {
+                release_reservation(exchange, owned_reservation_id);
+                return Err(error.into());
+            }
+        };
+
+        create_order_with_reservation(
+            exchange,
+            &order_to_create,
+            owned_reservation_id,
+            cancellation_token,
+        )
+        .await
+    }
+
+    fn validated_amount(&self) -> Result<Amount, OrderBuildError> {
+        let amount = self.amount.ok_or(OrderBuildError::AmountIsNotSpecified)?;
+        if amount <= dec!(0) {
+            return Err(OrderBuildError::NonPositiveAmount(amount));
+        }
+
+        Ok(amount)
+    }
+}
+
+fn try_reserve(
+    balance_manager: &Mutex<BalanceManager>,
+    reserve_parameters: &ReserveParameters,
+) -> Result<ReservationId, OrderBuildError> {
+    let mut explanation = Some(Explanation::default());
+    balance_manager
+        .lock()
+        .try_reserve_checked(reserve_parameters, &mut explanation)
+        .map_err(|error| match error {
+            ReserveError::CapitalAllocation(error) => OrderBuildError::CapitalAllocation(error),
+            ReserveError::NotEnoughBalance => {
+                OrderBuildError::UnableToReserveBalance(format!("{:?}", explanation))
+            }
+        })
+}
+
+/// Reserves balance for order which is built without `OrderBuilder::reserve`, e.g. order
+/// derived from another one. Returns order with reservation and id of the reservation
+pub(crate) fn reserve_order(
+    exchange: &Exchange,
+    order: &OrderCreating,
+    configuration_descriptor: ConfigurationDescriptor,
+) -> Result<(OrderCreating, ReservationId)> {
+    let header = &order.header;
+    let amount = header
+        .base_amount(order.price)
+        .ok_or(OrderBuildError::NonPositivePrice(order.price))?;
+    let reserve_parameters = ReserveParameters::new(
+        configuration_descriptor,
+        header.exchange_account_id,
+        exchange.get_symbol(header.currency_pair)?,
+        header.side,
+        order.price,
+        amount,
+    );
+
+    let balance_manager = exchange.get_balance_manager().with_context(|| {
+        format!(
+            "Unable to reserve balance for order {}: BalanceManager isn't set up on {}",
+            header.client_order_id, exchange.exchange_account_id
+        )
+    })?;
+    let reservation_id = try_reserve(&balance_manager, &reserve_parameters)?;
+
+    let mut reserved_header = (**header).clone();
+    reserved_header.reservation_id = Some(reservation_id);
+    let reserved_order = OrderCreating {
+        header: Arc::new(reserved_header),
+        price: order.price,
+    };
+    Ok((reserved_order, reservation_id))
+}
+
+/// Creates order like `Exchange::create_order`. Reservation owned by caller is released
+/// if order isn't created
+pub(crate) async fn create_order_with_reservation(
+    exchange: &Exchange,
+    order: &OrderCreating,
+    owned_reservation_id: Option<ReservationId>,
+    cancellation_token: CancellationToken,
+) -> MmbResult<OrderRef> {
+    let result = exchange.create_order(order, None, cancellation_token).await;
+
+    if result.is_err() {
+        // Order in state Creating still can be created, and then it approves the reservation
+        let is_not_created = exchange
+            .orders
+            .cache_by_client_id
+            .get(&order.header.client_order_id)
+            .map_or(true, |order| order.status() == OrderStatus::FailedToCreate);
+        if is_not_created {
+            release_reservation(exchange, owned_reservation_id);
+        }
+    }
+
+    result
+}
+
+pub(crate) fn release_reservation(exchange: &Exchange, reservation_id: Option<ReservationId>) {
+    let reservation_id = match reservation_id {
+        Some(reservation_id) => reservation_id,
+        None => return,
+    };
+
+    match exchange.get_balance_manager() {
+        Some(balance_manager) => {
+            if let Err(error) = balance_manager.lock().unreserve_rest(reservation_id) {
+                log::error!("Unable to release reservation {reservation_id}: {error:?}");
+            }
+        }
+        None => log::warn!(
+            "Unable to release reservation {reservation_id}: BalanceManager isn't set up on {}",
+            exchange.exchange_account_id
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mmb_utils::hashmap;
+
+    use super::*;
+    use crate::balance_manager::tests::balance_manager_base::BalanceManagerBase;
+    use crate::balance_manager::tests::balance_manager_ordinal::BalanceManagerOrdinal;
+    use crate::error::MmbError;
+    use crate::exchanges::general::test_helper::get_test_exchange_with_symbol_and_id;
+    use crate::test_util::SymbolBuilder;
+
+    fn builder() -> OrderBuilder {
+        OrderBuilder::new(
+            "Binance_0".parse().expect("in test"),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        )
+    }
+
+    #[test]
+    fn build_limit_order() {
+        let order = builder()
+            .sell()
+            .limit(dec!(100))
+            .amount(dec!(2))
+            .maker_only()
+            .build()
+            .expect("in test");
+
+        assert_eq!(order.price, dec!(100));
+        assert_eq!(order.header.side, OrderSide::Sell);
+        assert_eq!(order.header.order_type, OrderType::Limit);
+        assert_eq!(order.header.amount, dec!(2));
+        assert_eq!(order.header.execution_type, OrderExecutionType::MakerOnly);
+        assert_eq!(order.header.strategy_name, DEFAULT_STRATEGY_NAME);
+        assert_eq!(order.header.position_side, None);
+        assert!(!order.header.reduce_only);
+    }
+
+    #[test]
+    fn build_market_order_without_price() {
+        let order = builder()
+            .buy()
+            .market(None)
+            .amount(dec!(1))
+            .position_side(PositionSide::Long)
+            .reduce_only()
+            .build()
+            .expect("in test");
+
+        assert_eq!(order.header.order_type, OrderType::Market);
+        assert_eq!(order.header.position_side, Some(PositionSide::Long));
+        assert!(order.header.reduce_only);
+        assert_eq!(order.header.amount_kind, OrderAmountKind::Base);
+    }
+
+    #[test]
+    fn build_market_order_with_quote_amount() {
+        let order = builder()
+            .buy()
+            .market(None)
+            .quote_amount(dec!(100))
+            .build()
+            .expect("in test");
+
+        assert_eq!(order.header.amount, dec!(100));
+        assert_eq!(order.header.amount_kind, OrderAmountKind::Quote);
+
+        assert_eq!(
+            builder()
+                .buy()
+                .limit(dec!(1))
+                .quote_amount(dec!(100))
+                .build()
+                .expect_err("in test"),
+            OrderBuildError::QuoteAmountForNonMarketOrder
+        );
+    }
+
+    #[test]
+    fn build_good_till_date_order() {
+        let expire_at = chrono::Utc::now() + chrono::Duration::minutes(5);
+        let order = builder()
+            .buy()
+            .limit(dec!(100))
+            .amount(dec!(1))
+            .expire_at(expire_at)
+            .build()
+            .expect("in test");
+
+        assert_eq!(order.header.expire_at, Some(expire_at));
+        assert!(!order
+            .header
+            .is_expired(expire_at - chrono::Duration::seconds(1)));
+        assert!(order.header.is_expired(expire_at));
+    }
+
+    #[test]
+    fn invalid_orders_are_not_built() {
+        let error = |builder: OrderBuilder| builder.build().expect_err("in test");
+
+        assert_eq!(
+            error(builder().limit(dec!(1)).amount(dec!(1))),
+            OrderBuildError::SideIsNotSpecified
+        );
+        assert_eq!(
+            error(builder().buy().amount(dec!(1))),
+            OrderBuildError::PriceIsNotSpecified
+        );
+        assert_eq!(
+            error(builder().buy().limit(dec!(1))),
+            OrderBuildError::AmountIsNotSpecified
+        );
+        assert_eq!(
+            error(builder().buy().limit(dec!(1)).amount(dec!(-1))),
+            OrderBuildError::NonPositiveAmount(dec!(-1))
+        );
+        assert_eq!(
+            error(builder().buy().limit(dec!(0)).amount(dec!(1))),
+            OrderBuildError::NonPositivePrice(dec!(0))
+        );
+    }
+
+    #[test]
+    fn limit_price_is_validated_by_symbol_precision() {
+        let symbol = SymbolBuilder::new("btc".into(), "usdt".into()).build();
+        let order = |side| builder().side(side).limit(dec!(100.15)).amount(dec!(1));
+
+        assert_eq!(
+            order(OrderSide::Buy).validate_by_symbol(&symbol),
+            Err(OrderBuildError::PriceIsNotRounded {
+                price: dec!(100.15),
+                rounded_price: dec!(100.1)
+            })
+        );
+        assert_eq!(
+            order(OrderSide::Sell).validate_by_symbol(&symbol),
+            Err(OrderBuildError::PriceIsNotRounded {
+                price: dec!(100.15),
+                rounded_price: dec!(100.2)
+            })
+        );
+        assert_eq!(
+            builder()
+                .buy()
+                .limit(dec!(100.1))
+                .amount(dec!(1))
+                .validate_by_symbol(&symbol),
+            Ok(())
+        );
+    }
+
+    #[tokio::test]
+    async fn reservation_is_released_if_order_is_not_created() {
+        let (symbol, balance_manager) = BalanceManagerOrdinal::create_balance_manager();
+        let mut test_object = BalanceManagerBase::new();
+        test_object.set_balance_manager(balance_manager.clone());
+        test_object.set_symbol(symbol.clone());
+
+        let exchange_account_id = test_object.exchange_account_id_1;
+        BalanceManagerBase::update_balance(
+            &mut *test_object.balance_manager(),
+            exchange_account_id,
+            hashmap![BalanceManagerBase::btc() => dec!(10)],
+        );
+
+        let (exchange, _rx) =
+            get_test_exchange_with_symbol_and_id(symbol.clone(), exchange_account_id);
+        exchange.setup_balance_manager(balance_manager.clone());
+
+        // Balance is reserved for quote amount, but limit order with quote amount isn't built
+        let order_builder = OrderBuilder::new(exchange_account_id, test_object.currency_pair)
+            .buy()
+            .limit(dec!(0.2))
+            .quote_amount(dec!(1))
+            .reserve(
+                &balance_manager,
+                test_object.configuration_descriptor,
+                symbol,
+            )
+            .expect("in test");
+        assert_eq!(balance_manager.lock().get_reservation_ids().len(), 1);
+
+        let error = order_builder
+            .create(&exchange, CancellationToken::default())
+            .await
+            .expect_err("in test");
+
+        assert!(matches!(error, MmbError::Validation { .. }));
+        assert!(balance_manager.lock().get_reservation_ids().is_empty());
+    }
+}
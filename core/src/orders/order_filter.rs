@@ -0,0 +1,262 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{Datelike, Timelike};
+use parking_lot::RwLock;
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use rust_decimal::prelude::ToPrimitive;
+
+use crate::exchanges::common::Price;
+use crate::misc::time::time_manager;
+use crate::orders::order::{OrderAmountKind, OrderCreating};
+
+/// Name of script function which is called for every order before submission
+const FILTER_FUNCTION: &str = "filter";
+/// Limit of script operations to prevent hanging of order creation
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// Last-mile rules for orders written by operators in rhai script without recompiling of engine.
+/// Script defines function `filter(order)` which returns `true` (or nothing) to submit order,
+/// `false` or string with reason to reject it:
+///
+/// ```text
+/// fn filter(order) {
+///     if order.currency_pair == "btc/usdt" && order.hour >= 22 && order.notional > 1000.0 {
+///         return "Notional limit for btc/usdt after 22:00 UTC";
+///     }
+///     true
+/// }
+/// ```
+///
+/// Order is passed as map with fields `exchange_account_id`, `currency_pair`, `side`, `order_type`,
+/// `execution_type`, `strategy_name`, `price`, `amount`, `notional` and current UTC time `hour`, `minute`, `weekday`.
+/// `amount` is in base currency and `notional` is in quote currency for both kinds of order amount.
+/// Market orders are priced by top of order book. Orders are rejected if script fails
+pub struct OrderFilter {
+    engine: Engine,
+    script_path: Option<String>,
+    ast: RwLock<Option<AST>>,
+}
+
+impl OrderFilter {
+    /// Filter accepts all orders if script isn't specified
+    pub fn new(script_path: Option<String>) -> Result<Self> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+
+        let order_filter = Self {
+            engine,
+            script_path,
+            ast: RwLock::new(None),
+        };
+
+        if order_filter.script_path.is_some() {
+            order_filter.reload()?;
+        }
+
+        Ok(order_filter)
+    }
+
+    /// Reads script file again. Previous script is kept if new one can't be compiled
+    pub fn reload(&self) -> Result<()> {
+        let script_path = match &self.script_path {
+            Some(script_path) => script_path,
+            None => bail!("Script of order filter isn't specified in settings"),
+        };
+
+        let script = std::fs::read_to_string(script_path)
+            .with_context(|| format!("Unable to read order filter script {}", script_path))?;
+        let ast = self.compile(&script)?;
+
+        *self.ast.write() = Some(ast);
+        log::info!("Order filter script {} is loaded", script_path);
+
+        Ok(())
+    }
+
+    fn compile(&self, script: &str) -> Result<AST> {
+        let ast = self
+            .engine
+            .compile(script)
+            .map_err(|error| anyhow!("Unable to compile order filter script: {}", error))?;
+
+        if !ast
+            .iter_functions()
+            .any(|function| function.name == FILTER_FUNCTION && function.params.len() == 1)
+        {
+            bail!(
+                "Order filter script should contain function `{}(order)`",
+                FILTER_FUNCTION
+            );
+        }
+
+        Ok(ast)
+    }
+
+    /// Returns error with reason if order is rejected by script.
+    /// `price` is price of order or estimated price of market order
+    pub fn check(&self, order: &OrderCreating, price: Price) -> Result<()> {
+        let ast = self.ast.read();
+        let ast = match ast.as_ref() {
+            Some(ast) => ast,
+            None => return Ok(()),
+        };
+
+        let result = self
+            .engine
+            .call_fn::<Dynamic>(
+                &mut Scope::new(),
+                ast,
+                FILTER_FUNCTION,
+                (Dynamic::from(order_to_map(order, price)),),
+            )
+            .map_err(|error| anyhow!("Order filter script failed: {}", error))?;
+
+        if result.is::<()>() {
+            return Ok(());
+        }
+
+        if let Some(is_accepted) = result.clone().try_cast::<bool>() {
+            return match is_accepted {
+                true => Ok(()),
+                false => Err(anyhow!("Order is rejected by order filter script")),
+            };
+        }
+
+        match result.into_string() {
+            Ok(reason) => Err(anyhow!(
+                "Order is rejected by order filter script: {}",
+                reason
+            )),
+            Err(type_name) => Err(anyhow!(
+                "Order filter script returned unexpected value of type {}",
+                type_name
+            )),
+        }
+    }
+}
+
+fn order_to_map(order: &OrderCreating, price: Price) -> Map {
+    let header = &order.header;
+    let to_float = |value: rust_decimal::Decimal| value.to_f64().unwrap_or(f64::NAN);
+    let now = time_manager::now();
+
+    let mut map = Map::new();
+    let mut insert = |key: &str, value: Dynamic| {
+        map.insert(key.into(), value);
+    };
+
+    insert(
+        "exchange_account_id",
+        header.exchange_account_id.to_string().into(),
+    );
+    insert("currency_pair", header.currency_pair.to_string().into());
+    insert("side", format!("{:?}", header.side).into());
+    insert("order_type", format!("{:?}", header.order_type).into());
+    insert(
+        "execution_type",
+        format!("{:?}", header.execution_type).into(),
+    );
+    insert("strategy_name", header.strategy_name.clone().into());
+    let notional = match header.amount_kind {
+        OrderAmountKind::Base => header.amount * price,
+        OrderAmountKind::Quote => header.amount,
+    };
+    insert("price", to_float(price).into());
+    insert(
+        "amount",
+        header.base_amount(price).map_or(f64::NAN, to_float).into(),
+    );
+    insert("notional", to_float(notional).into());
+    insert("hour", (now.hour() as i64).into());
+    insert("minute", (now.minute() as i64).into());
+    insert(
+        "weekday",
+        (now.weekday().number_from_monday() as i64).into(),
+    );
+
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::exchanges::common::CurrencyPair;
+    use crate::orders::order_builder::OrderBuilder;
+
+    fn order_filter(script: &str) -> OrderFilter {
+        let order_filter = OrderFilter::new(None).expect("in test");
+        let ast = order_filter.compile(script).expect("in test");
+        *order_filter.ast.write() = Some(ast);
+        order_filter
+    }
+
+    fn order(amount: rust_decimal::Decimal) -> OrderCreating {
+        OrderBuilder::new(
+            "Binance_0".parse().expect("in test"),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        )
+        .buy()
+        .limit(dec!(100))
+        .amount(amount)
+        .build()
+        .expect("in test")
+    }
+
+    #[test]
+    fn orders_are_accepted_without_script() {
+        let order_filter = OrderFilter::new(None).expect("in test");
+        assert!(order_filter.check(&order(dec!(1)), dec!(100)).is_ok());
+        assert!(order_filter.reload().is_err());
+    }
+
+    #[test]
+    fn orders_are_filtered_by_script() {
+        let order_filter = order_filter(
+            r#"
+            fn filter(order) {
+                if order.currency_pair == "btc/usdt" && order.notional > 1000.0 {
+                    return "Notional is too big";
+                }
+                order.side == "Buy"
+            }
+            "#,
+        );
+
+        assert!(order_filter.check(&order(dec!(1)), dec!(100)).is_ok());
+
+        let error = order_filter
+            .check(&order(dec!(11)), dec!(100))
+            .expect_err("in test");
+        assert!(error.to_string().contains("Notional is too big"));
+    }
+
+    #[test]
+    fn quote_amount_of_market_order_is_converted_by_estimated_price() {
+        let order_filter = order_filter(
+            r#"
+            fn filter(order) {
+                order.amount == 4.0 && order.notional == 400.0
+            }
+            "#,
+        );
+        let order = OrderBuilder::new(
+            "Binance_0".parse().expect("in test"),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        )
+        .sell()
+        .market(None)
+        .quote_amount(dec!(400))
+        .build()
+        .expect("in test");
+
+        assert!(order_filter.check(&order, dec!(100)).is_ok());
+        assert!(order_filter.check(&order, dec!(50)).is_err());
+    }
+
+    #[test]
+    fn script_without_filter_function_is_not_compiled() {
+        let order_filter = OrderFilter::new(None).expect("in test");
+        assert!(order_filter.compile("fn check(order) { true }").is_err());
+    }
+}
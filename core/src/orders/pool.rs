@@ -12,7 +12,8 @@ use super::{
 };
 use crate::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId, MarketAccountId};
 use crate::orders::order::{
-    ClientOrderId, ExchangeOrderId, OrderHeader, OrderSimpleProps, OrderSnapshot, OrderStatus,
+    ClientOrderId, ExchangeOrderId, OrderAmountKind, OrderHeader, OrderSimpleProps, OrderSnapshot,
+    OrderStatus,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +41,15 @@ impl OrderRef {
     pub fn amount(&self) -> Decimal {
         self.fn_ref(|x| x.header.amount)
     }
+    pub fn amount_kind(&self) -> OrderAmountKind {
+        self.fn_ref(|x| x.header.amount_kind)
+    }
+    pub fn base_amount(&self) -> Option<Amount> {
+        self.fn_ref(|x| x.base_amount())
+    }
+    pub fn remaining_base_amount(&self) -> Option<Amount> {
+        self.fn_ref(|x| x.remaining_base_amount())
+    }
     pub fn status(&self) -> OrderStatus {
         self.fn_ref(|x| x.props.status)
     }
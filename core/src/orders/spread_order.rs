@@ -0,0 +1,566 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use futures::future::join;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::{DateTime, OPERATION_CANCELED_MSG};
+use parking_lot::Mutex;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::exchanges::common::Amount;
+use crate::exchanges::events::ExchangeEvent;
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::symbol::{Round, Symbol};
+use crate::misc::time::time_manager;
+use crate::orders::order::{OrderCreating, ReservationId};
+use crate::orders::order_builder::{
+    create_order_with_reservation, release_reservation, reserve_order, OrderBuilder,
+};
+use crate::orders::pool::OrderRef;
+use crate::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+
+/// Leg of spread order, e.g. buy order on spot market or sell order on perpetual market.
+/// Balance is reserved for leg by `SpreadOrder::create` unless order already has reservation
+pub struct SpreadLeg {
+    pub exchange: Arc<Exchange>,
+    pub order: OrderCreating,
+}
+
+impl SpreadLeg {
+    /// Leg order with reservation and id of reservation which is made for it
+    fn reserve(
+        &self,
+        configuration_descriptor: ConfigurationDescriptor,
+    ) -> Result<(OrderCreating, Option<ReservationId>)> {
+        if self.order.header.reservation_id.is_some() {
+            return Ok((self.order.clone(), None));
+        }
+
+        let (order, reservation_id) =
+            reserve_order(&self.exchange, &self.order, configuration_descriptor)?;
+        Ok((order, Some(reservation_id)))
+    }
+}
+
+/// Hedging of amount filled by one leg and not filled by the other one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HedgePolicy {
+    /// Time during which legs can be filled unequally. After that the rest of lagging leg
+    /// is cancelled and the difference is hedged by market order on market of lagging leg
+    pub max_unbalanced_time: Duration,
+    /// Difference of leg fills which is less than this amount isn't hedged
+    pub min_hedge_amount: Amount,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SpreadOrderStatus {
+    /// Legs are filled equally and at least one of them isn't finished
+    Open,
+    /// One leg is filled more than the other one
+    Unbalanced,
+    /// Market order which hedges difference of leg fills isn't finished
+    Hedging,
+    /// Legs are finished and fully filled
+    Filled,
+    /// Legs are finished and filled equally, but not fully
+    Canceled,
+    /// Hedging of difference of leg fills failed
+    Failed,
+    /// Legs are finished, but difference of leg fills isn't hedged, because it's less than
+    /// min amount of symbol or `min_hedge_amount` after rounding
+    Unhedgeable,
+}
+
+struct SpreadLegState {
+    exchange: Arc<Exchange>,
+    order: OrderRef,
+    hedge_orders: Mutex<Vec<OrderRef>>,
+}
+
+impl SpreadLegState {
+    fn snapshot(&self) -> LegSnapshot {
+        let hedge_orders = self.hedge_orders.lock();
+        LegSnapshot {
+            filled_amount: self.order.filled_amount()
+                + hedge_orders
+                    .iter()
+                    .map(|x| x.filled_amount())
+                    .sum::<Amount>(),
+            is_finished: self.order.is_finished(),
+            is_hedging: hedge_orders.iter().any(|x| !x.is_finished()),
+        }
+    }
+
+    async fn cancel(&self, cancellation_token: CancellationToken) -> Result<()> {
+        if !self.order.is_finished() {
+            let _ = self
+                .exchange
+                .wait_cancel_order_confirmed(self.order.clone(), None, true, cancellation_token)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    fn contains(&self, order: &OrderRef) -> bool {
+        let client_order_id = order.client_order_id();
+        self.order.client_order_id() == client_order_id
+            || self
+                .hedge_orders
+                .lock()
+                .iter()
+                .any(|x| x.client_order_id() == client_order_id)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LegSnapshot {
+    /// Filled amount of leg order together with its hedge orders
+    filled_amount: Amount,
+    is_finished: bool,
+    is_hedging: bool,
+}
+
+/// `unhedgeable_imbalance` is difference of leg fills which is found unhedgeable by the last
+/// attempt of hedging. Other difference is hedged again, because legs are filled further
+fn combined_status(
+    amount: Amount,
+    legs: [LegSnapshot; 2],
+    min_hedge_amount: Amount,
+    unhedgeable_imbalance: Option<Amount>,
+) -> SpreadOrderStatus {
+    if legs.iter().any(|x| x.is_hedging) {
+        return SpreadOrderStatus::Hedging;
+    }
+
+    let imbalance = (legs[0].filled_amount - legs[1].filled_amount).abs();
+    if !imbalance.is_zero() && imbalance >= min_hedge_amount {
+        if unhedgeable_imbalance != Some(imbalance) {
+            return SpreadOrderStatus::Unbalanced;
+        }
+
+        return match legs.iter().all(|x| x.is_finished) {
+            true => SpreadOrderStatus::Unhedgeable,
+            false => SpreadOrderStatus::Open,
+        };
+    }
+
+    if !legs.iter().all(|x| x.is_finished) {
+        return SpreadOrderStatus::Open;
+    }
+
+    match legs.iter().all(|x| x.filled_amount >= amount) {
+        true => SpreadOrderStatus::Filled,
+        false => SpreadOrderStatus::Canceled,
+    }
+}
+
+/// Amount of hedge order which covers difference of leg fills.
+/// Returns `None` if rounded difference can't be placed or is less than `min_hedge_amount`
+fn hedge_amount(imbalance: Amount, symbol: &Symbol, min_hedge_amount: Amount) -> Option<Amount> {
+    let amount = symbol.amount_round(imbalance, Round::Floor);
+    let min_amount = symbol.min_amount.unwrap_or_default().max(min_hedge_amount);
+    match amount > dec!(0) && amount >= min_amount {
+        true => Some(amount),
+        false => None,
+    }
+}
+
+struct SpreadState {
+    is_failed: bool,
+    unbalanced_since: Option<DateTime>,
+    unhedgeable_imbalance: Option<Amount>,
+}
+
+/// Simultaneous orders on two markets with the same amount (e.g. buy on spot and sell
+/// on perpetual market for basis trading) which are tracked as a whole. If one leg is filled
+/// first, the difference of fills is hedged according to `HedgePolicy`.
+/// Amounts of legs are in the same base currency. Balance of legs and hedge orders is reserved
+/// for strategy with `configuration_descriptor`
+pub struct SpreadOrder {
+    amount: Amount,
+    legs: [SpreadLegState; 2],
+    hedge_policy: HedgePolicy,
+    configuration_descriptor: ConfigurationDescriptor,
+    state: Mutex<SpreadState>,
+}
+
+impl SpreadOrder {
+    /// Places both legs at the same time. If placement of one leg fails, the other one is cancelled
+    pub async fn create(
+        first: SpreadLeg,
+        second: SpreadLeg,
+        hedge_policy: HedgePolicy,
+        configuration_descriptor: ConfigurationDescriptor,
+        cancellation_token: CancellationToken,
+    ) -> Result<Arc<Self>> {
+        let amount = first.order.header.amount;
+        if second.order.header.amount != amount {
+            bail!(
+                "Amounts of spread legs should be equal: {} and {}",
+                amount,
+                second.order.header.amount
+            );
+        }
+
+        let (first_order, first_reservation_id) = first
+            .reserve(configuration_descriptor)
+            .context("Unable to reserve balance for first leg of spread order")?;
+        let (second_order, second_reservation_id) = match second.reserve(configuration_descriptor) {
+            Ok(reserved) => reserved,
+            Err(error) => {
+                release_reservation(&first.exchange, first_reservation_id);
+                return Err(error)
+                    .context("Unable to reserve balance for second leg of spread order");
+            }
+        };
+
+        let (first_result, second_result) = join(
+            create_order_with_reservation(
+                &first.exchange,
+                &first_order,
+                first_reservation_id,
+                cancellation_token.clone(),
+            ),
+            create_order_with_reservation(
+                &second.exchange,
+                &second_order,
+                second_reservation_id,
+                cancellation_token.clone(),
+            ),
+        )
+        .await;
+
+        let (first_order, second_order) = match (first_result, second_result) {
+            (Ok(first_order), Ok(second_order)) => (first_order, second_order),
+            (Ok(placed_order), Err(error)) => {
+                cancel_placed_leg(&first.exchange, placed_order, cancellation_token).await;
+                return Err(error).context("Unable to place second leg of spread order");
+            }
+            (Err(error), Ok(placed_order)) => {
+                cancel_placed_leg(&second.exchange, placed_order, cancellation_token).await;
+                return Err(error).context("Unable to place first leg of spread order");
+            }
+            (Err(error), Err(_)) => {
+                return Err(error).context("Unable to place legs of spread order")
+            }
+        };
+
+        let leg_state = |exchange, order| SpreadLegState {
+            exchange,
+            order,
+            hedge_orders: Mutex::new(vec![]),
+        };
+
+        Ok(Arc::new(Self {
+            amount,
+            legs: [
+                leg_state(first.exchange, first_order),
+                leg_state(second.exchange, second_order),
+            ],
+            hedge_policy,
+            configuration_descriptor,
+            state: Mutex::new(SpreadState {
+                is_failed: false,
+                unbalanced_since: None,
+                unhedgeable_imbalance: None,
+            }),
+        }))
+    }
+
+    pub fn status(&self) -> SpreadOrderStatus {
+        let (is_failed, unhedgeable_imbalance) = {
+            let state = self.state.lock();
+            (state.is_failed, state.unhedgeable_imbalance)
+        };
+        if is_failed {
+            return SpreadOrderStatus::Failed;
+        }
+
+        combined_status(
+            self.amount,
+            self.leg_snapshots(),
+            self.hedge_policy.min_hedge_amount,
+            unhedgeable_imbalance,
+        )
+    }
+
+    pub fn amount(&self) -> Amount {
+        self.amount
+    }
+
+    /// Leg orders without hedge orders
+    pub fn leg_orders(&self) -> [OrderRef; 2] {
+        [self.legs[0].order.clone(), self.legs[1].order.clone()]
+    }
+
+    /// Filled amounts of legs including their hedge orders
+    pub fn filled_amounts(&self) -> [Amount; 2] {
+        let [first, second] = self.leg_snapshots();
+        [first.filled_amount, second.filled_amount]
+    }
+
+    pub fn contains(&self, order: &OrderRef) -> bool {
+        self.legs.iter().any(|x| x.contains(order))
+    }
+
+    /// Tracks fills of legs by order events and hedges difference of fills until spread order is
+    /// finished. Returns final status, which is `Filled`, `Canceled` or `Unhedgeable`
+    pub async fn run(
+        self: Arc<Self>,
+        mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+        cancellation_token: CancellationToken,
+    ) -> Result<SpreadOrderStatus> {
+        loop {
+            let status = self.status();
+            let hedge_delay = match status {
+                SpreadOrderStatus::Filled
+                | SpreadOrderStatus::Canceled
+                | SpreadOrderStatus::Unhedgeable => return Ok(status),
+                SpreadOrderStatus::Unbalanced => Some(self.hedge_delay(time_manager::now())),
+                _ => {
+                    self.state.lock().unbalanced_since = None;
+                    None
+                }
+            };
+
+            tokio::select! {
+                event = events_receiver.recv() => match event {
+                    Ok(ExchangeEvent::OrderEvent(order_event)) if self.contains(&order_event.order) => {}
+                    Ok(_) => continue,
+                    // Statuses of orders are checked again, so skipped events aren't lost
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => bail!("Events channel of spread order is closed"),
+                },
+                _ = tokio::time::sleep(hedge_delay.unwrap_or_default()), if hedge_delay.is_some() => {
+                    if let Err(error) = self.hedge(cancellation_token.clone()).await {
+                        self.state.lock().is_failed = true;
+                        return Err(error).context("Unable to hedge spread order");
+                    }
+                }
+                _ = cancellation_token.when_cancelled() => bail!(OPERATION_CANCELED_MSG),
+            }
+        }
+    }
+
+    /// Cancels not finished leg orders. Difference of fills is still hedged by `run()`
+    pub async fn cancel(&self, cancellation_token: CancellationToken) -> Result<()> {
+        let (first_result, second_result) = join(
+            self.legs[0].cancel(cancellation_token.clone()),
+            self.legs[1].cancel(cancellation_token),
+        )
+        .await;
+
+        first_result.and(second_result)
+    }
+
+    fn leg_snapshots(&self) -> [LegSnapshot; 2] {
+        [self.legs[0].snapshot(), self.legs[1].snapshot()]
+    }
+
+    fn hedge_delay(&self, now: DateTime) -> Duration {
+        let unbalanced_since = *self.state.lock().unbalanced_since.get_or_insert(now);
+        let unbalanced_time = (now - unbalanced_since).to_std().unwrap_or_default();
+        self.hedge_policy
+            .max_unbalanced_time
+            .saturating_sub(unbalanced_time)
+    }
+
+    async fn hedge(&self, cancellation_token: CancellationToken) -> Result<()> {
+        let lagging_leg = match self.lagging_leg() {
+            Some(lagging_leg) => lagging_leg,
+            None => return Ok(()),
+        };
+
+        lagging_leg.exchange.check_trading_enabled()?;
+
+        // Lagging leg is cancelled, so it can't be filled in addition to hedge order.
+        // Hedging is stopped if exchange doesn't confirm cancellation
+        lagging_leg.cancel(cancellation_token.clone()).await?;
+
+        // Fills received during cancellation are taken into account
+        let lagging_leg = match self.lagging_leg() {
+            Some(lagging_leg) => lagging_leg,
+            None => return Ok(()),
+        };
+        let [first, second] = self.filled_amounts();
+        let imbalance = (first - second).abs();
+        let header = lagging_leg.order.fn_ref(|x| x.header.clone());
+        let symbol = lagging_leg.exchange.get_symbol(header.currency_pair)?;
+        let amount = match hedge_amount(imbalance, &symbol, self.hedge_policy.min_hedge_amount) {
+            Some(amount) => amount,
+            None => {
+                log::warn!(
+                    "Difference {} of fills of spread order leg {} on {} is too small to be hedged",
+                    imbalance,
+                    header.client_order_id,
+                    header.exchange_account_id
+                );
+                let mut state = self.state.lock();
+                state.unhedgeable_imbalance = Some(imbalance);
+                state.unbalanced_since = None;
+                return Ok(());
+            }
+        };
+
+        log::info!(
+            "Hedging {} of spread order leg {} on {}",
+            amount,
+            header.client_order_id,
+            header.exchange_account_id
+        );
+
+        let mut builder = OrderBuilder::new(header.exchange_account_id, header.currency_pair)
+            .side(header.side)
+            .market(Some(lagging_leg.order.price()))
+            .amount(amount)
+            .strategy_name(header.strategy_name.clone());
+        if let Some(position_side) = header.position_side {
+            builder = builder.position_side(position_side);
+        }
+
+        let balance_manager = lagging_leg
+            .exchange
+            .get_balance_manager()
+            .with_context(|| {
+                format!(
+                    "Unable to reserve balance for hedge order: BalanceManager isn't set up on {}",
+                    header.exchange_account_id
+                )
+            })?;
+        let hedge_order = builder
+            .reserve(&balance_manager, self.configuration_descriptor, symbol)?
+            .create(&lagging_leg.exchange, cancellation_token)
+            .await?;
+        lagging_leg.hedge_orders.lock().push(hedge_order);
+        self.state.lock().unbalanced_since = None;
+
+        Ok(())
+    }
+
+    /// Leg which is filled less than the other one
+    fn lagging_leg(&self) -> Option<&SpreadLegState> {
+        let [first, second] = self.filled_amounts();
+        match first.cmp(&second) {
+            std::cmp::Ordering::Less => Some(&self.legs[0]),
+            std::cmp::Ordering::Greater => Some(&self.legs[1]),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+}
+
+async fn cancel_placed_leg(
+    exchange: &Exchange,
+    order: OrderRef,
+    cancellation_token: CancellationToken,
+) {
+    let client_order_id = order.client_order_id();
+    if let Err(error) = exchange
+        .wait_cancel_order_confirmed(order.clone(), None, true, cancellation_token)
+        .await
+    {
+        log::error!(
+            "Unable to cancel leg {} of failed spread order on {}: {:?}",
+            client_order_id,
+            exchange.exchange_account_id,
+            error
+        );
+        return;
+    }
+
+    let filled_amount = order.filled_amount();
+    if !filled_amount.is_zero() {
+        log::error!(
+            "Leg {} of failed spread order on {} is filled by {} before cancellation and isn't hedged",
+            client_order_id,
+            exchange.exchange_account_id,
+            filled_amount
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::SymbolBuilder;
+
+    fn leg(filled_amount: Amount, is_finished: bool, is_hedging: bool) -> LegSnapshot {
+        LegSnapshot {
+            filled_amount,
+            is_finished,
+            is_hedging,
+        }
+    }
+
+    fn symbol() -> Symbol {
+        SymbolBuilder::new("btc".into(), "usdt".into())
+            .min_amount(dec!(0.01))
+            .build()
+    }
+
+    #[test]
+    fn combined_status_of_legs() {
+        let amount = dec!(1);
+        let min_hedge_amount = dec!(0.01);
+        let status = |legs| combined_status(amount, legs, min_hedge_amount, None);
+
+        assert_eq!(
+            status([leg(dec!(0), false, false), leg(dec!(0), false, false)]),
+            SpreadOrderStatus::Open
+        );
+        assert_eq!(
+            status([leg(dec!(0.5), false, false), leg(dec!(0.2), false, false)]),
+            SpreadOrderStatus::Unbalanced
+        );
+        // Difference less than min hedge amount isn't hedged
+        assert_eq!(
+            status([leg(dec!(0.5), false, false), leg(dec!(0.495), false, false)]),
+            SpreadOrderStatus::Open
+        );
+        assert_eq!(
+            status([leg(dec!(1), true, false), leg(dec!(0.2), true, true)]),
+            SpreadOrderStatus::Hedging
+        );
+        assert_eq!(
+            status([leg(dec!(1), true, false), leg(dec!(1), true, false)]),
+            SpreadOrderStatus::Filled
+        );
+        assert_eq!(
+            status([leg(dec!(0.5), true, false), leg(dec!(0.5), true, false)]),
+            SpreadOrderStatus::Canceled
+        );
+    }
+
+    #[test]
+    fn residual_below_min_amount_is_unhedgeable() {
+        let symbol = symbol();
+        let amount = dec!(1);
+        let min_hedge_amount = dec!(0.001);
+
+        assert_eq!(
+            hedge_amount(dec!(0.0157), &symbol, min_hedge_amount),
+            Some(dec!(0.015))
+        );
+        let imbalance = dec!(0.005);
+        assert_eq!(hedge_amount(imbalance, &symbol, min_hedge_amount), None);
+
+        let status = |legs| combined_status(amount, legs, min_hedge_amount, Some(imbalance));
+        assert_eq!(
+            status([leg(dec!(0.5), false, false), leg(dec!(0.495), true, false)]),
+            SpreadOrderStatus::Open
+        );
+        assert_eq!(
+            status([leg(dec!(0.5), true, false), leg(dec!(0.495), true, false)]),
+            SpreadOrderStatus::Unhedgeable
+        );
+        // Leading leg is filled further, so difference is hedged again
+        assert_eq!(
+            status([leg(dec!(0.6), false, false), leg(dec!(0.495), true, false)]),
+            SpreadOrderStatus::Unbalanced
+        );
+    }
+}
@@ -0,0 +1,509 @@
+use anyhow::{Context, Result};
+use chrono::Duration;
+use hyper::Uri;
+use mmb_utils::DateTime;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::connectivity::connectivity_manager::WebSocketRole;
+use crate::exchanges::common::{Amount, CurrencyCode, CurrencyPair, ExchangeAccountId, Price};
+use crate::exchanges::events::TradeId;
+use crate::exchanges::transport::{for_each_traffic_record, RestMethod, TrafficRecord};
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::misc::time::time_manager;
+use crate::orders::fill::EventSourceType;
+use crate::orders::order::{
+    ClientOrderId, ExchangeOrderId, OrderFillRole, OrderSide, OrderSnapshot, OrderStatus,
+};
+use crate::storage::order_history::ORDERS_LOG;
+use crate::storage::LogQuery;
+
+/// Messages about order can arrive after it's finished, e.g. trades of filled order
+const TRAFFIC_GRACE_PERIOD_SECS: i64 = 60;
+/// Order history is queried by periods, so orders older than 30 days aren't found
+const ORDER_HISTORY_PERIOD_DAYS: i64 = 1;
+const ORDER_HISTORY_PERIODS_COUNT: usize = 30;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TimelineEvent {
+    Created {
+        price: Option<Price>,
+        strategy_name: String,
+    },
+    StatusChanged {
+        status: OrderStatus,
+    },
+    Fill {
+        trade_id: Option<TradeId>,
+        price: Price,
+        amount: Amount,
+        role: OrderFillRole,
+        commission_currency_code: CurrencyCode,
+        commission_amount: Amount,
+        source: Option<EventSourceType>,
+    },
+    /// Recorded REST request which mentions order
+    RestRequest {
+        method: RestMethod,
+        url: String,
+        body: String,
+        status: u16,
+        content: String,
+    },
+    /// Recorded websocket message which mentions order
+    WebSocketMessage {
+        role: WebSocketRole,
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimelineEntry {
+    pub time: DateTime,
+    /// Human-readable summary of event
+    pub description: String,
+    pub event: TimelineEvent,
+}
+
+/// Everything that happened to order in chronological order: creation, status changes, fills
+/// and recorded exchange traffic mentioning order id. It answers "what happened to order X"
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OrderTimeline {
+    pub client_order_id: ClientOrderId,
+    pub exchange_order_id: Option<ExchangeOrderId>,
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub side: OrderSide,
+    pub amount: Amount,
+    pub filled_amount: Amount,
+    pub status: OrderStatus,
+    /// REST requests and websocket messages aren't included if traffic of exchange isn't recorded
+    /// (see `traffic_record_path` of exchange settings)
+    pub is_traffic_recorded: bool,
+    pub entries: Vec<TimelineEntry>,
+}
+
+impl OrderTimeline {
+    /// Order is searched in order pools of exchanges, then in order history of the last 30 days.
+    /// Returns `None` if order isn't found
+    pub async fn collect(
+        engine_context: &EngineContext,
+        client_order_id: &ClientOrderId,
+    ) -> Result<Option<Self>> {
+        let order = match find_order(engine_context, client_order_id).await? {
+            Some(order) => order,
+            None => return Ok(None),
+        };
+
+        let traffic_record_path = engine_context
+            .app_settings
+            .exchanges
+            .iter()
+            .find(|x| x.exchange_account_id == order.header.exchange_account_id)
+            .and_then(|x| x.traffic_record_path.clone());
+        let is_traffic_recorded = traffic_record_path.is_some();
+        let traffic_entries = match traffic_record_path {
+            Some(path) => {
+                let traffic_filter = TrafficFilter::new(&order);
+                // Traffic record can be large, so it's read in a blocking thread record by record
+                tokio::task::spawn_blocking(move || {
+                    let mut entries = Vec::new();
+                    for_each_traffic_record(&path, |record| {
+                        entries.extend(traffic_filter.to_entry(record))
+                    })?;
+                    Ok::<_, anyhow::Error>(entries)
+                })
+                .await
+                .context("Unable to read traffic record")??
+            }
+            None => Vec::new(),
+        };
+
+        Ok(Some(Self::new(
+            &order,
+            traffic_entries,
+            is_traffic_recorded,
+        )))
+    }
+
+    fn new(
+        order: &OrderSnapshot,
+        traffic_entries: Vec<TimelineEntry>,
+        is_traffic_recorded: bool,
+    ) -> Self {
+        let header = &order.header;
+        let mut entries = vec![TimelineEntry {
+            time: header.init_time,
+            description: format!(
+                "Order is created by {}: {} {} {} at {}",
+                header.strategy_name,
+                header.side,
+                header.amount,
+                header.currency_pair,
+                order
+                    .props
+                    .raw_price
+                    .map_or_else(|| "market price".to_owned(), |x| x.to_string())
+            ),
+            event: TimelineEvent::Created {
+                price: order.props.raw_price,
+                strategy_name: header.strategy_name.clone(),
+            },
+        }];
+
+        for status_change in order.status_history.status_changes() {
+            let status = status_change.status();
+            let mut description = format!("Status is changed to {:?}", status);
+            let error_message = &order.internal_props.last_creation_error_message;
+            if status == OrderStatus::FailedToCreate && !error_message.is_empty() {
+                description += &format!(": {}", error_message);
+            }
+
+            entries.push(TimelineEntry {
+                time: status_change.time(),
+                description,
+                event: TimelineEvent::StatusChanged { status },
+            });
+        }
+
+        for fill in &order.fills.fills {
+            let trade = fill
+                .trade_id()
+                .map_or(String::new(), |x| format!(" by trade {}", x));
+            entries.push(TimelineEntry {
+                time: fill.receive_time(),
+                description: format!(
+                    "Filled {} at {} as {:?}{}, commission {} {}",
+                    fill.amount(),
+                    fill.price(),
+                    fill.role(),
+                    trade,
+                    fill.commission_amount(),
+                    fill.commission_currency_code()
+                ),
+                event: TimelineEvent::Fill {
+                    trade_id: fill.trade_id().cloned(),
+                    price: fill.price(),
+                    amount: fill.amount(),
+                    role: fill.role(),
+                    commission_currency_code: fill.commission_currency_code(),
+                    commission_amount: fill.commission_amount(),
+                    source: fill.event_source_type(),
+                },
+            });
+        }
+
+        entries.extend(traffic_entries);
+
+        // Sorting is stable, so events with the same time keep order of their sources
+        entries.sort_by_key(|x| x.time);
+
+        Self {
+            client_order_id: header.client_order_id.clone(),
+            exchange_order_id: order.props.exchange_order_id.clone(),
+            exchange_account_id: header.exchange_account_id,
+            currency_pair: header.currency_pair,
+            side: header.side,
+            amount: header.amount,
+            filled_amount: order.filled_amount(),
+            status: order.status(),
+            is_traffic_recorded,
+            entries,
+        }
+    }
+}
+
+/// Selects recorded traffic which mentions order during its lifetime
+struct TrafficFilter {
+    ids: Vec<String>,
+    from_time: DateTime,
+    to_time: Option<DateTime>,
+}
+
+impl TrafficFilter {
+    fn new(order: &OrderSnapshot) -> Self {
+        let ids = [
+            Some(order.header.client_order_id.as_str()),
+            order.props.exchange_order_id.as_ref().map(|x| x.as_str()),
+        ];
+        Self {
+            ids: ids
+                .iter()
+                .flatten()
+                .filter(|x| !x.is_empty())
+                .map(|x| x.to_string())
+                .collect(),
+            from_time: order.header.init_time,
+            to_time: order
+                .props
+                .finished_time
+                .map(|x| x + Duration::seconds(TRAFFIC_GRACE_PERIOD_SECS)),
+        }
+    }
+
+    fn mentions_order(&self, text: &str) -> bool {
+        self.ids.iter().any(|id| contains_id(text, id))
+    }
+
+    fn is_in_lifetime(&self, time: DateTime) -> bool {
+        time >= self.from_time && self.to_time.map_or(true, |to| time <= to)
+    }
+
+    fn to_entry(&self, record: TrafficRecord) -> Option<TimelineEntry> {
+        match record {
+            TrafficRecord::Rest {
+                timestamp,
+                method,
+                url,
+                body,
+                status,
+                content,
+            } if self.is_in_lifetime(timestamp)
+                && (self.mentions_order(&url)
+                    || self.mentions_order(&body)
+                    || self.mentions_order(&content)) =>
+            {
+                let path = url
+                    .parse::<Uri>()
+                    .map_or_else(|_| url.clone(), |x| x.path().to_owned());
+                Some(TimelineEntry {
+                    time: timestamp,
+                    description: format!(
+                        "REST request {:?} {} is responded with status {}",
+                        method, path, status
+                    ),
+                    event: TimelineEvent::RestRequest {
+                        method,
+                        url,
+                        body,
+                        status,
+                        content,
+                    },
+                })
+            }
+            TrafficRecord::WebSocket {
+                timestamp,
+                role,
+                message,
+            } if self.is_in_lifetime(timestamp) && self.mentions_order(&message) => {
+                Some(TimelineEntry {
+                    time: timestamp,
+                    description: format!("Websocket message is received by {:?} websocket", role),
+                    event: TimelineEvent::WebSocketMessage { role, message },
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+async fn find_order(
+    engine_context: &EngineContext,
+    client_order_id: &ClientOrderId,
+) -> Result<Option<OrderSnapshot>> {
+    for exchange in engine_context.exchanges.iter() {
+        if let Some(order) = exchange.orders.cache_by_client_id.get(client_order_id) {
+            return Ok(Some(order.deep_clone()));
+        }
+    }
+
+    // History contains all finished orders, so it's searched from the latest records back
+    // by periods and records are checked before deserialization
+    let mut to_time = None;
+    let mut from_time = time_manager::now() - Duration::days(ORDER_HISTORY_PERIOD_DAYS);
+    for _ in 0..ORDER_HISTORY_PERIODS_COUNT {
+        let query = LogQuery {
+            from_time: Some(from_time),
+            to_time,
+            ..LogQuery::default()
+        };
+        let record = engine_context
+            .storage
+            .query(ORDERS_LOG, &query)
+            .await?
+            .into_iter()
+            .rev()
+            .find(|record| {
+                record
+                    .value
+                    .pointer("/header/client_order_id")
+                    .and_then(Value::as_str)
+                    == Some(client_order_id.as_str())
+            });
+
+        if let Some(record) = record {
+            let order = serde_json::from_value(record.value)
+                .context("Unable to deserialize order from history")?;
+            return Ok(Some(order));
+        }
+
+        to_time = Some(from_time);
+        from_time = from_time - Duration::days(ORDER_HISTORY_PERIOD_DAYS);
+    }
+
+    Ok(None)
+}
+
+/// Id is mentioned if it isn't part of longer id or number, e.g. exchange order id `123`
+/// isn't found in price `1234.5`
+fn contains_id(text: &str, id: &str) -> bool {
+    let is_id_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.');
+    text.match_indices(id).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + id.len()..].chars().next();
+        !before.map_or(false, is_id_char) && !after.map_or(false, is_id_char)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{TimeZone, Utc};
+    use rust_decimal_macros::dec;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::orders::fill::{OrderFill, OrderFillType};
+    use crate::orders::order::OrderType;
+
+    fn time(secs: u32) -> DateTime {
+        Utc.ymd(2022, 3, 1).and_hms(0, 0, secs)
+    }
+
+    fn order() -> OrderSnapshot {
+        let mut order = OrderSnapshot::with_params(
+            "client-1".into(),
+            OrderType::Limit,
+            None,
+            ExchangeAccountId::new("Binance".into(), 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            dec!(100),
+            dec!(1),
+            OrderSide::Buy,
+            None,
+            "test",
+        );
+        Arc::make_mut(&mut order.header).init_time = time(0);
+        order.props.exchange_order_id = Some("555".into());
+        order.set_status(OrderStatus::Created, time(1));
+        order.add_fill(OrderFill::new(
+            Uuid::default(),
+            None,
+            time(3),
+            OrderFillType::UserTrade,
+            Some(TradeId::Number(7)),
+            dec!(100),
+            dec!(1),
+            dec!(100),
+            OrderFillRole::Maker,
+            "usdt".into(),
+            dec!(0.1),
+            dec!(0),
+            "usdt".into(),
+            dec!(0.1),
+            dec!(0.1),
+            false,
+            Some(EventSourceType::WebSocket),
+            Some(OrderSide::Buy),
+        ));
+        order.set_status(OrderStatus::Completed, time(3));
+        order.props.finished_time = Some(time(3));
+        order
+    }
+
+    fn timeline(order: &OrderSnapshot, traffic: Vec<TrafficRecord>) -> OrderTimeline {
+        let traffic_filter = TrafficFilter::new(order);
+        let traffic_entries = traffic
+            .into_iter()
+            .filter_map(|x| traffic_filter.to_entry(x))
+            .collect();
+        OrderTimeline::new(order, traffic_entries, true)
+    }
+
+    fn websocket_record(secs: u32, message: &str) -> TrafficRecord {
+        TrafficRecord::WebSocket {
+            timestamp: time(secs),
+            role: WebSocketRole::Main,
+            message: message.to_owned(),
+        }
+    }
+
+    #[test]
+    fn timeline_contains_events_of_order_in_chronological_order() {
+        let traffic = vec![
+            TrafficRecord::Rest {
+                timestamp: time(1),
+                method: RestMethod::Post,
+                url: "https://api.binance.com/api/v3/order?newClientOrderId=client-1".to_owned(),
+                body: String::new(),
+                status: 200,
+                content: r#"{"orderId":555}"#.to_owned(),
+            },
+            websocket_record(2, r#"{"e":"executionReport","i":555,"p":"5550.1"}"#),
+            websocket_record(2, r#"{"e":"executionReport","i":556,"p":"5550.1"}"#),
+        ];
+
+        let timeline = timeline(&order(), traffic);
+
+        let events = timeline
+            .entries
+            .iter()
+            .map(|x| match &x.event {
+                TimelineEvent::Created { .. } => "created",
+                TimelineEvent::StatusChanged { .. } => "status_changed",
+                TimelineEvent::Fill { .. } => "fill",
+                TimelineEvent::RestRequest { .. } => "rest_request",
+                TimelineEvent::WebSocketMessage { .. } => "websocket_message",
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            [
+                "created",
+                "status_changed",
+                "rest_request",
+                "websocket_message",
+                "status_changed",
+                "fill",
+            ]
+        );
+        assert_eq!(
+            timeline.entries[2].description,
+            "REST request Post /api/v3/order is responded with status 200"
+        );
+        assert_eq!(timeline.status, OrderStatus::Completed);
+        assert_eq!(timeline.filled_amount, dec!(1));
+    }
+
+    #[test]
+    fn traffic_outside_of_order_lifetime_is_skipped() {
+        let traffic = vec![
+            websocket_record(0, r#"{"i":555}"#),
+            websocket_record(63, r#"{"i":555}"#),
+            websocket_record(64, r#"{"i":555}"#),
+        ];
+        let mut order = order();
+        Arc::make_mut(&mut order.header).init_time = time(1);
+
+        let timeline = timeline(&order, traffic);
+
+        let message_times = timeline
+            .entries
+            .iter()
+            .filter(|x| matches!(x.event, TimelineEvent::WebSocketMessage { .. }))
+            .map(|x| x.time)
+            .collect::<Vec<_>>();
+        assert_eq!(message_times, [time(63)]);
+    }
+
+    #[test]
+    fn id_is_found_only_as_whole_value() {
+        assert!(contains_id(r#"{"i":555}"#, "555"));
+        assert!(contains_id(r#"{"c":"client-1"}"#, "client-1"));
+        assert!(!contains_id(r#"{"p":"5550.1"}"#, "555"));
+        assert!(!contains_id(r#"{"p":"1.555"}"#, "555"));
+        assert!(!contains_id(r#"{"c":"client-10"}"#, "client-1"));
+    }
+}
@@ -0,0 +1,46 @@
+//! Strategy-facing API of trading engine.
+//! Items are re-exported here so strategy crates don't depend on internal module layout:
+//! `use mmb_core::prelude::*;`
+
+pub use crate::balance_manager::balance_manager::BalanceManager;
+pub use crate::config::{CONFIG_PATH, CREDENTIALS_PATH};
+pub use crate::disposition_execution::{
+    PriceSlot, TradeCycle, TradeDisposition, TradingContext, TradingContextBySide,
+};
+pub use crate::error::{ConnectivityError, MmbError, MmbResult, RiskError, ValidationError};
+pub use crate::exchanges::common::{
+    Amount, CurrencyCode, CurrencyPair, ExchangeAccountId, ExchangeError, ExchangeErrorType,
+    ExchangeId, MarketAccountId, MarketId, Price,
+};
+pub use crate::exchanges::events::{
+    BalanceDeltaEvent, BalanceUpdateEvent, DustConversionEvent, ExchangeBalance,
+    ExchangeBalancesAndPositions, ExchangeEvent, LiquidationOrderEvent, LiquidationPriceEvent,
+    MarginCallEvent, MarketTradingStatusEvent, SymbolEvent, Trade, TradeId, TradesEvent,
+};
+pub use crate::exchanges::general::exchange::{Exchange, RequestResult};
+pub use crate::exchanges::general::handlers::handle_order_filled::FillEventData;
+pub use crate::exchanges::general::order::rejection::{OrderRejectedError, RejectionReason};
+pub use crate::exchanges::general::symbol::{Round, Symbol};
+pub use crate::exchanges::traits::ExchangeClientBuilder;
+pub use crate::explanation::{Explanation, WithExplanation};
+pub use crate::lifecycle::app_lifetime_manager::ActionAfterGracefulShutdown;
+pub use crate::lifecycle::engine_builder::TradingEngineBuilder;
+pub use crate::lifecycle::launcher::{launch_trading_engine, EngineBuildConfig, InitSettings};
+pub use crate::lifecycle::trading_engine::{EngineContext, TradingEngine};
+pub use crate::order_book::local_snapshot_service::LocalSnapshotsService;
+pub use crate::orders::event::{OrderEvent, OrderEventType};
+pub use crate::orders::fill::{OrderFill, OrderFillType};
+pub use crate::orders::order::{
+    ClientOrderId, ExchangeOrderId, OrderCreating, OrderExecutionType, OrderHeader, OrderRole,
+    OrderSide, OrderSnapshot, OrderStatus, OrderType, PositionSide, ReservationId,
+};
+pub use crate::orders::order_builder::{OrderBuildError, OrderBuilder};
+pub use crate::orders::pool::OrderRef;
+pub use crate::orders::spread_order::{HedgePolicy, SpreadLeg, SpreadOrder, SpreadOrderStatus};
+pub use crate::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+pub use crate::services::scheduler::{JobId, Schedule, Scheduler};
+pub use crate::settings::{
+    AppSettings, BaseStrategySettings, CoreSettings, CurrencyPairSetting, ExchangeSettings,
+};
+pub use crate::strategies::disposition_strategy::DispositionStrategy;
+pub use crate::strategies::inventory_skew::{InventorySkew, InventorySkewSettings};
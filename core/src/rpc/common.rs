@@ -4,28 +4,51 @@ use anyhow::Context;
 use futures::FutureExt;
 use jsonrpc_core::{MetaIoHandler, Result};
 use jsonrpc_ipc_server::{Server, ServerBuilder};
-use mmb_rpc::rest_api::{server_side_error, ErrorCode, MmbRpc, IPC_ADDRESS};
+use mmb_rpc::rest_api::{server_side_error, ErrorCode, MmbRpc};
 use mmb_utils::infrastructure::SpawnFutureFlags;
 use parking_lot::Mutex;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
 use tokio::sync::{mpsc, oneshot};
 
 use crate::{
-    config::{save_settings, CONFIG_PATH, CREDENTIALS_PATH},
     infrastructure::spawn_future,
     lifecycle::app_lifetime_manager::{ActionAfterGracefulShutdown, AppLifetimeManager},
     rpc::core_api::FAILED_TO_SEND_STOP_NOTIFICATION,
+    services::audit_log::{AuditLog, AuditOutcome},
 };
 
-pub(super) fn set_config(settings: String) -> Result<()> {
-    save_settings(settings.as_str(), CONFIG_PATH, CREDENTIALS_PATH).map_err(|err| {
-        log::warn!(
-            "Error while trying to save new config in set_config endpoint: {}",
-            err.to_string()
-        );
-        server_side_error(ErrorCode::FailedToSaveNewConfig)
-    })?;
+/// Writes outcome of operator action to audit log
+pub(super) fn audit<T>(
+    audit_log: &AuditLog,
+    operator: Option<&str>,
+    action: &str,
+    params: Value,
+    result: &Result<T>,
+) {
+    let outcome = match result {
+        Ok(_) => AuditOutcome::Succeeded,
+        Err(error) => AuditOutcome::Failed {
+            error: error.message.clone(),
+        },
+    };
+    audit_log.record(operator, action, params, outcome);
+}
 
-    Ok(())
+/// Settings contain credentials, so only hash of settings is written to audit log.
+/// It allows to find saved config among config backups
+pub(super) fn get_config_audit_params(settings: &str) -> Value {
+    json!({ "config_sha256": hex::encode(Sha256::digest(settings.as_bytes())) })
+}
+
+pub(super) fn get_audit_log(audit_log: &AuditLog, count: usize) -> Result<String> {
+    audit_log
+        .last_records(count)
+        .and_then(|records| Ok(serde_json::to_string(&records)?))
+        .map_err(|err| {
+            log::warn!("Failed to read audit log: {:?}", err);
+            server_side_error(ErrorCode::FailedToReadAuditLog)
+        })
 }
 
 /// Send signal to stop TradingEngine
@@ -91,11 +114,14 @@ pub(super) struct RpcServerAndChannels<T> {
     pub work_finished_receiver: oneshot::Receiver<T>,
 }
 
-pub(super) fn crate_server_and_channels<T>(rpc: impl MmbRpc) -> RpcServerAndChannels<T> {
+pub(super) fn crate_server_and_channels<T>(
+    rpc: impl MmbRpc,
+    ipc_address: &str,
+) -> RpcServerAndChannels<T> {
     let (work_finished_sender, work_finished_receiver) = oneshot::channel();
     let io = build_io(rpc);
     let builder = ServerBuilder::new(io);
-    let server = builder.start(IPC_ADDRESS).expect("Couldn't open socket");
+    let server = builder.start(ipc_address).expect("Couldn't open socket");
 
     RpcServerAndChannels {
         server,
@@ -0,0 +1,75 @@
+use anyhow::Context;
+use jsonrpc_core::Result;
+use mmb_rpc::rest_api::{server_side_error, server_side_error_with_message, ErrorCode};
+use serde::de::DeserializeOwned;
+
+use crate::config::{save_settings, CONFIG_PATH, CREDENTIALS_PATH};
+use crate::config_validation::{supported_exchanges, validate_settings, SupportedExchanges};
+use crate::lifecycle::launcher::EngineBuildConfig;
+use crate::settings::{AppSettings, BaseStrategySettings};
+
+type SettingsValidator = fn(&str, &SupportedExchanges) -> anyhow::Result<()>;
+
+/// Editing of settings by control panel: schema of strategy settings for rendering of settings form
+/// and server-side validation of submitted settings before saving
+#[derive(Clone)]
+pub(crate) struct ConfigEditor {
+    strategy_settings_schema: Option<String>,
+    supported_exchanges: SupportedExchanges,
+    validator: SettingsValidator,
+}
+
+impl ConfigEditor {
+    pub(crate) fn new<StrategySettings>(build_settings: &EngineBuildConfig) -> Self
+    where
+        StrategySettings: BaseStrategySettings + Clone + DeserializeOwned,
+    {
+        Self {
+            strategy_settings_schema: build_settings.strategy_settings_schema.clone(),
+            supported_exchanges: supported_exchanges(&build_settings.supported_exchange_clients),
+            validator: validate_submitted_settings::<StrategySettings>,
+        }
+    }
+
+    pub(crate) fn get_schema(&self) -> Result<String> {
+        self.strategy_settings_schema
+            .clone()
+            .ok_or_else(|| server_side_error(ErrorCode::ConfigSchemaIsNotSet))
+    }
+
+    /// Saves settings (with credentials) to config files if they are valid
+    pub(crate) fn set_config(&self, settings: &str) -> Result<()> {
+        if let Err(error) = (self.validator)(settings, &self.supported_exchanges) {
+            log::warn!(
+                "Invalid config was received in set_config endpoint: {:?}",
+                error
+            );
+            return Err(server_side_error_with_message(
+                ErrorCode::InvalidConfig,
+                format!("{:#}", error),
+            ));
+        }
+
+        save_settings(settings, CONFIG_PATH, CREDENTIALS_PATH).map_err(|err| {
+            log::warn!(
+                "Error while trying to save new config in set_config endpoint: {}",
+                err.to_string()
+            );
+            server_side_error(ErrorCode::FailedToSaveNewConfig)
+        })
+    }
+}
+
+fn validate_submitted_settings<StrategySettings>(
+    settings: &str,
+    supported_exchanges: &SupportedExchanges,
+) -> anyhow::Result<()>
+where
+    StrategySettings: BaseStrategySettings + Clone + DeserializeOwned,
+{
+    let settings = toml_edit::de::from_str::<AppSettings<StrategySettings>>(settings)
+        .context("Unable parse settings")?;
+    validate_settings(&settings, supported_exchanges)?;
+
+    Ok(())
+}
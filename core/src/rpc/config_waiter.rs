@@ -1,16 +1,19 @@
 use std::sync::Arc;
 
 use anyhow::Result;
+use mmb_rpc::rest_api::IPC_ADDRESS;
 use mmb_utils::logger::print_info;
 use parking_lot::Mutex;
 use tokio::sync::{mpsc, oneshot};
 
 use crate::lifecycle::app_lifetime_manager::ActionAfterGracefulShutdown;
+use crate::services::audit_log::{AuditLog, AUDIT_LOG_PATH};
 
 use super::{
     common::{
         crate_server_and_channels, spawn_server_stopping_action, stop_server, RpcServerAndChannels,
     },
+    config_editor::ConfigEditor,
     rpc_impl_no_config::RpcImplNoConfig,
 };
 
@@ -20,7 +23,10 @@ pub(crate) struct ConfigWaiter {
 }
 
 impl ConfigWaiter {
-    pub(crate) fn create_and_start(wait_config_tx: mpsc::Sender<()>) -> Result<Arc<Self>> {
+    pub(crate) fn create_and_start(
+        wait_config_tx: mpsc::Sender<()>,
+        config_editor: ConfigEditor,
+    ) -> Result<Arc<Self>> {
         let (server_stopper_tx, server_stopper_rx) =
             mpsc::channel::<ActionAfterGracefulShutdown>(10);
         let server_stopper_tx = Arc::new(Mutex::new(Some(server_stopper_tx.clone())));
@@ -28,10 +34,15 @@ impl ConfigWaiter {
             server,
             work_finished_sender,
             work_finished_receiver,
-        } = crate_server_and_channels(RpcImplNoConfig::new(
-            server_stopper_tx.clone(),
-            wait_config_tx,
-        ));
+        } = crate_server_and_channels(
+            RpcImplNoConfig::new(
+                server_stopper_tx.clone(),
+                wait_config_tx,
+                config_editor,
+                AuditLog::new(AUDIT_LOG_PATH),
+            ),
+            IPC_ADDRESS,
+        );
 
         spawn_server_stopping_action(
             "waiting to stop ConfigWaiter",
@@ -2,20 +2,29 @@ use anyhow::Result;
 use parking_lot::Mutex;
 use tokio::sync::{mpsc, oneshot};
 
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 
 use crate::{
     lifecycle::{
         app_lifetime_manager::{ActionAfterGracefulShutdown, AppLifetimeManager},
-        trading_engine::Service,
+        exchange_registrar::ExchangeRegistrar,
+        trading_engine::{EngineContext, Service},
+    },
+    orders::order_filter::OrderFilter,
+    services::{
+        audit_log::{AuditLog, AUDIT_LOG_PATH},
+        kill_switch::KillSwitch,
+        treasury::TreasuryService,
     },
     statistic_service::StatisticService,
+    strategies::shadow::ShadowStrategyService,
 };
 
 use super::{
     common::{
         crate_server_and_channels, spawn_server_stopping_action, stop_server, RpcServerAndChannels,
     },
+    config_editor::ConfigEditor,
     rpc_impl::RpcImpl,
 };
 
@@ -31,7 +40,15 @@ impl CoreApi {
     pub(crate) fn create_and_start(
         lifetime_manager: Arc<AppLifetimeManager>,
         engine_settings: String,
+        config_editor: ConfigEditor,
         statistics: Arc<StatisticService>,
+        treasury: Arc<TreasuryService>,
+        order_filter: Arc<OrderFilter>,
+        kill_switch: Arc<KillSwitch>,
+        engine_context: Weak<EngineContext>,
+        exchange_registrar: Arc<ExchangeRegistrar>,
+        shadow_strategy: Option<Arc<ShadowStrategyService>>,
+        ipc_address: &str,
     ) -> Result<Arc<Self>> {
         let (server_stopper_tx, server_stopper_rx) =
             mpsc::channel::<ActionAfterGracefulShutdown>(10);
@@ -40,11 +57,22 @@ impl CoreApi {
             server,
             work_finished_sender,
             work_finished_receiver,
-        } = crate_server_and_channels(RpcImpl::new(
-            server_stopper_tx.clone(),
-            statistics,
-            engine_settings,
-        ));
+        } = crate_server_and_channels(
+            RpcImpl::new(
+                server_stopper_tx.clone(),
+                statistics,
+                engine_settings,
+                config_editor,
+                treasury,
+                order_filter,
+                kill_switch,
+                AuditLog::new(AUDIT_LOG_PATH),
+                engine_context,
+                exchange_registrar,
+                shadow_strategy,
+            ),
+            ipc_address,
+        );
 
         spawn_server_stopping_action(
             "waiting to stop ControlPanel",
@@ -1,4 +1,5 @@
 pub mod common;
+pub(crate) mod config_editor;
 pub mod config_waiter;
 pub mod core_api;
 pub mod rpc_impl;
@@ -1,37 +1,87 @@
-use jsonrpc_core::Result;
-use mmb_rpc::rest_api::server_side_error;
+use anyhow::{anyhow, bail, Context};
+use futures::FutureExt;
+use jsonrpc_core::{BoxFuture, Result};
 use mmb_rpc::rest_api::MmbRpc;
+use mmb_rpc::rest_api::{server_side_error, server_side_error_with_message};
 use parking_lot::Mutex;
+use serde_json::{json, Value};
 use tokio::sync::mpsc;
 
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 
+use crate::balance_manager::balance_snapshot::BalanceSnapshot;
+use crate::exchanges::common::ExchangeAccountId;
 use crate::lifecycle::app_lifetime_manager::ActionAfterGracefulShutdown;
-use crate::statistic_service::StatisticService;
+use crate::lifecycle::engine_status::EngineStatus;
+use crate::lifecycle::exchange_registrar::ExchangeRegistrar;
+use crate::lifecycle::state_archive::StateArchive;
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::orders::order_filter::OrderFilter;
+use crate::orders::timeline::OrderTimeline;
+use crate::services::audit_log::AuditLog;
+use crate::services::kill_switch::KillSwitch;
+use crate::services::treasury::{get_token_hash, TreasuryService, WithdrawalRequest};
+use crate::settings::ExchangeSettings;
+use crate::statistic_service::{StatisticService, StatisticsQuery};
+use crate::storage::order_history::{parse_currency_pair, query_orders, OrdersQuery};
+use crate::strategies::shadow::ShadowStrategyService;
 use mmb_rpc::rest_api::ErrorCode;
 
 use super::common::send_restart;
 use super::common::send_stop;
-use super::common::set_config;
+use super::common::{audit, get_audit_log, get_config_audit_params};
+use super::config_editor::ConfigEditor;
 
 pub struct RpcImpl {
     server_stopper_tx: Arc<Mutex<Option<mpsc::Sender<ActionAfterGracefulShutdown>>>>,
     statistics: Arc<StatisticService>,
     engine_settings: String,
+    config_editor: ConfigEditor,
+    treasury: Arc<TreasuryService>,
+    order_filter: Arc<OrderFilter>,
+    kill_switch: Arc<KillSwitch>,
+    audit_log: Arc<AuditLog>,
+    engine_context: Weak<EngineContext>,
+    exchange_registrar: Arc<ExchangeRegistrar>,
+    shadow_strategy: Option<Arc<ShadowStrategyService>>,
 }
 
 impl RpcImpl {
-    pub fn new(
+    pub(crate) fn new(
         server_stopper_tx: Arc<Mutex<Option<mpsc::Sender<ActionAfterGracefulShutdown>>>>,
         statistics: Arc<StatisticService>,
         engine_settings: String,
+        config_editor: ConfigEditor,
+        treasury: Arc<TreasuryService>,
+        order_filter: Arc<OrderFilter>,
+        kill_switch: Arc<KillSwitch>,
+        audit_log: Arc<AuditLog>,
+        engine_context: Weak<EngineContext>,
+        exchange_registrar: Arc<ExchangeRegistrar>,
+        shadow_strategy: Option<Arc<ShadowStrategyService>>,
     ) -> Self {
         Self {
             server_stopper_tx,
             statistics,
             engine_settings,
+            config_editor,
+            treasury,
+            order_filter,
+            kill_switch,
+            audit_log,
+            engine_context,
+            exchange_registrar,
+            shadow_strategy,
         }
     }
+
+    fn get_stats(&self, query: &StatisticsQuery) -> Result<String> {
+        let report = self.statistics.report(query);
+        serde_json::to_string(&report).map_err(|err| {
+            log::warn!("Failed to convert stats for {:?} to string: {}", query, err);
+            server_side_error(ErrorCode::FailedToGetStats)
+        })
+    }
 }
 
 impl MmbRpc for RpcImpl {
@@ -39,31 +89,605 @@ impl MmbRpc for RpcImpl {
         Ok("Engine is working".into())
     }
 
-    fn stop(&self) -> Result<String> {
-        send_stop(self.server_stopper_tx.clone())
+    fn status(&self) -> BoxFuture<Result<String>> {
+        let engine_context = self.engine_context.clone();
+        async move {
+            let engine_context = engine_context.upgrade().ok_or_else(|| {
+                log::warn!("Failed to get status: engine context is already dropped");
+                server_side_error(ErrorCode::FailedToGetStatus)
+            })?;
+
+            let status = EngineStatus::collect(&engine_context).await;
+            serde_json::to_string(&status).map_err(|err| {
+                log::warn!("Failed to convert {:?} to string: {}", status, err);
+                server_side_error(ErrorCode::FailedToGetStatus)
+            })
+        }
+        .boxed()
+    }
+
+    fn stop(&self, operator: Option<String>) -> Result<String> {
+        let result = send_stop(self.server_stopper_tx.clone());
+        audit(
+            &self.audit_log,
+            operator.as_deref(),
+            "stop",
+            Value::Null,
+            &result,
+        );
+        result
     }
 
     fn get_config(&self) -> Result<String> {
         Ok(self.engine_settings.clone())
     }
 
-    fn set_config(&self, settings: String) -> Result<String> {
-        set_config(settings)?;
-        send_restart(self.server_stopper_tx.clone())?;
-        Ok("Config was successfully updated. Trading engine will be restarted".into())
+    fn set_config(&self, settings: String, operator: Option<String>) -> Result<String> {
+        let result = self
+            .config_editor
+            .set_config(&settings)
+            .and_then(|_| send_restart(self.server_stopper_tx.clone()))
+            .map(|_| "Config was successfully updated. Trading engine will be restarted".into());
+        audit(
+            &self.audit_log,
+            operator.as_deref(),
+            "set_config",
+            get_config_audit_params(&settings),
+            &result,
+        );
+        result
+    }
+
+    fn get_config_schema(&self) -> Result<String> {
+        self.config_editor.get_schema()
     }
 
     fn stats(&self) -> Result<String> {
-        let json_statistic = serde_json::to_string(&self.statistics.statistic_service_state)
+        self.get_stats(&StatisticsQuery::default())
+    }
+
+    fn query_stats(&self, query: String) -> Result<String> {
+        let query = serde_json::from_str::<StatisticsQuery>(&query).map_err(|err| {
+            log::warn!("Failed to parse stats query {}: {}", query, err);
+            server_side_error_with_message(
+                ErrorCode::InvalidStatsQuery,
+                format!("Invalid stats query: {}", err),
+            )
+        })?;
+
+        self.get_stats(&query)
+    }
+
+    fn balances(&self) -> Result<String> {
+        let engine_context = self.engine_context.upgrade().ok_or_else(|| {
+            log::warn!("Failed to get balances: engine context is already dropped");
+            server_side_error(ErrorCode::FailedToGetBalances)
+        })?;
+
+        let snapshot =
+            BalanceSnapshot::collect(&engine_context.balance_manager, &engine_context.exchanges);
+        serde_json::to_string(&snapshot).map_err(|err| {
+            log::warn!("Failed to convert {:?} to string: {}", snapshot, err);
+            server_side_error(ErrorCode::FailedToGetBalances)
+        })
+    }
+
+    fn rate_limits(&self) -> Result<String> {
+        rate_limits(&self.engine_context).map_err(|err| {
+            log::warn!("Failed to get rate limits: {:?}", err);
+            server_side_error_with_message(ErrorCode::FailedToGetRateLimits, format!("{err:#}"))
+        })
+    }
+
+    fn shadow_report(&self) -> Result<String> {
+        shadow_report(self.shadow_strategy.as_deref()).map_err(|err| {
+            log::warn!("Failed to get shadow report: {:?}", err);
+            server_side_error_with_message(ErrorCode::FailedToGetShadowReport, format!("{err:#}"))
+        })
+    }
+
+    fn withdraw(&self, withdrawal_request: String, operator: Option<String>) -> Result<String> {
+        let result = serde_json::from_str::<WithdrawalRequest>(&withdrawal_request)
+            .map_err(anyhow::Error::from)
+            .and_then(|request| self.treasury.request_withdrawal(request))
             .map_err(|err| {
                 log::warn!(
-                    "Failed to convert {:?} to string: {}",
-                    self.statistics,
-                    err.to_string()
+                    "Failed to request withdrawal {}: {:?}",
+                    withdrawal_request,
+                    err
                 );
-                server_side_error(ErrorCode::FailedToSaveNewConfig)
+                server_side_error(ErrorCode::FailedToRequestWithdrawal)
+            });
+
+        let params = serde_json::from_str(&withdrawal_request)
+            .unwrap_or_else(|_| Value::String(withdrawal_request.clone()));
+        audit(
+            &self.audit_log,
+            operator.as_deref(),
+            "withdraw",
+            params,
+            &result,
+        );
+        result
+    }
+
+    fn confirm_withdraw(&self, token: String, operator: Option<String>) -> Result<String> {
+        let result = self
+            .treasury
+            .confirm_withdrawal(&token)
+            .map(|_| "Withdrawal was confirmed. Result will be written to log".into())
+            .map_err(|err| {
+                log::warn!("Failed to confirm withdrawal: {:?}", err);
+                server_side_error(ErrorCode::FailedToConfirmWithdrawal)
+            });
+        audit(
+            &self.audit_log,
+            operator.as_deref(),
+            "confirm_withdraw",
+            json!({ "token_sha256": get_token_hash(&token) }),
+            &result,
+        );
+        result
+    }
+
+    fn pending_refills(&self) -> Result<String> {
+        serde_json::to_string(&self.treasury.pending_refills()).map_err(|err| {
+            log::warn!("Failed to get pending refills: {:?}", err);
+            server_side_error(ErrorCode::FailedToGetPendingRefills)
+        })
+    }
+
+    fn reload_order_filter(&self, operator: Option<String>) -> Result<String> {
+        let result = self
+            .order_filter
+            .reload()
+            .map(|_| "Order filter script was reloaded".into())
+            .map_err(|err| {
+                log::warn!("Failed to reload order filter: {:?}", err);
+                server_side_error(ErrorCode::FailedToReloadOrderFilter)
+            });
+        audit(
+            &self.audit_log,
+            operator.as_deref(),
+            "reload_order_filter",
+            Value::Null,
+            &result,
+        );
+        result
+    }
+
+    fn panic_button(
+        &self,
+        close_positions: bool,
+        operator: Option<String>,
+    ) -> BoxFuture<Result<String>> {
+        let kill_switch = self.kill_switch.clone();
+        let audit_log = self.audit_log.clone();
+        async move {
+            let report = kill_switch.press(close_positions).await;
+            let result = serde_json::to_string(&report).map_err(|err| {
+                log::warn!("Failed to convert {:?} to string: {}", report, err);
+                server_side_error(ErrorCode::FailedToPressPanicButton)
+            });
+            audit(
+                &audit_log,
+                operator.as_deref(),
+                "panic_button",
+                json!({ "close_positions": close_positions }),
+                &result,
+            );
+            result
+        }
+        .boxed()
+    }
+
+    fn stop_exchange(
+        &self,
+        exchange_account_id: String,
+        operator: Option<String>,
+    ) -> BoxFuture<Result<String>> {
+        let engine_context = self.engine_context.clone();
+        let audit_log = self.audit_log.clone();
+        async move {
+            let result = stop_exchange(engine_context, &exchange_account_id)
+                .await
+                .map_err(|err| {
+                    log::warn!("Failed to stop exchange {}: {:?}", exchange_account_id, err);
+                    server_side_error_with_message(ErrorCode::FailedToStopExchange, err.to_string())
+                });
+
+            audit(
+                &audit_log,
+                operator.as_deref(),
+                "stop_exchange",
+                json!({ "exchange_account_id": exchange_account_id }),
+                &result,
+            );
+            result
+        }
+        .boxed()
+    }
+
+    fn add_exchange(
+        &self,
+        exchange_settings: String,
+        operator: Option<String>,
+    ) -> BoxFuture<Result<String>> {
+        let exchange_registrar = self.exchange_registrar.clone();
+        let audit_log = self.audit_log.clone();
+        async move {
+            let exchange_settings = toml_edit::de::from_str::<ExchangeSettings>(&exchange_settings)
+                .context("Unable to parse exchange settings");
+            // Settings contain credentials, so only exchange account id is written to audit log
+            let params = match &exchange_settings {
+                Ok(x) => json!({ "exchange_account_id": x.exchange_account_id.to_string() }),
+                Err(_) => Value::Null,
+            };
+
+            let result = match exchange_settings {
+                Ok(exchange_settings) => exchange_registrar
+                    .add_exchange(exchange_settings)
+                    .await
+                    .map(|exchange| format!("Exchange {} is added", exchange.exchange_account_id)),
+                Err(err) => Err(err),
+            }
+            .map_err(|err| {
+                log::warn!("Failed to add exchange: {:?}", err);
+                server_side_error_with_message(ErrorCode::FailedToAddExchange, format!("{err:#}"))
+            });
+
+            audit(
+                &audit_log,
+                operator.as_deref(),
+                "add_exchange",
+                params,
+                &result,
+            );
+            result
+        }
+        .boxed()
+    }
+
+    fn disable_market(
+        &self,
+        exchange_account_id: String,
+        currency_pair: String,
+        operator: Option<String>,
+    ) -> BoxFuture<Result<String>> {
+        let engine_context = self.engine_context.clone();
+        let audit_log = self.audit_log.clone();
+        async move {
+            let result =
+                set_market_enabled(engine_context, &exchange_account_id, &currency_pair, false)
+                    .await
+                    .map_err(|err| {
+                        log::warn!(
+                            "Failed to disable market {} on {}: {:?}",
+                            currency_pair,
+                            exchange_account_id,
+                            err
+                        );
+                        server_side_error_with_message(
+                            ErrorCode::FailedToDisableMarket,
+                            format!("{err:#}"),
+                        )
+                    });
+
+            audit(
+                &audit_log,
+                operator.as_deref(),
+                "disable_market",
+                json!({
+                    "exchange_account_id": exchange_account_id,
+                    "currency_pair": currency_pair
+                }),
+                &result,
+            );
+            result
+        }
+        .boxed()
+    }
+
+    fn enable_market(
+        &self,
+        exchange_account_id: String,
+        currency_pair: String,
+        operator: Option<String>,
+    ) -> BoxFuture<Result<String>> {
+        let engine_context = self.engine_context.clone();
+        let audit_log = self.audit_log.clone();
+        async move {
+            let result =
+                set_market_enabled(engine_context, &exchange_account_id, &currency_pair, true)
+                    .await
+                    .map_err(|err| {
+                        log::warn!(
+                            "Failed to enable market {} on {}: {:?}",
+                            currency_pair,
+                            exchange_account_id,
+                            err
+                        );
+                        server_side_error_with_message(
+                            ErrorCode::FailedToEnableMarket,
+                            format!("{err:#}"),
+                        )
+                    });
+
+            audit(
+                &audit_log,
+                operator.as_deref(),
+                "enable_market",
+                json!({
+                    "exchange_account_id": exchange_account_id,
+                    "currency_pair": currency_pair
+                }),
+                &result,
+            );
+            result
+        }
+        .boxed()
+    }
+
+    fn audit_log(&self, count: usize) -> Result<String> {
+        get_audit_log(&self.audit_log, count)
+    }
+
+    fn orders(&self, query: String) -> BoxFuture<Result<String>> {
+        let engine_context = self.engine_context.clone();
+        async move {
+            let orders_query = OrdersQuery::from_url_query(&query).map_err(|err| {
+                log::warn!("Failed to parse orders query {}: {:?}", query, err);
+                server_side_error_with_message(
+                    ErrorCode::InvalidOrdersQuery,
+                    format!("Invalid orders query: {:#}", err),
+                )
             })?;
 
-        Ok(json_statistic)
+            let engine_context = engine_context.upgrade().ok_or_else(|| {
+                log::warn!("Failed to get orders: engine context is already dropped");
+                server_side_error(ErrorCode::FailedToGetOrders)
+            })?;
+
+            let page = query_orders(
+                engine_context.storage.as_ref(),
+                &engine_context.exchanges,
+                &orders_query,
+            )
+            .await
+            .map_err(|err| {
+                log::warn!("Failed to get orders for {:?}: {:?}", orders_query, err);
+                server_side_error(ErrorCode::FailedToGetOrders)
+            })?;
+
+            serde_json::to_string(&page).map_err(|err| {
+                log::warn!("Failed to convert orders page to string: {}", err);
+                server_side_error(ErrorCode::FailedToGetOrders)
+            })
+        }
+        .boxed()
+    }
+
+    fn order_timeline(&self, client_order_id: String) -> BoxFuture<Result<String>> {
+        let engine_context = self.engine_context.clone();
+        async move {
+            order_timeline(engine_context, &client_order_id)
+                .await
+                .map_err(|err| {
+                    log::warn!(
+                        "Failed to get timeline of order {}: {:?}",
+                        client_order_id,
+                        err
+                    );
+                    server_side_error_with_message(
+                        ErrorCode::FailedToGetOrderTimeline,
+                        format!("{err:#}"),
+                    )
+                })
+        }
+        .boxed()
+    }
+
+    fn export_state(&self, hand_over: bool, operator: Option<String>) -> BoxFuture<Result<String>> {
+        let engine_context = self.engine_context.clone();
+        let audit_log = self.audit_log.clone();
+        async move {
+            let result = export_state(engine_context, hand_over)
+                .await
+                .map_err(|err| {
+                    log::warn!("Failed to export state: {:?}", err);
+                    server_side_error_with_message(
+                        ErrorCode::FailedToExportState,
+                        format!("{err:#}"),
+                    )
+                });
+
+            audit(
+                &audit_log,
+                operator.as_deref(),
+                "export_state",
+                json!({ "hand_over": hand_over }),
+                &result,
+            );
+            result
+        }
+        .boxed()
+    }
+
+    fn import_state(&self, archive: String, operator: Option<String>) -> BoxFuture<Result<String>> {
+        let engine_context = self.engine_context.clone();
+        let audit_log = self.audit_log.clone();
+        async move {
+            let archive = StateArchive::parse(&archive);
+            // Archive is too big for audit log, so only its summary is written
+            let params = match &archive {
+                Ok(x) => json!({
+                    "created_at": x.created_at,
+                    "orders": x.orders.len(),
+                    "reservations": x.reservations.len(),
+                }),
+                Err(_) => Value::Null,
+            };
+
+            let result = match archive {
+                Ok(archive) => import_state(engine_context, archive).await,
+                Err(err) => Err(err),
+            }
+            .map_err(|err| {
+                log::warn!("Failed to import state: {:?}", err);
+                server_side_error_with_message(ErrorCode::FailedToImportState, format!("{err:#}"))
+            });
+
+            audit(
+                &audit_log,
+                operator.as_deref(),
+                "import_state",
+                params,
+                &result,
+            );
+            result
+        }
+        .boxed()
+    }
+
+    fn cancel_handover(&self, operator: Option<String>) -> Result<String> {
+        let result = cancel_handover(&self.engine_context).map_err(|err| {
+            log::warn!("Failed to cancel handover: {:?}", err);
+            server_side_error_with_message(ErrorCode::FailedToCancelHandover, format!("{err:#}"))
+        });
+        audit(
+            &self.audit_log,
+            operator.as_deref(),
+            "cancel_handover",
+            Value::Null,
+            &result,
+        );
+        result
+    }
+}
+
+async fn export_state(
+    engine_context: Weak<EngineContext>,
+    hand_over: bool,
+) -> anyhow::Result<String> {
+    let engine_context = engine_context
+        .upgrade()
+        .context("Engine context is already dropped")?;
+
+    // Trading is stopped before collecting of state, so archive contains all orders of engine
+    if hand_over {
+        engine_context.hand_over_state();
+    }
+
+    let archive = StateArchive::export(&engine_context).await?;
+    Ok(serde_json::to_string(&archive)?)
+}
+
+async fn import_state(
+    engine_context: Weak<EngineContext>,
+    archive: StateArchive,
+) -> anyhow::Result<String> {
+    let engine_context = engine_context
+        .upgrade()
+        .context("Engine context is already dropped")?;
+
+    let report = archive.import(&engine_context).await?;
+    Ok(serde_json::to_string(&report)?)
+}
+
+fn cancel_handover(engine_context: &Weak<EngineContext>) -> anyhow::Result<String> {
+    let engine_context = engine_context
+        .upgrade()
+        .context("Engine context is already dropped")?;
+
+    if !engine_context.cancel_handover() {
+        bail!("State of engine isn't handed over");
+    }
+    Ok("Handover is cancelled, trading is resumed".into())
+}
+
+fn rate_limits(engine_context: &Weak<EngineContext>) -> anyhow::Result<String> {
+    let engine_context = engine_context
+        .upgrade()
+        .context("Engine context is already dropped")?;
+
+    let stats = engine_context.timeout_manager.stats()?;
+    Ok(serde_json::to_string(&stats)?)
+}
+
+fn shadow_report(shadow_strategy: Option<&ShadowStrategyService>) -> anyhow::Result<String> {
+    let shadow_strategy = shadow_strategy.context("Shadow strategy isn't specified in settings")?;
+    Ok(serde_json::to_string(&shadow_strategy.report())?)
+}
+
+async fn order_timeline(
+    engine_context: Weak<EngineContext>,
+    client_order_id: &str,
+) -> anyhow::Result<String> {
+    let engine_context = engine_context
+        .upgrade()
+        .context("Engine context is already dropped")?;
+
+    let timeline = OrderTimeline::collect(&engine_context, &client_order_id.into())
+        .await?
+        .with_context(|| format!("Order {client_order_id} isn't found"))?;
+    Ok(serde_json::to_string(&timeline)?)
+}
+
+async fn set_market_enabled(
+    engine_context: Weak<EngineContext>,
+    exchange_account_id: &str,
+    currency_pair: &str,
+    is_enabled: bool,
+) -> anyhow::Result<String> {
+    let exchange_account_id = exchange_account_id
+        .parse::<ExchangeAccountId>()
+        .map_err(|err| anyhow!("Invalid exchange account id {exchange_account_id}: {err:?}"))?;
+    let currency_pair = parse_currency_pair(currency_pair)?;
+    let engine_context = engine_context
+        .upgrade()
+        .context("Engine context is already dropped")?;
+    let exchange = engine_context
+        .exchanges
+        .get(&exchange_account_id)
+        .map(|x| x.value().clone())
+        .with_context(|| format!("Exchange {exchange_account_id} isn't found"))?;
+
+    if is_enabled {
+        exchange
+            .enable_market(currency_pair, engine_context.storage.as_ref())
+            .await?;
+        return Ok(format!(
+            "Market {currency_pair} on {exchange_account_id} is enabled"
+        ));
     }
+
+    let cancelled_count = exchange
+        .disable_market(
+            currency_pair,
+            engine_context.storage.as_ref(),
+            engine_context
+                .app_settings
+                .timeouts
+                .cancel_orders
+                .duration(),
+        )
+        .await?;
+    Ok(format!(
+        "Market {currency_pair} on {exchange_account_id} is disabled, {cancelled_count} orders are cancelled"
+    ))
+}
+
+async fn stop_exchange(
+    engine_context: Weak<EngineContext>,
+    exchange_account_id: &str,
+) -> anyhow::Result<String> {
+    let exchange_account_id = exchange_account_id
+        .parse::<ExchangeAccountId>()
+        .map_err(|err| anyhow!("Invalid exchange account id {exchange_account_id}: {err:?}"))?;
+    let engine_context = engine_context
+        .upgrade()
+        .context("Engine context is already dropped")?;
+
+    engine_context.stop_exchange(exchange_account_id).await?;
+    Ok(format!("Exchange {exchange_account_id} is stopped"))
 }
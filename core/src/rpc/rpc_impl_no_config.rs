@@ -1,31 +1,40 @@
-use jsonrpc_core::Result;
+use futures::future;
+use jsonrpc_core::{BoxFuture, Result};
 use mmb_rpc::rest_api::MmbRpc;
 use mmb_utils::send_expected::SendExpectedByRef;
 use parking_lot::Mutex;
+use serde_json::Value;
 use tokio::sync::mpsc;
 
 use std::sync::Arc;
 
 use crate::lifecycle::app_lifetime_manager::ActionAfterGracefulShutdown;
+use crate::services::audit_log::AuditLog;
 
-use super::common::send_stop;
-use super::common::set_config;
+use super::common::{audit, get_audit_log, get_config_audit_params, send_stop};
+use super::config_editor::ConfigEditor;
 
 static CONFIG_IS_NOT_SET: &str = "Config isn't set";
 
 pub struct RpcImplNoConfig {
     server_stopper_tx: Arc<Mutex<Option<mpsc::Sender<ActionAfterGracefulShutdown>>>>,
     wait_config_tx: mpsc::Sender<()>,
+    config_editor: ConfigEditor,
+    audit_log: Arc<AuditLog>,
 }
 
 impl RpcImplNoConfig {
-    pub fn new(
+    pub(crate) fn new(
         server_stopper_tx: Arc<Mutex<Option<mpsc::Sender<ActionAfterGracefulShutdown>>>>,
         wait_config_tx: mpsc::Sender<()>,
+        config_editor: ConfigEditor,
+        audit_log: Arc<AuditLog>,
     ) -> Self {
         Self {
             server_stopper_tx,
             wait_config_tx,
+            config_editor,
+            audit_log,
         }
     }
 }
@@ -35,21 +44,152 @@ impl MmbRpc for RpcImplNoConfig {
         Ok(CONFIG_IS_NOT_SET.into())
     }
 
-    fn stop(&self) -> Result<String> {
-        send_stop(self.server_stopper_tx.clone())
+    fn status(&self) -> BoxFuture<Result<String>> {
+        Box::pin(future::ok(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn stop(&self, operator: Option<String>) -> Result<String> {
+        let result = send_stop(self.server_stopper_tx.clone());
+        audit(
+            &self.audit_log,
+            operator.as_deref(),
+            "stop",
+            Value::Null,
+            &result,
+        );
+        result
     }
 
     fn get_config(&self) -> Result<String> {
         Ok(CONFIG_IS_NOT_SET.into())
     }
 
-    fn set_config(&self, settings: String) -> Result<String> {
-        set_config(settings)?;
+    fn set_config(&self, settings: String, operator: Option<String>) -> Result<String> {
+        let result = self.config_editor.set_config(&settings);
+        audit(
+            &self.audit_log,
+            operator.as_deref(),
+            "set_config",
+            get_config_audit_params(&settings),
+            &result,
+        );
+        result?;
+
         self.wait_config_tx.send_expected(());
         Ok("Config was successfully set. Trading engine will be launched".into())
     }
 
+    fn get_config_schema(&self) -> Result<String> {
+        self.config_editor.get_schema()
+    }
+
     fn stats(&self) -> Result<String> {
         Ok(CONFIG_IS_NOT_SET.into())
     }
+
+    fn query_stats(&self, _query: String) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn balances(&self) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn rate_limits(&self) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn shadow_report(&self) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn withdraw(&self, _withdrawal_request: String, _operator: Option<String>) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn confirm_withdraw(&self, _token: String, _operator: Option<String>) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn pending_refills(&self) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn reload_order_filter(&self, _operator: Option<String>) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn panic_button(
+        &self,
+        _close_positions: bool,
+        _operator: Option<String>,
+    ) -> BoxFuture<Result<String>> {
+        Box::pin(future::ok(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn stop_exchange(
+        &self,
+        _exchange_account_id: String,
+        _operator: Option<String>,
+    ) -> BoxFuture<Result<String>> {
+        Box::pin(future::ok(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn add_exchange(
+        &self,
+        _exchange_settings: String,
+        _operator: Option<String>,
+    ) -> BoxFuture<Result<String>> {
+        Box::pin(future::ok(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn disable_market(
+        &self,
+        _exchange_account_id: String,
+        _currency_pair: String,
+        _operator: Option<String>,
+    ) -> BoxFuture<Result<String>> {
+        Box::pin(future::ok(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn enable_market(
+        &self,
+        _exchange_account_id: String,
+        _currency_pair: String,
+        _operator: Option<String>,
+    ) -> BoxFuture<Result<String>> {
+        Box::pin(future::ok(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn audit_log(&self, count: usize) -> Result<String> {
+        get_audit_log(&self.audit_log, count)
+    }
+
+    fn orders(&self, _query: String) -> BoxFuture<Result<String>> {
+        Box::pin(future::ok(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn order_timeline(&self, _client_order_id: String) -> BoxFuture<Result<String>> {
+        Box::pin(future::ok(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn export_state(
+        &self,
+        _hand_over: bool,
+        _operator: Option<String>,
+    ) -> BoxFuture<Result<String>> {
+        Box::pin(future::ok(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn import_state(
+        &self,
+        _archive: String,
+        _operator: Option<String>,
+    ) -> BoxFuture<Result<String>> {
+        Box::pin(future::ok(CONFIG_IS_NOT_SET.into()))
+    }
+
+    fn cancel_handover(&self, _operator: Option<String>) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
 }
@@ -0,0 +1,159 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub static AUDIT_LOG_PATH: &str = "audit_log.jsonl";
+/// Principal of actions which are requested without operator identity
+pub static ANONYMOUS_PRINCIPAL: &str = "anonymous";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Succeeded,
+    Failed { error: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub time: DateTime,
+    pub principal: String,
+    pub action: String,
+    pub params: Value,
+    pub outcome: AuditOutcome,
+}
+
+/// Append-only log of operator actions requested via control panel.
+/// Every record is written as a separate JSON line, so log is never rewritten
+pub struct AuditLog {
+    path: PathBuf,
+    file: Mutex<Option<File>>,
+}
+
+impl AuditLog {
+    pub fn new(path: impl Into<PathBuf>) -> Arc<Self> {
+        Arc::new(Self {
+            path: path.into(),
+            file: Mutex::new(None),
+        })
+    }
+
+    pub fn record(
+        &self,
+        principal: Option<&str>,
+        action: &str,
+        params: Value,
+        outcome: AuditOutcome,
+    ) {
+        let record = AuditRecord {
+            time: Utc::now(),
+            principal: principal.unwrap_or(ANONYMOUS_PRINCIPAL).to_owned(),
+            action: action.to_owned(),
+            params,
+            outcome,
+        };
+
+        log::info!("Operator action: {:?}", record);
+        if let Err(error) = self.append(&record) {
+            log::error!("Unable to write audit record {:?}: {:?}", record, error);
+        }
+    }
+
+    fn append(&self, record: &AuditRecord) -> Result<()> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+
+        let mut file_guard = self.file.lock();
+        if file_guard.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .with_context(|| format!("Unable open audit log {:?}", self.path))?;
+            *file_guard = Some(file);
+        }
+        let file = file_guard.as_mut().expect("Audit log is opened above");
+
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?;
+
+        Ok(())
+    }
+
+    /// The latest `count` records in chronological order
+    pub fn last_records(&self, count: usize) -> Result<Vec<AuditRecord>> {
+        // Writing is locked, so partially written record isn't read
+        let _file_guard = self.file.lock();
+
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(error) => return Err(error.into()),
+        };
+
+        let lines = BufReader::new(file)
+            .lines()
+            .collect::<Result<Vec<_>, _>>()?;
+        lines[lines.len().saturating_sub(count)..]
+            .iter()
+            .map(|line| serde_json::from_str(line).context("Unable parse audit record"))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn records_are_appended_and_read_from_the_end() {
+        let path = std::env::temp_dir().join(format!("mmb_audit_{}.jsonl", uuid::Uuid::new_v4()));
+        let audit_log = AuditLog::new(&path);
+
+        audit_log.record(Some("alice"), "stop", Value::Null, AuditOutcome::Succeeded);
+        audit_log.record(
+            None,
+            "panic_button",
+            json!({ "close_positions": true }),
+            AuditOutcome::Failed {
+                error: "Trading engine service unavailable".into(),
+            },
+        );
+        audit_log.record(
+            Some("bob"),
+            "reload_order_filter",
+            Value::Null,
+            AuditOutcome::Succeeded,
+        );
+
+        // Records written earlier are kept after reopening of log
+        let records = AuditLog::new(&path).last_records(2).expect("in test");
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].principal, ANONYMOUS_PRINCIPAL);
+        assert_eq!(records[0].action, "panic_button");
+        assert_eq!(records[0].params["close_positions"], true);
+        assert!(matches!(records[0].outcome, AuditOutcome::Failed { .. }));
+        assert_eq!(records[1].principal, "bob");
+
+        std::fs::remove_file(&path).expect("in test");
+    }
+
+    #[test]
+    fn missing_audit_log_is_empty() {
+        let path = std::env::temp_dir().join(format!("mmb_audit_{}.jsonl", uuid::Uuid::new_v4()));
+
+        assert_eq!(
+            AuditLog::new(path).last_records(10).expect("in test"),
+            vec![]
+        );
+    }
+}
@@ -0,0 +1,336 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::FutureExt;
+use itertools::Itertools;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::exchanges::events::{EventLoopStallEvent, ExchangeEvent};
+use crate::exchanges::lag_aware_receiver::{LagAwareReceiver, ReceivedEvent};
+use crate::infrastructure::{spawn_by_timer, spawn_future};
+use crate::lifecycle::app_lifetime_manager::{ActionAfterGracefulShutdown, AppLifetimeManager};
+use crate::misc::human_duration::HumanDuration;
+use crate::misc::time::time_manager;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EventLoopWatchdogSettings {
+    /// Loop is stalled if it handles one event longer than this time
+    pub stall_threshold: HumanDuration,
+    /// Engine is restarted by graceful shutdown when loop is stalled
+    #[serde(default)]
+    pub restart_on_stall: bool,
+}
+
+impl Default for EventLoopWatchdogSettings {
+    fn default() -> Self {
+        Self {
+            stall_threshold: HumanDuration::from_secs(5),
+            restart_on_stall: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Iteration {
+    started_at: DateTime,
+    event_kind: &'static str,
+    /// Count of events in channel when iteration started
+    channel_events_count: u64,
+    is_stall_reported: bool,
+}
+
+/// State of one event loop which is updated by loop on every iteration
+pub struct LoopHeartbeat {
+    loop_name: String,
+    channel_events_count: Arc<AtomicU64>,
+    handled_events_count: AtomicU64,
+    iteration: Mutex<Option<Iteration>>,
+    last_event_time: Mutex<Option<DateTime>>,
+}
+
+impl LoopHeartbeat {
+    /// Marks start of handling of event. Iteration is finished when guard is dropped
+    pub fn iteration(&self, event: &ExchangeEvent) -> IterationGuard<'_> {
+        let now = time_manager::now();
+        *self.last_event_time.lock() = Some(now);
+        *self.iteration.lock() = Some(Iteration {
+            started_at: now,
+            event_kind: event.kind_name(),
+            channel_events_count: self.channel_events_count.load(Ordering::Relaxed),
+            is_stall_reported: false,
+        });
+
+        IterationGuard { heartbeat: self }
+    }
+}
+
+pub struct IterationGuard<'a> {
+    heartbeat: &'a LoopHeartbeat,
+}
+
+impl Drop for IterationGuard<'_> {
+    fn drop(&mut self) {
+        let iteration = self.heartbeat.iteration.lock().take();
+        let _ = self
+            .heartbeat
+            .handled_events_count
+            .fetch_add(1, Ordering::Relaxed);
+
+        if let Some(iteration) = iteration.filter(|x| x.is_stall_reported) {
+            log::warn!(
+                "Event loop '{}' recovered after handling of {} for {} ms",
+                self.heartbeat.loop_name,
+                iteration.event_kind,
+                (time_manager::now() - iteration.started_at).num_milliseconds()
+            );
+        }
+    }
+}
+
+/// State of event loop for diagnostics
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LoopState {
+    pub loop_name: String,
+    pub handled_events_count: u64,
+    /// Kind of event which is being handled now. `None` if loop waits for events
+    pub handled_event_kind: Option<&'static str>,
+    pub iteration_duration_ms: Option<i64>,
+    /// Count of events sent to channel since start of current iteration
+    pub pending_events_count: u64,
+    /// Time when loop started handling of the latest event
+    pub last_event_time: Option<DateTime>,
+}
+
+/// Watches main event loops (internal events loop, disposition executors) and raises
+/// `ExchangeEvent::EventLoopStall` when some loop handles one event for too long,
+/// e.g. because of deadlock or blocking call. Idle loops which wait for events aren't stalled
+pub struct EventLoopWatchdog {
+    settings: EventLoopWatchdogSettings,
+    heartbeats: Mutex<Vec<Arc<LoopHeartbeat>>>,
+    /// Count of all events sent to events channel, so backlog of stalled loop can be estimated
+    channel_events_count: Arc<AtomicU64>,
+    events_sender: broadcast::Sender<ExchangeEvent>,
+    lifetime_manager: Arc<AppLifetimeManager>,
+}
+
+impl EventLoopWatchdog {
+    pub fn new(
+        settings: EventLoopWatchdogSettings,
+        events_sender: broadcast::Sender<ExchangeEvent>,
+        lifetime_manager: Arc<AppLifetimeManager>,
+    ) -> Arc<Self> {
+        let watchdog = Arc::new(Self {
+            settings,
+            heartbeats: Default::default(),
+            channel_events_count: Default::default(),
+            events_sender: events_sender.clone(),
+            lifetime_manager,
+        });
+
+        let events_receiver = LagAwareReceiver::new("Event loop watchdog", &events_sender);
+        let action = count_channel_events(events_receiver, watchdog.channel_events_count.clone());
+        let _ = spawn_future(
+            "Count events for event loop watchdog",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action.boxed(),
+        );
+
+        let watchdog_weak = Arc::downgrade(&watchdog);
+        let check_period = watchdog.stall_threshold() / 2;
+        let _ = spawn_by_timer(
+            move || {
+                let watchdog_weak = watchdog_weak.clone();
+                async move {
+                    if let Some(watchdog) = watchdog_weak.upgrade() {
+                        watchdog.check_loops();
+                    }
+                }
+                .boxed()
+            },
+            "Check event loops",
+            check_period,
+            check_period,
+            SpawnFutureFlags::STOP_BY_TOKEN,
+        );
+
+        watchdog
+    }
+
+    /// Heartbeat which loop should update on every iteration. `loop_name` is used in diagnostics
+    pub fn register(&self, loop_name: impl Into<String>) -> Arc<LoopHeartbeat> {
+        let heartbeat = Arc::new(LoopHeartbeat {
+            loop_name: loop_name.into(),
+            channel_events_count: self.channel_events_count.clone(),
+            handled_events_count: Default::default(),
+            iteration: Default::default(),
+            last_event_time: Default::default(),
+        });
+        self.heartbeats.lock().push(heartbeat.clone());
+        heartbeat
+    }
+
+    pub fn loop_states(&self) -> Vec<LoopState> {
+        let now = time_manager::now();
+        let channel_events_count = self.channel_events_count.load(Ordering::Relaxed);
+        self.heartbeats
+            .lock()
+            .iter()
+            .map(|heartbeat| {
+                let iteration = *heartbeat.iteration.lock();
+                LoopState {
+                    loop_name: heartbeat.loop_name.clone(),
+                    handled_events_count: heartbeat.handled_events_count.load(Ordering::Relaxed),
+                    handled_event_kind: iteration.map(|x| x.event_kind),
+                    iteration_duration_ms: iteration
+                        .map(|x| (now - x.started_at).num_milliseconds()),
+                    pending_events_count: iteration.map_or(0, |x| {
+                        channel_events_count.saturating_sub(x.channel_events_count)
+                    }),
+                    last_event_time: *heartbeat.last_event_time.lock(),
+                }
+            })
+            .collect()
+    }
+
+    fn stall_threshold(&self) -> Duration {
+        self.settings.stall_threshold.duration()
+    }
+
+    fn check_loops(&self) {
+        let now = time_manager::now();
+        let stall_threshold =
+            chrono::Duration::from_std(self.stall_threshold()).expect("threshold is too long");
+
+        let stalled_loops = self
+            .heartbeats
+            .lock()
+            .iter()
+            .filter(|heartbeat| {
+                let mut iteration = heartbeat.iteration.lock();
+                match iteration.as_mut() {
+                    Some(iteration)
+                        if !iteration.is_stall_reported
+                            && now - iteration.started_at > stall_threshold =>
+                    {
+                        iteration.is_stall_reported = true;
+                        true
+                    }
+                    _ => false,
+                }
+            })
+            .map(|heartbeat| heartbeat.loop_name.clone())
+            .collect_vec();
+        if stalled_loops.is_empty() {
+            return;
+        }
+
+        let loop_states = self.loop_states();
+        log::error!(
+            "Event loops {} are stalled longer than {}. States of event loops: {:#?}",
+            stalled_loops.iter().join(", "),
+            self.settings.stall_threshold,
+            loop_states
+        );
+
+        for state in loop_states
+            .into_iter()
+            .filter(|x| stalled_loops.contains(&x.loop_name))
+        {
+            let _ = self
+                .events_sender
+                .send(ExchangeEvent::EventLoopStall(EventLoopStallEvent {
+                    loop_name: state.loop_name,
+                    handled_event_kind: state.handled_event_kind.unwrap_or_default(),
+                    stalled_for_ms: state.iteration_duration_ms.unwrap_or_default(),
+                    pending_events_count: state.pending_events_count,
+                }));
+        }
+
+        if self.settings.restart_on_stall {
+            let _ = self.lifetime_manager.spawn_graceful_shutdown_with_action(
+                format!("Event loops {} are stalled", stalled_loops.join(", ")),
+                ActionAfterGracefulShutdown::Restart,
+            );
+        }
+    }
+}
+
+async fn count_channel_events(
+    mut events_receiver: LagAwareReceiver<ExchangeEvent>,
+    channel_events_count: Arc<AtomicU64>,
+) -> anyhow::Result<()> {
+    while let Some(received) = events_receiver.recv().await {
+        let count = match received {
+            ReceivedEvent::Event(_) => 1,
+            ReceivedEvent::EventsDropped { count } => count,
+        };
+        let _ = channel_events_count.fetch_add(count, Ordering::Relaxed);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use mmb_utils::cancellation_token::CancellationToken;
+
+    use super::*;
+    use crate::exchanges::common::ExchangeAccountId;
+    use crate::exchanges::events::MarginCallEvent;
+
+    #[test]
+    fn long_iteration_is_reported_as_stall_once() {
+        let (events_sender, mut events_receiver) = broadcast::channel(10);
+        let watchdog = EventLoopWatchdog {
+            settings: EventLoopWatchdogSettings::default(),
+            heartbeats: Default::default(),
+            channel_events_count: Default::default(),
+            events_sender,
+            lifetime_manager: AppLifetimeManager::new(CancellationToken::default()),
+        };
+        let _idle_heartbeat = watchdog.register("idle");
+        let heartbeat = watchdog.register("busy");
+
+        let event = ExchangeEvent::MarginCall(MarginCallEvent {
+            exchange_account_id: ExchangeAccountId::new("Binance".into(), 0),
+            margin_ratio: None,
+            positions: Vec::new(),
+        });
+        let iteration = heartbeat.iteration(&event);
+        watchdog.check_loops();
+        assert!(events_receiver.try_recv().is_err());
+
+        heartbeat
+            .iteration
+            .lock()
+            .as_mut()
+            .expect("in test")
+            .started_at -= chrono::Duration::seconds(10);
+        watchdog.channel_events_count.store(3, Ordering::Relaxed);
+        watchdog.check_loops();
+        watchdog.check_loops();
+
+        match events_receiver.try_recv().expect("in test") {
+            ExchangeEvent::EventLoopStall(stall) => {
+                assert_eq!(stall.loop_name, "busy");
+                assert_eq!(stall.handled_event_kind, "margin_call");
+                assert_eq!(stall.pending_events_count, 3);
+            }
+            _ => panic!("unexpected event"),
+        }
+        assert!(events_receiver.try_recv().is_err());
+
+        drop(iteration);
+        let states = watchdog.loop_states();
+        assert_eq!(states[0].handled_events_count, 0);
+        assert_eq!(states[1].handled_events_count, 1);
+        assert_eq!(states[1].handled_event_kind, None);
+        assert_eq!(states[0].last_event_time, None);
+        assert!(states[1].last_event_time.is_some());
+    }
+}
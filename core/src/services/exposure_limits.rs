@@ -0,0 +1,1006 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use dashmap::DashMap;
+use futures::future::join_all;
+use futures::FutureExt;
+use itertools::Itertools;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::balance_manager::balance_manager::BalanceManager;
+use crate::exchanges::common::{
+    ActivePosition, Amount, CurrencyCode, CurrencyPair, ExchangeAccountId, Price,
+};
+use crate::exchanges::events::ExchangeEvent;
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::order::close_position::SmartCloseSettings;
+use crate::exchanges::general::symbol::{Round, Symbol};
+use crate::exchanges::lag_aware_receiver::{ExchangeEventsReceiver, ReceivedEvent};
+use crate::infrastructure::spawn_future;
+use crate::misc::derivative_position::DerivativePosition;
+use crate::orders::event::OrderEventType;
+use crate::orders::order::{OrderCreating, OrderSide, OrderSnapshot};
+use crate::orders::pool::OrderRef;
+use crate::services::scheduler::{Schedule, Scheduler};
+
+const CHECK_PERIOD: Duration = Duration::from_secs(5);
+/// Time for requesting of positions from one exchange during post-trade check
+const POSITIONS_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const REDUCTION_ORDER_BOOK_DEPTH: usize = 20;
+const REDUCTION_LIMIT_ORDER_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ExposureLimitsSettings {
+    /// Max gross notional of positions and open orders of all exchange accounts by quote currency
+    #[serde(default)]
+    pub max_notional_by_currency: HashMap<CurrencyCode, Amount>,
+    /// Max absolute net position of all exchange accounts by base currency
+    #[serde(default)]
+    pub max_delta_by_underlying: HashMap<CurrencyCode, Amount>,
+    /// Max slippage of orders which reduce positions after breach of limits as part of price.
+    /// Positions aren't reduced if it isn't specified, only open orders are cancelled
+    pub reduction_max_slippage: Option<Decimal>,
+}
+
+impl ExposureLimitsSettings {
+    fn is_empty(&self) -> bool {
+        self.max_notional_by_currency.is_empty() && self.max_delta_by_underlying.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ExposureLimitError {
+    #[error("Notional {requested_notional} {currency_code} of order exceeds limit {max_notional} {currency_code}, current notional {notional} {currency_code}")]
+    Notional {
+        currency_code: CurrencyCode,
+        max_notional: Amount,
+        notional: Amount,
+        requested_notional: Amount,
+    },
+    #[error("Order can change delta of {underlying} to {delta} which exceeds limit {max_delta}")]
+    Delta {
+        underlying: CurrencyCode,
+        max_delta: Amount,
+        delta: Amount,
+    },
+    #[error("Amount in quote currency can't be converted by non-positive price {price}")]
+    NonPositivePrice { price: Price },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct UnderlyingExposure {
+    /// Sum of signed positions in base currency (positive for long positions) and spot balances
+    pub delta: Amount,
+    /// Balance of currency on spot exchange accounts which is included in delta
+    pub spot_balance: Amount,
+    /// Not filled amount of open buy orders
+    pub open_buy_amount: Amount,
+    /// Not filled amount of open sell orders
+    pub open_sell_amount: Amount,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct NotionalExposure {
+    pub positions: Amount,
+    pub open_orders: Amount,
+}
+
+/// Exposure of all exchange accounts of engine
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PortfolioExposure {
+    pub by_underlying: HashMap<CurrencyCode, UnderlyingExposure>,
+    pub notional_by_currency: HashMap<CurrencyCode, NotionalExposure>,
+}
+
+impl PortfolioExposure {
+    fn add_position(&mut self, symbol: &Symbol, position: &ActivePosition) {
+        let delta = get_signed_position(position);
+        self.by_underlying
+            .entry(symbol.base_currency_code())
+            .or_default()
+            .delta += delta;
+        self.notional_by_currency
+            .entry(symbol.quote_currency_code())
+            .or_default()
+            .positions += delta.abs() * position.derivative.average_entry_price;
+    }
+
+    fn add_spot_balance(&mut self, currency_code: CurrencyCode, balance: Amount) {
+        let exposure = self.by_underlying.entry(currency_code).or_default();
+        exposure.delta += balance;
+        exposure.spot_balance += balance;
+    }
+
+    fn add_order(&mut self, symbol: &Symbol, side: OrderSide, amount: Amount, price: Price) {
+        let exposure = self
+            .by_underlying
+            .entry(symbol.base_currency_code())
+            .or_default();
+        match side {
+            OrderSide::Buy => exposure.open_buy_amount += amount,
+            OrderSide::Sell => exposure.open_sell_amount += amount,
+        }
+        self.notional_by_currency
+            .entry(symbol.quote_currency_code())
+            .or_default()
+            .open_orders += amount * price;
+    }
+}
+
+/// Breach of limit by positions which is found by post-trade check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExposureBreach {
+    /// Excess of notional of positions over limit
+    Notional {
+        currency_code: CurrencyCode,
+        excess: Amount,
+    },
+    /// Excess of delta over limit with sign of delta
+    Delta {
+        underlying: CurrencyCode,
+        excess: Amount,
+    },
+}
+
+/// Part of position which should be closed to get exposure back within limits
+#[derive(Debug, Clone)]
+struct PositionReduction {
+    exchange_account_id: ExchangeAccountId,
+    position: ActivePosition,
+    amount: Amount,
+}
+
+fn get_signed_position(position: &ActivePosition) -> Amount {
+    let derivative = &position.derivative;
+    match derivative.side {
+        Some(OrderSide::Buy) => derivative.position.abs(),
+        Some(OrderSide::Sell) => -derivative.position.abs(),
+        None => derivative.position,
+    }
+}
+
+/// Updates position of market by fill, so checks see fills before the next request of positions.
+/// Returns `false` for positions in hedge mode, because it isn't known which of them is changed
+fn apply_fill(
+    positions: &mut Vec<ActivePosition>,
+    currency_pair: CurrencyPair,
+    side: OrderSide,
+    amount: Amount,
+    price: Price,
+) -> bool {
+    let fill_amount = match side {
+        OrderSide::Buy => amount,
+        OrderSide::Sell => -amount,
+    };
+
+    let indices = positions
+        .iter()
+        .positions(|x| x.derivative.currency_pair == currency_pair)
+        .collect_vec();
+    let index = match indices[..] {
+        [] => {
+            positions.push(ActivePosition::new(DerivativePosition::new(
+                currency_pair,
+                fill_amount,
+                None,
+                None,
+                price,
+                dec!(0),
+                dec!(1),
+            )));
+            return true;
+        }
+        [index] if positions[index].derivative.position_side.is_none() => index,
+        _ => return false,
+    };
+
+    let current = get_signed_position(&positions[index]);
+    let updated = current + fill_amount;
+    if updated.is_zero() {
+        let _ = positions.remove(index);
+        return true;
+    }
+
+    let derivative = &mut positions[index].derivative;
+    if updated.signum() != current.signum() {
+        derivative.average_entry_price = price;
+    } else if updated.abs() > current.abs() {
+        derivative.average_entry_price =
+            (current.abs() * derivative.average_entry_price + amount * price) / updated.abs();
+    }
+    derivative.position = updated;
+    derivative.side = None;
+    true
+}
+
+/// Pre-trade check of order. Open orders on the side of order are supposed to be filled, so delta
+/// is checked in the worst case. Orders which move delta toward zero pass the check even if
+/// limit is already breached
+fn check_order_exposure(
+    settings: &ExposureLimitsSettings,
+    exposure: &PortfolioExposure,
+    symbol: &Symbol,
+    side: OrderSide,
+    amount: Amount,
+    price: Price,
+) -> Result<(), ExposureLimitError> {
+    let currency_code = symbol.quote_currency_code();
+    if let Some(&max_notional) = settings.max_notional_by_currency.get(&currency_code) {
+        let notional = exposure
+            .notional_by_currency
+            .get(&currency_code)
+            .map_or(dec!(0), |x| x.positions + x.open_orders);
+        let requested_notional = amount * price;
+        if notional + requested_notional > max_notional {
+            return Err(ExposureLimitError::Notional {
+                currency_code,
+                max_notional,
+                notional,
+                requested_notional,
+            });
+        }
+    }
+
+    let underlying = symbol.base_currency_code();
+    if let Some(&max_delta) = settings.max_delta_by_underlying.get(&underlying) {
+        let current = exposure
+            .by_underlying
+            .get(&underlying)
+            .copied()
+            .unwrap_or_default();
+        let delta = match side {
+            OrderSide::Buy => current.delta + current.open_buy_amount + amount,
+            OrderSide::Sell => current.delta - current.open_sell_amount - amount,
+        };
+        let is_increased = match side {
+            OrderSide::Buy => delta > dec!(0),
+            OrderSide::Sell => delta < dec!(0),
+        };
+        if is_increased && delta.abs() > max_delta {
+            return Err(ExposureLimitError::Delta {
+                underlying,
+                max_delta,
+                delta,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn get_breaches(
+    settings: &ExposureLimitsSettings,
+    exposure: &PortfolioExposure,
+) -> Vec<ExposureBreach> {
+    let mut breaches = Vec::new();
+
+    for (&currency_code, &max_notional) in &settings.max_notional_by_currency {
+        let notional = exposure
+            .notional_by_currency
+            .get(&currency_code)
+            .map_or(dec!(0), |x| x.positions);
+        if notional > max_notional {
+            breaches.push(ExposureBreach::Notional {
+                currency_code,
+                excess: notional - max_notional,
+            });
+        }
+    }
+
+    for (&underlying, &max_delta) in &settings.max_delta_by_underlying {
+        let delta = exposure
+            .by_underlying
+            .get(&underlying)
+            .map_or(dec!(0), |x| x.delta);
+        if delta.abs() > max_delta {
+            breaches.push(ExposureBreach::Delta {
+                underlying,
+                excess: (delta.abs() - max_delta) * delta.signum(),
+            });
+        }
+    }
+
+    breaches
+}
+
+/// Positions which are reduced to eliminate breach, the largest positions are reduced first
+fn plan_reductions(
+    breach: ExposureBreach,
+    positions: &[(ExchangeAccountId, Arc<Symbol>, ActivePosition)],
+) -> Vec<PositionReduction> {
+    let mut candidates = positions
+        .iter()
+        .filter(|(_, symbol, position)| match breach {
+            ExposureBreach::Notional { currency_code, .. } => {
+                symbol.quote_currency_code() == currency_code
+            }
+            ExposureBreach::Delta { underlying, excess } => {
+                symbol.base_currency_code() == underlying
+                    && get_signed_position(position).signum() == excess.signum()
+            }
+        })
+        .map(|(exchange_account_id, symbol, position)| {
+            // Exposure which is removed by closing of unit of position
+            let unit_exposure = match breach {
+                ExposureBreach::Notional { .. } => position.derivative.average_entry_price,
+                ExposureBreach::Delta { .. } => dec!(1),
+            };
+            (*exchange_account_id, symbol, position, unit_exposure)
+        })
+        .filter(|(_, _, _, unit_exposure)| !unit_exposure.is_zero())
+        .collect_vec();
+    candidates.sort_by(|(_, _, a, a_unit), (_, _, b, b_unit)| {
+        let a_exposure = get_signed_position(a).abs() * a_unit;
+        let b_exposure = get_signed_position(b).abs() * b_unit;
+        b_exposure.cmp(&a_exposure)
+    });
+
+    let mut remaining_excess = match breach {
+        ExposureBreach::Notional { excess, .. } => excess,
+        ExposureBreach::Delta { excess, .. } => excess.abs(),
+    };
+    let mut reductions = Vec::new();
+    for (exchange_account_id, symbol, position, unit_exposure) in candidates {
+        if remaining_excess <= dec!(0) {
+            break;
+        }
+
+        let position_amount = get_signed_position(position).abs();
+        let amount = symbol
+            .amount_round(remaining_excess / unit_exposure, Round::Ceiling)
+            .min(position_amount);
+        if amount.is_zero() {
+            continue;
+        }
+
+        remaining_excess -= amount * unit_exposure;
+        reductions.push(PositionReduction {
+            exchange_account_id,
+            position: position.clone(),
+            amount,
+        });
+    }
+
+    reductions
+}
+
+/// Cross-strategy limits of exposure of all exchange accounts: gross notional by quote currency
+/// and net delta by underlying. Every order of engine is checked before creation against
+/// positions of the latest post-trade check and current open orders. Post-trade check is executed
+/// periodically, it cancels open orders which increase breached exposure and optionally reduces
+/// positions. Positions are also updated by fills between checks. Balances of spot exchange
+/// accounts are counted in delta of their currencies, but only derivative positions are reduced
+/// after breach
+pub struct ExposureLimits {
+    settings: ExposureLimitsSettings,
+    /// Exchanges of engine context, so exchanges which are added at runtime are checked too
+    exchanges: Arc<DashMap<ExchangeAccountId, Arc<Exchange>>>,
+    balance_manager: Arc<Mutex<BalanceManager>>,
+    positions: Mutex<HashMap<ExchangeAccountId, Vec<ActivePosition>>>,
+    spot_balances: Mutex<HashMap<ExchangeAccountId, HashMap<CurrencyCode, Amount>>>,
+    is_checking: tokio::sync::Mutex<()>,
+}
+
+impl ExposureLimits {
+    pub fn new(
+        settings: ExposureLimitsSettings,
+        exchanges: Arc<DashMap<ExchangeAccountId, Arc<Exchange>>>,
+        balance_manager: Arc<Mutex<BalanceManager>>,
+        scheduler: Arc<Scheduler>,
+        events_receiver: ExchangeEventsReceiver,
+    ) -> Arc<Self> {
+        let is_empty = settings.is_empty();
+        let service = Arc::new(Self {
+            settings,
+            exchanges,
+            balance_manager,
+            positions: Mutex::new(HashMap::new()),
+            spot_balances: Mutex::new(HashMap::new()),
+            is_checking: tokio::sync::Mutex::new(()),
+        });
+
+        if is_empty {
+            return service;
+        }
+
+        let action = service.clone().handle_fills(events_receiver);
+        let _ = spawn_future(
+            "Update positions of exposure limits by fills",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action.boxed(),
+        );
+
+        let service_weak = Arc::downgrade(&service);
+        let _ = scheduler.schedule(
+            "Check exposure limits",
+            Schedule::Every(CHECK_PERIOD),
+            move |cancellation_token| {
+                let service_weak = service_weak.clone();
+                async move {
+                    if let Some(service) = service_weak.upgrade() {
+                        service.check_post_trade(cancellation_token).await;
+                    }
+                }
+                .boxed()
+            },
+        );
+
+        service
+    }
+
+    async fn handle_fills(
+        self: Arc<Self>,
+        mut events_receiver: ExchangeEventsReceiver,
+    ) -> Result<()> {
+        loop {
+            // Positions are restored by the next post-trade check if fills are dropped
+            let event = match events_receiver.recv().await {
+                Some(ReceivedEvent::Event(event)) => event,
+                Some(ReceivedEvent::EventsDropped { .. }) => continue,
+                None => return Ok(()),
+            };
+
+            if let ExchangeEvent::OrderEvent(order_event) = event {
+                if let OrderEventType::OrderFilled { cloned_order } = order_event.event_type {
+                    self.update_position_by_fill(&cloned_order);
+                }
+            }
+        }
+    }
+
+    fn update_position_by_fill(&self, order: &OrderSnapshot) {
+        let fill = match order.fills.fills.last() {
+            Some(fill) => fill,
+            None => return,
+        };
+
+        let exchange_account_id = order.header.exchange_account_id;
+        let currency_pair = order.header.currency_pair;
+        let is_derivative = self
+            .exchanges
+            .get(&exchange_account_id)
+            .and_then(|exchange| {
+                exchange
+                    .symbols
+                    .get(&currency_pair)
+                    .map(|x| x.is_derivative)
+            })
+            .unwrap_or(false);
+        if !is_derivative {
+            return;
+        }
+
+        let is_updated = apply_fill(
+            self.positions
+                .lock()
+                .entry(exchange_account_id)
+                .or_default(),
+            currency_pair,
+            order.header.side,
+            fill.amount(),
+            fill.price(),
+        );
+        if !is_updated {
+            log::debug!(
+                "Position of {} on {} isn't updated by fill in hedge mode until the next check",
+                currency_pair,
+                exchange_account_id
+            );
+        }
+    }
+
+    /// Pre-trade check which is called by `Exchange::create_order` with price of order
+    /// or estimated price of market order.
+    /// Reduce-only orders aren't checked because they can only decrease exposure
+    pub fn check_order(
+        &self,
+        symbol: &Symbol,
+        order: &OrderCreating,
+        price: Price,
+    ) -> Result<(), ExposureLimitError> {
+        if self.settings.is_empty() || order.header.reduce_only {
+            return Ok(());
+        }
+
+        let amount = order
+            .header
+            .base_amount(price)
+            .ok_or(ExposureLimitError::NonPositivePrice { price })?;
+
+        check_order_exposure(
+            &self.settings,
+            &self.get_exposure(),
+            symbol,
+            order.header.side,
+            amount,
+            price,
+        )
+    }
+
+    /// Exposure by positions of the latest post-trade check updated by fills, spot balances
+    /// and open orders
+    pub fn get_exposure(&self) -> PortfolioExposure {
+        let mut exposure = PortfolioExposure::default();
+
+        for (exchange_account_id, positions) in self.positions.lock().iter() {
+            let exchange = match self.exchanges.get(exchange_account_id) {
+                Some(exchange) => exchange,
+                None => continue,
+            };
+            for position in positions {
+                if let Some(symbol) = exchange.symbols.get(&position.derivative.currency_pair) {
+                    exposure.add_position(&symbol, position);
+                }
+            }
+        }
+
+        for balances in self.spot_balances.lock().values() {
+            for (&currency_code, &balance) in balances {
+                exposure.add_spot_balance(currency_code, balance);
+            }
+        }
+
+        for exchange in self.exchanges.iter() {
+            for order in exchange.orders.not_finished.iter() {
+                let (currency_pair, side, amount, price) = order.fn_ref(|x| {
+                    (
+                        x.header.currency_pair,
+                        x.header.side,
+                        x.remaining_base_amount(),
+                        x.price(),
+                    )
+                });
+                let amount = match amount {
+                    Some(amount) => amount,
+                    None => continue,
+                };
+                if let Some(symbol) = exchange.symbols.get(&currency_pair) {
+                    exposure.add_order(&symbol, side, amount, price);
+                }
+            }
+        }
+
+        exposure
+    }
+
+    async fn check_post_trade(&self, cancellation_token: CancellationToken) {
+        // Reduction of previous check is still executed
+        let _checking_guard = match self.is_checking.try_lock() {
+            Ok(guard) => guard,
+            Err(_) => return,
+        };
+
+        self.refresh_positions(cancellation_token.clone()).await;
+        self.refresh_spot_balances();
+
+        for breach in get_breaches(&self.settings, &self.get_exposure()) {
+            log::warn!("Exposure limit is breached: {:?}", breach);
+
+            self.cancel_increasing_orders(breach, cancellation_token.clone())
+                .await;
+            if let Some(max_slippage) = self.settings.reduction_max_slippage {
+                self.reduce_positions(breach, max_slippage, cancellation_token.clone())
+                    .await;
+            }
+        }
+    }
+
+    async fn refresh_positions(&self, cancellation_token: CancellationToken) {
+        let exchanges = self
+            .exchanges
+            .iter()
+            .map(|x| x.value().clone())
+            .collect_vec();
+        let requests = exchanges.into_iter().map(|exchange| {
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                let positions = tokio::time::timeout(
+                    POSITIONS_REQUEST_TIMEOUT,
+                    exchange.get_active_positions(cancellation_token),
+                )
+                .await;
+                (exchange.exchange_account_id, positions)
+            }
+        });
+
+        for (exchange_account_id, positions) in join_all(requests).await {
+            match positions {
+                Ok(positions) => {
+                    let positions = positions
+                        .into_iter()
+                        .filter(|x| !x.derivative.position.is_zero())
+                        .collect_vec();
+                    let _ = self.positions.lock().insert(exchange_account_id, positions);
+                }
+                // Positions of previous check are used
+                Err(_) => log::warn!(
+                    "Unable to get positions of {} for exposure limits check in {} secs",
+                    exchange_account_id,
+                    POSITIONS_REQUEST_TIMEOUT.as_secs()
+                ),
+            }
+        }
+    }
+
+    /// Balances are taken from balance manager, so they aren't requested from exchanges
+    fn refresh_spot_balances(&self) {
+        let balances_by_exchange_id = self
+            .balance_manager
+            .lock()
+            .get_balances()
+            .balances_by_exchange_id
+            .unwrap_or_default();
+
+        let spot_balances = self
+            .exchanges
+            .iter()
+            .filter(|exchange| is_spot_exchange(exchange))
+            .filter_map(|exchange| {
+                let balances = balances_by_exchange_id.get(&exchange.exchange_account_id)?;
+                Some((exchange.exchange_account_id, balances.clone()))
+            })
+            .collect();
+        *self.spot_balances.lock() = spot_balances;
+    }
+
+    async fn cancel_increasing_orders(
+        &self,
+        breach: ExposureBreach,
+        cancellation_token: CancellationToken,
+    ) {
+        let orders = self
+            .exchanges
+            .iter()
+            .flat_map(|exchange| {
+                exchange
+                    .orders
+                    .not_finished
+                    .iter()
+                    .filter(|order| is_increasing_order(&exchange, order.value(), breach))
+                    .map(|order| (exchange.value().clone(), order.value().clone()))
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        let cancellations = orders.into_iter().map(|(exchange, order)| {
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                log::warn!(
+                    "Cancelling order {} on {} because exposure limit is breached",
+                    order.client_order_id(),
+                    exchange.exchange_account_id
+                );
+
+                if let Err(error) = exchange
+                    .wait_cancel_order_confirmed(order.clone(), None, true, cancellation_token)
+                    .await
+                {
+                    log::warn!(
+                        "Unable to cancel order {} on {}: {:?}",
+                        order.client_order_id(),
+                        exchange.exchange_account_id,
+                        error
+                    );
+                }
+            }
+        });
+        join_all(cancellations).await;
+    }
+
+    async fn reduce_positions(
+        &self,
+        breach: ExposureBreach,
+        max_slippage: Decimal,
+        cancellation_token: CancellationToken,
+    ) {
+        let positions = self
+            .positions
+            .lock()
+            .iter()
+            .flat_map(|(exchange_account_id, positions)| {
+                let exchange = self.exchanges.get(exchange_account_id)?;
+                let positions = positions
+                    .iter()
+                    .filter_map(|position| {
+                        let symbol = exchange
+                            .symbols
+                            .get(&position.derivative.currency_pair)?
+                            .clone();
+                        Some((*exchange_account_id, symbol, position.clone()))
+                    })
+                    .collect_vec();
+                Some(positions)
+            })
+            .flatten()
+            .collect_vec();
+
+        let close_settings = SmartCloseSettings {
+            max_slippage,
+            max_child_amount: None,
+            order_book_depth: REDUCTION_ORDER_BOOK_DEPTH,
+            limit_order_timeout: REDUCTION_LIMIT_ORDER_TIMEOUT,
+        };
+        for reduction in plan_reductions(breach, &positions) {
+            let exchange = match self.exchanges.get(&reduction.exchange_account_id) {
+                Some(exchange) => exchange.value().clone(),
+                None => continue,
+            };
+
+            // Only part of position is closed
+            let mut position = reduction.position;
+            position.derivative.position = reduction.amount * position.derivative.position.signum();
+
+            log::warn!(
+                "Reducing position {} on {} by {} because exposure limit is breached",
+                position.id,
+                reduction.exchange_account_id,
+                reduction.amount
+            );
+            if let Err(error) = exchange
+                .close_position_smart(&position, &close_settings, cancellation_token.clone())
+                .await
+            {
+                log::error!(
+                    "Unable to reduce position {} on {}: {:?}",
+                    position.id,
+                    reduction.exchange_account_id,
+                    error
+                );
+            }
+        }
+    }
+}
+
+/// Balances of derivative exchange accounts are collateral, so they aren't exposure
+fn is_spot_exchange(exchange: &Exchange) -> bool {
+    !exchange.symbols.is_empty() && exchange.symbols.iter().all(|x| !x.is_derivative)
+}
+
+fn is_increasing_order(exchange: &Exchange, order: &OrderRef, breach: ExposureBreach) -> bool {
+    let (currency_pair, side, reduce_only) =
+        order.fn_ref(|x| (x.header.currency_pair, x.header.side, x.header.reduce_only));
+    if reduce_only || order.is_external_order() {
+        return false;
+    }
+
+    let symbol = match exchange.symbols.get(&currency_pair) {
+        Some(symbol) => symbol,
+        None => return false,
+    };
+    match breach {
+        ExposureBreach::Notional { currency_code, .. } => {
+            symbol.quote_currency_code() == currency_code
+        }
+        ExposureBreach::Delta { underlying, excess } => {
+            let increasing_side = match excess > dec!(0) {
+                true => OrderSide::Buy,
+                false => OrderSide::Sell,
+            };
+            symbol.base_currency_code() == underlying && side == increasing_side
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orders::order::PositionSide;
+    use crate::test_util::SymbolBuilder;
+
+    fn symbol(base: &str) -> Arc<Symbol> {
+        Arc::new(
+            SymbolBuilder::new(base.into(), "usdt".into())
+                .is_derivative(true)
+                .build(),
+        )
+    }
+
+    fn position(base: &str, position: Amount, price: Price) -> ActivePosition {
+        ActivePosition::new(DerivativePosition::new(
+            CurrencyPair::from_codes(base.into(), "usdt".into()),
+            position,
+            None,
+            None,
+            price,
+            dec!(0),
+            dec!(1),
+        ))
+    }
+
+    fn settings() -> ExposureLimitsSettings {
+        ExposureLimitsSettings {
+            max_notional_by_currency: HashMap::from([("usdt".into(), dec!(10000))]),
+            max_delta_by_underlying: HashMap::from([("btc".into(), dec!(1))]),
+            reduction_max_slippage: None,
+        }
+    }
+
+    #[test]
+    fn order_is_checked_against_positions_and_open_orders() {
+        let btc = symbol("btc");
+        let mut exposure = PortfolioExposure::default();
+        exposure.add_position(&btc, &position("btc", dec!(0.5), dec!(1000)));
+        exposure.add_order(&btc, OrderSide::Buy, dec!(0.3), dec!(1000));
+
+        let check = |side, amount| {
+            check_order_exposure(&settings(), &exposure, &btc, side, amount, dec!(1000))
+        };
+        assert_eq!(check(OrderSide::Buy, dec!(0.2)), Ok(()));
+        assert_eq!(
+            check(OrderSide::Buy, dec!(0.3)),
+            Err(ExposureLimitError::Delta {
+                underlying: "btc".into(),
+                max_delta: dec!(1),
+                delta: dec!(1.1),
+            })
+        );
+        // Sell order decreases delta
+        assert_eq!(check(OrderSide::Sell, dec!(1.5)), Ok(()));
+        assert_eq!(
+            check(OrderSide::Sell, dec!(9.5)),
+            Err(ExposureLimitError::Notional {
+                currency_code: "usdt".into(),
+                max_notional: dec!(10000),
+                notional: dec!(800),
+                requested_notional: dec!(9500),
+            })
+        );
+    }
+
+    #[test]
+    fn spot_balance_is_counted_in_delta() {
+        let btc = symbol("btc");
+        let mut exposure = PortfolioExposure::default();
+        exposure.add_spot_balance("btc".into(), dec!(0.8));
+        exposure.add_position(&btc, &position("btc", dec!(-0.3), dec!(1000)));
+
+        let underlying = exposure.by_underlying[&CurrencyCode::from("btc")];
+        assert_eq!(underlying.delta, dec!(0.5));
+        assert_eq!(underlying.spot_balance, dec!(0.8));
+
+        let check = |amount| {
+            check_order_exposure(
+                &settings(),
+                &exposure,
+                &btc,
+                OrderSide::Buy,
+                amount,
+                dec!(1000),
+            )
+        };
+        assert_eq!(check(dec!(0.5)), Ok(()));
+        assert_eq!(
+            check(dec!(0.6)),
+            Err(ExposureLimitError::Delta {
+                underlying: "btc".into(),
+                max_delta: dec!(1),
+                delta: dec!(1.1),
+            })
+        );
+
+        exposure.add_spot_balance("btc".into(), dec!(0.7));
+        assert_eq!(
+            get_breaches(&settings(), &exposure),
+            vec![ExposureBreach::Delta {
+                underlying: "btc".into(),
+                excess: dec!(0.2),
+            }]
+        );
+    }
+
+    fn fill_btc(
+        positions: &mut Vec<ActivePosition>,
+        side: OrderSide,
+        amount: Amount,
+        price: Price,
+    ) {
+        let btc_usdt = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        let is_updated = apply_fill(positions, btc_usdt, side, amount, price);
+        assert!(is_updated, "position should be updated by fill");
+    }
+
+    #[test]
+    fn position_is_updated_by_fills() {
+        let mut positions = Vec::new();
+
+        fill_btc(&mut positions, OrderSide::Buy, dec!(1), dec!(1000));
+        fill_btc(&mut positions, OrderSide::Buy, dec!(1), dec!(2000));
+        assert_eq!(get_signed_position(&positions[0]), dec!(2));
+        assert_eq!(positions[0].derivative.average_entry_price, dec!(1500));
+
+        // Decrease of position doesn't change entry price
+        fill_btc(&mut positions, OrderSide::Sell, dec!(0.5), dec!(3000));
+        assert_eq!(get_signed_position(&positions[0]), dec!(1.5));
+        assert_eq!(positions[0].derivative.average_entry_price, dec!(1500));
+
+        fill_btc(&mut positions, OrderSide::Sell, dec!(2), dec!(3000));
+        assert_eq!(get_signed_position(&positions[0]), dec!(-0.5));
+        assert_eq!(positions[0].derivative.average_entry_price, dec!(3000));
+
+        fill_btc(&mut positions, OrderSide::Buy, dec!(0.5), dec!(3000));
+        assert!(positions.is_empty());
+    }
+
+    #[test]
+    fn position_with_side_is_updated_by_fill() {
+        let mut short = position("btc", dec!(0.5), dec!(1000));
+        short.derivative.side = Some(OrderSide::Sell);
+        let mut positions = vec![short, position("eth", dec!(1), dec!(100))];
+
+        fill_btc(&mut positions, OrderSide::Sell, dec!(0.5), dec!(1000));
+
+        assert_eq!(get_signed_position(&positions[0]), dec!(-1));
+        assert_eq!(get_signed_position(&positions[1]), dec!(1));
+    }
+
+    #[test]
+    fn positions_in_hedge_mode_are_not_updated_by_fill() {
+        let mut long = position("btc", dec!(0.5), dec!(1000));
+        long.derivative.position_side = Some(PositionSide::Long);
+        let mut positions = vec![long];
+
+        let btc_usdt = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        let is_updated = apply_fill(
+            &mut positions,
+            btc_usdt,
+            OrderSide::Sell,
+            dec!(0.5),
+            dec!(1000),
+        );
+        assert!(!is_updated);
+
+        assert_eq!(positions.len(), 1);
+        assert_eq!(get_signed_position(&positions[0]), dec!(0.5));
+    }
+
+    #[test]
+    fn breached_delta_is_reduced_by_the_largest_positions() {
+        let btc = symbol("btc");
+        let positions = [
+            (
+                ExchangeAccountId::new("Binance".into(), 0),
+                btc.clone(),
+                position("btc", dec!(0.5), dec!(1000)),
+            ),
+            (
+                ExchangeAccountId::new("Binance".into(), 1),
+                btc.clone(),
+                position("btc", dec!(1.2), dec!(1000)),
+            ),
+            (
+                ExchangeAccountId::new("Binance".into(), 2),
+                btc.clone(),
+                position("btc", dec!(-0.2), dec!(1000)),
+            ),
+        ];
+
+        let mut exposure = PortfolioExposure::default();
+        for (_, symbol, position) in &positions {
+            exposure.add_position(symbol, position);
+        }
+        let breaches = get_breaches(&settings(), &exposure);
+        assert_eq!(
+            breaches,
+            vec![ExposureBreach::Delta {
+                underlying: "btc".into(),
+                excess: dec!(0.5),
+            }]
+        );
+
+        let reductions = plan_reductions(breaches[0], &positions);
+        assert_eq!(reductions.len(), 1);
+        assert_eq!(
+            reductions[0].exchange_account_id,
+            ExchangeAccountId::new("Binance".into(), 1)
+        );
+        assert_eq!(reductions[0].amount, dec!(0.5));
+    }
+}
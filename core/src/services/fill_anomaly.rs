@@ -0,0 +1,364 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use futures::FutureExt;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::exchanges::common::{Amount, MarketAccountId, Price};
+use crate::exchanges::events::{ExchangeEvent, FillAnomalyEvent, FillAnomalyKind};
+use crate::exchanges::lag_aware_receiver::{ExchangeEventsReceiver, ReceivedEvent};
+use crate::infrastructure::spawn_future;
+use crate::misc::time::time_manager;
+use crate::order_book::local_snapshot_service::LocalSnapshotsService;
+use crate::orders::event::OrderEventType;
+use crate::orders::order::{OrderAmountKind, OrderSnapshot};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FillAnomalySettings {
+    /// Fill is anomalous if its price deviates from middle of the last order book top
+    /// by more than this percent
+    pub max_price_deviation_percent: Decimal,
+    /// Fill is anomalous if filled amount of order exceeds order amount by more than this percent
+    pub amount_tolerance_percent: Decimal,
+}
+
+impl Default for FillAnomalySettings {
+    fn default() -> Self {
+        Self {
+            max_price_deviation_percent: dec!(5),
+            amount_tolerance_percent: dec!(0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct FillAnomalyMetrics {
+    pub checked_fills_count: u64,
+    pub price_deviation_count: u64,
+    pub oversized_fill_count: u64,
+    pub unknown_order_count: u64,
+    pub last_anomaly_time: Option<DateTime>,
+}
+
+impl FillAnomalyMetrics {
+    fn add(&mut self, kind: &FillAnomalyKind, time: DateTime) {
+        match kind {
+            FillAnomalyKind::PriceDeviation { .. } => self.price_deviation_count += 1,
+            FillAnomalyKind::OversizedFill { .. } => self.oversized_fill_count += 1,
+            FillAnomalyKind::UnknownOrder => self.unknown_order_count += 1,
+        }
+        self.last_anomaly_time = Some(time);
+    }
+}
+
+/// Checks fills of orders against the last order book top and order amount.
+/// Anomalies are logged as errors, counted in metrics and raised as `ExchangeEvent::FillAnomaly`,
+/// so they can be delivered to operator. Fills for unknown orders are raised by exchange itself
+/// when they are buffered and are only counted here
+pub struct FillAnomalyDetector {
+    settings: FillAnomalySettings,
+    local_snapshots_service: Mutex<LocalSnapshotsService>,
+    metrics: Mutex<FillAnomalyMetrics>,
+    events_sender: broadcast::Sender<ExchangeEvent>,
+}
+
+impl FillAnomalyDetector {
+    pub fn new(
+        settings: FillAnomalySettings,
+        events_sender: broadcast::Sender<ExchangeEvent>,
+        events_receiver: ExchangeEventsReceiver,
+    ) -> Arc<Self> {
+        let detector = Arc::new(Self {
+            settings,
+            local_snapshots_service: Mutex::new(LocalSnapshotsService::default()),
+            metrics: Default::default(),
+            events_sender,
+        });
+
+        let action = detector.clone().start(events_receiver);
+        let _ = spawn_future(
+            "Start fill anomaly detector",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action.boxed(),
+        );
+
+        detector
+    }
+
+    pub fn metrics(&self) -> FillAnomalyMetrics {
+        *self.metrics.lock()
+    }
+
+    async fn start(self: Arc<Self>, mut events_receiver: ExchangeEventsReceiver) -> Result<()> {
+        while let Some(received) = events_receiver.recv().await {
+            match received {
+                ReceivedEvent::Event(event) => self.handle_event(event),
+                ReceivedEvent::EventsDropped { .. } => {
+                    // Order book updates could be dropped, so reference prices are taken
+                    // only from the next snapshots
+                    *self.local_snapshots_service.lock() = LocalSnapshotsService::default();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_event(&self, event: ExchangeEvent) {
+        match event {
+            ExchangeEvent::OrderBookEvent(event) => {
+                let _ = self.local_snapshots_service.lock().update(event);
+            }
+            ExchangeEvent::OrderEvent(event) => {
+                if let OrderEventType::OrderFilled { cloned_order } = &event.event_type {
+                    self.check_fill(cloned_order);
+                }
+            }
+            ExchangeEvent::FillAnomaly(event) => {
+                if event.kind == FillAnomalyKind::UnknownOrder {
+                    self.metrics.lock().add(&event.kind, time_manager::now());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check_fill(&self, order: &OrderSnapshot) {
+        let fill = match order.fills.fills.last() {
+            Some(fill) => fill,
+            None => return,
+        };
+
+        let header = &order.header;
+        let market_account_id =
+            MarketAccountId::new(header.exchange_account_id, header.currency_pair);
+        let reference_price = self.get_reference_price(market_account_id);
+
+        let mut anomalies = Vec::new();
+        if let Some(reference_price) = reference_price {
+            anomalies.extend(check_price_deviation(
+                fill.price(),
+                reference_price,
+                self.settings.max_price_deviation_percent,
+            ));
+        }
+        // Amount of quote orders is in quote currency, so it isn't comparable with filled amount
+        if header.amount_kind == OrderAmountKind::Base {
+            anomalies.extend(check_filled_amount(
+                header.amount,
+                order.fills.filled_amount,
+                self.settings.amount_tolerance_percent,
+            ));
+        }
+
+        let now = time_manager::now();
+        let mut metrics = self.metrics.lock();
+        metrics.checked_fills_count += 1;
+        for kind in anomalies {
+            metrics.add(&kind, now);
+
+            log::error!(
+                "Fill anomaly {:?} of order {} on {} {}: price {}, amount {}",
+                kind,
+                header.client_order_id,
+                header.exchange_account_id,
+                header.currency_pair,
+                fill.price(),
+                fill.amount()
+            );
+
+            let _ = self
+                .events_sender
+                .send(ExchangeEvent::FillAnomaly(FillAnomalyEvent {
+                    exchange_account_id: header.exchange_account_id,
+                    currency_pair: Some(header.currency_pair),
+                    client_order_id: Some(header.client_order_id.clone()),
+                    exchange_order_id: order.props.exchange_order_id.clone(),
+                    price: fill.price(),
+                    amount: fill.amount(),
+                    kind,
+                }));
+        }
+    }
+
+    /// Middle of the last order book top of market
+    fn get_reference_price(&self, market_account_id: MarketAccountId) -> Option<Price> {
+        let local_snapshots_service = self.local_snapshots_service.lock();
+        let snapshot = local_snapshots_service.get_snapshot(market_account_id.market_id())?;
+        let (ask, _) = snapshot.get_top_ask()?;
+        let (bid, _) = snapshot.get_top_bid()?;
+        Some((ask + bid) / dec!(2))
+    }
+}
+
+fn check_price_deviation(
+    price: Price,
+    reference_price: Price,
+    max_deviation_percent: Decimal,
+) -> Option<FillAnomalyKind> {
+    if reference_price <= dec!(0) {
+        return None;
+    }
+
+    let deviation_percent = (price - reference_price).abs() / reference_price * dec!(100);
+    (deviation_percent > max_deviation_percent).then(|| FillAnomalyKind::PriceDeviation {
+        reference_price,
+        deviation_percent,
+    })
+}
+
+fn check_filled_amount(
+    order_amount: Amount,
+    filled_amount: Amount,
+    tolerance_percent: Decimal,
+) -> Option<FillAnomalyKind> {
+    let max_filled_amount = order_amount * (dec!(1) + tolerance_percent / dec!(100));
+    (filled_amount > max_filled_amount).then(|| FillAnomalyKind::OversizedFill {
+        order_amount,
+        filled_amount,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::exchanges::common::{CurrencyPair, ExchangeAccountId};
+    use crate::order_book::event::{EventType, OrderBookEvent};
+    use crate::order_book_data;
+    use crate::orders::event::OrderEvent;
+    use crate::orders::fill::{OrderFill, OrderFillType};
+    use crate::orders::order::OrderFillRole;
+    use crate::orders::pool::OrdersPool;
+    use crate::test_util::OrderSnapshotBuilder;
+
+    fn fill(price: Price, amount: Amount) -> OrderFill {
+        OrderFill::new(
+            Uuid::new_v4(),
+            None,
+            Utc::now(),
+            OrderFillType::UserTrade,
+            None,
+            price,
+            amount,
+            price * amount,
+            OrderFillRole::Taker,
+            "usdt".into(),
+            dec!(0),
+            dec!(0),
+            "usdt".into(),
+            dec!(0),
+            dec!(0),
+            false,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn anomalous_fills_are_raised_and_counted() {
+        let (events_sender, mut events_receiver) = broadcast::channel(10);
+        let detector = FillAnomalyDetector {
+            settings: FillAnomalySettings::default(),
+            local_snapshots_service: Mutex::new(LocalSnapshotsService::default()),
+            metrics: Default::default(),
+            events_sender,
+        };
+
+        let exchange_account_id = ExchangeAccountId::new("Binance".into(), 0);
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        detector.handle_event(ExchangeEvent::OrderBookEvent(OrderBookEvent::new(
+            Utc::now(),
+            exchange_account_id,
+            currency_pair,
+            String::new(),
+            EventType::Snapshot,
+            Arc::new(order_book_data![
+                dec!(101) => dec!(1),
+                ;
+                dec!(99) => dec!(1),
+            ]),
+        )));
+
+        let orders_pool = OrdersPool::new();
+        let handle_fill = |fill_price: Price, fill_amount: Amount| {
+            let order = OrderSnapshotBuilder::new(exchange_account_id, currency_pair)
+                .price(dec!(100))
+                .amount(dec!(1))
+                .fill(fill(fill_price, fill_amount))
+                .build_ref(&orders_pool);
+            let cloned_order = Arc::new(order.deep_clone());
+            detector.handle_event(ExchangeEvent::OrderEvent(OrderEvent::new(
+                order,
+                OrderEventType::OrderFilled { cloned_order },
+            )));
+        };
+
+        handle_fill(dec!(100.5), dec!(1));
+        assert!(events_receiver.try_recv().is_err());
+
+        handle_fill(dec!(90), dec!(1.5));
+        let kinds = [
+            events_receiver.try_recv().expect("in test"),
+            events_receiver.try_recv().expect("in test"),
+        ]
+        .map(|event| match event {
+            ExchangeEvent::FillAnomaly(event) => {
+                assert_eq!(event.currency_pair, Some(currency_pair));
+                assert_eq!(event.price, dec!(90));
+                event.kind
+            }
+            _ => panic!("unexpected event"),
+        });
+        assert_eq!(
+            kinds,
+            [
+                FillAnomalyKind::PriceDeviation {
+                    reference_price: dec!(100),
+                    deviation_percent: dec!(10),
+                },
+                FillAnomalyKind::OversizedFill {
+                    order_amount: dec!(1),
+                    filled_amount: dec!(1.5),
+                },
+            ]
+        );
+
+        let metrics = detector.metrics();
+        assert_eq!(metrics.checked_fills_count, 2);
+        assert_eq!(metrics.price_deviation_count, 1);
+        assert_eq!(metrics.oversized_fill_count, 1);
+        assert_eq!(metrics.unknown_order_count, 0);
+        assert!(metrics.last_anomaly_time.is_some());
+    }
+
+    #[test]
+    fn fill_anomalies_are_detected_by_thresholds() {
+        assert_eq!(check_price_deviation(dec!(104), dec!(100), dec!(5)), None);
+        assert_eq!(
+            check_price_deviation(dec!(94), dec!(100), dec!(5)),
+            Some(FillAnomalyKind::PriceDeviation {
+                reference_price: dec!(100),
+                deviation_percent: dec!(6),
+            })
+        );
+
+        assert_eq!(check_filled_amount(dec!(1), dec!(1), dec!(0)), None);
+        assert_eq!(check_filled_amount(dec!(1), dec!(1.01), dec!(1)), None);
+        assert_eq!(
+            check_filled_amount(dec!(1), dec!(1.02), dec!(1)),
+            Some(FillAnomalyKind::OversizedFill {
+                order_amount: dec!(1),
+                filled_amount: dec!(1.02),
+            })
+        );
+    }
+}
@@ -0,0 +1,260 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use dashmap::DashMap;
+use futures::future::join_all;
+use itertools::Itertools;
+use mmb_utils::cancellation_token::CancellationToken;
+use serde::Serialize;
+
+use crate::exchanges::common::{ActivePosition, ClosedPosition, ExchangeAccountId};
+use crate::exchanges::exchange_blocker::{BlockReason, BlockType, ExchangeBlocker};
+use crate::exchanges::general::exchange::Exchange;
+use crate::lifecycle::app_lifetime_manager::AppLifetimeManager;
+
+pub static PANIC_BUTTON: BlockReason = BlockReason::new("PANIC_BUTTON");
+
+/// Time for cancellation of orders and closing of positions on one exchange
+const FLATTEN_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExchangeKillReport {
+    pub exchange_account_id: ExchangeAccountId,
+    /// Count of open orders which are cancelled with confirmation of exchange
+    pub cancelled_orders_count: usize,
+    pub closed_positions_count: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KillReport {
+    pub blocked_exchanges: Vec<ExchangeAccountId>,
+    pub exchanges: Vec<ExchangeKillReport>,
+}
+
+/// Emergency stop of trading on all exchanges at once: exchanges are blocked for new orders,
+/// open orders are cancelled and positions are optionally closed at market price.
+/// Exchanges stay blocked until restart of engine
+pub struct KillSwitch {
+    exchanges: Arc<DashMap<ExchangeAccountId, Arc<Exchange>>>,
+    exchange_blocker: Arc<ExchangeBlocker>,
+    lifetime_manager: Arc<AppLifetimeManager>,
+}
+
+impl KillSwitch {
+    pub fn new(
+        exchanges: Arc<DashMap<ExchangeAccountId, Arc<Exchange>>>,
+        exchange_blocker: Arc<ExchangeBlocker>,
+        lifetime_manager: Arc<AppLifetimeManager>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            exchanges,
+            exchange_blocker,
+            lifetime_manager,
+        })
+    }
+
+    pub async fn press(&self, close_positions: bool) -> KillReport {
+        log::warn!(
+            "Panic button is pressed (close positions: {})",
+            close_positions
+        );
+
+        let exchanges = self
+            .exchanges
+            .iter()
+            .map(|exchange| exchange.value().clone())
+            .collect_vec();
+
+        let blocked_exchanges = exchanges
+            .iter()
+            .map(|exchange| {
+                self.exchange_blocker.block(
+                    exchange.exchange_account_id,
+                    PANIC_BUTTON,
+                    BlockType::Manual,
+                );
+                exchange.exchange_account_id
+            })
+            .collect_vec();
+
+        let cancellation_token = self.lifetime_manager.stop_token();
+        let exchanges = join_all(exchanges.into_iter().map(|exchange| {
+            Self::flatten_exchange(exchange, close_positions, cancellation_token.clone())
+        }))
+        .await;
+
+        let report = KillReport {
+            blocked_exchanges,
+            exchanges,
+        };
+        log::warn!("Panic button processing finished: {:?}", report);
+
+        report
+    }
+
+    async fn flatten_exchange(
+        exchange: Arc<Exchange>,
+        close_positions: bool,
+        cancellation_token: CancellationToken,
+    ) -> ExchangeKillReport {
+        let mut report = ExchangeKillReport {
+            exchange_account_id: exchange.exchange_account_id,
+            cancelled_orders_count: 0,
+            closed_positions_count: 0,
+            error: None,
+        };
+
+        let action =
+            Self::flatten_exchange_core(exchange, close_positions, cancellation_token, &mut report);
+        let result = match tokio::time::timeout(FLATTEN_TIMEOUT, action).await {
+            Ok(result) => result,
+            Err(_) => Err(anyhow::anyhow!(
+                "Timeout {} secs is exceeded",
+                FLATTEN_TIMEOUT.as_secs()
+            )),
+        };
+
+        if let Err(error) = result {
+            log::error!(
+                "Panic button processing failed for {}: {:?}",
+                report.exchange_account_id,
+                error
+            );
+            report.error = Some(format!("{:?}", error));
+        }
+
+        report
+    }
+
+    async fn flatten_exchange_core(
+        exchange: Arc<Exchange>,
+        close_positions: bool,
+        cancellation_token: CancellationToken,
+        report: &mut ExchangeKillReport,
+    ) -> Result<()> {
+        let orders = exchange
+            .get_open_orders(true)
+            .await
+            .context("Unable to get open orders")?;
+        report.cancelled_orders_count = exchange
+            .cancel_orders(orders, cancellation_token.clone())
+            .await;
+
+        if !close_positions {
+            return Ok(());
+        }
+
+        let positions = exchange
+            .get_active_positions(cancellation_token.clone())
+            .await
+            .into_iter()
+            .filter(|position| !position.derivative.position.is_zero())
+            .collect_vec();
+
+        close_all_positions(&positions, report, |position| {
+            exchange.close_position_loop(position, None, cancellation_token.clone())
+        })
+        .await
+    }
+}
+
+/// Positions are closed one by one, so failure of one position doesn't prevent closing of others
+async fn close_all_positions<'a, Fut>(
+    positions: &'a [ActivePosition],
+    report: &mut ExchangeKillReport,
+    close_position: impl Fn(&'a ActivePosition) -> Fut,
+) -> Result<()>
+where
+    Fut: Future<Output = Result<ClosedPosition>>,
+{
+    let mut errors = Vec::new();
+    for position in positions {
+        match close_position(position).await {
+            Ok(_) => report.closed_positions_count += 1,
+            Err(error) => errors.push(format!("{:?}", error)),
+        }
+    }
+
+    if !errors.is_empty() {
+        bail!(
+            "{} of {} positions aren't closed: {}",
+            errors.len(),
+            positions.len(),
+            errors.join("; ")
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::exchanges::common::{ActivePositionId, CurrencyPair};
+    use crate::misc::derivative_position::DerivativePosition;
+    use crate::orders::order::ExchangeOrderId;
+
+    fn position(id: &str) -> ActivePosition {
+        let mut position = ActivePosition::new(DerivativePosition::new(
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            dec!(1),
+            None,
+            None,
+            dec!(20000),
+            dec!(0),
+            dec!(1),
+        ));
+        position.id = ActivePositionId::new(id.into());
+        position
+    }
+
+    fn closed_position() -> ClosedPosition {
+        ClosedPosition::new(ExchangeOrderId::new("1".into()), dec!(1))
+    }
+
+    fn report() -> ExchangeKillReport {
+        ExchangeKillReport {
+            exchange_account_id: ExchangeAccountId::new("Binance".into(), 0),
+            cancelled_orders_count: 0,
+            closed_positions_count: 0,
+            error: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn all_positions_are_closed() {
+        let positions = [position("first"), position("second")];
+        let mut report = report();
+
+        close_all_positions(&positions, &mut report, |_| async { Ok(closed_position()) })
+            .await
+            .expect("in test");
+
+        assert_eq!(report.closed_positions_count, 2);
+    }
+
+    #[tokio::test]
+    async fn failed_position_is_reported() {
+        let positions = [position("first"), position("second")];
+        let mut report = report();
+
+        let error = close_all_positions(&positions, &mut report, |position| async move {
+            match position.id.as_str() {
+                "first" => bail!("Position {} isn't closed", position.id.as_str()),
+                _ => Ok(closed_position()),
+            }
+        })
+        .await
+        .expect_err("in test");
+
+        assert_eq!(report.closed_positions_count, 1);
+        let error = error.to_string();
+        assert!(error.contains("1 of 2 positions aren't closed"));
+        assert!(error.contains("Position first isn't closed"));
+    }
+}
@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::common::Price;
+use crate::exchanges::general::commission::{Commission, Percent};
+use crate::exchanges::general::exchange::Exchange;
+use crate::math::ConvertPercentToRate;
+use crate::orders::order::OrderRole;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MinProfitableSpreadSettings {
+    /// Expected part of fills executed as maker. Orders far from front of queue are filled rarely,
+    /// so part of position is expected to be closed by taker orders
+    pub maker_fill_ratio: Decimal,
+    /// Cost of conversion of commission and received currency (e.g. BNB commission or quote
+    /// currency to reference currency) in percents of order cost
+    pub conversion_cost_percent: Percent,
+}
+
+impl Default for MinProfitableSpreadSettings {
+    fn default() -> Self {
+        Self {
+            maker_fill_ratio: dec!(1),
+            conversion_cost_percent: dec!(0),
+        }
+    }
+}
+
+/// Min spread between buy and sell prices which covers fees and conversion costs of round trip
+/// (buy and sell of the same amount), so strategies don't quote at a loss.
+/// Disposition executor excludes maker trade cycles which are closer to mid price than half of it
+pub struct MinProfitableSpreadService {
+    settings: MinProfitableSpreadSettings,
+}
+
+impl MinProfitableSpreadService {
+    pub fn new(settings: MinProfitableSpreadSettings) -> Arc<Self> {
+        Arc::new(Self { settings })
+    }
+
+    /// Min spread of markets of exchange as part of mid price, e.g. 0.002 means 0.2%.
+    /// It's negative if rebates exceed costs
+    pub fn min_spread_rate(&self, exchange: &Exchange) -> Decimal {
+        min_spread_rate(exchange.commission(), &self.settings)
+    }
+
+    /// Min distance between buy and sell prices of market with specified mid price
+    pub fn min_spread(&self, exchange: &Exchange, mid_price: Price) -> Price {
+        self.min_spread_rate(exchange) * mid_price
+    }
+}
+
+fn min_spread_rate(commission: &Commission, settings: &MinProfitableSpreadSettings) -> Decimal {
+    let fee_rate = |order_role| {
+        let commission = commission.get_commission(order_role);
+        let referral_reward_rate = commission.referral_reward.percent_to_rate();
+        commission.fee.percent_to_rate() * (Decimal::ONE - referral_reward_rate)
+    };
+
+    let maker_fill_ratio = settings.maker_fill_ratio;
+    let expected_fee_rate = maker_fill_ratio * fee_rate(OrderRole::Maker)
+        + (Decimal::ONE - maker_fill_ratio) * fee_rate(OrderRole::Taker);
+
+    // Both legs of round trip pay fee and conversion cost
+    dec!(2) * (expected_fee_rate + settings.conversion_cost_percent.percent_to_rate())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::commission::CommissionForType;
+
+    fn commission(maker_fee: Percent, taker_fee: Percent, referral_reward: Percent) -> Commission {
+        Commission::new(
+            CommissionForType::new(maker_fee, referral_reward),
+            CommissionForType::new(taker_fee, referral_reward),
+        )
+    }
+
+    #[test]
+    fn spread_covers_expected_fees_and_conversion_of_both_legs() {
+        let settings = MinProfitableSpreadSettings {
+            maker_fill_ratio: dec!(0.75),
+            conversion_cost_percent: dec!(0.05),
+        };
+
+        let rate = min_spread_rate(&commission(dec!(0.1), dec!(0.2), dec!(0)), &settings);
+
+        assert_eq!(rate, dec!(0.0035));
+    }
+
+    #[test]
+    fn referral_reward_reduces_fees() {
+        let settings = MinProfitableSpreadSettings::default();
+
+        let rate = min_spread_rate(&commission(dec!(0.1), dec!(0.2), dec!(20)), &settings);
+
+        assert_eq!(rate, dec!(0.0016));
+    }
+
+    #[test]
+    fn maker_rebate_makes_spread_negative() {
+        let settings = MinProfitableSpreadSettings::default();
+
+        let rate = min_spread_rate(&commission(dec!(-0.01), dec!(0.04), dec!(0)), &settings);
+
+        assert_eq!(rate, dec!(-0.0002));
+    }
+}
@@ -1,2 +1,16 @@
+pub mod audit_log;
+pub mod event_loop_watchdog;
+pub mod exposure_limits;
+pub mod fill_anomaly;
+pub mod kill_switch;
 pub(crate) mod market_prices;
+pub mod min_profitable_spread;
+pub mod order_expiry;
+pub mod order_status_prober;
+pub mod scheduler;
+pub mod stale_order_reaper;
+pub mod stats_snapshots;
+pub mod trade_flow;
+pub mod treasury;
 pub mod usd_converter;
+pub mod volatility;
@@ -0,0 +1,289 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use dashmap::DashMap;
+use futures::FutureExt;
+use itertools::Itertools;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+
+use crate::exchanges::common::ExchangeAccountId;
+use crate::exchanges::events::ExchangeEvent;
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::lag_aware_receiver::{ExchangeEventsReceiver, ReceivedEvent};
+use crate::infrastructure::spawn_future;
+use crate::orders::event::{OrderEvent, OrderEventType};
+use crate::orders::order::{ClientOrderId, OrderStatus};
+use crate::orders::pool::OrderRef;
+use crate::services::scheduler::{JobId, Schedule, Scheduler};
+
+/// Cancels orders with `expire_at` at that time on exchanges without native good-till-date orders
+pub struct OrderExpiryService {
+    exchanges: Arc<DashMap<ExchangeAccountId, Arc<Exchange>>>,
+    scheduler: Arc<Scheduler>,
+    jobs: DashMap<ClientOrderId, JobId>,
+}
+
+impl OrderExpiryService {
+    pub fn new(
+        exchanges: Arc<DashMap<ExchangeAccountId, Arc<Exchange>>>,
+        scheduler: Arc<Scheduler>,
+        events_receiver: ExchangeEventsReceiver,
+    ) -> Arc<Self> {
+        let service = Arc::new(Self {
+            exchanges,
+            scheduler,
+            jobs: DashMap::new(),
+        });
+
+        let action = service.clone().start(events_receiver);
+        let _ = spawn_future(
+            "Start order expiry service",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action.boxed(),
+        );
+
+        service
+    }
+
+    async fn start(self: Arc<Self>, mut events_receiver: ExchangeEventsReceiver) -> Result<()> {
+        loop {
+            let event = match events_receiver.recv().await {
+                Some(ReceivedEvent::Event(event)) => event,
+                Some(ReceivedEvent::EventsDropped { .. }) => {
+                    self.schedule_missed_expiries();
+                    continue;
+                }
+                None => return Ok(()),
+            };
+
+            if let ExchangeEvent::OrderEvent(order_event) = event {
+                self.handle_order_event(order_event);
+            }
+        }
+    }
+
+    fn handle_order_event(&self, order_event: OrderEvent) {
+        match order_event.event_type {
+            OrderEventType::CreateOrderSucceeded => self.schedule_cancellation(&order_event.order),
+            OrderEventType::CreateOrderFailed
+            | OrderEventType::CancelOrderSucceeded
+            | OrderEventType::OrderCompleted { .. } => {
+                if let Some((_, job_id)) = self.jobs.remove(&order_event.order.client_order_id()) {
+                    let _ = self.scheduler.cancel(job_id);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Events about created orders could be dropped, so expiry is scheduled by open orders
+    fn schedule_missed_expiries(&self) {
+        let orders = self
+            .exchanges
+            .iter()
+            .flat_map(|exchange| {
+                exchange
+                    .orders
+                    .not_finished
+                    .iter()
+                    .map(|order| order.value().clone())
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        for order in orders {
+            if order.status() == OrderStatus::Created
+                && !self.jobs.contains_key(&order.client_order_id())
+            {
+                self.schedule_cancellation(&order);
+            }
+        }
+    }
+
+    fn schedule_cancellation(&self, order: &OrderRef) {
+        let expire_at = match order.fn_ref(|x| x.header.expire_at) {
+            Some(expire_at) => expire_at,
+            None => return,
+        };
+
+        let exchange_account_id = order.exchange_account_id();
+        let exchange = match self.exchanges.get(&exchange_account_id) {
+            Some(exchange) => exchange.value().clone(),
+            None => {
+                log::error!(
+                    "Unable to schedule expiry of order {}: exchange {} not found",
+                    order.client_order_id(),
+                    exchange_account_id
+                );
+                return;
+            }
+        };
+
+        if exchange.features.order_features.supports_good_till_date {
+            return;
+        }
+
+        let exchange = Arc::downgrade(&exchange);
+        let order = order.clone();
+        let client_order_id = order.client_order_id();
+        let cancel_expired_order = move |cancellation_token| {
+            let exchange = exchange.clone();
+            let order = order.clone();
+            async move {
+                let exchange = match exchange.upgrade() {
+                    Some(exchange) => exchange,
+                    None => return,
+                };
+
+                if order.is_finished() || exchange.are_orders_handed_over() {
+                    return;
+                }
+
+                log::info!(
+                    "Cancelling expired order {} {:?} on {}",
+                    order.client_order_id(),
+                    order.exchange_order_id(),
+                    exchange.exchange_account_id
+                );
+
+                if let Err(error) = exchange
+                    .wait_cancel_order_confirmed(order.clone(), None, true, cancellation_token)
+                    .await
+                {
+                    log::error!(
+                        "Unable to cancel expired order {}: {:?}",
+                        order.client_order_id(),
+                        error
+                    );
+                }
+            }
+            .boxed()
+        };
+
+        let job_id = self.scheduler.schedule(
+            &format!("Expiry of order {}", client_order_id),
+            Schedule::Once(expire_at),
+            cancel_expired_order,
+        );
+        let _ = self.jobs.insert(client_order_id, job_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::test_helper::{create_order_ref, get_test_exchange};
+    use crate::infrastructure::init_lifetime_manager;
+    use crate::orders::order::OrderSide;
+    use mmb_utils::cancellation_token::CancellationToken;
+    use rust_decimal_macros::dec;
+
+    fn create_service() -> (OrderExpiryService, Arc<Exchange>) {
+        let _ = init_lifetime_manager();
+        let (exchange, _) = get_test_exchange(false);
+        let exchanges = DashMap::new();
+        let _ = exchanges.insert(exchange.exchange_account_id, exchange.clone());
+
+        let service = OrderExpiryService {
+            exchanges: Arc::new(exchanges),
+            scheduler: Scheduler::new(CancellationToken::new()),
+            jobs: DashMap::new(),
+        };
+        (service, exchange)
+    }
+
+    fn create_order(exchange: &Exchange, expire_in: Option<chrono::Duration>) -> OrderRef {
+        let symbol = exchange
+            .symbols
+            .iter()
+            .next()
+            .map(|x| x.value().clone())
+            .expect("in test");
+        let order = create_order_ref(
+            &ClientOrderId::unique_id(),
+            None,
+            exchange.exchange_account_id,
+            symbol.currency_pair(),
+            dec!(1),
+            dec!(1),
+            OrderSide::Buy,
+        );
+        order.fn_mut(|x| {
+            let mut header = (*x.header).clone();
+            header.expire_at = expire_in.map(|expire_in| chrono::Utc::now() + expire_in);
+            x.header = Arc::new(header);
+        });
+        order
+    }
+
+    fn order_event(order: &OrderRef, event_type: OrderEventType) -> OrderEvent {
+        OrderEvent::new(order.clone(), event_type)
+    }
+
+    #[tokio::test]
+    async fn cancellation_is_scheduled_for_order_with_expire_at() {
+        let (service, exchange) = create_service();
+        let order = create_order(&exchange, Some(chrono::Duration::hours(1)));
+
+        service.handle_order_event(order_event(&order, OrderEventType::CreateOrderSucceeded));
+
+        assert!(service.jobs.contains_key(&order.client_order_id()));
+        assert_eq!(service.scheduler.jobs().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn cancellation_is_not_scheduled_without_expire_at() {
+        let (service, exchange) = create_service();
+        let order = create_order(&exchange, None);
+
+        service.handle_order_event(order_event(&order, OrderEventType::CreateOrderSucceeded));
+
+        assert!(service.jobs.is_empty());
+        assert!(service.scheduler.jobs().is_empty());
+    }
+
+    #[tokio::test]
+    async fn job_is_removed_after_order_completion() {
+        let (service, exchange) = create_service();
+        let order = create_order(&exchange, Some(chrono::Duration::hours(1)));
+        service.handle_order_event(order_event(&order, OrderEventType::CreateOrderSucceeded));
+
+        let cloned_order = Arc::new(order.deep_clone());
+        service.handle_order_event(order_event(
+            &order,
+            OrderEventType::OrderCompleted { cloned_order },
+        ));
+
+        assert!(service.jobs.is_empty());
+        assert!(service.scheduler.jobs().is_empty());
+    }
+
+    #[tokio::test]
+    async fn job_is_removed_after_cancellation() {
+        let (service, exchange) = create_service();
+        let order = create_order(&exchange, Some(chrono::Duration::hours(1)));
+        service.handle_order_event(order_event(&order, OrderEventType::CreateOrderSucceeded));
+
+        service.handle_order_event(order_event(&order, OrderEventType::CancelOrderSucceeded));
+
+        assert!(service.jobs.is_empty());
+        assert!(service.scheduler.jobs().is_empty());
+    }
+
+    #[tokio::test]
+    async fn expiry_of_created_order_is_scheduled_after_dropped_events() {
+        let (service, exchange) = create_service();
+        let order = create_order(&exchange, Some(chrono::Duration::hours(1)));
+        order.fn_mut(|x| x.set_status(OrderStatus::Created, chrono::Utc::now()));
+        let _ = exchange
+            .orders
+            .not_finished
+            .insert(order.client_order_id(), order.clone());
+
+        service.schedule_missed_expiries();
+        service.schedule_missed_expiries();
+
+        assert!(service.jobs.contains_key(&order.client_order_id()));
+        assert_eq!(service.scheduler.jobs().len(), 1);
+    }
+}
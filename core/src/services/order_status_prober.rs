@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::Duration;
+use dashmap::DashMap;
+use futures::FutureExt;
+use itertools::Itertools;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+
+use crate::exchanges::common::ExchangeAccountId;
+use crate::exchanges::events::ExchangeEvent;
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::order::status_probe::is_order_status_ambiguous;
+use crate::exchanges::lag_aware_receiver::{ExchangeEventsReceiver, ReceivedEvent};
+use crate::infrastructure::spawn_future;
+use crate::misc::time::time_manager;
+use crate::orders::event::{OrderEvent, OrderEventType};
+use crate::orders::order::ClientOrderId;
+use crate::orders::pool::OrderRef;
+use crate::services::scheduler::{Schedule, Scheduler};
+
+const CHECK_PERIOD: std::time::Duration = std::time::Duration::from_secs(1);
+/// Time without events of order with ambiguous status before the first probe
+const SILENCE_TIMEOUT_SECS: i64 = 10;
+const MAX_BACKOFF_SECS: i64 = 300;
+const MAX_PROBES_COUNT: u32 = 8;
+
+struct ProbedOrder {
+    order: OrderRef,
+    last_event_time: DateTime,
+    /// Time of the next probe if it's requested explicitly or rescheduled after probe
+    next_probe_time: Option<DateTime>,
+    is_requested: bool,
+    probes_count: u32,
+}
+
+impl ProbedOrder {
+    fn new(order: OrderRef, now: DateTime) -> Self {
+        Self {
+            order,
+            last_event_time: now,
+            next_probe_time: None,
+            is_requested: false,
+            probes_count: 0,
+        }
+    }
+
+    fn probe_time(&self) -> Option<DateTime> {
+        if self.probes_count >= MAX_PROBES_COUNT {
+            return None;
+        }
+
+        Some(
+            self.next_probe_time
+                .unwrap_or(self.last_event_time + Duration::seconds(SILENCE_TIMEOUT_SECS)),
+        )
+    }
+}
+
+/// Orders which are probed, one entry by client order id
+#[derive(Default)]
+struct ProbeQueue {
+    orders: HashMap<ClientOrderId, ProbedOrder>,
+}
+
+impl ProbeQueue {
+    fn track(&mut self, order: &OrderRef, now: DateTime) {
+        let _ = self
+            .orders
+            .entry(order.client_order_id())
+            .or_insert_with(|| ProbedOrder::new(order.clone(), now));
+    }
+
+    /// Event of order means websocket isn't silent, so the order isn't probed until next silence
+    fn register_event(&mut self, order: &OrderRef, now: DateTime) {
+        let probed_order = self
+            .orders
+            .entry(order.client_order_id())
+            .or_insert_with(|| ProbedOrder::new(order.clone(), now));
+        probed_order.last_event_time = now;
+        probed_order.next_probe_time = None;
+        probed_order.is_requested = false;
+        probed_order.probes_count = 0;
+    }
+
+    fn request(&mut self, order: &OrderRef, now: DateTime) {
+        let probed_order = self
+            .orders
+            .entry(order.client_order_id())
+            .or_insert_with(|| ProbedOrder::new(order.clone(), now));
+        if probed_order.is_requested {
+            return;
+        }
+
+        probed_order.is_requested = true;
+        probed_order.next_probe_time = Some(now);
+        probed_order.probes_count = 0;
+    }
+
+    fn remove(&mut self, client_order_id: &ClientOrderId) {
+        let _ = self.orders.remove(client_order_id);
+    }
+
+    /// Orders to probe now: explicitly requested ones first, then by probe time
+    fn take_due(&mut self, now: DateTime) -> Vec<OrderRef> {
+        self.orders.retain(|_, x| !x.order.is_finished());
+
+        self.orders
+            .values()
+            .filter_map(|x| {
+                let probe_time = x.probe_time()?;
+                let is_due =
+                    probe_time <= now && (x.is_requested || is_order_status_ambiguous(&x.order));
+                is_due.then(|| (!x.is_requested, probe_time, x.order.clone()))
+            })
+            .sorted_by_key(|(is_not_requested, probe_time, _)| (*is_not_requested, *probe_time))
+            .map(|(_, _, order)| order)
+            .collect_vec()
+    }
+
+    fn complete_probe(&mut self, order: &OrderRef, is_resolved: bool, now: DateTime) {
+        let client_order_id = order.client_order_id();
+        if is_resolved {
+            self.remove(&client_order_id);
+            return;
+        }
+
+        let probed_order = match self.orders.get_mut(&client_order_id) {
+            Some(probed_order) => probed_order,
+            None => return,
+        };
+
+        probed_order.is_requested = false;
+        probed_order.probes_count += 1;
+        if probed_order.probes_count >= MAX_PROBES_COUNT {
+            log::error!(
+                "Status of order {} on {} is still ambiguous after {} probes",
+                client_order_id,
+                order.exchange_account_id(),
+                probed_order.probes_count
+            );
+            return;
+        }
+
+        let backoff_secs = SILENCE_TIMEOUT_SECS * 2i64.pow(probed_order.probes_count);
+        probed_order.next_probe_time =
+            Some(now + Duration::seconds(backoff_secs.min(MAX_BACKOFF_SECS)));
+    }
+}
+
+/// Checks status of orders through REST when their websocket events are silent and status is
+/// ambiguous, so strategies don't need to poll orders. Every order has a single scheduled probe
+/// with exponential backoff, probes are executed one by one when requests are allowed
+/// by timeout manager
+pub struct OrderStatusProber {
+    exchanges: Arc<DashMap<ExchangeAccountId, Arc<Exchange>>>,
+    queue: Mutex<ProbeQueue>,
+    is_probing: tokio::sync::Mutex<()>,
+}
+
+impl OrderStatusProber {
+    pub fn new(
+        exchanges: Arc<DashMap<ExchangeAccountId, Arc<Exchange>>>,
+        scheduler: Arc<Scheduler>,
+        events_receiver: ExchangeEventsReceiver,
+    ) -> Arc<Self> {
+        let service = Arc::new(Self {
+            exchanges,
+            queue: Mutex::new(ProbeQueue::default()),
+            is_probing: tokio::sync::Mutex::new(()),
+        });
+
+        let action = service.clone().start(events_receiver);
+        let _ = spawn_future(
+            "Start order status prober",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action.boxed(),
+        );
+
+        let service_weak = Arc::downgrade(&service);
+        let _ = scheduler.schedule(
+            "Probe status of silent orders",
+            Schedule::Every(CHECK_PERIOD),
+            move |cancellation_token| {
+                let service_weak = service_weak.clone();
+                async move {
+                    if let Some(service) = service_weak.upgrade() {
+                        service.probe_silent_orders(cancellation_token).await;
+                    }
+                }
+                .boxed()
+            },
+        );
+
+        service
+    }
+
+    /// Schedules probe of order as soon as possible. Request is ignored if probe
+    /// is already requested for the order
+    pub fn request_probe(&self, order: &OrderRef) {
+        if !order.is_finished() {
+            self.queue.lock().request(order, time_manager::now());
+        }
+    }
+
+    async fn start(self: Arc<Self>, mut events_receiver: ExchangeEventsReceiver) -> Result<()> {
+        loop {
+            let event = match events_receiver.recv().await {
+                Some(ReceivedEvent::Event(event)) => event,
+                Some(ReceivedEvent::EventsDropped { .. }) => {
+                    self.request_probes_of_open_orders();
+                    continue;
+                }
+                None => return Ok(()),
+            };
+
+            if let ExchangeEvent::OrderEvent(order_event) = event {
+                self.handle_order_event(order_event);
+            }
+        }
+    }
+
+    /// Status changes of any order could be dropped, so all open orders are probed
+    fn request_probes_of_open_orders(&self) {
+        for exchange in self
+            .exchanges
+            .iter()
+            .filter(|x| !x.are_orders_handed_over())
+        {
+            for order in exchange.orders.not_finished.iter() {
+                if !order.is_external_order() {
+                    self.request_probe(order.value());
+                }
+            }
+        }
+    }
+
+    fn handle_order_event(&self, order_event: OrderEvent) {
+        let order = &order_event.order;
+        if order.is_external_order() {
+            return;
+        }
+
+        let mut queue = self.queue.lock();
+        match order_event.event_type {
+            OrderEventType::CreateOrderFailed
+            | OrderEventType::CancelOrderSucceeded
+            | OrderEventType::OrderCompleted { .. } => queue.remove(&order.client_order_id()),
+            _ => queue.register_event(order, time_manager::now()),
+        }
+    }
+
+    async fn probe_silent_orders(&self, cancellation_token: CancellationToken) {
+        // Probes of previous check are still executed
+        let _probing_guard = match self.is_probing.try_lock() {
+            Ok(guard) => guard,
+            Err(_) => return,
+        };
+
+        let due_orders = {
+            let now = time_manager::now();
+            let mut queue = self.queue.lock();
+            for exchange in self
+                .exchanges
+                .iter()
+                .filter(|x| !x.are_orders_handed_over())
+            {
+                for order in exchange.orders.not_finished.iter() {
+                    if is_order_status_ambiguous(order.value()) {
+                        queue.track(order.value(), now);
+                    }
+                }
+            }
+
+            queue.take_due(now)
+        };
+
+        for order in due_orders {
+            if cancellation_token.is_cancellation_requested() {
+                return;
+            }
+
+            let exchange = match self.exchanges.get(&order.exchange_account_id()) {
+                Some(exchange) => exchange.value().clone(),
+                None => continue,
+            };
+
+            log::info!(
+                "Probing status of order {} {:?} on {}",
+                order.client_order_id(),
+                order.status(),
+                exchange.exchange_account_id
+            );
+
+            let is_resolved = match exchange
+                .probe_order_status(&order, cancellation_token.clone())
+                .await
+            {
+                Ok(is_resolved) => is_resolved,
+                Err(error) => {
+                    log::warn!(
+                        "Unable to probe status of order {} on {}: {:?}",
+                        order.client_order_id(),
+                        exchange.exchange_account_id,
+                        error
+                    );
+                    false
+                }
+            };
+
+            self.queue
+                .lock()
+                .complete_probe(&order, is_resolved, time_manager::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::CurrencyPair;
+    use crate::exchanges::general::test_helper::create_order_ref;
+    use crate::orders::order::{OrderSide, OrderStatus};
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+
+    fn creating_order(client_order_id: &str) -> OrderRef {
+        let order = create_order_ref(
+            &client_order_id.into(),
+            None,
+            ExchangeAccountId::new("Binance".into(), 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            dec!(1),
+            dec!(1),
+            OrderSide::Buy,
+        );
+        order.fn_mut(|x| x.set_status(OrderStatus::Creating, Utc::now()));
+        order
+    }
+
+    #[test]
+    fn silent_order_is_probed_with_backoff() {
+        let now = Utc::now();
+        let order = creating_order("silent");
+        let mut queue = ProbeQueue::default();
+
+        queue.track(&order, now);
+        queue.track(&order, now + Duration::seconds(5));
+        assert!(queue.take_due(now + Duration::seconds(9)).is_empty());
+
+        let probe_time = now + Duration::seconds(SILENCE_TIMEOUT_SECS);
+        assert_eq!(queue.take_due(probe_time).len(), 1);
+
+        queue.complete_probe(&order, false, probe_time);
+        assert!(queue
+            .take_due(probe_time + Duration::seconds(19))
+            .is_empty());
+        assert_eq!(queue.take_due(probe_time + Duration::seconds(20)).len(), 1);
+
+        // Event of order means that websocket isn't silent anymore
+        queue.register_event(&order, probe_time + Duration::seconds(20));
+        assert!(queue
+            .take_due(probe_time + Duration::seconds(25))
+            .is_empty());
+
+        queue.complete_probe(&order, true, probe_time + Duration::seconds(30));
+        assert!(queue.orders.is_empty());
+    }
+
+    #[test]
+    fn requested_probes_are_deduplicated_and_prioritized() {
+        let now = Utc::now();
+        let silent_order = creating_order("silent");
+        let requested_order = creating_order("requested");
+        let mut queue = ProbeQueue::default();
+
+        queue.track(&silent_order, now - Duration::seconds(SILENCE_TIMEOUT_SECS));
+        queue.request(&requested_order, now);
+        queue.request(&requested_order, now + Duration::seconds(1));
+
+        let due = queue
+            .take_due(now + Duration::seconds(1))
+            .iter()
+            .map(|x| x.client_order_id())
+            .collect_vec();
+        assert_eq!(due, vec!["requested".into(), "silent".into()]);
+    }
+}
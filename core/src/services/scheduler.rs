@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::NaiveTime;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use itertools::Itertools;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+
+use crate::infrastructure::spawn_future;
+use crate::misc::time::time_manager;
+
+/// When scheduled job is executed. Times are in UTC
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Schedule {
+    /// Single execution at specified time. Job is executed immediately if time has passed
+    Once(DateTime),
+    /// Execution with fixed period. First execution happens after one period
+    Every(Duration),
+    /// Execution every day at specified times, e.g. before funding on derivative markets
+    DailyAt(Vec<NaiveTime>),
+}
+
+impl Schedule {
+    /// Next execution time after `now`. `None` if job shouldn't be executed anymore
+    pub fn next_run_time(&self, now: DateTime, is_first_run: bool) -> Option<DateTime> {
+        match self {
+            Schedule::Once(time) => is_first_run.then(|| *time),
+            Schedule::Every(period) => chrono::Duration::from_std(*period)
+                .ok()
+                .map(|period| now + period),
+            Schedule::DailyAt(times) => {
+                let today = now.date();
+                let tomorrow = today.succ();
+                times
+                    .iter()
+                    .flat_map(|time| [today.and_time(*time), tomorrow.and_time(*time)])
+                    .flatten()
+                    .filter(|run_time| *run_time > now)
+                    .min()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JobId(u64);
+
+/// Runs time-based actions of engine and strategies. Every job gets own cancellation token
+/// which is cancelled on job cancellation or engine shutdown
+pub struct Scheduler {
+    stop_token: CancellationToken,
+    jobs: Mutex<HashMap<JobId, (String, CancellationToken)>>,
+    last_job_id: AtomicU64,
+}
+
+impl Scheduler {
+    pub fn new(stop_token: CancellationToken) -> Arc<Self> {
+        Arc::new(Self {
+            stop_token,
+            jobs: Default::default(),
+            last_job_id: Default::default(),
+        })
+    }
+
+    /// Registers job. Action is called sequentially, so next execution waits finish of previous one
+    pub fn schedule(
+        self: &Arc<Self>,
+        name: &str,
+        schedule: Schedule,
+        action: impl Fn(CancellationToken) -> BoxFuture<'static, ()> + Send + Sync + 'static,
+    ) -> JobId {
+        let job_id = JobId(self.last_job_id.fetch_add(1, Ordering::Relaxed) + 1);
+        let job_token = self.stop_token.create_linked_token();
+        self.jobs
+            .lock()
+            .insert(job_id, (name.to_owned(), job_token.clone()));
+
+        let scheduler = Arc::downgrade(self);
+        let job_name = name.to_owned();
+        let job = async move {
+            let mut is_first_run = true;
+            while let Some(run_time) = schedule.next_run_time(time_manager::now(), is_first_run) {
+                is_first_run = false;
+
+                let delay = (run_time - time_manager::now())
+                    .to_std()
+                    .unwrap_or_default();
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = job_token.when_cancelled() => break,
+                }
+
+                log::trace!("Scheduled job {} is started", job_name);
+                action(job_token.clone()).await;
+            }
+
+            if let Some(scheduler) = scheduler.upgrade() {
+                scheduler.jobs.lock().remove(&job_id);
+            }
+
+            Ok(())
+        };
+
+        let _ = spawn_future(
+            &format!("Scheduled job {}", name),
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            job.boxed(),
+        );
+
+        job_id
+    }
+
+    /// Returns `false` if job is already finished
+    pub fn cancel(&self, job_id: JobId) -> bool {
+        match self.jobs.lock().remove(&job_id) {
+            Some((_, job_token)) => {
+                job_token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Names of active jobs
+    pub fn jobs(&self) -> Vec<(JobId, String)> {
+        self.jobs
+            .lock()
+            .iter()
+            .map(|(job_id, (name, _))| (*job_id, name.clone()))
+            .sorted()
+            .collect_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms(hour, minute, 0)
+    }
+
+    #[test]
+    fn once_is_executed_only_first_time() {
+        let now = Utc.ymd(2022, 3, 1).and_hms(12, 0, 0);
+        let schedule = Schedule::Once(now - chrono::Duration::hours(1));
+
+        assert_eq!(
+            schedule.next_run_time(now, true),
+            Some(now - chrono::Duration::hours(1))
+        );
+        assert_eq!(schedule.next_run_time(now, false), None);
+    }
+
+    #[test]
+    fn every_is_executed_after_period() {
+        let now = Utc.ymd(2022, 3, 1).and_hms(12, 0, 0);
+        let schedule = Schedule::Every(Duration::from_secs(60));
+
+        assert_eq!(
+            schedule.next_run_time(now, true),
+            Some(Utc.ymd(2022, 3, 1).and_hms(12, 1, 0))
+        );
+    }
+
+    #[test]
+    fn daily_is_executed_at_nearest_time() {
+        let schedule = Schedule::DailyAt(vec![time(23, 55), time(7, 55), time(15, 55)]);
+
+        assert_eq!(
+            schedule.next_run_time(Utc.ymd(2022, 3, 1).and_hms(12, 0, 0), false),
+            Some(Utc.ymd(2022, 3, 1).and_hms(15, 55, 0))
+        );
+        assert_eq!(
+            schedule.next_run_time(Utc.ymd(2022, 3, 1).and_hms(23, 55, 0), false),
+            Some(Utc.ymd(2022, 3, 2).and_hms(7, 55, 0))
+        );
+        assert_eq!(
+            Schedule::DailyAt(vec![]).next_run_time(Utc::now(), true),
+            None
+        );
+    }
+}
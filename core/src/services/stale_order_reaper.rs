@@ -0,0 +1,230 @@
+use std::sync::Arc;
+
+use chrono::Duration;
+use dashmap::DashMap;
+use futures::future::join_all;
+use futures::FutureExt;
+use itertools::Itertools;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::common::{ExchangeAccountId, Price};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::symbol::Precision;
+use crate::misc::human_duration::HumanDuration;
+use crate::misc::time::time_manager;
+use crate::orders::order::{OrderSide, OrderStatus, OrderType};
+use crate::orders::pool::OrderRef;
+use crate::services::scheduler::{Schedule, Scheduler};
+
+const CHECK_PERIOD: std::time::Duration = std::time::Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StaleOrderReaperSettings {
+    /// Resting orders which are older than this time are cancelled, e.g. `10m`.
+    /// Age isn't checked if it isn't specified
+    pub max_order_age: Option<HumanDuration>,
+    /// Resting orders with price which is further than this count of price ticks from the top of
+    /// order book on their side are cancelled. Drift isn't checked if it isn't specified
+    pub max_price_drift_ticks: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StaleReason {
+    Age(Duration),
+    PriceDrift { drift_ticks: Decimal },
+}
+
+fn get_stale_reason(
+    settings: &StaleOrderReaperSettings,
+    order_age: Duration,
+    price: Price,
+    top_price: Option<Price>,
+    tick: Option<Price>,
+) -> Option<StaleReason> {
+    let max_order_age = settings
+        .max_order_age
+        .and_then(|x| Duration::from_std(x.duration()).ok());
+    if let Some(max_order_age) = max_order_age {
+        if order_age > max_order_age {
+            return Some(StaleReason::Age(order_age));
+        }
+    }
+
+    let max_price_drift_ticks = settings.max_price_drift_ticks?;
+    match (top_price, tick) {
+        (Some(top_price), Some(tick)) if !tick.is_zero() => {
+            let drift_ticks = (top_price - price).abs() / tick;
+            (drift_ticks > Decimal::from(max_price_drift_ticks))
+                .then(|| StaleReason::PriceDrift { drift_ticks })
+        }
+        _ => None,
+    }
+}
+
+/// Backstop against strategies which hang while their quotes go stale: cancels resting limit
+/// orders of engine which are too old or too far from the top of order book. Drift of price is
+/// checked only for markets with price precision by tick
+pub struct StaleOrderReaper {
+    settings: StaleOrderReaperSettings,
+    exchanges: Arc<DashMap<ExchangeAccountId, Arc<Exchange>>>,
+    is_reaping: tokio::sync::Mutex<()>,
+}
+
+impl StaleOrderReaper {
+    pub fn new(
+        settings: StaleOrderReaperSettings,
+        exchanges: Arc<DashMap<ExchangeAccountId, Arc<Exchange>>>,
+        scheduler: Arc<Scheduler>,
+    ) -> Arc<Self> {
+        let service = Arc::new(Self {
+            settings,
+            exchanges,
+            is_reaping: tokio::sync::Mutex::new(()),
+        });
+
+        let service_weak = Arc::downgrade(&service);
+        let _ = scheduler.schedule(
+            "Reap stale orders",
+            Schedule::Every(CHECK_PERIOD),
+            move |cancellation_token| {
+                let service_weak = service_weak.clone();
+                async move {
+                    if let Some(service) = service_weak.upgrade() {
+                        service.reap_stale_orders(cancellation_token).await;
+                    }
+                }
+                .boxed()
+            },
+        );
+
+        service
+    }
+
+    async fn reap_stale_orders(&self, cancellation_token: CancellationToken) {
+        // Cancellations of previous check are still executed
+        let _reaping_guard = match self.is_reaping.try_lock() {
+            Ok(guard) => guard,
+            Err(_) => return,
+        };
+
+        let now = time_manager::now();
+        let stale_orders = self
+            .exchanges
+            .iter()
+            .filter(|exchange| !exchange.are_orders_handed_over())
+            .flat_map(|exchange| {
+                exchange
+                    .orders
+                    .not_finished
+                    .iter()
+                    .filter_map(|order| {
+                        let reason = self.stale_reason(exchange.value(), order.value(), now)?;
+                        Some((exchange.value().clone(), order.value().clone(), reason))
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        let cancellations = stale_orders.into_iter().map(|(exchange, order, reason)| {
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                log::warn!(
+                    "Cancelling stale order {} on {}: {:?}",
+                    order.client_order_id(),
+                    exchange.exchange_account_id,
+                    reason
+                );
+
+                if let Err(error) = exchange
+                    .wait_cancel_order_confirmed(order.clone(), None, true, cancellation_token)
+                    .await
+                {
+                    log::warn!(
+                        "Unable to cancel stale order {} on {}: {:?}",
+                        order.client_order_id(),
+                        exchange.exchange_account_id,
+                        error
+                    );
+                }
+            }
+        });
+        join_all(cancellations).await;
+    }
+
+    fn stale_reason(
+        &self,
+        exchange: &Exchange,
+        order: &OrderRef,
+        now: DateTime,
+    ) -> Option<StaleReason> {
+        if order.status() != OrderStatus::Created
+            || order.order_type() != OrderType::Limit
+            || order.is_external_order()
+        {
+            return None;
+        }
+
+        let currency_pair = order.currency_pair();
+        let top_price = exchange.order_book_top.get(&currency_pair).and_then(|top| {
+            let level = match order.side() {
+                OrderSide::Buy => top.bid.as_ref(),
+                OrderSide::Sell => top.ask.as_ref(),
+            };
+            level.map(|x| x.price)
+        });
+        let price_precision = exchange
+            .symbols
+            .get(&currency_pair)
+            .map(|symbol| symbol.price_precision.clone());
+        let tick = match price_precision {
+            Some(Precision::ByTick { tick }) => Some(tick),
+            _ => None,
+        };
+        let order_age = now - order.fn_ref(|x| x.header.init_time);
+
+        get_stale_reason(&self.settings, order_age, order.price(), top_price, tick)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn stale_orders_are_detected_by_age_and_price_drift() {
+        let settings = StaleOrderReaperSettings {
+            max_order_age: Some(HumanDuration::from_secs(60)),
+            max_price_drift_ticks: Some(5),
+        };
+        let young = Duration::seconds(10);
+        let tick = Some(dec!(0.1));
+
+        assert_eq!(
+            get_stale_reason(&settings, young, dec!(100), Some(dec!(100.5)), tick),
+            None
+        );
+        assert_eq!(
+            get_stale_reason(&settings, young, dec!(100), Some(dec!(100.6)), tick),
+            Some(StaleReason::PriceDrift {
+                drift_ticks: dec!(6)
+            })
+        );
+        assert_eq!(
+            get_stale_reason(&settings, Duration::seconds(61), dec!(100), None, tick),
+            Some(StaleReason::Age(Duration::seconds(61)))
+        );
+        // Drift isn't checked without top of order book or tick
+        assert_eq!(
+            get_stale_reason(&settings, young, dec!(100), None, tick),
+            None
+        );
+        assert_eq!(
+            get_stale_reason(&settings, young, dec!(100), Some(dec!(200)), None),
+            None
+        );
+    }
+}
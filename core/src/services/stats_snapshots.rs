@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use futures::FutureExt;
+use itertools::Itertools;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::balance_manager::balance_manager::BalanceManager;
+use crate::exchanges::common::{CurrencyPair, ExchangeAccountId, MarketAccountId};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::latency::{LatencyRecord, LatencyStatistics};
+use crate::misc::human_duration::HumanDuration;
+use crate::misc::time::time_manager;
+use crate::services::scheduler::{Schedule, Scheduler};
+use crate::statistic_service::{OrderCounters, StatisticService};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StatsSnapshotsSettings {
+    /// Snapshot is recorded once per interval, so size of log doesn't depend on activity of engine
+    pub interval: HumanDuration,
+}
+
+impl Default for StatsSnapshotsSettings {
+    fn default() -> Self {
+        Self {
+            interval: HumanDuration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PositionSnapshot {
+    pub currency_pair: CurrencyPair,
+    pub position: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExchangeSnapshot {
+    pub exchange_account_id: ExchangeAccountId,
+    pub open_orders_count: usize,
+    /// Not zero positions by filled amount
+    pub positions: Vec<PositionSnapshot>,
+    pub latencies: Vec<LatencyRecord>,
+}
+
+/// Counts of orders per minute since the previous snapshot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MarketRates {
+    #[serde(serialize_with = "MarketAccountId::serialize_as_struct")]
+    pub market_account_id: MarketAccountId,
+    pub created_per_minute: Decimal,
+    pub canceled_per_minute: Decimal,
+    pub filled_per_minute: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StatsSnapshot {
+    pub exchanges: Vec<ExchangeSnapshot>,
+    /// Only markets with orders since the previous snapshot
+    pub markets: Vec<MarketRates>,
+}
+
+impl StatsSnapshot {
+    /// Part of snapshot which is related to specified exchange
+    pub fn for_exchange(&self, exchange_account_id: ExchangeAccountId) -> StatsSnapshot {
+        StatsSnapshot {
+            exchanges: self
+                .exchanges
+                .iter()
+                .filter(|x| x.exchange_account_id == exchange_account_id)
+                .cloned()
+                .collect_vec(),
+            markets: self
+                .markets
+                .iter()
+                .filter(|x| x.market_account_id.exchange_account_id == exchange_account_id)
+                .cloned()
+                .collect_vec(),
+        }
+    }
+}
+
+/// Records snapshots of open orders, positions, order rates and latencies to traffic record
+/// file of each exchange (see `traffic_record_path` of exchange settings). Snapshots get into
+/// exported event log, so state of engine can be correlated with market data during post-mortems
+pub struct StatsSnapshotRecorder {
+    exchanges: Arc<DashMap<ExchangeAccountId, Arc<Exchange>>>,
+    balance_manager: Arc<Mutex<BalanceManager>>,
+    statistics: Arc<StatisticService>,
+    /// Order counters of the previous snapshot for calculation of rates
+    previous_counters: Mutex<(DateTime, HashMap<MarketAccountId, OrderCounters>)>,
+}
+
+impl StatsSnapshotRecorder {
+    pub fn new(
+        settings: StatsSnapshotsSettings,
+        exchanges: Arc<DashMap<ExchangeAccountId, Arc<Exchange>>>,
+        balance_manager: Arc<Mutex<BalanceManager>>,
+        statistics: Arc<StatisticService>,
+        scheduler: Arc<Scheduler>,
+    ) -> Arc<Self> {
+        let previous_counters = (time_manager::now(), statistics.order_counters());
+        let recorder = Arc::new(Self {
+            exchanges,
+            balance_manager,
+            statistics,
+            previous_counters: Mutex::new(previous_counters),
+        });
+
+        let recorder_clone = recorder.clone();
+        let _ = scheduler.schedule(
+            "Record statistics snapshot",
+            Schedule::Every(settings.interval.duration()),
+            move |_| recorder_clone.clone().record().boxed(),
+        );
+
+        recorder
+    }
+
+    async fn record(self: Arc<Self>) {
+        let now = time_manager::now();
+        let snapshot = self.snapshot(now);
+
+        for exchange in self.exchanges.iter() {
+            let exchange_account_id = exchange.exchange_account_id;
+            let traffic_recorder = match exchange.traffic_recorder() {
+                Ok(Some(traffic_recorder)) => traffic_recorder,
+                Ok(None) => continue,
+                Err(error) => {
+                    log::warn!(
+                        "Unable to record statistics snapshot for {}: {:?}",
+                        exchange_account_id,
+                        error
+                    );
+                    continue;
+                }
+            };
+
+            match serde_json::to_string(&snapshot.for_exchange(exchange_account_id)) {
+                Ok(json) => traffic_recorder.record_stats_snapshot(now, &json),
+                Err(error) => log::error!(
+                    "Unable to serialize statistics snapshot for {}: {:?}",
+                    exchange_account_id,
+                    error
+                ),
+            }
+        }
+    }
+
+    fn snapshot(&self, now: DateTime) -> StatsSnapshot {
+        let balances = self.balance_manager.lock().get_balances();
+        let positions = balances
+            .position_by_fill_amount
+            .iter()
+            .flat_map(|x| x.positions())
+            .filter(|(_, position)| !position.is_zero())
+            .map(|(market_account_id, position)| (*market_account_id, *position))
+            .collect_vec();
+
+        let exchanges = self
+            .exchanges
+            .iter()
+            .map(|exchange| {
+                let exchange_account_id = exchange.exchange_account_id;
+                ExchangeSnapshot {
+                    exchange_account_id,
+                    open_orders_count: exchange.orders.not_finished.len(),
+                    positions: positions
+                        .iter()
+                        .filter(|(market_account_id, _)| {
+                            market_account_id.exchange_account_id == exchange_account_id
+                        })
+                        .map(|(market_account_id, position)| PositionSnapshot {
+                            currency_pair: market_account_id.currency_pair,
+                            position: *position,
+                        })
+                        .sorted_by_key(|x| x.currency_pair.to_string())
+                        .collect_vec(),
+                    latencies: LatencyStatistics::get_or_create(exchange_account_id).records(),
+                }
+            })
+            .sorted_by_key(|x| x.exchange_account_id.to_string())
+            .collect_vec();
+
+        let counters = self.statistics.order_counters();
+        let markets = {
+            let mut previous_counters = self.previous_counters.lock();
+            let (previous_time, previous) = &*previous_counters;
+            let markets = market_rates(previous, &counters, now - *previous_time);
+            *previous_counters = (now, counters);
+            markets
+        };
+
+        StatsSnapshot { exchanges, markets }
+    }
+}
+
+fn market_rates(
+    previous: &HashMap<MarketAccountId, OrderCounters>,
+    current: &HashMap<MarketAccountId, OrderCounters>,
+    elapsed: chrono::Duration,
+) -> Vec<MarketRates> {
+    let per_minute = |count: u64| {
+        let elapsed_ms = elapsed.num_milliseconds();
+        match elapsed_ms > 0 {
+            true => (Decimal::from(count * 60_000) / Decimal::from(elapsed_ms)).round_dp(2),
+            false => Decimal::ZERO,
+        }
+    };
+
+    current
+        .iter()
+        .filter_map(|(market_account_id, counters)| {
+            let previous = previous.get(market_account_id).copied().unwrap_or_default();
+            let created = counters.created.saturating_sub(previous.created);
+            let canceled = counters.canceled.saturating_sub(previous.canceled);
+            let filled = counters.filled.saturating_sub(previous.filled);
+            (created + canceled + filled > 0).then(|| MarketRates {
+                market_account_id: *market_account_id,
+                created_per_minute: per_minute(created),
+                canceled_per_minute: per_minute(canceled),
+                filled_per_minute: per_minute(filled),
+            })
+        })
+        .sorted_by_key(|x| x.market_account_id.to_string())
+        .collect_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn market_account_id(currency_pair: &str) -> MarketAccountId {
+        let (base, quote) = currency_pair.split_once('/').expect("in test");
+        MarketAccountId::new(
+            "Binance_0".parse().expect("in test"),
+            CurrencyPair::from_codes(base.into(), quote.into()),
+        )
+    }
+
+    fn counters(created: u64, canceled: u64, filled: u64) -> OrderCounters {
+        OrderCounters {
+            created,
+            canceled,
+            filled,
+        }
+    }
+
+    #[test]
+    fn snapshot_for_exchange_contains_only_its_data() {
+        let binance = market_account_id("btc/usdt");
+        let serum =
+            MarketAccountId::new("Serum_0".parse().expect("in test"), binance.currency_pair);
+        let exchange_snapshot = |exchange_account_id| ExchangeSnapshot {
+            exchange_account_id,
+            open_orders_count: 1,
+            positions: vec![],
+            latencies: vec![],
+        };
+        let rates = |market_account_id| MarketRates {
+            market_account_id,
+            created_per_minute: dec!(1),
+            canceled_per_minute: dec!(0),
+            filled_per_minute: dec!(0),
+        };
+        let snapshot = StatsSnapshot {
+            exchanges: vec![
+                exchange_snapshot(binance.exchange_account_id),
+                exchange_snapshot(serum.exchange_account_id),
+            ],
+            markets: vec![rates(binance), rates(serum)],
+        };
+
+        assert_eq!(
+            snapshot.for_exchange(serum.exchange_account_id),
+            StatsSnapshot {
+                exchanges: vec![exchange_snapshot(serum.exchange_account_id)],
+                markets: vec![rates(serum)],
+            }
+        );
+    }
+
+    #[test]
+    fn rates_are_calculated_since_previous_snapshot() {
+        let btc = market_account_id("btc/usdt");
+        let eth = market_account_id("eth/usdt");
+        let idle = market_account_id("ltc/usdt");
+        let previous = HashMap::from([(btc, counters(10, 5, 2)), (idle, counters(1, 1, 0))]);
+        let current = HashMap::from([
+            (btc, counters(16, 8, 2)),
+            (eth, counters(1, 0, 0)),
+            (idle, counters(1, 1, 0)),
+        ]);
+
+        let rates = market_rates(&previous, &current, chrono::Duration::seconds(120));
+
+        assert_eq!(
+            rates,
+            vec![
+                MarketRates {
+                    market_account_id: btc,
+                    created_per_minute: dec!(3),
+                    canceled_per_minute: dec!(1.5),
+                    filled_per_minute: dec!(0),
+                },
+                MarketRates {
+                    market_account_id: eth,
+                    created_per_minute: dec!(0.5),
+                    canceled_per_minute: dec!(0),
+                    filled_per_minute: dec!(0),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use dashmap::DashMap;
+use futures::FutureExt;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+
+use crate::exchanges::common::{Amount, MarketAccountId};
+use crate::exchanges::events::{ExchangeEvent, TradesEvent};
+use crate::exchanges::lag_aware_receiver::{ExchangeEventsReceiver, ReceivedEvent};
+use crate::infrastructure::spawn_future;
+use crate::misc::time::time_manager;
+use crate::orders::order::OrderSide;
+
+pub const DEFAULT_TRADE_FLOW_WINDOW: Duration = Duration::from_secs(60);
+/// Count of equal volume buckets for toxicity calculation
+const TOXICITY_BUCKETS_COUNT: usize = 10;
+
+/// Aggressive trades flow of market in rolling window
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TradeFlowMetrics {
+    pub buy_volume: Amount,
+    pub sell_volume: Amount,
+    /// (buy_volume - sell_volume) / total volume in range [-1, 1], positive if aggressive buyers dominate
+    pub imbalance: Decimal,
+    pub trades_count: usize,
+    /// Trades per second
+    pub arrival_rate: Decimal,
+    /// VPIN-like toxicity in range [0, 1]: average imbalance of equal volume buckets of window.
+    /// High values mean one-sided (probably informed) flow, so quotes should be wider
+    pub toxicity: Decimal,
+}
+
+struct FlowTrade {
+    time: DateTime,
+    side: OrderSide,
+    quantity: Amount,
+}
+
+/// Trades of one market in rolling window
+pub struct TradeFlow {
+    window: chrono::Duration,
+    trades: VecDeque<FlowTrade>,
+}
+
+impl TradeFlow {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window: chrono::Duration::from_std(window)
+                .expect("Unable to convert trade flow window"),
+            trades: VecDeque::new(),
+        }
+    }
+
+    pub fn add_trade(&mut self, time: DateTime, side: OrderSide, quantity: Amount) {
+        self.trades.push_back(FlowTrade {
+            time,
+            side,
+            quantity,
+        });
+        self.remove_outdated(time);
+    }
+
+    pub fn metrics(&mut self, now: DateTime) -> TradeFlowMetrics {
+        self.remove_outdated(now);
+
+        let volume_of = |side| {
+            self.trades
+                .iter()
+                .filter(|trade| trade.side == side)
+                .map(|trade| trade.quantity)
+                .sum::<Amount>()
+        };
+        let buy_volume = volume_of(OrderSide::Buy);
+        let sell_volume = volume_of(OrderSide::Sell);
+        let total_volume = buy_volume + sell_volume;
+
+        let imbalance = match total_volume.is_zero() {
+            true => dec!(0),
+            false => (buy_volume - sell_volume) / total_volume,
+        };
+
+        let window_secs = Decimal::from(self.window.num_milliseconds()) / dec!(1000);
+        let arrival_rate = match window_secs.is_zero() {
+            true => dec!(0),
+            false => Decimal::from(self.trades.len()) / window_secs,
+        };
+
+        TradeFlowMetrics {
+            buy_volume,
+            sell_volume,
+            imbalance,
+            trades_count: self.trades.len(),
+            arrival_rate,
+            toxicity: self.toxicity(total_volume),
+        }
+    }
+
+    /// Sum of absolute imbalances of buckets divided by total volume.
+    /// Trade can be split between neighbour buckets
+    fn toxicity(&self, total_volume: Amount) -> Decimal {
+        if total_volume.is_zero() {
+            return dec!(0);
+        }
+
+        let bucket_volume = total_volume / Decimal::from(TOXICITY_BUCKETS_COUNT);
+        let mut imbalances_sum = dec!(0);
+        // Signed volume and total volume of current bucket
+        let mut bucket = (dec!(0), dec!(0));
+        for trade in &self.trades {
+            let mut rest = trade.quantity;
+            while !rest.is_zero() {
+                let part = rest.min(bucket_volume - bucket.1);
+                bucket.0 += match trade.side {
+                    OrderSide::Buy => part,
+                    OrderSide::Sell => -part,
+                };
+                bucket.1 += part;
+                rest -= part;
+
+                if bucket.1 >= bucket_volume {
+                    imbalances_sum += bucket.0.abs();
+                    bucket = (dec!(0), dec!(0));
+                }
+            }
+        }
+        imbalances_sum += bucket.0.abs();
+
+        (imbalances_sum / total_volume).min(dec!(1))
+    }
+
+    fn remove_outdated(&mut self, now: DateTime) {
+        let window_start = now - self.window;
+        while let Some(trade) = self.trades.front() {
+            if trade.time >= window_start {
+                break;
+            }
+            let _ = self.trades.pop_front();
+        }
+    }
+}
+
+/// Trade flow metrics of all markets which strategies can use to adapt width of quotes
+pub struct TradeFlowService {
+    window: Duration,
+    flows: DashMap<MarketAccountId, Mutex<TradeFlow>>,
+}
+
+impl TradeFlowService {
+    pub fn new(window: Duration, events_receiver: ExchangeEventsReceiver) -> Arc<Self> {
+        let service = Arc::new(Self {
+            window,
+            flows: DashMap::new(),
+        });
+
+        let action = service.clone().start(events_receiver);
+        let _ = spawn_future(
+            "Start trade flow service",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action.boxed(),
+        );
+
+        service
+    }
+
+    /// Metrics of market at current time. `None` if there were no trades of market
+    pub fn metrics(&self, market_account_id: MarketAccountId) -> Option<TradeFlowMetrics> {
+        self.flows
+            .get(&market_account_id)
+            .map(|flow| flow.lock().metrics(time_manager::now()))
+    }
+
+    async fn start(self: Arc<Self>, mut events_receiver: ExchangeEventsReceiver) -> Result<()> {
+        loop {
+            match events_receiver.recv().await {
+                Some(ReceivedEvent::Event(ExchangeEvent::Trades(trades_event))) => {
+                    self.add_trades(&trades_event)
+                }
+                Some(ReceivedEvent::Event(_)) => continue,
+                Some(ReceivedEvent::EventsDropped { .. }) => self.reset(),
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Volumes and arrival rate are underestimated if trades were dropped,
+    /// so metrics are calculated again by new trades
+    fn reset(&self) {
+        self.flows.clear();
+    }
+
+    fn add_trades(&self, trades_event: &TradesEvent) {
+        let market_account_id =
+            MarketAccountId::new(trades_event.exchange_account_id, trades_event.currency_pair);
+        let flow = self
+            .flows
+            .entry(market_account_id)
+            .or_insert_with(|| Mutex::new(TradeFlow::new(self.window)));
+
+        let mut flow = flow.lock();
+        for trade in &trades_event.trades {
+            flow.add_trade(trade.transaction_time, trade.side, trade.quantity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    #[test]
+    fn imbalance_and_arrival_rate() {
+        let now = Utc::now();
+        let mut flow = TradeFlow::new(Duration::from_secs(10));
+        flow.add_trade(now, OrderSide::Buy, dec!(3));
+        flow.add_trade(now, OrderSide::Sell, dec!(1));
+
+        let metrics = flow.metrics(now);
+
+        assert_eq!(metrics.buy_volume, dec!(3));
+        assert_eq!(metrics.sell_volume, dec!(1));
+        assert_eq!(metrics.imbalance, dec!(0.5));
+        assert_eq!(metrics.trades_count, 2);
+        assert_eq!(metrics.arrival_rate, dec!(0.2));
+    }
+
+    #[test]
+    fn outdated_trades_are_removed() {
+        let now = Utc::now();
+        let mut flow = TradeFlow::new(Duration::from_secs(10));
+        flow.add_trade(now - chrono::Duration::seconds(20), OrderSide::Buy, dec!(1));
+        flow.add_trade(now, OrderSide::Sell, dec!(1));
+
+        let metrics = flow.metrics(now);
+
+        assert_eq!(metrics.trades_count, 1);
+        assert_eq!(metrics.imbalance, dec!(-1));
+    }
+
+    #[test]
+    fn toxicity_of_alternating_and_one_sided_flow() {
+        let now = Utc::now();
+
+        let mut alternating = TradeFlow::new(Duration::from_secs(10));
+        for _ in 0..10 {
+            alternating.add_trade(now, OrderSide::Buy, dec!(1));
+            alternating.add_trade(now, OrderSide::Sell, dec!(1));
+        }
+        assert_eq!(alternating.metrics(now).toxicity, dec!(0));
+
+        let mut one_sided = TradeFlow::new(Duration::from_secs(10));
+        for _ in 0..10 {
+            one_sided.add_trade(now, OrderSide::Buy, dec!(1));
+        }
+        assert_eq!(one_sided.metrics(now).toxicity, dec!(1));
+    }
+}
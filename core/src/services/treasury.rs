@@ -0,0 +1,681 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use dashmap::DashMap;
+use futures::FutureExt;
+use itertools::Itertools;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::exchanges::common::{CurrencyCode, ExchangeAccountId};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::sub_account::SubAccountTransfer;
+use crate::exchanges::general::withdrawal::{WalletType, Withdrawal};
+use crate::infrastructure::{spawn_by_timer, spawn_future};
+use crate::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use crate::misc::time::time_manager;
+use crate::settings::{TreasuryRefillSettings, TreasurySettings};
+
+/// Time for confirmation of requested withdrawal
+const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_CHECK_PERIOD: Duration = Duration::from_secs(60);
+/// Deposits are credited with delay, so repeated refill of the same account is postponed.
+/// Refill waits for confirmation during the same time
+const REFILL_COOLDOWN: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WithdrawalRequest {
+    pub exchange_account_id: ExchangeAccountId,
+    pub withdrawal: Withdrawal,
+}
+
+/// Movement of funds which is executed only after confirmation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransferRequest {
+    Withdrawal(WithdrawalRequest),
+    /// Transfer between master account and its sub-accounts which is executed by master account
+    SubAccount {
+        master_account_id: ExchangeAccountId,
+        transfer: SubAccountTransfer,
+    },
+}
+
+impl TransferRequest {
+    /// Exchange account which executes transfer
+    fn exchange_account_id(&self) -> ExchangeAccountId {
+        match self {
+            TransferRequest::Withdrawal(request) => request.exchange_account_id,
+            TransferRequest::SubAccount {
+                master_account_id, ..
+            } => *master_account_id,
+        }
+    }
+
+    /// Returns exchange id of transfer
+    async fn execute(&self, exchange: &Exchange) -> Result<String> {
+        match self {
+            TransferRequest::Withdrawal(request) => exchange.withdraw(&request.withdrawal).await,
+            TransferRequest::SubAccount { transfer, .. } => {
+                exchange.sub_account_transfer(transfer).await
+            }
+        }
+    }
+}
+
+struct PendingWithdrawal {
+    request: TransferRequest,
+    expires_at: DateTime,
+}
+
+/// Automatic refill which is waiting for confirmation by operator
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingRefill {
+    /// Token for `confirm_withdrawal`. It's returned only by RPC and isn't written to log
+    pub token: String,
+    pub request: TransferRequest,
+    pub expires_at: DateTime,
+}
+
+/// Confirmation tokens are kept and logged only as hashes, so they can't be taken from logs
+pub(crate) fn get_token_hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Withdrawals which are waiting for confirmation by token
+#[derive(Default)]
+struct PendingWithdrawals {
+    /// Pending withdrawals by hash of confirmation token
+    withdrawals: HashMap<String, PendingWithdrawal>,
+    /// Pending withdrawals which are requested by automatic refills
+    refills: Vec<PendingRefill>,
+}
+
+impl PendingWithdrawals {
+    fn add(&mut self, request: TransferRequest, now: DateTime, timeout: Duration) -> String {
+        self.remove_expired(now);
+
+        let token = Uuid::new_v4().to_string();
+        let expires_at = now
+            + chrono::Duration::from_std(timeout).expect("Unable to convert confirmation timeout");
+        self.withdrawals.insert(
+            get_token_hash(&token),
+            PendingWithdrawal {
+                request,
+                expires_at,
+            },
+        );
+
+        token
+    }
+
+    fn add_refill(&mut self, request: TransferRequest, now: DateTime) -> String {
+        let token = self.add(request.clone(), now, REFILL_COOLDOWN);
+        let expires_at = self.withdrawals[&get_token_hash(&token)].expires_at;
+        self.refills.push(PendingRefill {
+            token: token.clone(),
+            request,
+            expires_at,
+        });
+
+        token
+    }
+
+    fn refills(&mut self, now: DateTime) -> Vec<PendingRefill> {
+        self.remove_expired(now);
+        self.refills.clone()
+    }
+
+    fn take(&mut self, token: &str, now: DateTime) -> Result<TransferRequest> {
+        let pending = self
+            .withdrawals
+            .remove(&get_token_hash(token))
+            .context("There is no withdrawal with such confirmation token")?;
+        self.refills.retain(|refill| refill.token != token);
+
+        if pending.expires_at <= now {
+            bail!("Confirmation token is expired");
+        }
+
+        Ok(pending.request)
+    }
+
+    fn remove_expired(&mut self, now: DateTime) {
+        self.withdrawals
+            .retain(|_, pending| pending.expires_at > now);
+        self.refills.retain(|refill| refill.expires_at > now);
+    }
+}
+
+/// Moves funds between exchange accounts. Withdrawals are executed only after confirmation.
+/// Automatic refills are requested when balance of exchange account becomes lower than configured
+/// minimum and wait for confirmation by operator like withdrawals requested by user
+pub struct TreasuryService {
+    exchanges: Arc<DashMap<ExchangeAccountId, Arc<Exchange>>>,
+    settings: TreasurySettings,
+    lifetime_manager: Arc<AppLifetimeManager>,
+    pending_withdrawals: Mutex<PendingWithdrawals>,
+    last_refill_times: Mutex<HashMap<(ExchangeAccountId, CurrencyCode), DateTime>>,
+}
+
+impl TreasuryService {
+    pub fn new(
+        exchanges: Arc<DashMap<ExchangeAccountId, Arc<Exchange>>>,
+        settings: Option<TreasurySettings>,
+        lifetime_manager: Arc<AppLifetimeManager>,
+    ) -> Arc<Self> {
+        let treasury = Arc::new(Self {
+            exchanges,
+            settings: settings.unwrap_or_default(),
+            lifetime_manager,
+            pending_withdrawals: Default::default(),
+            last_refill_times: Default::default(),
+        });
+
+        treasury.spawn_refills_checking();
+
+        treasury
+    }
+
+    /// Returns token which should be passed to `confirm_withdrawal` for execution of withdrawal
+    pub fn request_withdrawal(&self, request: WithdrawalRequest) -> Result<String> {
+        let exchange = self.get_exchange(request.exchange_account_id)?;
+        if !exchange
+            .exchange_client
+            .get_settings()
+            .is_withdrawal_enabled
+            .unwrap_or(false)
+        {
+            bail!(
+                "Withdrawals aren't enabled in settings for {}",
+                request.exchange_account_id
+            );
+        }
+
+        if request.withdrawal.amount <= dec!(0) {
+            bail!(
+                "Withdrawal amount should be positive: {}",
+                request.withdrawal.amount
+            );
+        }
+
+        log::warn!("Withdrawal {:?} is waiting for confirmation", request);
+
+        Ok(self.pending_withdrawals.lock().add(
+            TransferRequest::Withdrawal(request),
+            time_manager::now(),
+            CONFIRMATION_TIMEOUT,
+        ))
+    }
+
+    /// Automatic refills which are waiting for confirmation by `confirm_withdrawal`
+    pub fn pending_refills(&self) -> Vec<PendingRefill> {
+        self.pending_withdrawals.lock().refills(time_manager::now())
+    }
+
+    /// Withdrawal or transfer of refill is executed in background, result is written to log
+    pub fn confirm_withdrawal(&self, token: &str) -> Result<()> {
+        let request = self
+            .pending_withdrawals
+            .lock()
+            .take(token, time_manager::now())?;
+        let exchange = self.get_exchange(request.exchange_account_id())?;
+
+        let action = async move {
+            let transfer_id = request.execute(&exchange).await?;
+            log::info!("{:?} is created with id {}", request, transfer_id);
+            Ok(())
+        };
+
+        let _ = spawn_future(
+            "Confirmed withdrawal",
+            // Sent withdrawal request shouldn't be interrupted by shutdown
+            SpawnFutureFlags::empty(),
+            action.boxed(),
+        );
+
+        Ok(())
+    }
+
+    fn get_exchange(&self, exchange_account_id: ExchangeAccountId) -> Result<Arc<Exchange>> {
+        self.exchanges
+            .get(&exchange_account_id)
+            .map(|exchange| exchange.value().clone())
+            .with_context(|| format!("There is no exchange {}", exchange_account_id))
+    }
+
+    fn spawn_refills_checking(self: &Arc<Self>) {
+        if self.settings.refills.is_empty() {
+            return;
+        }
+
+        let period = self
+            .settings
+            .check_period
+            .map_or(DEFAULT_CHECK_PERIOD, |x| x.duration());
+
+        let treasury_weak = Arc::downgrade(self);
+        let check_refills = move || {
+            let treasury_weak = treasury_weak.clone();
+            async move {
+                if let Some(treasury) = treasury_weak.upgrade() {
+                    treasury.check_refills().await;
+                }
+            }
+            .boxed()
+        };
+
+        let _ = spawn_by_timer(
+            check_refills,
+            "Check treasury refills",
+            period,
+            period,
+            SpawnFutureFlags::STOP_BY_TOKEN,
+        );
+    }
+
+    async fn check_refills(&self) {
+        let refills_by_exchange = self
+            .settings
+            .refills
+            .iter()
+            .into_group_map_by(|refill| refill.exchange_account_id);
+
+        for (exchange_account_id, refills) in refills_by_exchange {
+            let exchange = match self.get_exchange(exchange_account_id) {
+                Ok(exchange) => exchange,
+                Err(error) => {
+                    log::error!("Unable to check treasury refills: {:?}", error);
+                    continue;
+                }
+            };
+
+            let balances = match exchange
+                .get_balance(self.lifetime_manager.stop_token())
+                .await
+            {
+                Ok(balances_and_positions) => balances_and_positions.balances,
+                Err(error) => {
+                    log::error!("Unable to check treasury refills: {:?}", error);
+                    continue;
+                }
+            };
+
+            for refill in refills {
+                let balance = balances
+                    .iter()
+                    .find(|balance| balance.currency_code == refill.currency_code)
+                    .map(|balance| balance.balance)
+                    .unwrap_or_default();
+
+                if balance < refill.min_balance {
+                    if let Err(error) = self.request_refill(refill, time_manager::now()) {
+                        log::error!(
+                            "Unable to refill {} on {}: {:?}",
+                            refill.currency_code,
+                            refill.exchange_account_id,
+                            error
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Transfer of refill is executed only after confirmation of returned token. Returns `None`
+    /// if refill of the same account was requested recently
+    fn request_refill(
+        &self,
+        refill: &TreasuryRefillSettings,
+        now: DateTime,
+    ) -> Result<Option<String>> {
+        let key = (refill.exchange_account_id, refill.currency_code);
+        if let Some(last_refill_time) = self.last_refill_times.lock().get(&key) {
+            if now - *last_refill_time < chrono::Duration::from_std(REFILL_COOLDOWN)? {
+                return Ok(None);
+            }
+        }
+
+        let _ = self.get_exchange(refill.source_exchange_account_id)?;
+        let request = match self.sub_account_refill(refill)? {
+            Some(request) => request,
+            None => {
+                let address = refill.deposit_address.clone().with_context(|| {
+                    format!(
+                        "Deposit address for refill of {} on {} isn't specified",
+                        refill.currency_code, refill.exchange_account_id
+                    )
+                })?;
+                TransferRequest::Withdrawal(WithdrawalRequest {
+                    exchange_account_id: refill.source_exchange_account_id,
+                    withdrawal: Withdrawal {
+                        currency_code: refill.currency_code,
+                        amount: refill.refill_amount,
+                        address,
+                        network: refill.network.clone(),
+                        address_tag: None,
+                    },
+                })
+            }
+        };
+
+        log::warn!(
+            "Refill of {} on {} is waiting for confirmation: {:?}",
+            refill.currency_code,
+            refill.exchange_account_id,
+            request
+        );
+
+        self.last_refill_times.lock().insert(key, now);
+        let token = self.pending_withdrawals.lock().add_refill(request, now);
+
+        Ok(Some(token))
+    }
+
+    /// Accounts of the same master account are refilled by sub-account transfer instead of
+    /// withdrawal to deposit address
+    fn sub_account_refill(
+        &self,
+        refill: &TreasuryRefillSettings,
+    ) -> Result<Option<TransferRequest>> {
+        let master_account_id = refill.source_exchange_account_id.master_account();
+        if refill.exchange_account_id.master_account() != master_account_id {
+            return Ok(None);
+        }
+
+        let _ = self.get_exchange(master_account_id)?;
+        let sub_account = |exchange_account_id: ExchangeAccountId| -> Result<Option<String>> {
+            if !exchange_account_id.is_sub_account() {
+                return Ok(None);
+            }
+
+            let sub_account = self
+                .get_exchange(exchange_account_id)?
+                .exchange_client
+                .get_settings()
+                .sub_account
+                .clone()
+                .with_context(|| {
+                    format!(
+                        "Sub-account identifier isn't specified in settings of {}",
+                        exchange_account_id
+                    )
+                })?;
+            Ok(Some(sub_account))
+        };
+
+        Ok(Some(TransferRequest::SubAccount {
+            master_account_id,
+            transfer: SubAccountTransfer {
+                currency_code: refill.currency_code,
+                amount: refill.refill_amount,
+                from_sub_account: sub_account(refill.source_exchange_account_id)?,
+                to_sub_account: sub_account(refill.exchange_account_id)?,
+                from_wallet: WalletType::Spot,
+                to_wallet: WalletType::Spot,
+            },
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::test_helper::{
+        get_test_exchange, get_test_exchange_with_settings,
+    };
+    use crate::infrastructure::init_lifetime_manager;
+    use crate::settings::ExchangeSettings;
+
+    fn withdrawal_request() -> TransferRequest {
+        TransferRequest::Withdrawal(WithdrawalRequest {
+            exchange_account_id: "Binance_0".parse().expect("in test"),
+            withdrawal: Withdrawal {
+                currency_code: "usdt".into(),
+                amount: dec!(100),
+                address: "address".to_owned(),
+                network: Some("TRX".to_owned()),
+                address_tag: None,
+            },
+        })
+    }
+
+    /// Treasury with exchanges of specified accounts and identifiers of their sub-accounts
+    fn treasury(accounts: &[(&str, Option<&str>)]) -> Arc<TreasuryService> {
+        let exchanges = Arc::new(DashMap::new());
+        for (exchange_account_id, sub_account) in accounts {
+            let (exchange, _rx) = get_test_exchange_with_settings(ExchangeSettings {
+                exchange_account_id: exchange_account_id.parse().expect("in test"),
+                sub_account: sub_account.map(|x| x.to_owned()),
+                ..ExchangeSettings::default()
+            });
+            exchanges.insert(exchange.exchange_account_id, exchange);
+        }
+
+        TreasuryService::new(exchanges, None, init_lifetime_manager())
+    }
+
+    fn refill_settings(source_exchange_account_id: ExchangeAccountId) -> TreasuryRefillSettings {
+        TreasuryRefillSettings {
+            exchange_account_id: "Binance_1".parse().expect("in test"),
+            currency_code: "usdt".into(),
+            min_balance: dec!(10),
+            refill_amount: dec!(100),
+            source_exchange_account_id,
+            deposit_address: Some("address".to_owned()),
+            network: None,
+        }
+    }
+
+    #[test]
+    fn confirm_pending_withdrawal_once() {
+        let mut pending_withdrawals = PendingWithdrawals::default();
+        let now = time_manager::now();
+        let token = pending_withdrawals.add(withdrawal_request(), now, CONFIRMATION_TIMEOUT);
+
+        assert!(pending_withdrawals.take("unknown", now).is_err());
+        assert_eq!(
+            pending_withdrawals.take(&token, now).expect("in test"),
+            withdrawal_request()
+        );
+        assert!(pending_withdrawals.take(&token, now).is_err());
+    }
+
+    #[test]
+    fn confirmation_token_is_not_stored() {
+        let mut pending_withdrawals = PendingWithdrawals::default();
+        let token = pending_withdrawals.add(
+            withdrawal_request(),
+            time_manager::now(),
+            CONFIRMATION_TIMEOUT,
+        );
+
+        assert!(!pending_withdrawals.withdrawals.contains_key(&token));
+        assert!(pending_withdrawals
+            .withdrawals
+            .contains_key(&get_token_hash(&token)));
+    }
+
+    #[test]
+    fn expired_withdrawal_is_not_confirmed() {
+        let mut pending_withdrawals = PendingWithdrawals::default();
+        let now = time_manager::now();
+        let token = pending_withdrawals.add(withdrawal_request(), now, CONFIRMATION_TIMEOUT);
+
+        let after_timeout =
+            now + chrono::Duration::from_std(CONFIRMATION_TIMEOUT).expect("in test");
+        assert!(pending_withdrawals.take(&token, after_timeout).is_err());
+    }
+
+    #[test]
+    fn refill_is_executed_only_after_confirmation() {
+        let mut pending_withdrawals = PendingWithdrawals::default();
+        let now = time_manager::now();
+        let token = pending_withdrawals.add_refill(withdrawal_request(), now);
+
+        let refills = pending_withdrawals.refills(now);
+        assert_eq!(refills.len(), 1);
+        assert_eq!(refills[0].token, token);
+        assert_eq!(refills[0].request, withdrawal_request());
+
+        assert_eq!(
+            pending_withdrawals.take(&token, now).expect("in test"),
+            withdrawal_request()
+        );
+        assert!(pending_withdrawals.refills(now).is_empty());
+    }
+
+    #[test]
+    fn unconfirmed_refill_expires_after_cooldown() {
+        let mut pending_withdrawals = PendingWithdrawals::default();
+        let now = time_manager::now();
+        let token = pending_withdrawals.add_refill(withdrawal_request(), now);
+
+        let after_timeout =
+            now + chrono::Duration::from_std(CONFIRMATION_TIMEOUT).expect("in test");
+        assert_eq!(pending_withdrawals.refills(after_timeout).len(), 1);
+
+        let after_cooldown = now + chrono::Duration::from_std(REFILL_COOLDOWN).expect("in test");
+        assert!(pending_withdrawals.refills(after_cooldown).is_empty());
+        assert!(pending_withdrawals.take(&token, after_cooldown).is_err());
+    }
+
+    #[test]
+    fn refill_is_requested_once_per_cooldown() {
+        let lifetime_manager = init_lifetime_manager();
+        let (exchange, _rx) = get_test_exchange(false);
+        let exchanges = Arc::new(DashMap::new());
+        exchanges.insert(exchange.exchange_account_id, exchange.clone());
+        let treasury = TreasuryService::new(exchanges, None, lifetime_manager);
+
+        let refill = refill_settings(exchange.exchange_account_id);
+        let now = time_manager::now();
+
+        let token = treasury
+            .request_refill(&refill, now)
+            .expect("in test")
+            .expect("refill should be requested");
+        assert_eq!(
+            treasury.request_refill(&refill, now).expect("in test"),
+            None
+        );
+
+        let refills = treasury.pending_refills();
+        assert_eq!(refills.len(), 1);
+        assert_eq!(refills[0].token, token);
+        assert_eq!(
+            refills[0].request.exchange_account_id(),
+            exchange.exchange_account_id
+        );
+        assert!(matches!(
+            &refills[0].request,
+            TransferRequest::Withdrawal(request) if request.withdrawal.amount == dec!(100)
+        ));
+
+        let after_cooldown = now + chrono::Duration::from_std(REFILL_COOLDOWN).expect("in test");
+        assert!(treasury
+            .request_refill(&refill, after_cooldown)
+            .expect("in test")
+            .is_some());
+    }
+
+    #[test]
+    fn refill_from_unknown_exchange_is_not_requested() {
+        let treasury =
+            TreasuryService::new(Arc::new(DashMap::new()), None, init_lifetime_manager());
+        let refill = refill_settings("Binance_0".parse().expect("in test"));
+
+        assert!(treasury
+            .request_refill(&refill, time_manager::now())
+            .is_err());
+        assert!(treasury.pending_refills().is_empty());
+    }
+
+    #[test]
+    fn refill_without_deposit_address_is_not_requested() {
+        let treasury = treasury(&[("Binance_0", None)]);
+        let mut refill = refill_settings("Binance_0".parse().expect("in test"));
+        refill.deposit_address = None;
+
+        assert!(treasury
+            .request_refill(&refill, time_manager::now())
+            .is_err());
+        assert!(treasury.pending_refills().is_empty());
+    }
+
+    #[test]
+    fn sub_account_is_refilled_by_transfer() {
+        let treasury = treasury(&[
+            ("Binance_0", None),
+            ("Binance_0_1", Some("source@example.com")),
+            ("Binance_0_2", Some("target@example.com")),
+        ]);
+        let mut refill = refill_settings("Binance_0_1".parse().expect("in test"));
+        refill.exchange_account_id = "Binance_0_2".parse().expect("in test");
+        refill.deposit_address = None;
+
+        let token = treasury
+            .request_refill(&refill, time_manager::now())
+            .expect("in test")
+            .expect("refill should be requested");
+
+        let refills = treasury.pending_refills();
+        assert_eq!(refills.len(), 1);
+        assert_eq!(refills[0].token, token);
+        assert_eq!(
+            refills[0].request,
+            TransferRequest::SubAccount {
+                master_account_id: "Binance_0".parse().expect("in test"),
+                transfer: SubAccountTransfer {
+                    currency_code: "usdt".into(),
+                    amount: dec!(100),
+                    from_sub_account: Some("source@example.com".to_owned()),
+                    to_sub_account: Some("target@example.com".to_owned()),
+                    from_wallet: WalletType::Spot,
+                    to_wallet: WalletType::Spot,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn sub_account_is_refilled_from_master_account() {
+        let treasury = treasury(&[
+            ("Binance_0", None),
+            ("Binance_0_1", Some("target@example.com")),
+        ]);
+        let mut refill = refill_settings("Binance_0".parse().expect("in test"));
+        refill.exchange_account_id = "Binance_0_1".parse().expect("in test");
+
+        let _ = treasury
+            .request_refill(&refill, time_manager::now())
+            .expect("in test")
+            .expect("refill should be requested");
+
+        let refills = treasury.pending_refills();
+        assert!(matches!(
+            &refills[0].request,
+            TransferRequest::SubAccount { transfer, .. }
+                if transfer.from_sub_account.is_none()
+                    && transfer.to_sub_account.as_deref() == Some("target@example.com")
+        ));
+    }
+
+    #[test]
+    fn sub_account_without_identifier_is_not_refilled() {
+        let treasury = treasury(&[("Binance_0", None), ("Binance_0_1", None)]);
+        let mut refill = refill_settings("Binance_0".parse().expect("in test"));
+        refill.exchange_account_id = "Binance_0_1".parse().expect("in test");
+
+        assert!(treasury
+            .request_refill(&refill, time_manager::now())
+            .is_err());
+        assert!(treasury.pending_refills().is_empty());
+    }
+}
@@ -430,12 +430,12 @@ pub mod test {
     use crate::{
         exchanges::{
             common::{CurrencyPair, ExchangeAccountId},
-            general::{
-                symbol::Precision,
-                test_helper::{get_test_exchange_by_currency_codes, get_test_exchange_with_symbol},
+            general::test_helper::{
+                get_test_exchange_by_currency_codes, get_test_exchange_with_symbol,
             },
         },
         settings::ExchangeIdCurrencyPairSettings,
+        test_util::SymbolBuilder,
     };
 
     use super::*;
@@ -461,23 +461,7 @@ pub mod test {
     }
 
     fn create_symbol(base: CurrencyCode, quote: CurrencyCode) -> Arc<Symbol> {
-        Arc::new(Symbol::new(
-            false,
-            false,
-            base.as_str().into(),
-            base,
-            quote.as_str().into(),
-            quote,
-            None,
-            None,
-            None,
-            None,
-            None,
-            base,
-            None,
-            Precision::ByTick { tick: dec!(0.1) },
-            Precision::ByTick { tick: dec!(0) },
-        ))
+        Arc::new(SymbolBuilder::new(base, quote).amount_tick(dec!(0)).build())
     }
 
     #[test]
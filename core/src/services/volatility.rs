@@ -0,0 +1,336 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use dashmap::DashMap;
+use futures::FutureExt;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::common::{CurrencyPair, ExchangeAccountId, MarketAccountId, Price};
+use crate::exchanges::events::ExchangeEvent;
+use crate::exchanges::lag_aware_receiver::{ExchangeEventsReceiver, ReceivedEvent};
+use crate::infrastructure::spawn_future;
+use crate::misc::human_duration::HumanDuration;
+use crate::order_book::event::{EventType, OrderBookEvent};
+use crate::services::scheduler::{Schedule, Scheduler};
+use crate::storage::Storage;
+
+const VOLATILITY_NAMESPACE: &str = "volatility";
+const VOLATILITY_KEY: &str = "state";
+const VOLATILITY_SAVING_PERIOD: Duration = Duration::from_secs(60);
+
+/// Prices from which returns are calculated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum VolatilitySource {
+    Trades,
+    /// Mid price of order book snapshots
+    OrderBookMid,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct VolatilitySettings {
+    pub source: VolatilitySource,
+    /// Time after which weight of return in estimation is halved, e.g. `5m`
+    pub half_life: HumanDuration,
+    /// Min time between prices used for returns, so noise of bid-ask bounce is reduced
+    pub sampling_interval: HumanDuration,
+}
+
+impl Default for VolatilitySettings {
+    fn default() -> Self {
+        Self {
+            source: VolatilitySource::Trades,
+            half_life: HumanDuration::from_secs(300),
+            sampling_interval: HumanDuration::from_secs(1),
+        }
+    }
+}
+
+/// Online EWMA estimation of variance of log returns of one market
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct VolatilityEstimator {
+    last_price: Price,
+    last_time: DateTime,
+    /// Variance of log returns per second. `None` until second price is sampled
+    variance_rate: Option<f64>,
+}
+
+impl VolatilityEstimator {
+    pub fn new(price: Price, time: DateTime) -> Self {
+        Self {
+            last_price: price,
+            last_time: time,
+            variance_rate: None,
+        }
+    }
+
+    pub fn update(&mut self, price: Price, time: DateTime, settings: &VolatilitySettings) {
+        let elapsed_secs = (time - self.last_time).num_milliseconds() as f64 / 1000.;
+        let sampling_interval_secs = settings.sampling_interval.duration().as_secs_f64();
+        if elapsed_secs < sampling_interval_secs || elapsed_secs <= 0. {
+            return;
+        }
+
+        let ratio = match (price / self.last_price).to_f64() {
+            Some(ratio) if ratio > 0. => ratio,
+            _ => return,
+        };
+
+        let log_return = ratio.ln();
+        let sample = log_return * log_return / elapsed_secs;
+        let half_life_secs = settings.half_life.duration().as_secs_f64();
+        let alpha = 1. - 0.5f64.powf(elapsed_secs / half_life_secs);
+        self.variance_rate = Some(match self.variance_rate {
+            Some(variance_rate) => variance_rate + alpha * (sample - variance_rate),
+            None => sample,
+        });
+
+        self.last_price = price;
+        self.last_time = time;
+    }
+
+    /// Standard deviation of log returns over period, e.g. 0.01 means 1% of price
+    pub fn volatility(&self, period: Duration) -> Option<Decimal> {
+        let variance_rate = self.variance_rate?;
+        Decimal::from_f64((variance_rate * period.as_secs_f64()).sqrt())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct MarketVolatility {
+    exchange_account_id: ExchangeAccountId,
+    currency_pair: CurrencyPair,
+    estimator: VolatilityEstimator,
+}
+
+/// Volatility of all markets of engine for spread sizing in strategies and risk checks.
+/// State of estimators is saved to storage, so estimation isn't started from scratch after restart
+pub struct VolatilityService {
+    settings: VolatilitySettings,
+    estimators: DashMap<MarketAccountId, VolatilityEstimator>,
+}
+
+impl VolatilityService {
+    pub fn new(settings: VolatilitySettings, events_receiver: ExchangeEventsReceiver) -> Arc<Self> {
+        let service = Arc::new(Self {
+            settings,
+            estimators: DashMap::new(),
+        });
+
+        let action = service.clone().start(events_receiver);
+        let _ = spawn_future(
+            "Start volatility service",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action.boxed(),
+        );
+
+        service
+    }
+
+    /// Standard deviation of log returns of market over period. `None` if there isn't enough prices yet
+    pub fn volatility(
+        &self,
+        market_account_id: MarketAccountId,
+        period: Duration,
+    ) -> Option<Decimal> {
+        self.estimators
+            .get(&market_account_id)
+            .and_then(|estimator| estimator.volatility(period))
+    }
+
+    pub fn add_price(&self, market_account_id: MarketAccountId, price: Price, time: DateTime) {
+        self.estimators
+            .entry(market_account_id)
+            .and_modify(|estimator| estimator.update(price, time, &self.settings))
+            .or_insert_with(|| VolatilityEstimator::new(price, time));
+    }
+
+    pub async fn restore(&self, storage: &dyn Storage) -> Result<()> {
+        let markets: Vec<MarketVolatility> = match storage
+            .get_deserialized(VOLATILITY_NAMESPACE, VOLATILITY_KEY)
+            .await?
+        {
+            Some(markets) => markets,
+            None => return Ok(()),
+        };
+
+        for market in markets {
+            let market_account_id =
+                MarketAccountId::new(market.exchange_account_id, market.currency_pair);
+            let _ = self.estimators.insert(market_account_id, market.estimator);
+        }
+
+        Ok(())
+    }
+
+    pub async fn save(&self, storage: &dyn Storage) -> Result<()> {
+        let markets = self
+            .estimators
+            .iter()
+            .map(|x| MarketVolatility {
+                exchange_account_id: x.key().exchange_account_id,
+                currency_pair: x.key().currency_pair,
+                estimator: x.value().clone(),
+            })
+            .collect::<Vec<_>>();
+
+        storage
+            .put_serialized(VOLATILITY_NAMESPACE, VOLATILITY_KEY, &markets)
+            .await
+    }
+
+    pub(crate) fn schedule_saving(
+        self: &Arc<Self>,
+        scheduler: &Arc<Scheduler>,
+        storage: Arc<dyn Storage>,
+    ) {
+        let service = self.clone();
+        let save_volatility = move |_| {
+            let service = service.clone();
+            let storage = storage.clone();
+            async move {
+                if let Err(error) = service.save(storage.as_ref()).await {
+                    log::warn!("Unable to save volatility to storage: {:?}", error);
+                }
+            }
+            .boxed()
+        };
+
+        let _ = scheduler.schedule(
+            "Volatility saving",
+            Schedule::Every(VOLATILITY_SAVING_PERIOD),
+            save_volatility,
+        );
+    }
+
+    async fn start(self: Arc<Self>, mut events_receiver: ExchangeEventsReceiver) -> Result<()> {
+        loop {
+            let event = match events_receiver.recv().await {
+                Some(ReceivedEvent::Event(event)) => event,
+                // Estimator is sampled by time, so dropped prices only decrease count of samples
+                Some(ReceivedEvent::EventsDropped { .. }) => continue,
+                None => return Ok(()),
+            };
+
+            match (self.settings.source, event) {
+                (VolatilitySource::Trades, ExchangeEvent::Trades(trades_event)) => {
+                    let market_account_id = MarketAccountId::new(
+                        trades_event.exchange_account_id,
+                        trades_event.currency_pair,
+                    );
+                    for trade in &trades_event.trades {
+                        self.add_price(market_account_id, trade.price, trade.transaction_time);
+                    }
+                }
+                (VolatilitySource::OrderBookMid, ExchangeEvent::OrderBookEvent(event)) => {
+                    if let Some(mid_price) = get_mid_price(&event) {
+                        let market_account_id =
+                            MarketAccountId::new(event.exchange_account_id, event.currency_pair);
+                        self.add_price(market_account_id, mid_price, event.creation_time);
+                    }
+                }
+                _ => continue,
+            }
+        }
+    }
+}
+
+/// Order book updates contain only changed levels, so only snapshots are used
+fn get_mid_price(event: &OrderBookEvent) -> Option<Price> {
+    if let EventType::Update = event.event_type {
+        return None;
+    }
+
+    let best_ask = event.data.asks.keys().next()?;
+    let best_bid = event.data.bids.keys().next_back()?;
+    Some((best_ask + best_bid) / dec!(2))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::storage::memory::MemoryStorage;
+
+    fn settings() -> VolatilitySettings {
+        VolatilitySettings {
+            source: VolatilitySource::Trades,
+            half_life: HumanDuration::from_secs(60),
+            sampling_interval: HumanDuration::from_secs(1),
+        }
+    }
+
+    #[test]
+    fn volatility_of_constant_price_is_zero() {
+        let start = Utc::now();
+        let mut estimator = VolatilityEstimator::new(dec!(100), start);
+        assert_eq!(estimator.volatility(Duration::from_secs(1)), None);
+
+        for i in 1..10 {
+            estimator.update(dec!(100), start + chrono::Duration::seconds(i), &settings());
+        }
+
+        assert_eq!(estimator.volatility(Duration::from_secs(1)), Some(dec!(0)));
+    }
+
+    #[test]
+    fn prices_inside_sampling_interval_are_skipped() {
+        let start = Utc::now();
+        let mut estimator = VolatilityEstimator::new(dec!(100), start);
+
+        estimator.update(
+            dec!(200),
+            start + chrono::Duration::milliseconds(500),
+            &settings(),
+        );
+        assert_eq!(estimator.volatility(Duration::from_secs(1)), None);
+
+        estimator.update(dec!(101), start + chrono::Duration::seconds(1), &settings());
+        let volatility = estimator
+            .volatility(Duration::from_secs(1))
+            .expect("in test");
+        // ln(1.01) ~ 0.00995
+        assert!(dec!(0.0099) < volatility && volatility < dec!(0.01));
+    }
+
+    #[tokio::test]
+    async fn state_is_restored_from_storage() {
+        let storage = MemoryStorage::default();
+        let market_account_id = MarketAccountId::new(
+            ExchangeAccountId::new("Binance".into(), 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        );
+        let start = Utc::now();
+
+        let service = VolatilityService {
+            settings: settings(),
+            estimators: DashMap::new(),
+        };
+        service.add_price(market_account_id, dec!(100), start);
+        service.add_price(
+            market_account_id,
+            dec!(102),
+            start + chrono::Duration::seconds(2),
+        );
+        service.save(&storage).await.expect("in test");
+
+        let restored = VolatilityService {
+            settings: settings(),
+            estimators: DashMap::new(),
+        };
+        restored.restore(&storage).await.expect("in test");
+
+        let period = Duration::from_secs(60);
+        assert!(restored.volatility(market_account_id, period).is_some());
+        assert_eq!(
+            restored.volatility(market_account_id, period),
+            service.volatility(market_account_id, period)
+        );
+    }
+}
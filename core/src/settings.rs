@@ -1,10 +1,73 @@
+use crate::balance_manager::capital_allocation::CapitalAllocationSettings;
 use crate::exchanges::common::{Amount, CurrencyCode, CurrencyPair, ExchangeAccountId};
+use crate::exchanges::events::CHANNEL_MAX_EVENTS_COUNT;
+use crate::exchanges::general::commission::FeeSettings;
+use crate::exchanges::general::maintenance::ScheduledMaintenance;
+use crate::exchanges::general::margin::MarginMonitoringSettings;
+use crate::exchanges::general::market_queues::MARKET_EVENTS_WARNING_DEPTH;
+use crate::exchanges::simulation::execution_models::ExecutionModelsSettings;
+use crate::lifecycle::handover::HandoverSettings;
+use crate::misc::human_duration::HumanDuration;
+use crate::order_tracing::TracingSettings;
+use crate::orders::cancel_priority::CancelPrioritySettings;
+use crate::orders::client_order_id::ClientOrderIdSettings;
+use crate::services::event_loop_watchdog::EventLoopWatchdogSettings;
+use crate::services::exposure_limits::ExposureLimitsSettings;
+use crate::services::fill_anomaly::FillAnomalySettings;
+use crate::services::min_profitable_spread::MinProfitableSpreadSettings;
+use crate::services::stale_order_reaper::StaleOrderReaperSettings;
+use crate::services::stats_snapshots::StatsSnapshotsSettings;
+use crate::services::volatility::VolatilitySettings;
+use crate::strategies::inventory_skew::InventorySkewSettings;
+use chrono::NaiveTime;
+use rust_decimal::Decimal;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 pub trait BaseStrategySettings {
     fn exchange_account_id(&self) -> ExchangeAccountId;
     fn currency_pair(&self) -> CurrencyPair;
     fn max_amount(&self) -> Amount;
+
+    /// Source of max amount of strategy orders. Static `max_amount` is used by default
+    fn max_amount_settings(&self) -> MaxAmountSettings {
+        MaxAmountSettings::Fixed(self.max_amount())
+    }
+
+    /// Randomization of sizes and prices of strategy orders. Orders aren't randomized by default
+    fn order_randomization(&self) -> Option<OrderRandomizationSettings> {
+        None
+    }
+
+    /// Shifting of quotes to return position to target inventory. Quotes aren't skewed by default
+    fn inventory_skew(&self) -> Option<InventorySkewSettings> {
+        None
+    }
+}
+
+/// Max amount of strategy orders. It can be specified in config as number for fixed max amount
+/// or as table with `utilization_percent`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum MaxAmountSettings {
+    Fixed(Amount),
+    /// Percent of available leveraged balance which is recomputed every strategy cycle
+    /// by `BalanceManager::get_max_amount_by_utilization`
+    BalanceUtilization {
+        utilization_percent: Decimal,
+    },
+}
+
+/// Orders of strategy are made smaller and more passive by random values at submission time,
+/// so they don't leave predictable round-number footprints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+pub struct OrderRandomizationSettings {
+    /// Max reduction of order amount in percents
+    pub max_amount_reduction_percent: Decimal,
+    /// Max shift of order price to passive side in price ticks. Price isn't shifted for symbols
+    /// without price tick
+    #[serde(default)]
+    pub max_price_shift_ticks: u32,
 }
 
 /// Application settings
@@ -17,14 +80,180 @@ where
 {
     pub strategy: StrategySettings,
     pub core: CoreSettings,
+    /// Candidate strategy which is evaluated alongside live strategy without sending of orders.
+    /// Shadow mode is disabled if it isn't specified
+    pub shadow_strategy: Option<ShadowStrategySettings<StrategySettings>>,
+}
+
+/// Candidate strategy is built by the same builder as live strategy, but with its own settings
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ShadowStrategySettings<StrategySettings> {
+    /// Name of shadow strategy in its report
+    pub name: String,
+    pub strategy: StrategySettings,
+    /// Execution assumptions of simulator which fills shadow orders
+    #[serde(default)]
+    pub execution_models: ExecutionModelsSettings,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
 pub struct CoreSettings {
     pub exchanges: Vec<ExchangeSettings>,
+    /// Automatic refilling of exchange accounts. Disabled if it isn't specified
+    pub treasury: Option<TreasurySettings>,
+    /// Path to rhai script with rules for orders checked before submission (see `OrderFilter`)
+    pub order_filter_script: Option<String>,
+    /// Window of trade flow metrics (imbalance, toxicity) available to strategies, e.g. `1m`.
+    /// Default is 60 seconds
+    pub trade_flow_window: Option<HumanDuration>,
+    /// Connectivity of exchange account is reported as degraded in engine status if average
+    /// latency of REST requests or websocket heartbeats exceeds it. Latency isn't checked if it isn't specified
+    pub latency_degradation_threshold: Option<HumanDuration>,
+    /// Limits of requoting in disposition executor. Requotes aren't limited if it isn't specified
+    pub quote_governor: Option<QuoteGovernorSettings>,
+    /// Persistence of order history, statistics and other engine data. Data is kept in memory if it isn't specified
+    pub storage: Option<StorageSettings>,
+    /// Currency to which commissions are converted in statistics. Commissions aren't converted if it isn't specified
+    pub commission_reference_currency_code: Option<CurrencyCode>,
+    /// Estimation of volatility of markets. Default settings are used if it isn't specified
+    pub volatility: Option<VolatilitySettings>,
+    /// Scheme of client order ids with embedded strategy code. Numeric unique ids are used if it isn't specified
+    pub client_order_id: Option<ClientOrderIdSettings>,
+    /// Max amounts which strategy configurations can reserve and hold in filled positions.
+    /// Strategies aren't limited if it isn't specified
+    pub capital_allocations: Option<Vec<CapitalAllocationSettings>>,
+    /// Export of order lifecycle traces to Jaeger. Traces aren't exported if it isn't specified
+    pub tracing: Option<TracingSettings>,
+    /// Cancellation of resting orders which are too old or too far from the top of order book.
+    /// Orders aren't reaped if it isn't specified
+    pub stale_order_reaper: Option<StaleOrderReaperSettings>,
+    /// Limits of notional and delta of all exchange accounts which are checked before every order
+    /// and periodically after trades. Exposure isn't limited if it isn't specified
+    pub exposure_limits: Option<ExposureLimitsSettings>,
+    /// Thresholds of fill anomalies which are alerted. Default settings are used if it isn't specified
+    pub fill_anomaly: Option<FillAnomalySettings>,
+    /// Order of cancellation of open orders by their classes on graceful shutdown.
+    /// Quotes are cancelled first, then algo orders and hedges last if it isn't specified
+    pub cancel_priorities: Option<Vec<CancelPrioritySettings>>,
+    /// Detection of stalled event loops. Default settings are used if it isn't specified
+    pub event_loop_watchdog: Option<EventLoopWatchdogSettings>,
+    /// IPC address of control panel API. `IPC_ADDRESS` of `mmb_rpc` is used if it isn't specified
+    pub rpc_ipc_address: Option<String>,
+    /// Takeover of open orders and state from running instance of engine on start.
+    /// Engine starts trading immediately if it isn't specified
+    pub handover: Option<HandoverSettings>,
+    /// Periodic snapshots of statistics in traffic record files of exchanges.
+    /// Snapshots aren't recorded if it isn't specified
+    pub stats_snapshots: Option<StatsSnapshotsSettings>,
+    /// Costs which are taken into account by min profitable spread of strategies. Default settings
+    /// are used if it isn't specified
+    pub min_profitable_spread: Option<MinProfitableSpreadSettings>,
+    /// Capacities of event channels. Default settings are used if it isn't specified
+    pub event_channels: Option<EventChannelsSettings>,
+    #[serde(default)]
+    pub timeouts: TimeoutsSettings,
+}
+
+/// Overflow behavior of every channel is fixed. Events are kept in memory only: they contain
+/// orders and callbacks which can't be serialized, so spilling of events to disk isn't supported
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EventChannelsSettings {
+    /// Capacity of broadcast channel of exchange events (order books, trades, order events)
+    /// for services and strategies. Lagging subscribers lose the oldest events
+    pub exchange_events_capacity: usize,
+    /// Depth of queue of websocket order events of market (see `MarketEventQueues`) after which
+    /// market is logged as overloaded. Order events can't be lost and receiving of websocket
+    /// messages can't be paused, so these queues are unbounded
+    pub market_events_warning_depth: usize,
+}
+
+impl Default for EventChannelsSettings {
+    fn default() -> Self {
+        Self {
+            exchange_events_capacity: CHANNEL_MAX_EVENTS_COUNT,
+            market_events_warning_depth: MARKET_EVENTS_WARNING_DEPTH,
+        }
+    }
+}
+
+/// Timeouts of engine lifecycle in human-readable form, e.g. `5s` or `2m`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TimeoutsSettings {
+    /// Max time of cancellation of open orders on graceful shutdown and stopping of exchange
+    pub cancel_orders: HumanDuration,
+    /// Max time of waiting for services to finish their work on graceful shutdown
+    pub services_shutdown: HumanDuration,
 }
 
+impl Default for TimeoutsSettings {
+    fn default() -> Self {
+        Self {
+            cancel_orders: HumanDuration::from_secs(5),
+            services_shutdown: HumanDuration::from_secs(3),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StorageSettings {
+    Memory,
+    /// Local sqlite database file, it's created if doesn't exist
+    Sqlite {
+        path: String,
+    },
+    Postgres {
+        connection_string: String,
+    },
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct QuoteGovernorSettings {
+    /// Resting orders aren't cancelled for requoting until they live at least this time,
+    /// e.g. `500ms`
+    pub min_order_lifetime: Option<HumanDuration>,
+    /// Max count of requotes per minute for market
+    pub max_requotes_per_minute: Option<usize>,
+}
+
+/// Daily time window in UTC. Window ends on the next day if `end` is less than `start`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TradingWindowSettings {
+    /// All currency pairs of exchange if it isn't specified
+    pub currency_pair: Option<CurrencyPair>,
+    /// Time in format "HH:MM:SS"
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    /// Days of week when window is applied (1 - Monday, 7 - Sunday). Every day if it isn't specified
+    pub weekdays: Option<Vec<u32>>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TreasurySettings {
+    /// Period of balances checking, e.g. `1m`. Default is 60 seconds
+    pub check_period: Option<HumanDuration>,
+    pub refills: Vec<TreasuryRefillSettings>,
+}
+
+/// Withdraw `refill_amount` from source account to `deposit_address`
+/// when balance of currency on exchange account becomes lower than `min_balance`.
+/// Accounts of the same master account are refilled by sub-account transfer instead,
+/// so `deposit_address` isn't required for them.
+/// Transfer is executed only after confirmation of pending refill by operator
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TreasuryRefillSettings {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_code: CurrencyCode,
+    pub min_balance: Amount,
+    pub refill_amount: Amount,
+    pub source_exchange_account_id: ExchangeAccountId,
+    pub deposit_address: Option<String>,
+    pub network: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize, JsonSchema)]
 #[serde(untagged)]
 pub enum CurrencyPairSetting {
     Ordinary {
@@ -34,6 +263,26 @@ pub enum CurrencyPairSetting {
     Specific(String),
 }
 
+/// Polling of order book snapshots by REST for exchanges without websocket depth stream
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct OrderBookPollingSettings {
+    /// Count of price levels on each side of snapshot
+    pub depth: usize,
+    /// Percent of exchange request limit which is used for polling. Default is 20
+    pub request_range_percent: Option<f64>,
+    /// All currency pairs of exchange are polled if it isn't specified
+    pub currency_pairs: Option<Vec<CurrencyPair>>,
+}
+
+/// Position mode of derivative account
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub enum PositionMode {
+    /// Single position for each currency pair
+    OneWay,
+    /// Separate long and short positions for each currency pair
+    Hedge,
+}
+
 // Field order are matter for serialization:
 // Simple values must be emmited before struct with custom serialization
 // https://github.com/alexcrichton/toml-rs/issues/142#issuecomment-278970591
@@ -47,6 +296,55 @@ pub struct ExchangeSettings {
     pub request_trades: bool,
     pub is_reducing_market_data: Option<bool>,
     pub subscribe_to_market_data: bool,
+    /// Period of symbols metadata refreshing. Symbols are loaded only on startup if it isn't specified
+    pub symbols_refresh_period: Option<HumanDuration>,
+    /// Time window in which signed requests are valid on exchange side (if exchange supports it)
+    pub recv_window: Option<HumanDuration>,
+    pub rest_connect_timeout: Option<HumanDuration>,
+    /// Timeout of whole REST request including reading of response
+    pub rest_request_timeout: Option<HumanDuration>,
+    pub websocket_ping_interval: Option<HumanDuration>,
+    /// Delay between failed attempts to open websocket connection, e.g. `1s`. Connection is retried
+    /// immediately if it isn't specified
+    pub websocket_reconnect_backoff: Option<HumanDuration>,
+    /// Outbound proxy for REST and websocket connections, e.g. `http://host:port` or `socks5://host:port`
+    pub proxy: Option<String>,
+    /// File for recording of all REST and websocket traffic of exchange
+    pub traffic_record_path: Option<String>,
+    /// File with recorded traffic. REST responses are taken from it instead of sending requests to exchange,
+    /// websockets aren't connected and recorded websocket messages are replayed after strategy is started
+    pub traffic_replay_path: Option<String>,
+    /// Position mode of derivative account. One-way mode is used if it isn't specified
+    pub position_mode: Option<PositionMode>,
+    /// Allows withdrawals and transfers of funds from exchange account. Disabled if it isn't specified
+    pub is_withdrawal_enabled: Option<bool>,
+    /// Market data and balances are received, but orders aren't placed, e.g. for warm-standby engine
+    /// or checking of new exchange integration. Disabled if it isn't specified
+    pub is_market_data_only: Option<bool>,
+    /// Identifier of sub-account on exchange (email for Binance) if `exchange_account_id` contains sub-account number.
+    /// Master account uses it for balance requests and transfers of sub-account
+    pub sub_account: Option<String>,
+    /// Balances which are worth less than threshold in currency of dust conversion (BNB for Binance)
+    /// are periodically converted by exchange. Disabled if it isn't specified
+    pub dust_conversion_threshold: Option<Amount>,
+    /// Period of dust balances checking. Default is 1 hour
+    pub dust_conversion_period: Option<HumanDuration>,
+    /// Windows when trading is allowed. Outside of them orders are cancelled and new orders are rejected.
+    /// Trading isn't restricted for currency pairs without windows
+    pub trading_windows: Option<Vec<TradingWindowSettings>>,
+    /// Period of polling of exchange system status. Exchange account is blocked during maintenance
+    pub maintenance_check_period: Option<HumanDuration>,
+    /// Maintenances announced by exchange which aren't available in system status
+    pub scheduled_maintenances: Option<Vec<ScheduledMaintenance>>,
+    /// Polling of account margin ratio of derivative account with alerts and automatic deleveraging
+    pub margin_monitoring: Option<MarginMonitoringSettings>,
+    /// Order book snapshots are polled by REST if it's specified, e.g. when websocket depth isn't available
+    pub order_book_polling: Option<OrderBookPollingSettings>,
+    /// Maker and taker fees of account for commissions which aren't reported by exchange and for
+    /// min profitable spread of strategies. Fees are zero if it isn't specified
+    pub fees: Option<FeeSettings>,
+    /// Channels of main websocket subscribed for every traded currency pair, e.g. `depth20` or `trade`.
+    /// Every channel should have handler in websocket message router of exchange client
     pub websocket_channels: Vec<String>,
     pub currency_pairs: Option<Vec<CurrencyPairSetting>>,
     pub empty_response_is_ok: bool,
@@ -71,6 +369,27 @@ impl ExchangeSettings {
             currency_pairs: None,
             subscribe_to_market_data: true,
             is_reducing_market_data: None,
+            symbols_refresh_period: None,
+            recv_window: None,
+            rest_connect_timeout: None,
+            rest_request_timeout: None,
+            websocket_ping_interval: None,
+            websocket_reconnect_backoff: None,
+            proxy: None,
+            traffic_record_path: None,
+            traffic_replay_path: None,
+            position_mode: None,
+            is_withdrawal_enabled: None,
+            is_market_data_only: None,
+            sub_account: None,
+            dust_conversion_threshold: None,
+            dust_conversion_period: None,
+            trading_windows: None,
+            maintenance_check_period: None,
+            scheduled_maintenances: None,
+            margin_monitoring: None,
+            order_book_polling: None,
+            fees: None,
             empty_response_is_ok,
         }
     }
@@ -88,6 +407,27 @@ impl Default for ExchangeSettings {
             currency_pairs: None,
             subscribe_to_market_data: true,
             is_reducing_market_data: None,
+            symbols_refresh_period: None,
+            recv_window: None,
+            rest_connect_timeout: None,
+            rest_request_timeout: None,
+            websocket_ping_interval: None,
+            websocket_reconnect_backoff: None,
+            proxy: None,
+            traffic_record_path: None,
+            traffic_replay_path: None,
+            position_mode: None,
+            is_withdrawal_enabled: None,
+            is_market_data_only: None,
+            sub_account: None,
+            dust_conversion_threshold: None,
+            dust_conversion_period: None,
+            trading_windows: None,
+            maintenance_check_period: None,
+            scheduled_maintenances: None,
+            margin_monitoring: None,
+            order_book_polling: None,
+            fees: None,
             empty_response_is_ok: false,
         }
     }
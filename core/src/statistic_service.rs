@@ -1,24 +1,34 @@
-use super::orders::{event::OrderEventType, order::ClientOrderId};
+use super::orders::{
+    event::OrderEventType,
+    order::{ClientOrderId, OrderFillRole},
+};
 use anyhow::{Context, Result};
 use futures::FutureExt;
 use mmb_utils::infrastructure::SpawnFutureFlags;
-use mmb_utils::nothing_to_do;
+use mmb_utils::{nothing_to_do, DateTime};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
+use itertools::Itertools;
 use parking_lot::{Mutex, RwLock};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use tokio::sync::broadcast;
 
 use super::{
+    commission_ledger::{CommissionLedger, PriceSource},
     exchanges::{
-        common::{Amount, MarketAccountId, Price},
-        events::ExchangeEvent,
+        common::{
+            Amount, CurrencyCode, CurrencyPair, ExchangeAccountId, ExchangeId, MarketAccountId,
+            Price,
+        },
+        events::{CommissionCorrectionEvent, ExchangeEvent},
     },
     infrastructure::spawn_future,
+    misc::time::time_manager,
 };
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct MarketAccountIdStatistic {
     opened_orders_count: u64,
     canceled_orders_count: u64,
@@ -28,9 +38,35 @@ pub struct MarketAccountIdStatistic {
     summary_filled_amount: Amount,
     // Calculated only for completely filled orders
     summary_commission: Amount,
+    // Requotes suppressed by quote governor of disposition executor
+    suppressed_requotes_count: u64,
+    maker_fills_count: u64,
+    taker_fills_count: u64,
+    // Amount ahead of orders at their price levels when orders were placed
+    summary_queue_amount_ahead: Amount,
+    queue_positions_count: u64,
+    first_activity_time: Option<DateTime>,
+    last_activity_time: Option<DateTime>,
 }
 
 impl MarketAccountIdStatistic {
+    fn register_activity(&mut self, time: DateTime) {
+        let _ = self.first_activity_time.get_or_insert(time);
+        self.last_activity_time = Some(time);
+    }
+
+    /// Statistics are cumulative, so market is matched if it has activity within time range
+    fn is_active_within(&self, from: Option<DateTime>, to: Option<DateTime>) -> bool {
+        let (first_activity_time, last_activity_time) =
+            match (self.first_activity_time, self.last_activity_time) {
+                (Some(first), Some(last)) => (first, last),
+                _ => return from.is_none() && to.is_none(),
+            };
+
+        from.map_or(true, |from| last_activity_time >= from)
+            && to.map_or(true, |to| first_activity_time <= to)
+    }
+
     fn register_created_order(&mut self) {
         self.opened_orders_count += 1;
     }
@@ -62,9 +98,68 @@ impl MarketAccountIdStatistic {
     fn add_summary_commission(&mut self, commission: Price) {
         self.summary_commission += commission;
     }
+
+    fn register_suppressed_requote(&mut self) {
+        self.suppressed_requotes_count += 1;
+    }
+
+    fn register_fill_role(&mut self, role: OrderFillRole) {
+        match role {
+            OrderFillRole::Maker => self.maker_fills_count += 1,
+            OrderFillRole::Taker => self.taker_fills_count += 1,
+        }
+    }
+
+    fn register_queue_position(&mut self, amount_ahead: Amount) {
+        self.summary_queue_amount_ahead += amount_ahead;
+        self.queue_positions_count += 1;
+    }
+
+    fn order_counters(&self) -> OrderCounters {
+        OrderCounters {
+            created: self.opened_orders_count,
+            canceled: self.canceled_orders_count,
+            filled: self.fully_filled_orders_count,
+        }
+    }
+
+    fn fill_analytics(&self) -> FillAnalytics {
+        let fills_count = self.maker_fills_count + self.taker_fills_count;
+        let maker_fill_ratio = (fills_count > 0)
+            .then(|| Decimal::from(self.maker_fills_count) / Decimal::from(fills_count));
+        let average_queue_amount_ahead = (self.queue_positions_count > 0)
+            .then(|| self.summary_queue_amount_ahead / Decimal::from(self.queue_positions_count));
+
+        FillAnalytics {
+            maker_fills_count: self.maker_fills_count,
+            taker_fills_count: self.taker_fills_count,
+            maker_fill_ratio,
+            average_queue_amount_ahead,
+        }
+    }
+}
+
+/// Maker/taker fills and queue positions of orders on market, so strategies can tune
+/// how aggressively they price quotes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FillAnalytics {
+    pub maker_fills_count: u64,
+    pub taker_fills_count: u64,
+    /// Share of maker fills among all fills, `None` if there are no fills yet
+    pub maker_fill_ratio: Option<Decimal>,
+    /// Average amount which was ahead of orders at their price levels on placement
+    pub average_queue_amount_ahead: Option<Amount>,
+}
+
+/// Cumulative counts of orders of market
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OrderCounters {
+    pub created: u64,
+    pub canceled: u64,
+    pub filled: u64,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct DispositionExecutorStatistic {
     skipped_events_amount: u64,
 }
@@ -73,47 +168,43 @@ pub struct DispositionExecutorStatistic {
 pub(crate) struct StatisticServiceState {
     market_account_id_stats: RwLock<HashMap<MarketAccountId, MarketAccountIdStatistic>>,
     disposition_executor_stats: Mutex<DispositionExecutorStatistic>,
+    commissions: CommissionLedger,
 }
 
 impl StatisticServiceState {
-    pub(crate) fn register_created_order(&self, market_account_id: MarketAccountId) {
-        self.market_account_id_stats
-            .write()
+    fn update_market_stats(
+        &self,
+        market_account_id: MarketAccountId,
+        update: impl FnOnce(&mut MarketAccountIdStatistic),
+    ) {
+        let mut market_account_id_stats = self.market_account_id_stats.write();
+        let stats = market_account_id_stats
             .entry(market_account_id)
-            .or_default()
-            .register_created_order();
+            .or_default();
+        stats.register_activity(time_manager::now());
+        update(stats);
+    }
+
+    pub(crate) fn register_created_order(&self, market_account_id: MarketAccountId) {
+        self.update_market_stats(market_account_id, |x| x.register_created_order());
     }
 
     pub(crate) fn register_canceled_order(&self, market_account_id: MarketAccountId) {
-        self.market_account_id_stats
-            .write()
-            .entry(market_account_id)
-            .or_default()
-            .register_canceled_order();
+        self.update_market_stats(market_account_id, |x| x.register_canceled_order());
     }
 
     pub(crate) fn register_partially_filled_order(&self, market_account_id: MarketAccountId) {
-        self.market_account_id_stats
-            .write()
-            .entry(market_account_id)
-            .or_default()
-            .increment_partially_filled_orders();
+        self.update_market_stats(market_account_id, |x| x.increment_partially_filled_orders());
     }
 
     fn decrement_partially_filled_orders(&self, market_account_id: MarketAccountId) {
-        self.market_account_id_stats
-            .write()
-            .entry(market_account_id)
-            .or_default()
-            .decrement_partially_filled_orders();
+        self.update_market_stats(market_account_id, |x| x.decrement_partially_filled_orders());
     }
 
     pub(crate) fn register_completely_filled_order(&self, market_account_id: MarketAccountId) {
-        self.market_account_id_stats
-            .write()
-            .entry(market_account_id)
-            .or_default()
-            .increment_completely_filled_orders();
+        self.update_market_stats(market_account_id, |x| {
+            x.increment_completely_filled_orders()
+        });
     }
 
     pub(crate) fn register_filled_amount(
@@ -121,11 +212,9 @@ impl StatisticServiceState {
         market_account_id: MarketAccountId,
         filled_amount: Amount,
     ) {
-        self.market_account_id_stats
-            .write()
-            .entry(market_account_id)
-            .or_default()
-            .add_summary_filled_amount(filled_amount);
+        self.update_market_stats(market_account_id, |x| {
+            x.add_summary_filled_amount(filled_amount)
+        });
     }
 
     pub(crate) fn register_commission(
@@ -133,16 +222,138 @@ impl StatisticServiceState {
         market_account_id: MarketAccountId,
         commission: Price,
     ) {
-        self.market_account_id_stats
-            .write()
-            .entry(market_account_id)
-            .or_default()
-            .add_summary_commission(commission);
+        self.update_market_stats(market_account_id, |x| x.add_summary_commission(commission));
     }
 
     pub(crate) fn register_skipped_event(&self) {
         (*self.disposition_executor_stats.lock()).skipped_events_amount += 1;
     }
+
+    pub(crate) fn register_commission_fill(&self, currency_code: CurrencyCode, amount: Amount) {
+        self.commissions.register_commission(currency_code, amount);
+    }
+
+    pub(crate) fn register_commission_correction(&self, event: &CommissionCorrectionEvent) {
+        self.commissions.correct_commission(
+            event.commission_currency_code,
+            event.commission_amount,
+            event.converted_commission_currency_code,
+            event.converted_commission_amount,
+        );
+    }
+
+    pub(crate) fn register_suppressed_requote(&self, market_account_id: MarketAccountId) {
+        self.update_market_stats(market_account_id, |x| x.register_suppressed_requote());
+    }
+
+    pub(crate) fn register_fill_role(
+        &self,
+        market_account_id: MarketAccountId,
+        role: OrderFillRole,
+    ) {
+        self.update_market_stats(market_account_id, |x| x.register_fill_role(role));
+    }
+
+    pub(crate) fn register_queue_position(
+        &self,
+        market_account_id: MarketAccountId,
+        amount_ahead: Amount,
+    ) {
+        self.update_market_stats(market_account_id, |x| {
+            x.register_queue_position(amount_ahead)
+        });
+    }
+
+    fn fill_analytics(&self, market_account_id: MarketAccountId) -> Option<FillAnalytics> {
+        self.market_account_id_stats
+            .read()
+            .get(&market_account_id)
+            .map(|x| x.fill_analytics())
+    }
+
+    fn order_counters(&self) -> HashMap<MarketAccountId, OrderCounters> {
+        self.market_account_id_stats
+            .read()
+            .iter()
+            .map(|(market_account_id, stats)| (*market_account_id, stats.order_counters()))
+            .collect()
+    }
+
+    fn report(&self, query: &StatisticsQuery) -> StatisticsReport<'_> {
+        let market_account_id_stats: HashMap<_, _> = self
+            .market_account_id_stats
+            .read()
+            .iter()
+            .filter(|(market_account_id, stats)| query.matches(market_account_id, stats))
+            .map(|(market_account_id, stats)| (*market_account_id, stats.clone()))
+            .collect();
+
+        let markets = market_account_id_stats
+            .iter()
+            .map(|(market_account_id, stats)| MarketStatisticReport {
+                market_account_id: *market_account_id,
+                statistic: stats.clone(),
+            })
+            .sorted_by_key(|x| x.market_account_id.to_string())
+            .collect_vec();
+
+        StatisticsReport {
+            markets,
+            market_account_id_stats,
+            disposition_executor_stats: self.disposition_executor_stats.lock().clone(),
+            commissions: &self.commissions,
+        }
+    }
+}
+
+/// Filter of statistics report. All markets are reported if filter is empty
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct StatisticsQuery {
+    pub exchange_id: Option<ExchangeId>,
+    pub exchange_account_id: Option<ExchangeAccountId>,
+    pub currency_pair: Option<CurrencyPair>,
+    /// Markets without activity since this time are skipped
+    pub from: Option<DateTime>,
+    /// Markets without activity before this time are skipped
+    pub to: Option<DateTime>,
+}
+
+impl StatisticsQuery {
+    fn matches(
+        &self,
+        market_account_id: &MarketAccountId,
+        stats: &MarketAccountIdStatistic,
+    ) -> bool {
+        let exchange_account_id = market_account_id.exchange_account_id;
+        self.exchange_id
+            .map_or(true, |x| x == exchange_account_id.exchange_id)
+            && self
+                .exchange_account_id
+                .map_or(true, |x| x == exchange_account_id)
+            && self
+                .currency_pair
+                .map_or(true, |x| x == market_account_id.currency_pair)
+            && stats.is_active_within(self.from, self.to)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct MarketStatisticReport {
+    #[serde(serialize_with = "MarketAccountId::serialize_as_struct")]
+    pub market_account_id: MarketAccountId,
+    #[serde(flatten)]
+    pub statistic: MarketAccountIdStatistic,
+}
+
+#[derive(Serialize)]
+pub struct StatisticsReport<'a> {
+    pub markets: Vec<MarketStatisticReport>,
+    /// Statistics of `markets` keyed by strings like "Binance_0|cnd/btc" for WebUI.
+    /// Deprecated, it will be removed after WebUI switches to `markets`
+    pub market_account_id_stats: HashMap<MarketAccountId, MarketAccountIdStatistic>,
+    pub disposition_executor_stats: DispositionExecutorStatistic,
+    pub commissions: &'a CommissionLedger,
 }
 
 #[derive(Default, Debug)]
@@ -225,6 +436,69 @@ impl StatisticService {
     pub(crate) fn register_skipped_event(&self) {
         self.statistic_service_state.register_skipped_event();
     }
+
+    pub(crate) fn register_commission_fill(&self, currency_code: CurrencyCode, amount: Amount) {
+        self.statistic_service_state
+            .register_commission_fill(currency_code, amount);
+    }
+
+    /// Commissions of fills are converted to reference currency by prices of price source
+    pub fn setup_commission_conversion(
+        &self,
+        reference_currency_code: CurrencyCode,
+        price_source: Arc<dyn PriceSource>,
+    ) {
+        self.statistic_service_state
+            .commissions
+            .setup_conversion(reference_currency_code, price_source);
+    }
+
+    pub fn commissions(&self) -> &CommissionLedger {
+        &self.statistic_service_state.commissions
+    }
+
+    pub(crate) fn register_commission_correction(&self, event: &CommissionCorrectionEvent) {
+        self.statistic_service_state
+            .register_commission_correction(event);
+    }
+
+    pub(crate) fn register_suppressed_requote(&self, market_account_id: MarketAccountId) {
+        self.statistic_service_state
+            .register_suppressed_requote(market_account_id);
+    }
+
+    pub(crate) fn register_fill_role(
+        &self,
+        market_account_id: MarketAccountId,
+        role: OrderFillRole,
+    ) {
+        self.statistic_service_state
+            .register_fill_role(market_account_id, role);
+    }
+
+    /// Registers amount ahead of new order at its price level in local order book snapshot
+    pub(crate) fn register_queue_position(
+        &self,
+        market_account_id: MarketAccountId,
+        amount_ahead: Amount,
+    ) {
+        self.statistic_service_state
+            .register_queue_position(market_account_id, amount_ahead);
+    }
+
+    /// Returns `None` if there are no statistics for market yet
+    pub fn fill_analytics(&self, market_account_id: MarketAccountId) -> Option<FillAnalytics> {
+        self.statistic_service_state
+            .fill_analytics(market_account_id)
+    }
+
+    pub fn order_counters(&self) -> HashMap<MarketAccountId, OrderCounters> {
+        self.statistic_service_state.order_counters()
+    }
+
+    pub fn report(&self, query: &StatisticsQuery) -> StatisticsReport<'_> {
+        self.statistic_service_state.report(query)
+    }
 }
 
 pub struct StatisticEventHandler {
@@ -286,6 +560,15 @@ impl StatisticEventHandler {
                             market_account_id,
                             &cloned_order.header.client_order_id,
                         );
+
+                        if let Some(fill) = cloned_order.fills.fills.last() {
+                            self.stats.register_commission_fill(
+                                fill.commission_currency_code(),
+                                fill.commission_amount(),
+                            );
+                            self.stats
+                                .register_fill_role(market_account_id, fill.role());
+                        }
                     }
                     OrderEventType::OrderCompleted { cloned_order } => {
                         let commission = cloned_order
@@ -307,9 +590,182 @@ impl StatisticEventHandler {
                     _ => nothing_to_do(),
                 }
             }
+            ExchangeEvent::CommissionCorrection(event) => {
+                self.stats.register_commission_correction(&event);
+            }
+            ExchangeEvent::DustConversion(event) => {
+                for conversion in &event.conversions {
+                    self.stats
+                        .register_commission_fill(conversion.converted_to, conversion.commission);
+                }
+            }
             _ => nothing_to_do(),
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::{CurrencyPair, ExchangeAccountId};
+    use crate::exchanges::events::{DustConversion, DustConversionEvent};
+    use rust_decimal_macros::dec;
+
+    struct NoPriceSource;
+
+    impl PriceSource for NoPriceSource {
+        fn get_price(&self, _from: CurrencyCode, _to: CurrencyCode) -> Option<Price> {
+            None
+        }
+    }
+
+    #[test]
+    fn fill_analytics_are_aggregated_by_market() {
+        let stats = StatisticService::new();
+        let market_account_id = MarketAccountId::new(
+            ExchangeAccountId::new("Binance".into(), 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        );
+        assert_eq!(stats.fill_analytics(market_account_id), None);
+
+        stats.register_queue_position(market_account_id, dec!(1));
+        stats.register_queue_position(market_account_id, dec!(2));
+        let analytics = stats.fill_analytics(market_account_id).expect("in test");
+        assert_eq!(analytics.maker_fill_ratio, None);
+        assert_eq!(analytics.average_queue_amount_ahead, Some(dec!(1.5)));
+
+        stats.register_fill_role(market_account_id, OrderFillRole::Maker);
+        stats.register_fill_role(market_account_id, OrderFillRole::Maker);
+        stats.register_fill_role(market_account_id, OrderFillRole::Maker);
+        stats.register_fill_role(market_account_id, OrderFillRole::Taker);
+        let analytics = stats.fill_analytics(market_account_id).expect("in test");
+        assert_eq!(analytics.maker_fills_count, 3);
+        assert_eq!(analytics.taker_fills_count, 1);
+        assert_eq!(analytics.maker_fill_ratio, Some(dec!(0.75)));
+    }
+
+    #[test]
+    fn commission_correction_is_registered_in_ledger() {
+        let handler = StatisticEventHandler {
+            stats: StatisticService::new(),
+        };
+        let commission_currency_code: CurrencyCode = "BNB".into();
+        handler
+            .stats
+            .setup_commission_conversion("BTC".into(), Arc::new(NoPriceSource));
+        handler
+            .stats
+            .register_commission_fill(commission_currency_code, dec!(15));
+
+        let event = CommissionCorrectionEvent {
+            exchange_account_id: ExchangeAccountId::new("Binance".into(), 0),
+            client_order_id: ClientOrderId::unique_id(),
+            trade_id: None,
+            commission_currency_code,
+            commission_amount: dec!(15),
+            converted_commission_currency_code: "BTC".into(),
+            converted_commission_amount: dec!(4.5),
+        };
+        handler
+            .handle_event(ExchangeEvent::CommissionCorrection(event))
+            .expect("in test");
+
+        let accrual = &handler.stats.commissions().accruals()[&commission_currency_code];
+        assert_eq!(accrual.reference_amount, dec!(4.5));
+        assert_eq!(accrual.unconverted_amount, dec!(0));
+    }
+
+    #[test]
+    fn commissions_of_dust_conversion_are_registered_in_ledger() {
+        let handler = StatisticEventHandler {
+            stats: StatisticService::new(),
+        };
+        let bnb: CurrencyCode = "BNB".into();
+        let conversion = |currency_code: &str, commission| DustConversion {
+            currency_code: currency_code.into(),
+            amount: dec!(1),
+            converted_to: bnb,
+            converted_amount: dec!(0.01),
+            commission,
+        };
+
+        let event = DustConversionEvent {
+            exchange_account_id: ExchangeAccountId::new("Binance".into(), 0),
+            conversions: vec![
+                conversion("DOGE", dec!(0.0002)),
+                conversion("XRP", dec!(0.0003)),
+            ],
+        };
+        handler
+            .handle_event(ExchangeEvent::DustConversion(event))
+            .expect("in test");
+
+        assert_eq!(
+            handler.stats.commissions().accruals()[&bnb].amount,
+            dec!(0.0005)
+        );
+    }
+
+    #[test]
+    fn report_is_filtered_by_query() {
+        let stats = StatisticService::new();
+        let btc_usdt = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        let binance = MarketAccountId::new(ExchangeAccountId::new("Binance".into(), 0), btc_usdt);
+        let bitmex = MarketAccountId::new(ExchangeAccountId::new("Bitmex".into(), 0), btc_usdt);
+        stats.register_created_order(binance);
+        stats.register_created_order(bitmex);
+
+        let report = stats.report(&StatisticsQuery::default());
+        assert_eq!(report.markets.len(), 2);
+
+        let query = StatisticsQuery {
+            exchange_id: Some("Binance".into()),
+            currency_pair: Some(btc_usdt),
+            ..Default::default()
+        };
+        let report = stats.report(&query);
+        assert_eq!(
+            report
+                .markets
+                .iter()
+                .map(|x| x.market_account_id)
+                .collect_vec(),
+            vec![binance]
+        );
+
+        let query = StatisticsQuery {
+            from: Some(time_manager::now() + chrono::Duration::hours(1)),
+            ..Default::default()
+        };
+        assert!(stats.report(&query).markets.is_empty());
+    }
+
+    #[test]
+    fn market_account_id_is_serialized_as_struct_in_report() {
+        let stats = StatisticService::new();
+        let market_account_id = MarketAccountId::new(
+            ExchangeAccountId::new("Binance".into(), 0),
+            CurrencyPair::from_codes("cnd".into(), "btc".into()),
+        );
+        stats.register_created_order(market_account_id);
+
+        let report =
+            serde_json::to_value(stats.report(&StatisticsQuery::default())).expect("in test");
+
+        assert_eq!(
+            report["markets"][0]["market_account_id"],
+            serde_json::json!({
+                "exchange_account_id": "Binance_0",
+                "currency_pair": "cnd/btc",
+            })
+        );
+        assert_eq!(report["markets"][0]["opened_orders_count"], 1);
+        // Compat field for WebUI
+        assert_eq!(
+            report["market_account_id_stats"]["Binance_0|cnd/btc"]["opened_orders_count"],
+            1
+        );
+    }
+}
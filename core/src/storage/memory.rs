@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use itertools::Itertools;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use serde_json::Value;
+
+use super::{LogQuery, LogRecord, Storage};
+
+/// Non-persistent storage, e.g. for tests and backtests
+#[derive(Default)]
+pub struct MemoryStorage {
+    values: Mutex<HashMap<(String, String), Value>>,
+    logs: Mutex<HashMap<String, Vec<LogRecord>>>,
+    last_sequence: Mutex<i64>,
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn put(&self, namespace: &str, key: &str, value: Value) -> Result<()> {
+        let _ = self
+            .values
+            .lock()
+            .insert((namespace.to_owned(), key.to_owned()), value);
+
+        Ok(())
+    }
+
+    async fn get(&self, namespace: &str, key: &str) -> Result<Option<Value>> {
+        Ok(self
+            .values
+            .lock()
+            .get(&(namespace.to_owned(), key.to_owned()))
+            .cloned())
+    }
+
+    async fn keys(&self, namespace: &str) -> Result<Vec<String>> {
+        Ok(self
+            .values
+            .lock()
+            .keys()
+            .filter(|(key_namespace, _)| key_namespace == namespace)
+            .map(|(_, key)| key.clone())
+            .sorted()
+            .collect_vec())
+    }
+
+    async fn append(&self, log: &str, time: DateTime, value: Value) -> Result<()> {
+        let mut last_sequence = self.last_sequence.lock();
+        *last_sequence += 1;
+
+        self.logs
+            .lock()
+            .entry(log.to_owned())
+            .or_default()
+            .push(LogRecord {
+                sequence: *last_sequence,
+                time,
+                value,
+            });
+
+        Ok(())
+    }
+
+    async fn query(&self, log: &str, query: &LogQuery) -> Result<Vec<LogRecord>> {
+        let logs = self.logs.lock();
+        let records = match logs.get(log) {
+            Some(records) => records,
+            None => return Ok(Vec::new()),
+        };
+
+        Ok(records
+            .iter()
+            .filter(|record| query.from_time.map_or(true, |from| record.time >= from))
+            .filter(|record| query.to_time.map_or(true, |to| record.time < to))
+            .filter(|record| query.after_sequence.map_or(true, |x| record.sequence > x))
+            .take(query.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect_vec())
+    }
+}
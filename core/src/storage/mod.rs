@@ -0,0 +1,210 @@
+pub mod memory;
+pub mod order_history;
+pub mod postgres;
+pub mod sqlite;
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
+use mmb_utils::DateTime;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::settings::StorageSettings;
+use memory::MemoryStorage;
+use postgres::PostgresStorage;
+use sqlite::SqliteStorage;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogRecord {
+    /// Position of record in storage, increases with every appended record
+    pub sequence: i64,
+    pub time: DateTime,
+    pub value: Value,
+}
+
+/// Filter of log records. Bounds are `[from_time, to_time)`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogQuery {
+    pub from_time: Option<DateTime>,
+    pub to_time: Option<DateTime>,
+    /// Only records appended after record with this sequence
+    pub after_sequence: Option<i64>,
+    pub limit: Option<usize>,
+}
+
+/// Persistence shared by engine components: key-value part for state and append-only logs for history.
+/// Values are stored as json, so components choose their own data format
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn put(&self, namespace: &str, key: &str, value: Value) -> Result<()>;
+
+    async fn get(&self, namespace: &str, key: &str) -> Result<Option<Value>>;
+
+    /// Keys of namespace in ascending order
+    async fn keys(&self, namespace: &str) -> Result<Vec<String>>;
+
+    async fn append(&self, log: &str, time: DateTime, value: Value) -> Result<()>;
+
+    /// Records of log ordered by sequence
+    async fn query(&self, log: &str, query: &LogQuery) -> Result<Vec<LogRecord>>;
+}
+
+impl dyn Storage {
+    pub async fn put_serialized<T: Serialize>(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &T,
+    ) -> Result<()> {
+        let value = serde_json::to_value(value)
+            .with_context(|| format!("Unable to serialize {}/{}", namespace, key))?;
+        self.put(namespace, key, value).await
+    }
+
+    pub async fn get_deserialized<T: DeserializeOwned>(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> Result<Option<T>> {
+        self.get(namespace, key)
+            .await?
+            .map(|value| {
+                serde_json::from_value(value)
+                    .with_context(|| format!("Unable to deserialize {}/{}", namespace, key))
+            })
+            .transpose()
+    }
+}
+
+/// Storage is kept in memory if it isn't specified in settings
+pub async fn create_storage(settings: Option<&StorageSettings>) -> Result<Arc<dyn Storage>> {
+    let storage: Arc<dyn Storage> = match settings {
+        None | Some(StorageSettings::Memory) => Arc::new(MemoryStorage::default()),
+        Some(StorageSettings::Sqlite { path }) => Arc::new(SqliteStorage::open(path)?),
+        Some(StorageSettings::Postgres { connection_string }) => {
+            Arc::new(PostgresStorage::connect(connection_string).await?)
+        }
+    };
+
+    Ok(storage)
+}
+
+fn time_to_millis(time: DateTime) -> i64 {
+    time.timestamp_millis()
+}
+
+fn millis_to_time(millis: i64) -> DateTime {
+    Utc.timestamp_millis(millis)
+}
+
+/// Time bounds, sequence bound and limit of query in form which is suitable for SQL parameters
+fn sql_query_bounds(query: &LogQuery) -> (i64, i64, i64, i64) {
+    (
+        query.from_time.map_or(i64::MIN, time_to_millis),
+        query.to_time.map_or(i64::MAX, time_to_millis),
+        query.after_sequence.unwrap_or(i64::MIN),
+        query.limit.map_or(i64::MAX, |limit| limit as i64),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use serde_json::json;
+
+    use super::*;
+
+    async fn check_storage(storage: Arc<dyn Storage>) {
+        assert_eq!(storage.get("state", "key").await.expect("in test"), None);
+
+        storage
+            .put("state", "key", json!({"value": 1}))
+            .await
+            .expect("in test");
+        storage
+            .put_serialized("state", "key", &json!({"value": 2}))
+            .await
+            .expect("in test");
+        assert_eq!(
+            storage.get("state", "key").await.expect("in test"),
+            Some(json!({"value": 2}))
+        );
+        assert_eq!(storage.get("other", "key").await.expect("in test"), None);
+
+        storage
+            .put("state", "another_key", json!({"value": 3}))
+            .await
+            .expect("in test");
+        assert_eq!(
+            storage.keys("state").await.expect("in test"),
+            vec!["another_key", "key"]
+        );
+        assert!(storage.keys("other").await.expect("in test").is_empty());
+
+        let start = Utc.ymd(2022, 3, 1).and_hms(12, 0, 0);
+        for i in 0..3 {
+            storage
+                .append("log", start + Duration::seconds(i), json!(i))
+                .await
+                .expect("in test");
+        }
+        storage
+            .append("other_log", start, json!(10))
+            .await
+            .expect("in test");
+
+        let all = storage
+            .query("log", &LogQuery::default())
+            .await
+            .expect("in test");
+        assert_eq!(
+            all.iter().map(|x| x.value.clone()).collect::<Vec<_>>(),
+            vec![json!(0), json!(1), json!(2)]
+        );
+
+        let filtered = storage
+            .query(
+                "log",
+                &LogQuery {
+                    from_time: Some(start + Duration::seconds(1)),
+                    to_time: Some(start + Duration::seconds(3)),
+                    after_sequence: None,
+                    limit: Some(1),
+                },
+            )
+            .await
+            .expect("in test");
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].value, json!(1));
+        assert_eq!(filtered[0].time, start + Duration::seconds(1));
+
+        let newer = storage
+            .query(
+                "log",
+                &LogQuery {
+                    after_sequence: Some(all[1].sequence),
+                    ..LogQuery::default()
+                },
+            )
+            .await
+            .expect("in test");
+        assert_eq!(
+            newer.iter().map(|x| x.value.clone()).collect::<Vec<_>>(),
+            vec![json!(2)]
+        );
+    }
+
+    #[tokio::test]
+    async fn memory_storage() {
+        check_storage(Arc::new(MemoryStorage::default())).await;
+    }
+
+    #[tokio::test]
+    async fn sqlite_storage() {
+        check_storage(Arc::new(SqliteStorage::open_in_memory().expect("in test"))).await;
+    }
+}
@@ -0,0 +1,457 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use dashmap::DashMap;
+use futures::FutureExt;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use super::{LogQuery, Storage};
+use crate::exchanges::common::{CurrencyPair, ExchangeAccountId};
+use crate::exchanges::events::ExchangeEvent;
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_future;
+use crate::misc::time::time_manager;
+use crate::orders::event::OrderEventType;
+use crate::orders::order::{OrderSnapshot, OrderStatus};
+
+/// Log of storage with snapshots of finished orders
+pub const ORDERS_LOG: &str = "orders";
+
+/// Saves finished orders (filled, cancelled or failed to create) to storage
+pub struct OrderHistoryRecorder {
+    storage: Arc<dyn Storage>,
+}
+
+impl OrderHistoryRecorder {
+    pub fn new(
+        events_receiver: broadcast::Receiver<ExchangeEvent>,
+        storage: Arc<dyn Storage>,
+    ) -> Arc<Self> {
+        let recorder = Arc::new(Self { storage });
+
+        let action = recorder.clone().start(events_receiver);
+        spawn_future(
+            "Start order history recorder",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action.boxed(),
+        );
+
+        recorder
+    }
+
+    async fn start(
+        self: Arc<Self>,
+        mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+    ) -> Result<()> {
+        loop {
+            let event = events_receiver
+                .recv()
+                .await
+                .context("Error during receiving event in OrderHistoryRecorder::start()")?;
+
+            let order = match event {
+                ExchangeEvent::OrderEvent(order_event) => match order_event.event_type {
+                    OrderEventType::OrderCompleted { cloned_order } => (*cloned_order).clone(),
+                    OrderEventType::CancelOrderSucceeded | OrderEventType::CreateOrderFailed => {
+                        order_event.order.deep_clone()
+                    }
+                    _ => continue,
+                },
+                _ => continue,
+            };
+
+            if let Err(error) = self.save_order(&order).await {
+                log::error!(
+                    "Unable to save order {} to history: {:?}",
+                    order.header.client_order_id,
+                    error
+                );
+            }
+        }
+    }
+
+    async fn save_order(&self, order: &OrderSnapshot) -> Result<()> {
+        let value = serde_json::to_value(order).context("Unable to serialize order")?;
+        self.storage
+            .append(ORDERS_LOG, time_manager::now(), value)
+            .await
+    }
+}
+
+/// Max count of orders in one page of order blotter
+pub const MAX_ORDERS_PAGE_LIMIT: usize = 500;
+const DEFAULT_ORDERS_PAGE_LIMIT: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderBlotterStatus {
+    /// Orders which aren't finished yet, they are taken from order pools of exchanges
+    Open,
+    Filled,
+    Canceled,
+    Failed,
+}
+
+impl OrderBlotterStatus {
+    fn matches(&self, status: OrderStatus) -> bool {
+        match self {
+            OrderBlotterStatus::Open => !status.is_finished(),
+            OrderBlotterStatus::Filled => status == OrderStatus::Completed,
+            OrderBlotterStatus::Canceled => status == OrderStatus::Canceled,
+            OrderBlotterStatus::Failed => status == OrderStatus::FailedToCreate,
+        }
+    }
+}
+
+/// Filter of order blotter. Orders are filtered by creation time in bounds `[from, to)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrdersQuery {
+    pub status: Option<OrderBlotterStatus>,
+    pub exchange_account_id: Option<ExchangeAccountId>,
+    pub currency_pair: Option<CurrencyPair>,
+    pub from: Option<DateTime>,
+    pub to: Option<DateTime>,
+    /// Only finished orders which are saved to history after this sequence are returned
+    /// together with open orders, so live refresh loads new records of history only
+    pub after_sequence: Option<i64>,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl Default for OrdersQuery {
+    fn default() -> Self {
+        Self {
+            status: None,
+            exchange_account_id: None,
+            currency_pair: None,
+            from: None,
+            to: None,
+            after_sequence: None,
+            offset: 0,
+            limit: DEFAULT_ORDERS_PAGE_LIMIT,
+        }
+    }
+}
+
+impl OrdersQuery {
+    /// Parses url query like `status=open&pair=btc/usdt&from=2022-03-01T00:00:00Z&offset=100&limit=50`
+    pub fn from_url_query(query: &str) -> Result<Self> {
+        let mut result = OrdersQuery::default();
+        for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+            if value.is_empty() {
+                continue;
+            }
+
+            match key.as_ref() {
+                "status" => result.status = Some(parse_status(&value)?),
+                "exchange_account_id" => {
+                    let exchange_account_id = value.parse().map_err(|err| {
+                        anyhow!("Invalid exchange account id {}: {:?}", value, err)
+                    })?;
+                    result.exchange_account_id = Some(exchange_account_id);
+                }
+                "pair" => result.currency_pair = Some(parse_currency_pair(&value)?),
+                "from" => result.from = Some(parse_time(&value)?),
+                "to" => result.to = Some(parse_time(&value)?),
+                "after_sequence" => {
+                    result.after_sequence = Some(value.parse().context("Invalid after_sequence")?)
+                }
+                "offset" => result.offset = value.parse().context("Invalid offset")?,
+                "limit" => result.limit = value.parse().context("Invalid limit")?,
+                _ => bail!("Unknown parameter {}", key),
+            }
+        }
+
+        if result.limit == 0 || result.limit > MAX_ORDERS_PAGE_LIMIT {
+            bail!(
+                "Limit should be in range [1, {}], but it is {}",
+                MAX_ORDERS_PAGE_LIMIT,
+                result.limit
+            );
+        }
+
+        Ok(result)
+    }
+
+    fn matches(&self, order: &OrderSnapshot) -> bool {
+        let header = &order.header;
+        self.status.map_or(true, |x| x.matches(order.status()))
+            && self
+                .exchange_account_id
+                .map_or(true, |x| x == header.exchange_account_id)
+            && self
+                .currency_pair
+                .map_or(true, |x| x == header.currency_pair)
+            && self.from.map_or(true, |x| header.init_time >= x)
+            && self.to.map_or(true, |x| header.init_time < x)
+    }
+}
+
+fn parse_status(value: &str) -> Result<OrderBlotterStatus> {
+    let status = match value {
+        "open" => OrderBlotterStatus::Open,
+        "filled" => OrderBlotterStatus::Filled,
+        "canceled" => OrderBlotterStatus::Canceled,
+        "failed" => OrderBlotterStatus::Failed,
+        _ => bail!(
+            "Unknown status {}, supported statuses: open, filled, canceled, failed",
+            value
+        ),
+    };
+
+    Ok(status)
+}
+
+pub(crate) fn parse_currency_pair(value: &str) -> Result<CurrencyPair> {
+    let (base, quote) = value
+        .split_once('/')
+        .with_context(|| format!("Currency pair {} should be in format BASE/QUOTE", value))?;
+
+    Ok(CurrencyPair::from_codes(base.into(), quote.into()))
+}
+
+fn parse_time(value: &str) -> Result<DateTime> {
+    Ok(chrono::DateTime::parse_from_rfc3339(value)
+        .with_context(|| format!("Unable to parse time {}", value))?
+        .into())
+}
+
+/// Page of order blotter. Orders are sorted from the newest to the oldest by creation time
+#[derive(Debug, Clone, Serialize)]
+pub struct OrdersPage {
+    pub orders: Vec<OrderSnapshot>,
+    /// Count of orders which match filter on all pages
+    pub total: usize,
+    pub offset: usize,
+    pub next_offset: Option<usize>,
+    /// Sequence of the latest record of order history for `after_sequence` of live refresh
+    pub last_sequence: Option<i64>,
+}
+
+/// Open orders of exchanges together with finished orders from history
+pub async fn query_orders(
+    storage: &dyn Storage,
+    exchanges: &DashMap<ExchangeAccountId, Arc<Exchange>>,
+    query: &OrdersQuery,
+) -> Result<OrdersPage> {
+    let mut orders = Vec::new();
+    let mut last_sequence = query.after_sequence;
+
+    if query.status != Some(OrderBlotterStatus::Open) {
+        // Orders are finished after creation, so records before `from` can't match
+        let log_query = LogQuery {
+            from_time: query.from,
+            after_sequence: query.after_sequence,
+            ..Default::default()
+        };
+        for record in storage.query(ORDERS_LOG, &log_query).await? {
+            last_sequence = Some(record.sequence);
+
+            let order: OrderSnapshot = serde_json::from_value(record.value)
+                .context("Unable to deserialize order from history")?;
+            if query.matches(&order) {
+                orders.push(order);
+            }
+        }
+    }
+
+    let is_open_requested = matches!(query.status, None | Some(OrderBlotterStatus::Open));
+    if is_open_requested {
+        // Order can be saved to history right before it's removed from pool
+        let finished_ids = orders
+            .iter()
+            .map(|x| x.header.client_order_id.clone())
+            .collect::<HashSet<_>>();
+        for exchange in exchanges.iter() {
+            for order in exchange.orders.not_finished.iter() {
+                let order = order.deep_clone();
+                if !finished_ids.contains(&order.header.client_order_id) && query.matches(&order) {
+                    orders.push(order);
+                }
+            }
+        }
+    }
+
+    orders.sort_by(|a, b| b.header.init_time.cmp(&a.header.init_time));
+
+    let total = orders.len();
+    let next_offset = query.offset + query.limit;
+    let orders = orders
+        .into_iter()
+        .skip(query.offset)
+        .take(query.limit)
+        .collect();
+
+    Ok(OrdersPage {
+        orders,
+        total,
+        offset: query.offset,
+        next_offset: (next_offset < total).then(|| next_offset),
+        last_sequence,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::exchanges::general::test_helper::get_test_exchange;
+    use crate::orders::order::ClientOrderId;
+    use crate::storage::memory::MemoryStorage;
+    use crate::test_util::OrderSnapshotBuilder;
+
+    fn client_order_ids(page: &OrdersPage) -> HashSet<ClientOrderId> {
+        page.orders
+            .iter()
+            .map(|order| order.header.client_order_id.clone())
+            .collect()
+    }
+
+    fn exchanges_with(exchange: Arc<Exchange>) -> DashMap<ExchangeAccountId, Arc<Exchange>> {
+        let exchanges = DashMap::new();
+        let _ = exchanges.insert(exchange.exchange_account_id, exchange);
+        exchanges
+    }
+
+    #[test]
+    fn orders_query_from_url_query() {
+        let query = OrdersQuery::from_url_query(concat!(
+            "status=filled&exchange_account_id=Binance_0&pair=btc/usdt",
+            "&from=2022-03-01T00:00:00Z&after_sequence=5&offset=100&limit=50"
+        ))
+        .expect("in test");
+        assert_eq!(
+            query,
+            OrdersQuery {
+                status: Some(OrderBlotterStatus::Filled),
+                exchange_account_id: Some("Binance_0".parse().expect("in test")),
+                currency_pair: Some(CurrencyPair::from_codes("btc".into(), "usdt".into())),
+                from: Some(Utc.ymd(2022, 3, 1).and_hms(0, 0, 0)),
+                to: None,
+                after_sequence: Some(5),
+                offset: 100,
+                limit: 50,
+            }
+        );
+
+        assert_eq!(
+            OrdersQuery::from_url_query("status=&limit=").expect("in test"),
+            OrdersQuery::default()
+        );
+        assert!(OrdersQuery::from_url_query("status=pending").is_err());
+        assert!(OrdersQuery::from_url_query("pair=btcusdt").is_err());
+        assert!(OrdersQuery::from_url_query("unknown=1").is_err());
+        assert!(OrdersQuery::from_url_query("limit=0").is_err());
+        assert!(
+            OrdersQuery::from_url_query(&format!("limit={}", MAX_ORDERS_PAGE_LIMIT + 1)).is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn open_orders_are_returned_on_live_refresh() {
+        let (exchange, _events_receiver) = get_test_exchange(false);
+        let exchange_account_id = exchange.exchange_account_id;
+        let currency_pair = CurrencyPair::from_codes("phb".into(), "btc".into());
+        let exchanges = exchanges_with(exchange.clone());
+
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::default());
+        let recorder = OrderHistoryRecorder {
+            storage: storage.clone(),
+        };
+        let filled_order = OrderSnapshotBuilder::new(exchange_account_id, currency_pair)
+            .status(OrderStatus::Completed, Utc::now())
+            .build();
+        recorder.save_order(&filled_order).await.expect("in test");
+        let open_order = OrderSnapshotBuilder::new(exchange_account_id, currency_pair)
+            .build_ref(&exchange.orders);
+
+        let page = query_orders(storage.as_ref(), &exchanges, &OrdersQuery::default())
+            .await
+            .expect("in test");
+        assert_eq!(page.total, 2);
+        assert_eq!(
+            client_order_ids(&page),
+            HashSet::from([
+                filled_order.header.client_order_id.clone(),
+                open_order.client_order_id(),
+            ])
+        );
+
+        let filled_query = OrdersQuery {
+            status: Some(OrderBlotterStatus::Filled),
+            ..OrdersQuery::default()
+        };
+        let filled_page = query_orders(storage.as_ref(), &exchanges, &filled_query)
+            .await
+            .expect("in test");
+        assert_eq!(
+            client_order_ids(&filled_page),
+            HashSet::from([filled_order.header.client_order_id.clone()])
+        );
+
+        let refresh_query = OrdersQuery {
+            after_sequence: page.last_sequence,
+            ..OrdersQuery::default()
+        };
+        let refreshed_page = query_orders(storage.as_ref(), &exchanges, &refresh_query)
+            .await
+            .expect("in test");
+        assert_eq!(
+            client_order_ids(&refreshed_page),
+            HashSet::from([open_order.client_order_id()])
+        );
+        assert_eq!(refreshed_page.last_sequence, page.last_sequence);
+
+        let canceled_order = OrderSnapshotBuilder::new(exchange_account_id, currency_pair)
+            .status(OrderStatus::Canceled, Utc::now())
+            .build();
+        recorder.save_order(&canceled_order).await.expect("in test");
+
+        let refreshed_page = query_orders(storage.as_ref(), &exchanges, &refresh_query)
+            .await
+            .expect("in test");
+        assert_eq!(
+            client_order_ids(&refreshed_page),
+            HashSet::from([
+                canceled_order.header.client_order_id.clone(),
+                open_order.client_order_id(),
+            ])
+        );
+        assert!(refreshed_page.last_sequence > page.last_sequence);
+    }
+
+    #[tokio::test]
+    async fn orders_are_paginated() {
+        let (exchange, _events_receiver) = get_test_exchange(false);
+        let currency_pair = CurrencyPair::from_codes("phb".into(), "btc".into());
+        for _ in 0..3 {
+            let _ = OrderSnapshotBuilder::new(exchange.exchange_account_id, currency_pair)
+                .build_ref(&exchange.orders);
+        }
+        let exchanges = exchanges_with(exchange);
+        let storage = MemoryStorage::default();
+
+        let query = OrdersQuery {
+            limit: 2,
+            ..OrdersQuery::default()
+        };
+        let first_page = query_orders(&storage, &exchanges, &query)
+            .await
+            .expect("in test");
+        assert_eq!(first_page.orders.len(), 2);
+        assert_eq!(first_page.total, 3);
+        assert_eq!(first_page.next_offset, Some(2));
+        assert_eq!(first_page.last_sequence, None);
+
+        let query = OrdersQuery { offset: 2, ..query };
+        let last_page = query_orders(&storage, &exchanges, &query)
+            .await
+            .expect("in test");
+        assert_eq!(last_page.orders.len(), 1);
+        assert_eq!(last_page.next_offset, None);
+    }
+}
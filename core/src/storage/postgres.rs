@@ -0,0 +1,137 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::FutureExt;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use serde_json::Value;
+use tokio_postgres::{Client, NoTls};
+
+use super::{millis_to_time, sql_query_bounds, time_to_millis, LogQuery, LogRecord, Storage};
+use crate::infrastructure::spawn_future;
+
+const CREATE_TABLES: &str = "
+CREATE TABLE IF NOT EXISTS key_values (
+    namespace TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (namespace, key)
+);
+CREATE TABLE IF NOT EXISTS log_records (
+    sequence BIGSERIAL PRIMARY KEY,
+    log TEXT NOT NULL,
+    time BIGINT NOT NULL,
+    value TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS log_records_by_time ON log_records (log, time);
+";
+
+/// Storage in postgres database, e.g. shared by several engines
+pub struct PostgresStorage {
+    client: Client,
+}
+
+impl PostgresStorage {
+    pub async fn connect(connection_string: &str) -> Result<Self> {
+        let (client, connection) = tokio_postgres::connect(connection_string, NoTls)
+            .await
+            .context("Unable to connect to postgres storage")?;
+
+        let action = async move {
+            connection
+                .await
+                .context("Postgres storage connection failed")
+        };
+        let _ = spawn_future(
+            "Postgres storage connection",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action.boxed(),
+        );
+
+        client
+            .batch_execute(CREATE_TABLES)
+            .await
+            .context("Unable to create postgres storage tables")?;
+
+        Ok(Self { client })
+    }
+}
+
+#[async_trait]
+impl Storage for PostgresStorage {
+    async fn put(&self, namespace: &str, key: &str, value: Value) -> Result<()> {
+        let _ = self
+            .client
+            .execute(
+                "INSERT INTO key_values (namespace, key, value) VALUES ($1, $2, $3)
+                ON CONFLICT (namespace, key) DO UPDATE SET value = EXCLUDED.value",
+                &[&namespace, &key, &value.to_string()],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get(&self, namespace: &str, key: &str) -> Result<Option<Value>> {
+        let row = self
+            .client
+            .query_opt(
+                "SELECT value FROM key_values WHERE namespace = $1 AND key = $2",
+                &[&namespace, &key],
+            )
+            .await?;
+
+        row.map(|row| {
+            let value: String = row.get(0);
+            serde_json::from_str(&value).context("Unable to parse stored value")
+        })
+        .transpose()
+    }
+
+    async fn keys(&self, namespace: &str) -> Result<Vec<String>> {
+        let rows = self
+            .client
+            .query(
+                "SELECT key FROM key_values WHERE namespace = $1 ORDER BY key",
+                &[&namespace],
+            )
+            .await?;
+
+        Ok(rows.into_iter().map(|row| row.get(0)).collect())
+    }
+
+    async fn append(&self, log: &str, time: DateTime, value: Value) -> Result<()> {
+        let _ = self
+            .client
+            .execute(
+                "INSERT INTO log_records (log, time, value) VALUES ($1, $2, $3)",
+                &[&log, &time_to_millis(time), &value.to_string()],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn query(&self, log: &str, query: &LogQuery) -> Result<Vec<LogRecord>> {
+        let (from, to, after_sequence, limit) = sql_query_bounds(query);
+        let rows = self
+            .client
+            .query(
+                "SELECT sequence, time, value FROM log_records
+                WHERE log = $1 AND time >= $2 AND time < $3 AND sequence > $4
+                ORDER BY sequence LIMIT $5",
+                &[&log, &from, &to, &after_sequence, &limit],
+            )
+            .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let value: String = row.get(2);
+                Ok(LogRecord {
+                    sequence: row.get(0),
+                    time: millis_to_time(row.get(1)),
+                    value: serde_json::from_str(&value).context("Unable to parse log record")?,
+                })
+            })
+            .collect()
+    }
+}
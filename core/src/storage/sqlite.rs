@@ -0,0 +1,155 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+
+use super::{millis_to_time, sql_query_bounds, time_to_millis, LogQuery, LogRecord, Storage};
+
+const CREATE_TABLES: &str = "
+CREATE TABLE IF NOT EXISTS key_values (
+    namespace TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (namespace, key)
+);
+CREATE TABLE IF NOT EXISTS log_records (
+    sequence INTEGER PRIMARY KEY AUTOINCREMENT,
+    log TEXT NOT NULL,
+    time INTEGER NOT NULL,
+    value TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS log_records_by_time ON log_records (log, time);
+";
+
+/// Storage in local sqlite database file
+pub struct SqliteStorage {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStorage {
+    pub fn open(path: &str) -> Result<Self> {
+        let connection = Connection::open(path)
+            .with_context(|| format!("Unable to open sqlite database {}", path))?;
+
+        Self::new(connection)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::new(Connection::open_in_memory()?)
+    }
+
+    fn new(connection: Connection) -> Result<Self> {
+        connection
+            .execute_batch(CREATE_TABLES)
+            .context("Unable to create sqlite storage tables")?;
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    /// Sqlite API is blocking, so it's called outside of async runtime threads
+    async fn execute<T: Send + 'static>(
+        &self,
+        action: impl FnOnce(&Connection) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let connection = self.connection.clone();
+        tokio::task::spawn_blocking(move || action(&connection.lock()))
+            .await
+            .context("Sqlite storage task failed")?
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn put(&self, namespace: &str, key: &str, value: Value) -> Result<()> {
+        let (namespace, key) = (namespace.to_owned(), key.to_owned());
+        self.execute(move |connection| {
+            let _ = connection.execute(
+                "INSERT INTO key_values (namespace, key, value) VALUES (?1, ?2, ?3)
+                ON CONFLICT (namespace, key) DO UPDATE SET value = excluded.value",
+                params![namespace, key, value.to_string()],
+            )?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get(&self, namespace: &str, key: &str) -> Result<Option<Value>> {
+        let (namespace, key) = (namespace.to_owned(), key.to_owned());
+        self.execute(move |connection| {
+            let value: Option<String> = connection
+                .query_row(
+                    "SELECT value FROM key_values WHERE namespace = ?1 AND key = ?2",
+                    params![namespace, key],
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            value
+                .map(|value| serde_json::from_str(&value).context("Unable to parse stored value"))
+                .transpose()
+        })
+        .await
+    }
+
+    async fn keys(&self, namespace: &str) -> Result<Vec<String>> {
+        let namespace = namespace.to_owned();
+        self.execute(move |connection| {
+            let mut statement = connection
+                .prepare("SELECT key FROM key_values WHERE namespace = ?1 ORDER BY key")?;
+            let keys = statement
+                .query_map(params![namespace], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+
+            Ok(keys)
+        })
+        .await
+    }
+
+    async fn append(&self, log: &str, time: DateTime, value: Value) -> Result<()> {
+        let log = log.to_owned();
+        self.execute(move |connection| {
+            let _ = connection.execute(
+                "INSERT INTO log_records (log, time, value) VALUES (?1, ?2, ?3)",
+                params![log, time_to_millis(time), value.to_string()],
+            )?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn query(&self, log: &str, query: &LogQuery) -> Result<Vec<LogRecord>> {
+        let log = log.to_owned();
+        let (from, to, after_sequence, limit) = sql_query_bounds(query);
+        self.execute(move |connection| {
+            let mut statement = connection.prepare(
+                "SELECT sequence, time, value FROM log_records
+                WHERE log = ?1 AND time >= ?2 AND time < ?3 AND sequence > ?4
+                ORDER BY sequence LIMIT ?5",
+            )?;
+
+            let rows = statement
+                .query_map(params![log, from, to, after_sequence, limit], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get::<_, String>(2)?))
+                })?;
+
+            rows.map(|row| {
+                let (sequence, time, value) = row?;
+                Ok(LogRecord {
+                    sequence,
+                    time: millis_to_time(time),
+                    value: serde_json::from_str(&value).context("Unable to parse log record")?,
+                })
+            })
+            .collect()
+        })
+        .await
+    }
+}
@@ -0,0 +1,156 @@
+use anyhow::{ensure, Result};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::balance_manager::balance_manager::BalanceManager;
+use crate::disposition_execution::SmallOrder;
+use crate::exchanges::common::{Amount, MarketAccountId, Price};
+use crate::orders::order::OrderSide;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+pub struct InventorySkewSettings {
+    /// Desired position in amount currency, positive for long
+    pub target_inventory: Amount,
+    /// Deviation from target inventory when skew reaches its maximum
+    pub max_deviation: Amount,
+    /// Max shift of prices as part of price, e.g. 0.001 shifts prices by 0.1%
+    pub max_price_skew: Decimal,
+}
+
+impl InventorySkewSettings {
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            self.max_deviation > dec!(0),
+            "max deviation {} of inventory skew should be positive",
+            self.max_deviation
+        );
+        ensure!(
+            self.max_price_skew >= dec!(0) && self.max_price_skew < dec!(1),
+            "max price skew {} of inventory skew should be in range [0, 1)",
+            self.max_price_skew
+        );
+
+        Ok(())
+    }
+}
+
+/// Shifts quotes of market-making strategies to return position to target inventory.
+/// If position exceeds target, both prices are lowered to sell more eagerly and size of buy orders
+/// is reduced; size of orders which increase deviation reaches zero at `max_deviation`
+#[derive(Debug, Clone)]
+pub struct InventorySkew {
+    settings: InventorySkewSettings,
+}
+
+impl InventorySkew {
+    pub fn new(settings: InventorySkewSettings) -> Result<Self> {
+        settings.validate()?;
+
+        Ok(Self { settings })
+    }
+
+    /// Current position in amount currency, positive for long
+    pub fn current_position(
+        balance_manager: &BalanceManager,
+        market_account_id: MarketAccountId,
+    ) -> Amount {
+        balance_manager.get_position(
+            market_account_id.exchange_account_id,
+            market_account_id.currency_pair,
+            OrderSide::Buy,
+        )
+    }
+
+    /// Deviation of position from target inventory normalized to range [-1, 1]
+    pub fn inventory_ratio(&self, position: Amount) -> Decimal {
+        let ratio = (position - self.settings.target_inventory) / self.settings.max_deviation;
+        ratio.max(dec!(-1)).min(dec!(1))
+    }
+
+    pub fn price_offset(&self, price: Price, position: Amount) -> Price {
+        -self.inventory_ratio(position) * self.settings.max_price_skew * price
+    }
+
+    pub fn size_multiplier(&self, side: OrderSide, position: Amount) -> Decimal {
+        let ratio = self.inventory_ratio(position);
+        match side {
+            OrderSide::Buy => dec!(1) - ratio.max(dec!(0)),
+            OrderSide::Sell => dec!(1) + ratio.min(dec!(0)),
+        }
+    }
+
+    /// Adjusted level of disposition strategy. Result should be rounded by symbol before order creation
+    pub fn apply(&self, side: OrderSide, order: SmallOrder, position: Amount) -> SmallOrder {
+        SmallOrder::new(
+            order.price + self.price_offset(order.price, position),
+            order.amount * self.size_multiplier(side, position),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inventory_skew() -> InventorySkew {
+        InventorySkew::new(InventorySkewSettings {
+            target_inventory: dec!(1),
+            max_deviation: dec!(2),
+            max_price_skew: dec!(0.01),
+        })
+        .expect("in test")
+    }
+
+    #[test]
+    fn non_positive_max_deviation_is_rejected() {
+        let settings = InventorySkewSettings {
+            target_inventory: dec!(1),
+            max_deviation: dec!(0),
+            max_price_skew: dec!(0.01),
+        };
+
+        let error = InventorySkew::new(settings).expect_err("in test");
+        assert_eq!(
+            error.to_string(),
+            "max deviation 0 of inventory skew should be positive"
+        );
+    }
+
+    #[test]
+    fn no_skew_at_target_inventory() {
+        let order = SmallOrder::new(dec!(100), dec!(1));
+        let inventory_skew = inventory_skew();
+
+        assert_eq!(inventory_skew.apply(OrderSide::Buy, order, dec!(1)), order);
+        assert_eq!(inventory_skew.apply(OrderSide::Sell, order, dec!(1)), order);
+    }
+
+    #[test]
+    fn long_position_lowers_prices_and_buy_size() {
+        let order = SmallOrder::new(dec!(100), dec!(1));
+        let inventory_skew = inventory_skew();
+
+        assert_eq!(
+            inventory_skew.apply(OrderSide::Buy, order, dec!(2)),
+            SmallOrder::new(dec!(99.5), dec!(0.5))
+        );
+        assert_eq!(
+            inventory_skew.apply(OrderSide::Sell, order, dec!(2)),
+            SmallOrder::new(dec!(99.5), dec!(1))
+        );
+    }
+
+    #[test]
+    fn skew_is_limited_by_max_deviation() {
+        let order = SmallOrder::new(dec!(100), dec!(1));
+        let inventory_skew = inventory_skew();
+
+        assert_eq!(inventory_skew.inventory_ratio(dec!(-10)), dec!(-1));
+        assert_eq!(
+            inventory_skew.apply(OrderSide::Sell, order, dec!(-10)),
+            SmallOrder::new(dec!(101), dec!(0))
+        );
+    }
+}
@@ -1 +1,4 @@
 pub mod disposition_strategy;
+pub mod inventory_skew;
+pub mod shadow;
+pub mod walk_forward;
@@ -0,0 +1,544 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use anyhow::Result;
+use enum_map::EnumMap;
+use futures::FutureExt;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+
+use crate::disposition_execution::{TradeCycle, TradingContext};
+use crate::exchanges::common::{Amount, MarketAccountId, Price};
+use crate::exchanges::events::{ExchangeEvent, TradesEvent};
+use crate::exchanges::lag_aware_receiver::{ExchangeEventsReceiver, ReceivedEvent};
+use crate::exchanges::simulation::execution_models::ExecutionModelsSettings;
+use crate::exchanges::simulation::matching::{MatchingSimulator, SimulatedFill, SimulatedOrder};
+use crate::explanation::Explanation;
+use crate::infrastructure::spawn_future;
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::misc::time::time_manager;
+use crate::order_book::event::{EventType, OrderBookEvent};
+use crate::order_book::local_snapshot_service::LocalSnapshotsService;
+use crate::orders::event::{OrderEvent, OrderEventType};
+use crate::orders::order::{ClientOrderId, OrderExecutionType, OrderSide};
+use crate::strategies::disposition_strategy::DispositionStrategy;
+
+/// Max count of the latest shadow orders which are kept for report
+const RECENT_ORDERS_LIMIT: usize = 100;
+
+/// Orders and fills of strategy with position and cash flow in quote currency
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct StrategyScore {
+    pub orders_count: u64,
+    pub fills_count: u64,
+    pub filled_amount: Amount,
+    /// Signed amount of base currency bought minus sold
+    pub position: Amount,
+    /// Quote currency received minus paid
+    pub cash: Amount,
+}
+
+impl StrategyScore {
+    pub fn add_order(&mut self) {
+        self.orders_count += 1;
+    }
+
+    pub fn add_fill(&mut self, side: OrderSide, price: Price, amount: Amount) {
+        self.fills_count += 1;
+        self.filled_amount += amount;
+        match side {
+            OrderSide::Buy => {
+                self.position += amount;
+                self.cash -= price * amount;
+            }
+            OrderSide::Sell => {
+                self.position -= amount;
+                self.cash += price * amount;
+            }
+        }
+    }
+
+    /// Profit if position is closed at `mark_price`
+    pub fn pnl(&self, mark_price: Price) -> Amount {
+        self.cash + self.position * mark_price
+    }
+}
+
+/// Order which shadow strategy would have sent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ShadowOrderRecord {
+    pub time: DateTime,
+    pub client_order_id: ClientOrderId,
+    pub side: OrderSide,
+    pub price: Price,
+    pub amount: Amount,
+    /// The best price of live orders on the same side at the moment of shadow order
+    pub live_price: Option<Price>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ShadowReport {
+    pub name: String,
+    /// Score by hypothetical fills of simulator
+    pub shadow: StrategyScore,
+    /// Score by real fills of live orders on the same market
+    pub live: StrategyScore,
+    /// Middle price of simulated order book which PnL is calculated by
+    pub mark_price: Option<Price>,
+    pub shadow_pnl: Option<Amount>,
+    pub live_pnl: Option<Amount>,
+    pub recent_orders: VecDeque<ShadowOrderRecord>,
+    /// Events which weren't received by shadow strategy, so scores can be incomplete
+    pub dropped_events_count: u64,
+}
+
+#[derive(Debug, Clone)]
+struct ShadowOrder {
+    client_order_id: ClientOrderId,
+    price: Price,
+    amount: Amount,
+}
+
+/// Candidate strategy which receives the same events as live one but whose orders are only
+/// placed in `MatchingSimulator`. Live orders of market are tracked for comparison
+pub struct ShadowStrategy {
+    market_account_id: MarketAccountId,
+    strategy: Box<dyn DispositionStrategy>,
+    local_snapshots_service: LocalSnapshotsService,
+    simulator: MatchingSimulator,
+    orders: EnumMap<OrderSide, Option<ShadowOrder>>,
+    live_orders: HashMap<ClientOrderId, (OrderSide, Price)>,
+    report: ShadowReport,
+}
+
+impl ShadowStrategy {
+    pub fn new(
+        name: impl Into<String>,
+        market_account_id: MarketAccountId,
+        strategy: Box<dyn DispositionStrategy>,
+        simulator: MatchingSimulator,
+    ) -> Self {
+        Self {
+            market_account_id,
+            strategy,
+            local_snapshots_service: LocalSnapshotsService::default(),
+            simulator,
+            orders: EnumMap::default(),
+            live_orders: HashMap::new(),
+            report: ShadowReport {
+                name: name.into(),
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &ExchangeEvent, now: DateTime) {
+        match event {
+            ExchangeEvent::OrderBookEvent(event) => self.handle_order_book_event(event, now),
+            ExchangeEvent::Trades(event) => self.handle_trades_event(event),
+            ExchangeEvent::OrderEvent(event) => self.handle_live_order_event(event),
+            _ => {}
+        }
+    }
+
+    pub fn on_events_dropped(&mut self, count: u64) {
+        self.report.dropped_events_count += count;
+    }
+
+    pub fn report(&self) -> ShadowReport {
+        let mut report = self.report.clone();
+        report.mark_price = self.get_mark_price();
+        if let Some(mark_price) = report.mark_price {
+            report.shadow_pnl = Some(report.shadow.pnl(mark_price));
+            report.live_pnl = Some(report.live.pnl(mark_price));
+        }
+
+        report
+    }
+
+    fn handle_order_book_event(&mut self, event: &OrderBookEvent, now: DateTime) {
+        if event.market_account_id() != self.market_account_id {
+            return;
+        }
+
+        let fills = match event.event_type {
+            EventType::Snapshot => self
+                .simulator
+                .on_order_book_snapshot(event.data.as_ref().clone(), event.creation_time),
+            EventType::Update => self
+                .simulator
+                .on_order_book_update(&event.data, event.creation_time),
+        };
+        self.add_simulated_fills(fills);
+
+        if self.local_snapshots_service.update(event.clone()).is_some() {
+            self.requote(now);
+        }
+    }
+
+    fn handle_trades_event(&mut self, event: &TradesEvent) {
+        let market_account_id =
+            MarketAccountId::new(event.exchange_account_id, event.currency_pair);
+        if market_account_id != self.market_account_id {
+            return;
+        }
+
+        for trade in &event.trades {
+            let fills = self.simulator.on_trade(trade);
+            self.add_simulated_fills(fills);
+        }
+    }
+
+    fn handle_live_order_event(&mut self, event: &OrderEvent) {
+        let order = &event.order;
+        if order.market_account_id() != self.market_account_id || order.is_external_order() {
+            return;
+        }
+
+        match &event.event_type {
+            OrderEventType::CreateOrderSucceeded => {
+                self.report.live.add_order();
+                let _ = self
+                    .live_orders
+                    .insert(order.client_order_id(), (order.side(), order.price()));
+            }
+            // Event is raised for every fill including the last one
+            OrderEventType::OrderFilled { cloned_order } => {
+                if let Some(fill) = cloned_order.fills.fills.last() {
+                    self.report
+                        .live
+                        .add_fill(order.side(), fill.price(), fill.amount());
+                }
+            }
+            OrderEventType::OrderCompleted { .. } | OrderEventType::CancelOrderSucceeded => {
+                let _ = self.live_orders.remove(&order.client_order_id());
+            }
+            _ => {}
+        }
+    }
+
+    /// Shadow orders follow the first estimated trade cycle of every side like disposition
+    /// executor does: order is replaced if its price or amount is changed
+    fn requote(&mut self, now: DateTime) {
+        let mut explanation = Explanation::default();
+        let trading_context = match self.strategy.calculate_trading_context(
+            now,
+            &self.local_snapshots_service,
+            &mut explanation,
+        ) {
+            Some(trading_context) => trading_context,
+            None => return,
+        };
+
+        for side in [OrderSide::Buy, OrderSide::Sell] {
+            let trade_cycle = get_first_trade_cycle(&trading_context, side);
+            let current = self.orders[side]
+                .as_ref()
+                .map(|order| (order.price, order.amount));
+            let target = trade_cycle.map(|x| (x.disposition.price(), x.disposition.amount()));
+            if current == target && self.is_order_alive(side) {
+                continue;
+            }
+
+            if let Some(order) = self.orders[side].take() {
+                let _ = self.simulator.cancel_order(&order.client_order_id);
+            }
+
+            if let Some((price, amount)) = target {
+                self.submit_order(side, price, amount, now);
+            }
+        }
+    }
+
+    fn submit_order(&mut self, side: OrderSide, price: Price, amount: Amount, now: DateTime) {
+        let client_order_id = ClientOrderId::generate(&self.report.name);
+        self.simulator.submit_order(
+            SimulatedOrder::new(
+                client_order_id.clone(),
+                side,
+                price,
+                amount,
+                OrderExecutionType::None,
+                now,
+            ),
+            now,
+        );
+        self.orders[side] = Some(ShadowOrder {
+            client_order_id: client_order_id.clone(),
+            price,
+            amount,
+        });
+
+        self.report.shadow.add_order();
+        if self.report.recent_orders.len() == RECENT_ORDERS_LIMIT {
+            let _ = self.report.recent_orders.pop_front();
+        }
+        self.report.recent_orders.push_back(ShadowOrderRecord {
+            time: now,
+            client_order_id,
+            side,
+            price,
+            amount,
+            live_price: self.get_best_live_price(side),
+        });
+    }
+
+    /// Order isn't alive if it's completely filled or rejected by simulator
+    fn is_order_alive(&mut self, side: OrderSide) -> bool {
+        for error in self.simulator.take_rejected_orders() {
+            log::warn!(
+                "Shadow order of {} is rejected: {}",
+                self.report.name,
+                error
+            );
+        }
+
+        match &self.orders[side] {
+            Some(order) => self.simulator.is_order_active(&order.client_order_id),
+            None => true,
+        }
+    }
+
+    fn add_simulated_fills(&mut self, fills: Vec<SimulatedFill>) {
+        for fill in fills {
+            let side = [OrderSide::Buy, OrderSide::Sell].into_iter().find(|side| {
+                self.orders[*side]
+                    .as_ref()
+                    .map_or(false, |order| order.client_order_id == fill.client_order_id)
+            });
+
+            // Fills of replaced orders which were pending in simulator are counted too
+            let side = match side.or_else(|| self.find_recent_order_side(&fill.client_order_id)) {
+                Some(side) => side,
+                None => continue,
+            };
+            self.report.shadow.add_fill(side, fill.price, fill.amount);
+        }
+    }
+
+    fn find_recent_order_side(&self, client_order_id: &ClientOrderId) -> Option<OrderSide> {
+        self.report
+            .recent_orders
+            .iter()
+            .rev()
+            .find(|x| &x.client_order_id == client_order_id)
+            .map(|x| x.side)
+    }
+
+    fn get_best_live_price(&self, side: OrderSide) -> Option<Price> {
+        let prices = self
+            .live_orders
+            .values()
+            .filter(|(order_side, _)| *order_side == side)
+            .map(|(_, price)| *price);
+
+        match side {
+            OrderSide::Buy => prices.max(),
+            OrderSide::Sell => prices.min(),
+        }
+    }
+
+    fn get_mark_price(&self) -> Option<Price> {
+        let order_book = self.simulator.order_book();
+        let best_ask = order_book.asks.keys().next()?;
+        let best_bid = order_book.bids.keys().next_back()?;
+        Some((best_ask + best_bid) / dec!(2))
+    }
+}
+
+fn get_first_trade_cycle(trading_context: &TradingContext, side: OrderSide) -> Option<&TradeCycle> {
+    trading_context.by_side[side]
+        .estimating
+        .iter()
+        .find_map(|x| x.value.as_ref())
+}
+
+/// Runs candidate strategy in shadow mode alongside live strategies. Shadow strategy shouldn't
+/// create orders or reserve balance by itself, because its orders exist only in simulator
+pub struct ShadowStrategyService {
+    shadow_strategy: Arc<Mutex<ShadowStrategy>>,
+}
+
+impl ShadowStrategyService {
+    pub fn start(
+        name: &str,
+        engine_context: &EngineContext,
+        market_account_id: MarketAccountId,
+        strategy: Box<dyn DispositionStrategy>,
+        execution_models: &ExecutionModelsSettings,
+    ) -> Arc<Self> {
+        let shadow_strategy = ShadowStrategy::new(
+            name,
+            market_account_id,
+            strategy,
+            MatchingSimulator::from_settings(execution_models),
+        );
+        let service = Arc::new(Self {
+            shadow_strategy: Arc::new(Mutex::new(shadow_strategy)),
+        });
+
+        let events_receiver =
+            engine_context.get_lag_aware_events_receiver(&format!("Shadow strategy {}", name));
+        let action = service.clone().run(events_receiver);
+        let _ = spawn_future(
+            "Start shadow strategy service",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action.boxed(),
+        );
+
+        service
+    }
+
+    pub fn report(&self) -> ShadowReport {
+        self.shadow_strategy.lock().report()
+    }
+
+    async fn run(self: Arc<Self>, mut events_receiver: ExchangeEventsReceiver) -> Result<()> {
+        while let Some(received) = events_receiver.recv().await {
+            let mut shadow_strategy = self.shadow_strategy.lock();
+            match received {
+                ReceivedEvent::Event(event) => {
+                    shadow_strategy.handle_event(&event, time_manager::now())
+                }
+                ReceivedEvent::EventsDropped { count } => shadow_strategy.on_events_dropped(count),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::disposition_execution::{PriceSlot, TradeDisposition, TradingContextBySide};
+    use crate::exchanges::common::{CurrencyPair, ExchangeAccountId};
+    use crate::exchanges::events::{TickDirection, Trade, TradeId};
+    use crate::explanation::WithExplanation;
+    use crate::order_book_data;
+    use crate::orders::order::{OrderRole, OrderSnapshot};
+    use crate::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+    use mmb_utils::cancellation_token::CancellationToken;
+
+    /// Quotes inside of the spread by offset from the best prices
+    pub(crate) struct TestStrategy {
+        pub(crate) market_account_id: MarketAccountId,
+        pub(crate) offset: Price,
+    }
+
+    impl TestStrategy {
+        fn trade_cycle(&self, side: OrderSide, price: Price) -> TradingContextBySide {
+            TradingContextBySide {
+                max_amount: dec!(1),
+                estimating: vec![WithExplanation {
+                    value: Some(TradeCycle {
+                        order_role: OrderRole::Maker,
+                        strategy_name: "test".into(),
+                        disposition: TradeDisposition::new(
+                            self.market_account_id,
+                            side,
+                            price,
+                            dec!(1),
+                        ),
+                    }),
+                    explanation: Explanation::default(),
+                }],
+            }
+        }
+    }
+
+    impl DispositionStrategy for TestStrategy {
+        fn calculate_trading_context(
+            &mut self,
+            _now: DateTime,
+            local_snapshots_service: &LocalSnapshotsService,
+            _explanation: &mut Explanation,
+        ) -> Option<TradingContext> {
+            let snapshot =
+                local_snapshots_service.get_snapshot(self.market_account_id.market_id())?;
+            let (ask, _) = snapshot.get_top_ask()?;
+            let (bid, _) = snapshot.get_top_bid()?;
+
+            Some(TradingContext::new(
+                self.trade_cycle(OrderSide::Buy, bid + self.offset),
+                self.trade_cycle(OrderSide::Sell, ask - self.offset),
+            ))
+        }
+
+        fn handle_order_fill(
+            &self,
+            _cloned_order: &Arc<OrderSnapshot>,
+            _price_slot: &PriceSlot,
+            _target_eai: ExchangeAccountId,
+            _cancellation_token: CancellationToken,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        fn configuration_descriptor(&self) -> ConfigurationDescriptor {
+            ConfigurationDescriptor::new("TestStrategy".into(), "test".into())
+        }
+    }
+
+    #[test]
+    fn shadow_orders_are_filled_by_simulator() {
+        let market_account_id = MarketAccountId::new(
+            ExchangeAccountId::new("Binance".into(), 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        );
+        let mut shadow_strategy = ShadowStrategy::new(
+            "candidate",
+            market_account_id,
+            Box::new(TestStrategy {
+                market_account_id,
+                offset: dec!(1),
+            }),
+            MatchingSimulator::new(),
+        );
+
+        let now = Utc::now();
+        let snapshot = OrderBookEvent::new(
+            now,
+            market_account_id.exchange_account_id,
+            market_account_id.currency_pair,
+            "snapshot".into(),
+            EventType::Snapshot,
+            Arc::new(order_book_data![
+                dec!(110) => dec!(1),
+                ;
+                dec!(100) => dec!(1),
+            ]),
+        );
+        shadow_strategy.handle_event(&ExchangeEvent::OrderBookEvent(snapshot), now);
+
+        // Sell trade at the new best bid goes through shadow buy order at 101
+        let trades = TradesEvent {
+            exchange_account_id: market_account_id.exchange_account_id,
+            currency_pair: market_account_id.currency_pair,
+            trades: vec![Trade {
+                trade_id: TradeId::Number(1),
+                price: dec!(101),
+                quantity: dec!(2),
+                side: OrderSide::Sell,
+                transaction_time: now,
+                tick_direction: TickDirection::None,
+            }],
+            receipt_time: now,
+        };
+        shadow_strategy.handle_event(&ExchangeEvent::Trades(trades), now);
+
+        let report = shadow_strategy.report();
+        assert_eq!(report.shadow.orders_count, 2);
+        assert_eq!(report.shadow.fills_count, 1);
+        assert_eq!(report.shadow.position, dec!(1));
+        assert_eq!(report.shadow.cash, dec!(-101));
+        assert_eq!(report.mark_price, Some(dec!(105)));
+        assert_eq!(report.shadow_pnl, Some(dec!(4)));
+        assert_eq!(report.live, StrategyScore::default());
+    }
+}
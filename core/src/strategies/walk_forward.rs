@@ -0,0 +1,654 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use itertools::Itertools;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::exchanges::common::MarketAccountId;
+use crate::exchanges::events::ExchangeEvent;
+use crate::exchanges::simulation::execution_models::ExecutionModelsSettings;
+use crate::exchanges::simulation::matching::MatchingSimulator;
+use crate::strategies::disposition_strategy::DispositionStrategy;
+use crate::strategies::shadow::ShadowStrategy;
+
+/// Values of strategy parameters by parameter name
+pub type ParameterSet = BTreeMap<String, Decimal>;
+
+/// Result of one backtest run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BacktestReport {
+    /// Objective of optimization, higher is better (e.g. PnL or Sharpe ratio)
+    pub score: Decimal,
+    /// Other statistics of run for comparison table, e.g. filled amount or commission
+    pub metrics: BTreeMap<String, Decimal>,
+}
+
+/// Runs backtest of strategy with parameters on market data of time range
+#[async_trait]
+pub trait BacktestRunner: Send + Sync {
+    async fn run(
+        &self,
+        parameters: &ParameterSet,
+        start: DateTime,
+        end: DateTime,
+    ) -> Result<BacktestReport>;
+}
+
+/// Creates strategy with parameters of backtest run
+pub type BuildStrategy =
+    Box<dyn Fn(&ParameterSet) -> Result<Box<dyn DispositionStrategy>> + Send + Sync>;
+
+/// Backtest of strategy on recorded market events of one market, e.g. events received from
+/// exchange which replays recorded traffic. Orders of strategy are matched by `MatchingSimulator`
+/// like orders of shadow strategy. Score of run is PnL by middle price at the end of time range
+pub struct SimulatedBacktestRunner {
+    market_account_id: MarketAccountId,
+    events: Vec<ExchangeEvent>,
+    execution_models: ExecutionModelsSettings,
+    build_strategy: BuildStrategy,
+}
+
+impl SimulatedBacktestRunner {
+    pub fn new(
+        market_account_id: MarketAccountId,
+        events: Vec<ExchangeEvent>,
+        execution_models: ExecutionModelsSettings,
+        build_strategy: BuildStrategy,
+    ) -> Self {
+        Self {
+            market_account_id,
+            events,
+            execution_models,
+            build_strategy,
+        }
+    }
+
+    fn run_simulation(
+        &self,
+        parameters: &ParameterSet,
+        start: DateTime,
+        end: DateTime,
+    ) -> Result<BacktestReport> {
+        let strategy = (self.build_strategy)(parameters)?;
+        let mut shadow_strategy = ShadowStrategy::new(
+            "backtest",
+            self.market_account_id,
+            strategy,
+            MatchingSimulator::from_settings(&self.execution_models),
+        );
+
+        for event in &self.events {
+            // Events of other types don't affect simulation
+            let time = match get_market_event_time(event) {
+                Some(time) => time,
+                None => continue,
+            };
+            if time >= start && time < end {
+                shadow_strategy.handle_event(event, time);
+            }
+        }
+
+        let report = shadow_strategy.report();
+        let score = report
+            .shadow_pnl
+            .with_context(|| format!("There is no order book between {start} and {end}"))?;
+        let metrics = BTreeMap::from([
+            ("orders_count".to_owned(), report.shadow.orders_count.into()),
+            ("fills_count".to_owned(), report.shadow.fills_count.into()),
+            ("filled_amount".to_owned(), report.shadow.filled_amount),
+            ("position".to_owned(), report.shadow.position),
+        ]);
+
+        Ok(BacktestReport { score, metrics })
+    }
+}
+
+#[async_trait]
+impl BacktestRunner for SimulatedBacktestRunner {
+    async fn run(
+        &self,
+        parameters: &ParameterSet,
+        start: DateTime,
+        end: DateTime,
+    ) -> Result<BacktestReport> {
+        self.run_simulation(parameters, start, end)
+    }
+}
+
+fn get_market_event_time(event: &ExchangeEvent) -> Option<DateTime> {
+    match event {
+        ExchangeEvent::OrderBookEvent(event) => Some(event.creation_time),
+        ExchangeEvent::Trades(event) => Some(event.receipt_time),
+        _ => None,
+    }
+}
+
+/// Candidate values of each optimized parameter
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParameterGrid {
+    parameters: BTreeMap<String, Vec<Decimal>>,
+}
+
+impl ParameterGrid {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(mut self, name: &str, values: Vec<Decimal>) -> Self {
+        let _ = self.parameters.insert(name.to_owned(), values);
+        self
+    }
+
+    /// All combinations of parameter values
+    pub fn combinations(&self) -> Vec<ParameterSet> {
+        self.parameters
+            .iter()
+            .map(|(name, values)| values.iter().map(move |value| (name.clone(), *value)))
+            .multi_cartesian_product()
+            .map(|parameters| parameters.into_iter().collect())
+            .collect_vec()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMethod {
+    /// Every combination of grid is run
+    Grid,
+    /// Parameters are optimized one at a time with others fixed, starting from the first values of grid.
+    /// Needs much fewer runs than grid search for many parameters, but can stop at local optimum
+    CoordinateDescent { max_rounds: usize },
+}
+
+/// In-sample range is used for search of parameters, which are then checked on the following out-of-sample range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct WalkForwardWindow {
+    pub in_sample_start: DateTime,
+    pub out_of_sample_start: DateTime,
+    pub out_of_sample_end: DateTime,
+}
+
+impl WalkForwardWindow {
+    /// Rolling windows over range, shifted by out-of-sample duration, so out-of-sample ranges don't overlap
+    pub fn rolling(
+        start: DateTime,
+        end: DateTime,
+        in_sample: chrono::Duration,
+        out_of_sample: chrono::Duration,
+    ) -> Result<Vec<Self>> {
+        if in_sample <= chrono::Duration::zero() || out_of_sample <= chrono::Duration::zero() {
+            bail!(
+                "In-sample {} and out-of-sample {} durations should be positive",
+                in_sample,
+                out_of_sample
+            );
+        }
+
+        let mut windows = Vec::new();
+        let mut in_sample_start = start;
+        while in_sample_start + in_sample + out_of_sample <= end {
+            windows.push(Self {
+                in_sample_start,
+                out_of_sample_start: in_sample_start + in_sample,
+                out_of_sample_end: in_sample_start + in_sample + out_of_sample,
+            });
+            in_sample_start = in_sample_start + out_of_sample;
+        }
+
+        Ok(windows)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BacktestRun {
+    pub parameters: ParameterSet,
+    pub report: BacktestReport,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WindowResult {
+    pub window: WalkForwardWindow,
+    /// All in-sample runs of search
+    pub in_sample_runs: Vec<BacktestRun>,
+    /// Best in-sample run
+    pub best: BacktestRun,
+    pub out_of_sample: BacktestReport,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WalkForwardReport {
+    pub windows: Vec<WindowResult>,
+}
+
+impl WalkForwardReport {
+    /// Sum of out-of-sample scores, which estimates live performance of optimization process
+    pub fn out_of_sample_score(&self) -> Decimal {
+        self.windows
+            .iter()
+            .map(|window| window.out_of_sample.score)
+            .sum()
+    }
+
+    /// Best parameters and scores of each window, followed by out-of-sample metrics
+    pub fn comparison_table(&self) -> String {
+        let metric_names = self
+            .windows
+            .iter()
+            .flat_map(|window| window.out_of_sample.metrics.keys())
+            .unique()
+            .sorted()
+            .collect_vec();
+
+        let mut header = vec![
+            "out-of-sample range".to_owned(),
+            "parameters".to_owned(),
+            "in-sample score".to_owned(),
+            "out-of-sample score".to_owned(),
+        ];
+        header.extend(metric_names.iter().map(|name| name.to_string()));
+
+        let mut rows = vec![header];
+        for window in &self.windows {
+            let parameters = window
+                .best
+                .parameters
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .join(" ");
+
+            let mut row = vec![
+                format!(
+                    "{} - {}",
+                    window.window.out_of_sample_start.format("%Y-%m-%d %H:%M"),
+                    window.window.out_of_sample_end.format("%Y-%m-%d %H:%M")
+                ),
+                parameters,
+                window.best.report.score.to_string(),
+                window.out_of_sample.score.to_string(),
+            ];
+            row.extend(metric_names.iter().map(|name| {
+                window
+                    .out_of_sample
+                    .metrics
+                    .get(*name)
+                    .map(|value| value.to_string())
+                    .unwrap_or_default()
+            }));
+            rows.push(row);
+        }
+
+        let widths = (0..rows[0].len())
+            .map(|column| rows.iter().map(|row| row[column].len()).max().unwrap_or(0))
+            .collect_vec();
+
+        let mut table = String::new();
+        for row in rows {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .join(" | ");
+            let _ = writeln!(table, "{}", line.trim_end());
+        }
+
+        table
+    }
+}
+
+/// Walk-forward optimization of strategy parameters: parameters are searched on in-sample range
+/// of each window and the best ones are evaluated on unseen out-of-sample range
+pub struct WalkForwardOptimizer<R: BacktestRunner> {
+    runner: R,
+    grid: ParameterGrid,
+    search_method: SearchMethod,
+}
+
+impl<R: BacktestRunner> WalkForwardOptimizer<R> {
+    pub fn new(runner: R, grid: ParameterGrid, search_method: SearchMethod) -> Self {
+        Self {
+            runner,
+            grid,
+            search_method,
+        }
+    }
+
+    pub async fn run(&self, windows: &[WalkForwardWindow]) -> Result<WalkForwardReport> {
+        let mut results = Vec::new();
+        for window in windows {
+            log::info!(
+                "Walk-forward optimization of window {} - {}",
+                window.in_sample_start,
+                window.out_of_sample_end
+            );
+
+            let in_sample_runs = match self.search_method {
+                SearchMethod::Grid => self.grid_search(window).await?,
+                SearchMethod::CoordinateDescent { max_rounds } => {
+                    self.coordinate_descent(window, max_rounds).await?
+                }
+            };
+
+            let best = match in_sample_runs
+                .iter()
+                .max_by_key(|run| run.report.score)
+                .cloned()
+            {
+                Some(best) => best,
+                None => bail!("There are no parameters to optimize"),
+            };
+
+            let out_of_sample = self
+                .runner
+                .run(
+                    &best.parameters,
+                    window.out_of_sample_start,
+                    window.out_of_sample_end,
+                )
+                .await
+                .with_context(|| format!("Out-of-sample run with {:?} failed", best.parameters))?;
+
+            results.push(WindowResult {
+                window: *window,
+                in_sample_runs,
+                best,
+                out_of_sample,
+            });
+        }
+
+        Ok(WalkForwardReport { windows: results })
+    }
+
+    async fn grid_search(&self, window: &WalkForwardWindow) -> Result<Vec<BacktestRun>> {
+        let mut runs = Vec::new();
+        for parameters in self.grid.combinations() {
+            runs.push(self.run_in_sample(parameters, window).await?);
+        }
+
+        Ok(runs)
+    }
+
+    async fn coordinate_descent(
+        &self,
+        window: &WalkForwardWindow,
+        max_rounds: usize,
+    ) -> Result<Vec<BacktestRun>> {
+        let mut current: ParameterSet = self
+            .grid
+            .parameters
+            .iter()
+            .filter_map(|(name, values)| Some((name.clone(), *values.first()?)))
+            .collect();
+
+        let mut runs: Vec<BacktestRun> = Vec::new();
+        let mut best_score = None;
+        for _ in 0..max_rounds {
+            let mut is_improved = false;
+            for (name, values) in &self.grid.parameters {
+                for value in values {
+                    let mut parameters = current.clone();
+                    let _ = parameters.insert(name.clone(), *value);
+
+                    let score = match runs.iter().find(|run| run.parameters == parameters) {
+                        Some(run) => run.report.score,
+                        None => {
+                            let run = self.run_in_sample(parameters.clone(), window).await?;
+                            let score = run.report.score;
+                            runs.push(run);
+                            score
+                        }
+                    };
+
+                    if best_score.map_or(true, |best_score| score > best_score) {
+                        is_improved = best_score.is_some();
+                        best_score = Some(score);
+                        current = parameters;
+                    }
+                }
+            }
+
+            if !is_improved {
+                break;
+            }
+        }
+
+        Ok(runs)
+    }
+
+    async fn run_in_sample(
+        &self,
+        parameters: ParameterSet,
+        window: &WalkForwardWindow,
+    ) -> Result<BacktestRun> {
+        let report = self
+            .runner
+            .run(
+                &parameters,
+                window.in_sample_start,
+                window.out_of_sample_start,
+            )
+            .await
+            .with_context(|| format!("In-sample run with {:?} failed", parameters))?;
+
+        Ok(BacktestRun { parameters, report })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{TimeZone, Utc};
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::exchanges::common::{CurrencyPair, ExchangeAccountId};
+    use crate::exchanges::events::{TickDirection, Trade, TradeId, TradesEvent};
+    use crate::order_book::event::{EventType, OrderBookEvent};
+    use crate::order_book_data;
+    use crate::orders::order::OrderSide;
+    use crate::strategies::shadow::tests::TestStrategy;
+
+    /// Score is maximal at x = 2 and y = 1 in the first half of time and at x = 3 later
+    struct QuadraticRunner {
+        middle: DateTime,
+    }
+
+    #[async_trait]
+    impl BacktestRunner for QuadraticRunner {
+        async fn run(
+            &self,
+            parameters: &ParameterSet,
+            start: DateTime,
+            _end: DateTime,
+        ) -> Result<BacktestReport> {
+            let x = parameters["x"];
+            let y = parameters["y"];
+            let best_x = match start < self.middle {
+                true => dec!(2),
+                false => dec!(3),
+            };
+
+            Ok(BacktestReport {
+                score: -(x - best_x) * (x - best_x) - (y - dec!(1)) * (y - dec!(1)),
+                metrics: BTreeMap::from([("x".to_owned(), x)]),
+            })
+        }
+    }
+
+    fn grid() -> ParameterGrid {
+        ParameterGrid::new()
+            .add("x", vec![dec!(1), dec!(2), dec!(3), dec!(4)])
+            .add("y", vec![dec!(0), dec!(1), dec!(2)])
+    }
+
+    fn windows() -> Vec<WalkForwardWindow> {
+        WalkForwardWindow::rolling(
+            Utc.ymd(2022, 1, 1).and_hms(0, 0, 0),
+            Utc.ymd(2022, 1, 5).and_hms(0, 0, 0),
+            chrono::Duration::days(2),
+            chrono::Duration::days(1),
+        )
+        .expect("in test")
+    }
+
+    fn runner() -> QuadraticRunner {
+        QuadraticRunner {
+            middle: Utc.ymd(2022, 1, 2).and_hms(0, 0, 0),
+        }
+    }
+
+    #[test]
+    fn grid_combinations_and_rolling_windows() {
+        assert_eq!(grid().combinations().len(), 12);
+
+        let windows = windows();
+        assert_eq!(windows.len(), 2);
+        assert_eq!(
+            windows[0].out_of_sample_start,
+            windows[1].in_sample_start + chrono::Duration::days(1)
+        );
+        assert_eq!(
+            windows[1].out_of_sample_end,
+            Utc.ymd(2022, 1, 5).and_hms(0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn rolling_windows_need_positive_durations() {
+        let start = Utc.ymd(2022, 1, 1).and_hms(0, 0, 0);
+        let end = Utc.ymd(2022, 1, 5).and_hms(0, 0, 0);
+
+        let windows = |in_sample, out_of_sample| {
+            WalkForwardWindow::rolling(start, end, in_sample, out_of_sample)
+        };
+        assert!(windows(chrono::Duration::days(1), chrono::Duration::zero()).is_err());
+        assert!(windows(chrono::Duration::zero(), chrono::Duration::days(1)).is_err());
+        assert!(windows(chrono::Duration::days(1), chrono::Duration::days(-1)).is_err());
+    }
+
+    #[tokio::test]
+    async fn simulated_runner_scores_strategy_by_pnl_within_range() {
+        let market_account_id = MarketAccountId::new(
+            ExchangeAccountId::new("Binance".into(), 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        );
+        let start = Utc.ymd(2022, 1, 1).and_hms(0, 0, 0);
+        let trade_time = start + chrono::Duration::minutes(1);
+        let end = start + chrono::Duration::minutes(2);
+
+        let snapshot = OrderBookEvent::new(
+            start,
+            market_account_id.exchange_account_id,
+            market_account_id.currency_pair,
+            "snapshot".into(),
+            EventType::Snapshot,
+            Arc::new(order_book_data![
+                dec!(110) => dec!(1),
+                ;
+                dec!(100) => dec!(1),
+            ]),
+        );
+        // Sell trade goes through buy orders of strategy
+        let trades = TradesEvent {
+            exchange_account_id: market_account_id.exchange_account_id,
+            currency_pair: market_account_id.currency_pair,
+            trades: vec![Trade {
+                trade_id: TradeId::Number(1),
+                price: dec!(101),
+                quantity: dec!(2),
+                side: OrderSide::Sell,
+                transaction_time: trade_time,
+                tick_direction: TickDirection::None,
+            }],
+            receipt_time: trade_time,
+        };
+        let runner = SimulatedBacktestRunner::new(
+            market_account_id,
+            vec![
+                ExchangeEvent::OrderBookEvent(snapshot),
+                ExchangeEvent::Trades(trades),
+            ],
+            ExecutionModelsSettings::default(),
+            Box::new(move |parameters: &ParameterSet| {
+                let strategy: Box<dyn DispositionStrategy> = Box::new(TestStrategy {
+                    market_account_id,
+                    offset: parameters["offset"],
+                });
+                Ok(strategy)
+            }),
+        );
+        let parameters = |offset| ParameterSet::from([("offset".to_owned(), offset)]);
+
+        // Buy order at 101 is filled and marked by middle price 105
+        let report = runner
+            .run(&parameters(dec!(1)), start, end)
+            .await
+            .expect("in test");
+        assert_eq!(report.score, dec!(4));
+        assert_eq!(report.metrics["fills_count"], dec!(1));
+        assert_eq!(report.metrics["position"], dec!(1));
+
+        let report = runner
+            .run(&parameters(dec!(3)), start, end)
+            .await
+            .expect("in test");
+        assert_eq!(report.score, dec!(2));
+
+        // Trade is outside of range
+        let report = runner
+            .run(&parameters(dec!(1)), start, trade_time)
+            .await
+            .expect("in test");
+        assert_eq!(report.score, dec!(0));
+        assert_eq!(report.metrics["fills_count"], dec!(0));
+
+        assert!(runner
+            .run(
+                &parameters(dec!(1)),
+                end,
+                end + chrono::Duration::minutes(1)
+            )
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn grid_search_selects_best_parameters_of_each_window() {
+        let optimizer = WalkForwardOptimizer::new(runner(), grid(), SearchMethod::Grid);
+
+        let report = optimizer.run(&windows()).await.expect("in test");
+
+        let best_x = report
+            .windows
+            .iter()
+            .map(|window| window.best.parameters["x"])
+            .collect_vec();
+        assert_eq!(best_x, vec![dec!(2), dec!(3)]);
+        assert_eq!(report.windows[0].in_sample_runs.len(), 12);
+        // Parameters of the first window are worse on its out-of-sample range
+        assert_eq!(report.windows[0].out_of_sample.score, dec!(-1));
+        assert_eq!(report.out_of_sample_score(), dec!(-1));
+
+        let table = report.comparison_table();
+        assert_eq!(table.lines().count(), 3);
+        assert!(table.contains("x=2 y=1"));
+    }
+
+    #[tokio::test]
+    async fn coordinate_descent_finds_optimum_with_fewer_runs() {
+        let optimizer = WalkForwardOptimizer::new(
+            runner(),
+            grid(),
+            SearchMethod::CoordinateDescent { max_rounds: 5 },
+        );
+
+        let report = optimizer.run(&windows()[..1]).await.expect("in test");
+
+        let best = &report.windows[0].best.parameters;
+        assert_eq!((best["x"], best["y"]), (dec!(2), dec!(1)));
+        assert!(report.windows[0].in_sample_runs.len() < 12);
+    }
+}
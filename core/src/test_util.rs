@@ -0,0 +1,491 @@
+//! Fixtures for tests of core and exchange crates.
+//! Available in other crates with `test-util` feature
+//!
+//! ```ignore
+//! let event_data = FillEventDataBuilder::new()
+//!     .client_order_id(client_order_id)
+//!     .fill_price(dec!(0.2))
+//!     .fill_amount(dec!(5))
+//!     .build();
+//!
+//! let order = OrderSnapshotBuilder::new(exchange_account_id, currency_pair)
+//!     .side(OrderSide::Sell)
+//!     .price(dec!(0.2))
+//!     .amount(dec!(12))
+//!     .build();
+//!
+//! let symbol = SymbolBuilder::new("btc".into(), "usdt".into())
+//!     .min_amount(dec!(0.01))
+//!     .build();
+//! ```
+
+use std::sync::Arc;
+
+use mmb_utils::DateTime;
+use parking_lot::RwLock;
+use rust_decimal_macros::dec;
+
+use crate::exchanges::common::{Amount, CurrencyCode, CurrencyPair, ExchangeAccountId, Price};
+use crate::exchanges::events::TradeId;
+use crate::exchanges::general::commission::Percent;
+use crate::exchanges::general::handlers::handle_order_filled::FillEventData;
+use crate::exchanges::general::symbol::{Precision, Symbol};
+use crate::orders::fill::{EventSourceType, OrderFill, OrderFillType};
+use crate::orders::order::{
+    ClientOrderId, ExchangeOrderId, OrderAmountKind, OrderExecutionType, OrderHeader, OrderRole,
+    OrderSide, OrderSimpleProps, OrderSnapshot, OrderStatus, OrderType, PositionSide,
+    ReservationId,
+};
+use crate::orders::pool::{OrderRef, OrdersPool};
+
+pub const TEST_EXCHANGE_ORDER_ID: &str = "test";
+pub const TEST_STRATEGY_NAME: &str = "FromTest";
+
+/// `FillEventData` of user trade received by websocket with zero price and amount if they aren't
+/// specified. Optional fields are empty by default
+pub struct FillEventDataBuilder {
+    event_data: FillEventData,
+}
+
+impl FillEventDataBuilder {
+    pub fn new() -> Self {
+        Self {
+            event_data: FillEventData {
+                source_type: EventSourceType::WebSocket,
+                trade_id: None,
+                client_order_id: None,
+                exchange_order_id: ExchangeOrderId::new(TEST_EXCHANGE_ORDER_ID.into()),
+                fill_price: dec!(0),
+                fill_amount: dec!(0),
+                is_diff: false,
+                total_filled_amount: None,
+                order_role: None,
+                commission_currency_code: None,
+                commission_rate: None,
+                commission_amount: None,
+                fill_type: OrderFillType::UserTrade,
+                trade_currency_pair: None,
+                order_side: None,
+                order_amount: None,
+                fill_date: None,
+                position_side: None,
+            },
+        }
+    }
+
+    pub fn source_type(mut self, source_type: EventSourceType) -> Self {
+        self.event_data.source_type = source_type;
+        self
+    }
+
+    pub fn trade_id(mut self, trade_id: TradeId) -> Self {
+        self.event_data.trade_id = Some(trade_id);
+        self
+    }
+
+    pub fn client_order_id(mut self, client_order_id: ClientOrderId) -> Self {
+        self.event_data.client_order_id = Some(client_order_id);
+        self
+    }
+
+    pub fn exchange_order_id(mut self, exchange_order_id: ExchangeOrderId) -> Self {
+        self.event_data.exchange_order_id = exchange_order_id;
+        self
+    }
+
+    pub fn fill_price(mut self, fill_price: Price) -> Self {
+        self.event_data.fill_price = fill_price;
+        self
+    }
+
+    pub fn fill_amount(mut self, fill_amount: Amount) -> Self {
+        self.event_data.fill_amount = fill_amount;
+        self
+    }
+
+    pub fn is_diff(mut self, is_diff: bool) -> Self {
+        self.event_data.is_diff = is_diff;
+        self
+    }
+
+    pub fn total_filled_amount(mut self, total_filled_amount: Amount) -> Self {
+        self.event_data.total_filled_amount = Some(total_filled_amount);
+        self
+    }
+
+    pub fn order_role(mut self, order_role: OrderRole) -> Self {
+        self.event_data.order_role = Some(order_role);
+        self
+    }
+
+    pub fn commission_currency_code(mut self, commission_currency_code: CurrencyCode) -> Self {
+        self.event_data.commission_currency_code = Some(commission_currency_code);
+        self
+    }
+
+    pub fn commission_rate(mut self, commission_rate: Percent) -> Self {
+        self.event_data.commission_rate = Some(commission_rate);
+        self
+    }
+
+    pub fn commission_amount(mut self, commission_amount: Amount) -> Self {
+        self.event_data.commission_amount = Some(commission_amount);
+        self
+    }
+
+    pub fn fill_type(mut self, fill_type: OrderFillType) -> Self {
+        self.event_data.fill_type = fill_type;
+        self
+    }
+
+    pub fn trade_currency_pair(mut self, trade_currency_pair: CurrencyPair) -> Self {
+        self.event_data.trade_currency_pair = Some(trade_currency_pair);
+        self
+    }
+
+    pub fn order_side(mut self, order_side: OrderSide) -> Self {
+        self.event_data.order_side = Some(order_side);
+        self
+    }
+
+    pub fn order_amount(mut self, order_amount: Amount) -> Self {
+        self.event_data.order_amount = Some(order_amount);
+        self
+    }
+
+    pub fn fill_date(mut self, fill_date: DateTime) -> Self {
+        self.event_data.fill_date = Some(fill_date);
+        self
+    }
+
+    pub fn position_side(mut self, position_side: PositionSide) -> Self {
+        self.event_data.position_side = Some(position_side);
+        self
+    }
+
+    pub fn build(self) -> FillEventData {
+        self.event_data
+    }
+}
+
+impl Default for FillEventDataBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `OrderSnapshot` of limit buy order with unique client order id, price 1 and amount 1
+/// if they aren't specified
+pub struct OrderSnapshotBuilder {
+    exchange_account_id: ExchangeAccountId,
+    currency_pair: CurrencyPair,
+    client_order_id: ClientOrderId,
+    order_type: OrderType,
+    side: OrderSide,
+    price: Price,
+    amount: Amount,
+    amount_kind: OrderAmountKind,
+    execution_type: OrderExecutionType,
+    role: Option<OrderRole>,
+    reservation_id: Option<ReservationId>,
+    strategy_name: String,
+    exchange_order_id: Option<ExchangeOrderId>,
+    status: Option<(OrderStatus, DateTime)>,
+    fills: Vec<OrderFill>,
+}
+
+impl OrderSnapshotBuilder {
+    pub fn new(exchange_account_id: ExchangeAccountId, currency_pair: CurrencyPair) -> Self {
+        Self {
+            exchange_account_id,
+            currency_pair,
+            client_order_id: ClientOrderId::unique_id(),
+            order_type: OrderType::Limit,
+            side: OrderSide::Buy,
+            price: dec!(1),
+            amount: dec!(1),
+            amount_kind: OrderAmountKind::Base,
+            execution_type: OrderExecutionType::None,
+            role: None,
+            reservation_id: None,
+            strategy_name: TEST_STRATEGY_NAME.to_owned(),
+            exchange_order_id: None,
+            status: None,
+            fills: Vec::new(),
+        }
+    }
+
+    pub fn client_order_id(mut self, client_order_id: ClientOrderId) -> Self {
+        self.client_order_id = client_order_id;
+        self
+    }
+
+    pub fn order_type(mut self, order_type: OrderType) -> Self {
+        self.order_type = order_type;
+        self
+    }
+
+    pub fn side(mut self, side: OrderSide) -> Self {
+        self.side = side;
+        self
+    }
+
+    pub fn price(mut self, price: Price) -> Self {
+        self.price = price;
+        self
+    }
+
+    pub fn amount(mut self, amount: Amount) -> Self {
+        self.amount = amount;
+        self
+    }
+
+    pub fn amount_kind(mut self, amount_kind: OrderAmountKind) -> Self {
+        self.amount_kind = amount_kind;
+        self
+    }
+
+    pub fn execution_type(mut self, execution_type: OrderExecutionType) -> Self {
+        self.execution_type = execution_type;
+        self
+    }
+
+    pub fn role(mut self, role: OrderRole) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    pub fn reservation_id(mut self, reservation_id: ReservationId) -> Self {
+        self.reservation_id = Some(reservation_id);
+        self
+    }
+
+    pub fn strategy_name(mut self, strategy_name: impl Into<String>) -> Self {
+        self.strategy_name = strategy_name.into();
+        self
+    }
+
+    pub fn exchange_order_id(mut self, exchange_order_id: ExchangeOrderId) -> Self {
+        self.exchange_order_id = Some(exchange_order_id);
+        self
+    }
+
+    pub fn status(mut self, status: OrderStatus, time: DateTime) -> Self {
+        self.status = Some((status, time));
+        self
+    }
+
+    /// Fill is added to order as is, so filled amount of order is increased by amount of fill
+    pub fn fill(mut self, fill: OrderFill) -> Self {
+        self.fills.push(fill);
+        self
+    }
+
+    pub fn build(self) -> OrderSnapshot {
+        let header = OrderHeader::new(
+            self.client_order_id,
+            chrono::Utc::now(),
+            self.exchange_account_id,
+            self.currency_pair,
+            self.order_type,
+            self.side,
+            self.amount,
+            self.execution_type,
+            self.reservation_id,
+            None,
+            self.strategy_name,
+        )
+        .with_amount_kind(self.amount_kind);
+
+        let mut props = OrderSimpleProps::from_price(Some(self.price));
+        props.role = self.role;
+        props.exchange_order_id = self.exchange_order_id;
+
+        let mut order = OrderSnapshot::new(
+            header,
+            props,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        );
+        if let Some((status, time)) = self.status {
+            order.set_status(status, time);
+        }
+        for fill in self.fills {
+            order.add_fill(fill);
+        }
+
+        order
+    }
+
+    /// Builds order and adds it to pool
+    pub fn build_ref(self, orders_pool: &OrdersPool) -> OrderRef {
+        orders_pool.add_snapshot_initial(Arc::new(RwLock::new(self.build())))
+    }
+}
+
+/// Inactive spot `Symbol` with price tick 0.1 and amount tick 0.001 which amount is specified
+/// in base currency. Currency ids are the same as currency codes. Limits aren't set by default
+pub struct SymbolBuilder {
+    is_active: bool,
+    is_derivative: bool,
+    base_currency_code: CurrencyCode,
+    quote_currency_code: CurrencyCode,
+    min_price: Option<Price>,
+    max_price: Option<Price>,
+    min_amount: Option<Amount>,
+    max_amount: Option<Amount>,
+    min_cost: Option<Price>,
+    amount_currency_code: CurrencyCode,
+    balance_currency_code: Option<CurrencyCode>,
+    price_tick: Price,
+    amount_tick: Amount,
+}
+
+impl SymbolBuilder {
+    pub fn new(base_currency_code: CurrencyCode, quote_currency_code: CurrencyCode) -> Self {
+        Self {
+            is_active: false,
+            is_derivative: false,
+            base_currency_code,
+            quote_currency_code,
+            min_price: None,
+            max_price: None,
+            min_amount: None,
+            max_amount: None,
+            min_cost: None,
+            amount_currency_code: base_currency_code,
+            balance_currency_code: None,
+            price_tick: dec!(0.1),
+            amount_tick: dec!(0.001),
+        }
+    }
+
+    pub fn is_active(mut self, is_active: bool) -> Self {
+        self.is_active = is_active;
+        self
+    }
+
+    pub fn is_derivative(mut self, is_derivative: bool) -> Self {
+        self.is_derivative = is_derivative;
+        self
+    }
+
+    pub fn min_price(mut self, min_price: Price) -> Self {
+        self.min_price = Some(min_price);
+        self
+    }
+
+    pub fn max_price(mut self, max_price: Price) -> Self {
+        self.max_price = Some(max_price);
+        self
+    }
+
+    pub fn min_amount(mut self, min_amount: Amount) -> Self {
+        self.min_amount = Some(min_amount);
+        self
+    }
+
+    pub fn max_amount(mut self, max_amount: Amount) -> Self {
+        self.max_amount = Some(max_amount);
+        self
+    }
+
+    pub fn min_cost(mut self, min_cost: Price) -> Self {
+        self.min_cost = Some(min_cost);
+        self
+    }
+
+    pub fn amount_currency_code(mut self, amount_currency_code: CurrencyCode) -> Self {
+        self.amount_currency_code = amount_currency_code;
+        self
+    }
+
+    pub fn balance_currency_code(mut self, balance_currency_code: CurrencyCode) -> Self {
+        self.balance_currency_code = Some(balance_currency_code);
+        self
+    }
+
+    pub fn price_tick(mut self, price_tick: Price) -> Self {
+        self.price_tick = price_tick;
+        self
+    }
+
+    pub fn amount_tick(mut self, amount_tick: Amount) -> Self {
+        self.amount_tick = amount_tick;
+        self
+    }
+
+    pub fn build(self) -> Symbol {
+        Symbol::new(
+            self.is_active,
+            self.is_derivative,
+            self.base_currency_code.as_str().into(),
+            self.base_currency_code,
+            self.quote_currency_code.as_str().into(),
+            self.quote_currency_code,
+            self.min_price,
+            self.max_price,
+            self.min_amount,
+            self.max_amount,
+            self.min_cost,
+            self.amount_currency_code,
+            self.balance_currency_code,
+            Precision::ByTick {
+                tick: self.price_tick,
+            },
+            Precision::ByTick {
+                tick: self.amount_tick,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn order_snapshot_is_built_with_specified_fields() {
+        let order = OrderSnapshotBuilder::new(
+            ExchangeAccountId::new("Binance".into(), 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        )
+        .client_order_id("built".into())
+        .side(OrderSide::Sell)
+        .price(dec!(0.2))
+        .amount(dec!(12))
+        .role(OrderRole::Maker)
+        .status(OrderStatus::Created, chrono::Utc::now())
+        .build();
+
+        assert_eq!(order.header.client_order_id, "built".into());
+        assert_eq!(order.header.side, OrderSide::Sell);
+        assert_eq!(order.header.amount, dec!(12));
+        assert_eq!(order.price(), dec!(0.2));
+        assert_eq!(order.props.role, Some(OrderRole::Maker));
+        assert_eq!(order.status(), OrderStatus::Created);
+        assert_eq!(order.header.strategy_name, TEST_STRATEGY_NAME);
+    }
+
+    #[test]
+    fn symbol_is_built_with_specified_fields() {
+        let symbol = SymbolBuilder::new("btc".into(), "usdt".into())
+            .is_derivative(true)
+            .min_amount(dec!(0.01))
+            .balance_currency_code("btc".into())
+            .amount_tick(dec!(1))
+            .build();
+
+        assert_eq!(
+            symbol.currency_pair(),
+            CurrencyPair::from_codes("btc".into(), "usdt".into())
+        );
+        assert!(!symbol.is_active);
+        assert!(symbol.is_derivative);
+        assert_eq!(symbol.min_amount, Some(dec!(0.01)));
+        assert_eq!(symbol.amount_currency_code, "btc".into());
+        assert_eq!(symbol.balance_currency_code, Some("btc".into()));
+        assert_eq!(symbol.amount_precision, Precision::ByTick { tick: dec!(1) });
+    }
+}
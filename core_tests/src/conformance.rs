@@ -0,0 +1,348 @@
+use std::sync::Arc;
+
+use anyhow::{bail, ensure, Context, Result};
+use mmb_core::exchanges::common::{
+    Amount, CurrencyPair, ExchangeAccountId, ExchangeErrorType, Price, RestRequestOutcome,
+};
+use mmb_core::exchanges::general::exchange::Exchange;
+use mmb_core::exchanges::general::handlers::handle_order_filled::FillEventData;
+use mmb_core::exchanges::general::helpers::get_rest_error;
+use mmb_core::exchanges::general::symbol::Precision;
+use mmb_core::exchanges::traits::ExchangeClient;
+use mmb_core::orders::order::{ClientOrderId, ExchangeOrderId, OrderStatus};
+use parking_lot::Mutex;
+use rust_decimal_macros::dec;
+
+use crate::order::OrderProxy;
+
+/// Fill which connector should produce from websocket message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedFill {
+    pub price: Price,
+    pub amount: Amount,
+    pub is_diff: bool,
+}
+
+/// Recorded responses and websocket messages of exchange for one order lifecycle
+pub struct ConformanceFixture {
+    /// Currency pair of order which should be among loaded symbols
+    pub currency_pair: CurrencyPair,
+    pub all_symbols_response: RestRequestOutcome,
+    pub create_order_response: RestRequestOutcome,
+    pub client_order_id: ClientOrderId,
+    /// Order id which should be taken from `create_order_response`
+    pub exchange_order_id: ExchangeOrderId,
+    /// Websocket message about order creation. Not every exchange sends it
+    pub order_created_message: Option<String>,
+    pub order_filled_messages: Vec<(String, ExpectedFill)>,
+    pub order_cancelled_message: String,
+    /// Error responses of exchange with error types which they should be mapped to
+    pub error_responses: Vec<(RestRequestOutcome, ExchangeErrorType)>,
+}
+
+#[derive(Default)]
+struct ReceivedEvents {
+    created: Vec<(ClientOrderId, ExchangeOrderId)>,
+    cancelled: Vec<(ClientOrderId, ExchangeOrderId)>,
+    fills: Vec<FillEventData>,
+}
+
+/// Standard script which connector of new exchange should pass before live use:
+/// symbols loading, order creation and cancellation, fill handling by injected websocket messages
+/// and mapping of REST errors. Callbacks of client are replaced, so client shouldn't be used
+/// by `Exchange` at the same time
+///
+/// ```no_run
+/// use core_tests::conformance::{ConformanceFixture, ConformanceSuite};
+/// use mmb_core::exchanges::traits::ExchangeClient;
+///
+/// fn check_connector(client: &dyn ExchangeClient, fixture: ConformanceFixture) {
+///     ConformanceSuite::new(client, fixture)
+///         .run()
+///         .expect("connector should be conformant");
+/// }
+/// ```
+pub struct ConformanceSuite<'a> {
+    client: &'a dyn ExchangeClient,
+    fixture: ConformanceFixture,
+    events: Arc<Mutex<ReceivedEvents>>,
+}
+
+impl<'a> ConformanceSuite<'a> {
+    pub fn new(client: &'a dyn ExchangeClient, fixture: ConformanceFixture) -> Self {
+        Self {
+            client,
+            fixture,
+            events: Arc::new(Mutex::new(ReceivedEvents::default())),
+        }
+    }
+
+    /// Runs all checks and returns error with every failed check
+    pub fn run(&self) -> Result<()> {
+        self.subscribe_to_events();
+
+        let checks: [(&str, &dyn Fn() -> Result<()>); 5] = [
+            ("symbols loading", &|| self.check_symbols()),
+            ("order creation", &|| self.check_order_creation()),
+            ("fill handling", &|| self.check_fills()),
+            ("order cancellation", &|| self.check_order_cancellation()),
+            ("error mapping", &|| self.check_error_mapping()),
+        ];
+
+        let failures = checks
+            .iter()
+            .filter_map(|(name, check)| {
+                check()
+                    .err()
+                    .map(|error| format!("{} failed: {:?}", name, error))
+            })
+            .collect::<Vec<_>>();
+
+        if !failures.is_empty() {
+            bail!("Exchange client isn't conformant:\n{}", failures.join("\n"));
+        }
+
+        Ok(())
+    }
+
+    pub fn check_symbols(&self) -> Result<()> {
+        let symbols = self
+            .client
+            .parse_all_symbols(&self.fixture.all_symbols_response)
+            .context("Unable to parse symbols")?;
+        ensure!(!symbols.is_empty(), "No symbols are parsed");
+
+        // Like `Exchange` does after symbols loading, so fills can be handled with currency codes
+        for symbol in &symbols {
+            let supported_currencies = self.client.get_supported_currencies();
+            supported_currencies.insert(symbol.base_currency_id, symbol.base_currency_code);
+            supported_currencies.insert(symbol.quote_currency_id, symbol.quote_currency_code);
+        }
+
+        let currency_pair = self.fixture.currency_pair;
+        let symbol = symbols
+            .iter()
+            .find(|x| x.currency_pair() == currency_pair)
+            .with_context(|| format!("Symbol {} isn't found", currency_pair))?;
+
+        check_precision("price", &symbol.price_precision)?;
+        check_precision("amount", &symbol.amount_precision)?;
+        if let Some(min_amount) = symbol.min_amount {
+            ensure!(
+                min_amount > dec!(0),
+                "Min amount should be positive: {}",
+                min_amount
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn check_order_creation(&self) -> Result<()> {
+        let response = &self.fixture.create_order_response;
+        if let Some(error) = get_rest_error(response, self.exchange_account_id(), false) {
+            bail!("Create order response is recognized as error: {:?}", error);
+        }
+
+        let exchange_order_id = self
+            .client
+            .get_order_id(response)
+            .context("Unable to get order id from create order response")?;
+        ensure!(
+            exchange_order_id == self.fixture.exchange_order_id,
+            "Order id {} is taken from create order response instead of {}",
+            exchange_order_id,
+            self.fixture.exchange_order_id
+        );
+
+        let message = match &self.fixture.order_created_message {
+            Some(message) => message,
+            None => return Ok(()),
+        };
+        self.client
+            .on_websocket_message(message)
+            .context("Unable to handle order created message")?;
+
+        let expected = (
+            self.fixture.client_order_id.clone(),
+            self.fixture.exchange_order_id.clone(),
+        );
+        let events = self.events.lock();
+        ensure!(
+            events.created.contains(&expected),
+            "Order created callback isn't called for {:?}, received: {:?}",
+            expected,
+            events.created
+        );
+
+        Ok(())
+    }
+
+    pub fn check_fills(&self) -> Result<()> {
+        for (message, expected_fill) in &self.fixture.order_filled_messages {
+            let fills_count = self.events.lock().fills.len();
+            self.client
+                .on_websocket_message(message)
+                .with_context(|| format!("Unable to handle order filled message {}", message))?;
+
+            let events = self.events.lock();
+            ensure!(
+                events.fills.len() == fills_count + 1,
+                "Order filled callback should be called once for message {}",
+                message
+            );
+
+            let fill = events.fills.last().expect("fill was received above");
+            ensure!(
+                fill.exchange_order_id == self.fixture.exchange_order_id,
+                "Fill has exchange order id {} instead of {}",
+                fill.exchange_order_id,
+                self.fixture.exchange_order_id
+            );
+            if let Some(client_order_id) = &fill.client_order_id {
+                ensure!(
+                    client_order_id == &self.fixture.client_order_id,
+                    "Fill has client order id {} instead of {}",
+                    client_order_id,
+                    self.fixture.client_order_id
+                );
+            }
+            ensure!(fill.trade_id.is_some(), "Fill doesn't have trade id");
+
+            let received_fill = ExpectedFill {
+                price: fill.fill_price,
+                amount: fill.fill_amount,
+                is_diff: fill.is_diff,
+            };
+            ensure!(
+                &received_fill == expected_fill,
+                "Fill {:?} is received instead of {:?}",
+                received_fill,
+                expected_fill
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn check_order_cancellation(&self) -> Result<()> {
+        self.client
+            .on_websocket_message(&self.fixture.order_cancelled_message)
+            .context("Unable to handle order cancelled message")?;
+
+        let expected = (
+            self.fixture.client_order_id.clone(),
+            self.fixture.exchange_order_id.clone(),
+        );
+        let events = self.events.lock();
+        ensure!(
+            events.cancelled.contains(&expected),
+            "Order cancelled callback isn't called for {:?}, received: {:?}",
+            expected,
+            events.cancelled
+        );
+
+        Ok(())
+    }
+
+    pub fn check_error_mapping(&self) -> Result<()> {
+        for (response, expected_error_type) in &self.fixture.error_responses {
+            let error = get_rest_error(response, self.exchange_account_id(), false)
+                .with_context(|| format!("Error isn't recognized in response {:?}", response))?;
+            ensure!(
+                error.error_type == *expected_error_type,
+                "Error {:?} is mapped to {:?} instead of {:?}",
+                error,
+                error.error_type,
+                expected_error_type
+            );
+        }
+
+        Ok(())
+    }
+
+    fn exchange_account_id(&self) -> ExchangeAccountId {
+        self.client.get_settings().exchange_account_id
+    }
+
+    fn subscribe_to_events(&self) {
+        let events = self.events.clone();
+        self.client.set_order_created_callback(Box::new(
+            move |client_order_id, exchange_order_id, _| {
+                events
+                    .lock()
+                    .created
+                    .push((client_order_id, exchange_order_id))
+            },
+        ));
+
+        let events = self.events.clone();
+        self.client.set_order_cancelled_callback(Box::new(
+            move |client_order_id, exchange_order_id, _| {
+                events
+                    .lock()
+                    .cancelled
+                    .push((client_order_id, exchange_order_id))
+            },
+        ));
+
+        let events = self.events.clone();
+        self.client
+            .set_handle_order_filled_callback(Box::new(move |event_data| {
+                events.lock().fills.push(event_data)
+            }));
+    }
+}
+
+fn check_precision(name: &str, precision: &Precision) -> Result<()> {
+    match precision {
+        Precision::ByTick { tick } => {
+            ensure!(*tick > dec!(0), "Tick of {} should be positive", name)
+        }
+        Precision::ByMantissa { precision } => ensure!(
+            *precision > 0,
+            "Mantissa precision of {} should be positive",
+            name
+        ),
+    }
+
+    Ok(())
+}
+
+/// Live part of conformance script: creates order by `order_proxy` which shouldn't be filled
+/// (e.g. with price far from market) and cancels it
+pub async fn check_live_create_and_cancel(
+    exchange: Arc<Exchange>,
+    order_proxy: &OrderProxy,
+) -> Result<()> {
+    let order = order_proxy
+        .create_order(exchange.clone())
+        .await
+        .context("Unable to create order")?;
+    ensure!(
+        order.status() == OrderStatus::Created,
+        "Order has status {:?} after creation",
+        order.status()
+    );
+    ensure!(
+        order.exchange_order_id().is_some(),
+        "Created order doesn't have exchange order id"
+    );
+
+    let _ = exchange
+        .wait_cancel_order(
+            order.clone(),
+            None,
+            true,
+            order_proxy.cancellation_token.clone(),
+        )
+        .await
+        .context("Unable to cancel order")?;
+    ensure!(
+        order.status() == OrderStatus::Canceled,
+        "Order has status {:?} after cancellation",
+        order.status()
+    );
+
+    Ok(())
+}
@@ -14,4 +14,6 @@
     unused_must_use
 )]
 
+pub mod conformance;
 pub mod order;
+pub mod simulated_exchange;
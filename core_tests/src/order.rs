@@ -111,11 +111,11 @@ impl OrderProxy {
             header: header.clone(),
         };
 
-        with_timeout(
+        Ok(with_timeout(
             self.timeout,
             exchange.create_order(&to_create, None, self.cancellation_token.clone()),
         )
-        .await
+        .await?)
     }
 
     pub async fn cancel_order_or_fail(&self, order_ref: &OrderRef, exchange: Arc<Exchange>) {
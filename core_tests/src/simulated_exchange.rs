@@ -0,0 +1,486 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use futures::StreamExt;
+use hyper::StatusCode;
+use mmb_core::connectivity::connectivity_manager::WebSocketRole;
+use mmb_core::exchanges::common::{
+    ActivePosition, Amount, ClosedPosition, CurrencyCode, CurrencyId, CurrencyPair, ExchangeError,
+    ExchangeErrorType, Price, RestRequestOutcome, SpecificCurrencyPair,
+};
+use mmb_core::exchanges::events::{
+    AllowedEventSourceType, ExchangeBalance, ExchangeBalancesAndPositions, ExchangeEvent, TradeId,
+    TradeStream,
+};
+use mmb_core::exchanges::general::exchange::BoxExchangeClient;
+use mmb_core::exchanges::general::features::{
+    ExchangeFeatures, OpenOrdersType, OrderFeatures, OrderTradeOption, RestFillsFeatures,
+    WebSocketOptions,
+};
+use mmb_core::exchanges::general::handlers::handle_order_filled::FillEventData;
+use mmb_core::exchanges::general::order::get_order_trades::OrderTrade;
+use mmb_core::exchanges::general::symbol::{Precision, Symbol};
+use mmb_core::exchanges::timeouts::requests_timeout_manager_factory::RequestTimeoutArguments;
+use mmb_core::exchanges::traits::{
+    ExchangeClient, ExchangeClientBuilder, ExchangeClientBuilderResult, Support,
+};
+use mmb_core::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use mmb_core::orders::fill::EventSourceType;
+use mmb_core::orders::order::{
+    ClientOrderId, ExchangeOrderId, OrderCancelling, OrderCreating, OrderInfo, OrderSide,
+    OrderStatus,
+};
+use mmb_core::orders::pool::OrderRef;
+use mmb_core::settings::ExchangeSettings;
+use mmb_utils::DateTime;
+use rust_decimal_macros::dec;
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, oneshot};
+use url::Url;
+
+type OrderCallback = Box<dyn FnMut(ClientOrderId, ExchangeOrderId, EventSourceType) + Send + Sync>;
+
+/// Balance of every currency of simulated exchange
+const CURRENCY_BALANCE: Amount = dec!(1000);
+
+#[derive(Default)]
+struct VenueState {
+    open_orders: Vec<OrderInfo>,
+    cancel_requests: Vec<ClientOrderId>,
+    is_cancellation_ignored: bool,
+    websocket_url: Option<Url>,
+}
+
+/// In-memory exchange for running of trading engine in tests without network. Orders are
+/// accepted immediately and cancellations are confirmed by simulated websocket events unless
+/// they are ignored. Venue is shared by test and client, so test can inspect requests of engine
+///
+/// ```no_run
+/// use core_tests::simulated_exchange::{SimulatedExchangeBuilder, SimulatedVenue};
+/// use mmb_core::exchanges::traits::ExchangeClientBuilder;
+///
+/// let venue = SimulatedVenue::new();
+/// let builder: Box<dyn ExchangeClientBuilder> =
+///     Box::new(SimulatedExchangeBuilder::new(venue.clone()));
+/// ```
+pub struct SimulatedVenue {
+    currency_pair: CurrencyPair,
+    state: Mutex<VenueState>,
+}
+
+impl SimulatedVenue {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            currency_pair: CurrencyPair::from_codes("cnd".into(), "btc".into()),
+            state: Mutex::new(VenueState::default()),
+        })
+    }
+
+    /// The only market of venue
+    pub fn currency_pair(&self) -> CurrencyPair {
+        self.currency_pair
+    }
+
+    /// Cancellation requests are accepted, but orders stay open and cancellation events aren't sent
+    pub fn ignore_cancellation(&self) {
+        self.state().is_cancellation_ignored = true;
+    }
+
+    pub fn open_orders(&self) -> Vec<ClientOrderId> {
+        self.state()
+            .open_orders
+            .iter()
+            .map(|x| x.client_order_id.clone())
+            .collect()
+    }
+
+    /// Orders in the same order as cancellation requests were received
+    pub fn cancel_requests(&self) -> Vec<ClientOrderId> {
+        self.state().cancel_requests.clone()
+    }
+
+    /// Starts local websocket server for main websocket of client. Returned receiver is completed
+    /// when connection is closed by engine
+    pub async fn start_websocket_server(&self) -> Result<oneshot::Receiver<()>> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("ws://{}", listener.local_addr()?).parse()?;
+        self.state().websocket_url = Some(url);
+
+        let (closed_tx, closed_rx) = oneshot::channel();
+        let _ = tokio::spawn(async move {
+            let (stream, _) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(error) => {
+                    log::error!("Unable to accept connection: {:?}", error);
+                    return;
+                }
+            };
+            let mut websocket = match tokio_tungstenite::accept_async(stream).await {
+                Ok(websocket) => websocket,
+                Err(error) => {
+                    log::error!("Unable to accept websocket: {:?}", error);
+                    return;
+                }
+            };
+
+            while let Some(Ok(message)) = websocket.next().await {
+                if message.is_close() {
+                    break;
+                }
+            }
+
+            let _ = closed_tx.send(());
+        });
+
+        Ok(closed_rx)
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, VenueState> {
+        self.state
+            .lock()
+            .expect("venue state shouldn't be poisoned")
+    }
+}
+
+pub struct SimulatedExchangeBuilder {
+    venue: Arc<SimulatedVenue>,
+}
+
+impl SimulatedExchangeBuilder {
+    pub fn new(venue: Arc<SimulatedVenue>) -> Self {
+        Self { venue }
+    }
+}
+
+impl ExchangeClientBuilder for SimulatedExchangeBuilder {
+    fn create_exchange_client(
+        &self,
+        exchange_settings: ExchangeSettings,
+        _events_channel: broadcast::Sender<ExchangeEvent>,
+        _lifetime_manager: Arc<AppLifetimeManager>,
+    ) -> ExchangeClientBuilderResult {
+        ExchangeClientBuilderResult {
+            client: Box::new(SimulatedExchangeClient::new(
+                self.venue.clone(),
+                exchange_settings,
+            )) as BoxExchangeClient,
+            features: ExchangeFeatures::new(
+                OpenOrdersType::AllCurrencyPair,
+                RestFillsFeatures::default(),
+                OrderFeatures::default(),
+                OrderTradeOption::default(),
+                WebSocketOptions::default(),
+                false,
+                true,
+                AllowedEventSourceType::All,
+                AllowedEventSourceType::All,
+            ),
+        }
+    }
+
+    fn get_timeout_arguments(&self) -> RequestTimeoutArguments {
+        RequestTimeoutArguments::from_requests_per_minute(1200)
+    }
+}
+
+pub struct SimulatedExchangeClient {
+    venue: Arc<SimulatedVenue>,
+    settings: ExchangeSettings,
+    supported_currencies: DashMap<CurrencyId, CurrencyCode>,
+    order_created_callback: Mutex<OrderCallback>,
+    order_cancelled_callback: Mutex<OrderCallback>,
+}
+
+impl SimulatedExchangeClient {
+    pub fn new(venue: Arc<SimulatedVenue>, settings: ExchangeSettings) -> Self {
+        Self {
+            venue,
+            settings,
+            supported_currencies: Default::default(),
+            order_created_callback: Mutex::new(Box::new(|_, _, _| {})),
+            order_cancelled_callback: Mutex::new(Box::new(|_, _, _| {})),
+        }
+    }
+
+    fn ok_response(content: &str) -> RestRequestOutcome {
+        RestRequestOutcome::new(content.to_owned(), StatusCode::OK)
+    }
+
+    fn raise_order_event(
+        callback: &Mutex<OrderCallback>,
+        client_order_id: ClientOrderId,
+        exchange_order_id: ExchangeOrderId,
+    ) {
+        let mut callback = callback.lock().expect("callback shouldn't be poisoned");
+        (*callback)(
+            client_order_id,
+            exchange_order_id,
+            EventSourceType::WebSocket,
+        );
+    }
+}
+
+#[async_trait]
+impl ExchangeClient for SimulatedExchangeClient {
+    async fn request_all_symbols(&self) -> Result<RestRequestOutcome> {
+        Ok(Self::ok_response("[]"))
+    }
+
+    async fn create_order(&self, order: &OrderCreating) -> Result<RestRequestOutcome> {
+        let header = &order.header;
+        let exchange_order_id = ExchangeOrderId::unique_id();
+        self.venue.state().open_orders.push(OrderInfo::new(
+            header.currency_pair,
+            exchange_order_id.clone(),
+            header.client_order_id.clone(),
+            header.side,
+            OrderStatus::Created,
+            order.price,
+            header.amount,
+            dec!(0),
+            dec!(0),
+            None,
+            None,
+            None,
+        ));
+
+        Self::raise_order_event(
+            &self.order_created_callback,
+            header.client_order_id.clone(),
+            exchange_order_id.clone(),
+        );
+
+        Ok(Self::ok_response(exchange_order_id.as_str()))
+    }
+
+    async fn request_cancel_order(&self, order: &OrderCancelling) -> Result<RestRequestOutcome> {
+        let client_order_id = order.header.client_order_id.clone();
+        let is_cancelled = {
+            let mut state = self.venue.state();
+            state.cancel_requests.push(client_order_id.clone());
+            if state.is_cancellation_ignored {
+                false
+            } else {
+                state
+                    .open_orders
+                    .retain(|x| x.exchange_order_id != order.exchange_order_id);
+                true
+            }
+        };
+
+        if is_cancelled {
+            Self::raise_order_event(
+                &self.order_cancelled_callback,
+                client_order_id,
+                order.exchange_order_id.clone(),
+            );
+        }
+
+        Ok(Self::ok_response(order.exchange_order_id.as_str()))
+    }
+
+    async fn cancel_all_orders(&self, currency_pair: CurrencyPair) -> Result<()> {
+        self.venue
+            .state()
+            .open_orders
+            .retain(|x| x.currency_pair != currency_pair);
+        Ok(())
+    }
+
+    async fn get_open_orders(&self) -> Result<Vec<OrderInfo>> {
+        Ok(self.venue.state().open_orders.clone())
+    }
+
+    async fn get_open_orders_by_currency_pair(
+        &self,
+        currency_pair: CurrencyPair,
+    ) -> Result<Vec<OrderInfo>> {
+        Ok(self
+            .venue
+            .state()
+            .open_orders
+            .iter()
+            .filter(|x| x.currency_pair == currency_pair)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_order_info(&self, order: &OrderRef) -> Result<OrderInfo, ExchangeError> {
+        let client_order_id = order.client_order_id();
+        self.venue
+            .state()
+            .open_orders
+            .iter()
+            .find(|x| x.client_order_id == client_order_id)
+            .cloned()
+            .ok_or_else(|| {
+                ExchangeError::new(
+                    ExchangeErrorType::OrderNotFound,
+                    format!("Order {} isn't open", client_order_id),
+                    None,
+                )
+            })
+    }
+
+    async fn request_my_trades(
+        &self,
+        _symbol: &Symbol,
+        _last_date_time: Option<DateTime>,
+    ) -> Result<RestRequestOutcome> {
+        Ok(Self::ok_response("[]"))
+    }
+
+    async fn request_get_position(&self) -> Result<RestRequestOutcome> {
+        Ok(Self::ok_response("[]"))
+    }
+
+    async fn request_get_balance_and_position(&self) -> Result<RestRequestOutcome> {
+        Ok(Self::ok_response("[]"))
+    }
+
+    async fn get_balance(&self) -> Result<ExchangeBalancesAndPositions> {
+        let codes = self.venue.currency_pair.to_codes();
+        let balances = [codes.base, codes.quote]
+            .into_iter()
+            .map(|currency_code| ExchangeBalance {
+                currency_code,
+                balance: CURRENCY_BALANCE,
+            })
+            .collect();
+
+        Ok(ExchangeBalancesAndPositions {
+            balances,
+            positions: None,
+        })
+    }
+
+    async fn request_close_position(
+        &self,
+        _position: &ActivePosition,
+        _price: Option<Price>,
+    ) -> Result<RestRequestOutcome> {
+        bail!("Positions aren't supported by simulated exchange")
+    }
+}
+
+#[async_trait]
+impl Support for SimulatedExchangeClient {
+    fn get_order_id(&self, response: &RestRequestOutcome) -> Result<ExchangeOrderId> {
+        Ok(response.content.as_str().into())
+    }
+
+    fn on_websocket_message(&self, _msg: &str) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_connecting(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_order_created_callback(&self, callback: OrderCallback) {
+        *self
+            .order_created_callback
+            .lock()
+            .expect("callback shouldn't be poisoned") = callback;
+    }
+
+    fn set_order_cancelled_callback(&self, callback: OrderCallback) {
+        *self
+            .order_cancelled_callback
+            .lock()
+            .expect("callback shouldn't be poisoned") = callback;
+    }
+
+    fn set_handle_order_filled_callback(
+        &self,
+        _callback: Box<dyn FnMut(FillEventData) + Send + Sync>,
+    ) {
+    }
+
+    fn set_handle_trade_callback(
+        &self,
+        _callback: Box<
+            dyn FnMut(CurrencyPair, TradeStream, TradeId, Price, Amount, OrderSide, DateTime)
+                + Send
+                + Sync,
+        >,
+    ) {
+    }
+
+    fn set_traded_specific_currencies(&self, _currencies: Vec<SpecificCurrencyPair>) {}
+
+    fn is_websocket_enabled(&self, role: WebSocketRole) -> bool {
+        role == WebSocketRole::Main && self.venue.state().websocket_url.is_some()
+    }
+
+    async fn create_ws_url(&self, _role: WebSocketRole) -> Result<Url> {
+        self.venue
+            .state()
+            .websocket_url
+            .clone()
+            .context("Websocket server of simulated exchange isn't started")
+    }
+
+    fn get_specific_currency_pair(&self, currency_pair: CurrencyPair) -> SpecificCurrencyPair {
+        currency_pair.as_str().into()
+    }
+
+    fn get_supported_currencies(&self) -> &DashMap<CurrencyId, CurrencyCode> {
+        &self.supported_currencies
+    }
+
+    fn should_log_message(&self, _message: &str) -> bool {
+        true
+    }
+
+    fn parse_all_symbols(&self, _response: &RestRequestOutcome) -> Result<Vec<Arc<Symbol>>> {
+        let codes = self.venue.currency_pair.to_codes();
+
+        Ok(vec![Arc::new(Symbol::new(
+            true,
+            false,
+            codes.base.as_str().into(),
+            codes.base,
+            codes.quote.as_str().into(),
+            codes.quote,
+            None,
+            None,
+            None,
+            None,
+            None,
+            codes.base,
+            None,
+            Precision::ByTick { tick: dec!(0.1) },
+            Precision::ByTick { tick: dec!(0.001) },
+        ))])
+    }
+
+    fn parse_get_my_trades(
+        &self,
+        _response: &RestRequestOutcome,
+        _last_date_time: Option<DateTime>,
+    ) -> Result<Vec<OrderTrade>> {
+        Ok(vec![])
+    }
+
+    fn get_settings(&self) -> &ExchangeSettings {
+        &self.settings
+    }
+
+    fn parse_get_position(&self, _response: &RestRequestOutcome) -> Vec<ActivePosition> {
+        vec![]
+    }
+
+    fn parse_close_position(&self, _response: &RestRequestOutcome) -> Result<ClosedPosition> {
+        bail!("Positions aren't supported by simulated exchange")
+    }
+
+    fn parse_get_balance(&self, _response: &RestRequestOutcome) -> ExchangeBalancesAndPositions {
+        ExchangeBalancesAndPositions {
+            balances: vec![],
+            positions: None,
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use core_tests::order::OrderProxy;
+use core_tests::simulated_exchange::{SimulatedExchangeBuilder, SimulatedVenue};
+use mmb_core::disposition_execution::{PriceSlot, TradingContext};
+use mmb_core::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId};
+use mmb_core::exchanges::general::exchange::Exchange;
+use mmb_core::explanation::Explanation;
+use mmb_core::lifecycle::app_lifetime_manager::ActionAfterGracefulShutdown;
+use mmb_core::lifecycle::engine_builder::TradingEngineBuilder;
+use mmb_core::lifecycle::trading_engine::TradingEngine;
+use mmb_core::order_book::local_snapshot_service::LocalSnapshotsService;
+use mmb_core::orders::cancel_priority::{CancelClass, CancelPrioritySettings};
+use mmb_core::orders::order::{ClientOrderId, OrderSnapshot, OrderType};
+use mmb_core::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use mmb_core::settings::{
+    BaseStrategySettings, CoreSettings, CurrencyPairSetting, ExchangeSettings,
+};
+use mmb_core::strategies::disposition_strategy::DispositionStrategy;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::DateTime;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use tokio::time::{timeout, Instant};
+
+/// Lifetime manager of engine is global, so engines of different tests can't run in parallel
+static ENGINE_LOCK: Mutex<()> = Mutex::new(());
+
+const STRATEGY_NAME: &str = "GracefulShutdownTest";
+
+fn exchange_account_id() -> ExchangeAccountId {
+    "Simulated_0".parse().expect("in test")
+}
+
+#[derive(Default, Clone, Debug, Deserialize, Serialize)]
+struct TestStrategySettings {}
+
+impl BaseStrategySettings for TestStrategySettings {
+    fn exchange_account_id(&self) -> ExchangeAccountId {
+        exchange_account_id()
+    }
+
+    fn currency_pair(&self) -> CurrencyPair {
+        OrderProxy::default_currency_pair()
+    }
+
+    fn max_amount(&self) -> Amount {
+        dec!(1)
+    }
+}
+
+/// Strategy which doesn't trade, so only orders of test are open on exchange
+struct IdleStrategy;
+
+impl DispositionStrategy for IdleStrategy {
+    fn calculate_trading_context(
+        &mut self,
+        _now: DateTime,
+        _local_snapshots_service: &LocalSnapshotsService,
+        _explanation: &mut Explanation,
+    ) -> Option<TradingContext> {
+        None
+    }
+
+    fn handle_order_fill(
+        &self,
+        _cloned_order: &Arc<OrderSnapshot>,
+        _price_slot: &PriceSlot,
+        _target_eai: ExchangeAccountId,
+        _cancellation_token: CancellationToken,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn configuration_descriptor(&self) -> ConfigurationDescriptor {
+        ConfigurationDescriptor::new("IdleStrategy".into(), "graceful_shutdown_test".into())
+    }
+}
+
+/// Runs test on its own single-threaded runtime. Tokio clock is paused if `is_clock_paused`
+/// is set, so timeouts of engine elapse as soon as there is no other work
+fn run_engine_test(is_clock_paused: bool, test: impl Future<Output = ()>) {
+    let _guard = ENGINE_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("in test");
+    runtime.block_on(async move {
+        if is_clock_paused {
+            tokio::time::pause();
+        }
+        test.await
+    });
+}
+
+async fn launch_engine(venue: &Arc<SimulatedVenue>, core_settings: CoreSettings) -> TradingEngine {
+    let mut exchange_settings = ExchangeSettings::new_short(
+        exchange_account_id(),
+        "api_key".to_owned(),
+        "secret_key".to_owned(),
+        false,
+        false,
+    );
+    let codes = venue.currency_pair().to_codes();
+    exchange_settings.currency_pairs = Some(vec![CurrencyPairSetting::Ordinary {
+        base: codes.base,
+        quote: codes.quote,
+    }]);
+
+    TradingEngineBuilder::new()
+        .add_exchange(
+            Box::new(SimulatedExchangeBuilder::new(venue.clone())),
+            exchange_settings,
+        )
+        .add_strategy(TestStrategySettings::default(), |_, _| {
+            Box::new(IdleStrategy)
+        })
+        .with_core_settings(core_settings)
+        .with_control_panel(false)
+        .build()
+        .await
+        .expect("engine should be launched")
+        .expect("graceful shutdown shouldn't be requested during launch")
+}
+
+async fn create_order(exchange: &Arc<Exchange>, order_type: OrderType) -> ClientOrderId {
+    let mut order_proxy = OrderProxy::new(
+        exchange_account_id(),
+        Some(STRATEGY_NAME.to_owned()),
+        CancellationToken::default(),
+        dec!(0.1),
+        dec!(1),
+    );
+    order_proxy.order_type = order_type;
+
+    order_proxy
+        .create_order(exchange.clone())
+        .await
+        .expect("order should be created on simulated exchange")
+        .client_order_id()
+}
+
+#[test]
+fn open_orders_are_cancelled_by_priority() {
+    run_engine_test(false, async {
+        let venue = SimulatedVenue::new();
+        let core_settings = CoreSettings {
+            cancel_priorities: Some(vec![CancelPrioritySettings {
+                strategy_name: None,
+                order: vec![CancelClass::Hedge, CancelClass::Quote],
+            }]),
+            ..Default::default()
+        };
+        let engine = launch_engine(&venue, core_settings).await;
+        let exchange = engine.exchange(exchange_account_id()).expect("in test");
+
+        let quote_order = create_order(&exchange, OrderType::Limit).await;
+        let algo_order = create_order(&exchange, OrderType::StopLoss).await;
+        let hedge_order = create_order(&exchange, OrderType::ClosePosition).await;
+
+        engine.stop("test of cancel priorities");
+        let action = engine.run().await;
+
+        assert!(matches!(action, ActionAfterGracefulShutdown::Nothing));
+        assert_eq!(venue.cancel_requests(), vec![hedge_order, quote_order]);
+        assert_eq!(venue.open_orders(), vec![algo_order]);
+    });
+}
+
+#[test]
+fn cancellation_of_orders_is_stopped_by_timeout() {
+    run_engine_test(true, async {
+        let venue = SimulatedVenue::new();
+        venue.ignore_cancellation();
+        let core_settings = CoreSettings::default();
+        let cancel_timeout = core_settings.timeouts.cancel_orders.duration();
+        let engine = launch_engine(&venue, core_settings).await;
+        let exchange = engine.exchange(exchange_account_id()).expect("in test");
+
+        let order = create_order(&exchange, OrderType::Limit).await;
+
+        let shutdown_started = Instant::now();
+        engine.stop("test of cancellation timeout");
+        let action = engine.run().await;
+
+        assert!(matches!(action, ActionAfterGracefulShutdown::Nothing));
+        assert!(shutdown_started.elapsed() >= cancel_timeout);
+        assert_eq!(venue.cancel_requests(), vec![order.clone()]);
+        assert_eq!(venue.open_orders(), vec![order]);
+    });
+}
+
+#[test]
+fn websockets_are_disconnected_on_restart() {
+    run_engine_test(false, async {
+        let venue = SimulatedVenue::new();
+        let websocket_closed = venue
+            .start_websocket_server()
+            .await
+            .expect("websocket server should be started");
+        let engine = launch_engine(&venue, CoreSettings::default()).await;
+        let exchange = engine.exchange(exchange_account_id()).expect("in test");
+
+        let order = create_order(&exchange, OrderType::Limit).await;
+
+        let _ = engine
+            .context()
+            .lifetime_manager
+            .spawn_graceful_shutdown_with_action(
+                "test of restart".to_owned(),
+                ActionAfterGracefulShutdown::Restart,
+            );
+        let action = engine.run().await;
+
+        assert!(matches!(action, ActionAfterGracefulShutdown::Restart));
+        assert_eq!(venue.cancel_requests(), vec![order]);
+        assert!(venue.open_orders().is_empty());
+        timeout(Duration::from_secs(5), websocket_closed)
+            .await
+            .expect("websocket should be closed by engine")
+            .expect("websocket server shouldn't be dropped");
+    });
+}
@@ -0,0 +1,242 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use core_tests::order::OrderProxy;
+use core_tests::simulated_exchange::{SimulatedExchangeBuilder, SimulatedVenue};
+use mmb_core::disposition_execution::{PriceSlot, TradingContext};
+use mmb_core::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId};
+use mmb_core::exchanges::events::ExchangeEvent;
+use mmb_core::exchanges::general::exchange::Exchange;
+use mmb_core::explanation::Explanation;
+use mmb_core::lifecycle::engine_builder::TradingEngineBuilder;
+use mmb_core::lifecycle::trading_engine::TradingEngine;
+use mmb_core::order_book::local_snapshot_service::LocalSnapshotsService;
+use mmb_core::orders::event::OrderEventType;
+use mmb_core::orders::order::{ClientOrderId, OrderSide, OrderSnapshot};
+use mmb_core::orders::order_builder::OrderBuilder;
+use mmb_core::orders::pool::OrderRef;
+use mmb_core::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use mmb_core::settings::{
+    BaseStrategySettings, CoreSettings, CurrencyPairSetting, ExchangeSettings,
+};
+use mmb_core::strategies::disposition_strategy::DispositionStrategy;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::DateTime;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tokio::time::timeout;
+
+/// Lifetime manager of engine is global, so engines of different tests can't run in parallel
+static ENGINE_LOCK: Mutex<()> = Mutex::new(());
+
+const STRATEGY_NAME: &str = "OrderExpiryTest";
+
+fn exchange_account_id() -> ExchangeAccountId {
+    "Simulated_0".parse().expect("in test")
+}
+
+#[derive(Default, Clone, Debug, Deserialize, Serialize)]
+struct TestStrategySettings {}
+
+impl BaseStrategySettings for TestStrategySettings {
+    fn exchange_account_id(&self) -> ExchangeAccountId {
+        exchange_account_id()
+    }
+
+    fn currency_pair(&self) -> CurrencyPair {
+        OrderProxy::default_currency_pair()
+    }
+
+    fn max_amount(&self) -> Amount {
+        dec!(1)
+    }
+}
+
+/// Strategy which doesn't trade, so only orders of test are open on exchange
+struct IdleStrategy;
+
+impl DispositionStrategy for IdleStrategy {
+    fn calculate_trading_context(
+        &mut self,
+        _now: DateTime,
+        _local_snapshots_service: &LocalSnapshotsService,
+        _explanation: &mut Explanation,
+    ) -> Option<TradingContext> {
+        None
+    }
+
+    fn handle_order_fill(
+        &self,
+        _cloned_order: &Arc<OrderSnapshot>,
+        _price_slot: &PriceSlot,
+        _target_eai: ExchangeAccountId,
+        _cancellation_token: CancellationToken,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn configuration_descriptor(&self) -> ConfigurationDescriptor {
+        ConfigurationDescriptor::new(STRATEGY_NAME.into(), "order_expiry_test".into())
+    }
+}
+
+/// Expiry is scheduled by wall clock, so tokio clock isn't paused
+fn run_engine_test(test: impl Future<Output = ()>) {
+    let _guard = ENGINE_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("in test");
+    runtime.block_on(test);
+}
+
+async fn launch_engine(venue: &Arc<SimulatedVenue>) -> TradingEngine {
+    let mut exchange_settings = ExchangeSettings::new_short(
+        exchange_account_id(),
+        "api_key".to_owned(),
+        "secret_key".to_owned(),
+        false,
+        false,
+    );
+    let codes = venue.currency_pair().to_codes();
+    exchange_settings.currency_pairs = Some(vec![CurrencyPairSetting::Ordinary {
+        base: codes.base,
+        quote: codes.quote,
+    }]);
+
+    TradingEngineBuilder::new()
+        .add_exchange(
+            Box::new(SimulatedExchangeBuilder::new(venue.clone())),
+            exchange_settings,
+        )
+        .add_strategy(TestStrategySettings::default(), |_, _| {
+            Box::new(IdleStrategy)
+        })
+        .with_core_settings(CoreSettings::default())
+        .with_control_panel(false)
+        .build()
+        .await
+        .expect("engine should be launched")
+        .expect("graceful shutdown shouldn't be requested during launch")
+}
+
+async fn create_order(exchange: &Exchange, expire_at: Option<DateTime>) -> OrderRef {
+    let mut builder = OrderBuilder::new(
+        exchange.exchange_account_id,
+        OrderProxy::default_currency_pair(),
+    )
+    .side(OrderSide::Buy)
+    .limit(dec!(0.1))
+    .amount(dec!(1))
+    .strategy_name(STRATEGY_NAME);
+    if let Some(expire_at) = expire_at {
+        builder = builder.expire_at(expire_at);
+    }
+
+    let order = builder.build().expect("in test");
+    exchange
+        .create_order(&order, None, CancellationToken::default())
+        .await
+        .expect("order should be created on simulated exchange")
+}
+
+/// Types of events of order in the same order as they were received
+async fn wait_order_events(
+    events: &mut broadcast::Receiver<ExchangeEvent>,
+    client_order_id: &ClientOrderId,
+    is_last: impl Fn(&OrderEventType) -> bool,
+) -> Vec<OrderEventType> {
+    let mut order_events = Vec::new();
+    timeout(Duration::from_secs(5), async {
+        loop {
+            let event = events.recv().await.expect("events channel is open");
+            if let ExchangeEvent::OrderEvent(order_event) = event {
+                if &order_event.order.client_order_id() != client_order_id {
+                    continue;
+                }
+
+                let is_last = is_last(&order_event.event_type);
+                order_events.push(order_event.event_type);
+                if is_last {
+                    return;
+                }
+            }
+        }
+    })
+    .await
+    .expect("expected event of order should be received");
+
+    order_events
+}
+
+async fn stop_engine(engine: TradingEngine) {
+    engine.stop("end of order expiry test");
+    let _ = engine.run().await;
+}
+
+#[test]
+fn order_is_cancelled_at_expire_at() {
+    run_engine_test(async {
+        let venue = SimulatedVenue::new();
+        let engine = launch_engine(&venue).await;
+        let exchange = engine.exchange(exchange_account_id()).expect("in test");
+        let mut events = engine.events();
+
+        let expire_at = chrono::Utc::now() + chrono::Duration::milliseconds(300);
+        let order = create_order(&exchange, Some(expire_at)).await;
+        assert!(venue.cancel_requests().is_empty());
+
+        let order_events = wait_order_events(&mut events, &order.client_order_id(), |x| {
+            matches!(x, OrderEventType::OrderExpired)
+        })
+        .await;
+
+        assert!(chrono::Utc::now() >= expire_at);
+        assert_eq!(venue.cancel_requests(), vec![order.client_order_id()]);
+        assert!(venue.open_orders().is_empty());
+        // Expiry is reported right after cancellation
+        let cancelled = &order_events[order_events.len() - 2];
+        assert!(matches!(cancelled, OrderEventType::CancelOrderSucceeded));
+
+        stop_engine(engine).await;
+    });
+}
+
+#[test]
+fn order_without_expire_at_is_not_expired_on_cancellation() {
+    run_engine_test(async {
+        let venue = SimulatedVenue::new();
+        let engine = launch_engine(&venue).await;
+        let exchange = engine.exchange(exchange_account_id()).expect("in test");
+        let mut events = engine.events();
+
+        let order = create_order(&exchange, None).await;
+        let outcome = exchange
+            .wait_cancel_order_confirmed(order.clone(), None, true, CancellationToken::default())
+            .await
+            .expect("order should be cancelled on simulated exchange");
+        assert!(outcome.is_finished());
+
+        let mut order_events = wait_order_events(&mut events, &order.client_order_id(), |x| {
+            matches!(x, OrderEventType::CancelOrderSucceeded)
+        })
+        .await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        while let Ok(event) = events.try_recv() {
+            if let ExchangeEvent::OrderEvent(order_event) = event {
+                if order_event.order.client_order_id() == order.client_order_id() {
+                    order_events.push(order_event.event_type);
+                }
+            }
+        }
+
+        assert!(order_events
+            .iter()
+            .all(|x| !matches!(x, OrderEventType::OrderExpired)));
+
+        stop_engine(engine).await;
+    });
+}
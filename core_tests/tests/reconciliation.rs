@@ -0,0 +1,192 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use core_tests::order::OrderProxy;
+use core_tests::simulated_exchange::{SimulatedExchangeBuilder, SimulatedVenue};
+use mmb_core::disposition_execution::{PriceSlot, TradingContext};
+use mmb_core::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId};
+use mmb_core::exchanges::general::exchange::Exchange;
+use mmb_core::explanation::Explanation;
+use mmb_core::lifecycle::engine_builder::TradingEngineBuilder;
+use mmb_core::lifecycle::reconciliation::{reconcile_exchanges, ExchangeReconciliation};
+use mmb_core::lifecycle::trading_engine::TradingEngine;
+use mmb_core::order_book::local_snapshot_service::LocalSnapshotsService;
+use mmb_core::orders::order::{OrderSide, OrderSnapshot};
+use mmb_core::orders::order_builder::OrderBuilder;
+use mmb_core::orders::pool::OrderRef;
+use mmb_core::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use mmb_core::settings::{
+    BaseStrategySettings, CoreSettings, CurrencyPairSetting, ExchangeSettings,
+};
+use mmb_core::strategies::disposition_strategy::DispositionStrategy;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::DateTime;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+/// Lifetime manager of engine is global, so engines of different tests can't run in parallel
+static ENGINE_LOCK: Mutex<()> = Mutex::new(());
+
+const STRATEGY_NAME: &str = "ReconciliationTest";
+
+fn exchange_account_id() -> ExchangeAccountId {
+    "Simulated_0".parse().expect("in test")
+}
+
+#[derive(Default, Clone, Debug, Deserialize, Serialize)]
+struct TestStrategySettings {}
+
+impl BaseStrategySettings for TestStrategySettings {
+    fn exchange_account_id(&self) -> ExchangeAccountId {
+        exchange_account_id()
+    }
+
+    fn currency_pair(&self) -> CurrencyPair {
+        OrderProxy::default_currency_pair()
+    }
+
+    fn max_amount(&self) -> Amount {
+        dec!(1)
+    }
+}
+
+/// Strategy which doesn't trade, so only orders of test are open on exchange
+struct IdleStrategy;
+
+impl DispositionStrategy for IdleStrategy {
+    fn calculate_trading_context(
+        &mut self,
+        _now: DateTime,
+        _local_snapshots_service: &LocalSnapshotsService,
+        _explanation: &mut Explanation,
+    ) -> Option<TradingContext> {
+        None
+    }
+
+    fn handle_order_fill(
+        &self,
+        _cloned_order: &Arc<OrderSnapshot>,
+        _price_slot: &PriceSlot,
+        _target_eai: ExchangeAccountId,
+        _cancellation_token: CancellationToken,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn configuration_descriptor(&self) -> ConfigurationDescriptor {
+        ConfigurationDescriptor::new(STRATEGY_NAME.into(), "reconciliation_test".into())
+    }
+}
+
+fn run_engine_test(test: impl Future<Output = ()>) {
+    let _guard = ENGINE_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("in test");
+    runtime.block_on(test);
+}
+
+async fn launch_engine(venue: &Arc<SimulatedVenue>) -> TradingEngine {
+    let mut exchange_settings = ExchangeSettings::new_short(
+        exchange_account_id(),
+        "api_key".to_owned(),
+        "secret_key".to_owned(),
+        false,
+        false,
+    );
+    let codes = venue.currency_pair().to_codes();
+    exchange_settings.currency_pairs = Some(vec![CurrencyPairSetting::Ordinary {
+        base: codes.base,
+        quote: codes.quote,
+    }]);
+
+    TradingEngineBuilder::new()
+        .add_exchange(
+            Box::new(SimulatedExchangeBuilder::new(venue.clone())),
+            exchange_settings,
+        )
+        .add_strategy(TestStrategySettings::default(), |_, _| {
+            Box::new(IdleStrategy)
+        })
+        .with_core_settings(CoreSettings::default())
+        .with_control_panel(false)
+        .build()
+        .await
+        .expect("engine should be launched")
+        .expect("graceful shutdown shouldn't be requested during launch")
+}
+
+async fn create_order(exchange: &Exchange) -> OrderRef {
+    let order = OrderBuilder::new(
+        exchange.exchange_account_id,
+        OrderProxy::default_currency_pair(),
+    )
+    .side(OrderSide::Buy)
+    .limit(dec!(0.1))
+    .amount(dec!(1))
+    .strategy_name(STRATEGY_NAME)
+    .build()
+    .expect("in test");
+
+    exchange
+        .create_order(&order, None, CancellationToken::default())
+        .await
+        .expect("order should be created on simulated exchange")
+}
+
+async fn reconcile(engine: &TradingEngine) -> ExchangeReconciliation {
+    let mut report = reconcile_exchanges(&engine.context()).await;
+    assert_eq!(report.exchanges.len(), 1);
+    report.exchanges.remove(0)
+}
+
+async fn stop_engine(engine: TradingEngine) {
+    engine.stop("end of reconciliation test");
+    let _ = engine.run().await;
+}
+
+#[test]
+fn report_contains_state_of_exchange_account() {
+    run_engine_test(async {
+        let venue = SimulatedVenue::new();
+        let engine = launch_engine(&venue).await;
+
+        let exchange_report = reconcile(&engine).await;
+
+        assert_eq!(exchange_report.exchange_account_id, exchange_account_id());
+        assert!(exchange_report.open_orders.is_empty());
+        assert!(exchange_report.positions.is_empty());
+        assert!(exchange_report.errors.is_empty());
+        let codes = venue.currency_pair().to_codes();
+        assert!(exchange_report.balances.contains_key(&codes.base));
+        assert!(exchange_report.balances.contains_key(&codes.quote));
+
+        stop_engine(engine).await;
+    });
+}
+
+#[test]
+fn report_contains_orders_open_on_exchange() {
+    run_engine_test(async {
+        let venue = SimulatedVenue::new();
+        let engine = launch_engine(&venue).await;
+        let exchange = engine.exchange(exchange_account_id()).expect("in test");
+        let order = create_order(&exchange).await;
+
+        let exchange_report = reconcile(&engine).await;
+
+        let open_orders = exchange_report
+            .open_orders
+            .iter()
+            .map(|x| x.client_order_id.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(open_orders, vec![order.client_order_id()]);
+        assert!(exchange_report.errors.is_empty());
+        // Reconciliation doesn't change state of exchange
+        assert_eq!(venue.open_orders(), vec![order.client_order_id()]);
+
+        stop_engine(engine).await;
+    });
+}
@@ -0,0 +1,306 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use core_tests::order::OrderProxy;
+use core_tests::simulated_exchange::{SimulatedExchangeBuilder, SimulatedVenue};
+use futures::future::join;
+use mmb_core::disposition_execution::{PriceSlot, TradingContext};
+use mmb_core::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId};
+use mmb_core::exchanges::general::exchange::Exchange;
+use mmb_core::explanation::Explanation;
+use mmb_core::lifecycle::engine_builder::TradingEngineBuilder;
+use mmb_core::lifecycle::trading_engine::TradingEngine;
+use mmb_core::order_book::local_snapshot_service::LocalSnapshotsService;
+use mmb_core::orders::order::{ClientOrderId, OrderRole, OrderSide, OrderSnapshot};
+use mmb_core::orders::order_builder::OrderBuilder;
+use mmb_core::orders::pool::OrderRef;
+use mmb_core::orders::spread_order::{HedgePolicy, SpreadLeg, SpreadOrder, SpreadOrderStatus};
+use mmb_core::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use mmb_core::settings::{
+    BaseStrategySettings, CoreSettings, CurrencyPairSetting, ExchangeSettings,
+};
+use mmb_core::strategies::disposition_strategy::DispositionStrategy;
+use mmb_core::test_util::FillEventDataBuilder;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::DateTime;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use tokio::time::timeout;
+
+/// Lifetime manager of engine is global, so engines of different tests can't run in parallel
+static ENGINE_LOCK: Mutex<()> = Mutex::new(());
+
+const STRATEGY_NAME: &str = "SpreadOrderTest";
+
+fn exchange_account_id() -> ExchangeAccountId {
+    "Simulated_0".parse().expect("in test")
+}
+
+fn configuration_descriptor() -> ConfigurationDescriptor {
+    ConfigurationDescriptor::new(STRATEGY_NAME.into(), "spread_order_test".into())
+}
+
+#[derive(Default, Clone, Debug, Deserialize, Serialize)]
+struct TestStrategySettings {}
+
+impl BaseStrategySettings for TestStrategySettings {
+    fn exchange_account_id(&self) -> ExchangeAccountId {
+        exchange_account_id()
+    }
+
+    fn currency_pair(&self) -> CurrencyPair {
+        OrderProxy::default_currency_pair()
+    }
+
+    fn max_amount(&self) -> Amount {
+        dec!(1)
+    }
+}
+
+/// Strategy which doesn't trade, so only orders of spread order are open on exchange
+struct IdleStrategy;
+
+impl DispositionStrategy for IdleStrategy {
+    fn calculate_trading_context(
+        &mut self,
+        _now: DateTime,
+        _local_snapshots_service: &LocalSnapshotsService,
+        _explanation: &mut Explanation,
+    ) -> Option<TradingContext> {
+        None
+    }
+
+    fn handle_order_fill(
+        &self,
+        _cloned_order: &Arc<OrderSnapshot>,
+        _price_slot: &PriceSlot,
+        _target_eai: ExchangeAccountId,
+        _cancellation_token: CancellationToken,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn configuration_descriptor(&self) -> ConfigurationDescriptor {
+        configuration_descriptor()
+    }
+}
+
+/// Runs test on its own single-threaded runtime. Tokio clock is paused if `is_clock_paused`
+/// is set, so timeouts of engine elapse as soon as there is no other work
+fn run_engine_test(is_clock_paused: bool, test: impl Future<Output = ()>) {
+    let _guard = ENGINE_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("in test");
+    runtime.block_on(async move {
+        if is_clock_paused {
+            tokio::time::pause();
+        }
+        test.await
+    });
+}
+
+async fn launch_engine(venue: &Arc<SimulatedVenue>) -> TradingEngine {
+    let mut exchange_settings = ExchangeSettings::new_short(
+        exchange_account_id(),
+        "api_key".to_owned(),
+        "secret_key".to_owned(),
+        false,
+        false,
+    );
+    let codes = venue.currency_pair().to_codes();
+    exchange_settings.currency_pairs = Some(vec![CurrencyPairSetting::Ordinary {
+        base: codes.base,
+        quote: codes.quote,
+    }]);
+
+    TradingEngineBuilder::new()
+        .add_exchange(
+            Box::new(SimulatedExchangeBuilder::new(venue.clone())),
+            exchange_settings,
+        )
+        .add_strategy(TestStrategySettings::default(), |_, _| {
+            Box::new(IdleStrategy)
+        })
+        .with_core_settings(CoreSettings::default())
+        .with_control_panel(false)
+        .build()
+        .await
+        .expect("engine should be launched")
+        .expect("graceful shutdown shouldn't be requested during launch")
+}
+
+fn leg(exchange: &Arc<Exchange>, side: OrderSide) -> SpreadLeg {
+    let order = OrderBuilder::new(
+        exchange.exchange_account_id,
+        OrderProxy::default_currency_pair(),
+    )
+    .side(side)
+    .limit(dec!(0.1))
+    .amount(dec!(1))
+    .strategy_name(STRATEGY_NAME)
+    .build()
+    .expect("in test");
+
+    SpreadLeg {
+        exchange: exchange.clone(),
+        order,
+    }
+}
+
+/// Spread order with buy and sell legs on the same market. Sell leg is lagging,
+/// because 0.4 of buy leg is already filled
+async fn create_unbalanced_spread_order(exchange: &Arc<Exchange>) -> Arc<SpreadOrder> {
+    let hedge_policy = HedgePolicy {
+        max_unbalanced_time: Duration::from_millis(10),
+        min_hedge_amount: dec!(0.001),
+    };
+    let spread_order = SpreadOrder::create(
+        leg(exchange, OrderSide::Buy),
+        leg(exchange, OrderSide::Sell),
+        hedge_policy,
+        configuration_descriptor(),
+        CancellationToken::default(),
+    )
+    .await
+    .expect("spread order should be created on simulated exchange");
+
+    let [buy_leg, _] = spread_order.leg_orders();
+    fill_order(exchange, &buy_leg, dec!(0.4));
+
+    spread_order
+}
+
+fn fill_order(exchange: &Exchange, order: &OrderRef, amount: Amount) {
+    exchange.handle_order_filled(
+        FillEventDataBuilder::new()
+            .client_order_id(order.client_order_id())
+            .exchange_order_id(order.exchange_order_id().expect("order should be created"))
+            .fill_price(order.price())
+            .fill_amount(amount)
+            .is_diff(true)
+            .order_role(OrderRole::Maker)
+            .build(),
+    );
+}
+
+/// Order which is created on venue in addition to legs of spread order
+async fn wait_hedge_order(venue: &SimulatedVenue, legs: &[OrderRef; 2]) -> ClientOrderId {
+    let is_leg = |client_order_id: &ClientOrderId| {
+        legs.iter()
+            .any(|leg| &leg.client_order_id() == client_order_id)
+    };
+
+    timeout(Duration::from_secs(5), async {
+        loop {
+            if let Some(hedge_order) = venue.open_orders().into_iter().find(|x| !is_leg(x)) {
+                return hedge_order;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("hedge order should be created")
+}
+
+async fn stop_engine(engine: TradingEngine) {
+    engine.stop("end of spread order test");
+    let _ = engine.run().await;
+}
+
+#[test]
+fn partial_fill_is_hedged_after_max_unbalanced_time() {
+    run_engine_test(false, async {
+        let venue = SimulatedVenue::new();
+        let engine = launch_engine(&venue).await;
+        let exchange = engine.exchange(exchange_account_id()).expect("in test");
+
+        let spread_order = create_unbalanced_spread_order(&exchange).await;
+        let legs = spread_order.leg_orders();
+        assert_eq!(
+            engine.balance_manager().lock().get_reservation_ids().len(),
+            2,
+            "balance should be reserved for both legs"
+        );
+
+        let run = tokio::spawn(
+            spread_order
+                .clone()
+                .run(engine.events(), CancellationToken::default()),
+        );
+
+        let hedge_order_id = wait_hedge_order(&venue, &legs).await;
+        let hedge_order = exchange
+            .orders
+            .cache_by_client_id
+            .get(&hedge_order_id)
+            .map(|x| x.value().clone())
+            .expect("in test");
+        assert!(hedge_order.reservation_id().is_some());
+        assert_eq!(hedge_order.side(), OrderSide::Sell);
+        assert_eq!(hedge_order.amount(), dec!(0.4));
+        // Lagging leg is cancelled before hedging
+        assert_eq!(venue.cancel_requests(), vec![legs[1].client_order_id()]);
+        assert_eq!(spread_order.status(), SpreadOrderStatus::Hedging);
+
+        fill_order(&exchange, &hedge_order, dec!(0.4));
+        spread_order
+            .cancel(CancellationToken::default())
+            .await
+            .expect("leading leg should be cancelled");
+
+        let status = timeout(Duration::from_secs(5), run)
+            .await
+            .expect("spread order should be finished")
+            .expect("in test")
+            .expect("spread order should be hedged");
+        assert_eq!(status, SpreadOrderStatus::Canceled);
+        assert_eq!(spread_order.filled_amounts(), [dec!(0.4), dec!(0.4)]);
+
+        stop_engine(engine).await;
+    });
+}
+
+#[test]
+fn hedging_is_stopped_if_cancellation_of_lagging_leg_is_not_confirmed() {
+    run_engine_test(true, async {
+        let venue = SimulatedVenue::new();
+        venue.ignore_cancellation();
+        let engine = launch_engine(&venue).await;
+        let exchange = engine.exchange(exchange_account_id()).expect("in test");
+
+        let spread_order = create_unbalanced_spread_order(&exchange).await;
+        let legs = spread_order.leg_orders();
+
+        let cancellation_token = CancellationToken::default();
+        let stop_waiting = async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            cancellation_token.cancel();
+        };
+        let (result, _) = join(
+            spread_order
+                .clone()
+                .run(engine.events(), cancellation_token.clone()),
+            stop_waiting,
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(spread_order.status(), SpreadOrderStatus::Failed);
+        // Hedge order isn't created while lagging leg can still be filled
+        assert_eq!(
+            venue.open_orders(),
+            vec![legs[0].client_order_id(), legs[1].client_order_id()]
+        );
+        assert!(!venue.cancel_requests().is_empty());
+        assert!(venue
+            .cancel_requests()
+            .iter()
+            .all(|x| x == &legs[1].client_order_id()));
+
+        stop_engine(engine).await;
+    });
+}
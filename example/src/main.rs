@@ -3,20 +3,38 @@ use binance::binance::BinanceBuilder;
 use mmb_core::exchanges::traits::ExchangeClientBuilder;
 use mmb_core::lifecycle::app_lifetime_manager::ActionAfterGracefulShutdown;
 use rust_decimal::Decimal;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use mmb_core::config::{CONFIG_PATH, CREDENTIALS_PATH};
 use mmb_core::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId};
-use mmb_core::lifecycle::launcher::{launch_trading_engine, EngineBuildConfig, InitSettings};
-use mmb_core::settings::{BaseStrategySettings, CurrencyPairSetting};
+use mmb_core::lifecycle::launcher::{
+    check_config, launch_recover_only, launch_trading_engine, EngineBuildConfig, InitSettings,
+};
+use mmb_core::settings::{
+    BaseStrategySettings, CurrencyPairSetting, MaxAmountSettings, OrderRandomizationSettings,
+};
+use mmb_core::strategies::inventory_skew::InventorySkewSettings;
 
-use example::strategies::example_strategy::ExampleStrategy;
+use example::strategies::example_strategy::{AdaptiveSpreadSettings, ExampleStrategy};
 
-#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub struct ExampleStrategySettings {
     pub spread: Decimal,
     pub currency_pair: CurrencyPairSetting,
     pub max_amount: Decimal,
+    /// Max amount is derived from available balance instead of static `max_amount`
+    /// if it's specified
+    #[serde(default)]
+    pub max_amount_utilization_percent: Option<Decimal>,
+    #[serde(default)]
+    pub order_randomization: Option<OrderRandomizationSettings>,
+    /// Widening of spread by volatility and trade flow of market
+    #[serde(default)]
+    pub adaptive_spread: AdaptiveSpreadSettings,
+    /// Shifting of quotes by position to return it to target inventory
+    #[serde(default)]
+    pub inventory_skew: Option<InventorySkewSettings>,
 }
 
 impl BaseStrategySettings for ExampleStrategySettings {
@@ -41,17 +59,47 @@ impl BaseStrategySettings for ExampleStrategySettings {
     fn max_amount(&self) -> Amount {
         self.max_amount
     }
+
+    fn max_amount_settings(&self) -> MaxAmountSettings {
+        match self.max_amount_utilization_percent {
+            Some(utilization_percent) => MaxAmountSettings::BalanceUtilization {
+                utilization_percent,
+            },
+            None => MaxAmountSettings::Fixed(self.max_amount),
+        }
+    }
+
+    fn order_randomization(&self) -> Option<OrderRandomizationSettings> {
+        self.order_randomization
+    }
+
+    fn inventory_skew(&self) -> Option<InventorySkewSettings> {
+        self.inventory_skew.clone()
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let engine_config =
-        EngineBuildConfig::standard(Box::new(BinanceBuilder) as Box<dyn ExchangeClientBuilder>);
+        EngineBuildConfig::standard(Box::new(BinanceBuilder) as Box<dyn ExchangeClientBuilder>)
+            .with_strategy_settings_schema::<ExampleStrategySettings>();
 
     let init_settings = InitSettings::<ExampleStrategySettings>::Load {
         config_path: CONFIG_PATH.to_owned(),
         credentials_path: CREDENTIALS_PATH.to_owned(),
     };
+
+    // Validation of settings without starting of trading
+    if std::env::args().any(|arg| arg == "--check-config") {
+        return check_config(&engine_config, init_settings);
+    }
+
+    // Inspection of accounts state after crash before resuming of trading
+    if std::env::args().any(|arg| arg == "--recover-only") {
+        let _ = launch_recover_only(&engine_config, init_settings).await?;
+        return Ok(());
+    }
+
     loop {
         let engine =
             launch_trading_engine(&engine_config, init_settings.clone(), |settings, ctx| {
@@ -59,7 +107,10 @@ async fn main() -> Result<()> {
                     settings.strategy.exchange_account_id(),
                     settings.strategy.currency_pair(),
                     settings.strategy.spread,
+                    settings.strategy.adaptive_spread.clone(),
+                    settings.strategy.inventory_skew.clone(),
                     settings.strategy.max_amount,
+                    settings.strategy.max_amount_settings(),
                     ctx,
                 ))
             })
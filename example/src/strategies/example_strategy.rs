@@ -1,4 +1,5 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
 use itertools::Itertools;
@@ -6,28 +7,52 @@ use mmb_utils::infrastructure::WithExpect;
 use mmb_utils::DateTime;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
 use mmb_core::balance_manager::balance_manager::BalanceManager;
 use mmb_core::disposition_execution::{
-    PriceSlot, TradeCycle, TradeDisposition, TradingContext, TradingContextBySide,
+    PriceSlot, SmallOrder, TradeCycle, TradeDisposition, TradingContext, TradingContextBySide,
+};
+use mmb_core::exchanges::common::{
+    CurrencyPair, ExchangeAccountId, MarketAccountId, MarketId, Price,
 };
-use mmb_core::exchanges::common::{CurrencyPair, ExchangeAccountId, MarketAccountId, MarketId};
 use mmb_core::exchanges::general::symbol::Round;
 use mmb_core::explanation::{Explanation, WithExplanation};
 use mmb_core::lifecycle::trading_engine::EngineContext;
 use mmb_core::order_book::local_snapshot_service::LocalSnapshotsService;
 use mmb_core::orders::order::{OrderRole, OrderSide, OrderSnapshot};
 use mmb_core::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use mmb_core::settings::MaxAmountSettings;
 use mmb_core::strategies::disposition_strategy::DispositionStrategy;
+use mmb_core::strategies::inventory_skew::{InventorySkew, InventorySkewSettings};
 use mmb_utils::cancellation_token::CancellationToken;
 
+/// Period of volatility by which spread is sized
+const VOLATILITY_PERIOD: Duration = Duration::from_secs(60);
+
+/// Widening of spread by market conditions. Spread isn't adapted if factors aren't specified
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct AdaptiveSpreadSettings {
+    /// Spread isn't narrower than price change of one standard deviation of volatility
+    /// for 1 minute multiplied by `volatility_factor`
+    #[serde(default)]
+    pub volatility_factor: Option<Decimal>,
+    /// Spread is multiplied by `1 + toxicity * toxicity_factor`, where toxicity of trade flow
+    /// is in range [0, 1], so quotes are wider under one-sided flow
+    #[serde(default)]
+    pub toxicity_factor: Option<Decimal>,
+}
+
 pub struct ExampleStrategy {
     target_eai: ExchangeAccountId,
     currency_pair: CurrencyPair,
     spread: Decimal,
+    adaptive_spread: AdaptiveSpreadSettings,
+    inventory_skew: Option<InventorySkew>,
     engine_context: Arc<EngineContext>,
     configuration_descriptor: ConfigurationDescriptor,
-    max_amount: Decimal,
+    max_amount_settings: MaxAmountSettings,
 }
 
 impl ExampleStrategy {
@@ -35,7 +60,10 @@ impl ExampleStrategy {
         target_eai: ExchangeAccountId,
         currency_pair: CurrencyPair,
         spread: Decimal,
+        adaptive_spread: AdaptiveSpreadSettings,
+        inventory_skew: Option<InventorySkewSettings>,
         max_amount: Decimal,
+        max_amount_settings: MaxAmountSettings,
         engine_context: Arc<EngineContext>,
     ) -> Self {
         let configuration_descriptor = ConfigurationDescriptor::new(
@@ -45,6 +73,11 @@ impl ExampleStrategy {
                 .into(),
         );
 
+        let inventory_skew = inventory_skew
+            .map(InventorySkew::new)
+            .transpose()
+            .expect("settings of inventory skew should be checked by config validation");
+
         let exchanges = &engine_context.clone().exchanges;
         let exchange = exchanges.get(&target_eai).with_expect(|| {
             format!(
@@ -78,9 +111,11 @@ impl ExampleStrategy {
             target_eai,
             currency_pair,
             spread,
+            adaptive_spread,
+            inventory_skew,
             engine_context,
             configuration_descriptor,
-            max_amount,
+            max_amount_settings,
         }
     }
 
@@ -96,6 +131,47 @@ impl ExampleStrategy {
         self.market_account_id().market_id()
     }
 
+    /// Quotes are widened in volatile markets and under one-sided (toxic) trade flow,
+    /// so they aren't picked off by informed traders
+    fn adapt_spread(
+        &self,
+        spread: Decimal,
+        order_book_middle: Price,
+        explanation: &mut Explanation,
+    ) -> Decimal {
+        let market_account_id = self.market_account_id();
+        let mut spread = spread;
+
+        if let Some(volatility_factor) = self.adaptive_spread.volatility_factor {
+            if let Some(volatility) = self
+                .engine_context
+                .volatility
+                .volatility(market_account_id, VOLATILITY_PERIOD)
+            {
+                let volatility_spread = volatility * order_book_middle * volatility_factor;
+                if volatility_spread > spread {
+                    spread = volatility_spread;
+                    explanation.add_reason(format!(
+                        "Spread is widened to {} by volatility {}",
+                        spread, volatility
+                    ));
+                }
+            }
+        }
+
+        if let Some(toxicity_factor) = self.adaptive_spread.toxicity_factor {
+            if let Some(trade_flow) = self.engine_context.trade_flow.metrics(market_account_id) {
+                spread *= dec!(1) + trade_flow.toxicity * toxicity_factor;
+                explanation.add_reason(format!(
+                    "Spread is widened to {} by toxicity {} of trade flow",
+                    spread, trade_flow.toxicity
+                ));
+            }
+        }
+
+        spread
+    }
+
     fn calc_trading_context_by_side(
         &mut self,
         side: OrderSide,
@@ -108,25 +184,30 @@ impl ExampleStrategy {
         let bid_max_price = snapshot.get_top_bid()?.0;
 
         let current_spread = ask_min_price - bid_max_price;
+        let order_book_middle = (bid_max_price + ask_min_price) * dec!(0.5);
 
-        let symbol = self
-            .engine_context
-            .exchanges
-            .get(&self.target_eai)?
-            .symbols
-            .get(&self.currency_pair)?
-            .clone();
+        let exchange = self.engine_context.exchanges.get(&self.target_eai)?.clone();
+        let symbol = exchange.symbols.get(&self.currency_pair)?.clone();
 
-        let price = if current_spread < self.spread {
-            let order_book_middle = (bid_max_price + ask_min_price) * dec!(0.5);
+        // Spread isn't narrower than fees and conversion costs of round trip
+        let min_profitable_spread = self
+            .engine_context
+            .min_profitable_spread
+            .min_spread(&exchange, order_book_middle);
+        let spread = self.adapt_spread(
+            self.spread.max(min_profitable_spread),
+            order_book_middle,
+            &mut explanation,
+        );
 
+        let price = if current_spread < spread {
             match side {
                 OrderSide::Sell => {
-                    let price = order_book_middle + (self.spread * dec!(0.5));
+                    let price = order_book_middle + (spread * dec!(0.5));
                     symbol.price_round(price, Round::Ceiling)
                 }
                 OrderSide::Buy => {
-                    let price = order_book_middle - (self.spread * dec!(0.5));
+                    let price = order_book_middle - (spread * dec!(0.5));
                     symbol.price_round(price, Round::Floor)
                 }
             }
@@ -135,6 +216,7 @@ impl ExampleStrategy {
         };
 
         let amount;
+        let max_amount;
         explanation = {
             let mut explanation = Some(explanation);
 
@@ -170,16 +252,57 @@ impl ExampleStrategy {
                 )
                 .with_expect(|| format!("Failed to get balance for {}", self.target_eai));
 
+            // Max amount is recomputed every cycle, so it follows changes of balance
+            max_amount = match self.max_amount_settings {
+                MaxAmountSettings::Fixed(max_amount) => max_amount,
+                MaxAmountSettings::BalanceUtilization {
+                    utilization_percent,
+                } => balance_manager
+                    .lock()
+                    .get_max_amount_by_utilization(
+                        self.configuration_descriptor.clone(),
+                        side,
+                        self.target_eai,
+                        symbol.clone(),
+                        price,
+                        utilization_percent,
+                        &mut explanation,
+                    )
+                    .unwrap_or(dec!(0)),
+            };
+
             // This expect can happened if get_leveraged_balance_in_amount_currency_code() sets the explanation to None
             explanation.expect(
                 "ExampleStrategy::calc_trading_context_by_side(): Explanation should be non None here"
             )
         };
 
+        // Quotes are shifted by position, so it returns to target inventory
+        let (price, amount) = match &self.inventory_skew {
+            Some(inventory_skew) => {
+                let position = InventorySkew::current_position(
+                    &self.engine_context.balance_manager.lock(),
+                    self.market_account_id(),
+                );
+                let order = inventory_skew.apply(side, SmallOrder::new(price, amount), position);
+                explanation.add_reason(format!(
+                    "Price is skewed to {} and amount to {} by position {}",
+                    order.price, order.amount, position
+                ));
+
+                let round = match side {
+                    OrderSide::Sell => Round::Ceiling,
+                    OrderSide::Buy => Round::Floor,
+                };
+                (symbol.price_round(order.price, round), order.amount)
+            }
+            None => (price, amount),
+        };
+
         let amount = symbol.amount_round(amount, Round::Floor);
 
         Some(TradingContextBySide {
-            max_amount: self.max_amount,
+            max_amount,
             estimating: vec![WithExplanation {
                 value: Some(TradeCycle {
                     order_role: OrderRole::Maker,
@@ -1,30 +1,37 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use anyhow::{anyhow, bail, Context, Result};
+use anyhow::{bail, Context, Result};
 use dashmap::DashMap;
 use hex;
 use hmac::{Hmac, Mac, NewMac};
 use itertools::Itertools;
 use mmb_utils::infrastructure::WithExpect;
-use mmb_utils::time::{get_current_milliseconds, u64_to_date_time};
+use mmb_utils::time::u64_to_date_time;
 use mmb_utils::DateTime;
 use parking_lot::{Mutex, RwLock};
-use serde_json::Value;
 use sha2::Sha256;
 use tokio::sync::broadcast;
 
-use super::support::{BinanceBalances, BinanceOrderInfo};
+use super::support::{BinanceBalances, BinanceOrderInfo, PUBLIC_STREAM_CHANNELS};
+use super::websocket_messages::{
+    BinanceExecutionType, BinanceOrderStatus, BinanceOrderUpdate, BinanceTimeInForce,
+};
+use mmb_core::connectivity::websocket_channels::WebSocketChannels;
+use mmb_core::connectivity::websocket_message_router::WebSocketMessageRouter;
 use mmb_core::exchanges::common::{Amount, Price};
 use mmb_core::exchanges::events::{
-    ExchangeBalance, ExchangeBalancesAndPositions, ExchangeEvent, TradeId,
+    ExchangeBalance, ExchangeBalancesAndPositions, ExchangeEvent, TradeId, TradeStream,
 };
 use mmb_core::exchanges::general::features::{
     OrderFeatures, OrderTradeOption, RestFillsFeatures, RestFillsType, WebSocketOptions,
 };
 use mmb_core::exchanges::general::helpers::{get_rest_error, handle_parse_error};
+use mmb_core::exchanges::general::pagination::{PageCursor, PageRequest};
+use mmb_core::exchanges::general::withdrawal::WalletType;
 use mmb_core::exchanges::hosts::Hosts;
 use mmb_core::exchanges::rest_client::RestClient;
+use mmb_core::exchanges::time_sync::ServerTimeOffset;
 use mmb_core::exchanges::traits::{ExchangeClientBuilderResult, Support};
 use mmb_core::exchanges::{
     common::CurrencyCode,
@@ -41,7 +48,7 @@ use mmb_core::lifecycle::app_lifetime_manager::AppLifetimeManager;
 use mmb_core::orders::fill::EventSourceType;
 use mmb_core::orders::order::*;
 use mmb_core::orders::pool::OrderRef;
-use mmb_core::settings::ExchangeSettings;
+use mmb_core::settings::{ExchangeSettings, PositionMode};
 use mmb_core::{exchanges::traits::ExchangeClientBuilder, orders::fill::OrderFillType};
 
 pub struct Binance {
@@ -54,7 +61,11 @@ pub struct Binance {
         Mutex<Box<dyn FnMut(ClientOrderId, ExchangeOrderId, EventSourceType) + Send + Sync>>,
     pub handle_order_filled_callback: Mutex<Box<dyn FnMut(FillEventData) + Send + Sync>>,
     pub handle_trade_callback: Mutex<
-        Box<dyn FnMut(CurrencyPair, TradeId, Price, Amount, OrderSide, DateTime) + Send + Sync>,
+        Box<
+            dyn FnMut(CurrencyPair, TradeStream, TradeId, Price, Amount, OrderSide, DateTime)
+                + Send
+                + Sync,
+        >,
     >,
 
     pub unified_to_specific: RwLock<HashMap<CurrencyPair, SpecificCurrencyPair>>,
@@ -62,7 +73,8 @@ pub struct Binance {
     pub supported_currencies: DashMap<CurrencyId, CurrencyCode>,
     // Currencies used for trading according to user settings
     pub traded_specific_currencies: Mutex<Vec<SpecificCurrencyPair>>,
-    pub(super) last_trade_ids: DashMap<CurrencyPair, TradeId>,
+    /// Last trade ids by market and stream, because streams number trades independently
+    pub(super) last_trade_ids: DashMap<(CurrencyPair, TradeStream), TradeId>,
 
     pub(super) lifetime_manager: Arc<AppLifetimeManager>,
 
@@ -72,6 +84,9 @@ pub struct Binance {
     pub(super) is_reducing_market_data: bool,
 
     pub(super) rest_client: RestClient,
+    pub(super) server_time_offset: ServerTimeOffset,
+    pub(super) message_router: WebSocketMessageRouter<Binance>,
+    pub(super) websocket_channels: WebSocketChannels,
 }
 
 impl Binance {
@@ -87,13 +102,18 @@ impl Binance {
             .unwrap_or(is_reducing_market_data);
 
         let hosts = Self::make_hosts(settings.is_margin_trading);
+        let rest_client = RestClient::from_settings(&settings)
+            .with_expect(|| format!("Unable to create RestClient for {}", id));
+        let message_router = Self::create_message_router();
+        let websocket_channels =
+            WebSocketChannels::new(&settings.websocket_channels, &message_router);
 
         Self {
             id,
             order_created_callback: Mutex::new(Box::new(|_, _, _| {})),
             order_cancelled_callback: Mutex::new(Box::new(|_, _, _| {})),
             handle_order_filled_callback: Mutex::new(Box::new(|_| {})),
-            handle_trade_callback: Mutex::new(Box::new(|_, _, _, _, _, _| {})),
+            handle_trade_callback: Mutex::new(Box::new(|_, _, _, _, _, _, _| {})),
             unified_to_specific: Default::default(),
             specific_to_unified: Default::default(),
             supported_currencies: Default::default(),
@@ -105,7 +125,10 @@ impl Binance {
             hosts,
             events_channel,
             lifetime_manager,
-            rest_client: RestClient::new(),
+            rest_client,
+            server_time_offset: ServerTimeOffset::default(),
+            message_router,
+            websocket_channels,
         }
     }
 
@@ -161,23 +184,62 @@ impl Binance {
         }
     }
 
-    pub(super) fn to_local_order_side(side: &str) -> OrderSide {
-        match side {
-            "BUY" => OrderSide::Buy,
-            "SELL" => OrderSide::Sell,
-            // TODO just propagate and log there
-            _ => panic!("Unexpected order side"),
+    pub(super) fn to_server_position_side(position_side: PositionSide) -> String {
+        match position_side {
+            PositionSide::Both => "BOTH".to_owned(),
+            PositionSide::Long => "LONG".to_owned(),
+            PositionSide::Short => "SHORT".to_owned(),
         }
     }
 
-    fn to_local_order_status(status: &str) -> OrderStatus {
-        match status {
-            "NEW" | "PARTIALLY_FILLED" => OrderStatus::Created,
-            "FILLED" => OrderStatus::Completed,
-            "PENDING_CANCEL" => OrderStatus::Canceling,
-            "CANCELED" | "EXPIRED" | "REJECTED" => OrderStatus::Canceled,
-            // TODO just propagate and log there
-            _ => panic!("Unexpected order status"),
+    /// Position side is required by Binance futures only for accounts in hedge mode.
+    /// If order position side isn't specified, it's derived from `reduce_only` flag:
+    /// reduce-only order closes position opposite to order side, other orders open position
+    /// in direction of order side
+    pub(super) fn get_order_position_side(&self, header: &OrderHeader) -> Option<PositionSide> {
+        if !self.settings.is_margin_trading
+            || self.settings.position_mode != Some(PositionMode::Hedge)
+        {
+            return None;
+        }
+
+        let position_side =
+            header
+                .position_side
+                .unwrap_or(match (header.side, header.reduce_only) {
+                    (OrderSide::Buy, false) | (OrderSide::Sell, true) => PositionSide::Long,
+                    (OrderSide::Sell, false) | (OrderSide::Buy, true) => PositionSide::Short,
+                });
+
+        Some(position_side)
+    }
+
+    pub(super) fn to_server_transfer_type(
+        from: WalletType,
+        to: WalletType,
+    ) -> Result<&'static str> {
+        let transfer_type = match (from, to) {
+            (WalletType::Spot, WalletType::Futures) => "MAIN_UMFUTURE",
+            (WalletType::Spot, WalletType::Margin) => "MAIN_MARGIN",
+            (WalletType::Futures, WalletType::Spot) => "UMFUTURE_MAIN",
+            (WalletType::Futures, WalletType::Margin) => "UMFUTURE_MARGIN",
+            (WalletType::Margin, WalletType::Spot) => "MARGIN_MAIN",
+            (WalletType::Margin, WalletType::Futures) => "MARGIN_UMFUTURE",
+            _ => bail!(
+                "Unable to transfer funds from {:?} to {:?} wallet",
+                from,
+                to
+            ),
+        };
+
+        Ok(transfer_type)
+    }
+
+    pub(super) fn to_server_sub_account_type(wallet: WalletType) -> &'static str {
+        match wallet {
+            WalletType::Spot => "SPOT",
+            WalletType::Margin => "MARGIN",
+            WalletType::Futures => "USDT_FUTURE",
         }
     }
 
@@ -202,7 +264,12 @@ impl Binance {
         &self,
         parameters: &mut rest_client::HttpParams,
     ) -> Result<()> {
-        let time_stamp = get_current_milliseconds();
+        if let Some(recv_window) = self.settings.recv_window {
+            let recv_window = recv_window.duration().as_millis();
+            parameters.push(("recvWindow".to_owned(), recv_window.to_string()));
+        }
+
+        let time_stamp = self.server_time_offset.get_server_milliseconds();
         parameters.push(("timestamp".to_owned(), time_stamp.to_string()));
 
         let message_to_sign = rest_client::to_http_string(&parameters);
@@ -234,8 +301,8 @@ impl Binance {
                 .expect("expected known currency pair"),
             specific.exchange_order_id.to_string().as_str().into(),
             specific.client_order_id.clone(),
-            Self::to_local_order_side(&specific.side),
-            Self::to_local_order_status(&specific.status),
+            specific.side.into(),
+            specific.status.into(),
             specific.price,
             specific.orig_quantity,
             specific.price,
@@ -246,69 +313,65 @@ impl Binance {
         )
     }
 
-    pub(super) fn handle_order_fill(&self, msg_to_log: &str, json_response: Value) -> Result<()> {
-        let original_client_order_id = json_response["C"]
-            .as_str()
-            .ok_or(anyhow!("Unable to parse original client order id"))?;
-
-        let client_order_id = if original_client_order_id.is_empty() {
-            json_response["c"]
-                .as_str()
-                .ok_or(anyhow!("Unable to parse client order id"))?
-        } else {
-            original_client_order_id
-        };
-
-        let exchange_order_id = json_response["i"].to_string();
-        let exchange_order_id = exchange_order_id.trim_matches('"');
-        let execution_type = json_response["x"]
-            .as_str()
-            .ok_or(anyhow!("Unable to parse execution type"))?;
-        let order_status = json_response["X"]
-            .as_str()
-            .ok_or(anyhow!("Unable to parse order status"))?;
-        let time_in_force = json_response["f"]
-            .as_str()
-            .ok_or(anyhow!("Unable to parse time in force"))?;
+    pub(super) fn handle_order_fill(
+        &self,
+        msg_to_log: &str,
+        order_update: BinanceOrderUpdate,
+    ) -> Result<()> {
+        let client_order_id = order_update.get_client_order_id().clone();
+        let exchange_order_id: ExchangeOrderId =
+            order_update.exchange_order_id.to_string().as_str().into();
+        let execution_type = order_update.execution_type;
+        let order_status = order_update.order_status;
+
+        // Orders created by exchange itself are unknown locally, so only their fills are handled
+        if Self::get_external_order_fill_type(client_order_id.as_str()).is_some()
+            && !matches!(
+                execution_type,
+                BinanceExecutionType::Trade | BinanceExecutionType::Calculated
+            )
+        {
+            return Ok(());
+        }
 
         match execution_type {
-            "NEW" => match order_status {
-                "NEW" => {
+            BinanceExecutionType::New => match order_status {
+                BinanceOrderStatus::New => {
                     (&self.order_created_callback).lock()(
-                        client_order_id.into(),
-                        exchange_order_id.into(),
+                        client_order_id,
+                        exchange_order_id,
                         EventSourceType::WebSocket,
                     );
                 }
                 _ => log::error!(
-                    "execution_type is NEW but order_status is {} for message {}",
+                    "execution_type is NEW but order_status is {:?} for message {}",
                     order_status,
                     msg_to_log
                 ),
             },
-            "CANCELED" => match order_status {
-                "CANCELED" => {
+            BinanceExecutionType::Canceled => match order_status {
+                BinanceOrderStatus::Canceled => {
                     (&self.order_cancelled_callback).lock()(
-                        client_order_id.into(),
-                        exchange_order_id.into(),
+                        client_order_id,
+                        exchange_order_id,
                         EventSourceType::WebSocket,
                     );
                 }
                 _ => log::error!(
-                    "execution_type is CANCELED but order_status is {} for message {}",
+                    "execution_type is CANCELED but order_status is {:?} for message {}",
                     order_status,
                     msg_to_log
                 ),
             },
-            "REJECTED" => {
+            BinanceExecutionType::Rejected => {
                 // TODO: May be not handle error in Rest but move it here to make it unified?
                 // We get notification of rejected orders from the rest responses
             }
-            "EXPIRED" => match time_in_force {
-                "GTX" => {
+            BinanceExecutionType::Expired => match order_update.time_in_force {
+                BinanceTimeInForce::Gtx | BinanceTimeInForce::Gtd => {
                     (&self.order_cancelled_callback).lock()(
-                        client_order_id.into(),
-                        exchange_order_id.into(),
+                        client_order_id,
+                        exchange_order_id,
                         EventSourceType::WebSocket,
                     );
                 }
@@ -318,17 +381,20 @@ impl Binance {
                     msg_to_log
                 ),
             },
-            "TRADE" | "CALCULATED" => {
+            BinanceExecutionType::Trade | BinanceExecutionType::Calculated => {
                 let event_data = self.prepare_data_for_fill_handler(
-                    &json_response,
-                    execution_type,
-                    client_order_id.into(),
-                    exchange_order_id.into(),
+                    &order_update,
+                    client_order_id,
+                    exchange_order_id,
                 )?;
 
                 (&self.handle_order_filled_callback).lock()(event_data);
             }
-            _ => log::error!("Impossible execution type"),
+            _ => log::error!(
+                "Unexpected execution type {:?} for message {}",
+                execution_type,
+                msg_to_log
+            ),
         }
 
         Ok(())
@@ -340,6 +406,12 @@ impl Binance {
             .map(|some| some.value().clone())
     }
 
+    /// Currency code for asset which may be missing in supported currencies (e.g. delisted asset)
+    pub(crate) fn get_local_currency_code(&self, asset: &str) -> CurrencyCode {
+        self.get_currency_code(&asset.into())
+            .unwrap_or_else(|| asset.into())
+    }
+
     pub(crate) fn get_currency_code_expected(&self, currency_id: &CurrencyId) -> CurrencyCode {
         self.get_currency_code(currency_id).with_expect(|| {
             format!(
@@ -351,46 +423,39 @@ impl Binance {
 
     fn prepare_data_for_fill_handler(
         &self,
-        json_response: &Value,
-        execution_type: &str,
+        order_update: &BinanceOrderUpdate,
         client_order_id: ClientOrderId,
         exchange_order_id: ExchangeOrderId,
     ) -> Result<FillEventData> {
-        let trade_id = json_response["t"].clone().into();
-        let last_filled_price = json_response["L"]
-            .as_str()
-            .ok_or(anyhow!("Unable to parse last filled price"))?;
-        let last_filled_amount = json_response["l"]
-            .as_str()
-            .ok_or(anyhow!("Unable to parse last filled amount"))?;
-        let total_filled_amount = json_response["z"]
-            .as_str()
-            .ok_or(anyhow!("Unable to parse total filled amount"))?;
-        let commission_amount = json_response["n"]
-            .as_str()
-            .ok_or(anyhow!("Unable to parse last commission amount"))?;
-        let commission_currency = json_response["N"]
-            .as_str()
-            .ok_or(anyhow!("Unable to parse last commission currency"))?;
+        let trade_id = u64::try_from(order_update.trade_id)
+            .map(TradeId::Number)
+            .context("Unable to parse trade id")?;
+        let commission_amount = order_update
+            .commission_amount
+            .context("Unable to parse last commission amount")?;
+        let commission_currency = order_update
+            .commission_currency
+            .as_ref()
+            .context("Unable to parse last commission currency")?;
         let commission_currency_code = self
-            .get_currency_code(&commission_currency.into())
-            .ok_or(anyhow!("There are no suck supported currency code"))?;
-        let is_maker = json_response["m"]
-            .as_bool()
-            .ok_or(anyhow!("Unable to parse trade side"))?;
-        let order_side = Self::to_local_order_side(
-            json_response["S"]
-                .as_str()
-                .ok_or(anyhow!("Unable to parse last filled amount"))?,
-        );
-        let fill_date: DateTime = u64_to_date_time(
-            json_response["E"]
-                .as_u64()
-                .ok_or(anyhow!("Unable to parse transaction time"))?,
-        );
+            .get_currency_code(commission_currency)
+            .context("There are no such supported currency code")?;
+        let fill_date: DateTime = u64_to_date_time(order_update.transaction_time);
+
+        let fill_type = Self::get_fill_type(order_update.execution_type, client_order_id.as_str())?;
 
-        let fill_type = Self::get_fill_type(execution_type)?;
-        let order_role = if is_maker {
+        // Liquidation and ADL orders are created by exchange, so they are added as external orders
+        let (client_order_id, trade_currency_pair, order_amount) = match fill_type {
+            OrderFillType::Liquidation | OrderFillType::ClosePosition => {
+                let currency_pair =
+                    self.get_unified_currency_pair(&order_update.specific_currency_pair)?;
+
+                (None, Some(currency_pair), Some(order_update.order_amount))
+            }
+            _ => (Some(client_order_id), None, None),
+        };
+
+        let order_role = if order_update.is_maker {
             OrderRole::Maker
         } else {
             OrderRole::Taker
@@ -399,32 +464,52 @@ impl Binance {
         let event_data = FillEventData {
             source_type: EventSourceType::WebSocket,
             trade_id: Some(trade_id),
-            client_order_id: Some(client_order_id),
+            client_order_id,
             exchange_order_id,
-            fill_price: last_filled_price.parse()?,
-            fill_amount: last_filled_amount.parse()?,
+            fill_price: order_update.last_filled_price,
+            fill_amount: order_update.last_filled_amount,
             is_diff: true,
-            total_filled_amount: Some(total_filled_amount.parse()?),
+            total_filled_amount: Some(order_update.total_filled_amount),
             order_role: Some(order_role),
             commission_currency_code: Some(commission_currency_code),
             commission_rate: None,
-            commission_amount: Some(commission_amount.parse()?),
+            commission_amount: Some(commission_amount),
             fill_type,
-            trade_currency_pair: None,
-            order_side: Some(order_side),
-            order_amount: None,
+            trade_currency_pair,
+            order_side: Some(order_update.side.into()),
+            order_amount,
             fill_date: Some(fill_date),
+            position_side: order_update.position_side.map(Into::into),
         };
 
         Ok(event_data)
     }
 
     // According to https://binance-docs.github.io/apidocs/futures/en/#event-order-update
-    fn get_fill_type(raw_type: &str) -> Result<OrderFillType> {
-        match raw_type {
-            "CALCULATED" => Ok(OrderFillType::Liquidation),
-            "FILL" | "TRADE" | "PARTIAL_FILL" => Ok(OrderFillType::UserTrade),
-            _ => bail!("Unable to map trade type"),
+    fn get_fill_type(
+        execution_type: BinanceExecutionType,
+        client_order_id: &str,
+    ) -> Result<OrderFillType> {
+        match execution_type {
+            BinanceExecutionType::Calculated => Ok(OrderFillType::Liquidation),
+            BinanceExecutionType::Trade => Ok(Self::get_external_order_fill_type(client_order_id)
+                .unwrap_or(OrderFillType::UserTrade)),
+            _ => bail!("Unable to map trade type {:?}", execution_type),
+        }
+    }
+
+    /// Binance marks orders created by exchange with special client order ids:
+    /// `autoclose-` for liquidation, `adl_autoclose` for auto-deleveraging
+    /// and `settlement_autoclose-` for settlement of delisted contracts
+    fn get_external_order_fill_type(client_order_id: &str) -> Option<OrderFillType> {
+        if client_order_id.starts_with("autoclose-") {
+            Some(OrderFillType::Liquidation)
+        } else if client_order_id.starts_with("adl_autoclose")
+            || client_order_id.starts_with("settlement_autoclose-")
+        {
+            Some(OrderFillType::ClosePosition)
+        } else {
+            None
         }
     }
 
@@ -477,6 +562,32 @@ impl Binance {
         self.rest_client.get(full_url, &self.settings.api_key).await
     }
 
+    /// Binance returns items with id greater or equal to `id_param`, so the next id after cursor is requested
+    pub(super) fn add_page_params(
+        http_params: &mut rest_client::HttpParams,
+        page: &PageRequest,
+        id_param: &str,
+    ) -> Result<()> {
+        match &page.cursor {
+            Some(PageCursor::AfterId(id)) => {
+                let id = id
+                    .parse::<u64>()
+                    .with_context(|| format!("Unable to parse page cursor id {}", id))?;
+                http_params.push((id_param.to_owned(), (id + 1).to_string()));
+            }
+            Some(PageCursor::FromTime(time)) => {
+                http_params.push(("startTime".to_owned(), time.timestamp_millis().to_string()));
+            }
+            None => {
+                // Binance returns the first items if start id is 0
+                http_params.push((id_param.to_owned(), "0".to_owned()));
+            }
+        }
+        http_params.push(("limit".to_owned(), page.limit.to_string()));
+
+        Ok(())
+    }
+
     pub(super) async fn request_open_orders(&self) -> Result<RestRequestOutcome> {
         let mut http_params = rest_client::HttpParams::new();
         self.add_authentification_headers(&mut http_params)?;
@@ -524,8 +635,12 @@ impl Binance {
     }
 
     fn parse_open_orders(&self, response: &RestRequestOutcome) -> Result<Vec<OrderInfo>> {
-        let binance_orders: Vec<BinanceOrderInfo> = serde_json::from_str(&response.content)
-            .context("Unable to parse response content for get_open_orders request")?;
+        self.parse_orders(response)
+            .context("Unable to parse response content for get_open_orders request")
+    }
+
+    pub(super) fn parse_orders(&self, response: &RestRequestOutcome) -> Result<Vec<OrderInfo>> {
+        let binance_orders: Vec<BinanceOrderInfo> = serde_json::from_str(&response.content)?;
 
         let orders_info: Vec<OrderInfo> = binance_orders
             .iter()
@@ -554,6 +669,10 @@ impl ExchangeClientBuilder for BinanceBuilder {
         lifetime_manager: Arc<AppLifetimeManager>,
     ) -> ExchangeClientBuilderResult {
         let exchange_account_id = exchange_settings.exchange_account_id;
+        let order_features = OrderFeatures {
+            supports_good_till_date: exchange_settings.is_margin_trading,
+            ..OrderFeatures::default()
+        };
 
         ExchangeClientBuilderResult {
             client: Box::new(Binance::new(
@@ -566,8 +685,11 @@ impl ExchangeClientBuilder for BinanceBuilder {
             features: ExchangeFeatures::new(
                 OpenOrdersType::AllCurrencyPair,
                 RestFillsFeatures::new(RestFillsType::None),
-                OrderFeatures::default(),
-                OrderTradeOption::default(),
+                order_features,
+                OrderTradeOption {
+                    supports_trade_incremented_id: true,
+                    ..OrderTradeOption::default()
+                },
                 WebSocketOptions::default(),
                 false,
                 false,
@@ -580,12 +702,20 @@ impl ExchangeClientBuilder for BinanceBuilder {
     fn get_timeout_arguments(&self) -> RequestTimeoutArguments {
         RequestTimeoutArguments::from_requests_per_minute(1200)
     }
+
+    fn get_supported_websocket_channels(&self) -> Vec<String> {
+        PUBLIC_STREAM_CHANNELS
+            .iter()
+            .map(|channel| channel.to_string())
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use mmb_utils::cancellation_token::CancellationToken;
+    use rust_decimal_macros::dec;
 
     #[test]
     fn generate_signature() {
@@ -633,4 +763,157 @@ mod tests {
         let right_value = "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&quantity=1&price=0.1&recvWindow=5000&timestamp=1499827319559";
         assert_eq!(http_string, right_value);
     }
+
+    #[test]
+    fn fill_type_of_orders_created_by_exchange() {
+        let get_fill_type = |execution_type, client_order_id| {
+            Binance::get_fill_type(execution_type, client_order_id).expect("in test")
+        };
+
+        assert_eq!(
+            get_fill_type(BinanceExecutionType::Trade, "web_123"),
+            OrderFillType::UserTrade
+        );
+        assert_eq!(
+            get_fill_type(BinanceExecutionType::Calculated, "autoclose-123"),
+            OrderFillType::Liquidation
+        );
+        assert_eq!(
+            get_fill_type(BinanceExecutionType::Trade, "autoclose-123"),
+            OrderFillType::Liquidation
+        );
+        assert_eq!(
+            get_fill_type(BinanceExecutionType::Trade, "adl_autoclose"),
+            OrderFillType::ClosePosition
+        );
+    }
+
+    #[test]
+    fn order_position_side_in_hedge_mode() {
+        let exchange_account_id: ExchangeAccountId = "Binance_0".parse().expect("in test");
+
+        let mut settings =
+            ExchangeSettings::new_short(exchange_account_id, "".into(), "".into(), true, false);
+        settings.position_mode = Some(PositionMode::Hedge);
+
+        let (tx, _) = broadcast::channel(10);
+        let binance = Binance::new(
+            exchange_account_id,
+            settings,
+            tx,
+            AppLifetimeManager::new(CancellationToken::default()),
+            false,
+        );
+
+        let header = OrderHeader::new(
+            ClientOrderId::unique_id(),
+            chrono::Utc::now(),
+            exchange_account_id,
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            OrderType::Limit,
+            OrderSide::Sell,
+            dec!(1),
+            OrderExecutionType::None,
+            None,
+            None,
+            "FromTest".to_owned(),
+        );
+        assert_eq!(
+            binance.get_order_position_side(&header),
+            Some(PositionSide::Short)
+        );
+
+        let reduce_only_header = header.clone().with_reduce_only();
+        assert_eq!(
+            binance.get_order_position_side(&reduce_only_header),
+            Some(PositionSide::Long)
+        );
+
+        // Explicit position side has priority over reduce-only flag
+        let closing_header = reduce_only_header.with_position_side(PositionSide::Short);
+        assert_eq!(
+            binance.get_order_position_side(&closing_header),
+            Some(PositionSide::Short)
+        );
+
+        let closing_header = header.with_position_side(PositionSide::Long);
+        assert_eq!(
+            binance.get_order_position_side(&closing_header),
+            Some(PositionSide::Long)
+        );
+    }
+
+    #[test]
+    fn transfer_type_between_wallets() {
+        assert_eq!(
+            Binance::to_server_transfer_type(WalletType::Spot, WalletType::Futures)
+                .expect("in test"),
+            "MAIN_UMFUTURE"
+        );
+        assert_eq!(
+            Binance::to_server_transfer_type(WalletType::Margin, WalletType::Spot)
+                .expect("in test"),
+            "MARGIN_MAIN"
+        );
+        assert!(Binance::to_server_transfer_type(WalletType::Spot, WalletType::Spot).is_err());
+    }
+
+    #[test]
+    fn additional_websocket_channels() {
+        let exchange_account_id: ExchangeAccountId = "Binance_0".parse().expect("in test");
+
+        let mut settings =
+            ExchangeSettings::new_short(exchange_account_id, "".into(), "".into(), false, false);
+        settings.websocket_channels = vec!["depth20".into()];
+
+        let (tx, _) = broadcast::channel(10);
+        let binance = Binance::new(
+            exchange_account_id,
+            settings,
+            tx,
+            AppLifetimeManager::new(CancellationToken::default()),
+            false,
+        );
+        binance.set_traded_specific_currencies(vec!["BTCUSDT".into()]);
+
+        assert!(binance.add_websocket_channel("kline").is_err());
+        assert!(binance.add_websocket_channel("aggtrade").expect("in test"));
+        assert_eq!(
+            binance.build_ws_main_path(&binance.get_websocket_channels()),
+            "/stream?streams=btcusdt@depth20/btcusdt@aggtrade"
+        );
+
+        let message = binance
+            .build_ws_subscribe_message(&["aggtrade".to_owned()])
+            .expect("in test");
+        let message: serde_json::Value = serde_json::from_str(&message).expect("in test");
+        assert_eq!(message["method"], "SUBSCRIBE");
+        assert_eq!(message["params"][0], "btcusdt@aggtrade");
+
+        assert!(binance
+            .remove_websocket_channel("aggtrade")
+            .expect("in test"));
+        assert!(!binance
+            .remove_websocket_channel("aggtrade")
+            .expect("in test"));
+        assert_eq!(binance.get_websocket_channels(), vec!["depth20"]);
+
+        let message = binance
+            .build_ws_unsubscribe_message(&["aggtrade".to_owned()])
+            .expect("in test");
+        let message: serde_json::Value = serde_json::from_str(&message).expect("in test");
+        assert_eq!(message["method"], "UNSUBSCRIBE");
+        assert_eq!(message["params"][0], "btcusdt@aggtrade");
+    }
+
+    #[test]
+    fn supported_websocket_channels_are_routed() {
+        let router = Binance::create_message_router();
+        let channels = BinanceBuilder.get_supported_websocket_channels();
+
+        assert!(!channels.is_empty());
+        for channel in channels {
+            assert!(router.has_handler(&channel), "{}", channel);
+        }
+    }
 }
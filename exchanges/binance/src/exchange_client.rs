@@ -1,10 +1,16 @@
 use super::binance::Binance;
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
-use mmb_core::exchanges::common::{ActivePosition, ExchangeError, ExchangeErrorType, Price};
-use mmb_core::exchanges::events::ExchangeBalancesAndPositions;
+use itertools::Itertools;
+use mmb_core::exchanges::common::{
+    ActivePosition, CurrencyCode, ExchangeError, ExchangeErrorType, Price,
+};
+use mmb_core::exchanges::events::{ExchangeBalancesAndPositions, TradeStream};
 use mmb_core::exchanges::general::helpers::{get_rest_error_order, is_rest_error_code};
+use mmb_core::exchanges::general::pagination::PageRequest;
+use mmb_core::exchanges::general::sub_account::SubAccountTransfer;
 use mmb_core::exchanges::general::symbol::Symbol;
+use mmb_core::exchanges::general::withdrawal::{InternalTransfer, Withdrawal};
 use mmb_core::exchanges::rest_client;
 use mmb_core::exchanges::traits::{ExchangeClient, Support};
 use mmb_core::orders::order::*;
@@ -12,7 +18,9 @@ use mmb_core::{
     exchanges::common::{CurrencyPair, RestRequestOutcome},
     orders::pool::OrderRef,
 };
+use mmb_utils::time::get_current_milliseconds;
 use mmb_utils::DateTime;
+use serde_json::Value;
 
 #[async_trait]
 impl ExchangeClient for Binance {
@@ -27,6 +35,14 @@ impl ExchangeClient for Binance {
     async fn create_order(&self, order: &OrderCreating) -> Result<RestRequestOutcome> {
         let specific_currency_pair = self.get_specific_currency_pair(order.header.currency_pair);
 
+        let amount_param = match order.header.amount_kind {
+            OrderAmountKind::Base => "quantity",
+            OrderAmountKind::Quote if self.settings.is_margin_trading => {
+                bail!("Binance futures don't support order amount in quote currency")
+            }
+            OrderAmountKind::Quote => "quoteOrderQty",
+        };
+
         let mut http_params = vec![
             (
                 "symbol".to_owned(),
@@ -40,7 +56,7 @@ impl ExchangeClient for Binance {
                 "type".to_owned(),
                 Self::to_server_order_type(order.header.order_type),
             ),
-            ("quantity".to_owned(), order.header.amount.to_string()),
+            (amount_param.to_owned(), order.header.amount.to_string()),
             (
                 "newClientOrderId".to_owned(),
                 order.header.client_order_id.as_str().to_owned(),
@@ -48,11 +64,34 @@ impl ExchangeClient for Binance {
         ];
 
         if order.header.order_type != OrderType::Market {
-            http_params.push(("timeInForce".to_owned(), "GTC".to_owned()));
+            match order.header.expire_at {
+                // Engine cancels expired orders itself on spot, because only futures support GTD
+                Some(expire_at) if self.settings.is_margin_trading => {
+                    http_params.push(("timeInForce".to_owned(), "GTD".to_owned()));
+                    http_params.push((
+                        "goodTillDate".to_owned(),
+                        expire_at.timestamp_millis().to_string(),
+                    ));
+                }
+                _ => http_params.push(("timeInForce".to_owned(), "GTC".to_owned())),
+            }
             http_params.push(("price".to_owned(), order.price.to_string()));
         } else if order.header.execution_type == OrderExecutionType::MakerOnly {
             http_params.push(("timeInForce".to_owned(), "GTX".to_owned()));
         }
+
+        match self.get_order_position_side(&order.header) {
+            Some(position_side) => http_params.push((
+                "positionSide".to_owned(),
+                Self::to_server_position_side(position_side),
+            )),
+            // Binance rejects reduce-only flag in hedge mode, where position side is enough
+            None if order.header.reduce_only && self.settings.is_margin_trading => {
+                http_params.push(("reduceOnly".to_owned(), "true".to_owned()))
+            }
+            None => {}
+        }
+
         self.add_authentification_headers(&mut http_params)?;
 
         let url_path = match self.settings.is_margin_trading {
@@ -196,6 +235,154 @@ impl ExchangeClient for Binance {
         self.rest_client.get(full_url, &self.settings.api_key).await
     }
 
+    async fn request_my_trades_page(
+        &self,
+        symbol: &Symbol,
+        page: &PageRequest,
+    ) -> Result<RestRequestOutcome> {
+        let specific_currency_pair = self.get_specific_currency_pair(symbol.currency_pair());
+        let mut http_params = vec![(
+            "symbol".to_owned(),
+            specific_currency_pair.as_str().to_owned(),
+        )];
+        Binance::add_page_params(&mut http_params, page, "fromId")?;
+        self.add_authentification_headers(&mut http_params)?;
+
+        let url_path = match self.settings.is_margin_trading {
+            true => "/fapi/v1/userTrades",
+            false => "/api/v3/myTrades",
+        };
+
+        let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &http_params)?;
+        self.rest_client.get(full_url, &self.settings.api_key).await
+    }
+
+    async fn request_all_orders_page(
+        &self,
+        currency_pair: CurrencyPair,
+        page: &PageRequest,
+    ) -> Result<RestRequestOutcome> {
+        let specific_currency_pair = self.get_specific_currency_pair(currency_pair);
+        let mut http_params = vec![(
+            "symbol".to_owned(),
+            specific_currency_pair.as_str().to_owned(),
+        )];
+        Binance::add_page_params(&mut http_params, page, "orderId")?;
+        self.add_authentification_headers(&mut http_params)?;
+
+        let url_path = match self.settings.is_margin_trading {
+            true => "/fapi/v1/allOrders",
+            false => "/api/v3/allOrders",
+        };
+
+        let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &http_params)?;
+        self.rest_client.get(full_url, &self.settings.api_key).await
+    }
+
+    async fn request_historical_trades(
+        &self,
+        currency_pair: CurrencyPair,
+        page: &PageRequest,
+    ) -> Result<RestRequestOutcome> {
+        let specific_currency_pair = self.get_specific_currency_pair(currency_pair);
+        let mut http_params = vec![(
+            "symbol".to_owned(),
+            specific_currency_pair.as_str().to_owned(),
+        )];
+        Binance::add_page_params(&mut http_params, page, "fromId")?;
+
+        let url_path = match self.settings.is_margin_trading {
+            true => "/fapi/v1/aggTrades",
+            false => "/api/v3/aggTrades",
+        };
+
+        let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &http_params)?;
+        self.rest_client.get(full_url, &self.settings.api_key).await
+    }
+
+    async fn request_klines(
+        &self,
+        currency_pair: CurrencyPair,
+        interval: &str,
+        page: &PageRequest,
+    ) -> Result<RestRequestOutcome> {
+        let specific_currency_pair = self.get_specific_currency_pair(currency_pair);
+        let mut http_params = vec![
+            (
+                "symbol".to_owned(),
+                specific_currency_pair.as_str().to_owned(),
+            ),
+            ("interval".to_owned(), interval.to_owned()),
+        ];
+        // Kline id is its open time, so cursor is passed as start time
+        Binance::add_page_params(&mut http_params, page, "startTime")?;
+
+        let url_path = match self.settings.is_margin_trading {
+            true => "/fapi/v1/klines",
+            false => "/api/v3/klines",
+        };
+
+        let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &http_params)?;
+        self.rest_client.get(full_url, &self.settings.api_key).await
+    }
+
+    /// Aggregated trades are requested for `aggTrade` stream, because they have their own ids
+    async fn request_trades_from_id(
+        &self,
+        currency_pair: CurrencyPair,
+        trade_stream: TradeStream,
+        from_id: u64,
+        limit: usize,
+    ) -> Result<RestRequestOutcome> {
+        let specific_currency_pair = self.get_specific_currency_pair(currency_pair);
+        let http_params = vec![
+            (
+                "symbol".to_owned(),
+                specific_currency_pair.as_str().to_owned(),
+            ),
+            ("fromId".to_owned(), from_id.to_string()),
+            ("limit".to_owned(), limit.to_string()),
+        ];
+
+        let is_aggregated = trade_stream == TradeStream::AggregatedTrades;
+        let url_path = match (self.settings.is_margin_trading, is_aggregated) {
+            (true, true) => "/fapi/v1/aggTrades",
+            (true, false) => "/fapi/v1/historicalTrades",
+            (false, true) => "/api/v3/aggTrades",
+            (false, false) => "/api/v3/historicalTrades",
+        };
+
+        let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &http_params)?;
+        self.rest_client.get(full_url, &self.settings.api_key).await
+    }
+
+    async fn request_order_book_snapshot(
+        &self,
+        currency_pair: CurrencyPair,
+        depth: usize,
+    ) -> Result<RestRequestOutcome> {
+        let specific_currency_pair = self.get_specific_currency_pair(currency_pair);
+        let http_params = vec![
+            (
+                "symbol".to_owned(),
+                specific_currency_pair.as_str().to_owned(),
+            ),
+            ("limit".to_owned(), depth.to_string()),
+        ];
+
+        let url_path = match self.settings.is_margin_trading {
+            true => "/fapi/v1/depth",
+            false => "/api/v3/depth",
+        };
+
+        let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &http_params)?;
+        self.rest_client.get(full_url, &self.settings.api_key).await
+    }
+
+    fn page_limit(&self) -> usize {
+        1000
+    }
+
     async fn request_get_position(&self) -> Result<RestRequestOutcome> {
         let mut http_params = Vec::new();
         self.add_authentification_headers(&mut http_params)?;
@@ -250,7 +437,15 @@ impl ExchangeClient for Binance {
                 "leverage".to_string(),
                 position.derivative.leverage.to_string(),
             ),
-            ("positionSide".to_string(), "BOTH".to_string()),
+            (
+                "positionSide".to_string(),
+                Self::to_server_position_side(
+                    position
+                        .derivative
+                        .position_side
+                        .unwrap_or(PositionSide::Both),
+                ),
+            ),
             (
                 "quantity".to_string(),
                 position.derivative.position.abs().to_string(),
@@ -279,4 +474,198 @@ impl ExchangeClient for Binance {
             .post(full_url, &self.settings.api_key, &http_params)
             .await
     }
+
+    async fn sync_server_time(&self) -> Result<()> {
+        let url_path = match self.settings.is_margin_trading {
+            true => "/fapi/v1/time",
+            false => "/api/v3/time",
+        };
+        let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &vec![])?;
+
+        let request_start_ms = get_current_milliseconds();
+        let response = self
+            .rest_client
+            .get(full_url, &self.settings.api_key)
+            .await?;
+        let request_end_ms = get_current_milliseconds();
+
+        is_rest_error_code(&response)?;
+
+        let data: Value = serde_json::from_str(&response.content)
+            .context("Unable to parse server time response")?;
+        let server_time_ms = data["serverTime"]
+            .as_u64()
+            .context("Unable to get `serverTime` field")?;
+
+        self.server_time_offset.update(
+            self.id,
+            request_start_ms,
+            request_end_ms,
+            server_time_ms as u128,
+        );
+
+        Ok(())
+    }
+
+    async fn request_withdraw(&self, withdrawal: &Withdrawal) -> Result<RestRequestOutcome> {
+        let mut http_params = vec![
+            ("address".to_string(), withdrawal.address.clone()),
+            ("amount".to_string(), withdrawal.amount.to_string()),
+            (
+                "coin".to_string(),
+                withdrawal.currency_code.as_str().to_uppercase(),
+            ),
+        ];
+
+        if let Some(address_tag) = &withdrawal.address_tag {
+            http_params.push(("addressTag".to_string(), address_tag.clone()));
+        }
+
+        if let Some(network) = &withdrawal.network {
+            http_params.push(("network".to_string(), network.to_uppercase()));
+        }
+
+        self.add_authentification_headers(&mut http_params)?;
+
+        // Wallet endpoints are available only on spot host
+        let url_path = "/sapi/v1/capital/withdraw/apply";
+        let rest_host = Self::make_hosts(false).rest_host;
+        let full_url = rest_client::build_uri(rest_host, url_path, &http_params)?;
+
+        self.rest_client
+            .post(full_url, &self.settings.api_key, &http_params)
+            .await
+    }
+
+    async fn request_internal_transfer(
+        &self,
+        transfer: &InternalTransfer,
+    ) -> Result<RestRequestOutcome> {
+        let mut http_params = vec![
+            ("amount".to_string(), transfer.amount.to_string()),
+            (
+                "asset".to_string(),
+                transfer.currency_code.as_str().to_uppercase(),
+            ),
+            (
+                "type".to_string(),
+                Self::to_server_transfer_type(transfer.from, transfer.to)?.to_string(),
+            ),
+        ];
+
+        self.add_authentification_headers(&mut http_params)?;
+
+        let url_path = "/sapi/v1/asset/transfer";
+        let rest_host = Self::make_hosts(false).rest_host;
+        let full_url = rest_client::build_uri(rest_host, url_path, &http_params)?;
+
+        self.rest_client
+            .post(full_url, &self.settings.api_key, &http_params)
+            .await
+    }
+
+    async fn request_dust_balances(&self) -> Result<RestRequestOutcome> {
+        let mut http_params = Vec::new();
+        self.add_authentification_headers(&mut http_params)?;
+
+        let url_path = "/sapi/v1/asset/dust-btc";
+        let rest_host = Self::make_hosts(false).rest_host;
+        let full_url = rest_client::build_uri(rest_host, url_path, &http_params)?;
+
+        self.rest_client
+            .post(full_url, &self.settings.api_key, &http_params)
+            .await
+    }
+
+    async fn request_dust_conversion(
+        &self,
+        currency_codes: &[CurrencyCode],
+    ) -> Result<RestRequestOutcome> {
+        let mut http_params = currency_codes
+            .iter()
+            .map(|currency_code| ("asset".to_string(), currency_code.as_str().to_uppercase()))
+            .collect_vec();
+        self.add_authentification_headers(&mut http_params)?;
+
+        let url_path = "/sapi/v1/asset/dust";
+        let rest_host = Self::make_hosts(false).rest_host;
+        let full_url = rest_client::build_uri(rest_host, url_path, &http_params)?;
+
+        self.rest_client
+            .post(full_url, &self.settings.api_key, &http_params)
+            .await
+    }
+
+    async fn request_system_status(&self) -> Result<RestRequestOutcome> {
+        let url_path = "/sapi/v1/system/status";
+        let rest_host = Self::make_hosts(false).rest_host;
+        let full_url = rest_client::build_uri(rest_host, url_path, &vec![])?;
+
+        self.rest_client.get(full_url, &self.settings.api_key).await
+    }
+
+    async fn request_margin_info(&self) -> Result<RestRequestOutcome> {
+        if !self.settings.is_margin_trading {
+            bail!("Margin info is available only for Binance futures")
+        }
+
+        let mut http_params = Vec::new();
+        self.add_authentification_headers(&mut http_params)?;
+
+        let url_path = "/fapi/v2/account";
+        let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &http_params)?;
+
+        self.rest_client.get(full_url, &self.settings.api_key).await
+    }
+
+    async fn request_sub_account_balances(&self, sub_account: &str) -> Result<RestRequestOutcome> {
+        let mut http_params = vec![("email".to_string(), sub_account.to_string())];
+        self.add_authentification_headers(&mut http_params)?;
+
+        let url_path = "/sapi/v3/sub-account/assets";
+        let rest_host = Self::make_hosts(false).rest_host;
+        let full_url = rest_client::build_uri(rest_host, url_path, &http_params)?;
+
+        self.rest_client.get(full_url, &self.settings.api_key).await
+    }
+
+    async fn request_sub_account_transfer(
+        &self,
+        transfer: &SubAccountTransfer,
+    ) -> Result<RestRequestOutcome> {
+        let mut http_params = vec![
+            ("amount".to_string(), transfer.amount.to_string()),
+            (
+                "asset".to_string(),
+                transfer.currency_code.as_str().to_uppercase(),
+            ),
+            (
+                "fromAccountType".to_string(),
+                Self::to_server_sub_account_type(transfer.from_wallet).to_string(),
+            ),
+            (
+                "toAccountType".to_string(),
+                Self::to_server_sub_account_type(transfer.to_wallet).to_string(),
+            ),
+        ];
+
+        // Master account is used by exchange if email isn't specified
+        if let Some(from_sub_account) = &transfer.from_sub_account {
+            http_params.push(("fromEmail".to_string(), from_sub_account.clone()));
+        }
+
+        if let Some(to_sub_account) = &transfer.to_sub_account {
+            http_params.push(("toEmail".to_string(), to_sub_account.clone()));
+        }
+
+        self.add_authentification_headers(&mut http_params)?;
+
+        let url_path = "/sapi/v1/sub-account/universalTransfer";
+        let rest_host = Self::make_hosts(false).rest_host;
+        let full_url = rest_client::build_uri(rest_host, url_path, &http_params)?;
+
+        self.rest_client
+            .post(full_url, &self.settings.api_key, &http_params)
+            .await
+    }
 }
@@ -17,3 +17,4 @@
 pub mod binance;
 pub mod exchange_client;
 pub mod support;
+pub mod websocket_messages;
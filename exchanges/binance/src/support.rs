@@ -1,6 +1,7 @@
 use mmb_core::misc::derivative_position::DerivativePosition;
 use mmb_utils::infrastructure::WithExpect;
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, UNIX_EPOCH};
 
@@ -12,19 +13,34 @@ use itertools::Itertools;
 use mmb_utils::{value_to_decimal::GetOrErr, DateTime};
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
 use url::Url;
 
 use super::binance::Binance;
-use mmb_core::exchanges::common::{ActivePosition, ClosedPosition, SortedOrderData};
-use mmb_core::exchanges::events::{ExchangeBalancesAndPositions, ExchangeEvent, TradeId};
+use super::websocket_messages::{
+    BinanceAggTrade, BinanceForceOrder, BinanceOrderBookSnapshot, BinanceOrderSide,
+    BinanceOrderStatus, BinanceOrderTradeUpdate, BinanceOrderUpdate, BinancePositionSide,
+    BinanceTrade,
+};
+use mmb_core::connectivity::websocket_message_router::WebSocketMessageRouter;
+use mmb_core::exchanges::common::{ActivePosition, ClosedPosition};
+use mmb_core::exchanges::events::{
+    DustConversion, ExchangeBalance, ExchangeBalancesAndPositions, ExchangeEvent,
+    LiquidationOrderEvent, MarginCallEvent, MarginCallPosition, TickDirection, Trade, TradeId,
+    TradeStream,
+};
+use mmb_core::exchanges::general::dust_conversion::DustBalance;
+use mmb_core::exchanges::general::maintenance::SystemStatus;
+use mmb_core::exchanges::general::margin::MarginInfo;
 use mmb_core::exchanges::general::order::get_order_trades::OrderTrade;
+use mmb_core::exchanges::market_data_downloader::HistoricalMessage;
 use mmb_core::exchanges::rest_client;
 use mmb_core::exchanges::{
     common::CurrencyCode, common::CurrencyId,
-    general::handlers::handle_order_filled::FillEventData, general::symbol::Symbol,
-    traits::Support,
+    general::handlers::handle_order_filled::FillEventData, general::symbol::ContractType,
+    general::symbol::Symbol, traits::Support,
 };
 use mmb_core::order_book::event::{EventType, OrderBookEvent};
 use mmb_core::order_book::order_book_data::OrderBookData;
@@ -39,6 +55,9 @@ use mmb_core::{
     orders::fill::EventSourceType,
 };
 
+/// All dust balances are converted by Binance to BNB
+const DUST_CONVERSION_ASSET: &str = "BNB";
+
 #[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct BinanceOrderInfo {
     #[serde(rename = "symbol")]
@@ -52,8 +71,8 @@ pub struct BinanceOrderInfo {
     pub orig_quantity: Amount,
     #[serde(rename = "executedQty")]
     pub executed_quantity: Amount,
-    pub status: String,
-    pub side: String,
+    pub status: BinanceOrderStatus,
+    pub side: BinanceOrderSide,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
@@ -77,8 +96,86 @@ struct BinancePosition {
     #[serde(rename = "LiquidationPrice")]
     pub liquidation_price: Price,
     pub leverage: Decimal,
-    #[serde(rename = "PositionSide")]
-    pub position_side: Decimal,
+    #[serde(rename = "positionSide")]
+    pub position_side: BinancePositionSide,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+struct BinanceMarginCallPosition {
+    #[serde(rename = "s")]
+    pub specific_currency_pair: SpecificCurrencyPair,
+    #[serde(rename = "ps")]
+    pub position_side: BinancePositionSide,
+    #[serde(rename = "pa")]
+    pub position_amount: Amount,
+    #[serde(rename = "mt")]
+    pub margin_type: String,
+    #[serde(rename = "mp")]
+    pub mark_price: Price,
+    #[serde(rename = "up")]
+    pub unrealized_pnl: Decimal,
+    #[serde(rename = "mm")]
+    pub maintenance_margin: Decimal,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+struct BinanceDustAssets {
+    pub details: Vec<BinanceDustAsset>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+struct BinanceDustAsset {
+    pub asset: String,
+    #[serde(rename = "amountFree")]
+    pub amount_free: Amount,
+    #[serde(rename = "toBNB")]
+    pub to_bnb: Amount,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+struct BinanceDustConversionResult {
+    #[serde(rename = "transferResult")]
+    pub transfer_result: Vec<BinanceDustTransfer>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+struct BinanceDustTransfer {
+    #[serde(rename = "fromAsset")]
+    pub from_asset: String,
+    pub amount: Amount,
+    #[serde(rename = "transferedAmount")]
+    pub transfered_amount: Amount,
+    #[serde(rename = "serviceChargeAmount")]
+    pub service_charge_amount: Amount,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceMarginInfo {
+    pub total_margin_balance: Decimal,
+    pub total_maint_margin: Decimal,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+struct BinanceSystemStatus {
+    /// 0 - normal, 1 - system maintenance
+    pub status: u8,
+    pub msg: String,
+}
+
+/// Trade of `historicalTrades` or `aggTrades` endpoints
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+struct BinanceRestTrade {
+    #[serde(alias = "a")]
+    pub id: u64,
+    #[serde(alias = "p")]
+    pub price: Price,
+    #[serde(alias = "q")]
+    pub qty: Amount,
+    #[serde(alias = "T")]
+    pub time: i64,
+    #[serde(alias = "m", rename = "isBuyerMaker")]
+    pub is_buyer_maker: bool,
 }
 
 #[async_trait]
@@ -92,43 +189,15 @@ impl Support for Binance {
     }
 
     fn on_websocket_message(&self, msg: &str) -> Result<()> {
-        let mut data: Value =
-            serde_json::from_str(msg).context("Unable to parse websocket message")?;
-        // Public stream
-        if let Some(stream) = data.get("stream") {
-            let stream = stream
-                .as_str()
-                .ok_or(anyhow!("Unable to parse stream data"))?;
-
-            if let Some(byte_index) = stream.find('@') {
-                let currency_pair = self.currency_pair_from_web_socket(&stream[..byte_index])?;
-                let data = &data["data"];
-
-                if stream.ends_with("@trade") {
-                    self.handle_trade(currency_pair, data)?;
-                    return Ok(());
-                }
-
-                // TODO handle public stream
-                if stream.ends_with("depth20") {
-                    self.process_snapshot_update(currency_pair, data)?;
-                    return Ok(());
-                }
-            }
-
+        let data: Value = serde_json::from_str(msg).context("Unable to parse websocket message")?;
+        if data.get("result").is_some() && data.get("id").is_some() {
+            log::info!("Websocket subscription response on {}: {}", self.id, msg);
             return Ok(());
         }
 
-        // so it is userData stream
-        let event_type = data["e"]
-            .as_str()
-            .ok_or(anyhow!("Unable to parse event_type"))?;
-        if event_type == "executionReport" {
-            self.handle_order_fill(msg, data)?;
-        } else if event_type == "ORDER_TRADE_UPDATE" {
-            let json_response = data["o"].take();
-            self.handle_order_fill(msg, json_response)?;
-        } else {
+        let message_type = Self::get_message_type(&data)?;
+
+        if !self.message_router.route(self, &message_type, msg, &data)? {
             self.log_unknown_message(self.id, msg);
         }
 
@@ -136,14 +205,7 @@ impl Support for Binance {
     }
 
     fn on_connecting(&self) -> Result<()> {
-        self.unified_to_specific
-            .read()
-            .iter()
-            .for_each(|(currency_pair, _)| {
-                let _ = self
-                    .last_trade_ids
-                    .insert(currency_pair.clone(), TradeId::Number(0));
-            });
+        self.last_trade_ids.clear();
 
         Ok(())
     }
@@ -172,7 +234,9 @@ impl Support for Binance {
     fn set_handle_trade_callback(
         &self,
         callback: Box<
-            dyn FnMut(CurrencyPair, TradeId, Price, Amount, OrderSide, DateTime) + Send + Sync,
+            dyn FnMut(CurrencyPair, TradeStream, TradeId, Price, Amount, OrderSide, DateTime)
+                + Send
+                + Sync,
         >,
     ) {
         *self.handle_trade_callback.lock() = callback;
@@ -195,7 +259,7 @@ impl Support for Binance {
         let (host, path) = match role {
             WebSocketRole::Main => (
                 &self.hosts.web_socket_host,
-                self.build_ws_main_path(&self.settings.websocket_channels[..]),
+                self.build_ws_main_path(&self.websocket_channels.channels()),
             ),
             WebSocketRole::Secondary => (
                 &self.hosts.web_socket2_host,
@@ -207,6 +271,26 @@ impl Support for Binance {
             .with_context(|| format!("Unable parse websocket {:?} uri", role))
     }
 
+    fn get_websocket_channels(&self) -> Vec<String> {
+        self.websocket_channels.channels()
+    }
+
+    fn add_websocket_channel(&self, channel: &str) -> Result<bool> {
+        self.websocket_channels.add(channel, &self.message_router)
+    }
+
+    fn remove_websocket_channel(&self, channel: &str) -> Result<bool> {
+        Ok(self.websocket_channels.remove(channel))
+    }
+
+    fn build_ws_subscribe_message(&self, channels: &[String]) -> Option<String> {
+        self.build_ws_subscription_message("SUBSCRIBE", channels)
+    }
+
+    fn build_ws_unsubscribe_message(&self, channels: &[String]) -> Option<String> {
+        self.build_ws_subscription_message("UNSUBSCRIBE", channels)
+    }
+
     fn get_specific_currency_pair(&self, currency_pair: CurrencyPair) -> SpecificCurrencyPair {
         self.unified_to_specific.read()[&currency_pair]
     }
@@ -215,6 +299,10 @@ impl Support for Binance {
         &self.supported_currencies
     }
 
+    fn get_unhandled_messages_counts(&self) -> HashMap<String, u64> {
+        self.message_router.unhandled_messages_counts()
+    }
+
     fn should_log_message(&self, message: &str) -> bool {
         message.contains("executionReport")
     }
@@ -313,7 +401,13 @@ impl Support for Binance {
                 ),
             };
 
-            let symbol = Symbol::new(
+            // Only USD-M futures have margin asset, they are linear contracts settled in it
+            let margin_asset: Option<CurrencyCode> = symbol
+                .get("marginAsset")
+                .and_then(|margin_asset| margin_asset.as_str())
+                .map(|margin_asset| margin_asset.into());
+
+            let mut symbol = Symbol::new(
                 is_active,
                 is_derivative,
                 base_currency_id.as_str().into(),
@@ -331,6 +425,10 @@ impl Support for Binance {
                 amount_precision,
             );
 
+            if let Some(margin_asset) = margin_asset {
+                symbol = symbol.with_contract(ContractType::Linear, dec!(1), margin_asset);
+            }
+
             result.push(Arc::new(symbol))
         }
 
@@ -423,6 +521,246 @@ impl Support for Binance {
         Ok(closed_position)
     }
 
+    fn parse_withdraw(&self, response: &RestRequestOutcome) -> Result<String> {
+        let data: Value = serde_json::from_str(&response.content)
+            .context("Unable to parse withdrawal response")?;
+        let id = data["id"]
+            .as_str()
+            .context("Unable to get `id` field from withdrawal response")?;
+
+        Ok(id.to_owned())
+    }
+
+    fn parse_internal_transfer(&self, response: &RestRequestOutcome) -> Result<String> {
+        let data: Value = serde_json::from_str(&response.content)
+            .context("Unable to parse internal transfer response")?;
+        let transfer_id = data["tranId"]
+            .as_u64()
+            .context("Unable to get `tranId` field from internal transfer response")?;
+
+        Ok(transfer_id.to_string())
+    }
+
+    fn parse_sub_account_balances(
+        &self,
+        response: &RestRequestOutcome,
+    ) -> Result<Vec<ExchangeBalance>> {
+        let sub_account_info: BinanceAccountInfo = serde_json::from_str(&response.content)
+            .context("Unable to parse sub-account balances response")?;
+
+        let balances = sub_account_info
+            .balances
+            .iter()
+            .map(|balance| ExchangeBalance {
+                currency_code: self.get_local_currency_code(&balance.asset),
+                balance: balance.free,
+            })
+            .collect_vec();
+
+        Ok(balances)
+    }
+
+    fn parse_dust_balances(&self, response: &RestRequestOutcome) -> Result<Vec<DustBalance>> {
+        let dust_assets: BinanceDustAssets = serde_json::from_str(&response.content)
+            .context("Unable to parse dust balances response")?;
+
+        let dust_balances = dust_assets
+            .details
+            .into_iter()
+            .map(|dust_asset| DustBalance {
+                currency_code: self.get_local_currency_code(&dust_asset.asset),
+                amount: dust_asset.amount_free,
+                converted_amount: dust_asset.to_bnb,
+            })
+            .collect_vec();
+
+        Ok(dust_balances)
+    }
+
+    fn parse_dust_conversion(&self, response: &RestRequestOutcome) -> Result<Vec<DustConversion>> {
+        let conversion_result: BinanceDustConversionResult =
+            serde_json::from_str(&response.content)
+                .context("Unable to parse dust conversion response")?;
+
+        let conversions = conversion_result
+            .transfer_result
+            .into_iter()
+            .map(|transfer| DustConversion {
+                currency_code: self.get_local_currency_code(&transfer.from_asset),
+                amount: transfer.amount,
+                converted_to: self.get_local_currency_code(DUST_CONVERSION_ASSET),
+                converted_amount: transfer.transfered_amount,
+                commission: transfer.service_charge_amount,
+            })
+            .collect_vec();
+
+        Ok(conversions)
+    }
+
+    fn parse_system_status(&self, response: &RestRequestOutcome) -> Result<SystemStatus> {
+        let system_status: BinanceSystemStatus = serde_json::from_str(&response.content)
+            .context("Unable to parse system status response")?;
+
+        Ok(SystemStatus {
+            is_maintenance: system_status.status != 0,
+            scheduled_maintenances: vec![],
+        })
+    }
+
+    fn parse_margin_info(&self, response: &RestRequestOutcome) -> Result<MarginInfo> {
+        let margin_info: BinanceMarginInfo = serde_json::from_str(&response.content)
+            .context("Unable to parse margin info response")?;
+
+        Ok(MarginInfo {
+            margin_balance: margin_info.total_margin_balance,
+            maintenance_margin: margin_info.total_maint_margin,
+        })
+    }
+
+    fn parse_trades_from_id(&self, response: &RestRequestOutcome) -> Result<Vec<Trade>> {
+        let trades: Vec<BinanceRestTrade> =
+            serde_json::from_str(&response.content).context("Unable to parse trades response")?;
+
+        Ok(trades
+            .into_iter()
+            .map(|trade| Trade {
+                trade_id: TradeId::Number(trade.id),
+                price: trade.price,
+                quantity: trade.qty,
+                side: match trade.is_buyer_maker {
+                    true => OrderSide::Sell,
+                    false => OrderSide::Buy,
+                },
+                transaction_time: Utc.timestamp_millis(trade.time),
+                tick_direction: TickDirection::None,
+            })
+            .collect())
+    }
+
+    fn parse_order_book_snapshot(&self, response: &RestRequestOutcome) -> Result<OrderBookData> {
+        let snapshot: BinanceOrderBookSnapshot = serde_json::from_str(&response.content)
+            .context("Unable to parse order book snapshot response")?;
+
+        Ok(snapshot.into_order_book_data())
+    }
+
+    fn parse_all_orders(&self, response: &RestRequestOutcome) -> Result<Vec<OrderInfo>> {
+        self.parse_orders(response)
+            .context("Unable to parse response content for all orders request")
+    }
+
+    fn parse_historical_trades(
+        &self,
+        response: &RestRequestOutcome,
+        currency_pair: CurrencyPair,
+    ) -> Result<Vec<HistoricalMessage>> {
+        #[derive(Deserialize, Debug)]
+        struct BinanceAggTrade {
+            #[serde(rename = "a")]
+            id: u64,
+            #[serde(rename = "p")]
+            price: String,
+            #[serde(rename = "q")]
+            quantity: String,
+            #[serde(rename = "T")]
+            time: i64,
+            #[serde(rename = "m")]
+            is_buyer_maker: bool,
+        }
+
+        let trades: Vec<BinanceAggTrade> = serde_json::from_str(&response.content)
+            .context("Unable to parse historical trades response")?;
+
+        let stream = format!("{}@trade", self.stream_currency_pair(currency_pair));
+        let messages = trades
+            .into_iter()
+            .map(|trade| {
+                // Aggregated trades are converted to messages of trades stream which is handled by client
+                let message = json!({
+                    "stream": stream,
+                    "data": {
+                        "e": "trade",
+                        "t": trade.id,
+                        "p": trade.price,
+                        "q": trade.quantity,
+                        "T": trade.time,
+                        "m": trade.is_buyer_maker,
+                    }
+                });
+
+                HistoricalMessage {
+                    id: trade.id.to_string(),
+                    time: Utc.timestamp_millis(trade.time),
+                    message: message.to_string(),
+                }
+            })
+            .collect();
+
+        Ok(messages)
+    }
+
+    fn parse_klines(
+        &self,
+        response: &RestRequestOutcome,
+        currency_pair: CurrencyPair,
+        interval: &str,
+    ) -> Result<Vec<HistoricalMessage>> {
+        let klines: Vec<Vec<Value>> =
+            serde_json::from_str(&response.content).context("Unable to parse klines response")?;
+
+        let specific_currency_pair = self.get_specific_currency_pair(currency_pair);
+        let stream = format!(
+            "{}@kline_{}",
+            self.stream_currency_pair(currency_pair),
+            interval
+        );
+        klines
+            .into_iter()
+            .map(|kline| {
+                if kline.len() < 11 {
+                    bail!("Unexpected kline format: {:?}", kline);
+                }
+
+                let open_time = kline[0]
+                    .as_i64()
+                    .context("Unable to parse kline open time")?;
+                let close_time = kline[6]
+                    .as_i64()
+                    .context("Unable to parse kline close time")?;
+                let message = json!({
+                    "stream": stream,
+                    "data": {
+                        "e": "kline",
+                        "E": close_time,
+                        "s": specific_currency_pair.as_str(),
+                        "k": {
+                            "t": open_time,
+                            "T": close_time,
+                            "s": specific_currency_pair.as_str(),
+                            "i": interval,
+                            "o": kline[1],
+                            "h": kline[2],
+                            "l": kline[3],
+                            "c": kline[4],
+                            "v": kline[5],
+                            "q": kline[7],
+                            "n": kline[8],
+                            "V": kline[9],
+                            "Q": kline[10],
+                            "x": true,
+                        }
+                    }
+                });
+
+                Ok(HistoricalMessage {
+                    id: open_time.to_string(),
+                    time: Utc.timestamp_millis(close_time),
+                    message: message.to_string(),
+                })
+            })
+            .collect()
+    }
+
     fn parse_get_balance(&self, response: &RestRequestOutcome) -> ExchangeBalancesAndPositions {
         let binance_account_info: BinanceAccountInfo = serde_json::from_str(&response.content)
             .expect("Unable to parse response content for get_balance request");
@@ -435,17 +773,98 @@ impl Support for Binance {
     }
 }
 
+/// Public streams which can be requested as websocket channels in settings
+pub(crate) const PUBLIC_STREAM_CHANNELS: [&str; 4] = ["trade", "aggtrade", "depth20", "forceorder"];
+
 impl Binance {
-    pub(crate) fn handle_trade(&self, currency_pair: CurrencyPair, data: &Value) -> Result<()> {
-        let trade_id = TradeId::from(data["t"].clone());
+    pub(crate) fn create_message_router() -> WebSocketMessageRouter<Binance> {
+        let router = WebSocketMessageRouter::new();
+
+        // Public streams
+        router.register_handler(
+            "trade",
+            Self::stream_handler(|binance, currency_pair, trade: BinanceTrade| {
+                binance.handle_trade(currency_pair, TradeStream::Trades, trade)
+            }),
+        );
+        router.register_handler(
+            "aggtrade",
+            Self::stream_handler(|binance, currency_pair, trade: BinanceAggTrade| {
+                binance.handle_trade(currency_pair, TradeStream::AggregatedTrades, trade.into())
+            }),
+        );
+        router.register_handler(
+            "depth20",
+            Self::stream_handler(Self::process_snapshot_update),
+        );
+        router.register_handler("forceorder", Self::stream_handler(Self::handle_force_order));
+
+        // User data stream
+        router.register_handler("executionReport", |binance: &Binance, msg, data| {
+            let order_update = BinanceOrderUpdate::deserialize(data)
+                .context("Unable to parse execution report")?;
+            binance.handle_order_fill(msg, order_update)
+        });
+        router.register_handler("ORDER_TRADE_UPDATE", |binance: &Binance, msg, data| {
+            let order_trade_update = BinanceOrderTradeUpdate::deserialize(data)
+                .context("Unable to parse order trade update")?;
+            binance.handle_order_fill(msg, order_trade_update.order)
+        });
+        router.register_handler("MARGIN_CALL", |binance: &Binance, msg, data| {
+            binance.handle_margin_call(msg, data)
+        });
+
+        router
+    }
 
-        let mut trade_id_from_lasts =
-            self.last_trade_ids.get_mut(&currency_pair).with_expect(|| {
-                format!(
-                    "There are no last_trade_id for given currency_pair {}",
-                    currency_pair
-                )
-            });
+    /// Public stream messages are typed by stream name, user data messages are typed by event type
+    fn get_message_type(data: &Value) -> Result<String> {
+        match data.get("stream") {
+            Some(stream) => {
+                let stream = stream.as_str().context("Unable to parse stream data")?;
+                let stream_type = match stream.find('@') {
+                    Some(byte_index) => &stream[byte_index + 1..],
+                    None => stream,
+                };
+
+                Ok(stream_type.to_lowercase())
+            }
+            None => data["e"]
+                .as_str()
+                .map(|event_type| event_type.to_owned())
+                .context("Unable to parse event_type"),
+        }
+    }
+
+    /// Wraps handler of public stream with parsing of stream currency pair and message data
+    fn stream_handler<T: DeserializeOwned>(
+        handler: fn(&Binance, CurrencyPair, T) -> Result<()>,
+    ) -> impl Fn(&Binance, &str, &Value) -> Result<()> {
+        move |binance: &Binance, _msg: &str, data: &Value| {
+            let stream = data["stream"]
+                .as_str()
+                .context("Unable to parse stream data")?;
+            let specific_currency_pair = stream.split('@').next().unwrap_or_default();
+            let currency_pair = binance.currency_pair_from_web_socket(specific_currency_pair)?;
+            let message = T::deserialize(&data["data"])
+                .with_context(|| format!("Unable to parse data of stream {}", stream))?;
+
+            handler(binance, currency_pair, message)
+        }
+    }
+
+    pub(crate) fn handle_trade(
+        &self,
+        currency_pair: CurrencyPair,
+        trade_stream: TradeStream,
+        trade: BinanceTrade,
+    ) -> Result<()> {
+        let trade_id = TradeId::Number(trade.trade_id);
+
+        let mut trade_id_from_lasts = self
+            .last_trade_ids
+            .entry((currency_pair, trade_stream))
+            .or_insert(TradeId::Number(0));
 
         if self.is_reducing_market_data && trade_id_from_lasts.get_number() >= trade_id.get_number()
         {
@@ -461,50 +880,113 @@ impl Binance {
 
         *trade_id_from_lasts = trade_id.clone();
 
-        let price: Decimal = data["p"]
-            .as_str()
-            .context("Unable to get string from 'p' field json data")?
-            .parse()?;
-
-        let quantity: Decimal = data["q"]
-            .as_str()
-            .context("Unable to get string from 'q' field json data")?
-            .parse()?;
-        let order_side = if data["m"] == true {
+        let order_side = if trade.is_buyer_maker {
             OrderSide::Sell
         } else {
             OrderSide::Buy
         };
-        let datetime = data["T"]
-            .as_i64()
-            .context("Unable to get i64 from 'T' field json data")?;
 
         (&self.handle_trade_callback).lock()(
             currency_pair,
+            trade_stream,
             trade_id,
-            price,
-            quantity,
+            trade.price,
+            trade.amount,
             order_side,
-            Utc.timestamp_millis(datetime),
+            Utc.timestamp_millis(trade.trade_time),
         );
 
         Ok(())
     }
 
-    pub fn process_snapshot_update(&self, currency_pair: CurrencyPair, data: &Value) -> Result<()> {
-        let last_update_id = data["lastUpdateId"].to_string();
-        let last_update_id = last_update_id.trim_matches('"');
-        let raw_asks = data["asks"]
-            .as_array()
-            .ok_or(anyhow!("Unable to parse 'asks' in Binance"))?;
-        let raw_bids = data["bids"]
-            .as_array()
-            .ok_or(anyhow!("Unable to parse 'bids' in Binance"))?;
+    // According to https://binance-docs.github.io/apidocs/futures/en/#liquidation-order-streams
+    pub(crate) fn handle_force_order(
+        &self,
+        currency_pair: CurrencyPair,
+        force_order: BinanceForceOrder,
+    ) -> Result<()> {
+        let order = force_order.order;
+
+        self.send_event(ExchangeEvent::LiquidationOrder(LiquidationOrderEvent {
+            exchange_account_id: self.id,
+            currency_pair,
+            side: order.side.into(),
+            price: order.average_price,
+            amount: order.filled_amount,
+            transaction_time: Utc.timestamp_millis(order.transaction_time),
+        }))
+    }
+
+    // According to https://binance-docs.github.io/apidocs/futures/en/#event-margin-call
+    pub(crate) fn handle_margin_call(&self, msg: &str, data: &Value) -> Result<()> {
+        log::warn!("Margin call received for {}: {}", self.id, msg);
+
+        let binance_positions: Vec<BinanceMarginCallPosition> =
+            serde_json::from_value(data["p"].clone())
+                .context("Unable to parse positions of margin call")?;
+
+        let cross_wallet_balance = data["cw"]
+            .as_str()
+            .map(|balance| balance.parse::<Decimal>())
+            .transpose()?;
+        let margin_ratio = cross_wallet_balance.and_then(|cross_wallet_balance| {
+            Self::calculate_cross_margin_ratio(cross_wallet_balance, &binance_positions)
+        });
+
+        let positions = binance_positions
+            .into_iter()
+            .map(|position| {
+                Ok(MarginCallPosition {
+                    currency_pair: self
+                        .get_unified_currency_pair(&position.specific_currency_pair)?,
+                    position_side: Some(position.position_side.into()),
+                    position: position.position_amount,
+                    mark_price: position.mark_price,
+                    unrealized_pnl: position.unrealized_pnl,
+                    maintenance_margin: position.maintenance_margin,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.send_event(ExchangeEvent::MarginCall(MarginCallEvent {
+            exchange_account_id: self.id,
+            margin_ratio,
+            positions,
+        }))
+    }
+
+    /// Margin ratio of cross positions is maintenance margin divided by margin balance
+    fn calculate_cross_margin_ratio(
+        cross_wallet_balance: Decimal,
+        positions: &[BinanceMarginCallPosition],
+    ) -> Option<Decimal> {
+        let cross_positions = positions
+            .iter()
+            .filter(|position| position.margin_type == "CROSSED")
+            .collect_vec();
+        if cross_positions.is_empty() {
+            return None;
+        }
+
+        let unrealized_pnl: Decimal = cross_positions.iter().map(|x| x.unrealized_pnl).sum();
+        let maintenance_margin: Decimal =
+            cross_positions.iter().map(|x| x.maintenance_margin).sum();
 
-        let asks = get_order_book_side(raw_asks)?;
-        let bids = get_order_book_side(raw_bids)?;
+        let margin_balance = cross_wallet_balance + unrealized_pnl;
+        if margin_balance <= dec!(0) {
+            return Some(dec!(1));
+        }
 
-        let order_book_data = OrderBookData::new(asks, bids);
+        Some(maintenance_margin / margin_balance)
+    }
+
+    pub fn process_snapshot_update(
+        &self,
+        currency_pair: CurrencyPair,
+        snapshot: BinanceOrderBookSnapshot,
+    ) -> Result<()> {
+        let last_update_id = snapshot.last_update_id.to_string();
+        let order_book_data = snapshot.into_order_book_data();
         self.handle_order_book_snapshot(currency_pair, &last_update_id, order_book_data, None)
     }
 
@@ -541,6 +1023,12 @@ impl Binance {
         self.send_event(event)
     }
 
+    fn stream_currency_pair(&self, currency_pair: CurrencyPair) -> String {
+        self.get_specific_currency_pair(currency_pair)
+            .as_str()
+            .to_lowercase()
+    }
+
     fn currency_pair_from_web_socket(&self, currency_pair: &str) -> Result<CurrencyPair> {
         let specific_currency_pair = currency_pair.to_uppercase().as_str().into();
         self.get_unified_currency_pair(&specific_currency_pair)
@@ -560,22 +1048,36 @@ impl Binance {
         }
     }
 
-    fn build_ws_main_path(&self, websocket_channels: &[String]) -> String {
-        let stream_names = self
-            .traded_specific_currencies
+    pub(crate) fn build_ws_main_path(&self, websocket_channels: &[String]) -> String {
+        let stream_names = self.get_stream_names(websocket_channels).join("/");
+        format!("/stream?streams={}", stream_names)
+    }
+
+    fn build_ws_subscription_message(&self, method: &str, channels: &[String]) -> Option<String> {
+        let stream_names = self.get_stream_names(channels);
+        if stream_names.is_empty() {
+            return None;
+        }
+
+        let message = json!({
+            "method": method,
+            "params": stream_names,
+            "id": 1,
+        });
+        Some(message.to_string())
+    }
+
+    fn get_stream_names(&self, websocket_channels: &[String]) -> Vec<String> {
+        self.traded_specific_currencies
             .lock()
             .iter()
             .flat_map(|currency_pair| {
-                let mut results = Vec::new();
-                for channel in websocket_channels {
-                    let result = Self::get_stream_name(currency_pair, channel);
-                    results.push(result);
-                }
-                results
+                websocket_channels
+                    .iter()
+                    .map(move |channel| Self::get_stream_name(currency_pair, channel))
             })
-            .join("/");
-        let ws_path = format!("/stream?streams={}", stream_names);
-        ws_path.to_lowercase()
+            .map(|stream_name| stream_name.to_lowercase())
+            .collect()
     }
 
     async fn build_ws_secondary_path(&self) -> Result<String> {
@@ -622,15 +1124,22 @@ impl Binance {
                 )
             });
 
-        let side = match binance_position.position_side > dec!(0) {
-            true => OrderSide::Buy,
-            false => OrderSide::Sell,
+        let position_side = binance_position.position_side.into();
+
+        let side = match position_side {
+            PositionSide::Long => OrderSide::Buy,
+            PositionSide::Short => OrderSide::Sell,
+            PositionSide::Both => match binance_position.position_amount > dec!(0) {
+                true => OrderSide::Buy,
+                false => OrderSide::Sell,
+            },
         };
 
         let derivative_position = DerivativePosition::new(
             currency_pair,
             binance_position.position_amount,
             Some(side),
+            Some(position_side),
             dec!(0),
             binance_position.liquidation_price,
             binance_position.leverage,
@@ -639,20 +1148,3 @@ impl Binance {
         ActivePosition::new(derivative_position)
     }
 }
-
-fn get_order_book_side(levels: &Vec<Value>) -> Result<SortedOrderData> {
-    levels
-        .iter()
-        .map(|x| {
-            let price = x[0]
-                .as_str()
-                .ok_or(anyhow!("Unable parse price of order book side in Binance"))?
-                .parse()?;
-            let amount = x[1]
-                .as_str()
-                .ok_or(anyhow!("Unable parse amount of order book side in Binance"))?
-                .parse()?;
-            Ok((price, amount))
-        })
-        .try_collect()
-}
@@ -0,0 +1,304 @@
+use mmb_core::exchanges::common::{Amount, CurrencyId, Price, SpecificCurrencyPair};
+use mmb_core::order_book::order_book_data::OrderBookData;
+use mmb_core::orders::order::{ClientOrderId, OrderSide, OrderStatus, PositionSide};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BinanceOrderSide {
+    Buy,
+    Sell,
+}
+
+impl From<BinanceOrderSide> for OrderSide {
+    fn from(side: BinanceOrderSide) -> Self {
+        match side {
+            BinanceOrderSide::Buy => OrderSide::Buy,
+            BinanceOrderSide::Sell => OrderSide::Sell,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BinanceOrderStatus {
+    New,
+    PartiallyFilled,
+    Filled,
+    PendingCancel,
+    Canceled,
+    Expired,
+    ExpiredInMatch,
+    Rejected,
+    /// Liquidation order of futures executed by insurance fund
+    NewInsurance,
+    /// Auto-deleveraging order of futures
+    NewAdl,
+}
+
+impl From<BinanceOrderStatus> for OrderStatus {
+    fn from(status: BinanceOrderStatus) -> Self {
+        match status {
+            BinanceOrderStatus::New
+            | BinanceOrderStatus::PartiallyFilled
+            | BinanceOrderStatus::NewInsurance
+            | BinanceOrderStatus::NewAdl => OrderStatus::Created,
+            BinanceOrderStatus::Filled => OrderStatus::Completed,
+            BinanceOrderStatus::PendingCancel => OrderStatus::Canceling,
+            BinanceOrderStatus::Canceled
+            | BinanceOrderStatus::Expired
+            | BinanceOrderStatus::ExpiredInMatch
+            | BinanceOrderStatus::Rejected => OrderStatus::Canceled,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BinancePositionSide {
+    Both,
+    Long,
+    Short,
+}
+
+impl From<BinancePositionSide> for PositionSide {
+    fn from(position_side: BinancePositionSide) -> Self {
+        match position_side {
+            BinancePositionSide::Both => PositionSide::Both,
+            BinancePositionSide::Long => PositionSide::Long,
+            BinancePositionSide::Short => PositionSide::Short,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BinanceExecutionType {
+    New,
+    Canceled,
+    Replaced,
+    Rejected,
+    Trade,
+    Expired,
+    TradePrevention,
+    /// Liquidation of futures position
+    Calculated,
+    Amendment,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BinanceTimeInForce {
+    Gtc,
+    Ioc,
+    Fok,
+    /// Post only
+    Gtx,
+    Gtd,
+    GteGtc,
+}
+
+/// Order update of user data stream: `executionReport` event of spot
+/// or `o` field of `ORDER_TRADE_UPDATE` event of futures
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BinanceOrderUpdate {
+    #[serde(rename = "s")]
+    pub specific_currency_pair: SpecificCurrencyPair,
+    #[serde(rename = "c")]
+    pub client_order_id: ClientOrderId,
+    /// Client order id of cancelled spot order. It's empty for other events
+    #[serde(rename = "C", default)]
+    pub original_client_order_id: Option<ClientOrderId>,
+    #[serde(rename = "S")]
+    pub side: BinanceOrderSide,
+    #[serde(rename = "f")]
+    pub time_in_force: BinanceTimeInForce,
+    #[serde(rename = "q")]
+    pub order_amount: Amount,
+    #[serde(rename = "x")]
+    pub execution_type: BinanceExecutionType,
+    #[serde(rename = "X")]
+    pub order_status: BinanceOrderStatus,
+    #[serde(rename = "i")]
+    pub exchange_order_id: i64, //< local type is ExchangeOrderId
+    #[serde(rename = "l")]
+    pub last_filled_amount: Amount,
+    #[serde(rename = "z")]
+    pub total_filled_amount: Amount,
+    #[serde(rename = "L")]
+    pub last_filled_price: Price,
+    #[serde(rename = "n", default)]
+    pub commission_amount: Option<Amount>,
+    #[serde(rename = "N", default)]
+    pub commission_currency: Option<CurrencyId>,
+    #[serde(rename = "T")]
+    pub transaction_time: u64,
+    /// It's -1 for events without trade
+    #[serde(rename = "t")]
+    pub trade_id: i64,
+    #[serde(rename = "m")]
+    pub is_maker: bool,
+    /// Position side is specified only in futures order updates
+    #[serde(rename = "ps", default)]
+    pub position_side: Option<BinancePositionSide>,
+}
+
+impl BinanceOrderUpdate {
+    /// Client order id of order which the event relates to
+    pub fn get_client_order_id(&self) -> &ClientOrderId {
+        match &self.original_client_order_id {
+            Some(original) if !original.as_str().is_empty() => original,
+            _ => &self.client_order_id,
+        }
+    }
+}
+
+/// Futures user data event with order update
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BinanceOrderTradeUpdate {
+    #[serde(rename = "o")]
+    pub order: BinanceOrderUpdate,
+}
+
+/// Message of `trade` stream
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BinanceTrade {
+    #[serde(rename = "t")]
+    pub trade_id: u64,
+    #[serde(rename = "p")]
+    pub price: Price,
+    #[serde(rename = "q")]
+    pub amount: Amount,
+    /// Buyer is maker, so trade is initiated by seller
+    #[serde(rename = "m")]
+    pub is_buyer_maker: bool,
+    #[serde(rename = "T")]
+    pub trade_time: i64,
+}
+
+/// Message of `aggTrade` stream. Aggregated trades have their own ids, so only one of `trade`
+/// and `aggTrade` streams should be subscribed
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BinanceAggTrade {
+    #[serde(rename = "a")]
+    pub aggregated_trade_id: u64,
+    #[serde(rename = "p")]
+    pub price: Price,
+    #[serde(rename = "q")]
+    pub amount: Amount,
+    #[serde(rename = "m")]
+    pub is_buyer_maker: bool,
+    #[serde(rename = "T")]
+    pub trade_time: i64,
+}
+
+impl From<BinanceAggTrade> for BinanceTrade {
+    fn from(trade: BinanceAggTrade) -> Self {
+        BinanceTrade {
+            trade_id: trade.aggregated_trade_id,
+            price: trade.price,
+            amount: trade.amount,
+            is_buyer_maker: trade.is_buyer_maker,
+            trade_time: trade.trade_time,
+        }
+    }
+}
+
+/// Message of `depth<levels>` stream or response of order book snapshot request.
+/// Futures send stream message as `depthUpdate` event with short names
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BinanceOrderBookSnapshot {
+    #[serde(rename = "lastUpdateId", alias = "u")]
+    pub last_update_id: u64,
+    #[serde(alias = "a")]
+    pub asks: Vec<(Price, Amount)>,
+    #[serde(alias = "b")]
+    pub bids: Vec<(Price, Amount)>,
+}
+
+impl BinanceOrderBookSnapshot {
+    pub fn into_order_book_data(self) -> OrderBookData {
+        OrderBookData::new(
+            self.asks.into_iter().collect(),
+            self.bids.into_iter().collect(),
+        )
+    }
+}
+
+/// Message of `forceOrder` stream of futures
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BinanceForceOrder {
+    #[serde(rename = "o")]
+    pub order: BinanceLiquidationOrder,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BinanceLiquidationOrder {
+    #[serde(rename = "S")]
+    pub side: BinanceOrderSide,
+    #[serde(rename = "ap")]
+    pub average_price: Price,
+    #[serde(rename = "z")]
+    pub filled_amount: Amount,
+    #[serde(rename = "T")]
+    pub transaction_time: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn spot_execution_report_is_parsed() {
+        let message = r#"{"e":"executionReport","E":1499405658658,"s":"ETHBTC","c":"mUvoqJxFIILMdfAW5iGSOW","S":"BUY","o":"LIMIT","f":"GTC","q":"1.00000000","p":"0.10264410","P":"0.00000000","F":"0.00000000","g":-1,"C":"","x":"TRADE","X":"PARTIALLY_FILLED","r":"NONE","i":4293153,"l":"0.40000000","z":"0.40000000","L":"0.10264410","n":"0.00040000","N":"ETH","T":1499405658657,"t":12,"I":8641984,"w":false,"m":true,"M":false,"O":1499405658657,"Z":"0.04105764","Y":"0.04105764","Q":"0.00000000"}"#;
+
+        let order_update: BinanceOrderUpdate = serde_json::from_str(message).expect("in test");
+
+        assert_eq!(
+            order_update.get_client_order_id(),
+            &"mUvoqJxFIILMdfAW5iGSOW".into()
+        );
+        assert_eq!(order_update.side, BinanceOrderSide::Buy);
+        assert_eq!(order_update.execution_type, BinanceExecutionType::Trade);
+        assert_eq!(
+            OrderStatus::from(order_update.order_status),
+            OrderStatus::Created
+        );
+        assert_eq!(order_update.exchange_order_id, 4293153);
+        assert_eq!(order_update.last_filled_price, dec!(0.1026441));
+        assert_eq!(order_update.commission_currency, Some("ETH".into()));
+        assert_eq!(order_update.trade_id, 12);
+        assert_eq!(order_update.position_side, None);
+    }
+
+    #[test]
+    fn futures_order_trade_update_is_parsed() {
+        let message = r#"{"e":"ORDER_TRADE_UPDATE","E":1568879465651,"T":1568879465650,"o":{"s":"BTCUSDT","c":"TEST","S":"SELL","o":"TRAILING_STOP_MARKET","f":"GTC","q":"0.001","p":"0","ap":"0","sp":"7103.04","x":"NEW","X":"NEW","i":8886774,"l":"0","z":"0","L":"0","T":1568879465651,"t":0,"b":"0","a":"9.91","m":false,"R":false,"wt":"CONTRACT_PRICE","ot":"TRAILING_STOP_MARKET","ps":"LONG","cp":false,"AP":"7476.89","cr":"5.0","rp":"0"}}"#;
+
+        let order_update: BinanceOrderTradeUpdate = serde_json::from_str(message).expect("in test");
+        let order = order_update.order;
+
+        assert_eq!(order.get_client_order_id(), &"TEST".into());
+        assert_eq!(order.execution_type, BinanceExecutionType::New);
+        assert_eq!(order.order_status, BinanceOrderStatus::New);
+        assert_eq!(order.commission_amount, None);
+        assert_eq!(order.position_side, Some(BinancePositionSide::Long));
+    }
+
+    #[test]
+    fn unknown_order_side_is_error() {
+        assert!(serde_json::from_str::<BinanceOrderSide>(r#""BOTH""#).is_err());
+    }
+
+    #[test]
+    fn futures_partial_order_book_is_parsed() {
+        let message = r#"{"e":"depthUpdate","E":1571889248277,"T":1571889248276,"s":"BTCUSDT","U":390497796,"u":390497878,"pu":390497794,"b":[["7403.89","0.002"]],"a":[["7405.96","3.340"]]}"#;
+
+        let snapshot: BinanceOrderBookSnapshot = serde_json::from_str(message).expect("in test");
+
+        assert_eq!(snapshot.last_update_id, 390497878);
+        assert_eq!(snapshot.bids, vec![(dec!(7403.89), dec!(0.002))]);
+        assert_eq!(snapshot.asks, vec![(dec!(7405.96), dec!(3.340))]);
+    }
+}
@@ -105,7 +105,7 @@ impl BinanceBuilder {
             commission,
         );
         exchange.clone().connect().await;
-        exchange.build_symbols(&settings.currency_pairs).await;
+        exchange.build_symbols(&settings.currency_pairs).await?;
 
         let currency_pair_to_symbol_converter = CurrencyPairToSymbolConverter::new(
             hashmap![ exchange_account_id => exchange.clone()  ],
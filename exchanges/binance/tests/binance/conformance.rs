@@ -0,0 +1,108 @@
+use binance::binance::Binance;
+use core_tests::conformance::{ConformanceFixture, ConformanceSuite, ExpectedFill};
+use hyper::StatusCode;
+use mmb_core::exchanges::common::*;
+use mmb_core::infrastructure::init_lifetime_manager;
+use mmb_core::settings::ExchangeSettings;
+use rust_decimal_macros::dec;
+use tokio::sync::broadcast;
+
+const ALL_SYMBOLS_RESPONSE: &str = r#"{"timezone":"UTC","serverTime":1565246363776,"symbols":[{"symbol":"BTCUSDT","status":"TRADING","baseAsset":"BTC","baseAssetPrecision":8,"quoteAsset":"USDT","quotePrecision":8,"filters":[{"filterType":"PRICE_FILTER","minPrice":"0.01000000","maxPrice":"1000000.00000000","tickSize":"0.01000000"},{"filterType":"LOT_SIZE","minQty":"0.00001000","maxQty":"9000.00000000","stepSize":"0.00001000"},{"filterType":"MIN_NOTIONAL","minNotional":"10.00000000","applyToMarket":true,"avgPriceMins":5}]}]}"#;
+
+const CREATE_ORDER_RESPONSE: &str = r#"{"symbol":"BTCUSDT","orderId":28,"orderListId":-1,"clientOrderId":"6gCrw2kRUAF9CvJDGP16IP","transactTime":1507725176595}"#;
+
+const ORDER_CREATED_MESSAGE: &str = r#"{"e":"executionReport","E":1507725176595,"s":"BTCUSDT","c":"6gCrw2kRUAF9CvJDGP16IP","S":"BUY","o":"LIMIT","f":"GTC","q":"0.00200000","p":"20000.00000000","C":"","x":"NEW","X":"NEW","r":"NONE","i":28,"l":"0.00000000","z":"0.00000000","L":"0.00000000","n":"0","N":null,"T":1507725176595,"t":-1,"w":true,"m":false}"#;
+
+const ORDER_PARTIALLY_FILLED_MESSAGE: &str = r#"{"e":"executionReport","E":1507725177100,"s":"BTCUSDT","c":"6gCrw2kRUAF9CvJDGP16IP","S":"BUY","o":"LIMIT","f":"GTC","q":"0.00200000","p":"20000.00000000","C":"","x":"TRADE","X":"PARTIALLY_FILLED","r":"NONE","i":28,"l":"0.00050000","z":"0.00050000","L":"20000.00000000","n":"0.00000050","N":"BTC","T":1507725177099,"t":12,"w":false,"m":true}"#;
+
+const ORDER_FILLED_MESSAGE: &str = r#"{"e":"executionReport","E":1507725177300,"s":"BTCUSDT","c":"6gCrw2kRUAF9CvJDGP16IP","S":"BUY","o":"LIMIT","f":"GTC","q":"0.00200000","p":"20000.00000000","C":"","x":"TRADE","X":"FILLED","r":"NONE","i":28,"l":"0.00150000","z":"0.00200000","L":"19999.50000000","n":"0.02999925","N":"USDT","T":1507725177299,"t":13,"w":false,"m":false}"#;
+
+const ORDER_CANCELLED_MESSAGE: &str = r#"{"e":"executionReport","E":1507725178000,"s":"BTCUSDT","c":"web_c3c9c2b7a8b14d1c9e6b1c5bb0c4f2a1","S":"BUY","o":"LIMIT","f":"GTC","q":"0.00200000","p":"20000.00000000","C":"6gCrw2kRUAF9CvJDGP16IP","x":"CANCELED","X":"CANCELED","r":"NONE","i":28,"l":"0.00000000","z":"0.00000000","L":"0.00000000","n":"0","N":null,"T":1507725177999,"t":-1,"w":false,"m":false}"#;
+
+fn fixture() -> ConformanceFixture {
+    let error_response =
+        |content: &str, status| RestRequestOutcome::new(content.to_owned(), status);
+
+    ConformanceFixture {
+        currency_pair: CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        all_symbols_response: RestRequestOutcome::new(
+            ALL_SYMBOLS_RESPONSE.to_owned(),
+            StatusCode::OK,
+        ),
+        create_order_response: RestRequestOutcome::new(
+            CREATE_ORDER_RESPONSE.to_owned(),
+            StatusCode::OK,
+        ),
+        client_order_id: "6gCrw2kRUAF9CvJDGP16IP".into(),
+        exchange_order_id: "28".into(),
+        order_created_message: Some(ORDER_CREATED_MESSAGE.to_owned()),
+        order_filled_messages: vec![
+            (
+                ORDER_PARTIALLY_FILLED_MESSAGE.to_owned(),
+                ExpectedFill {
+                    price: dec!(20000),
+                    amount: dec!(0.0005),
+                    is_diff: true,
+                },
+            ),
+            (
+                ORDER_FILLED_MESSAGE.to_owned(),
+                ExpectedFill {
+                    price: dec!(19999.5),
+                    amount: dec!(0.0015),
+                    is_diff: true,
+                },
+            ),
+        ],
+        order_cancelled_message: ORDER_CANCELLED_MESSAGE.to_owned(),
+        error_responses: vec![
+            (
+                error_response(
+                    r#"{"code":-2010,"msg":"Account has insufficient balance for requested action."}"#,
+                    StatusCode::BAD_REQUEST,
+                ),
+                ExchangeErrorType::InsufficientFunds,
+            ),
+            (
+                error_response(
+                    r#"{"code":-2011,"msg":"Unknown order sent."}"#,
+                    StatusCode::BAD_REQUEST,
+                ),
+                ExchangeErrorType::OrderNotFound,
+            ),
+            (
+                error_response(
+                    r#"{"code":-1013,"msg":"Filter failure: LOT_SIZE"}"#,
+                    StatusCode::BAD_REQUEST,
+                ),
+                ExchangeErrorType::InvalidOrder,
+            ),
+            (
+                error_response(
+                    r#"{"code":-1003,"msg":"Too many requests; current limit is 1200 request weight per 1 MINUTE."}"#,
+                    StatusCode::TOO_MANY_REQUESTS,
+                ),
+                ExchangeErrorType::RateLimit,
+            ),
+        ],
+    }
+}
+
+#[test]
+fn binance_client_is_conformant() {
+    let exchange_account_id: ExchangeAccountId = "Binance_0".parse().expect("in test");
+    let settings =
+        ExchangeSettings::new_short(exchange_account_id, "".into(), "".into(), false, false);
+    let (tx, _) = broadcast::channel(10);
+    let binance = Binance::new(
+        exchange_account_id,
+        settings,
+        tx,
+        init_lifetime_manager(),
+        false,
+    );
+
+    ConformanceSuite::new(&binance, fixture())
+        .run()
+        .expect("in test");
+}
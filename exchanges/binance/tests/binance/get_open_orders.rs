@@ -93,16 +93,10 @@ async fn get_open_orders_for_each_currency_pair_separately() {
     let mut settings =
         ExchangeSettings::new_short(exchange_account_id, api_key, secret_key, false, false);
 
-    settings.currency_pairs = Some(vec![
-        CurrencyPairSetting::Ordinary {
-            base: "cnd".into(),
-            quote: "btc".into(),
-        },
-        CurrencyPairSetting::Ordinary {
-            base: "cnd".into(),
-            quote: "btc".into(),
-        },
-    ]);
+    settings.currency_pairs = Some(vec![CurrencyPairSetting::Ordinary {
+        base: "cnd".into(),
+        quote: "btc".into(),
+    }]);
 
     let binance_builder = match BinanceBuilder::try_new_with_settings(
         settings.clone(),
@@ -2,6 +2,7 @@ pub mod account_balance;
 pub mod binance_builder;
 pub mod cancel_order;
 pub mod common;
+pub mod conformance;
 pub mod create_order;
 pub mod get_open_orders;
 pub mod get_order_info;
@@ -3,6 +3,7 @@ use mmb_core::exchanges::common::*;
 use mmb_core::exchanges::events::AllowedEventSourceType;
 use mmb_core::exchanges::general::commission::Commission;
 use mmb_core::exchanges::general::features::*;
+use mmb_core::exchanges::general::order::wait_outcome::WaitOutcome;
 use mmb_core::settings::{CurrencyPairSetting, ExchangeSettings};
 use mmb_utils::cancellation_token::CancellationToken;
 use mmb_utils::infrastructure::init_infrastructure;
@@ -63,12 +64,12 @@ async fn cancellation_waited_successfully() {
         .await
         .expect("Create order failed with error");
 
-    // If here are no error - order was cancelled successfully
-    binance_builder
+    let outcome = binance_builder
         .exchange
         .wait_cancel_order(order_ref, None, true, CancellationToken::new())
         .await
         .expect("Error while trying wait_cancel_order");
+    assert!(outcome.is_cancelled(), "Unexpected outcome {:?}", outcome);
 }
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
@@ -112,15 +113,15 @@ async fn cancellation_waited_failed_fallback() {
         .await
         .expect("Create order failed with error");
 
-    let error = binance_builder
+    let outcome = binance_builder
         .exchange
         .wait_cancel_order(order_ref, None, true, CancellationToken::new())
         .await
-        .err()
-        .expect("Error was expected while trying wait_cancel_order()");
+        .expect("Error while trying wait_cancel_order");
 
-    assert_eq!(
-        "Order was expected to cancel explicitly via Rest or Web Socket but got timeout instead",
-        &error.to_string()[..86]
+    assert!(
+        matches!(outcome, WaitOutcome::Timeout),
+        "Unexpected outcome {:?}",
+        outcome
     );
 }
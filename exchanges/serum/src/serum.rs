@@ -35,7 +35,7 @@ use mmb_core::exchanges::common::{
     Amount, CurrencyCode, CurrencyId, CurrencyPair, ExchangeAccountId, Price, SpecificCurrencyPair,
 };
 use mmb_core::exchanges::events::{
-    AllowedEventSourceType, ExchangeBalance, ExchangeEvent, TradeId,
+    AllowedEventSourceType, ExchangeBalance, ExchangeEvent, TradeId, TradeStream,
 };
 use mmb_core::exchanges::general::exchange::BoxExchangeClient;
 use mmb_core::exchanges::general::features::{
@@ -108,7 +108,11 @@ pub struct Serum {
         Mutex<Box<dyn FnMut(ClientOrderId, ExchangeOrderId, EventSourceType) + Send + Sync>>,
     pub handle_order_filled_callback: Mutex<Box<dyn FnMut(FillEventData) + Send + Sync>>,
     pub handle_trade_callback: Mutex<
-        Box<dyn FnMut(CurrencyPair, TradeId, Price, Amount, OrderSide, DateTime) + Send + Sync>,
+        Box<
+            dyn FnMut(CurrencyPair, TradeStream, TradeId, Price, Amount, OrderSide, DateTime)
+                + Send
+                + Sync,
+        >,
     >,
 
     pub unified_to_specific: RwLock<HashMap<CurrencyPair, SpecificCurrencyPair>>,
@@ -137,7 +141,7 @@ impl Serum {
             order_created_callback: Mutex::new(Box::new(|_, _, _| {})),
             order_cancelled_callback: Mutex::new(Box::new(|_, _, _| {})),
             handle_order_filled_callback: Mutex::new(Box::new(|_| {})),
-            handle_trade_callback: Mutex::new(Box::new(|_, _, _, _, _, _| {})),
+            handle_trade_callback: Mutex::new(Box::new(|_, _, _, _, _, _, _| {})),
             unified_to_specific: Default::default(),
             supported_currencies: Default::default(),
             traded_specific_currencies: Default::default(),
@@ -18,7 +18,7 @@ use mmb_core::exchanges::common::{
     ActivePosition, Amount, ClosedPosition, CurrencyCode, CurrencyId, Price, RestRequestOutcome,
     SpecificCurrencyPair,
 };
-use mmb_core::exchanges::events::{ExchangeBalancesAndPositions, TradeId};
+use mmb_core::exchanges::events::{ExchangeBalancesAndPositions, TradeId, TradeStream};
 use mmb_core::exchanges::general::handlers::handle_order_filled::FillEventData;
 use mmb_core::exchanges::general::order::get_order_trades::OrderTrade;
 use mmb_core::exchanges::general::symbol::{Precision, Symbol};
@@ -72,7 +72,9 @@ impl Support for Serum {
     fn set_handle_trade_callback(
         &self,
         callback: Box<
-            dyn FnMut(CurrencyPair, TradeId, Price, Amount, OrderSide, DateTime) + Send + Sync,
+            dyn FnMut(CurrencyPair, TradeStream, TradeId, Price, Amount, OrderSide, DateTime)
+                + Send
+                + Sync,
         >,
     ) {
         *self.handle_trade_callback.lock() = callback;
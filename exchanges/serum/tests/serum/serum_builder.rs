@@ -83,7 +83,7 @@ impl SerumBuilder {
             commission,
         );
         exchange.clone().connect().await;
-        exchange.build_symbols(&settings.currency_pairs).await;
+        exchange.build_symbols(&settings.currency_pairs).await?;
 
         Ok(Self {
             exchange,
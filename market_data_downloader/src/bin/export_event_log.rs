@@ -0,0 +1,97 @@
+use std::env;
+
+use anyhow::{bail, Context, Result};
+use mmb_core::exchanges::event_log::{export_event_log, EventLogSource};
+use mmb_utils::infrastructure::init_infrastructure;
+use mmb_utils::DateTime;
+
+const USAGE: &str =
+    "Usage: export_event_log --source Binance_0=binance.jsonl --source Serum_0=serum.jsonl \
+--output events.json [--from 2022-03-01T00:00:00Z] [--to 2022-03-02T00:00:00Z]";
+
+struct Arguments {
+    sources: Vec<EventLogSource>,
+    output: String,
+    from: Option<DateTime>,
+    to: Option<DateTime>,
+}
+
+impl Arguments {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut sources = Vec::new();
+        let mut output = None;
+        let mut from = None;
+        let mut to = None;
+
+        let mut args = args;
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().with_context(|| format!("No value for {}", arg));
+            match arg.as_str() {
+                "--source" => sources.push(parse_source(&value()?)?),
+                "--output" => output = Some(value()?),
+                "--from" => from = Some(parse_time(&value()?)?),
+                "--to" => to = Some(parse_time(&value()?)?),
+                _ => bail!("Unknown argument {}", arg),
+            }
+        }
+
+        if sources.is_empty() {
+            bail!("At least one --source is required");
+        }
+
+        Ok(Self {
+            sources,
+            output: output.context("--output is required")?,
+            from,
+            to,
+        })
+    }
+}
+
+fn parse_source(value: &str) -> Result<EventLogSource> {
+    let (exchange_account_id, path) = value.split_once('=').with_context(|| {
+        format!(
+            "Source {} should be in format EXCHANGE_ACCOUNT_ID=PATH",
+            value
+        )
+    })?;
+
+    Ok(EventLogSource {
+        exchange_account_id: exchange_account_id.parse()?,
+        path: path.to_owned(),
+    })
+}
+
+fn parse_time(value: &str) -> Result<DateTime> {
+    Ok(chrono::DateTime::parse_from_rfc3339(value)
+        .with_context(|| format!("Unable to parse time {}", value))?
+        .into())
+}
+
+fn main() -> Result<()> {
+    init_infrastructure("export_event_log.log");
+
+    let arguments = match Arguments::parse(env::args().skip(1)) {
+        Ok(arguments) => arguments,
+        Err(error) => {
+            eprintln!("{:?}\n{}", error, USAGE);
+            return Err(error);
+        }
+    };
+
+    let exported_count = export_event_log(
+        &arguments.sources,
+        arguments.from,
+        arguments.to,
+        &arguments.output,
+    )?;
+
+    log::info!(
+        "Exporting of event log is finished: {} events of {} exchanges are written to {}",
+        exported_count,
+        arguments.sources.len(),
+        arguments.output
+    );
+
+    Ok(())
+}
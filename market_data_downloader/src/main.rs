@@ -0,0 +1,146 @@
+use std::env;
+
+use anyhow::{bail, Context, Result};
+use binance::binance::BinanceBuilder;
+use mmb_core::exchanges::common::{CurrencyPair, ExchangeAccountId};
+use mmb_core::exchanges::market_data_downloader::{MarketDataDownloader, MarketDataKind};
+use mmb_core::exchanges::traits::ExchangeClientBuilder;
+use mmb_core::exchanges::transport::TrafficRecorder;
+use mmb_core::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use mmb_core::settings::ExchangeSettings;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::init_infrastructure;
+use mmb_utils::DateTime;
+use tokio::sync::broadcast;
+
+const USAGE: &str = "Usage: market_data_downloader --exchange-account-id Binance_0 --currency-pair BTC/USDT \
+--from 2022-03-01T00:00:00Z --to 2022-03-02T00:00:00Z --output market_data.jsonl [--klines 1m] [--margin]";
+
+struct Arguments {
+    exchange_account_id: ExchangeAccountId,
+    currency_pair: CurrencyPair,
+    from: DateTime,
+    to: DateTime,
+    output: String,
+    kind: MarketDataKind,
+    is_margin_trading: bool,
+}
+
+impl Arguments {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut exchange_account_id = None;
+        let mut currency_pair = None;
+        let mut from = None;
+        let mut to = None;
+        let mut output = None;
+        let mut kind = MarketDataKind::Trades;
+        let mut is_margin_trading = false;
+
+        let mut args = args;
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().with_context(|| format!("No value for {}", arg));
+            match arg.as_str() {
+                "--exchange-account-id" => exchange_account_id = Some(value()?.parse()?),
+                "--currency-pair" => currency_pair = Some(parse_currency_pair(&value()?)?),
+                "--from" => from = Some(parse_time(&value()?)?),
+                "--to" => to = Some(parse_time(&value()?)?),
+                "--output" => output = Some(value()?),
+                "--klines" => kind = MarketDataKind::Klines { interval: value()? },
+                "--margin" => is_margin_trading = true,
+                _ => bail!("Unknown argument {}", arg),
+            }
+        }
+
+        Ok(Self {
+            exchange_account_id: exchange_account_id
+                .context("--exchange-account-id is required")?,
+            currency_pair: currency_pair.context("--currency-pair is required")?,
+            from: from.context("--from is required")?,
+            to: to.context("--to is required")?,
+            output: output.context("--output is required")?,
+            kind,
+            is_margin_trading,
+        })
+    }
+}
+
+fn parse_currency_pair(value: &str) -> Result<CurrencyPair> {
+    let (base, quote) = value
+        .split_once('/')
+        .with_context(|| format!("Currency pair {} should be in format BASE/QUOTE", value))?;
+
+    Ok(CurrencyPair::from_codes(base.into(), quote.into()))
+}
+
+fn parse_time(value: &str) -> Result<DateTime> {
+    Ok(chrono::DateTime::parse_from_rfc3339(value)
+        .with_context(|| format!("Unable to parse time {}", value))?
+        .into())
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    init_infrastructure("market_data_downloader.log");
+
+    let arguments = match Arguments::parse(env::args().skip(1)) {
+        Ok(arguments) => arguments,
+        Err(error) => {
+            eprintln!("{:?}\n{}", error, USAGE);
+            return Err(error);
+        }
+    };
+
+    if arguments.exchange_account_id.exchange_id.as_str() != "Binance" {
+        bail!(
+            "Downloading of market data isn't supported for {}",
+            arguments.exchange_account_id
+        );
+    }
+
+    // Only public endpoints are used, so credentials aren't needed
+    let settings = ExchangeSettings::new_short(
+        arguments.exchange_account_id,
+        String::new(),
+        String::new(),
+        arguments.is_margin_trading,
+        false,
+    );
+    let (events_sender, _) = broadcast::channel(10);
+    let lifetime_manager = AppLifetimeManager::new(CancellationToken::default());
+
+    let builder = BinanceBuilder;
+    let timeout_arguments = builder.get_timeout_arguments();
+    let request_period = timeout_arguments
+        .period
+        .to_std()
+        .context("Invalid period of requests timeout")?
+        / timeout_arguments.requests_per_period as u32;
+
+    let exchange_client = builder
+        .create_exchange_client(settings, events_sender, lifetime_manager)
+        .client;
+    let recorder = TrafficRecorder::get_or_create(&arguments.output)?;
+
+    let downloader = MarketDataDownloader::new(exchange_client, recorder.clone(), request_period);
+    downloader.init().await?;
+
+    let recorded_count = downloader
+        .download(
+            arguments.currency_pair,
+            &arguments.kind,
+            arguments.from,
+            arguments.to,
+        )
+        .await?;
+    recorder.flush();
+
+    log::info!(
+        "Downloading of {:?} for {} is finished: {} messages are recorded to {}",
+        arguments.kind,
+        arguments.currency_pair,
+        recorded_count,
+        arguments.output
+    );
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+#![deny(
+    non_shorthand_field_patterns,
+    no_mangle_generic_items,
+    overflowing_literals,
+    path_statements,
+    unused_allocation,
+    unused_comparisons,
+    unused_parens,
+    while_true,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_must_use
+)]
+
+//! Facade of trading engine for strategy crates.
+//! Exchange clients are enabled by cargo features: `binance` (default) and `serum`
+
+pub use mmb_core::prelude;
+
+use mmb_core::lifecycle::launcher::EngineBuildConfig;
+
+#[cfg(feature = "binance")]
+pub mod binance {
+    pub use ::binance::binance::{Binance, BinanceBuilder};
+}
+
+#[cfg(feature = "serum")]
+pub mod serum {
+    pub use ::serum::serum::{Serum, SerumBuilder};
+}
+
+/// Build config with clients of all exchanges enabled by cargo features
+pub fn enabled_exchanges_build_config() -> EngineBuildConfig {
+    #[allow(unused_mut)]
+    let mut build_config = EngineBuildConfig {
+        supported_exchange_clients: Default::default(),
+        strategy_settings_schema: None,
+    };
+
+    #[cfg(feature = "binance")]
+    build_config.supported_exchange_clients.insert(
+        "Binance".into(),
+        std::sync::Arc::new(self::binance::BinanceBuilder),
+    );
+
+    #[cfg(feature = "serum")]
+    build_config.supported_exchange_clients.insert(
+        "Serum".into(),
+        std::sync::Arc::new(self::serum::SerumBuilder),
+    );
+
+    build_config
+}
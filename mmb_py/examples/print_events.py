@@ -0,0 +1,28 @@
+"""Prints events of engine and places order after first order book snapshot.
+
+Build module with `maturin develop` from `mmb_py` directory before running.
+"""
+import mmb_py
+
+engine = mmb_py.Engine.launch("config.toml", "credentials.toml")
+order_placed = False
+
+
+def on_event(event):
+    global order_placed
+    print(event)
+
+    if event["type"] == "order_book" and event["is_snapshot"] and not order_placed and event["bids"]:
+        best_bid = event["bids"][-1][0]
+        client_order_id = engine.create_order(
+            event["exchange_account_id"], "eth", "btc", "buy", best_bid, "0.1"
+        )
+        print("Created order", client_order_id)
+        order_placed = True
+
+
+try:
+    engine.run_strategy(on_event)
+finally:
+    engine.stop()
+    engine.wait()
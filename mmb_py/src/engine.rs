@@ -0,0 +1,250 @@
+use std::fmt::Debug;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use mmb_core::config::try_load_settings;
+use mmb_core::exchanges::common::{CurrencyPair, ExchangeAccountId};
+use mmb_core::exchanges::events::ExchangeEvent;
+use mmb_core::exchanges::general::exchange::Exchange;
+use mmb_core::lifecycle::app_lifetime_manager::ActionAfterGracefulShutdown;
+use mmb_core::lifecycle::launcher::{launch_trading_engine, InitSettings};
+use mmb_core::lifecycle::trading_engine::EngineContext;
+use mmb_core::orders::order::{ClientOrderId, OrderSide};
+use mmb_core::orders::order_builder::OrderBuilder;
+use parking_lot::Mutex;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use rust_decimal::Decimal;
+use tokio::runtime::Runtime;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinHandle;
+
+use crate::events::event_to_py;
+use crate::strategy::{PassiveStrategy, PyStrategySettings, PYTHON_STRATEGY_NAME};
+
+/// Period of checking of Python signals (e.g. KeyboardInterrupt) while waiting for events
+const SIGNALS_CHECK_PERIOD: Duration = Duration::from_millis(100);
+
+fn to_py_err(error: impl Debug) -> PyErr {
+    PyRuntimeError::new_err(format!("{:?}", error))
+}
+
+/// Decimal values are accepted as any Python object convertible to string (`str`, `int`, `float`, `Decimal`)
+fn extract_decimal(value: &PyAny) -> PyResult<Decimal> {
+    let text = value.str()?.to_str()?;
+    Decimal::from_str(text)
+        .map_err(|error| PyValueError::new_err(format!("Invalid decimal {}: {}", text, error)))
+}
+
+/// Trading engine running in background tokio runtime
+#[pyclass(name = "Engine")]
+pub struct PyEngine {
+    runtime: Runtime,
+    context: Arc<EngineContext>,
+    events: Mutex<broadcast::Receiver<ExchangeEvent>>,
+    finished: Mutex<Option<JoinHandle<ActionAfterGracefulShutdown>>>,
+}
+
+impl PyEngine {
+    fn get_exchange(&self, exchange_account_id: &str) -> PyResult<Arc<Exchange>> {
+        let exchange_account_id =
+            ExchangeAccountId::from_str(exchange_account_id).map_err(to_py_err)?;
+
+        self.context
+            .exchanges
+            .get(&exchange_account_id)
+            .map(|exchange| exchange.value().clone())
+            .ok_or_else(|| {
+                PyValueError::new_err(format!("There is no exchange {}", exchange_account_id))
+            })
+    }
+
+    /// Waits for next event with released GIL. Returns `None` on timeout or after engine shutdown
+    fn recv_event(&self, py: Python, timeout: Duration) -> Option<ExchangeEvent> {
+        py.allow_threads(|| {
+            self.runtime.block_on(async {
+                let mut events = self.events.lock();
+                loop {
+                    match tokio::time::timeout(timeout, events.recv()).await {
+                        Ok(Ok(event)) => return Some(event),
+                        Ok(Err(RecvError::Lagged(skipped))) => {
+                            log::warn!("Python strategy skipped {} events", skipped)
+                        }
+                        Ok(Err(RecvError::Closed)) | Err(_) => return None,
+                    }
+                }
+            })
+        })
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.context
+            .lifetime_manager
+            .stop_token()
+            .is_cancellation_requested()
+    }
+}
+
+#[pymethods]
+impl PyEngine {
+    /// Launches engine with settings from files. Strategy section of config should contain
+    /// `exchange_account_id`, ordinary `currency_pair` and `max_amount`.
+    /// Raises `ValueError` if settings can't be loaded
+    #[staticmethod]
+    fn launch(py: Python, config_path: String, credentials_path: String) -> PyResult<Self> {
+        let settings = try_load_settings::<PyStrategySettings>(&config_path, &credentials_path)
+            .map_err(|error| PyValueError::new_err(format!("Invalid settings: {:?}", error)))?;
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .map_err(to_py_err)?;
+
+        let engine = py.allow_threads(|| {
+            runtime.block_on(async {
+                let build_config = mmb::enabled_exchanges_build_config();
+                let init_settings = InitSettings::Directly(settings);
+
+                launch_trading_engine(&build_config, init_settings, |_, _| {
+                    Box::new(PassiveStrategy)
+                })
+                .await
+            })
+        });
+
+        let engine = engine
+            .map_err(to_py_err)?
+            .ok_or_else(|| PyRuntimeError::new_err("TradingEngine was stopped during launch"))?;
+
+        let context = engine.context();
+        let events = Mutex::new(context.get_events_channel());
+        let finished = Mutex::new(Some(runtime.spawn(engine.run())));
+
+        Ok(Self {
+            runtime,
+            context,
+            events,
+            finished,
+        })
+    }
+
+    /// Returns next event as dict or `None` if there were no events during timeout
+    #[args(timeout_secs = "1.0")]
+    fn next_event(&self, py: Python, timeout_secs: f64) -> PyResult<Option<PyObject>> {
+        self.recv_event(py, Duration::from_secs_f64(timeout_secs))
+            .map(|event| event_to_py(py, &event))
+            .transpose()
+    }
+
+    /// Calls `on_event(event)` for every event until engine is stopped.
+    /// Exception raised by callback stops the loop and is propagated to caller
+    fn run_strategy(&self, py: Python, on_event: PyObject) -> PyResult<()> {
+        while !self.is_stopped() {
+            py.check_signals()?;
+
+            if let Some(event) = self.recv_event(py, SIGNALS_CHECK_PERIOD) {
+                on_event.call1(py, (event_to_py(py, &event)?,))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates limit order and waits for its creation on exchange. Returns client order id
+    #[allow(clippy::too_many_arguments)]
+    fn create_order(
+        &self,
+        py: Python,
+        exchange_account_id: &str,
+        base: &str,
+        quote: &str,
+        side: &str,
+        price: &PyAny,
+        amount: &PyAny,
+    ) -> PyResult<String> {
+        let exchange = self.get_exchange(exchange_account_id)?;
+        let side = match side.to_lowercase().as_str() {
+            "buy" => OrderSide::Buy,
+            "sell" => OrderSide::Sell,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown order side {}",
+                    side
+                )))
+            }
+        };
+
+        let builder = OrderBuilder::new(
+            exchange.exchange_account_id,
+            CurrencyPair::from_codes(base.into(), quote.into()),
+        )
+        .side(side)
+        .limit(extract_decimal(price)?)
+        .amount(extract_decimal(amount)?)
+        .strategy_name(PYTHON_STRATEGY_NAME);
+
+        let cancellation_token = self.context.lifetime_manager.stop_token();
+        let order = py
+            .allow_threads(|| {
+                self.runtime
+                    .block_on(builder.create(&exchange, cancellation_token))
+            })
+            .map_err(to_py_err)?;
+
+        Ok(order.client_order_id().as_str().to_owned())
+    }
+
+    /// Cancels order and waits for its cancellation
+    fn cancel_order(
+        &self,
+        py: Python,
+        exchange_account_id: &str,
+        client_order_id: &str,
+    ) -> PyResult<()> {
+        let exchange = self.get_exchange(exchange_account_id)?;
+        let order = exchange
+            .orders
+            .cache_by_client_id
+            .get(&ClientOrderId::from(client_order_id))
+            .map(|order| order.value().clone())
+            .ok_or_else(|| {
+                PyValueError::new_err(format!("There is no order {}", client_order_id))
+            })?;
+
+        let cancellation_token = self.context.lifetime_manager.stop_token();
+        py.allow_threads(|| {
+            self.runtime.block_on(exchange.wait_cancel_order_confirmed(
+                order,
+                None,
+                true,
+                cancellation_token,
+            ))
+        })
+        .map(|_| ())
+        .map_err(to_py_err)
+    }
+
+    /// Starts graceful shutdown of engine
+    #[args(reason = "\"Stopped from Python\"")]
+    fn stop(&self, reason: &str) {
+        let _ = self
+            .context
+            .lifetime_manager
+            .spawn_graceful_shutdown(reason.to_owned());
+    }
+
+    /// Waits for finish of graceful shutdown. Returns `True` if restart of engine was requested
+    fn wait(&self, py: Python) -> PyResult<bool> {
+        let finished = match self.finished.lock().take() {
+            Some(finished) => finished,
+            None => return Err(PyRuntimeError::new_err("Engine is already finished")),
+        };
+
+        let action = py
+            .allow_threads(|| self.runtime.block_on(finished))
+            .map_err(to_py_err)?;
+
+        Ok(matches!(action, ActionAfterGracefulShutdown::Restart))
+    }
+}
@@ -0,0 +1,151 @@
+use mmb_core::exchanges::common::SortedOrderData;
+use mmb_core::exchanges::events::ExchangeEvent;
+use mmb_core::order_book::event::EventType;
+use mmb_core::orders::event::OrderEventType;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Converts event of engine to Python dict with event name in field `type`.
+/// Decimal values are passed as strings to keep precision, they can be parsed by `decimal.Decimal`
+pub fn event_to_py(py: Python, event: &ExchangeEvent) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+
+    match event {
+        ExchangeEvent::OrderBookEvent(event) => {
+            dict.set_item("type", "order_book")?;
+            dict.set_item("exchange_account_id", event.exchange_account_id.to_string())?;
+            dict.set_item("currency_pair", event.currency_pair.as_str())?;
+            dict.set_item(
+                "is_snapshot",
+                matches!(event.event_type, EventType::Snapshot),
+            )?;
+            dict.set_item("asks", price_levels(&event.data.asks))?;
+            dict.set_item("bids", price_levels(&event.data.bids))?;
+        }
+        ExchangeEvent::OrderEvent(event) => {
+            let order = &event.order;
+            let event_type = match event.event_type {
+                OrderEventType::CreateOrderSucceeded => "create_order_succeeded",
+                OrderEventType::CreateOrderFailed => "create_order_failed",
+                OrderEventType::OrderFilled { .. } => "order_filled",
+                OrderEventType::OrderCompleted { .. } => "order_completed",
+                OrderEventType::CancelOrderSucceeded => "cancel_order_succeeded",
+                OrderEventType::CancelOrderFailed => "cancel_order_failed",
+                OrderEventType::OrderExpired => "order_expired",
+            };
+
+            dict.set_item("type", "order")?;
+            dict.set_item("event_type", event_type)?;
+            dict.set_item(
+                "exchange_account_id",
+                order.exchange_account_id().to_string(),
+            )?;
+            dict.set_item("currency_pair", order.currency_pair().as_str())?;
+            dict.set_item("client_order_id", order.client_order_id().as_str())?;
+            dict.set_item(
+                "exchange_order_id",
+                order.exchange_order_id().map(|id| id.as_str().to_owned()),
+            )?;
+            dict.set_item("side", format!("{:?}", order.side()))?;
+            dict.set_item("status", format!("{:?}", order.status()))?;
+            dict.set_item("price", order.price().to_string())?;
+            dict.set_item("amount", order.amount().to_string())?;
+            dict.set_item("filled_amount", order.filled_amount().to_string())?;
+        }
+        ExchangeEvent::BalanceUpdate(event) => {
+            let balances = PyDict::new(py);
+            for balance in &event.balances_and_positions.balances {
+                balances.set_item(balance.currency_code.as_str(), balance.balance.to_string())?;
+            }
+
+            dict.set_item("type", "balance_update")?;
+            dict.set_item("exchange_account_id", event.exchange_account_id.to_string())?;
+            dict.set_item("balances", balances)?;
+        }
+        ExchangeEvent::Trades(event) => {
+            let trades = event
+                .trades
+                .iter()
+                .map(|trade| {
+                    (
+                        trade.price.to_string(),
+                        trade.quantity.to_string(),
+                        format!("{:?}", trade.side),
+                        trade.transaction_time.to_rfc3339(),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            dict.set_item("type", "trades")?;
+            dict.set_item("exchange_account_id", event.exchange_account_id.to_string())?;
+            dict.set_item("currency_pair", event.currency_pair.as_str())?;
+            dict.set_item("trades", trades)?;
+        }
+        ExchangeEvent::MarketTradingStatus(event) => {
+            dict.set_item("type", "market_trading_status")?;
+            dict.set_item("exchange_account_id", event.exchange_account_id.to_string())?;
+            dict.set_item("currency_pair", event.currency_pair.as_str())?;
+            dict.set_item("is_halted", event.is_halted)?;
+        }
+        ExchangeEvent::LiquidationPrice(_) => dict.set_item("type", "liquidation_price")?,
+        ExchangeEvent::SymbolAdded(_) => dict.set_item("type", "symbol_added")?,
+        ExchangeEvent::SymbolUpdated(_) => dict.set_item("type", "symbol_updated")?,
+        ExchangeEvent::LiquidationOrder(_) => dict.set_item("type", "liquidation_order")?,
+        ExchangeEvent::MarginCall(_) => dict.set_item("type", "margin_call")?,
+        ExchangeEvent::MarginRatio(_) => dict.set_item("type", "margin_ratio")?,
+        ExchangeEvent::DustConversion(_) => dict.set_item("type", "dust_conversion")?,
+        ExchangeEvent::PartialFillTimeout(_) => dict.set_item("type", "partial_fill_timeout")?,
+        ExchangeEvent::FillAnomaly(event) => {
+            dict.set_item("type", "fill_anomaly")?;
+            dict.set_item("exchange_account_id", event.exchange_account_id.to_string())?;
+            dict.set_item("kind", format!("{:?}", event.kind))?;
+            dict.set_item("price", event.price.to_string())?;
+            dict.set_item("amount", event.amount.to_string())?;
+        }
+        ExchangeEvent::CommissionCorrection(event) => {
+            dict.set_item("type", "commission_correction")?;
+            dict.set_item("exchange_account_id", event.exchange_account_id.to_string())?;
+            dict.set_item("client_order_id", event.client_order_id.to_string())?;
+            dict.set_item(
+                "converted_commission_currency_code",
+                event.converted_commission_currency_code.to_string(),
+            )?;
+            dict.set_item(
+                "converted_commission_amount",
+                event.converted_commission_amount.to_string(),
+            )?;
+        }
+        ExchangeEvent::TradeGap(event) => {
+            dict.set_item("type", "trade_gap")?;
+            dict.set_item("exchange_account_id", event.exchange_account_id.to_string())?;
+            dict.set_item("currency_pair", event.currency_pair.to_string())?;
+            dict.set_item("first_missed_id", event.first_missed_id)?;
+            dict.set_item("last_missed_id", event.last_missed_id)?;
+            dict.set_item("backfilled_count", event.backfilled_count)?;
+        }
+        ExchangeEvent::EventLoopStall(event) => {
+            dict.set_item("type", "event_loop_stall")?;
+            dict.set_item("loop_name", event.loop_name.as_str())?;
+            dict.set_item("handled_event_kind", event.handled_event_kind)?;
+            dict.set_item("stalled_for_ms", event.stalled_for_ms)?;
+            dict.set_item("pending_events_count", event.pending_events_count)?;
+        }
+        ExchangeEvent::BalanceDelta(event) => {
+            dict.set_item("type", "balance_delta")?;
+            dict.set_item("exchange_account_id", event.exchange_account_id.to_string())?;
+            dict.set_item("currency_code", event.currency_code.to_string())?;
+            dict.set_item("delta", event.delta.to_string())?;
+            dict.set_item("reason", format!("{:?}", event.reason))?;
+            dict.set_item("client_order_id", event.client_order_id.to_string())?;
+        }
+    }
+
+    Ok(dict.into())
+}
+
+fn price_levels(levels: &SortedOrderData) -> Vec<(String, String)> {
+    levels
+        .iter()
+        .map(|(price, amount)| (price.to_string(), amount.to_string()))
+        .collect()
+}
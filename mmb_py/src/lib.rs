@@ -0,0 +1,40 @@
+#![deny(
+    non_shorthand_field_patterns,
+    no_mangle_generic_items,
+    overflowing_literals,
+    path_statements,
+    unused_allocation,
+    unused_comparisons,
+    unused_parens,
+    while_true,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_must_use
+)]
+
+//! Python bindings of trading engine for prototyping of strategies.
+//! Engine is running on own tokio runtime, all blocking calls release GIL while waiting:
+//!
+//! ```python
+//! import mmb_py
+//!
+//! engine = mmb_py.Engine.launch("config.toml", "credentials.toml")
+//! engine.create_order("Binance_0", "eth", "btc", "buy", "0.05", "0.1")
+//! engine.run_strategy(lambda event: print(event))
+//! ```
+
+use pyo3::prelude::*;
+
+pub mod engine;
+pub mod events;
+pub mod strategy;
+
+use crate::engine::PyEngine;
+
+#[pymodule]
+fn mmb_py(_py: Python, module: &PyModule) -> PyResult<()> {
+    module.add_class::<PyEngine>()?;
+    Ok(())
+}
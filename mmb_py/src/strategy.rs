@@ -0,0 +1,141 @@
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use anyhow::Result;
+use mmb_core::disposition_execution::{PriceSlot, TradingContext};
+use mmb_core::exchanges::common::{Amount, CurrencyCode, CurrencyPair, ExchangeAccountId};
+use mmb_core::explanation::Explanation;
+use mmb_core::order_book::local_snapshot_service::LocalSnapshotsService;
+use mmb_core::orders::order::OrderSnapshot;
+use mmb_core::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use mmb_core::settings::{BaseStrategySettings, CurrencyPairSetting};
+use mmb_core::strategies::disposition_strategy::DispositionStrategy;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::DateTime;
+use serde::{Deserialize, Serialize};
+
+pub const PYTHON_STRATEGY_NAME: &str = "Python";
+
+/// Strategy section of config for engine launched from Python
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct PyStrategySettings {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: PyCurrencyPair,
+    pub max_amount: Amount,
+}
+
+/// Python strategy trades on a single market, so only ordinary currency pair can be specified.
+/// Specific pair is rejected on loading of settings
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "CurrencyPairSetting", into = "CurrencyPairSetting")]
+pub struct PyCurrencyPair {
+    pub base: CurrencyCode,
+    pub quote: CurrencyCode,
+}
+
+impl TryFrom<CurrencyPairSetting> for PyCurrencyPair {
+    type Error = String;
+
+    fn try_from(setting: CurrencyPairSetting) -> Result<Self, Self::Error> {
+        match setting {
+            CurrencyPairSetting::Ordinary { base, quote } => Ok(Self { base, quote }),
+            CurrencyPairSetting::Specific(_) => Err(format!(
+                "Ordinary currency pair should be specified for Python strategy instead of {:?}",
+                setting
+            )),
+        }
+    }
+}
+
+impl From<PyCurrencyPair> for CurrencyPairSetting {
+    fn from(currency_pair: PyCurrencyPair) -> Self {
+        CurrencyPairSetting::Ordinary {
+            base: currency_pair.base,
+            quote: currency_pair.quote,
+        }
+    }
+}
+
+impl BaseStrategySettings for PyStrategySettings {
+    fn exchange_account_id(&self) -> ExchangeAccountId {
+        self.exchange_account_id
+    }
+
+    fn currency_pair(&self) -> CurrencyPair {
+        CurrencyPair::from_codes(self.currency_pair.base, self.currency_pair.quote)
+    }
+
+    fn max_amount(&self) -> Amount {
+        self.max_amount
+    }
+}
+
+/// Disposition strategy which never places orders by itself.
+/// Orders are placed from Python code in reaction to events of engine
+pub struct PassiveStrategy;
+
+impl DispositionStrategy for PassiveStrategy {
+    fn calculate_trading_context(
+        &mut self,
+        _now: DateTime,
+        _local_snapshots_service: &LocalSnapshotsService,
+        _explanation: &mut Explanation,
+    ) -> Option<TradingContext> {
+        None
+    }
+
+    fn handle_order_fill(
+        &self,
+        _cloned_order: &Arc<OrderSnapshot>,
+        _price_slot: &PriceSlot,
+        _target_eai: ExchangeAccountId,
+        _cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn configuration_descriptor(&self) -> ConfigurationDescriptor {
+        ConfigurationDescriptor::new(PYTHON_STRATEGY_NAME.into(), "python".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ordinary_currency_pair_is_accepted() {
+        let setting = CurrencyPairSetting::Ordinary {
+            base: "eth".into(),
+            quote: "btc".into(),
+        };
+
+        let currency_pair = PyCurrencyPair::try_from(setting.clone()).expect("in test");
+
+        assert_eq!(CurrencyPairSetting::from(currency_pair), setting);
+    }
+
+    #[test]
+    fn specific_currency_pair_is_rejected() {
+        let setting = CurrencyPairSetting::Specific("ETHBTC".into());
+
+        assert!(PyCurrencyPair::try_from(setting).is_err());
+    }
+
+    #[test]
+    fn currency_pair_of_settings() {
+        let settings = PyStrategySettings {
+            exchange_account_id: "Binance_0".parse().expect("in test"),
+            currency_pair: PyCurrencyPair {
+                base: "eth".into(),
+                quote: "btc".into(),
+            },
+            max_amount: Amount::ONE,
+        };
+
+        assert_eq!(
+            settings.currency_pair(),
+            CurrencyPair::from_codes("eth".into(), "btc".into())
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use jsonrpc_core::{Error, Result};
+use jsonrpc_core::{BoxFuture, Error, Result};
 use jsonrpc_derive::rpc;
 
 #[cfg(unix)]
@@ -6,28 +6,175 @@ pub static IPC_ADDRESS: &str = "/tmp/mmb_core.ipc";
 #[cfg(windows)]
 pub static IPC_ADDRESS: &str = r#"\\.\pipe\mmb_core"#;
 
+/// Actions which change state of engine take `operator` (identity of user of control panel) for audit log
 #[rpc]
 pub trait MmbRpc {
     #[rpc(name = "health")]
     fn health(&self) -> Result<String>;
 
+    /// Status of engine in JSON: connectivity, latencies, block reasons, disabled and halted markets
+    /// and open orders of exchange accounts, states of strategies, trade flow and volatility of their
+    /// markets, times of the latest events and fill anomalies
+    #[rpc(name = "status")]
+    fn status(&self) -> BoxFuture<Result<String>>;
+
     #[rpc(name = "stop")]
-    fn stop(&self) -> Result<String>;
+    fn stop(&self, operator: Option<String>) -> Result<String>;
 
     #[rpc(name = "get_config")]
     fn get_config(&self) -> Result<String>;
 
+    /// Settings are validated before saving. Errors contain all found problems of settings
     #[rpc(name = "set_config")]
-    fn set_config(&self, settings: String) -> Result<String>;
+    fn set_config(&self, settings: String, operator: Option<String>) -> Result<String>;
+
+    /// JSON schema of strategy settings for rendering of settings form
+    #[rpc(name = "get_config_schema")]
+    fn get_config_schema(&self) -> Result<String>;
 
     #[rpc(name = "stats")]
     fn stats(&self) -> Result<String>;
+
+    /// Statistics filtered by `query`, which is JSON with optional fields `exchange_id`,
+    /// `exchange_account_id`, `currency_pair`, `from` and `to`
+    #[rpc(name = "query_stats")]
+    fn query_stats(&self, query: String) -> Result<String>;
+
+    /// Balances of exchange accounts in JSON with amounts reserved by strategies and locked by orders
+    #[rpc(name = "balances")]
+    fn balances(&self) -> Result<String>;
+
+    /// Requests limits of exchange accounts in JSON: used part of limit, queue of requests which
+    /// wait for free slot and reservation metrics by request type (pending requests, average wait)
+    #[rpc(name = "rate_limits")]
+    fn rate_limits(&self) -> Result<String>;
+
+    /// Report of strategy which runs in shadow mode in JSON: scores of shadow and live orders
+    /// and the latest shadow orders
+    #[rpc(name = "shadow_report")]
+    fn shadow_report(&self) -> Result<String>;
+
+    /// Returns token for confirmation of withdrawal. Withdrawal is executed only after confirmation
+    #[rpc(name = "withdraw")]
+    fn withdraw(&self, withdrawal_request: String, operator: Option<String>) -> Result<String>;
+
+    #[rpc(name = "confirm_withdraw")]
+    fn confirm_withdraw(&self, token: String, operator: Option<String>) -> Result<String>;
+
+    /// Automatic refills of treasury in JSON with tokens for `confirm_withdraw`
+    #[rpc(name = "pending_refills")]
+    fn pending_refills(&self) -> Result<String>;
+
+    /// Rereads script of pre-submission order filter
+    #[rpc(name = "reload_order_filter")]
+    fn reload_order_filter(&self, operator: Option<String>) -> Result<String>;
+
+    /// Blocks all exchanges, cancels all open orders and optionally closes all positions at market price.
+    /// Returns summary of what was done
+    #[rpc(name = "panic_button")]
+    fn panic_button(
+        &self,
+        close_positions: bool,
+        operator: Option<String>,
+    ) -> BoxFuture<Result<String>>;
+
+    /// Stops one exchange account: cancels its open orders, disconnects websockets and releases reservations.
+    /// Other exchanges keep trading
+    #[rpc(name = "stop_exchange")]
+    fn stop_exchange(
+        &self,
+        exchange_account_id: String,
+        operator: Option<String>,
+    ) -> BoxFuture<Result<String>>;
+
+    /// Adds exchange account to running engine. `exchange_settings` are TOML of exchange settings
+    /// with credentials in the same format as in config
+    #[rpc(name = "add_exchange")]
+    fn add_exchange(
+        &self,
+        exchange_settings: String,
+        operator: Option<String>,
+    ) -> BoxFuture<Result<String>>;
+
+    /// Disables trading on market of exchange account and cancels open orders on it. Market stays
+    /// disabled after restart until it's enabled. `currency_pair` is in format `base/quote`
+    #[rpc(name = "disable_market")]
+    fn disable_market(
+        &self,
+        exchange_account_id: String,
+        currency_pair: String,
+        operator: Option<String>,
+    ) -> BoxFuture<Result<String>>;
+
+    /// Enables trading on market which is disabled by `disable_market`
+    #[rpc(name = "enable_market")]
+    fn enable_market(
+        &self,
+        exchange_account_id: String,
+        currency_pair: String,
+        operator: Option<String>,
+    ) -> BoxFuture<Result<String>>;
+
+    /// The latest `count` records of audit log of operator actions in JSON
+    #[rpc(name = "audit_log")]
+    fn audit_log(&self, count: usize) -> Result<String>;
+
+    /// Page of open and finished orders in JSON. `query` is url query with optional `status`
+    /// (open, filled, canceled or failed), `exchange_account_id`, `pair`, time range `from`/`to`,
+    /// `after_sequence` of order history for live refresh, `offset` and `limit`
+    #[rpc(name = "orders")]
+    fn orders(&self, query: String) -> BoxFuture<Result<String>>;
+
+    /// Timeline of order in JSON: creation, status changes, fills and recorded REST requests and
+    /// websocket messages which mention order, in chronological order
+    #[rpc(name = "order_timeline")]
+    fn order_timeline(&self, client_order_id: String) -> BoxFuture<Result<String>>;
+
+    /// Versioned archive in JSON with open orders, positions, reservations and state of strategies
+    /// for moving of engine to another host. If `hand_over` is set, engine stops trading and keeps
+    /// open orders on shutdown, so they are managed only by engine which imports archive
+    #[rpc(name = "export_state")]
+    fn export_state(&self, hand_over: bool, operator: Option<String>) -> BoxFuture<Result<String>>;
+
+    /// Adopts open orders and restores positions, reservations and state of strategies from
+    /// archive of `export_state`. Returns report in JSON with items which weren't imported
+    #[rpc(name = "import_state")]
+    fn import_state(&self, archive: String, operator: Option<String>) -> BoxFuture<Result<String>>;
+
+    /// Resumes trading of engine which handed over its state by `export_state` if another engine
+    /// failed to take it over
+    #[rpc(name = "cancel_handover")]
+    fn cancel_handover(&self, operator: Option<String>) -> Result<String>;
 }
 
 pub enum ErrorCode {
     StopperIsNone = 1,
     UnableToSendSignal = 2,
     FailedToSaveNewConfig = 3,
+    FailedToRequestWithdrawal = 4,
+    FailedToConfirmWithdrawal = 5,
+    FailedToReloadOrderFilter = 6,
+    FailedToPressPanicButton = 7,
+    InvalidConfig = 8,
+    ConfigSchemaIsNotSet = 9,
+    FailedToReadAuditLog = 10,
+    FailedToStopExchange = 11,
+    FailedToAddExchange = 12,
+    FailedToGetBalances = 13,
+    FailedToGetStats = 14,
+    InvalidStatsQuery = 15,
+    FailedToGetOrders = 16,
+    InvalidOrdersQuery = 17,
+    FailedToExportState = 18,
+    FailedToImportState = 19,
+    FailedToDisableMarket = 20,
+    FailedToEnableMarket = 21,
+    FailedToGetStatus = 22,
+    FailedToGetOrderTimeline = 23,
+    FailedToGetRateLimits = 24,
+    FailedToGetShadowReport = 25,
+    FailedToCancelHandover = 26,
+    FailedToGetPendingRefills = 27,
 }
 
 pub fn server_side_error(code: ErrorCode) -> Error {
@@ -35,7 +182,38 @@ pub fn server_side_error(code: ErrorCode) -> Error {
         ErrorCode::StopperIsNone => "Server stopper is none",
         ErrorCode::UnableToSendSignal => "Unable to send signal",
         ErrorCode::FailedToSaveNewConfig => "Failed to save new config",
+        ErrorCode::FailedToRequestWithdrawal => "Failed to request withdrawal",
+        ErrorCode::FailedToConfirmWithdrawal => "Failed to confirm withdrawal",
+        ErrorCode::FailedToReloadOrderFilter => "Failed to reload order filter",
+        ErrorCode::FailedToPressPanicButton => "Failed to press panic button",
+        ErrorCode::InvalidConfig => "Invalid config",
+        ErrorCode::ConfigSchemaIsNotSet => "Config schema isn't set",
+        ErrorCode::FailedToReadAuditLog => "Failed to read audit log",
+        ErrorCode::FailedToStopExchange => "Failed to stop exchange",
+        ErrorCode::FailedToAddExchange => "Failed to add exchange",
+        ErrorCode::FailedToGetBalances => "Failed to get balances",
+        ErrorCode::FailedToGetStats => "Failed to get stats",
+        ErrorCode::InvalidStatsQuery => "Invalid stats query",
+        ErrorCode::FailedToGetOrders => "Failed to get orders",
+        ErrorCode::InvalidOrdersQuery => "Invalid orders query",
+        ErrorCode::FailedToExportState => "Failed to export state",
+        ErrorCode::FailedToImportState => "Failed to import state",
+        ErrorCode::FailedToDisableMarket => "Failed to disable market",
+        ErrorCode::FailedToEnableMarket => "Failed to enable market",
+        ErrorCode::FailedToGetStatus => "Failed to get status",
+        ErrorCode::FailedToGetOrderTimeline => "Failed to get order timeline",
+        ErrorCode::FailedToGetRateLimits => "Failed to get rate limits",
+        ErrorCode::FailedToGetShadowReport => "Failed to get shadow report",
+        ErrorCode::FailedToCancelHandover => "Failed to cancel handover",
+        ErrorCode::FailedToGetPendingRefills => "Failed to get pending refills",
     };
     log::error!("Rest API error: {}", reason);
     Error::new(jsonrpc_core::ErrorCode::ServerError(code as i64))
 }
+
+/// Server error with message which is shown to user of control panel
+pub fn server_side_error_with_message(code: ErrorCode, message: String) -> Error {
+    let mut error = server_side_error(code);
+    error.message = message;
+    error
+}
@@ -41,6 +41,10 @@ impl FutureOutcome {
         }
     }
 
+    pub fn completion_reason(&self) -> CompletionReason {
+        self.completion_reason
+    }
+
     pub fn into_result(&self) -> Result<()> {
         match self.completion_reason {
             CompletionReason::Error => {
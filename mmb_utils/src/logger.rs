@@ -1,9 +1,11 @@
 use chrono::Utc;
 use log::LevelFilter;
+use parking_lot::{const_mutex, Mutex};
 use std::env;
 use std::fmt::Display;
 use std::path::{Path, PathBuf};
 use std::sync::Once;
+use std::time::{Duration, Instant};
 
 /// Function for getting path to log file. For `cargo run` it will be path to project directory. In other cases it will be `./`
 /// if binary file were called with path that contain `rusttradingengine` dir the log will be there
@@ -77,3 +79,145 @@ where
     log::info!("{msg}");
     println!("{msg}");
 }
+
+/// Count of messages which can be logged in a row by a rate limited log call site
+pub const LOG_BURST_CAPACITY: u32 = 10;
+/// Period of restoring a single message for a rate limited log call site
+pub const LOG_REFILL_PERIOD: Duration = Duration::from_secs(1);
+
+struct LogRateLimiterState {
+    tokens: u32,
+    last_refill: Option<Instant>,
+    suppressed_count: u64,
+}
+
+/// Token bucket for a single log call site. Messages over the limit are suppressed and counted,
+/// so the next logged message can report how many times similar messages were repeated
+pub struct LogRateLimiter {
+    capacity: u32,
+    refill_period: Duration,
+    state: Mutex<LogRateLimiterState>,
+}
+
+impl LogRateLimiter {
+    pub const fn new(capacity: u32, refill_period: Duration) -> Self {
+        Self {
+            capacity,
+            refill_period,
+            state: const_mutex(LogRateLimiterState {
+                tokens: capacity,
+                last_refill: None,
+                suppressed_count: 0,
+            }),
+        }
+    }
+
+    /// Returns count of messages suppressed since the last logged one if message should be logged
+    /// or `None` if message should be suppressed
+    pub fn try_acquire(&self) -> Option<u64> {
+        self.try_acquire_at(Instant::now())
+    }
+
+    fn try_acquire_at(&self, now: Instant) -> Option<u64> {
+        let mut state = self.state.lock();
+
+        match state.last_refill {
+            None => state.last_refill = Some(now),
+            Some(last_refill) => {
+                let restored = now.saturating_duration_since(last_refill).as_nanos()
+                    / self.refill_period.as_nanos().max(1);
+                if restored >= self.capacity as u128 {
+                    state.tokens = self.capacity;
+                    state.last_refill = Some(now);
+                } else if restored > 0 {
+                    let restored = restored as u32;
+                    state.tokens = (state.tokens + restored).min(self.capacity);
+                    state.last_refill = Some(last_refill + self.refill_period * restored);
+                }
+            }
+        }
+
+        if state.tokens == 0 {
+            state.suppressed_count += 1;
+            return None;
+        }
+
+        state.tokens -= 1;
+        Some(std::mem::take(&mut state.suppressed_count))
+    }
+}
+
+/// Logs a message with rate limit per call site. Suppressed messages are summarized
+/// as "repeated N times" in the next logged message of the call site
+#[macro_export]
+macro_rules! log_limited {
+    ($level:expr, $($arg:tt)+) => {{
+        static LIMITER: $crate::logger::LogRateLimiter = $crate::logger::LogRateLimiter::new(
+            $crate::logger::LOG_BURST_CAPACITY,
+            $crate::logger::LOG_REFILL_PERIOD,
+        );
+
+        if let Some(suppressed_count) = LIMITER.try_acquire() {
+            if suppressed_count == 0 {
+                log::log!($level, $($arg)+);
+            } else {
+                log::log!(
+                    $level,
+                    "{} (similar message repeated {} times)",
+                    format_args!($($arg)+),
+                    suppressed_count
+                );
+            }
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! warn_limited {
+    ($($arg:tt)+) => {
+        $crate::log_limited!(log::Level::Warn, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! info_limited {
+    ($($arg:tt)+) => {
+        $crate::log_limited!(log::Level::Info, $($arg)+)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_over_limit_are_suppressed_and_counted() {
+        let limiter = LogRateLimiter::new(2, Duration::from_secs(1));
+        let start = Instant::now();
+
+        assert_eq!(limiter.try_acquire_at(start), Some(0));
+        assert_eq!(limiter.try_acquire_at(start), Some(0));
+        assert_eq!(limiter.try_acquire_at(start), None);
+        assert_eq!(limiter.try_acquire_at(start), None);
+
+        let after_refill = start + Duration::from_millis(1500);
+        assert_eq!(limiter.try_acquire_at(after_refill), Some(2));
+        assert_eq!(limiter.try_acquire_at(after_refill), None);
+        assert_eq!(
+            limiter.try_acquire_at(start + Duration::from_secs(2)),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn tokens_are_not_restored_over_capacity() {
+        let limiter = LogRateLimiter::new(2, Duration::from_secs(1));
+        let start = Instant::now();
+        assert_eq!(limiter.try_acquire_at(start), Some(0));
+
+        let later = start + Duration::from_secs(100);
+        assert_eq!(limiter.try_acquire_at(later), Some(0));
+        assert_eq!(limiter.try_acquire_at(later), Some(0));
+        assert_eq!(limiter.try_acquire_at(later), None);
+    }
+}
@@ -0,0 +1,468 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use anyhow::Result;
+use mmb_core::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId, Price};
+use mmb_core::exchanges::general::symbol::Symbol;
+use mmb_core::orders::order::{ClientOrderId, OrderCreating, OrderSide};
+use mmb_core::orders::order_builder::OrderBuilder;
+use parking_lot::Mutex;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use wasmtime::{Caller, Extern, Linker, StoreLimits, StoreLimitsBuilder};
+
+/// Name of module with host functions in imports of plugin
+pub const HOST_MODULE: &str = "mmb";
+pub const WASM_STRATEGY_NAME: &str = "WasmStrategy";
+
+/// Max memory of plugin instance
+const MAX_MEMORY_SIZE: usize = 64 * 1024 * 1024;
+/// Max count of orders which can be created or cancelled by plugin during one call
+const MAX_COMMANDS_PER_CALL: usize = 16;
+const MAX_LOG_MESSAGE_LEN: usize = 1024;
+
+const SIDE_BUY: i32 = 0;
+const SIDE_SELL: i32 = 1;
+const CURRENCY_BASE: i32 = 0;
+const CURRENCY_QUOTE: i32 = 1;
+
+/// Actions requested by plugin. They are executed only if call of plugin finished without trap
+#[derive(Debug)]
+pub(crate) enum HostCommand {
+    CreateOrder(OrderCreating),
+    CancelOrder(ClientOrderId),
+}
+
+/// Market data and balances are collected before call of plugin, so plugin can't block engine
+/// by reading shared state
+#[derive(Debug, Default, Clone)]
+pub(crate) struct MarketState {
+    pub top_bid: Option<Price>,
+    pub top_ask: Option<Price>,
+    pub base_balance: Option<Amount>,
+    pub quote_balance: Option<Amount>,
+    /// Orders are validated by symbol, so they can't be created until symbol is loaded
+    pub symbol: Option<Arc<Symbol>>,
+}
+
+/// Client order ids can be arbitrary strings, so plugin refers to orders by numeric handles.
+/// Handles are shared between instances of plugin, so orders stay known after reloading
+#[derive(Debug)]
+pub(crate) struct OrderHandles {
+    next_handle: i64,
+    client_order_ids: HashMap<i64, ClientOrderId>,
+    handles: HashMap<ClientOrderId, i64>,
+}
+
+impl Default for OrderHandles {
+    fn default() -> Self {
+        Self {
+            // Handles are positive numbers, so -1 can be used as error
+            next_handle: 1,
+            client_order_ids: HashMap::new(),
+            handles: HashMap::new(),
+        }
+    }
+}
+
+impl OrderHandles {
+    pub fn add(&mut self, client_order_id: ClientOrderId) -> i64 {
+        let handle = self.next_handle;
+        self.next_handle += 1;
+        self.client_order_ids
+            .insert(handle, client_order_id.clone());
+        self.handles.insert(client_order_id, handle);
+        handle
+    }
+
+    pub fn get_client_order_id(&self, handle: i64) -> Option<ClientOrderId> {
+        self.client_order_ids.get(&handle).cloned()
+    }
+
+    pub fn get_handle(&self, client_order_id: &ClientOrderId) -> Option<i64> {
+        self.handles.get(client_order_id).copied()
+    }
+
+    /// Forgets finished order, so table doesn't grow during lifetime of strategy
+    pub fn remove(&mut self, client_order_id: &ClientOrderId) {
+        if let Some(handle) = self.handles.remove(client_order_id) {
+            self.client_order_ids.remove(&handle);
+        }
+    }
+}
+
+pub(crate) struct HostState {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub market_state: MarketState,
+    pub commands: Vec<HostCommand>,
+    pub order_handles: Arc<Mutex<OrderHandles>>,
+    pub limits: StoreLimits,
+}
+
+impl HostState {
+    pub fn new(
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        order_handles: Arc<Mutex<OrderHandles>>,
+    ) -> Self {
+        Self {
+            exchange_account_id,
+            currency_pair,
+            market_state: Default::default(),
+            commands: vec![],
+            order_handles,
+            limits: StoreLimitsBuilder::new()
+                .memory_size(MAX_MEMORY_SIZE)
+                .build(),
+        }
+    }
+
+    fn push_command(&mut self, command: HostCommand) -> bool {
+        if self.commands.len() >= MAX_COMMANDS_PER_CALL {
+            log::warn!(
+                "Command {:?} of {} is skipped: limit {} per call is exceeded",
+                command,
+                WASM_STRATEGY_NAME,
+                MAX_COMMANDS_PER_CALL
+            );
+            return false;
+        }
+
+        self.commands.push(command);
+        true
+    }
+
+    /// Forgets handles of orders requested during failed call, because they aren't created
+    pub fn discard_commands(&mut self) {
+        let mut order_handles = self.order_handles.lock();
+        for command in self.commands.drain(..) {
+            if let HostCommand::CreateOrder(order) = command {
+                order_handles.remove(&order.header.client_order_id);
+            }
+        }
+    }
+
+    /// Returns handle of order or -1 if order is invalid
+    fn create_order(&mut self, side: i32, price: f64, amount: f64) -> i64 {
+        let side = match side {
+            SIDE_BUY => OrderSide::Buy,
+            SIDE_SELL => OrderSide::Sell,
+            _ => {
+                log::warn!("Invalid order side {} from {}", side, WASM_STRATEGY_NAME);
+                return -1;
+            }
+        };
+
+        let (price, amount) = match (to_decimal(price), to_decimal(amount)) {
+            (Some(price), Some(amount)) => (price, amount),
+            _ => {
+                log::warn!(
+                    "Invalid order price {} or amount {} from {}",
+                    price,
+                    amount,
+                    WASM_STRATEGY_NAME
+                );
+                return -1;
+            }
+        };
+
+        let symbol = match &self.market_state.symbol {
+            Some(symbol) => symbol.clone(),
+            None => {
+                log::warn!(
+                    "Order from {} is rejected because symbol {} isn't loaded",
+                    WASM_STRATEGY_NAME,
+                    self.currency_pair
+                );
+                return -1;
+            }
+        };
+
+        let order_builder = OrderBuilder::new(self.exchange_account_id, self.currency_pair)
+            .side(side)
+            .limit(price)
+            .amount(amount)
+            .strategy_name(WASM_STRATEGY_NAME);
+        let order = match order_builder
+            .validate_by_symbol(&symbol)
+            .and_then(|_| order_builder.build())
+        {
+            Ok(order) => order,
+            Err(error) => {
+                log::warn!("Invalid order from {}: {}", WASM_STRATEGY_NAME, error);
+                return -1;
+            }
+        };
+
+        let client_order_id = order.header.client_order_id.clone();
+        match self.push_command(HostCommand::CreateOrder(order)) {
+            true => self.order_handles.lock().add(client_order_id),
+            false => -1,
+        }
+    }
+
+    fn cancel_order(&mut self, handle: i64) -> i32 {
+        let client_order_id = match self.order_handles.lock().get_client_order_id(handle) {
+            Some(client_order_id) => client_order_id,
+            None => {
+                log::warn!(
+                    "Unknown order handle {} from {}",
+                    handle,
+                    WASM_STRATEGY_NAME
+                );
+                return -1;
+            }
+        };
+
+        match self.push_command(HostCommand::CancelOrder(client_order_id)) {
+            true => 0,
+            false => -1,
+        }
+    }
+}
+
+fn to_f64(value: Option<Decimal>) -> f64 {
+    value.and_then(|value| value.to_f64()).unwrap_or(f64::NAN)
+}
+
+/// Only finite positive values are accepted as prices and amounts
+fn to_decimal(value: f64) -> Option<Decimal> {
+    if !value.is_finite() || value <= 0. {
+        return None;
+    }
+
+    Decimal::from_f64(value)
+}
+
+/// Range of log message in memory of plugin. Long messages are truncated
+fn get_message_range(ptr: i32, len: i32) -> Option<Range<usize>> {
+    let start = usize::try_from(ptr).ok()?;
+    let len = usize::try_from(len).ok()?.min(MAX_LOG_MESSAGE_LEN);
+    Some(start..start.checked_add(len)?)
+}
+
+fn log_message(mut caller: Caller<'_, HostState>, ptr: i32, len: i32) {
+    let memory = match caller.get_export("memory").and_then(Extern::into_memory) {
+        Some(memory) => memory,
+        None => return,
+    };
+
+    let range = match get_message_range(ptr, len) {
+        Some(range) => range,
+        None => {
+            log::warn!(
+                "Invalid log message (ptr: {}, len: {}) from {}",
+                ptr,
+                len,
+                WASM_STRATEGY_NAME
+            );
+            return;
+        }
+    };
+
+    match memory.data(&caller).get(range) {
+        Some(bytes) => log::info!("{}: {}", WASM_STRATEGY_NAME, String::from_utf8_lossy(bytes)),
+        None => log::warn!(
+            "Log message (ptr: {}, len: {}) from {} is out of memory bounds",
+            ptr,
+            len,
+            WASM_STRATEGY_NAME
+        ),
+    }
+}
+
+/// Host API available for plugins. Prices and amounts are passed as `f64`, unknown values are `NaN`:
+/// - `log(ptr: i32, len: i32)` writes UTF-8 message from memory of plugin to engine log
+/// - `best_price(side: i32) -> f64` top price of order book (0 - bid, 1 - ask)
+/// - `balance(currency: i32) -> f64` balance on exchange (0 - base currency, 1 - quote currency)
+/// - `create_order(side: i32, price: f64, amount: f64) -> i64` limit order (0 - buy, 1 - sell),
+///   returns positive handle of order or -1 if order is invalid
+/// - `cancel_order(handle: i64) -> i32` returns 0 or -1 if handle is unknown or cancellation
+///   is rejected
+pub(crate) fn create_linker(engine: &wasmtime::Engine) -> Result<Linker<HostState>> {
+    let mut linker = Linker::new(engine);
+
+    linker.func_wrap(HOST_MODULE, "log", log_message)?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "best_price",
+        |caller: Caller<'_, HostState>, side: i32| {
+            let market_state = &caller.data().market_state;
+            match side {
+                SIDE_BUY => to_f64(market_state.top_bid),
+                SIDE_SELL => to_f64(market_state.top_ask),
+                _ => f64::NAN,
+            }
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "balance",
+        |caller: Caller<'_, HostState>, currency: i32| {
+            let market_state = &caller.data().market_state;
+            match currency {
+                CURRENCY_BASE => to_f64(market_state.base_balance),
+                CURRENCY_QUOTE => to_f64(market_state.quote_balance),
+                _ => f64::NAN,
+            }
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "create_order",
+        |mut caller: Caller<'_, HostState>, side: i32, price: f64, amount: f64| {
+            caller.data_mut().create_order(side, price, amount)
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "cancel_order",
+        |mut caller: Caller<'_, HostState>, handle: i64| caller.data_mut().cancel_order(handle),
+    )?;
+
+    Ok(linker)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use mmb_core::orders::client_order_id::{
+        init_client_order_id_generator, ClientOrderIdSettings,
+    };
+    use mmb_core::test_util::SymbolBuilder;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    pub(crate) fn symbol() -> Arc<Symbol> {
+        Arc::new(
+            SymbolBuilder::new("eth".into(), "btc".into())
+                .min_amount(dec!(0.01))
+                .price_tick(dec!(0.01))
+                .build(),
+        )
+    }
+
+    fn host_state() -> HostState {
+        let mut state = HostState::new(
+            "Binance_0".parse().expect("in test"),
+            CurrencyPair::from_codes("eth".into(), "btc".into()),
+            Default::default(),
+        );
+        state.market_state.symbol = Some(symbol());
+        state
+    }
+
+    #[test]
+    fn invalid_orders_are_rejected() {
+        let mut state = host_state();
+
+        assert_eq!(state.create_order(2, 1., 1.), -1);
+        assert_eq!(state.create_order(SIDE_BUY, f64::NAN, 1.), -1);
+        assert_eq!(state.create_order(SIDE_BUY, f64::INFINITY, 1.), -1);
+        assert_eq!(state.create_order(SIDE_BUY, 1., -1.), -1);
+        assert_eq!(state.create_order(SIDE_BUY, 0., 1.), -1);
+        assert!(state.commands.is_empty());
+    }
+
+    #[test]
+    fn orders_are_validated_by_symbol() {
+        let mut state = host_state();
+
+        // Price doesn't match precision
+        assert_eq!(state.create_order(SIDE_BUY, 0.055, 1.), -1);
+        // Amount is less than min amount
+        assert_eq!(state.create_order(SIDE_BUY, 0.05, 0.001), -1);
+        assert!(state.commands.is_empty());
+
+        assert!(state.create_order(SIDE_BUY, 0.05, 1.) > 0);
+        match &state.commands[..] {
+            [HostCommand::CreateOrder(order)] => {
+                assert_eq!(order.price, dec!(0.05));
+                assert_eq!(order.header.amount, dec!(1));
+                assert_eq!(order.header.side, OrderSide::Buy);
+                assert_eq!(order.header.strategy_name, WASM_STRATEGY_NAME);
+            }
+            commands => panic!("Unexpected commands {:?}", commands),
+        }
+    }
+
+    #[test]
+    fn orders_are_rejected_until_symbol_is_loaded() {
+        let mut state = host_state();
+        state.market_state.symbol = None;
+
+        assert_eq!(state.create_order(SIDE_SELL, 0.05, 1.), -1);
+        assert!(state.commands.is_empty());
+    }
+
+    #[test]
+    fn unknown_order_handles_are_not_cancelled() {
+        let mut state = host_state();
+
+        assert_eq!(state.cancel_order(0), -1);
+        assert_eq!(state.cancel_order(-5), -1);
+        assert_eq!(state.cancel_order(5), -1);
+        assert!(state.commands.is_empty());
+    }
+
+    #[test]
+    fn orders_with_non_numeric_ids_are_cancelled_by_handle() {
+        init_client_order_id_generator(Some(&ClientOrderIdSettings {
+            prefix: "mmb".into(),
+            strategy_codes: Default::default(),
+        }));
+        let mut state = host_state();
+
+        let handle = state.create_order(SIDE_BUY, 0.05, 1.);
+        init_client_order_id_generator(None);
+        assert!(handle > 0);
+        let client_order_id = match &state.commands[..] {
+            [HostCommand::CreateOrder(order)] => order.header.client_order_id.clone(),
+            commands => panic!("Unexpected commands {:?}", commands),
+        };
+        assert!(client_order_id.as_str().starts_with("mmb-wasmstrategy-"));
+
+        assert_eq!(state.cancel_order(handle), 0);
+        match &state.commands[..] {
+            [_, HostCommand::CancelOrder(cancelled)] => assert_eq!(cancelled, &client_order_id),
+            commands => panic!("Unexpected commands {:?}", commands),
+        }
+
+        state.order_handles.lock().remove(&client_order_id);
+        assert_eq!(state.cancel_order(handle), -1);
+    }
+
+    #[test]
+    fn handles_of_discarded_orders_are_forgotten() {
+        let mut state = host_state();
+
+        let handle = state.create_order(SIDE_BUY, 0.05, 1.);
+        state.discard_commands();
+
+        assert!(state.commands.is_empty());
+        assert_eq!(state.cancel_order(handle), -1);
+    }
+
+    #[test]
+    fn log_message_range_is_validated() {
+        assert_eq!(get_message_range(8, 4), Some(8..12));
+        assert_eq!(get_message_range(0, i32::MAX), Some(0..MAX_LOG_MESSAGE_LEN));
+        assert_eq!(get_message_range(-1, 4), None);
+        assert_eq!(get_message_range(8, -4), None);
+    }
+
+    #[test]
+    fn commands_are_limited_per_call() {
+        let mut state = host_state();
+
+        let mut handle = -1;
+        for _ in 0..MAX_COMMANDS_PER_CALL {
+            handle = state.create_order(SIDE_SELL, 0.05, 1.);
+            assert!(handle > 0);
+        }
+        assert_eq!(state.cancel_order(handle), -1);
+        assert_eq!(state.commands.len(), MAX_COMMANDS_PER_CALL);
+    }
+}
@@ -0,0 +1,55 @@
+#![deny(
+    non_shorthand_field_patterns,
+    no_mangle_generic_items,
+    overflowing_literals,
+    path_statements,
+    unused_allocation,
+    unused_comparisons,
+    unused_parens,
+    while_true,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_must_use
+)]
+
+//! Strategies compiled to WASM and executed in sandbox with constrained host API.
+//! Plugin can be written in any language targeting `wasm32-unknown-unknown`, e.g. in Rust:
+//!
+//! ```ignore
+//! #[link(wasm_import_module = "mmb")]
+//! extern "C" {
+//!     fn best_price(side: i32) -> f64;
+//!     fn create_order(side: i32, price: f64, amount: f64) -> i64;
+//! }
+//!
+//! #[no_mangle]
+//! pub extern "C" fn on_tick() {
+//!     unsafe {
+//!         let bid = best_price(0);
+//!         if !bid.is_nan() {
+//!             create_order(0, bid, 0.1);
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Plugin is used as disposition strategy of engine:
+//!
+//! ```ignore
+//! launch_trading_engine(&build_config, init_settings, |settings, engine_context| {
+//!     Box::new(
+//!         WasmStrategy::new(
+//!             "strategy.wasm",
+//!             settings.strategy.exchange_account_id(),
+//!             settings.strategy.currency_pair(),
+//!             engine_context,
+//!         )
+//!         .expect("Unable to load WASM strategy"),
+//!     )
+//! })
+//! ```
+
+pub mod host;
+pub mod strategy;
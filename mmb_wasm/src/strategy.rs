@@ -0,0 +1,475 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use anyhow::{bail, Context, Result};
+use futures::FutureExt;
+use mmb_core::disposition_execution::{PriceSlot, TradingContext};
+use mmb_core::exchanges::common::{CurrencyPair, ExchangeAccountId, MarketAccountId};
+use mmb_core::exchanges::general::exchange::Exchange;
+use mmb_core::exchanges::general::symbol::Symbol;
+use mmb_core::explanation::Explanation;
+use mmb_core::infrastructure::spawn_future;
+use mmb_core::lifecycle::trading_engine::EngineContext;
+use mmb_core::order_book::local_snapshot_service::LocalSnapshotsService;
+use mmb_core::orders::order::OrderSnapshot;
+use mmb_core::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use mmb_core::strategies::disposition_strategy::DispositionStrategy;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal::prelude::ToPrimitive;
+use wasmtime::{Config, Linker, Module, Store, TypedFunc};
+
+use crate::host::{
+    create_linker, HostCommand, HostState, MarketState, OrderHandles, WASM_STRATEGY_NAME,
+};
+
+/// Max fuel (count of executed instructions) of one call of plugin to stop infinite loops
+const FUEL_PER_CALL: u64 = 50_000_000;
+
+fn create_wasm_engine() -> Result<wasmtime::Engine> {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    wasmtime::Engine::new(&config)
+}
+
+/// Loaded instance of plugin
+struct Plugin {
+    store: Store<HostState>,
+    on_tick: TypedFunc<(), ()>,
+    on_order_filled: Option<TypedFunc<(i64, f64), ()>>,
+}
+
+impl Plugin {
+    fn new(
+        wasm_engine: &wasmtime::Engine,
+        linker: &Linker<HostState>,
+        module: &Module,
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        order_handles: Arc<Mutex<OrderHandles>>,
+    ) -> Result<Self> {
+        let mut store = Store::new(
+            wasm_engine,
+            HostState::new(exchange_account_id, currency_pair, order_handles),
+        );
+        store.limiter(|state| &mut state.limits);
+        store.add_fuel(FUEL_PER_CALL)?;
+
+        let instance = linker.instantiate(&mut store, module)?;
+        let on_tick = instance.get_typed_func::<(), (), _>(&mut store, "on_tick")?;
+        let on_order_filled = instance
+            .get_typed_func::<(i64, f64), (), _>(&mut store, "on_order_filled")
+            .ok();
+
+        Ok(Plugin {
+            store,
+            on_tick,
+            on_order_filled,
+        })
+    }
+
+    fn call(
+        &mut self,
+        market_state: MarketState,
+        call: impl FnOnce(&mut Self) -> Result<()>,
+    ) -> Result<Vec<HostCommand>> {
+        let remaining_fuel = self.store.consume_fuel(0)?;
+        self.store
+            .add_fuel(FUEL_PER_CALL.saturating_sub(remaining_fuel))?;
+
+        let state = self.store.data_mut();
+        state.market_state = market_state;
+        state.commands.clear();
+
+        if let Err(error) = call(self) {
+            self.store.data_mut().discard_commands();
+            return Err(error);
+        }
+
+        Ok(std::mem::take(&mut self.store.data_mut().commands))
+    }
+}
+
+/// Strategy compiled to WASM and loaded at runtime. Plugin exports `memory`, `on_tick()`
+/// and optionally `on_order_filled(handle: i64, filled_amount: f64)`, host API is described in `create_linker`.
+///
+/// Plugin file is reloaded when it's modified. Plugin is disabled after trap until its file is replaced,
+/// orders requested during failed call are not created. Market data and balances are available only in `on_tick`
+pub struct WasmStrategy {
+    path: PathBuf,
+    wasm_engine: wasmtime::Engine,
+    linker: Linker<HostState>,
+    plugin: Mutex<Option<Plugin>>,
+    plugin_modified: Option<SystemTime>,
+    order_handles: Arc<Mutex<OrderHandles>>,
+    exchange_account_id: ExchangeAccountId,
+    currency_pair: CurrencyPair,
+    engine_context: Arc<EngineContext>,
+}
+
+impl WasmStrategy {
+    pub fn new(
+        path: impl Into<PathBuf>,
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        engine_context: Arc<EngineContext>,
+    ) -> Result<Self> {
+        let wasm_engine = create_wasm_engine()?;
+        let linker = create_linker(&wasm_engine)?;
+
+        let mut strategy = Self {
+            path: path.into(),
+            wasm_engine,
+            linker,
+            plugin: Mutex::new(None),
+            plugin_modified: None,
+            order_handles: Default::default(),
+            exchange_account_id,
+            currency_pair,
+            engine_context,
+        };
+
+        let plugin = strategy.load_plugin()?;
+        *strategy.plugin.get_mut() = Some(plugin);
+        strategy.plugin_modified = strategy.get_modified_time().ok();
+
+        Ok(strategy)
+    }
+
+    fn get_modified_time(&self) -> Result<SystemTime> {
+        Ok(std::fs::metadata(&self.path)?.modified()?)
+    }
+
+    fn load_plugin(&self) -> Result<Plugin> {
+        let module = Module::from_file(&self.wasm_engine, &self.path)
+            .with_context(|| format!("Unable to compile WASM plugin {:?}", self.path))?;
+
+        Plugin::new(
+            &self.wasm_engine,
+            &self.linker,
+            &module,
+            self.exchange_account_id,
+            self.currency_pair,
+            self.order_handles.clone(),
+        )
+    }
+
+    /// Hot swap of plugin if its file was modified since last loading
+    fn reload_if_modified(&mut self) {
+        let modified = match self.get_modified_time() {
+            Ok(modified) => modified,
+            Err(_) => return,
+        };
+
+        if self.plugin_modified == Some(modified) {
+            return;
+        }
+        self.plugin_modified = Some(modified);
+
+        match self.load_plugin() {
+            Ok(plugin) => {
+                log::info!("WASM plugin {:?} is reloaded", self.path);
+                *self.plugin.get_mut() = Some(plugin);
+            }
+            Err(error) => log::error!("Unable to reload WASM plugin: {:?}", error),
+        }
+    }
+
+    fn collect_market_state(&self, local_snapshots_service: &LocalSnapshotsService) -> MarketState {
+        let market_id =
+            MarketAccountId::new(self.exchange_account_id, self.currency_pair).market_id();
+        let snapshot = local_snapshots_service.get_snapshot(market_id);
+
+        let mut market_state = MarketState {
+            top_bid: snapshot
+                .and_then(|x| x.get_top_bid())
+                .map(|(price, _)| price),
+            top_ask: snapshot
+                .and_then(|x| x.get_top_ask())
+                .map(|(price, _)| price),
+            symbol: self.get_symbol(),
+            ..Default::default()
+        };
+
+        if let Some(symbol) = market_state.symbol.clone() {
+            let balance_manager = self.engine_context.balance_manager.lock();
+            let get_balance = |currency_code| {
+                balance_manager.get_exchange_balance(
+                    self.exchange_account_id,
+                    symbol.clone(),
+                    currency_code,
+                )
+            };
+            market_state.base_balance = get_balance(symbol.base_currency_code());
+            market_state.quote_balance = get_balance(symbol.quote_currency_code());
+        }
+
+        market_state
+    }
+
+    fn get_symbol(&self) -> Option<Arc<Symbol>> {
+        self.get_exchange().and_then(|exchange| {
+            exchange
+                .symbols
+                .get(&self.currency_pair)
+                .map(|symbol| symbol.value().clone())
+        })
+    }
+
+    fn get_exchange(&self) -> Option<Arc<Exchange>> {
+        self.engine_context
+            .exchanges
+            .get(&self.exchange_account_id)
+            .map(|exchange| exchange.value().clone())
+    }
+
+    /// Calls plugin and executes requested commands. Plugin is disabled on failure
+    fn call_plugin(&self, market_state: MarketState, call: impl FnOnce(&mut Plugin) -> Result<()>) {
+        let mut plugin_guard = self.plugin.lock();
+        let plugin = match plugin_guard.as_mut() {
+            Some(plugin) => plugin,
+            None => return,
+        };
+
+        match plugin.call(market_state, call) {
+            Ok(commands) => self.execute_commands(commands),
+            Err(error) => {
+                log::error!(
+                    "WASM plugin {:?} failed and is disabled until it's replaced: {:?}",
+                    self.path,
+                    error
+                );
+                *plugin_guard = None;
+            }
+        }
+    }
+
+    fn execute_commands(&self, commands: Vec<HostCommand>) {
+        let exchange = match self.get_exchange() {
+            Some(exchange) => exchange,
+            None => return,
+        };
+
+        for command in commands {
+            let exchange = exchange.clone();
+            let order_handles = self.order_handles.clone();
+            let cancellation_token = self.engine_context.lifetime_manager.stop_token();
+            let action = async move {
+                match command {
+                    HostCommand::CreateOrder(order) => {
+                        let _ = exchange
+                            .create_order(&order, None, cancellation_token)
+                            .await?;
+                    }
+                    HostCommand::CancelOrder(client_order_id) => {
+                        let order = exchange
+                            .orders
+                            .cache_by_client_id
+                            .get(&client_order_id)
+                            .map(|order| order.value().clone())
+                            .with_context(|| format!("There is no order {}", client_order_id))?;
+                        // Plugin can't cancel orders of other strategies
+                        if order.fn_ref(|x| x.header.strategy_name != WASM_STRATEGY_NAME) {
+                            bail!("Order {} isn't created by WASM plugin", client_order_id);
+                        }
+                        let _ = exchange
+                            .wait_cancel_order_confirmed(order, None, true, cancellation_token)
+                            .await?;
+                        order_handles.lock().remove(&client_order_id);
+                    }
+                }
+
+                Ok(())
+            };
+
+            let _ = spawn_future(
+                "Execute command of WASM plugin",
+                SpawnFutureFlags::STOP_BY_TOKEN,
+                action.boxed(),
+            );
+        }
+    }
+}
+
+impl DispositionStrategy for WasmStrategy {
+    fn calculate_trading_context(
+        &mut self,
+        _now: DateTime,
+        local_snapshots_service: &LocalSnapshotsService,
+        _explanation: &mut Explanation,
+    ) -> Option<TradingContext> {
+        self.reload_if_modified();
+
+        let market_state = self.collect_market_state(local_snapshots_service);
+        self.call_plugin(market_state, |plugin| {
+            plugin.on_tick.call(&mut plugin.store, ())?;
+            Ok(())
+        });
+
+        // Orders are managed by plugin directly
+        None
+    }
+
+    fn handle_order_fill(
+        &self,
+        cloned_order: &Arc<OrderSnapshot>,
+        _price_slot: &PriceSlot,
+        _target_eai: ExchangeAccountId,
+        _cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        if cloned_order.header.strategy_name != WASM_STRATEGY_NAME {
+            return Ok(());
+        }
+
+        let client_order_id = &cloned_order.header.client_order_id;
+        let handle = {
+            let mut order_handles = self.order_handles.lock();
+            let handle = order_handles.get_handle(client_order_id);
+            if cloned_order.props.is_finished() {
+                order_handles.remove(client_order_id);
+            }
+            handle
+        };
+        // Order was created before start of engine or by previous instance of strategy
+        let handle = match handle {
+            Some(handle) => handle,
+            None => return Ok(()),
+        };
+        let filled_amount = cloned_order
+            .fills
+            .filled_amount
+            .to_f64()
+            .unwrap_or(f64::NAN);
+
+        let market_state = MarketState {
+            symbol: self.get_symbol(),
+            ..Default::default()
+        };
+        self.call_plugin(market_state, |plugin| {
+            if let Some(on_order_filled) = plugin.on_order_filled {
+                on_order_filled.call(&mut plugin.store, (handle, filled_amount))?;
+            }
+            Ok(())
+        });
+
+        Ok(())
+    }
+
+    fn configuration_descriptor(&self) -> ConfigurationDescriptor {
+        ConfigurationDescriptor::new(WASM_STRATEGY_NAME.into(), "wasm".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::host::tests::symbol;
+
+    const ORDER_ON_TICK: &str = r#"
+        (module
+            (import "mmb" "best_price" (func $best_price (param i32) (result f64)))
+            (import "mmb" "create_order" (func $create_order (param i32 f64 f64) (result i64)))
+            (memory (export "memory") 1)
+            (func (export "on_tick")
+                (drop (call $create_order
+                    (i32.const 0)
+                    (call $best_price (i32.const 0))
+                    (f64.const 2)))))
+    "#;
+
+    const TRAP_AFTER_ORDER_ON_TICK: &str = r#"
+        (module
+            (import "mmb" "create_order" (func $create_order (param i32 f64 f64) (result i64)))
+            (memory (export "memory") 1)
+            (func (export "on_tick")
+                (drop (call $create_order (i32.const 1) (f64.const 0.5) (f64.const 1)))
+                unreachable))
+    "#;
+
+    const INFINITE_LOOP_ON_TICK: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (func (export "on_tick")
+                (loop $infinite (br $infinite))))
+    "#;
+
+    fn load_plugin(wat: &str) -> Plugin {
+        let wasm_engine = create_wasm_engine().expect("in test");
+        let linker = create_linker(&wasm_engine).expect("in test");
+        let module = Module::new(&wasm_engine, wat).expect("in test");
+
+        Plugin::new(
+            &wasm_engine,
+            &linker,
+            &module,
+            "Binance_0".parse().expect("in test"),
+            CurrencyPair::from_codes("eth".into(), "btc".into()),
+            Default::default(),
+        )
+        .expect("in test")
+    }
+
+    fn call_on_tick(plugin: &mut Plugin, market_state: MarketState) -> Result<Vec<HostCommand>> {
+        plugin.call(market_state, |plugin| {
+            plugin.on_tick.call(&mut plugin.store, ())?;
+            Ok(())
+        })
+    }
+
+    fn market_state() -> MarketState {
+        MarketState {
+            top_bid: Some(dec!(0.25)),
+            symbol: Some(symbol()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn orders_are_created_by_market_data() {
+        let mut plugin = load_plugin(ORDER_ON_TICK);
+
+        let commands = call_on_tick(&mut plugin, market_state()).expect("in test");
+
+        match &commands[..] {
+            [HostCommand::CreateOrder(order)] => {
+                assert_eq!(order.price, dec!(0.25));
+                assert_eq!(order.header.amount, dec!(2));
+            }
+            commands => panic!("Unexpected commands {:?}", commands),
+        }
+    }
+
+    #[test]
+    fn orders_are_not_created_without_market_data() {
+        let mut plugin = load_plugin(ORDER_ON_TICK);
+
+        // Best price is NaN, so order is rejected by host
+        let market_state = MarketState {
+            top_bid: None,
+            ..market_state()
+        };
+        let commands = call_on_tick(&mut plugin, market_state).expect("in test");
+
+        assert!(commands.is_empty());
+    }
+
+    #[test]
+    fn commands_are_dropped_after_trap() {
+        let mut plugin = load_plugin(TRAP_AFTER_ORDER_ON_TICK);
+
+        assert!(call_on_tick(&mut plugin, market_state()).is_err());
+        assert!(plugin.store.data().order_handles.lock().handles.is_empty());
+    }
+
+    #[test]
+    fn infinite_loop_is_stopped_by_fuel() {
+        let mut plugin = load_plugin(INFINITE_LOOP_ON_TICK);
+
+        assert!(call_on_tick(&mut plugin, market_state()).is_err());
+    }
+}